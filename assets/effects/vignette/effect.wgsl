@@ -0,0 +1,15 @@
+// 暗角：从中心向四周逐渐变暗
+
+@fragment
+fn fs_main(in: EffectVertexOutput) -> @location(0) vec4<f32> {
+    let color = textureSample(input_texture, input_sampler, in.uv);
+    let strength = param(0u);
+    let radius = param(1u);
+
+    // 按宽高比修正，使暗角为圆形
+    let aspect = effect.resolution.x * effect.resolution.w;
+    let offset = (in.uv - vec2<f32>(0.5)) * vec2<f32>(aspect, 1.0);
+    let falloff = 1.0 - smoothstep(radius * 0.4, radius, length(offset) * 2.0);
+    let shade = mix(1.0 - strength, 1.0, falloff);
+    return vec4<f32>(color.rgb * shade, color.a);
+}
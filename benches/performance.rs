@@ -1,6 +1,18 @@
 // 性能基准测试
 
-use criterion::{criterion_group, criterion_main, Criterion};
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use mira::shape::{MaskQuality, ShapeMask, ShapeType};
+use std::time::{Duration, Instant};
+
+const MASK_SIZE: u32 = 400;
+
+const SHAPES: [ShapeType; 5] = [
+    ShapeType::Circle,
+    ShapeType::Ellipse,
+    ShapeType::Rectangle,
+    ShapeType::RoundedRectangle { radius: 20.0 },
+    ShapeType::Heart,
+];
 
 fn benchmark_frame_rendering(c: &mut Criterion) {
     c.bench_function("render_frame", |b| {
@@ -18,5 +30,103 @@ fn benchmark_shape_mask_switching(c: &mut Criterion) {
     });
 }
 
-criterion_group!(benches, benchmark_frame_rendering, benchmark_shape_mask_switching);
+/// 各遮罩质量等级的生成耗时
+fn benchmark_mask_quality_levels(c: &mut Criterion) {
+    let mut group = c.benchmark_group("mask_quality");
+
+    for quality in MaskQuality::ALL {
+        for shape in SHAPES {
+            let mut mask = ShapeMask::with_quality(shape, MASK_SIZE, MASK_SIZE, quality);
+            mask.set_cache_budget(0); // 测量实际生成耗时，不走缓存
+            let id = BenchmarkId::new(quality.as_str(), format!("{:?}", shape));
+            group.bench_function(id, |b| {
+                b.iter(|| {
+                    mask.generate();
+                    black_box(mask.data());
+                });
+            });
+        }
+    }
+
+    group.finish();
+
+    // 自适应模式在拖拽开始/结束时切换的开销（不走缓存）
+    c.bench_function("mask_quality/Adaptive/interaction_toggle", |b| {
+        let mut mask = ShapeMask::with_quality(ShapeType::Heart, MASK_SIZE, MASK_SIZE, MaskQuality::Adaptive);
+        mask.set_cache_budget(0);
+        b.iter(|| {
+            mask.set_interacting(true);
+            mask.set_interacting(false);
+            black_box(mask.data());
+        });
+    });
+
+    verify_mask_quality_budgets();
+}
+
+/// 验证每个质量等级的平均生成时间都在预算内，超出时基准测试失败
+fn verify_mask_quality_budgets() {
+    const ITERATIONS: u32 = 10;
+
+    for quality in MaskQuality::ALL {
+        let budget = Duration::from_millis(quality.time_budget_ms());
+
+        for shape in SHAPES {
+            let mut mask = ShapeMask::with_quality(shape, MASK_SIZE, MASK_SIZE, quality);
+            mask.set_cache_budget(0);
+
+            let start = Instant::now();
+            for _ in 0..ITERATIONS {
+                mask.generate();
+                black_box(mask.data());
+            }
+            let average = start.elapsed() / ITERATIONS;
+
+            assert!(average <= budget,
+                    "{:?} 质量生成 {:?} 遮罩平均耗时 {:?}，超过预算 {:?}",
+                    quality, shape, average, budget);
+        }
+    }
+}
+
+/// 滚轮缩放在几个尺寸间来回切换时的遮罩缓存命中耗时
+fn benchmark_mask_cache(c: &mut Criterion) {
+    c.bench_function("mask_cache/resize_hit", |b| {
+        let mut mask = ShapeMask::with_quality(ShapeType::Heart, MASK_SIZE, MASK_SIZE, MaskQuality::High);
+        mask.resize(MASK_SIZE + 40, MASK_SIZE + 40);
+        b.iter(|| {
+            mask.resize(MASK_SIZE, MASK_SIZE);
+            mask.resize(MASK_SIZE + 40, MASK_SIZE + 40);
+            black_box(mask.data());
+        });
+    });
+}
+
+/// 串行与按行并行生成高质量遮罩的耗时对比
+fn benchmark_mask_threads(c: &mut Criterion) {
+    let mut group = c.benchmark_group("mask_threads");
+
+    // 单核机器上也比较两种路径，避免重复的基准 ID
+    for threads in [1, num_cpus::get().max(2)] {
+        let mut mask = ShapeMask::with_thread_count(ShapeType::Heart, MASK_SIZE, MASK_SIZE, MaskQuality::High, threads);
+        mask.set_cache_budget(0);
+        group.bench_function(BenchmarkId::from_parameter(threads), |b| {
+            b.iter(|| {
+                mask.generate();
+                black_box(mask.data());
+            });
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    benchmark_frame_rendering,
+    benchmark_shape_mask_switching,
+    benchmark_mask_quality_levels,
+    benchmark_mask_cache,
+    benchmark_mask_threads
+);
 criterion_main!(benches);
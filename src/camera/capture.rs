@@ -0,0 +1,206 @@
+// 后台帧捕获
+//
+// nokhwa 的 frame() 会阻塞到摄像头输出下一帧。捕获放在独立线程中进行，
+// 只保留最新的一帧，新帧到达时调用通知回调唤醒事件循环，主线程不必为等待帧而轮询。
+// 线程在多次重试仍无法读取帧时把错误交给主线程并退出，由上层重新打开设备。
+
+use super::manager::{Frame, PixelFormat};
+use crate::error::CameraError;
+use log::{debug, error, info, warn};
+use nokhwa::{pixel_format::RgbFormat, Camera};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+/// 新帧到达时的通知回调
+pub type FrameNotifier = Arc<dyn Fn() + Send + Sync>;
+
+/// 最新一帧（或捕获线程退出前的错误）
+#[derive(Default)]
+struct FrameSlot {
+    latest: Mutex<Option<Result<Frame, CameraError>>>,
+    ready: Condvar,
+}
+
+/// 摄像头捕获线程
+pub(crate) struct CaptureThread {
+    slot: Arc<FrameSlot>,
+    stop: Arc<AtomicBool>,
+    handle: Option<JoinHandle<Camera>>,
+}
+
+impl CaptureThread {
+    /// 启动捕获线程，视频流需已打开
+    pub fn spawn(mut camera: Camera, max_retries: u32, notifier: Option<FrameNotifier>) -> Result<Self, CameraError> {
+        let slot = Arc::new(FrameSlot::default());
+        let stop = Arc::new(AtomicBool::new(false));
+
+        let thread_slot = Arc::clone(&slot);
+        let thread_stop = Arc::clone(&stop);
+        let handle = std::thread::Builder::new()
+            .name("camera-capture".to_string())
+            .spawn(move || {
+                debug!("摄像头捕获线程已启动");
+                while !thread_stop.load(Ordering::Acquire) {
+                    let result = read_frame(&mut camera, max_retries);
+                    let failed = result.is_err();
+
+                    // 未被取走的旧帧直接丢弃，主线程总是拿到最新一帧
+                    if let Ok(mut latest) = thread_slot.latest.lock() {
+                        *latest = Some(result);
+                    }
+                    thread_slot.ready.notify_all();
+                    if let Some(notifier) = &notifier {
+                        notifier();
+                    }
+
+                    if failed {
+                        break;
+                    }
+                }
+                debug!("摄像头捕获线程已退出");
+                camera
+            })
+            .map_err(|e| CameraError::CaptureError(format!("启动捕获线程失败: {}", e)))?;
+
+        Ok(Self {
+            slot,
+            stop,
+            handle: Some(handle),
+        })
+    }
+
+    /// 取走最新一帧，没有新帧时立即返回 None
+    pub fn try_take(&self) -> Option<Result<Frame, CameraError>> {
+        self.slot.latest.lock().ok()?.take()
+    }
+
+    /// 等待并取走下一帧，超时返回 None
+    pub fn wait(&self, timeout: Duration) -> Option<Result<Frame, CameraError>> {
+        let latest = self.slot.latest.lock().ok()?;
+        let (mut latest, _) = self
+            .slot
+            .ready
+            .wait_timeout_while(latest, timeout, |latest| latest.is_none())
+            .ok()?;
+        latest.take()
+    }
+
+    /// 停止线程并取回摄像头
+    pub fn stop(mut self) -> Option<Camera> {
+        self.stop.store(true, Ordering::Release);
+        let handle = self.handle.take()?;
+        match handle.join() {
+            Ok(camera) => Some(camera),
+            Err(_) => {
+                error!("摄像头捕获线程异常退出");
+                None
+            }
+        }
+    }
+}
+
+impl Drop for CaptureThread {
+    fn drop(&mut self) {
+        // 未调用 stop 时只通知线程退出，不阻塞等待
+        self.stop.store(true, Ordering::Release);
+    }
+}
+
+/// 读取并解码一帧，带重试机制
+fn read_frame(camera: &mut Camera, max_retries: u32) -> Result<Frame, CameraError> {
+    for attempt in 0..=max_retries {
+        match camera.frame() {
+            Ok(frame) => {
+                if attempt > 0 {
+                    info!("摄像头捕获恢复正常，重试次数: {}", attempt);
+                }
+
+                // 获取帧的分辨率
+                let resolution = frame.resolution();
+                let width = resolution.width_x;
+                let height = resolution.height_y;
+
+                // 解码图像数据（处理 MJPEG 等压缩格式）
+                // 使用 decode_image 而不是 buffer，确保获取解码后的 RGB 数据
+                let data = match frame.decode_image::<RgbFormat>() {
+                    Ok(decoded_buffer) => {
+                        debug!("成功解码图像数据: {}x{}", width, height);
+                        decoded_buffer.to_vec()
+                    }
+                    Err(decode_err) => {
+                        warn!("解码图像失败: {}，尝试使用原始缓冲区", decode_err);
+                        // 如果解码失败，尝试使用原始缓冲区
+                        frame.buffer().to_vec()
+                    }
+                };
+
+                // 验证数据大小
+                let expected_size = (width * height * 3) as usize; // RGB8 格式
+                if data.len() != expected_size {
+                    warn!("摄像头帧数据大小不匹配: 实际 {} 字节, 期望 {} 字节 ({}x{}x3)",
+                           data.len(), expected_size, width, height);
+
+                    // 如果数据不完整，尝试重试
+                    if attempt < max_retries {
+                        debug!("帧数据不完整，尝试重新捕获 ({}/{})", attempt + 1, max_retries + 1);
+                        std::thread::sleep(Duration::from_millis(50));
+                        continue;
+                    }
+                    return Err(CameraError::CaptureError(format!(
+                        "帧数据大小不匹配: 实际 {} 字节, 期望 {} 字节",
+                        data.len(), expected_size
+                    )));
+                }
+
+                debug!("成功捕获帧: {}x{}, {} 字节", width, height, data.len());
+                return Ok(Frame {
+                    data,
+                    width,
+                    height,
+                    format: PixelFormat::RGB8,
+                });
+            }
+            Err(e) => {
+                if attempt < max_retries {
+                    warn!("捕获视频帧失败 (尝试 {}/{}): {}", attempt + 1, max_retries + 1, e);
+
+                    // 短暂等待后重试
+                    std::thread::sleep(Duration::from_millis(100));
+
+                    // 尝试重新启动视频流
+                    if let Err(restart_err) = restart_video_stream(camera) {
+                        warn!("重启视频流失败: {}", restart_err);
+                    }
+                } else {
+                    error!("捕获视频帧失败，已达到最大重试次数 ({}): {}", max_retries, e);
+                    return Err(CameraError::CaptureError(format!(
+                        "捕获帧失败，已重试 {} 次: {}",
+                        max_retries, e
+                    )));
+                }
+            }
+        }
+    }
+
+    // 理论上不会到达这里
+    Err(CameraError::CaptureError("未知的捕获错误".to_string()))
+}
+
+/// 重启视频流
+fn restart_video_stream(camera: &mut Camera) -> Result<(), CameraError> {
+    debug!("尝试重启视频流");
+
+    // 停止当前流
+    let _ = camera.stop_stream();
+
+    // 重新开始流
+    camera.open_stream().map_err(|e| {
+        error!("重启视频流失败: {}", e);
+        CameraError::CaptureError(format!("重启视频流失败: {}", e))
+    })?;
+
+    debug!("视频流重启成功");
+    Ok(())
+}
@@ -1,5 +1,6 @@
 // 摄像头管理器实现
 
+use super::capture::{CaptureThread, FrameNotifier};
 use crate::error::CameraError;
 use nokhwa::{
     pixel_format::RgbFormat,
@@ -7,6 +8,10 @@ use nokhwa::{
     Camera,
 };
 use log::{debug, error, info, warn};
+use std::time::Duration;
+
+/// 同步捕获时等待下一帧的最长时间（首帧可能需要等待设备启动）
+const FRAME_WAIT_TIMEOUT: Duration = Duration::from_secs(5);
 
 /// 摄像头设备信息
 #[derive(Debug, Clone, PartialEq)]
@@ -41,6 +46,9 @@ pub struct CameraManager {
     is_capturing: bool,
     retry_count: u32,
     max_retries: u32,
+    // 视频流打开后摄像头移交给捕获线程
+    capture: Option<CaptureThread>,
+    frame_notifier: Option<FrameNotifier>,
 }
 
 impl CameraManager {
@@ -55,6 +63,8 @@ impl CameraManager {
             is_capturing: false,
             retry_count: 0,
             max_retries: 3,
+            capture: None,
+            frame_notifier: None,
         };
         
         // 初始化时枚举设备
@@ -72,6 +82,8 @@ impl CameraManager {
             is_capturing: false,
             retry_count: 0,
             max_retries: 3,
+            capture: None,
+            frame_notifier: None,
         }
     }
 
@@ -124,7 +136,7 @@ impl CameraManager {
         }
         
         // 如果当前有设备打开，先关闭它
-        if self.camera.is_some() {
+        if self.camera.is_some() || self.capture.is_some() {
             self.close_device()?;
         }
         
@@ -161,6 +173,13 @@ impl CameraManager {
 
     /// 关闭当前摄像头设备
     pub fn close_device(&mut self) -> Result<(), CameraError> {
+        // 先停止捕获线程并取回摄像头
+        if let Some(capture) = self.capture.take() {
+            if let Some(camera) = capture.stop() {
+                self.camera = Some(camera);
+            }
+        }
+
         if let Some(mut camera) = self.camera.take() {
             info!("关闭摄像头设备");
             
@@ -179,122 +198,62 @@ impl CameraManager {
         Ok(())
     }
 
-    /// 捕获一帧视频
+    /// 设置新帧到达时的通知回调，在下次开始视频流时生效
+    pub fn set_frame_notifier(&mut self, notifier: FrameNotifier) {
+        self.frame_notifier = Some(notifier);
+    }
+
+    /// 捕获一帧视频，阻塞到下一帧到达
     pub fn capture_frame(&mut self) -> Result<Frame, CameraError> {
-        let camera = self.camera.as_mut()
-            .ok_or_else(|| {
-                error!("尝试捕获帧时没有打开的摄像头设备");
-                CameraError::CaptureError("没有打开的摄像头设备".to_string())
-            })?;
-        
-        // 如果还没有开始捕获，先开始视频流
-        if !self.is_capturing {
-            camera.open_stream()
-                .map_err(|e| {
-                    error!("开始视频流失败: {}", e);
-                    CameraError::CaptureError(format!("开始视频流失败: {}", e))
+        let result = self.start_capture()?.wait(FRAME_WAIT_TIMEOUT)
+            .unwrap_or_else(|| Err(CameraError::CaptureError("等待视频帧超时".to_string())));
+        self.record_capture_result(result)
+    }
+
+    /// 取走捕获线程的最新一帧，没有新帧时返回 Ok(None)，不阻塞
+    pub fn try_capture_frame(&mut self) -> Result<Option<Frame>, CameraError> {
+        let result = self.start_capture()?.try_take();
+        result.map(|result| self.record_capture_result(result)).transpose()
+    }
+
+    /// 如果还没有开始捕获，打开视频流并把摄像头移交给捕获线程
+    fn start_capture(&mut self) -> Result<&CaptureThread, CameraError> {
+        if self.capture.is_none() {
+            let mut camera = self.camera.take()
+                .ok_or_else(|| {
+                    error!("尝试捕获帧时没有打开的摄像头设备");
+                    CameraError::CaptureError("没有打开的摄像头设备".to_string())
                 })?;
+
+            if let Err(e) = camera.open_stream() {
+                error!("开始视频流失败: {}", e);
+                self.camera = Some(camera);
+                return Err(CameraError::CaptureError(format!("开始视频流失败: {}", e)));
+            }
             self.is_capturing = true;
             debug!("视频流已开始");
+
+            self.capture = Some(CaptureThread::spawn(camera, self.max_retries, self.frame_notifier.clone())?);
         }
-        
-        // 尝试捕获一帧，带重试机制
-        self.capture_frame_with_retry()
+
+        self.capture.as_ref()
+            .ok_or_else(|| CameraError::CaptureError("没有打开的摄像头设备".to_string()))
     }
-    
-    /// 带重试机制的帧捕获
-    fn capture_frame_with_retry(&mut self) -> Result<Frame, CameraError> {
-        for attempt in 0..=self.max_retries {
-            // 每次尝试都重新获取 camera 的可变引用
-            let frame_result = {
-                let camera = self.camera.as_mut().unwrap(); // 已在上层检查过
-                camera.frame()
-            };
-            
-            match frame_result {
-                Ok(frame) => {
-                    // 捕获成功，重置重试计数器
-                    if self.retry_count > 0 {
-                        info!("摄像头捕获恢复正常，重试次数: {}", self.retry_count);
-                        self.retry_count = 0;
-                    }
-                    
-                    // 获取帧的分辨率
-                    let resolution = frame.resolution();
-                    let width = resolution.width_x;
-                    let height = resolution.height_y;
-                    
-                    // 解码图像数据（处理 MJPEG 等压缩格式）
-                    // 使用 decode_image 而不是 buffer，确保获取解码后的 RGB 数据
-                    let data = match frame.decode_image::<RgbFormat>() {
-                        Ok(decoded_buffer) => {
-                            debug!("成功解码图像数据: {}x{}", width, height);
-                            decoded_buffer.to_vec()
-                        }
-                        Err(decode_err) => {
-                            warn!("解码图像失败: {}，尝试使用原始缓冲区", decode_err);
-                            // 如果解码失败，尝试使用原始缓冲区
-                            frame.buffer().to_vec()
-                        }
-                    };
-                    
-                    // 验证数据大小
-                    let expected_size = (width * height * 3) as usize; // RGB8 格式
-                    if data.len() != expected_size {
-                        warn!("摄像头帧数据大小不匹配: 实际 {} 字节, 期望 {} 字节 ({}x{}x3)", 
-                               data.len(), expected_size, width, height);
-                        
-                        // 如果数据不完整，尝试重试
-                        if attempt < self.max_retries {
-                            debug!("帧数据不完整，尝试重新捕获 ({}/{})", attempt + 1, self.max_retries + 1);
-                            std::thread::sleep(std::time::Duration::from_millis(50));
-                            continue;
-                        } else {
-                            return Err(CameraError::CaptureError(format!(
-                                "帧数据大小不匹配: 实际 {} 字节, 期望 {} 字节", 
-                                data.len(), expected_size
-                            )));
-                        }
-                    }
-                    
-                    debug!("成功捕获帧: {}x{}, {} 字节", width, height, data.len());
-                    
-                    return Ok(Frame {
-                        data,
-                        width,
-                        height,
-                        format: PixelFormat::RGB8,
-                    });
-                }
-                Err(e) => {
-                    self.retry_count += 1;
-                    
-                    if attempt < self.max_retries {
-                        warn!("捕获视频帧失败 (尝试 {}/{}): {}", attempt + 1, self.max_retries + 1, e);
-                        
-                        // 短暂等待后重试
-                        std::thread::sleep(std::time::Duration::from_millis(100));
-                        
-                        // 尝试重新启动视频流
-                        if let Err(restart_err) = self.restart_video_stream() {
-                            warn!("重启视频流失败: {}", restart_err);
-                        }
-                    } else {
-                        error!("捕获视频帧失败，已达到最大重试次数 ({}): {}", self.max_retries, e);
-                        return Err(CameraError::CaptureError(format!(
-                            "捕获帧失败，已重试 {} 次: {}", 
-                            self.max_retries, 
-                            e
-                        )));
-                    }
+
+    /// 更新重试计数：捕获线程在重试耗尽后才报告错误
+    fn record_capture_result(&mut self, result: Result<Frame, CameraError>) -> Result<Frame, CameraError> {
+        match &result {
+            Ok(_) => {
+                if self.retry_count > 0 {
+                    info!("摄像头捕获恢复正常，重试次数: {}", self.retry_count);
+                    self.retry_count = 0;
                 }
             }
+            Err(_) => self.retry_count += 1,
         }
-        
-        // 理论上不会到达这里
-        Err(CameraError::CaptureError("未知的捕获错误".to_string()))
+        result
     }
-    
+
     /// 将 nokhwa 错误映射到 CameraError
     fn map_nokhwa_error(error: nokhwa::NokhwaError) -> CameraError {
         match error {
@@ -427,28 +386,6 @@ impl CameraManager {
         }
     }
     
-    /// 重启视频流
-    fn restart_video_stream(&mut self) -> Result<(), CameraError> {
-        if let Some(camera) = &mut self.camera {
-            debug!("尝试重启视频流");
-            
-            // 停止当前流
-            let _ = camera.stop_stream();
-            
-            // 重新开始流
-            camera.open_stream()
-                .map_err(|e| {
-                    error!("重启视频流失败: {}", e);
-                    CameraError::CaptureError(format!("重启视频流失败: {}", e))
-                })?;
-            
-            debug!("视频流重启成功");
-            Ok(())
-        } else {
-            Err(CameraError::CaptureError("没有可用的摄像头设备".to_string()))
-        }
-    }
-
     /// 获取当前设备信息
     pub fn current_device(&self) -> Option<&CameraInfo> {
         self.current_device_index
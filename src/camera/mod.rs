@@ -1,5 +1,6 @@
 // 摄像头管理模块
 
+pub mod capture;
 pub mod manager;
 
 #[cfg(test)]
@@ -126,6 +126,8 @@ mod integration_tests {
             is_capturing: false,
             retry_count: 0,
             max_retries: 3,
+            capture: None,
+            frame_notifier: None,
         };
         
         let result = empty_manager.capture_frame();
@@ -180,6 +182,8 @@ mod mock_tests {
             is_capturing: false,
             retry_count: 0,
             max_retries: 3,
+            capture: None,
+            frame_notifier: None,
         }
     }
 
@@ -230,6 +234,8 @@ mod error_handling_tests {
             is_capturing: false,
             retry_count: 0,
             max_retries: 3,
+            capture: None,
+            frame_notifier: None,
         };
         
         // 测试初始状态
@@ -259,6 +265,8 @@ mod error_handling_tests {
             is_capturing: false,
             retry_count: 0,
             max_retries: 3,
+            capture: None,
+            frame_notifier: None,
         };
         
         let result = manager.check_device_permissions();
@@ -295,6 +303,8 @@ mod error_handling_tests {
             is_capturing: false,
             retry_count: 0,
             max_retries: 3,
+            capture: None,
+            frame_notifier: None,
         };
         
         // 测试有效索引
@@ -3,6 +3,7 @@
 use crate::error::ConfigError;
 use log::{info, warn};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs;
 use std::path::PathBuf;
 
@@ -14,6 +15,9 @@ pub struct AppConfig {
     pub version: String,
     pub window: WindowConfig,
     pub camera: CameraConfig,
+    /// 渲染配置（旧配置文件中可能缺失）
+    #[serde(default)]
+    pub render: RenderConfig,
 }
 
 fn default_version() -> String {
@@ -27,8 +31,25 @@ pub struct WindowConfig {
     pub position_y: f64,
     pub width: u32,
     pub height: u32,
+    /// 遮罩形状旋转角度（度）
     pub rotation: f32,
     pub shape: String,
+    /// 视频内容旋转角度（度）
+    #[serde(default)]
+    pub video_rotation: f32,
+    /// 视频保持正向，不跟随遮罩旋转
+    #[serde(default)]
+    pub keep_video_upright: bool,
+    /// 遮罩旋转自动对齐容差（度），0 表示禁用
+    #[serde(default = "default_snap_tolerance")]
+    pub rotation_snap_tolerance: f32,
+    /// 视频旋转自动对齐容差（度），0 表示禁用
+    #[serde(default = "default_snap_tolerance")]
+    pub video_snap_tolerance: f32,
+}
+
+fn default_snap_tolerance() -> f32 {
+    5.0
 }
 
 /// 摄像头配置
@@ -37,6 +58,529 @@ pub struct CameraConfig {
     pub device_index: usize,
 }
 
+/// 渲染配置
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RenderConfig {
+    /// 遮罩边缘质量：Fast / Balanced / High / Adaptive
+    #[serde(default = "default_mask_quality")]
+    pub mask_quality: String,
+    /// 遮罩缓存内存预算（MB），CPU 缓冲区和 GPU 纹理各自适用，0 表示禁用
+    #[serde(default = "default_mask_cache_budget_mb")]
+    pub mask_cache_budget_mb: u32,
+    /// 目标帧率（1-240），摄像头新帧到达或界面变化时按此上限渲染
+    #[serde(default = "default_target_fps")]
+    pub target_fps: u32,
+    /// 颜色调整与滤镜
+    #[serde(default)]
+    pub color: ColorConfig,
+    /// 绿幕抠像
+    #[serde(default)]
+    pub chroma_key: ChromaKeyConfig,
+    /// 磨皮平滑与时域降噪
+    #[serde(default)]
+    pub denoise: DenoiseConfig,
+    /// 自动白平衡、色阶与弱光增强
+    #[serde(default)]
+    pub auto_correct: AutoCorrectConfig,
+    /// 文字与名牌叠加
+    #[serde(default)]
+    pub text_overlay: TextOverlayConfig,
+    /// 贴纸与图片叠加
+    #[serde(default)]
+    pub stickers: StickerConfig,
+    /// 手绘标注画笔
+    #[serde(default)]
+    pub annotation: AnnotationConfig,
+    /// 后处理特效链
+    #[serde(default)]
+    pub effects: EffectsConfig,
+    /// GPU 适配器与呈现模式
+    #[serde(default)]
+    pub gpu: GpuConfig,
+    /// 快照
+    #[serde(default)]
+    pub snapshot: SnapshotConfig,
+    /// 录制
+    #[serde(default)]
+    pub recording: RecordingConfig,
+    /// 帧输出流
+    #[serde(default)]
+    pub frame_output: FrameOutputConfig,
+    /// 本地预览服务
+    #[serde(default)]
+    pub preview_server: PreviewServerConfig,
+}
+
+fn default_mask_quality() -> String {
+    "Balanced".to_string()
+}
+
+fn default_mask_cache_budget_mb() -> u32 {
+    32
+}
+
+fn default_target_fps() -> u32 {
+    30
+}
+
+impl Default for RenderConfig {
+    fn default() -> Self {
+        Self {
+            mask_quality: default_mask_quality(),
+            mask_cache_budget_mb: default_mask_cache_budget_mb(),
+            target_fps: default_target_fps(),
+            color: ColorConfig::default(),
+            chroma_key: ChromaKeyConfig::default(),
+            denoise: DenoiseConfig::default(),
+            auto_correct: AutoCorrectConfig::default(),
+            text_overlay: TextOverlayConfig::default(),
+            stickers: StickerConfig::default(),
+            annotation: AnnotationConfig::default(),
+            effects: EffectsConfig::default(),
+            gpu: GpuConfig::default(),
+            snapshot: SnapshotConfig::default(),
+            recording: RecordingConfig::default(),
+            frame_output: FrameOutputConfig::default(),
+            preview_server: PreviewServerConfig::default(),
+        }
+    }
+}
+
+/// 颜色调整配置
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct ColorConfig {
+    /// 预设外观：None / Grayscale / Sepia / HighContrast
+    pub preset: String,
+    /// 亮度偏移（-1.0 ~ 1.0）
+    pub brightness: f32,
+    /// 对比度（0.0 ~ 4.0）
+    pub contrast: f32,
+    /// 饱和度（0.0 ~ 4.0）
+    pub saturation: f32,
+    /// 伽马（0.1 ~ 5.0）
+    pub gamma: f32,
+    /// 色相偏移（度，-180 ~ 180）
+    pub hue_shift: f32,
+    /// 色温（-1.0 偏冷 ~ 1.0 偏暖）
+    pub temperature: f32,
+    /// .cube LUT 文件路径，空字符串表示不使用
+    pub lut_path: String,
+    /// 是否启用 LUT
+    pub lut_enabled: bool,
+}
+
+impl Default for ColorConfig {
+    fn default() -> Self {
+        Self {
+            preset: "None".to_string(),
+            brightness: 0.0,
+            contrast: 1.0,
+            saturation: 1.0,
+            gamma: 1.0,
+            hue_shift: 0.0,
+            temperature: 0.0,
+            lut_path: String::new(),
+            lut_enabled: true,
+        }
+    }
+}
+
+/// 绿幕抠像配置
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct ChromaKeyConfig {
+    /// 是否启用
+    pub enabled: bool,
+    /// 关键色 RGB（0 ~ 255）
+    pub key_color: [u8; 3],
+    /// 相似度（0.0 ~ 1.0）
+    pub similarity: f32,
+    /// 平滑度（0.0 ~ 1.0）
+    pub smoothness: f32,
+    /// 溢色抑制范围（0.0 ~ 1.0），0 表示不抑制
+    pub spill: f32,
+}
+
+impl Default for ChromaKeyConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            key_color: [0, 177, 64],
+            similarity: 0.2,
+            smoothness: 0.08,
+            spill: 0.1,
+        }
+    }
+}
+
+/// 降噪配置
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct DenoiseConfig {
+    /// 启用双边滤波平滑（磨皮）
+    pub smoothing_enabled: bool,
+    /// 平滑强度（0.0 ~ 1.0）
+    pub smoothing_strength: f32,
+    /// 启用时域降噪
+    pub temporal_enabled: bool,
+    /// 时域降噪强度（0.0 ~ 1.0）
+    pub temporal_strength: f32,
+    /// 运动阈值（0.001 ~ 1.0），越小越容易判定为运动
+    pub motion_threshold: f32,
+}
+
+impl Default for DenoiseConfig {
+    fn default() -> Self {
+        Self {
+            smoothing_enabled: false,
+            smoothing_strength: 0.5,
+            temporal_enabled: false,
+            temporal_strength: 0.5,
+            motion_threshold: 0.05,
+        }
+    }
+}
+
+/// 自动校正配置
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct AutoCorrectConfig {
+    /// 灰度世界自动白平衡
+    pub white_balance: bool,
+    /// 自动色阶
+    pub auto_levels: bool,
+    /// 弱光增强
+    pub low_light_boost: bool,
+    /// 时间平滑系数（0.0 ~ 0.99），越大亮度变化越平缓
+    pub smoothing: f32,
+}
+
+impl Default for AutoCorrectConfig {
+    fn default() -> Self {
+        Self {
+            white_balance: false,
+            auto_levels: false,
+            low_light_boost: false,
+            smoothing: 0.9,
+        }
+    }
+}
+
+/// 文字叠加配置
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct TextOverlayConfig {
+    /// 是否显示
+    pub enabled: bool,
+    /// 字体文件路径，为空时使用系统中文字体
+    pub font_path: String,
+    /// 文字列表
+    pub items: Vec<TextItemConfig>,
+}
+
+impl Default for TextOverlayConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            font_path: String::new(),
+            items: Vec::new(),
+        }
+    }
+}
+
+/// 单条文字配置
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct TextItemConfig {
+    /// 内容来源：Static（固定文字）/ Clock（时钟）
+    pub source: String,
+    /// 固定文字内容，或时钟的 strftime 格式（如 "%H:%M"）
+    pub content: String,
+    /// 字号（像素，6 ~ 200）
+    pub font_size: f32,
+    /// 文字颜色 RGBA
+    pub color: [u8; 4],
+    /// 是否绘制背景胶囊
+    pub background: bool,
+    /// 背景颜色 RGBA
+    pub background_color: [u8; 4],
+    /// 位置：Top / Bottom / Arc
+    pub anchor: String,
+    /// 与形状边缘的距离（相对窗口短边，0.0 ~ 0.5）
+    pub margin: f32,
+}
+
+impl Default for TextItemConfig {
+    fn default() -> Self {
+        Self {
+            source: "Static".to_string(),
+            content: String::new(),
+            font_size: 20.0,
+            color: [255, 255, 255, 255],
+            background: true,
+            background_color: [0, 0, 0, 160],
+            anchor: "Bottom".to_string(),
+            margin: 0.1,
+        }
+    }
+}
+
+/// 贴纸配置
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct StickerConfig {
+    /// 是否显示
+    pub enabled: bool,
+    /// 贴纸列表，靠后的绘制在上方
+    pub items: Vec<StickerItemConfig>,
+}
+
+impl Default for StickerConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            items: Vec::new(),
+        }
+    }
+}
+
+/// 单个贴纸配置
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct StickerItemConfig {
+    /// 图片路径（PNG / APNG / GIF 等）
+    pub path: String,
+    /// 中心位置，以形状内切圆半径为单位，x 向右、y 向下（-2.0 ~ 2.0）
+    pub position: [f32; 2],
+    /// 宽度占形状直径的比例（0.01 ~ 4.0）
+    pub scale: f32,
+    /// 顺时针旋转角度（-360 ~ 360）
+    pub rotation: f32,
+    /// 层级：Below（被遮罩裁剪）/ Above（可越过形状边缘）
+    pub layer: String,
+    /// 动画帧间隔（毫秒，0 使用图片自带时长，最大 10000）
+    pub frame_delay_ms: u32,
+}
+
+impl Default for StickerItemConfig {
+    fn default() -> Self {
+        Self {
+            path: String::new(),
+            position: [0.0, 0.0],
+            scale: 0.3,
+            rotation: 0.0,
+            layer: "Above".to_string(),
+            frame_delay_ms: 0,
+        }
+    }
+}
+
+/// 手绘标注配置（笔画本身不保存）
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct AnnotationConfig {
+    /// 画笔颜色 RGBA
+    pub color: [u8; 4],
+    /// 画笔粗细（相对窗口短边，0.002 ~ 0.1）
+    pub width: f32,
+    /// 是否自动淡出
+    pub auto_fade: bool,
+    /// 笔画完成后多少秒开始淡出（0.5 ~ 600）
+    pub fade_seconds: f32,
+}
+
+impl Default for AnnotationConfig {
+    fn default() -> Self {
+        Self {
+            color: [255, 59, 48, 255],
+            width: 0.012,
+            auto_fade: false,
+            fade_seconds: 5.0,
+        }
+    }
+}
+
+/// 后处理特效链配置
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct EffectsConfig {
+    /// 是否启用特效链
+    pub enabled: bool,
+    /// 特效目录，为空时使用配置文件所在目录下的 effects
+    pub directory: String,
+    /// 特效通道，按顺序依次处理
+    pub passes: Vec<EffectPassConfig>,
+}
+
+impl Default for EffectsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            directory: String::new(),
+            passes: Vec::new(),
+        }
+    }
+}
+
+/// 单个特效通道配置
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct EffectPassConfig {
+    /// 特效目录名
+    pub name: String,
+    /// 是否启用
+    pub enabled: bool,
+    /// 参数覆盖，未列出的参数使用清单中的默认值（范围由清单限制）
+    pub params: BTreeMap<String, f32>,
+}
+
+impl Default for EffectPassConfig {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            enabled: true,
+            params: BTreeMap::new(),
+        }
+    }
+}
+
+/// GPU 适配器配置
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct GpuConfig {
+    /// 电源偏好：LowPower / HighPerformance / None
+    pub power_preference: String,
+    /// 图形后端：Auto / Vulkan / Gl / Dx12 / Metal
+    pub backend: String,
+    /// 呈现模式：Auto / Fifo / Mailbox / Immediate，Auto 使用表面的首选模式
+    pub present_mode: String,
+    /// 强制使用软件适配器
+    pub force_fallback_adapter: bool,
+}
+
+impl Default for GpuConfig {
+    fn default() -> Self {
+        Self {
+            power_preference: "LowPower".to_string(),
+            backend: "Auto".to_string(),
+            present_mode: "Auto".to_string(),
+            force_fallback_adapter: false,
+        }
+    }
+}
+
+/// 快照配置
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct SnapshotConfig {
+    /// 保存目录，为空时使用图片目录下的 Mira 文件夹
+    pub directory: String,
+    /// 拍摄前倒计时（0-10 秒），0 表示立即拍摄
+    pub countdown_seconds: u32,
+    /// 拍摄时显示快门闪光
+    pub flash: bool,
+    /// 保存摄像头原始画面而不是遮罩合成后的画面
+    pub raw_frame: bool,
+}
+
+impl Default for SnapshotConfig {
+    fn default() -> Self {
+        Self {
+            directory: String::new(),
+            countdown_seconds: 0,
+            flash: true,
+            raw_frame: false,
+        }
+    }
+}
+
+/// 录制配置
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct RecordingConfig {
+    /// 保存目录，为空时与快照相同
+    pub directory: String,
+    /// 文件格式：Apng / Gif / PngSequence
+    pub format: String,
+    /// 最高录制帧率（1-60）
+    pub fps: u32,
+    /// 最长录制时长（1-600 秒），达到后自动停止
+    pub max_seconds: u32,
+    /// 文件大小上限（1-4096 MB），达到后自动停止
+    pub max_file_mb: u32,
+}
+
+impl Default for RecordingConfig {
+    fn default() -> Self {
+        Self {
+            directory: String::new(),
+            format: "Apng".to_string(),
+            fps: 15,
+            max_seconds: 60,
+            max_file_mb: 200,
+        }
+    }
+}
+
+/// 帧输出流配置
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct FrameOutputConfig {
+    /// 启动时开始输出
+    pub enabled: bool,
+    /// 输出格式：Y4m / RawRgba
+    pub format: String,
+    /// 输出目标："-" 表示标准输出，其余为文件或命名管道路径
+    pub target: String,
+    /// 输出宽高（0-4096），为 0 时使用窗口画面尺寸
+    pub width: u32,
+    pub height: u32,
+    /// 输出帧率（1-60）
+    pub fps: u32,
+}
+
+impl Default for FrameOutputConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            format: "Y4m".to_string(),
+            target: "-".to_string(),
+            width: 0,
+            height: 0,
+            fps: 30,
+        }
+    }
+}
+
+/// 本地预览服务配置
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct PreviewServerConfig {
+    /// 启动时开启服务
+    pub enabled: bool,
+    /// 监听端口（1-65535），只监听 127.0.0.1
+    pub port: u16,
+    /// 最高帧率（1-60）
+    pub fps: u32,
+    /// 同时连接数上限（1-32）
+    pub max_clients: u32,
+    /// MJPEG 的 JPEG 质量（1-100）
+    pub jpeg_quality: u32,
+}
+
+impl Default for PreviewServerConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: 8765,
+            fps: 15,
+            max_clients: 4,
+            jpeg_quality: 80,
+        }
+    }
+}
+
 /// 配置管理器
 pub struct ConfigManager {
     config_path: PathBuf,
@@ -91,8 +635,13 @@ impl ConfigManager {
                 height: 400,
                 rotation: 0.0,
                 shape: "Circle".to_string(),
+                video_rotation: 0.0,
+                keep_video_upright: false,
+                rotation_snap_tolerance: default_snap_tolerance(),
+                video_snap_tolerance: default_snap_tolerance(),
             },
             camera: CameraConfig { device_index: 0 },
+            render: RenderConfig::default(),
         }
     }
 
@@ -265,6 +814,32 @@ impl ConfigManager {
             config.window.rotation = normalized;
         }
 
+        // 验证视频旋转角度
+        if !config.window.video_rotation.is_finite() {
+            warn!("视频旋转角度无效，修正为 0.0");
+            config.window.video_rotation = 0.0;
+        } else if config.window.video_rotation < 0.0 || config.window.video_rotation >= 360.0 {
+            let normalized = config.window.video_rotation % 360.0;
+            let normalized = if normalized < 0.0 { normalized + 360.0 } else { normalized };
+            warn!("视频旋转角度 {} 超出范围，修正为 {}", config.window.video_rotation, normalized);
+            config.window.video_rotation = normalized;
+        }
+
+        // 验证自动对齐容差（0-45度）
+        for (name, tolerance) in [
+            ("遮罩", &mut config.window.rotation_snap_tolerance),
+            ("视频", &mut config.window.video_snap_tolerance),
+        ] {
+            if !tolerance.is_finite() {
+                warn!("{}旋转对齐容差无效，修正为默认值", name);
+                *tolerance = default_snap_tolerance();
+            } else if *tolerance < 0.0 || *tolerance > 45.0 {
+                let clamped = tolerance.clamp(0.0, 45.0);
+                warn!("{}旋转对齐容差 {} 超出范围，修正为 {}", name, tolerance, clamped);
+                *tolerance = clamped;
+            }
+        }
+
         // 验证形状名称
         let valid_shapes = ["Circle", "Ellipse", "Rectangle", "RoundedRectangle", "Heart"];
         if config.window.shape.is_empty() || !valid_shapes.contains(&config.window.shape.as_str()) {
@@ -272,6 +847,283 @@ impl ConfigManager {
             config.window.shape = "Circle".to_string();
         }
 
+        // 验证遮罩质量名称
+        let valid_qualities = ["Fast", "Balanced", "High", "Adaptive"];
+        if !valid_qualities.contains(&config.render.mask_quality.as_str()) {
+            warn!("无效的遮罩质量 '{}'，修正为 Balanced", config.render.mask_quality);
+            config.render.mask_quality = default_mask_quality();
+        }
+
+        // 验证遮罩缓存预算（最大 1024MB）
+        if config.render.mask_cache_budget_mb > 1024 {
+            warn!("遮罩缓存预算 {}MB 超过最大值，修正为 1024MB", config.render.mask_cache_budget_mb);
+            config.render.mask_cache_budget_mb = 1024;
+        }
+
+        // 验证目标帧率（1-240）
+        if config.render.target_fps == 0 {
+            warn!("目标帧率不能为 0，修正为 {}", default_target_fps());
+            config.render.target_fps = default_target_fps();
+        } else if config.render.target_fps > 240 {
+            warn!("目标帧率 {} 超过最大值，修正为 240", config.render.target_fps);
+            config.render.target_fps = 240;
+        }
+
+        // 验证颜色调整参数
+        let color = &mut config.render.color;
+        let valid_presets = ["None", "Grayscale", "Sepia", "HighContrast"];
+        if !valid_presets.contains(&color.preset.as_str()) {
+            warn!("无效的颜色预设 '{}'，修正为 None", color.preset);
+            color.preset = "None".to_string();
+        }
+
+        let defaults = ColorConfig::default();
+        let ranges = [
+            ("亮度", &mut color.brightness, defaults.brightness, -1.0, 1.0),
+            ("对比度", &mut color.contrast, defaults.contrast, 0.0, 4.0),
+            ("饱和度", &mut color.saturation, defaults.saturation, 0.0, 4.0),
+            ("伽马", &mut color.gamma, defaults.gamma, 0.1, 5.0),
+            ("色相偏移", &mut color.hue_shift, defaults.hue_shift, -180.0, 180.0),
+            ("色温", &mut color.temperature, defaults.temperature, -1.0, 1.0),
+        ];
+        for (name, value, default, min, max) in ranges {
+            if !value.is_finite() {
+                warn!("{} {} 无效，修正为 {}", name, value, default);
+                *value = default;
+            } else if *value < min || *value > max {
+                let clamped = value.clamp(min, max);
+                warn!("{} {} 超出范围，修正为 {}", name, value, clamped);
+                *value = clamped;
+            }
+        }
+
+        // 验证绿幕抠像参数
+        let chroma_key = &mut config.render.chroma_key;
+        let defaults = ChromaKeyConfig::default();
+        let ranges = [
+            ("抠像相似度", &mut chroma_key.similarity, defaults.similarity),
+            ("抠像平滑度", &mut chroma_key.smoothness, defaults.smoothness),
+            ("溢色抑制", &mut chroma_key.spill, defaults.spill),
+        ];
+        for (name, value, default) in ranges {
+            if !value.is_finite() {
+                warn!("{} {} 无效，修正为 {}", name, value, default);
+                *value = default;
+            } else if *value < 0.0 || *value > 1.0 {
+                let clamped = value.clamp(0.0, 1.0);
+                warn!("{} {} 超出范围，修正为 {}", name, value, clamped);
+                *value = clamped;
+            }
+        }
+
+        // 验证降噪参数
+        let denoise = &mut config.render.denoise;
+        let defaults = DenoiseConfig::default();
+        let ranges = [
+            ("平滑强度", &mut denoise.smoothing_strength, defaults.smoothing_strength, 0.0),
+            ("时域降噪强度", &mut denoise.temporal_strength, defaults.temporal_strength, 0.0),
+            ("运动阈值", &mut denoise.motion_threshold, defaults.motion_threshold, 0.001),
+        ];
+        for (name, value, default, min) in ranges {
+            if !value.is_finite() {
+                warn!("{} {} 无效，修正为 {}", name, value, default);
+                *value = default;
+            } else if *value < min || *value > 1.0 {
+                let clamped = value.clamp(min, 1.0);
+                warn!("{} {} 超出范围，修正为 {}", name, value, clamped);
+                *value = clamped;
+            }
+        }
+
+        // 验证自动校正平滑系数
+        let auto_correct = &mut config.render.auto_correct;
+        if !auto_correct.smoothing.is_finite() {
+            let default = AutoCorrectConfig::default().smoothing;
+            warn!("自动校正平滑系数 {} 无效，修正为 {}", auto_correct.smoothing, default);
+            auto_correct.smoothing = default;
+        } else if auto_correct.smoothing < 0.0 || auto_correct.smoothing > 0.99 {
+            let clamped = auto_correct.smoothing.clamp(0.0, 0.99);
+            warn!("自动校正平滑系数 {} 超出范围，修正为 {}", auto_correct.smoothing, clamped);
+            auto_correct.smoothing = clamped;
+        }
+
+        // 验证文字叠加
+        let item_defaults = TextItemConfig::default();
+        for item in &mut config.render.text_overlay.items {
+            if !["Static", "Clock"].contains(&item.source.as_str()) {
+                warn!("无效的文字来源 '{}'，修正为 Static", item.source);
+                item.source = item_defaults.source.clone();
+            }
+            if !["Top", "Bottom", "Arc"].contains(&item.anchor.as_str()) {
+                warn!("无效的文字位置 '{}'，修正为 Bottom", item.anchor);
+                item.anchor = item_defaults.anchor.clone();
+            }
+            let ranges = [
+                ("字号", &mut item.font_size, item_defaults.font_size, 6.0, 200.0),
+                ("文字边距", &mut item.margin, item_defaults.margin, 0.0, 0.5),
+            ];
+            for (name, value, default, min, max) in ranges {
+                if !value.is_finite() {
+                    warn!("{} {} 无效，修正为 {}", name, value, default);
+                    *value = default;
+                } else if *value < min || *value > max {
+                    let clamped = value.clamp(min, max);
+                    warn!("{} {} 超出范围，修正为 {}", name, value, clamped);
+                    *value = clamped;
+                }
+            }
+        }
+
+        // 验证贴纸
+        let sticker_defaults = StickerItemConfig::default();
+        for item in &mut config.render.stickers.items {
+            if !["Below", "Above"].contains(&item.layer.as_str()) {
+                warn!("无效的贴纸层级 '{}'，修正为 Above", item.layer);
+                item.layer = sticker_defaults.layer.clone();
+            }
+            if item.frame_delay_ms > 10000 {
+                warn!("贴纸帧间隔 {}ms 过大，修正为 10000ms", item.frame_delay_ms);
+                item.frame_delay_ms = 10000;
+            }
+            let [x, y] = &mut item.position;
+            let ranges = [
+                ("贴纸横向位置", x, sticker_defaults.position[0], -2.0, 2.0),
+                ("贴纸纵向位置", y, sticker_defaults.position[1], -2.0, 2.0),
+                ("贴纸缩放", &mut item.scale, sticker_defaults.scale, 0.01, 4.0),
+                ("贴纸旋转角度", &mut item.rotation, sticker_defaults.rotation, -360.0, 360.0),
+            ];
+            for (name, value, default, min, max) in ranges {
+                if !value.is_finite() {
+                    warn!("{} {} 无效，修正为 {}", name, value, default);
+                    *value = default;
+                } else if *value < min || *value > max {
+                    let clamped = value.clamp(min, max);
+                    warn!("{} {} 超出范围，修正为 {}", name, value, clamped);
+                    *value = clamped;
+                }
+            }
+        }
+
+        // 验证标注画笔
+        let annotation = &mut config.render.annotation;
+        let annotation_defaults = AnnotationConfig::default();
+        let ranges = [
+            ("标注粗细", &mut annotation.width, annotation_defaults.width, 0.002, 0.1),
+            ("标注淡出时间", &mut annotation.fade_seconds, annotation_defaults.fade_seconds, 0.5, 600.0),
+        ];
+        for (name, value, default, min, max) in ranges {
+            if !value.is_finite() {
+                warn!("{} {} 无效，修正为 {}", name, value, default);
+                *value = default;
+            } else if *value < min || *value > max {
+                let clamped = value.clamp(min, max);
+                warn!("{} {} 超出范围，修正为 {}", name, value, clamped);
+                *value = clamped;
+            }
+        }
+
+        // 验证特效通道（参数范围在加载清单时检查）
+        config.render.effects.passes.retain(|pass| {
+            let valid = !pass.name.is_empty() && !pass.name.contains(['/', '\\']) && pass.name != "..";
+            if !valid {
+                warn!("无效的特效名称 '{}'，已忽略", pass.name);
+            }
+            valid
+        });
+        for pass in &mut config.render.effects.passes {
+            pass.params.retain(|name, value| {
+                if !value.is_finite() {
+                    warn!("特效 {} 的参数 {} 无效，使用默认值", pass.name, name);
+                }
+                value.is_finite()
+            });
+        }
+
+        // 验证 GPU 选项名称
+        let gpu = &mut config.render.gpu;
+        let defaults = GpuConfig::default();
+        let options = [
+            ("电源偏好", &mut gpu.power_preference, defaults.power_preference, &["LowPower", "HighPerformance", "None"][..]),
+            ("图形后端", &mut gpu.backend, defaults.backend, &["Auto", "Vulkan", "Gl", "Dx12", "Metal"][..]),
+            ("呈现模式", &mut gpu.present_mode, defaults.present_mode, &["Auto", "Fifo", "Mailbox", "Immediate"][..]),
+        ];
+        for (name, value, default, valid) in options {
+            if !valid.contains(&value.as_str()) {
+                warn!("无效的{} '{}'，修正为 {}", name, value, default);
+                *value = default;
+            }
+        }
+
+        // 验证快照倒计时（0-10 秒）
+        if config.render.snapshot.countdown_seconds > 10 {
+            warn!("快照倒计时 {} 秒超过最大值，修正为 10 秒", config.render.snapshot.countdown_seconds);
+            config.render.snapshot.countdown_seconds = 10;
+        }
+
+        // 验证录制参数
+        let recording = &mut config.render.recording;
+        let defaults = RecordingConfig::default();
+        if !["Apng", "Gif", "PngSequence"].contains(&recording.format.as_str()) {
+            warn!("无效的录制格式 '{}'，修正为 {}", recording.format, defaults.format);
+            recording.format = defaults.format;
+        }
+        let ranges = [
+            ("录制帧率", &mut recording.fps, 1, 60),
+            ("最长录制时长（秒）", &mut recording.max_seconds, 1, 600),
+            ("录制文件大小上限（MB）", &mut recording.max_file_mb, 1, 4096),
+        ];
+        for (name, value, min, max) in ranges {
+            if *value < min || *value > max {
+                let clamped = (*value).clamp(min, max);
+                warn!("{} {} 超出范围，修正为 {}", name, value, clamped);
+                *value = clamped;
+            }
+        }
+
+        // 验证帧输出参数
+        let frame_output = &mut config.render.frame_output;
+        let defaults = FrameOutputConfig::default();
+        if !["Y4m", "RawRgba"].contains(&frame_output.format.as_str()) {
+            warn!("无效的帧输出格式 '{}'，修正为 {}", frame_output.format, defaults.format);
+            frame_output.format = defaults.format;
+        }
+        if frame_output.target.trim().is_empty() {
+            warn!("帧输出目标为空，修正为标准输出");
+            frame_output.target = defaults.target;
+        }
+        let ranges = [
+            ("帧输出宽度", &mut frame_output.width, 0, 4096),
+            ("帧输出高度", &mut frame_output.height, 0, 4096),
+            ("帧输出帧率", &mut frame_output.fps, 1, 60),
+        ];
+        for (name, value, min, max) in ranges {
+            if *value < min || *value > max {
+                let clamped = (*value).clamp(min, max);
+                warn!("{} {} 超出范围，修正为 {}", name, value, clamped);
+                *value = clamped;
+            }
+        }
+
+        // 验证预览服务参数
+        let preview_server = &mut config.render.preview_server;
+        if preview_server.port == 0 {
+            let port = PreviewServerConfig::default().port;
+            warn!("预览服务端口不能为 0，修正为 {}", port);
+            preview_server.port = port;
+        }
+        let ranges = [
+            ("预览服务帧率", &mut preview_server.fps, 1, 60),
+            ("预览服务连接数上限", &mut preview_server.max_clients, 1, 32),
+            ("预览服务 JPEG 质量", &mut preview_server.jpeg_quality, 1, 100),
+        ];
+        for (name, value, min, max) in ranges {
+            if *value < min || *value > max {
+                let clamped = (*value).clamp(min, max);
+                warn!("{} {} 超出范围，修正为 {}", name, value, clamped);
+                *value = clamped;
+            }
+        }
+
         // 验证摄像头设备索引（确保在合理范围内）
         if config.camera.device_index > 99 {
             warn!("摄像头设备索引 {} 过大，修正为 0", config.camera.device_index);
@@ -338,8 +1190,13 @@ mod tests {
                 height: 50, // 小于最小值
                 rotation: 400.0, // 超出范围
                 shape: "InvalidShape".to_string(), // 无效形状
+                video_rotation: 0.0,
+                keep_video_upright: false,
+                rotation_snap_tolerance: 5.0,
+                video_snap_tolerance: 5.0,
             },
             camera: CameraConfig { device_index: 0 },
+            render: RenderConfig::default(),
         };
 
         manager.validate_and_fix_config(&mut config);
@@ -362,8 +1219,13 @@ mod tests {
                 height: 400,
                 rotation: -45.0, // 负角度
                 shape: "Circle".to_string(),
+                video_rotation: 0.0,
+                keep_video_upright: false,
+                rotation_snap_tolerance: 5.0,
+                video_snap_tolerance: 5.0,
             },
             camera: CameraConfig { device_index: 0 },
+            render: RenderConfig::default(),
         };
 
         manager.validate_and_fix_config(&mut config);
@@ -397,8 +1259,13 @@ mod tests {
                 height: 600,
                 rotation: 45.0,
                 shape: "Heart".to_string(),
+                video_rotation: 0.0,
+                keep_video_upright: false,
+                rotation_snap_tolerance: 5.0,
+                video_snap_tolerance: 5.0,
             },
             camera: CameraConfig { device_index: 1 },
+            render: RenderConfig::default(),
         };
 
         // 保存配置
@@ -503,8 +1370,13 @@ mod tests {
                 height: 400,
                 rotation: 0.0,
                 shape: "Circle".to_string(),
+                video_rotation: 0.0,
+                keep_video_upright: false,
+                rotation_snap_tolerance: 5.0,
+                video_snap_tolerance: 5.0,
             },
             camera: CameraConfig { device_index: 0 },
+            render: RenderConfig::default(),
         };
 
         // 检查是否需要迁移
@@ -527,8 +1399,13 @@ mod tests {
                 height: 5000, // 超过最大值
                 rotation: f32::NAN, // 无效角度
                 shape: "".to_string(), // 空形状名称
+                video_rotation: 0.0,
+                keep_video_upright: false,
+                rotation_snap_tolerance: 5.0,
+                video_snap_tolerance: 5.0,
             },
             camera: CameraConfig { device_index: 999 }, // 过大的设备索引
+            render: RenderConfig::default(),
         };
 
         manager.validate_and_fix_config(&mut config);
@@ -555,8 +1432,13 @@ mod tests {
                 height: 10, // 小于最小值
                 rotation: 0.0,
                 shape: "Circle".to_string(),
+                video_rotation: 0.0,
+                keep_video_upright: false,
+                rotation_snap_tolerance: 5.0,
+                video_snap_tolerance: 5.0,
             },
             camera: CameraConfig { device_index: 0 },
+            render: RenderConfig::default(),
         };
 
         manager.validate_and_fix_config(&mut config);
@@ -577,8 +1459,13 @@ mod tests {
                 height: 400,
                 rotation: 0.0,
                 shape: "Circle".to_string(),
+                video_rotation: 0.0,
+                keep_video_upright: false,
+                rotation_snap_tolerance: 5.0,
+                video_snap_tolerance: 5.0,
             },
             camera: CameraConfig { device_index: 0 },
+            render: RenderConfig::default(),
         };
 
         assert!(manager.needs_migration(&future_config));
@@ -601,8 +1488,13 @@ mod tests {
                     height: 400,
                     rotation: 0.0,
                     shape: invalid_shape.to_string(),
+                    video_rotation: 0.0,
+                    keep_video_upright: false,
+                    rotation_snap_tolerance: 5.0,
+                    video_snap_tolerance: 5.0,
                 },
                 camera: CameraConfig { device_index: 0 },
+                render: RenderConfig::default(),
             };
 
             manager.validate_and_fix_config(&mut config);
@@ -625,12 +1517,853 @@ mod tests {
                     height: 400,
                     rotation: 0.0,
                     shape: valid_shape.to_string(),
+                    video_rotation: 0.0,
+                    keep_video_upright: false,
+                    rotation_snap_tolerance: 5.0,
+                    video_snap_tolerance: 5.0,
                 },
                 camera: CameraConfig { device_index: 0 },
+                render: RenderConfig::default(),
             };
 
             manager.validate_and_fix_config(&mut config);
             assert_eq!(config.window.shape, *valid_shape, "Valid shape '{}' should be preserved", valid_shape);
         }
     }
+
+    #[test]
+    fn test_video_rotation_validation() {
+        let manager = ConfigManager::new().unwrap();
+        let mut config = ConfigManager::default_config();
+        config.window.video_rotation = -90.0;
+        config.window.rotation_snap_tolerance = f32::NAN;
+        config.window.video_snap_tolerance = 60.0;
+
+        manager.validate_and_fix_config(&mut config);
+        assert_eq!(config.window.video_rotation, 270.0);
+        assert_eq!(config.window.rotation_snap_tolerance, 5.0);
+        assert_eq!(config.window.video_snap_tolerance, 45.0);
+    }
+
+    #[test]
+    fn test_config_without_video_rotation_fields() {
+        // 旧版本配置文件只有单一旋转角度，视频旋转相关字段应使用默认值
+        let old_toml = r#"
+version = "1.0"
+
+[window]
+position_x = 100.0
+position_y = 100.0
+width = 400
+height = 400
+rotation = 90.0
+shape = "Heart"
+
+[camera]
+device_index = 0
+"#;
+        let config: AppConfig = toml::from_str(old_toml).unwrap();
+        assert_eq!(config.window.rotation, 90.0);
+        assert_eq!(config.window.video_rotation, 0.0);
+        assert!(!config.window.keep_video_upright);
+        assert_eq!(config.window.rotation_snap_tolerance, 5.0);
+        assert_eq!(config.window.video_snap_tolerance, 5.0);
+    }
+
+    #[test]
+    fn test_mask_quality_validation() {
+        let manager = ConfigManager::new().unwrap();
+        let mut config = ConfigManager::default_config();
+        assert_eq!(config.render.mask_quality, "Balanced");
+
+        config.render.mask_quality = "Adaptive".to_string();
+        manager.validate_and_fix_config(&mut config);
+        assert_eq!(config.render.mask_quality, "Adaptive");
+
+        config.render.mask_quality = "Ultra".to_string();
+        manager.validate_and_fix_config(&mut config);
+        assert_eq!(config.render.mask_quality, "Balanced");
+    }
+
+    #[test]
+    fn test_mask_cache_budget_validation() {
+        let manager = ConfigManager::new().unwrap();
+        let mut config = ConfigManager::default_config();
+        assert_eq!(config.render.mask_cache_budget_mb, 32);
+
+        config.render.mask_cache_budget_mb = 0;
+        manager.validate_and_fix_config(&mut config);
+        assert_eq!(config.render.mask_cache_budget_mb, 0);
+
+        config.render.mask_cache_budget_mb = 100_000;
+        manager.validate_and_fix_config(&mut config);
+        assert_eq!(config.render.mask_cache_budget_mb, 1024);
+    }
+
+    #[test]
+    fn test_config_without_render_section() {
+        // 旧版本配置文件没有 [render] 段，应使用默认渲染配置
+        let old_toml = r#"
+version = "1.0"
+
+[window]
+position_x = 100.0
+position_y = 100.0
+width = 400
+height = 400
+rotation = 0.0
+shape = "Circle"
+
+[camera]
+device_index = 0
+"#;
+        let config: AppConfig = toml::from_str(old_toml).unwrap();
+        assert_eq!(config.render, RenderConfig::default());
+    }
+
+    #[test]
+    fn test_color_config_validation() {
+        let manager = ConfigManager::new().unwrap();
+        let mut config = ConfigManager::default_config();
+        assert_eq!(config.render.color, ColorConfig::default());
+
+        config.render.color.preset = "Sepia".to_string();
+        config.render.color.saturation = 1.5;
+        manager.validate_and_fix_config(&mut config);
+        assert_eq!(config.render.color.preset, "Sepia");
+        assert_eq!(config.render.color.saturation, 1.5);
+
+        config.render.color.preset = "Vintage".to_string();
+        config.render.color.brightness = 3.0;
+        config.render.color.gamma = 0.0;
+        config.render.color.hue_shift = f32::NAN;
+        config.render.color.temperature = -2.0;
+        manager.validate_and_fix_config(&mut config);
+        assert_eq!(config.render.color.preset, "None");
+        assert_eq!(config.render.color.brightness, 1.0);
+        assert_eq!(config.render.color.gamma, 0.1);
+        assert_eq!(config.render.color.hue_shift, 0.0);
+        assert_eq!(config.render.color.temperature, -1.0);
+    }
+
+    #[test]
+    fn test_config_without_color_section() {
+        // 旧版本 [render] 段没有颜色配置，部分字段缺失时其余使用默认值
+        let old_toml = r#"
+version = "1.0"
+
+[window]
+position_x = 100.0
+position_y = 100.0
+width = 400
+height = 400
+rotation = 0.0
+shape = "Circle"
+
+[camera]
+device_index = 0
+
+[render]
+mask_quality = "High"
+mask_cache_budget_mb = 32
+"#;
+        let config: AppConfig = toml::from_str(old_toml).unwrap();
+        assert_eq!(config.render.color, ColorConfig::default());
+
+        let partial = old_toml.to_string() + "\n[render.color]\npreset = \"Grayscale\"\ncontrast = 1.2\n";
+        let config: AppConfig = toml::from_str(&partial).unwrap();
+        assert_eq!(config.render.color.preset, "Grayscale");
+        assert_eq!(config.render.color.contrast, 1.2);
+        assert_eq!(config.render.color.saturation, 1.0);
+        assert!(config.render.color.lut_path.is_empty());
+    }
+
+    #[test]
+    fn test_chroma_key_config_validation() {
+        let manager = ConfigManager::new().unwrap();
+        let mut config = ConfigManager::default_config();
+        assert_eq!(config.render.chroma_key, ChromaKeyConfig::default());
+        assert!(!config.render.chroma_key.enabled);
+
+        config.render.chroma_key.similarity = 1.5;
+        config.render.chroma_key.smoothness = f32::NAN;
+        config.render.chroma_key.spill = -0.2;
+        manager.validate_and_fix_config(&mut config);
+        assert_eq!(config.render.chroma_key.similarity, 1.0);
+        assert_eq!(config.render.chroma_key.smoothness, 0.08);
+        assert_eq!(config.render.chroma_key.spill, 0.0);
+    }
+
+    #[test]
+    fn test_config_without_chroma_key_section() {
+        // 旧版本配置文件没有 [render.chroma_key] 段，应使用默认抠像配置
+        let old_toml = r#"
+version = "1.0"
+
+[window]
+position_x = 100.0
+position_y = 100.0
+width = 400
+height = 400
+rotation = 0.0
+shape = "Circle"
+
+[camera]
+device_index = 0
+
+[render]
+mask_quality = "Balanced"
+"#;
+        let config: AppConfig = toml::from_str(old_toml).unwrap();
+        assert_eq!(config.render.chroma_key, ChromaKeyConfig::default());
+
+        let saved = toml::to_string(&config).unwrap();
+        assert!(saved.contains("[render.chroma_key]"));
+        let reloaded: AppConfig = toml::from_str(&saved).unwrap();
+        assert_eq!(reloaded, config);
+    }
+
+    #[test]
+    fn test_denoise_config_validation() {
+        let manager = ConfigManager::new().unwrap();
+        let mut config = ConfigManager::default_config();
+        assert_eq!(config.render.denoise, DenoiseConfig::default());
+
+        config.render.denoise.smoothing_strength = 2.0;
+        config.render.denoise.temporal_strength = f32::INFINITY;
+        config.render.denoise.motion_threshold = 0.0;
+        manager.validate_and_fix_config(&mut config);
+        assert_eq!(config.render.denoise.smoothing_strength, 1.0);
+        assert_eq!(config.render.denoise.temporal_strength, 0.5);
+        assert_eq!(config.render.denoise.motion_threshold, 0.001);
+    }
+
+    #[test]
+    fn test_config_without_denoise_section() {
+        // 旧版本配置文件没有 [render.denoise] 段，降噪默认关闭
+        let old_toml = r#"
+version = "1.0"
+
+[window]
+position_x = 100.0
+position_y = 100.0
+width = 400
+height = 400
+rotation = 0.0
+shape = "Circle"
+
+[camera]
+device_index = 0
+
+[render]
+mask_quality = "Balanced"
+
+[render.denoise]
+temporal_enabled = true
+"#;
+        let config: AppConfig = toml::from_str(old_toml).unwrap();
+        assert!(!config.render.denoise.smoothing_enabled);
+        assert!(config.render.denoise.temporal_enabled);
+        assert_eq!(config.render.denoise.temporal_strength, 0.5);
+    }
+
+    #[test]
+    fn test_auto_correct_config_validation() {
+        let manager = ConfigManager::new().unwrap();
+        let mut config = ConfigManager::default_config();
+        assert_eq!(config.render.auto_correct, AutoCorrectConfig::default());
+
+        config.render.auto_correct.smoothing = 1.5;
+        manager.validate_and_fix_config(&mut config);
+        assert_eq!(config.render.auto_correct.smoothing, 0.99);
+
+        config.render.auto_correct.smoothing = f32::NAN;
+        manager.validate_and_fix_config(&mut config);
+        assert_eq!(config.render.auto_correct.smoothing, 0.9);
+    }
+
+    #[test]
+    fn test_config_without_auto_correct_section() {
+        // 旧版本配置文件没有 [render.auto_correct] 段，自动校正默认关闭
+        let old_toml = r#"
+version = "1.0"
+
+[window]
+position_x = 100.0
+position_y = 100.0
+width = 400
+height = 400
+rotation = 0.0
+shape = "Circle"
+
+[camera]
+device_index = 0
+
+[render]
+mask_quality = "Balanced"
+
+[render.auto_correct]
+low_light_boost = true
+"#;
+        let config: AppConfig = toml::from_str(old_toml).unwrap();
+        assert!(!config.render.auto_correct.white_balance);
+        assert!(!config.render.auto_correct.auto_levels);
+        assert!(config.render.auto_correct.low_light_boost);
+        assert_eq!(config.render.auto_correct.smoothing, 0.9);
+    }
+
+    #[test]
+    fn test_text_overlay_config_validation() {
+        let manager = ConfigManager::new().unwrap();
+        let mut config = ConfigManager::default_config();
+        assert_eq!(config.render.text_overlay, TextOverlayConfig::default());
+        assert!(config.render.text_overlay.items.is_empty());
+
+        config.render.text_overlay.items.push(TextItemConfig {
+            source: "Weather".to_string(),
+            anchor: "Left".to_string(),
+            font_size: 500.0,
+            margin: f32::NAN,
+            ..Default::default()
+        });
+        config.render.text_overlay.items.push(TextItemConfig {
+            source: "Clock".to_string(),
+            content: "%H:%M".to_string(),
+            anchor: "Arc".to_string(),
+            ..Default::default()
+        });
+        manager.validate_and_fix_config(&mut config);
+
+        let fixed = &config.render.text_overlay.items[0];
+        assert_eq!(fixed.source, "Static");
+        assert_eq!(fixed.anchor, "Bottom");
+        assert_eq!(fixed.font_size, 200.0);
+        assert_eq!(fixed.margin, 0.1);
+        assert_eq!(config.render.text_overlay.items[1].source, "Clock");
+        assert_eq!(config.render.text_overlay.items[1].anchor, "Arc");
+    }
+
+    #[test]
+    fn test_text_overlay_config_from_toml() {
+        // 旧版本配置文件没有 [render.text_overlay] 段
+        let old_toml = r#"
+version = "1.0"
+
+[window]
+position_x = 100.0
+position_y = 100.0
+width = 400
+height = 400
+rotation = 0.0
+shape = "Circle"
+
+[camera]
+device_index = 0
+
+[render]
+mask_quality = "Balanced"
+"#;
+        let config: AppConfig = toml::from_str(old_toml).unwrap();
+        assert_eq!(config.render.text_overlay, TextOverlayConfig::default());
+
+        let with_items = format!("{}{}", old_toml, r#"
+[render.text_overlay]
+font_path = "C:/Fonts/custom.ttf"
+
+[[render.text_overlay.items]]
+content = "张三 — 工程负责人"
+
+[[render.text_overlay.items]]
+source = "Clock"
+content = "%H:%M"
+anchor = "Top"
+background = false
+"#);
+        let config: AppConfig = toml::from_str(&with_items).unwrap();
+        let overlay = &config.render.text_overlay;
+        assert!(overlay.enabled);
+        assert_eq!(overlay.font_path, "C:/Fonts/custom.ttf");
+        assert_eq!(overlay.items.len(), 2);
+        assert_eq!(overlay.items[0].content, "张三 — 工程负责人");
+        assert_eq!(overlay.items[0].source, "Static");
+        assert_eq!(overlay.items[0].font_size, 20.0);
+        assert_eq!(overlay.items[1].anchor, "Top");
+        assert!(!overlay.items[1].background);
+
+        // 序列化后再解析保持一致
+        let serialized = toml::to_string(&config).unwrap();
+        let reparsed: AppConfig = toml::from_str(&serialized).unwrap();
+        assert_eq!(reparsed.render.text_overlay, config.render.text_overlay);
+    }
+
+    #[test]
+    fn test_sticker_config_validation() {
+        let manager = ConfigManager::new().unwrap();
+        let mut config = ConfigManager::default_config();
+        assert_eq!(config.render.stickers, StickerConfig::default());
+
+        config.render.stickers.items.push(StickerItemConfig {
+            path: "cat.gif".to_string(),
+            position: [3.0, f32::NAN],
+            scale: 0.0,
+            rotation: 720.0,
+            layer: "Middle".to_string(),
+            frame_delay_ms: 60000,
+        });
+        config.render.stickers.items.push(StickerItemConfig {
+            path: "hat.png".to_string(),
+            position: [0.0, -0.9],
+            layer: "Below".to_string(),
+            ..Default::default()
+        });
+        manager.validate_and_fix_config(&mut config);
+
+        let fixed = &config.render.stickers.items[0];
+        assert_eq!(fixed.position, [2.0, 0.0]);
+        assert_eq!(fixed.scale, 0.01);
+        assert_eq!(fixed.rotation, 360.0);
+        assert_eq!(fixed.layer, "Above");
+        assert_eq!(fixed.frame_delay_ms, 10000);
+        assert_eq!(config.render.stickers.items[1].position, [0.0, -0.9]);
+        assert_eq!(config.render.stickers.items[1].layer, "Below");
+    }
+
+    #[test]
+    fn test_sticker_config_from_toml() {
+        // 旧版本配置文件没有 [render.stickers] 段
+        let old_toml = r#"
+version = "1.0"
+
+[window]
+position_x = 100.0
+position_y = 100.0
+width = 400
+height = 400
+rotation = 0.0
+shape = "Circle"
+
+[camera]
+device_index = 0
+
+[render]
+mask_quality = "Balanced"
+"#;
+        let config: AppConfig = toml::from_str(old_toml).unwrap();
+        assert_eq!(config.render.stickers, StickerConfig::default());
+
+        let with_items = format!("{}{}", old_toml, r#"
+[render.stickers]
+enabled = false
+
+[[render.stickers.items]]
+path = "C:/Stickers/party.gif"
+position = [0.6, -0.6]
+rotation = 15.0
+frame_delay_ms = 80
+"#);
+        let config: AppConfig = toml::from_str(&with_items).unwrap();
+        let stickers = &config.render.stickers;
+        assert!(!stickers.enabled);
+        assert_eq!(stickers.items.len(), 1);
+        assert_eq!(stickers.items[0].path, "C:/Stickers/party.gif");
+        assert_eq!(stickers.items[0].position, [0.6, -0.6]);
+        assert_eq!(stickers.items[0].scale, 0.3);
+        assert_eq!(stickers.items[0].layer, "Above");
+        assert_eq!(stickers.items[0].frame_delay_ms, 80);
+
+        // 序列化后再解析保持一致
+        let serialized = toml::to_string(&config).unwrap();
+        let reparsed: AppConfig = toml::from_str(&serialized).unwrap();
+        assert_eq!(reparsed.render.stickers, config.render.stickers);
+    }
+
+    #[test]
+    fn test_annotation_config() {
+        let manager = ConfigManager::new().unwrap();
+        let mut config = ConfigManager::default_config();
+        assert_eq!(config.render.annotation, AnnotationConfig::default());
+
+        config.render.annotation.width = 1.0;
+        config.render.annotation.fade_seconds = f32::INFINITY;
+        manager.validate_and_fix_config(&mut config);
+        assert_eq!(config.render.annotation.width, 0.1);
+        assert_eq!(config.render.annotation.fade_seconds, 5.0);
+
+        // 旧版本配置文件没有 [render.annotation] 段
+        let old_toml = r#"
+version = "1.0"
+
+[window]
+position_x = 100.0
+position_y = 100.0
+width = 400
+height = 400
+rotation = 0.0
+shape = "Circle"
+
+[camera]
+device_index = 0
+
+[render]
+mask_quality = "Balanced"
+"#;
+        let config: AppConfig = toml::from_str(old_toml).unwrap();
+        assert_eq!(config.render.annotation, AnnotationConfig::default());
+
+        let with_annotation = format!("{}{}", old_toml, r#"
+[render.annotation]
+color = [0, 122, 255, 255]
+auto_fade = true
+"#);
+        let config: AppConfig = toml::from_str(&with_annotation).unwrap();
+        assert_eq!(config.render.annotation.color, [0, 122, 255, 255]);
+        assert!(config.render.annotation.auto_fade);
+        assert_eq!(config.render.annotation.fade_seconds, 5.0);
+    }
+
+    #[test]
+    fn test_effects_config() {
+        let manager = ConfigManager::new().unwrap();
+        let mut config = ConfigManager::default_config();
+        assert_eq!(config.render.effects, EffectsConfig::default());
+
+        config.render.effects.passes = vec![
+            EffectPassConfig {
+                name: "vignette".to_string(),
+                params: BTreeMap::from([("strength".to_string(), f32::NAN), ("radius".to_string(), 0.5)]),
+                ..Default::default()
+            },
+            EffectPassConfig::default(),
+            EffectPassConfig {
+                name: "../outside".to_string(),
+                ..Default::default()
+            },
+        ];
+        manager.validate_and_fix_config(&mut config);
+        assert_eq!(config.render.effects.passes.len(), 1);
+        assert_eq!(config.render.effects.passes[0].params, BTreeMap::from([("radius".to_string(), 0.5)]));
+
+        // 旧版本配置文件没有 [render.effects] 段
+        let old_toml = r#"
+version = "1.0"
+
+[window]
+position_x = 100.0
+position_y = 100.0
+width = 400
+height = 400
+rotation = 0.0
+shape = "Circle"
+
+[camera]
+device_index = 0
+
+[render]
+mask_quality = "Balanced"
+"#;
+        let config: AppConfig = toml::from_str(old_toml).unwrap();
+        assert_eq!(config.render.effects, EffectsConfig::default());
+
+        let with_effects = format!("{}{}", old_toml, r#"
+[[render.effects.passes]]
+name = "vignette"
+params = { strength = 0.8 }
+
+[[render.effects.passes]]
+name = "grain"
+enabled = false
+"#);
+        let config: AppConfig = toml::from_str(&with_effects).unwrap();
+        assert!(config.render.effects.enabled);
+        assert_eq!(config.render.effects.passes.len(), 2);
+        assert_eq!(config.render.effects.passes[0].params["strength"], 0.8);
+        assert!(config.render.effects.passes[0].enabled);
+        assert!(!config.render.effects.passes[1].enabled);
+
+        // 序列化后再解析保持一致
+        let serialized = toml::to_string(&config).unwrap();
+        let reparsed: AppConfig = toml::from_str(&serialized).unwrap();
+        assert_eq!(reparsed.render.effects, config.render.effects);
+    }
+
+    #[test]
+    fn test_gpu_config() {
+        let manager = ConfigManager::new().unwrap();
+        let mut config = ConfigManager::default_config();
+        assert_eq!(config.render.gpu, GpuConfig::default());
+
+        config.render.gpu.power_preference = "Fastest".to_string();
+        config.render.gpu.backend = "vulkan".to_string();
+        config.render.gpu.present_mode = "Mailbox".to_string();
+        config.render.gpu.force_fallback_adapter = true;
+        manager.validate_and_fix_config(&mut config);
+        assert_eq!(config.render.gpu.power_preference, "LowPower");
+        assert_eq!(config.render.gpu.backend, "Auto");
+        assert_eq!(config.render.gpu.present_mode, "Mailbox");
+        assert!(config.render.gpu.force_fallback_adapter);
+
+        // 旧版本配置文件没有 [render.gpu] 段
+        let old_toml = r#"
+version = "1.0"
+
+[window]
+position_x = 100.0
+position_y = 100.0
+width = 400
+height = 400
+rotation = 0.0
+shape = "Circle"
+
+[camera]
+device_index = 0
+
+[render]
+mask_quality = "Balanced"
+"#;
+        let config: AppConfig = toml::from_str(old_toml).unwrap();
+        assert_eq!(config.render.gpu, GpuConfig::default());
+
+        let with_gpu = format!("{}{}", old_toml, r#"
+[render.gpu]
+backend = "Gl"
+present_mode = "Immediate"
+"#);
+        let config: AppConfig = toml::from_str(&with_gpu).unwrap();
+        assert_eq!(config.render.gpu.backend, "Gl");
+        assert_eq!(config.render.gpu.present_mode, "Immediate");
+        assert_eq!(config.render.gpu.power_preference, "LowPower");
+        assert!(!config.render.gpu.force_fallback_adapter);
+    }
+
+    #[test]
+    fn test_snapshot_config() {
+        let manager = ConfigManager::new().unwrap();
+        let mut config = ConfigManager::default_config();
+        assert_eq!(config.render.snapshot, SnapshotConfig::default());
+
+        config.render.snapshot.countdown_seconds = 60;
+        manager.validate_and_fix_config(&mut config);
+        assert_eq!(config.render.snapshot.countdown_seconds, 10);
+
+        // 旧版本配置文件没有 [render.snapshot] 段
+        let old_toml = r#"
+version = "1.0"
+
+[window]
+position_x = 100.0
+position_y = 100.0
+width = 400
+height = 400
+rotation = 0.0
+shape = "Circle"
+
+[camera]
+device_index = 0
+
+[render]
+mask_quality = "Balanced"
+"#;
+        let config: AppConfig = toml::from_str(old_toml).unwrap();
+        assert_eq!(config.render.snapshot, SnapshotConfig::default());
+
+        let with_snapshot = format!("{}{}", old_toml, r#"
+[render.snapshot]
+directory = "/tmp/mira"
+countdown_seconds = 3
+"#);
+        let config: AppConfig = toml::from_str(&with_snapshot).unwrap();
+        assert_eq!(config.render.snapshot.directory, "/tmp/mira");
+        assert_eq!(config.render.snapshot.countdown_seconds, 3);
+        assert!(config.render.snapshot.flash);
+        assert!(!config.render.snapshot.raw_frame);
+    }
+
+    #[test]
+    fn test_recording_config() {
+        let manager = ConfigManager::new().unwrap();
+        let mut config = ConfigManager::default_config();
+        assert_eq!(config.render.recording, RecordingConfig::default());
+
+        config.render.recording.format = "WebP".to_string();
+        config.render.recording.fps = 0;
+        config.render.recording.max_seconds = 3600;
+        config.render.recording.max_file_mb = 0;
+        manager.validate_and_fix_config(&mut config);
+        assert_eq!(config.render.recording.format, "Apng");
+        assert_eq!(config.render.recording.fps, 1);
+        assert_eq!(config.render.recording.max_seconds, 600);
+        assert_eq!(config.render.recording.max_file_mb, 1);
+
+        // 旧版本配置文件没有 [render.recording] 段
+        let old_toml = r#"
+version = "1.0"
+
+[window]
+position_x = 100.0
+position_y = 100.0
+width = 400
+height = 400
+rotation = 0.0
+shape = "Circle"
+
+[camera]
+device_index = 0
+
+[render]
+mask_quality = "Balanced"
+"#;
+        let config: AppConfig = toml::from_str(old_toml).unwrap();
+        assert_eq!(config.render.recording, RecordingConfig::default());
+
+        let with_recording = format!("{}{}", old_toml, r#"
+[render.recording]
+format = "Gif"
+max_seconds = 10
+"#);
+        let config: AppConfig = toml::from_str(&with_recording).unwrap();
+        assert_eq!(config.render.recording.format, "Gif");
+        assert_eq!(config.render.recording.max_seconds, 10);
+        assert_eq!(config.render.recording.fps, 15);
+    }
+
+    #[test]
+    fn test_frame_output_config() {
+        let manager = ConfigManager::new().unwrap();
+        let mut config = ConfigManager::default_config();
+        assert_eq!(config.render.frame_output, FrameOutputConfig::default());
+        assert!(!config.render.frame_output.enabled);
+
+        config.render.frame_output.format = "H264".to_string();
+        config.render.frame_output.target = " ".to_string();
+        config.render.frame_output.width = 8000;
+        config.render.frame_output.fps = 0;
+        manager.validate_and_fix_config(&mut config);
+        assert_eq!(config.render.frame_output.format, "Y4m");
+        assert_eq!(config.render.frame_output.target, "-");
+        assert_eq!(config.render.frame_output.width, 4096);
+        assert_eq!(config.render.frame_output.height, 0);
+        assert_eq!(config.render.frame_output.fps, 1);
+
+        // 旧版本配置文件没有 [render.frame_output] 段
+        let old_toml = r#"
+version = "1.0"
+
+[window]
+position_x = 100.0
+position_y = 100.0
+width = 400
+height = 400
+rotation = 0.0
+shape = "Circle"
+
+[camera]
+device_index = 0
+
+[render]
+mask_quality = "Balanced"
+"#;
+        let config: AppConfig = toml::from_str(old_toml).unwrap();
+        assert_eq!(config.render.frame_output, FrameOutputConfig::default());
+
+        let with_output = format!("{}{}", old_toml, r#"
+[render.frame_output]
+enabled = true
+format = "RawRgba"
+target = "/tmp/mira.fifo"
+"#);
+        let config: AppConfig = toml::from_str(&with_output).unwrap();
+        assert!(config.render.frame_output.enabled);
+        assert_eq!(config.render.frame_output.format, "RawRgba");
+        assert_eq!(config.render.frame_output.target, "/tmp/mira.fifo");
+        assert_eq!(config.render.frame_output.fps, 30);
+    }
+
+    #[test]
+    fn test_preview_server_config() {
+        let manager = ConfigManager::new().unwrap();
+        let mut config = ConfigManager::default_config();
+        assert_eq!(config.render.preview_server, PreviewServerConfig::default());
+        assert!(!config.render.preview_server.enabled);
+
+        config.render.preview_server.port = 0;
+        config.render.preview_server.fps = 120;
+        config.render.preview_server.max_clients = 0;
+        config.render.preview_server.jpeg_quality = 500;
+        manager.validate_and_fix_config(&mut config);
+        assert_eq!(config.render.preview_server.port, 8765);
+        assert_eq!(config.render.preview_server.fps, 60);
+        assert_eq!(config.render.preview_server.max_clients, 1);
+        assert_eq!(config.render.preview_server.jpeg_quality, 100);
+
+        // 旧版本配置文件没有 [render.preview_server] 段
+        let old_toml = r#"
+version = "1.0"
+
+[window]
+position_x = 100.0
+position_y = 100.0
+width = 400
+height = 400
+rotation = 0.0
+shape = "Circle"
+
+[camera]
+device_index = 0
+
+[render]
+mask_quality = "Balanced"
+"#;
+        let config: AppConfig = toml::from_str(old_toml).unwrap();
+        assert_eq!(config.render.preview_server, PreviewServerConfig::default());
+
+        let with_server = format!("{}{}", old_toml, r#"
+[render.preview_server]
+enabled = true
+port = 9000
+"#);
+        let config: AppConfig = toml::from_str(&with_server).unwrap();
+        assert!(config.render.preview_server.enabled);
+        assert_eq!(config.render.preview_server.port, 9000);
+        assert_eq!(config.render.preview_server.max_clients, 4);
+    }
+
+    #[test]
+    fn test_target_fps_validation() {
+        let manager = ConfigManager::new().unwrap();
+        let mut config = ConfigManager::default_config();
+        assert_eq!(config.render.target_fps, 30);
+
+        config.render.target_fps = 60;
+        manager.validate_and_fix_config(&mut config);
+        assert_eq!(config.render.target_fps, 60);
+
+        config.render.target_fps = 0;
+        manager.validate_and_fix_config(&mut config);
+        assert_eq!(config.render.target_fps, 30);
+
+        config.render.target_fps = 1000;
+        manager.validate_and_fix_config(&mut config);
+        assert_eq!(config.render.target_fps, 240);
+
+        // 旧版本配置文件没有 target_fps
+        let old_toml = r#"
+version = "1.0"
+
+[window]
+position_x = 100.0
+position_y = 100.0
+width = 400
+height = 400
+rotation = 0.0
+shape = "Circle"
+
+[camera]
+device_index = 0
+
+[render]
+mask_quality = "Balanced"
+"#;
+        let config: AppConfig = toml::from_str(old_toml).unwrap();
+        assert_eq!(config.render.target_fps, 30);
+    }
 }
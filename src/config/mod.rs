@@ -2,4 +2,4 @@
 
 pub mod manager;
 
-pub use manager::{AppConfig, CameraConfig, ConfigManager, WindowConfig};
+pub use manager::{AnnotationConfig, AppConfig, AutoCorrectConfig, CameraConfig, ChromaKeyConfig, ColorConfig, ConfigManager, DenoiseConfig, EffectPassConfig, EffectsConfig, FrameOutputConfig, GpuConfig, PreviewServerConfig, RecordingConfig, RenderConfig, SnapshotConfig, StickerConfig, StickerItemConfig, TextItemConfig, TextOverlayConfig, WindowConfig};
@@ -50,6 +50,14 @@ pub enum RenderError {
     RenderFailed(String),
     /// UI 渲染失败
     UIRenderFailed(String),
+    /// LUT 文件加载或解析失败
+    LutLoadFailed(String),
+    /// 字体文件加载或解析失败
+    FontLoadFailed(String),
+    /// 贴纸图片加载或解码失败
+    StickerLoadFailed(String),
+    /// 特效清单或着色器加载失败
+    EffectLoadFailed(String),
 }
 
 /// 配置错误类型
@@ -112,6 +120,10 @@ impl fmt::Display for RenderError {
             RenderError::TextureUploadFailed => write!(f, "纹理上传失败"),
             RenderError::RenderFailed(msg) => write!(f, "渲染失败: {}", msg),
             RenderError::UIRenderFailed(msg) => write!(f, "UI 渲染失败: {}", msg),
+            RenderError::LutLoadFailed(msg) => write!(f, "LUT 加载失败: {}", msg),
+            RenderError::FontLoadFailed(msg) => write!(f, "字体加载失败: {}", msg),
+            RenderError::StickerLoadFailed(msg) => write!(f, "贴纸加载失败: {}", msg),
+            RenderError::EffectLoadFailed(msg) => write!(f, "特效加载失败: {}", msg),
         }
     }
 }
@@ -205,6 +217,18 @@ mod tests {
 
         let err = RenderError::UIRenderFailed("test".to_string());
         assert!(err.to_string().contains("UI 渲染失败"));
+
+        let err = RenderError::LutLoadFailed("test".to_string());
+        assert!(err.to_string().contains("LUT 加载失败"));
+
+        let err = RenderError::FontLoadFailed("test".to_string());
+        assert!(err.to_string().contains("字体加载失败"));
+
+        let err = RenderError::StickerLoadFailed("test".to_string());
+        assert!(err.to_string().contains("贴纸加载失败"));
+
+        let err = RenderError::EffectLoadFailed("test".to_string());
+        assert!(err.to_string().contains("特效加载失败"));
     }
 
     #[test]
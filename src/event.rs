@@ -1,10 +1,22 @@
 // 事件处理器实现
 
 use crate::camera::CameraManager;
-use crate::config::ConfigManager;
+use crate::config::{ConfigManager, FrameOutputConfig, PreviewServerConfig};
+use crate::frame_output::{FrameOutput, FrameOutputSettings, OutputFormat, OutputTarget};
+use crate::overlay::text::GlyphRasterizer;
+use crate::overlay::{ANNOTATION_COLORS, ANNOTATION_WIDTHS};
+use crate::preview_server::{PreviewServer, PreviewServerSettings};
+use crate::render::adapter::describe_adapter;
+use crate::render::color::ColorPreset;
+use crate::render::auto_correct::AutoCorrectSettings;
+use crate::render::capture::CaptureSource;
+use crate::render::denoise::DenoiseSettings;
+use crate::render::engine::{window_to_mask_coords, window_to_video_coords};
 use crate::render::RenderEngine;
-use crate::shape::{ShapeMask, ShapeType};
-use crate::ui::{ContextMenu, MenuRenderer};
+use crate::shape::{MaskQuality, ShapeMask, ShapeType};
+use crate::recording::{Recorder, RecordingFormat, RecordingSettings};
+use crate::snapshot::{self, SnapshotTimer};
+use crate::ui::{ContextMenu, MenuFont, MenuRenderer, SharedMenuFont};
 use crate::ui::context_menu::MenuState;
 use crate::window::WindowManager;
 use log::{debug, error, info, warn};
@@ -14,6 +26,18 @@ use winit::{
     keyboard::{Key, NamedKey, ModifiersState},
 };
 
+/// 指针移动等事件可能改变的界面状态，变化时才需要重绘
+#[derive(Debug, Clone, PartialEq)]
+pub struct PointerUiState {
+    show_controls: bool,
+    close_button_hovered: bool,
+    minimize_button_hovered: bool,
+    menu_state: MenuState,
+    hovered_menu_item: Option<String>,
+    dragging: bool,
+    annotation_revision: u64,
+}
+
 /// 事件处理器
 pub struct EventHandler {
     window_manager: WindowManager,
@@ -38,6 +62,27 @@ pub struct EventHandler {
     // 上下文菜单
     context_menu: ContextMenu,
     menu_renderer: Option<MenuRenderer>,
+    // 创建菜单渲染器时渲染引擎的设备代数，设备重建后需要重新创建
+    menu_renderer_generation: u64,
+    
+    // 最近一次拖拽/缩放的时间（用于自适应遮罩质量）
+    last_interaction_time: std::time::Instant,
+    
+    // 标注模式：左键拖动绘制笔画而不是移动窗口
+    annotation_mode: bool,
+    
+    // 快照倒计时与闪光、是否保存原始画面
+    snapshot_timer: SnapshotTimer,
+    snapshot_raw_frame: bool,
+    // 已请求、尚未回读完成的快照
+    snapshot_requests: Vec<CaptureSource>,
+    
+    // 录制
+    recorder: Recorder,
+    // 帧输出流，读取端关闭或写入失败后移除
+    frame_output: Option<FrameOutput>,
+    // 本地预览服务，随事件处理器一起停止
+    preview_server: Option<PreviewServer>,
     
     // 应用状态
     should_close: bool,
@@ -54,6 +99,9 @@ impl EventHandler {
     ) -> Self {
         info!("创建事件处理器");
         
+        let snapshot_raw_frame = config_manager.get_config().render.snapshot.raw_frame;
+        let frame_output = Self::start_frame_output(&config_manager.get_config().render.frame_output);
+        let preview_server = Self::start_preview_server(&config_manager.get_config().render.preview_server);
         let mut handler = Self {
             window_manager,
             camera_manager,
@@ -75,6 +123,18 @@ impl EventHandler {
             // 上下文菜单初始化
             context_menu: ContextMenu::new(PhysicalSize::new(1920, 1080)), // 默认屏幕尺寸，会在运行时更新
             menu_renderer: None, // 延迟初始化
+            menu_renderer_generation: 0,
+            
+            last_interaction_time: std::time::Instant::now(),
+            annotation_mode: false,
+            
+            snapshot_timer: SnapshotTimer::new(),
+            snapshot_raw_frame,
+            snapshot_requests: Vec::new(),
+            
+            recorder: Recorder::new(),
+            frame_output,
+            preview_server,
             
             // 应用状态初始化
             should_close: false,
@@ -83,6 +143,42 @@ impl EventHandler {
         // 设置菜单回调函数
         handler.setup_menu_callbacks();
         
+        // 同步遮罩质量菜单的选中状态
+        let quality_item = quality_menu_id(handler.shape_mask.quality());
+        handler.context_menu.select_radio_item(&quality_item);
+        
+        // 同步视频保持正向复选项
+        let keep_upright = handler.window_manager.keep_video_upright();
+        handler.context_menu.set_item_checked("keep_video_upright", keep_upright);
+        
+        // 同步颜色滤镜菜单的选中状态
+        let filter_item = filter_menu_id(handler.render_engine.color_preset());
+        handler.context_menu.select_radio_item(&filter_item);
+        let lut_enabled = handler.render_engine.lut_enabled();
+        handler.context_menu.set_item_checked("filter_lut", lut_enabled);
+        let chroma_key_enabled = handler.render_engine.chroma_key().enabled;
+        handler.context_menu.set_item_checked("chroma_key", chroma_key_enabled);
+        let effects_enabled = handler.render_engine.has_effects() && handler.render_engine.effects_enabled();
+        handler.context_menu.set_item_checked("effects", effects_enabled);
+        
+        // 同步降噪复选项
+        handler.sync_denoise_menu();
+        
+        // 同步自动校正复选项
+        handler.sync_auto_correct_menu();
+        
+        // 同步叠加层复选项
+        let text_overlay_visible = handler.render_engine.has_text_overlays() && handler.render_engine.overlay_visible();
+        handler.context_menu.set_item_checked("text_overlay", text_overlay_visible);
+        let stickers_visible = handler.render_engine.has_stickers() && handler.render_engine.stickers_visible();
+        handler.context_menu.set_item_checked("stickers", stickers_visible);
+        
+        // 同步标注画笔
+        handler.sync_annotation_menu();
+        
+        // 同步快照选项
+        handler.context_menu.set_item_checked("snapshot_raw", snapshot_raw_frame);
+        
         handler
     }
     
@@ -95,13 +191,25 @@ impl EventHandler {
         // 这是一个简化的实现，实际应用中可能需要更复杂的回调系统
     }
     
-    /// 初始化菜单渲染器
-    pub fn init_menu_renderer(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, surface_format: wgpu::TextureFormat) -> Result<(), String> {
+    /// 初始化菜单渲染器（使用渲染引擎的设备和表面格式）
+    pub fn init_menu_renderer(&mut self, rasterizer: Box<dyn GlyphRasterizer + Send>) -> Result<(), String> {
         debug!("初始化菜单渲染器");
         
-        match MenuRenderer::new(device, queue, surface_format) {
+        // 菜单尺寸和字号跟随显示器缩放系数
+        self.context_menu.set_scale_factor(self.window_manager.window().scale_factor());
+        
+        // 菜单布局与渲染共用同一份字体，测量的文字宽度与绘制结果一致
+        let font = MenuFont::shared(rasterizer);
+        self.context_menu.set_font(font.clone());
+        self.create_menu_renderer(font)
+    }
+    
+    /// 用菜单字体创建菜单渲染器
+    fn create_menu_renderer(&mut self, font: SharedMenuFont) -> Result<(), String> {
+        match MenuRenderer::new(self.render_engine.device(), self.render_engine.surface_format(), font) {
             Ok(renderer) => {
                 self.menu_renderer = Some(renderer);
+                self.menu_renderer_generation = self.render_engine.device_generation();
                 info!("菜单渲染器初始化成功");
                 Ok(())
             }
@@ -151,6 +259,11 @@ impl EventHandler {
                 self.handle_resize(*size);
                 false
             }
+            WindowEvent::ScaleFactorChanged { scale_factor, .. } => {
+                info!("显示器缩放系数变化: {:.2}", scale_factor);
+                self.context_menu.set_scale_factor(*scale_factor);
+                false
+            }
             WindowEvent::CloseRequested => {
                 self.handle_close_requested();
                 true // 返回 true 表示应该退出
@@ -216,6 +329,19 @@ impl EventHandler {
         &mut self.config_manager
     }
     
+    /// 当前的悬浮、拖拽、菜单和笔画状态
+    pub fn pointer_ui_state(&self) -> PointerUiState {
+        PointerUiState {
+            show_controls: self.show_controls,
+            close_button_hovered: self.close_button_hovered,
+            minimize_button_hovered: self.minimize_button_hovered,
+            menu_state: self.context_menu.state().clone(),
+            hovered_menu_item: self.context_menu.hovered_item().map(str::to_owned),
+            dragging: self.window_manager.is_dragging(),
+            annotation_revision: self.render_engine.annotations().revision(),
+        }
+    }
+    
     /// 检查应用是否应该关闭
     pub fn should_close(&self) -> bool {
         self.should_close
@@ -263,9 +389,8 @@ impl EventHandler {
         
         self.context_menu.update_status_info(window_size, window_position, rotation);
         
-        // 更新屏幕尺寸
-        // 注意：这里使用窗口尺寸作为近似，实际应用中应该获取真实的屏幕尺寸
-        self.context_menu.update_screen_size(PhysicalSize::new(1920, 1080));
+        // 菜单绘制在窗口内，以窗口尺寸作为边界
+        self.context_menu.update_screen_size(window_size);
     }
     
     /// 隐藏上下文菜单
@@ -303,6 +428,102 @@ impl EventHandler {
                 info!("切换到心形");
             }
             
+            // 遮罩质量切换
+            item_id if item_id.starts_with("quality_") => {
+                let quality = MaskQuality::ALL.iter()
+                    .copied()
+                    .find(|quality| quality_menu_id(*quality) == item_id)
+                    .ok_or_else(|| "无效的遮罩质量ID".to_string())?;
+                self.set_mask_quality(quality);
+            }
+            
+            // 颜色滤镜切换
+            "filter_lut" => {
+                self.toggle_lut();
+            }
+            "chroma_key" => {
+                self.toggle_chroma_key();
+            }
+            "effects" => {
+                self.toggle_effects();
+            }
+            
+            // 降噪开关
+            "denoise_smoothing" => {
+                self.update_denoise_settings(|settings| settings.smoothing_enabled = !settings.smoothing_enabled);
+            }
+            "denoise_temporal" => {
+                self.update_denoise_settings(|settings| settings.temporal_enabled = !settings.temporal_enabled);
+            }
+            "denoise_bypass" => {
+                self.toggle_denoise_bypass();
+            }
+            
+            // 自动校正开关
+            "auto_white_balance" => {
+                self.update_auto_correct_settings(|settings| settings.white_balance = !settings.white_balance);
+            }
+            "auto_levels" => {
+                self.update_auto_correct_settings(|settings| settings.auto_levels = !settings.auto_levels);
+            }
+            "low_light_boost" => {
+                self.update_auto_correct_settings(|settings| settings.low_light_boost = !settings.low_light_boost);
+            }
+            
+            // 叠加层
+            "text_overlay" => {
+                self.toggle_text_overlay();
+            }
+            "stickers" => {
+                self.toggle_stickers();
+            }
+            
+            // 手绘标注
+            "annotation_mode" => {
+                self.toggle_annotation_mode();
+            }
+            "annotation_fade" => {
+                self.toggle_annotation_fade();
+            }
+            "annotation_undo" => {
+                self.undo_annotation();
+            }
+            "annotation_clear" => {
+                self.clear_annotations();
+            }
+            
+            // 快照
+            "snapshot_take" => {
+                self.take_snapshot();
+            }
+            "snapshot_raw" => {
+                self.toggle_snapshot_raw_frame();
+            }
+            "recording_toggle" => {
+                self.toggle_recording();
+            }
+            item_id if item_id.starts_with("annotation_color_") => {
+                let color = item_id["annotation_color_".len()..].parse::<usize>().ok()
+                    .and_then(|index| ANNOTATION_COLORS.get(index))
+                    .ok_or_else(|| "无效的画笔颜色ID".to_string())?;
+                self.render_engine.annotations_mut().set_color(*color);
+                self.context_menu.select_radio_item(item_id);
+            }
+            item_id if item_id.starts_with("annotation_width_") => {
+                let width = item_id["annotation_width_".len()..].parse::<usize>().ok()
+                    .and_then(|index| ANNOTATION_WIDTHS.get(index))
+                    .ok_or_else(|| "无效的画笔粗细ID".to_string())?;
+                self.render_engine.annotations_mut().set_width(*width);
+                self.context_menu.select_radio_item(item_id);
+            }
+            item_id if item_id.starts_with("filter_") => {
+                let preset = ColorPreset::ALL.iter()
+                    .copied()
+                    .find(|preset| filter_menu_id(*preset) == item_id)
+                    .ok_or_else(|| "无效的颜色滤镜ID".to_string())?;
+                self.set_color_preset(preset);
+            }
+            
             // 摄像头设备切换
             item_id if item_id.starts_with("camera_") => {
                 if let Ok(device_index) = item_id.strip_prefix("camera_").unwrap().parse::<usize>() {
@@ -327,8 +548,12 @@ impl EventHandler {
             }
             "reset_rotation" => {
                 self.window_manager.set_rotation(0.0);
+                self.window_manager.set_video_rotation(0.0);
                 info!("重置窗口旋转");
             }
+            "keep_video_upright" => {
+                self.toggle_keep_video_upright();
+            }
             "reset_size" => {
                 self.window_manager.set_size(400, 400);
                 info!("重置窗口大小");
@@ -345,10 +570,44 @@ impl EventHandler {
                 
                 info!("=== 当前状态 ===");
                 info!("形状: {:?}", self.shape_mask.shape_type());
+                info!("遮罩质量: {}", self.shape_mask.quality().display_name());
+                info!("颜色滤镜: {}{}", self.render_engine.color_preset().display_name(),
+                      if self.render_engine.lut_enabled() { " + LUT" } else { "" });
+                info!("绿幕抠像: {}", if self.render_engine.chroma_key().enabled { "开启" } else { "关闭" });
+                let effect_names: Vec<&str> = self.render_engine.effect_passes()
+                    .filter(|pass| pass.enabled)
+                    .map(|pass| pass.id.as_str())
+                    .collect();
+                info!("特效链: [{}]{}", effect_names.join(" → "),
+                      if self.render_engine.effects_enabled() { "" } else { "（已关闭）" });
+                let denoise = self.render_engine.denoise_settings();
+                info!("降噪: 平滑={} 时域={} 旁路={} 等级={:?}",
+                      denoise.smoothing_enabled, denoise.temporal_enabled, denoise.bypass,
+                      self.render_engine.denoise_level());
+                let auto_correct = self.render_engine.auto_correct_settings();
+                info!("画面校正: 白平衡={} 色阶={} 弱光增强={}",
+                      auto_correct.white_balance, auto_correct.auto_levels, auto_correct.low_light_boost);
+                info!("文字叠加: {} 条{}", self.render_engine.text_overlays().len(),
+                      if self.render_engine.overlay_visible() { "" } else { "（已隐藏）" });
+                info!("贴纸: {} 个{}", self.render_engine.stickers().len(),
+                      if self.render_engine.stickers_visible() { "" } else { "（已隐藏）" });
+                info!("标注: {} 笔{}", self.render_engine.annotations().strokes().len(),
+                      if self.annotation_mode { "（标注模式）" } else { "" });
                 info!("尺寸: {}x{}", window_size.width, window_size.height);
                 info!("位置: ({:.0}, {:.0})", window_position.x, window_position.y);
                 info!("旋转: {:.1}°", rotation);
+                info!("视频旋转: {:.1}°{}", self.window_manager.video_rotation(),
+                      if self.window_manager.keep_video_upright() { "（保持正向）" } else { "" });
                 info!("摄像头: {}", current_device);
+                info!("GPU: {}，呈现模式: {:?}", describe_adapter(self.render_engine.adapter_info()),
+                      self.render_engine.present_mode());
+                let timings = self.render_engine.frame_timings();
+                let gpu_time = match timings.gpu_total() {
+                    Some(total) => format!("{:.2}ms", total.as_secs_f32() * 1000.0),
+                    None if self.render_engine.gpu_timing_supported() => "等待结果".to_string(),
+                    None => "适配器不支持".to_string(),
+                };
+                info!("渲染耗时: CPU 编码 {:.2}ms，GPU {}", timings.cpu_encode.as_secs_f32() * 1000.0, gpu_time);
                 info!("================");
             }
             
@@ -383,6 +642,11 @@ impl EventHandler {
                         .map(|id| id.to_string());
                     
                     if let Some(item_id) = item_id_opt {
+                        if self.context_menu.is_submenu(&item_id) {
+                            // 子菜单项只展开子菜单，菜单保持显示
+                            self.context_menu.expand_submenu(&item_id);
+                            return;
+                        }
                         // 执行菜单项
                         if let Err(e) = self.handle_menu_item_click(&item_id) {
                             error!("执行菜单项失败: {}", e);
@@ -431,7 +695,22 @@ impl EventHandler {
                     }
                 }
                 
+                // Shift+单击：在视频中拾取抠像关键色
+                if self.modifiers_state.shift_key() {
+                    self.pick_key_color(position);
+                    return;
+                }
+                
+                // 标注模式：左键拖动绘制笔画
+                if self.annotation_mode {
+                    if let Some(point) = self.annotation_point(position) {
+                        self.render_engine.annotations_mut().begin_stroke(point);
+                    }
+                    return;
+                }
+                
                 // 开始拖拽窗口
+                self.begin_mask_interaction();
                 self.window_manager.start_drag(position);
                 info!("开始拖拽窗口，鼠标位置: ({:.1}, {:.1})", position.x, position.y);
             }
@@ -449,6 +728,9 @@ impl EventHandler {
     fn handle_mouse_release(&mut self, button: MouseButton) {
         match button {
             MouseButton::Left => {
+                if self.render_engine.annotations().is_drawing() {
+                    self.render_engine.annotations_mut().end_stroke(std::time::Instant::now());
+                }
                 if self.window_manager.is_dragging() {
                     self.window_manager.end_drag();
                     let final_pos = self.window_manager.position();
@@ -524,6 +806,13 @@ impl EventHandler {
         if self.window_manager.is_dragging() {
             self.window_manager.update_drag(position);
         }
+        
+        // 正在绘制笔画时追加点，遮罩四边形之外的点被忽略
+        if self.render_engine.annotations().is_drawing() {
+            if let Some(point) = self.annotation_point(position) {
+                self.render_engine.annotations_mut().extend_stroke(point);
+            }
+        }
     }
     
     /// 更新按钮悬浮状态
@@ -549,26 +838,34 @@ impl EventHandler {
     
     /// 处理鼠标滚轮事件（缩放或旋转）
     fn handle_mouse_wheel(&mut self, delta: MouseScrollDelta, modifiers: ModifiersState) {
-        let ctrl_pressed = modifiers.control_key();
-        
         match delta {
             MouseScrollDelta::LineDelta(_, y) => {
-                self.handle_scroll_delta(y, ctrl_pressed);
+                self.handle_scroll_delta(y, modifiers);
             }
             MouseScrollDelta::PixelDelta(delta) => {
                 let y = delta.y as f32;
                 if y.abs() > 1.0 {
                     let normalized_y = if y > 0.0 { 1.0 } else { -1.0 };
-                    self.handle_scroll_delta(normalized_y, ctrl_pressed);
+                    self.handle_scroll_delta(normalized_y, modifiers);
                 }
             }
         }
     }
     
-    /// 处理滚轮增量（缩放或旋转）
-    fn handle_scroll_delta(&mut self, y: f32, ctrl_pressed: bool) {
-        if ctrl_pressed {
-            // Ctrl + 滚轮：旋转窗口
+    /// 处理滚轮增量（缩放、遮罩旋转或视频旋转）
+    fn handle_scroll_delta(&mut self, y: f32, modifiers: ModifiersState) {
+        if modifiers.alt_key() {
+            // Alt + 滚轮：只旋转视频内容
+            let old_rotation = self.window_manager.video_rotation();
+            let delta = if y > 0.0 { 15.0 } else if y < 0.0 { -15.0 } else { 0.0 };
+            
+            if delta != 0.0 {
+                self.window_manager.rotate_video(delta);
+                info!("视频旋转 {:+.0}°，角度: {:.1}° -> {:.1}°", 
+                      delta, old_rotation, self.window_manager.video_rotation());
+            }
+        } else if modifiers.control_key() {
+            // Ctrl + 滚轮：旋转遮罩形状
             let old_rotation = self.window_manager.rotation();
             
             if y > 0.0 {
@@ -585,6 +882,7 @@ impl EventHandler {
         } else {
             // 普通滚轮：缩放窗口
             let old_size = self.window_manager.size();
+            self.begin_mask_interaction();
             
             if y > 0.0 {
                 // 向上滚动，放大 10%
@@ -636,11 +934,63 @@ impl EventHandler {
                     // R: 重置窗口位置和旋转
                     self.reset_window();
                 }
+                Key::Character(c) if (c == "u" || c == "U") && !has_command_modifier(self.modifiers_state) => {
+                    // U: 切换视频保持正向
+                    self.toggle_keep_video_upright();
+                }
+                Key::Character(c) if (c == "k" || c == "K") && !has_command_modifier(self.modifiers_state) => {
+                    // K: 切换绿幕抠像
+                    self.toggle_chroma_key();
+                }
+                Key::Character(c) if (c == "e" || c == "E") && !has_command_modifier(self.modifiers_state) => {
+                    // E: 开关特效链
+                    self.toggle_effects();
+                }
+                Key::Character(c) if (c == "d" || c == "D") && !has_command_modifier(self.modifiers_state) => {
+                    // D: 旁路降噪，便于对比效果
+                    self.toggle_denoise_bypass();
+                }
+                Key::Character(c) if (c == "a" || c == "A") && !has_command_modifier(self.modifiers_state) => {
+                    // A: 一键开关全部自动校正
+                    self.toggle_auto_correct();
+                }
+                Key::Character(c) if (c == "t" || c == "T") && !has_command_modifier(self.modifiers_state) => {
+                    // T: 显示或隐藏文字叠加
+                    self.toggle_text_overlay();
+                }
+                Key::Character(c) if (c == "s" || c == "S") && !has_command_modifier(self.modifiers_state) => {
+                    // S: 显示或隐藏贴纸
+                    self.toggle_stickers();
+                }
+                Key::Character(c) if (c == "p" || c == "P") && !has_command_modifier(self.modifiers_state) => {
+                    // P: 切换标注模式
+                    self.toggle_annotation_mode();
+                }
+                Key::Character(c) if (c == "z" || c == "Z") && self.modifiers_state.control_key() => {
+                    // Ctrl+Z: 撤销最后一条笔画
+                    self.undo_annotation();
+                }
+                Key::Character(c) if (c == "c" || c == "C") && !has_command_modifier(self.modifiers_state) => {
+                    // C: 清除全部标注
+                    self.clear_annotations();
+                }
+                Key::Named(NamedKey::F10) => {
+                    // F10: 开始或停止录制
+                    self.toggle_recording();
+                }
+                Key::Named(NamedKey::F12) => {
+                    // F12: 拍摄快照，倒计时中再次按下取消
+                    self.take_snapshot();
+                }
                 Key::Named(NamedKey::Escape) => {
-                    // ESC: 关闭上下文菜单
+                    // ESC: 关闭上下文菜单，菜单未显示时取消快照倒计时或退出标注模式
                     if self.is_context_menu_visible() {
                         self.hide_context_menu();
                         info!("上下文菜单已关闭");
+                    } else if self.snapshot_timer.cancel() {
+                        info!("快照倒计时已取消");
+                    } else if self.annotation_mode {
+                        self.toggle_annotation_mode();
                     }
                 }
                 _ => {
@@ -662,6 +1012,7 @@ impl EventHandler {
         
         // 调整形状遮罩以适应新尺寸
         self.shape_mask.resize(size.width, size.height);
+        self.context_menu.update_screen_size(size);
         
         // 更新渲染引擎的遮罩
         if let Err(e) = self.render_engine.set_mask(&self.shape_mask) {
@@ -693,8 +1044,448 @@ impl EventHandler {
         info!("资源清理完成");
     }
     
+    /// 切换视频是否保持正向（不跟随遮罩旋转）
+    pub fn toggle_keep_video_upright(&mut self) {
+        let keep_upright = !self.window_manager.keep_video_upright();
+        self.window_manager.set_keep_video_upright(keep_upright);
+        self.context_menu.set_item_checked("keep_video_upright", keep_upright);
+    }
+    
+    /// 设置遮罩边缘质量
+    pub fn set_mask_quality(&mut self, quality: MaskQuality) {
+        let old_quality = self.shape_mask.quality();
+        if self.shape_mask.set_quality(quality) {
+            if let Err(e) = self.render_engine.set_mask(&self.shape_mask) {
+                error!("更新渲染引擎遮罩失败: {}", e);
+            }
+        }
+        self.context_menu.select_radio_item(&quality_menu_id(quality));
+        info!("遮罩质量切换: {:?} -> {:?}", old_quality, quality);
+    }
+    
+    /// 设置颜色预设滤镜
+    pub fn set_color_preset(&mut self, preset: ColorPreset) {
+        self.render_engine.set_color_preset(preset);
+        self.context_menu.select_radio_item(&filter_menu_id(preset));
+    }
+    
+    /// 切换 LUT 滤镜（未加载 LUT 时无效）
+    pub fn toggle_lut(&mut self) {
+        if !self.render_engine.has_lut() {
+            warn!("未加载 LUT 文件，请在配置中设置 render.color.lut_path");
+            self.context_menu.set_item_checked("filter_lut", false);
+            return;
+        }
+        let enabled = !self.render_engine.lut_enabled();
+        self.render_engine.set_lut_enabled(enabled);
+        self.context_menu.set_item_checked("filter_lut", enabled);
+        info!("LUT 滤镜: {}", if enabled { "开启" } else { "关闭" });
+    }
+    
+    /// 切换绿幕抠像
+    pub fn toggle_chroma_key(&mut self) {
+        let enabled = !self.render_engine.chroma_key().enabled;
+        self.render_engine.set_chroma_key_enabled(enabled);
+        self.context_menu.set_item_checked("chroma_key", enabled);
+    }
+    
+    /// 修改降噪参数并同步菜单
+    pub fn update_denoise_settings<F>(&mut self, update: F)
+    where
+        F: FnOnce(&mut DenoiseSettings),
+    {
+        let mut settings = self.render_engine.denoise_settings();
+        update(&mut settings);
+        info!("降噪: 平滑={} 时域={} 旁路={}",
+              settings.smoothing_enabled, settings.temporal_enabled, settings.bypass);
+        self.render_engine.set_denoise_settings(settings);
+        self.sync_denoise_menu();
+    }
+    
+    /// 切换降噪旁路
+    pub fn toggle_denoise_bypass(&mut self) {
+        self.update_denoise_settings(|settings| settings.bypass = !settings.bypass);
+    }
+    
+    /// 同步降噪复选项的状态
+    fn sync_denoise_menu(&mut self) {
+        let settings = self.render_engine.denoise_settings();
+        self.context_menu.set_item_checked("denoise_smoothing", settings.smoothing_enabled);
+        self.context_menu.set_item_checked("denoise_temporal", settings.temporal_enabled);
+        self.context_menu.set_item_checked("denoise_bypass", settings.bypass);
+    }
+    
+    /// 修改自动校正参数并同步菜单
+    pub fn update_auto_correct_settings<F>(&mut self, update: F)
+    where
+        F: FnOnce(&mut AutoCorrectSettings),
+    {
+        let mut settings = self.render_engine.auto_correct_settings();
+        update(&mut settings);
+        info!("画面校正: 白平衡={} 色阶={} 弱光增强={}",
+              settings.white_balance, settings.auto_levels, settings.low_light_boost);
+        self.render_engine.set_auto_correct_settings(settings);
+        self.sync_auto_correct_menu();
+    }
+    
+    /// 任一校正开启时全部关闭，否则全部开启
+    pub fn toggle_auto_correct(&mut self) {
+        let enable = !self.render_engine.auto_correct_settings().is_active();
+        self.update_auto_correct_settings(|settings| {
+            settings.white_balance = enable;
+            settings.auto_levels = enable;
+            settings.low_light_boost = enable;
+        });
+    }
+    
+    /// 同步自动校正复选项的状态
+    fn sync_auto_correct_menu(&mut self) {
+        let settings = self.render_engine.auto_correct_settings();
+        self.context_menu.set_item_checked("auto_white_balance", settings.white_balance);
+        self.context_menu.set_item_checked("auto_levels", settings.auto_levels);
+        self.context_menu.set_item_checked("low_light_boost", settings.low_light_boost);
+    }
+    
+    /// 切换文字叠加的显示（未配置文字或字体不可用时无效）
+    pub fn toggle_text_overlay(&mut self) {
+        if !self.render_engine.has_text_overlays() {
+            warn!("没有可显示的文字叠加，请在配置中添加 render.text_overlay.items 并确认字体可用");
+            self.context_menu.set_item_checked("text_overlay", false);
+            return;
+        }
+        let visible = !self.render_engine.overlay_visible();
+        self.render_engine.set_overlay_visible(visible);
+        self.context_menu.set_item_checked("text_overlay", visible);
+    }
+    
+    /// 开关特效链（没有可用特效时无效）
+    pub fn toggle_effects(&mut self) {
+        if !self.render_engine.has_effects() {
+            warn!("没有可用的特效，请在配置中添加 render.effects.passes");
+            self.context_menu.set_item_checked("effects", false);
+            return;
+        }
+        let enabled = !self.render_engine.effects_enabled();
+        self.render_engine.set_effects_enabled(enabled);
+        self.context_menu.set_item_checked("effects", enabled);
+    }
+    
+    /// 切换贴纸的显示（未配置贴纸时无效）
+    pub fn toggle_stickers(&mut self) {
+        if !self.render_engine.has_stickers() {
+            warn!("没有可显示的贴纸，请在配置中添加 render.stickers.items");
+            self.context_menu.set_item_checked("stickers", false);
+            return;
+        }
+        let visible = !self.render_engine.stickers_visible();
+        self.render_engine.set_stickers_visible(visible);
+        self.context_menu.set_item_checked("stickers", visible);
+    }
+    
+    /// 标注模式是否开启
+    pub fn annotation_mode(&self) -> bool {
+        self.annotation_mode
+    }
+    
+    /// 切换标注模式：开启后左键拖动绘制笔画，关闭后恢复拖动窗口
+    pub fn toggle_annotation_mode(&mut self) {
+        self.annotation_mode = !self.annotation_mode;
+        if !self.annotation_mode {
+            self.render_engine.annotations_mut().end_stroke(std::time::Instant::now());
+        }
+        self.context_menu.set_item_checked("annotation_mode", self.annotation_mode);
+        if self.annotation_mode {
+            info!("标注模式已开启：左键拖动绘制，Ctrl+Z 撤销，C 清除，Esc 退出");
+        } else {
+            info!("标注模式已关闭");
+        }
+    }
+    
+    /// 撤销最后一条笔画
+    pub fn undo_annotation(&mut self) {
+        if !self.render_engine.annotations_mut().undo() {
+            debug!("没有可撤销的标注");
+        }
+    }
+    
+    /// 清除全部标注
+    pub fn clear_annotations(&mut self) {
+        self.render_engine.annotations_mut().clear();
+        info!("标注已清除");
+    }
+    
+    /// 拍摄快照：配置了倒计时时先倒计时，倒计时中再次触发则取消
+    pub fn take_snapshot(&mut self) {
+        let seconds = self.config_manager.get_config().render.snapshot.countdown_seconds;
+        let countdown = std::time::Duration::from_secs(seconds as u64);
+        if self.snapshot_timer.trigger(std::time::Instant::now(), countdown) {
+            if seconds > 0 {
+                info!("{} 秒后拍摄快照，再次触发或按 Esc 取消", seconds);
+            }
+        } else if self.snapshot_timer.cancel() {
+            info!("快照倒计时已取消");
+        }
+    }
+    
+    /// 切换快照保存原始画面还是合成画面
+    pub fn toggle_snapshot_raw_frame(&mut self) {
+        self.snapshot_raw_frame = !self.snapshot_raw_frame;
+        self.context_menu.set_item_checked("snapshot_raw", self.snapshot_raw_frame);
+        let source = if self.snapshot_raw_frame { CaptureSource::Raw } else { CaptureSource::Composited };
+        info!("快照保存{}", source.name());
+    }
+    
+    /// 快照是否保存原始画面
+    pub fn snapshot_raw_frame(&self) -> bool {
+        self.snapshot_raw_frame
+    }
+    
+    /// 到达拍摄时间时请求回读画面，并更新快门闪光
+    fn update_snapshot(&mut self) {
+        let now = std::time::Instant::now();
+        if self.snapshot_timer.update(now) {
+            let source = if self.snapshot_raw_frame { CaptureSource::Raw } else { CaptureSource::Composited };
+            self.render_engine.request_capture(source);
+            self.snapshot_requests.push(source);
+        } else if let Some(seconds) = self.snapshot_timer.remaining_seconds(now) {
+            debug!("快照倒计时: {} 秒", seconds);
+        }
+        // 录制、输出帧流或预览服务有客户端时不闪光：有回读的帧不画闪光，按固定帧率回读时闪光会闪烁
+        let streaming = self.recorder.is_recording() || self.frame_output.is_some() || self.is_previewing();
+        let flash = if self.config_manager.get_config().render.snapshot.flash && !streaming {
+            self.snapshot_timer.flash_intensity(now)
+        } else {
+            0.0
+        };
+        self.render_engine.set_shutter_flash(flash);
+    }
+    
+    /// 把回读完成的画面分给快照、录制、帧输出流和预览服务：快照在后台保存，合成画面同时交给其余几项
+    fn dispatch_captures(&mut self) {
+        let frames = self.render_engine.poll_captures();
+        if frames.is_empty() {
+            return;
+        }
+        let directory = snapshot::snapshot_directory(&self.config_manager.get_config().render.snapshot.directory);
+        for frame in frames {
+            if frame.source == CaptureSource::Composited {
+                if let Some(output) = &self.frame_output {
+                    output.push_frame(frame.clone());
+                }
+                if let Some(server) = &self.preview_server {
+                    server.push_frame(frame.clone());
+                }
+            }
+            let for_snapshot = match self.snapshot_requests.iter().position(|&source| source == frame.source) {
+                Some(index) => {
+                    self.snapshot_requests.remove(index);
+                    true
+                }
+                None => false,
+            };
+            let for_recording = frame.source == CaptureSource::Composited && self.recorder.is_recording();
+            match (for_snapshot, for_recording) {
+                (true, true) => {
+                    snapshot::save_in_background(frame.clone(), directory.clone());
+                    self.recorder.push_frame(frame);
+                }
+                (true, false) => snapshot::save_in_background(frame, directory.clone()),
+                (false, true) => self.recorder.push_frame(frame),
+                (false, false) => {}
+            }
+        }
+        // 设备重建时未完成的回读被丢弃，对应的快照请求不会再完成
+        if !self.snapshot_requests.is_empty() && !self.render_engine.capture_pending() {
+            warn!("{} 个快照请求的回读已丢弃", self.snapshot_requests.len());
+            self.snapshot_requests.clear();
+        }
+    }
+    
+    /// 开始或停止录制，参数来自配置
+    pub fn toggle_recording(&mut self) {
+        let now = std::time::Instant::now();
+        if !self.recorder.stop(now) {
+            let config = &self.config_manager.get_config().render;
+            let settings = RecordingSettings {
+                format: RecordingFormat::from_name(&config.recording.format).unwrap_or(RecordingFormat::Apng),
+                fps: config.recording.fps,
+                max_duration: std::time::Duration::from_secs(config.recording.max_seconds as u64),
+                max_bytes: config.recording.max_file_mb as u64 * 1024 * 1024,
+            };
+            // 录制目录为空时与快照相同
+            let configured = if config.recording.directory.is_empty() {
+                &config.snapshot.directory
+            } else {
+                &config.recording.directory
+            };
+            let directory = snapshot::snapshot_directory(configured);
+            if let Err(e) = self.recorder.start(settings, &directory, now) {
+                error!("开始录制失败: {}", e);
+            }
+        }
+        self.context_menu.set_item_checked("recording_toggle", self.recorder.is_recording());
+    }
+    
+    /// 是否正在录制
+    pub fn is_recording(&self) -> bool {
+        self.recorder.is_recording()
+    }
+    
+    /// 检查录制是否自动停止，按录制帧率请求回读并更新录制指示
+    fn update_recording(&mut self) {
+        let now = std::time::Instant::now();
+        if self.recorder.update(now) {
+            self.context_menu.set_item_checked("recording_toggle", false);
+        }
+        if self.recorder.wants_frame(now) {
+            self.render_engine.request_capture(CaptureSource::Composited);
+        }
+        self.render_engine.set_recording_indicator(self.recorder.is_recording());
+    }
+    
+    /// 按配置启动帧输出流，未启用或启动失败时返回 None
+    fn start_frame_output(config: &FrameOutputConfig) -> Option<FrameOutput> {
+        if !config.enabled {
+            return None;
+        }
+        let settings = FrameOutputSettings {
+            format: OutputFormat::from_name(&config.format).unwrap_or(OutputFormat::Y4m),
+            target: OutputTarget::from_config(&config.target),
+            width: config.width,
+            height: config.height,
+            fps: config.fps,
+        };
+        match FrameOutput::start(settings, std::time::Instant::now()) {
+            Ok(output) => Some(output),
+            Err(e) => {
+                error!("启动帧输出流失败: {}", e);
+                None
+            }
+        }
+    }
+    
+    /// 按输出帧率请求回读；输出线程停止（读取端关闭或写入失败）后移除帧输出流
+    fn update_frame_output(&mut self) {
+        let Some(output) = &mut self.frame_output else {
+            return;
+        };
+        if !output.is_running() {
+            info!("帧输出流已结束");
+            self.frame_output = None;
+        } else if output.wants_frame(std::time::Instant::now()) {
+            self.render_engine.request_capture(CaptureSource::Composited);
+        }
+    }
+    
+    /// 按配置启动本地预览服务，未启用或启动失败时返回 None
+    fn start_preview_server(config: &PreviewServerConfig) -> Option<PreviewServer> {
+        if !config.enabled {
+            return None;
+        }
+        let settings = PreviewServerSettings {
+            port: config.port,
+            fps: config.fps,
+            max_clients: config.max_clients as usize,
+            jpeg_quality: config.jpeg_quality as u8,
+        };
+        match PreviewServer::start(settings, std::time::Instant::now()) {
+            Ok(server) => {
+                info!("预览服务已启动: http://{}/", server.local_addr());
+                Some(server)
+            }
+            Err(e) => {
+                error!("启动预览服务失败: {}", e);
+                None
+            }
+        }
+    }
+    
+    /// 预览服务是否有客户端正在观看或等待快照
+    fn is_previewing(&self) -> bool {
+        self.preview_server.as_ref().is_some_and(|server| server.is_active())
+    }
+    
+    /// 有客户端时按预览帧率上限请求回读
+    fn update_preview_server(&mut self) {
+        let now = std::time::Instant::now();
+        if self.preview_server.as_mut().is_some_and(|server| server.wants_frame(now)) {
+            self.render_engine.request_capture(CaptureSource::Composited);
+        }
+    }
+    
+    /// 切换笔画自动淡出，淡出时间来自配置
+    fn toggle_annotation_fade(&mut self) {
+        let enabled = self.render_engine.annotations().fade_after().is_none();
+        let seconds = self.config_manager.get_config().render.annotation.fade_seconds;
+        let fade_after = enabled.then(|| std::time::Duration::from_secs_f32(seconds));
+        self.render_engine.annotations_mut().set_fade_after(fade_after);
+        self.context_menu.set_item_checked("annotation_fade", enabled);
+    }
+    
+    /// 同步画笔颜色、粗细和自动淡出的菜单状态
+    fn sync_annotation_menu(&mut self) {
+        let annotations = self.render_engine.annotations();
+        let color = ANNOTATION_COLORS.iter().position(|color| *color == annotations.color());
+        let width = ANNOTATION_WIDTHS.iter().position(|width| *width == annotations.width());
+        let fade = annotations.fade_after().is_some();
+        if let Some(index) = color {
+            self.context_menu.select_radio_item(&format!("annotation_color_{}", index));
+        }
+        if let Some(index) = width {
+            self.context_menu.select_radio_item(&format!("annotation_width_{}", index));
+        }
+        self.context_menu.set_item_checked("annotation_fade", fade);
+        self.context_menu.set_item_checked("annotation_mode", self.annotation_mode);
+    }
+    
+    /// 窗口坐标转换为标注使用的遮罩纹理坐标，随遮罩一起旋转
+    fn annotation_point(&self, position: PhysicalPosition<f64>) -> Option<[f32; 2]> {
+        let mask_rotation = self.window_manager.rotation().to_radians();
+        window_to_mask_coords(position, self.window_manager.size(), mask_rotation).map(|(u, v)| [u, v])
+    }
+    
+    /// 在窗口位置对应的视频像素处拾取抠像关键色，并启用抠像
+    fn pick_key_color(&mut self, position: PhysicalPosition<f64>) {
+        let mask_rotation = self.window_manager.rotation().to_radians();
+        let video_rotation = self.window_manager.effective_video_rotation().to_radians();
+        match window_to_video_coords(position, self.window_manager.size(), mask_rotation, video_rotation) {
+            Some(tex_coords) => {
+                self.render_engine.request_key_color_pick(tex_coords);
+                if !self.render_engine.chroma_key().enabled {
+                    self.toggle_chroma_key();
+                }
+            }
+            None => debug!("取色位置 ({:.1}, {:.1}) 不在视频范围内", position.x, position.y),
+        }
+    }
+    
+    /// 标记窗口开始拖拽或缩放（自适应质量降级为快速）
+    fn begin_mask_interaction(&mut self) {
+        self.last_interaction_time = std::time::Instant::now();
+        if self.shape_mask.set_interacting(true) {
+            if let Err(e) = self.render_engine.set_mask(&self.shape_mask) {
+                error!("更新渲染引擎遮罩失败: {}", e);
+            }
+        }
+    }
+    
+    /// 窗口空闲后恢复自适应遮罩的高质量
+    fn update_adaptive_mask_quality(&mut self) {
+        if !self.shape_mask.is_interacting() || self.window_manager.is_dragging() {
+            return;
+        }
+        
+        // 最后一次缩放后静止超过300ms视为空闲
+        if self.last_interaction_time.elapsed().as_millis() > 300 && self.shape_mask.set_interacting(false) {
+            debug!("窗口已空闲，重新生成高质量遮罩");
+            if let Err(e) = self.render_engine.set_mask(&self.shape_mask) {
+                error!("更新渲染引擎遮罩失败: {}", e);
+            }
+        }
+    }
+    
     /// 切换形状
-    fn switch_shape(&mut self, shape_type: crate::shape::ShapeType) {
+    pub fn switch_shape(&mut self, shape_type: crate::shape::ShapeType) {
         let old_shape = self.shape_mask.shape_type();
         self.shape_mask.set_shape(shape_type);
         
@@ -749,8 +1540,9 @@ impl EventHandler {
         // 重置位置到默认值
         self.window_manager.set_position(100.0, 100.0);
         
-        // 重置旋转角度
+        // 重置遮罩和视频的旋转角度
         self.window_manager.set_rotation(0.0);
+        self.window_manager.set_video_rotation(0.0);
         
         // 重置尺寸到默认值
         self.window_manager.set_size(400, 400);
@@ -760,7 +1552,7 @@ impl EventHandler {
     
     /// 创建当前配置
     fn create_current_config(&self) -> crate::config::AppConfig {
-        use crate::config::{AppConfig, WindowConfig, CameraConfig};
+        use crate::config::{AppConfig, WindowConfig, CameraConfig, RenderConfig, ColorConfig, ChromaKeyConfig, DenoiseConfig, AutoCorrectConfig, StickerConfig, TextOverlayConfig, AnnotationConfig, EffectsConfig, SnapshotConfig};
         
         let window_pos = self.window_manager.position();
         let window_size = self.window_manager.size();
@@ -781,13 +1573,116 @@ impl EventHandler {
                 height: window_size.height,
                 rotation: self.window_manager.rotation(),
                 shape: shape_name.to_string(),
+                video_rotation: self.window_manager.video_rotation(),
+                keep_video_upright: self.window_manager.keep_video_upright(),
+                rotation_snap_tolerance: self.window_manager.snap_tolerances().0,
+                video_snap_tolerance: self.window_manager.snap_tolerances().1,
             },
             camera: CameraConfig {
                 device_index: self.camera_manager.current_device_index().unwrap_or(0),
             },
+            render: {
+                let saved = &self.config_manager.get_config().render;
+                let adjustments = self.render_engine.color_adjustments();
+                RenderConfig {
+                    mask_quality: self.shape_mask.quality().as_str().to_string(),
+                    color: ColorConfig {
+                        preset: self.render_engine.color_preset().as_str().to_string(),
+                        brightness: adjustments.brightness,
+                        contrast: adjustments.contrast,
+                        saturation: adjustments.saturation,
+                        gamma: adjustments.gamma,
+                        hue_shift: adjustments.hue_shift,
+                        temperature: adjustments.temperature,
+                        lut_path: saved.color.lut_path.clone(),
+                        lut_enabled: if self.render_engine.has_lut() {
+                            self.render_engine.lut_enabled()
+                        } else {
+                            saved.color.lut_enabled
+                        },
+                    },
+                    chroma_key: {
+                        let chroma_key = self.render_engine.chroma_key();
+                        ChromaKeyConfig {
+                            enabled: chroma_key.enabled,
+                            key_color: chroma_key.key_color.map(|c| (c.clamp(0.0, 1.0) * 255.0).round() as u8),
+                            similarity: chroma_key.similarity,
+                            smoothness: chroma_key.smoothness,
+                            spill: chroma_key.spill,
+                        }
+                    },
+                    denoise: {
+                        let denoise = self.render_engine.denoise_settings();
+                        DenoiseConfig {
+                            smoothing_enabled: denoise.smoothing_enabled,
+                            smoothing_strength: denoise.smoothing_strength,
+                            temporal_enabled: denoise.temporal_enabled,
+                            temporal_strength: denoise.temporal_strength,
+                            motion_threshold: denoise.motion_threshold,
+                        }
+                    },
+                    auto_correct: {
+                        let auto_correct = self.render_engine.auto_correct_settings();
+                        AutoCorrectConfig {
+                            white_balance: auto_correct.white_balance,
+                            auto_levels: auto_correct.auto_levels,
+                            low_light_boost: auto_correct.low_light_boost,
+                            smoothing: auto_correct.smoothing,
+                        }
+                    },
+                    // 文字内容和字体只来自配置文件，这里只保存显示状态
+                    text_overlay: TextOverlayConfig {
+                        enabled: self.render_engine.overlay_visible(),
+                        ..saved.text_overlay.clone()
+                    },
+                    // 贴纸列表同样只来自配置文件
+                    stickers: StickerConfig {
+                        enabled: self.render_engine.stickers_visible(),
+                        ..saved.stickers.clone()
+                    },
+                    // 特效列表和参数只来自配置文件，这里只保存总开关
+                    effects: EffectsConfig {
+                        enabled: self.render_engine.effects_enabled(),
+                        ..saved.effects.clone()
+                    },
+                    // 快照只保存画面来源，目录、倒计时和闪光只来自配置文件
+                    snapshot: SnapshotConfig {
+                        raw_frame: self.snapshot_raw_frame,
+                        ..saved.snapshot.clone()
+                    },
+                    // 只保存画笔设置，笔画不保存
+                    annotation: {
+                        let annotations = self.render_engine.annotations();
+                        AnnotationConfig {
+                            color: annotations.color(),
+                            width: annotations.width(),
+                            auto_fade: annotations.fade_after().is_some(),
+                            fade_seconds: annotations.fade_after()
+                                .map_or(saved.annotation.fade_seconds, |fade| fade.as_secs_f32()),
+                        }
+                    },
+                    ..saved.clone()
+                }
+            },
         }
     }
     
+    /// 是否有需要逐帧重绘的动画（标注渐隐、快照倒计时和闪光、动画贴纸、时钟文字）、
+    /// 正在录制、输出帧流或预览、有等待完成的回读，或遮罩等待空闲后恢复高质量
+    pub fn is_animating(&self) -> bool {
+        let now = std::time::Instant::now();
+        self.render_engine.annotations().is_fading(now)
+            || self.snapshot_timer.is_animating(now)
+            || self.render_engine.stickers_animating()
+            || self.render_engine.overlay_clock_changed()
+            // 交互结束后由 update_adaptive_mask_quality 在渲染时恢复高质量遮罩
+            || self.shape_mask.is_interacting()
+            || self.recorder.is_recording()
+            || self.frame_output.is_some()
+            || self.is_previewing()
+            || self.render_engine.capture_pending()
+    }
+    
     /// 渲染一帧
     pub fn render_frame(&mut self) -> Result<(), String> {
         // 如果应该关闭，直接返回
@@ -795,8 +1690,14 @@ impl EventHandler {
             return Ok(());
         }
         
-        // 从摄像头捕获帧
-        let frame = match self.camera_manager.capture_frame() {
+        self.update_adaptive_mask_quality();
+        self.update_snapshot();
+        self.update_recording();
+        self.update_frame_output();
+        self.update_preview_server();
+        
+        // 取走捕获线程的最新一帧；没有新帧时（界面变化或动画触发的重绘）沿用已上传的视频纹理
+        let frame = match self.camera_manager.try_capture_frame() {
             Ok(frame) => frame,
             Err(e) => {
                 // 记录捕获错误并尝试恢复
@@ -816,21 +1717,23 @@ impl EventHandler {
             }
         };
         
-        // 上传帧到 GPU - 转换 Frame 类型
-        let render_frame = crate::render::engine::Frame {
-            data: frame.data,
-            width: frame.width,
-            height: frame.height,
-            format: match frame.format {
-                crate::camera::manager::PixelFormat::RGB8 => crate::render::engine::PixelFormat::RGB8,
-                crate::camera::manager::PixelFormat::RGBA8 => crate::render::engine::PixelFormat::RGBA8,
-                crate::camera::manager::PixelFormat::YUV420 => crate::render::engine::PixelFormat::YUV420,
-            },
-        };
-        
-        if let Err(e) = self.render_engine.upload_frame(&render_frame) {
-            error!("上传视频帧到 GPU 失败: {}", e);
-            return Err(format!("GPU 上传失败: {}", e));
+        if let Some(frame) = frame {
+            // 上传帧到 GPU - 转换 Frame 类型
+            let render_frame = crate::render::engine::Frame {
+                data: frame.data,
+                width: frame.width,
+                height: frame.height,
+                format: match frame.format {
+                    crate::camera::manager::PixelFormat::RGB8 => crate::render::engine::PixelFormat::RGB8,
+                    crate::camera::manager::PixelFormat::RGBA8 => crate::render::engine::PixelFormat::RGBA8,
+                    crate::camera::manager::PixelFormat::YUV420 => crate::render::engine::PixelFormat::YUV420,
+                },
+            };
+            
+            if let Err(e) = self.render_engine.upload_frame(&render_frame) {
+                error!("上传视频帧到 GPU 失败: {}", e);
+                return Err(format!("GPU 上传失败: {}", e));
+            }
         }
         
         // 准备UI渲染信息
@@ -841,8 +1744,9 @@ impl EventHandler {
             minimize_button_hovered: self.minimize_button_hovered,
         };
         
-        // 调用渲染引擎渲染当前帧
+        // 调用渲染引擎渲染当前帧（遮罩和视频分别旋转）
         let rotation_radians = self.window_manager.rotation().to_radians();
+        self.render_engine.set_video_rotation(self.window_manager.effective_video_rotation().to_radians());
         
         // 如果上下文菜单可见，使用特殊的渲染路径
         if self.is_context_menu_visible() {
@@ -875,7 +1779,9 @@ impl EventHandler {
             }
         }
         
-        debug!("成功渲染一帧，帧尺寸: {}x{}", frame.width, frame.height);
+        self.dispatch_captures();
+        
+        debug!("成功渲染一帧");
         Ok(())
     }
     
@@ -883,27 +1789,26 @@ impl EventHandler {
     fn render_frame_with_context_menu(&mut self, rotation: f32, ui_info: &crate::render::engine::UIRenderInfo) -> Result<(), String> {
         debug!("渲染带上下文菜单的帧");
         
-        // 首先渲染主视频内容和UI控件
-        if let Err(e) = self.render_engine.render_with_ui(rotation, ui_info) {
-            return Err(format!("主内容渲染失败: {}", e));
+        // GPU 设备重建后旧的菜单渲染器资源已失效，用原来的字体重新创建
+        if self.menu_renderer_generation != self.render_engine.device_generation() {
+            if let Some(renderer) = self.menu_renderer.take() {
+                info!("GPU 设备已重建，重新创建菜单渲染器");
+                self.create_menu_renderer(renderer.font())?;
+            }
         }
         
-        // 然后渲染上下文菜单（目前使用简单文本菜单）
-        if self.menu_renderer.is_some() {
-            // 如果有菜单渲染器，尝试使用视觉菜单
-            let window_size = self.window_manager.size();
-            let screen_size = [window_size.width as f32, window_size.height as f32];
-            
-            if let Err(e) = self.render_engine.render_context_menu(self.menu_renderer.as_mut().unwrap(), &self.context_menu, screen_size) {
-                warn!("视觉菜单渲染失败，回退到简单文本菜单: {}", e);
-                self.render_simple_context_menu()?;
-            }
+        if let Some(menu_renderer) = self.menu_renderer.as_mut() {
+            // 视频、UI控件和菜单在同一帧中绘制后再呈现
+            self.render_engine
+                .render_with_menu(rotation, ui_info, menu_renderer, &self.context_menu)
+                .map_err(|e| format!("主内容渲染失败: {}", e))
         } else {
+            if let Err(e) = self.render_engine.render_with_ui(rotation, ui_info) {
+                return Err(format!("主内容渲染失败: {}", e));
+            }
             // 使用简单文本菜单
-            self.render_simple_context_menu()?;
+            self.render_simple_context_menu()
         }
-        
-        Ok(())
     }
     
     /// 渲染简单的上下文菜单（文本版本）
@@ -914,6 +1819,21 @@ impl EventHandler {
     }
 }
 
+/// 遮罩质量对应的菜单项ID
+fn quality_menu_id(quality: MaskQuality) -> String {
+    format!("quality_{}", quality.as_str().to_lowercase())
+}
+
+/// 颜色预设对应的菜单项ID
+fn filter_menu_id(preset: ColorPreset) -> String {
+    format!("filter_{}", preset.as_str().to_lowercase())
+}
+
+/// 是否按住了 Ctrl/Alt/Super，单字母热键此时不响应，避免与 Ctrl+C 等组合键冲突（Shift 不算）
+fn has_command_modifier(modifiers: ModifiersState) -> bool {
+    modifiers.control_key() || modifiers.alt_key() || modifiers.super_key()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -955,6 +1875,17 @@ mod tests {
         // 这里只是测试其基本功能
     }
 
+    #[test]
+    fn test_single_letter_hotkeys_ignore_command_modifiers() {
+        // Ctrl+C 等组合键不应触发单字母热键（如 C 清除标注），Shift 仅用于大小写
+        assert!(!has_command_modifier(ModifiersState::empty()));
+        assert!(!has_command_modifier(ModifiersState::SHIFT));
+        assert!(has_command_modifier(ModifiersState::CONTROL));
+        assert!(has_command_modifier(ModifiersState::ALT));
+        assert!(has_command_modifier(ModifiersState::SUPER));
+        assert!(has_command_modifier(ModifiersState::CONTROL | ModifiersState::SHIFT));
+    }
+
     #[test]
     fn test_cursor_position_tracking() {
         let pos1 = PhysicalPosition::new(10.0, 20.0);
@@ -1094,7 +2025,7 @@ mod tests {
     #[test]
     fn test_config_creation_logic() {
         // 测试配置创建逻辑
-        use crate::config::{AppConfig, WindowConfig, CameraConfig};
+        use crate::config::{AppConfig, WindowConfig, CameraConfig, RenderConfig};
         
         let config = AppConfig {
             version: "1.0".to_string(),
@@ -1105,10 +2036,15 @@ mod tests {
                 height: 400,
                 rotation: 45.0,
                 shape: "Circle".to_string(),
+                video_rotation: 0.0,
+                keep_video_upright: false,
+                rotation_snap_tolerance: 5.0,
+                video_snap_tolerance: 5.0,
             },
             camera: CameraConfig {
                 device_index: 0,
             },
+            render: RenderConfig::default(),
         };
         
         // 验证配置结构
@@ -1120,6 +2056,52 @@ mod tests {
         assert_eq!(config.window.rotation, 45.0);
         assert_eq!(config.window.shape, "Circle");
         assert_eq!(config.camera.device_index, 0);
+        assert_eq!(config.render.mask_quality, "Balanced");
+    }
+    
+    #[test]
+    fn test_quality_menu_ids_match_context_menu() {
+        let menu = ContextMenu::new(PhysicalSize::new(1920, 1080));
+        let item_ids: Vec<&str> = menu.get_display_items().iter().map(|item| item.id.as_str()).collect();
+        
+        for quality in MaskQuality::ALL {
+            let id = quality_menu_id(quality);
+            assert!(item_ids.contains(&id.as_str()), "上下文菜单缺少遮罩质量项: {}", id);
+        }
+    }
+    
+    #[test]
+    fn test_filter_menu_ids_match_context_menu() {
+        let menu = ContextMenu::new(PhysicalSize::new(1920, 1080));
+        let item_ids: Vec<&str> = menu.get_display_items().iter().map(|item| item.id.as_str()).collect();
+        
+        for preset in ColorPreset::ALL {
+            let id = filter_menu_id(preset);
+            assert!(item_ids.contains(&id.as_str()), "上下文菜单缺少颜色滤镜项: {}", id);
+        }
+        assert!(item_ids.contains(&"filter_lut"));
+        assert!(item_ids.contains(&"chroma_key"));
+        assert!(item_ids.contains(&"effects"));
+        for id in ["denoise_smoothing", "denoise_temporal", "denoise_bypass"] {
+            assert!(item_ids.contains(&id), "上下文菜单缺少降噪项: {}", id);
+        }
+        for id in ["auto_white_balance", "auto_levels", "low_light_boost"] {
+            assert!(item_ids.contains(&id), "上下文菜单缺少画面校正项: {}", id);
+        }
+        assert!(item_ids.contains(&"text_overlay"));
+        assert!(item_ids.contains(&"stickers"));
+        for id in ["annotation_mode", "annotation_fade", "annotation_undo", "annotation_clear"] {
+            assert!(item_ids.contains(&id), "上下文菜单缺少标注项: {}", id);
+        }
+        for id in ["snapshot_take", "snapshot_raw", "recording_toggle"] {
+            assert!(item_ids.contains(&id), "上下文菜单缺少快照或录制项: {}", id);
+        }
+        for index in 0..ANNOTATION_COLORS.len() {
+            assert!(item_ids.contains(&format!("annotation_color_{}", index).as_str()));
+        }
+        for index in 0..ANNOTATION_WIDTHS.len() {
+            assert!(item_ids.contains(&format!("annotation_width_{}", index).as_str()));
+        }
     }
     
     #[test]
@@ -0,0 +1,556 @@
+// 帧输出流
+//
+// 把合成画面以 YUV4MPEG2（Y4M）或原始 RGBA 流写到标准输出、命名管道或文件，供 ffmpeg 等本地工具读取，
+// 例如 `mira | ffmpeg -i - out.mp4`。输出按固定的帧率和分辨率写入：没有新画面时重复上一帧，
+// 两次写入之间到达多帧时只保留最新一帧，画面尺寸与输出不同时等比缩放后居中。
+//
+// 写入在后台线程进行。读取方来不及读时阻塞的只是后台线程，期间到达的画面和错过的输出帧都被丢弃，
+// 不会拖慢渲染。打开命名管道会等到读取方连接，同样在后台线程中进行。
+//
+// Y4M 使用 4:2:0 色度采样和 BT.601 有限范围，形状之外的透明区域合成到黑色背景；需要透明度时使用原始 RGBA，
+// 尺寸、帧率和像素格式写在输出文件旁的说明文件中（输出到标准输出时记录在日志中）。
+
+use crate::recording::fit_frame;
+use crate::render::capture::{CaptureThrottle, CapturedFrame};
+use log::{error, info, warn};
+use std::fs::File;
+use std::io::{BufWriter, ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex, PoisonError};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+/// 停止时等待输出线程结束的最长时间
+const STOP_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// 输出格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// YUV4MPEG2，4:2:0，不含透明度
+    Y4m,
+    /// 每帧 width * height * 4 字节的 RGBA，非预乘透明度
+    RawRgba,
+}
+
+impl OutputFormat {
+    /// 从配置名称解析格式
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "Y4m" => Some(OutputFormat::Y4m),
+            "RawRgba" => Some(OutputFormat::RawRgba),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            OutputFormat::Y4m => "Y4M",
+            OutputFormat::RawRgba => "原始 RGBA",
+        }
+    }
+}
+
+/// 输出目标
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OutputTarget {
+    Stdout,
+    /// 文件或命名管道
+    Path(PathBuf),
+}
+
+impl OutputTarget {
+    /// "-" 或空字符串表示标准输出，其余为文件或命名管道路径
+    pub fn from_config(target: &str) -> Self {
+        match target {
+            "" | "-" => OutputTarget::Stdout,
+            path => OutputTarget::Path(PathBuf::from(path)),
+        }
+    }
+}
+
+/// 帧输出参数
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FrameOutputSettings {
+    pub format: OutputFormat,
+    pub target: OutputTarget,
+    /// 输出尺寸，为 0 时使用第一帧画面的尺寸
+    pub width: u32,
+    pub height: u32,
+    pub fps: u32,
+}
+
+impl FrameOutputSettings {
+    /// 实际输出尺寸；Y4M 的 4:2:0 采样要求宽高为偶数，向上取整
+    pub fn output_size(&self, frame_width: u32, frame_height: u32) -> (u32, u32) {
+        let width = if self.width > 0 {
+            self.width
+        } else {
+            frame_width.max(1)
+        };
+        let height = if self.height > 0 {
+            self.height
+        } else {
+            frame_height.max(1)
+        };
+        match self.format {
+            OutputFormat::Y4m => (width.next_multiple_of(2), height.next_multiple_of(2)),
+            OutputFormat::RawRgba => (width, height),
+        }
+    }
+}
+
+/// 原始 RGBA 输出的说明：尺寸、帧率、像素格式和对应的 ffmpeg 输入参数
+pub fn raw_header(width: u32, height: u32, fps: u32) -> String {
+    format!(
+        "# Mira 原始帧输出：RGBA 顺序，非预乘透明度\n\
+         pixel_format=rgba\n\
+         width={width}\n\
+         height={height}\n\
+         fps={fps}\n\
+         frame_bytes={}\n\
+         ffmpeg=-f rawvideo -pix_fmt rgba -video_size {width}x{height} -framerate {fps}\n",
+        width as u64 * height as u64 * 4
+    )
+}
+
+/// 说明文件路径：输出路径加 .txt 后缀
+pub fn header_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".txt");
+    PathBuf::from(name)
+}
+
+/// 合成到黑色背景后的 BT.601 有限范围亮度
+fn luma(r: i32, g: i32, b: i32) -> u8 {
+    (((66 * r + 129 * g + 25 * b + 128) >> 8) + 16) as u8
+}
+
+fn chroma(r: i32, g: i32, b: i32) -> (u8, u8) {
+    let u = ((-38 * r - 74 * g + 112 * b + 128) >> 8) + 128;
+    let v = ((112 * r - 94 * g - 18 * b + 128) >> 8) + 128;
+    (u as u8, v as u8)
+}
+
+/// 把 RGBA 画面转换为 4:2:0 的 Y、U、V 平面（宽高须为偶数），透明区域合成到黑色背景
+pub fn rgba_to_yuv420(rgba: &[u8], width: u32, height: u32, out: &mut Vec<u8>) {
+    let (width, height) = (width as usize, height as usize);
+    // 预乘透明度即合成到黑色背景
+    let pixel = |x: usize, y: usize| {
+        let p = &rgba[(y * width + x) * 4..][..4];
+        let a = p[3] as i32;
+        [0, 1, 2].map(|c| (p[c] as i32 * a + 127) / 255)
+    };
+
+    out.clear();
+    out.reserve(width * height * 3 / 2);
+    for y in 0..height {
+        for x in 0..width {
+            let [r, g, b] = pixel(x, y);
+            out.push(luma(r, g, b));
+        }
+    }
+    let mut v_plane = Vec::with_capacity(width * height / 4);
+    for y in (0..height).step_by(2) {
+        for x in (0..width).step_by(2) {
+            // 2x2 块取平均
+            let mut sum = [0; 3];
+            for (dx, dy) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+                let rgb = pixel(x + dx, y + dy);
+                for c in 0..3 {
+                    sum[c] += rgb[c];
+                }
+            }
+            let (u, v) = chroma((sum[0] + 2) / 4, (sum[1] + 2) / 4, (sum[2] + 2) / 4);
+            out.push(u);
+            v_plane.push(v);
+        }
+    }
+    out.extend_from_slice(&v_plane);
+}
+
+/// 帧编码器：按输出格式写入画面，Y4M 在第一帧前写入流头
+pub struct FrameEncoder<W: Write> {
+    writer: W,
+    format: OutputFormat,
+    width: u32,
+    height: u32,
+    fps: u32,
+    header_written: bool,
+    yuv: Vec<u8>,
+}
+
+impl<W: Write> FrameEncoder<W> {
+    pub fn new(writer: W, format: OutputFormat, width: u32, height: u32, fps: u32) -> Self {
+        Self {
+            writer,
+            format,
+            width,
+            height,
+            fps,
+            header_written: false,
+            yuv: Vec::new(),
+        }
+    }
+
+    /// 写入一帧 RGBA 画面，尺寸须与输出尺寸一致
+    pub fn write_frame(&mut self, rgba: &[u8]) -> std::io::Result<()> {
+        match self.format {
+            OutputFormat::Y4m => {
+                if !self.header_written {
+                    writeln!(
+                        self.writer,
+                        "YUV4MPEG2 W{} H{} F{}:1 Ip A1:1 C420jpeg XYSCSS=420JPEG XCOLORRANGE=LIMITED",
+                        self.width, self.height, self.fps
+                    )?;
+                    self.header_written = true;
+                }
+                rgba_to_yuv420(rgba, self.width, self.height, &mut self.yuv);
+                self.writer.write_all(b"FRAME\n")?;
+                self.writer.write_all(&self.yuv)?;
+            }
+            OutputFormat::RawRgba => self.writer.write_all(rgba)?,
+        }
+        self.writer.flush()
+    }
+}
+
+/// 主线程与输出线程共享的状态
+#[derive(Default)]
+struct Shared {
+    // 最新一帧，输出线程取走前被新帧替换
+    latest: Option<CapturedFrame>,
+    stopping: bool,
+    dropped: u64,
+}
+
+/// 帧输出流：主线程节流回读并提交画面，后台线程按固定帧率写出
+pub struct FrameOutput {
+    shared: Arc<(Mutex<Shared>, Condvar)>,
+    running: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+    throttle: CaptureThrottle,
+}
+
+impl FrameOutput {
+    /// 启动输出线程；打开输出目标在线程中进行，失败时 is_running 变为 false
+    pub fn start(settings: FrameOutputSettings, now: Instant) -> Result<Self, String> {
+        let shared = Arc::new((Mutex::new(Shared::default()), Condvar::new()));
+        let running = Arc::new(AtomicBool::new(true));
+        let throttle = CaptureThrottle::new(settings.fps, now);
+        let handle = {
+            let shared = Arc::clone(&shared);
+            let running = Arc::clone(&running);
+            std::thread::Builder::new()
+                .name("frame-output".to_string())
+                .spawn(move || {
+                    info!(
+                        "帧输出流启动: {}，{:?}，{} fps",
+                        settings.format.name(),
+                        settings.target,
+                        settings.fps
+                    );
+                    match write_frames(&settings, &shared) {
+                        Ok(written) => info!("帧输出流已停止，共写入 {} 帧", written),
+                        Err(e) if e.kind() == ErrorKind::BrokenPipe => {
+                            info!("帧输出的读取端已关闭，输出停止")
+                        }
+                        Err(e) => error!("帧输出失败: {}", e),
+                    }
+                    let dropped = shared.0.lock().map_or(0, |shared| shared.dropped);
+                    if dropped > 0 {
+                        info!("帧输出期间丢弃了 {} 帧", dropped);
+                    }
+                    running.store(false, Ordering::Release);
+                })
+                .map_err(|e| format!("启动帧输出线程失败: {}", e))?
+        };
+        Ok(Self {
+            shared,
+            running,
+            handle: Some(handle),
+            throttle,
+        })
+    }
+
+    /// 输出线程是否仍在运行（读取端关闭或写入失败后停止）
+    pub fn is_running(&self) -> bool {
+        self.running.load(Ordering::Acquire)
+    }
+
+    /// 本帧是否需要回读画面，按输出帧率节流
+    pub fn wants_frame(&mut self, now: Instant) -> bool {
+        self.is_running() && self.throttle.ready(now)
+    }
+
+    /// 提交回读完成的画面；输出线程尚未取走上一帧时上一帧被丢弃
+    pub fn push_frame(&self, frame: CapturedFrame) {
+        let (lock, condvar) = &*self.shared;
+        if let Ok(mut shared) = lock.lock() {
+            if shared.latest.replace(frame).is_some() {
+                shared.dropped += 1;
+            }
+            condvar.notify_one();
+        }
+    }
+}
+
+impl Drop for FrameOutput {
+    fn drop(&mut self) {
+        let (lock, condvar) = &*self.shared;
+        if let Ok(mut shared) = lock.lock() {
+            shared.stopping = true;
+            condvar.notify_one();
+        }
+        // 等待命名管道的读取方或阻塞在写入时线程无法及时结束，等待片刻后不再等待，避免退出时卡住
+        if let Some(handle) = self.handle.take() {
+            let deadline = Instant::now() + STOP_TIMEOUT;
+            while !handle.is_finished() && Instant::now() < deadline {
+                std::thread::sleep(Duration::from_millis(5));
+            }
+            if handle.is_finished() {
+                let _ = handle.join();
+            } else {
+                warn!("帧输出线程仍阻塞在输出目标上，不再等待");
+            }
+        }
+    }
+}
+
+fn poisoned<T>(_: PoisonError<T>) -> std::io::Error {
+    std::io::Error::other("帧输出状态锁已损坏")
+}
+
+fn open_target(target: &OutputTarget) -> std::io::Result<Box<dyn Write + Send>> {
+    Ok(match target {
+        OutputTarget::Stdout => Box::new(BufWriter::new(std::io::stdout())),
+        // 命名管道在读取方打开前会阻塞
+        OutputTarget::Path(path) => Box::new(BufWriter::new(File::create(path)?)),
+    })
+}
+
+/// 输出线程主体，返回写入的帧数
+fn write_frames(
+    settings: &FrameOutputSettings,
+    shared: &(Mutex<Shared>, Condvar),
+) -> std::io::Result<u64> {
+    let (lock, condvar) = shared;
+
+    let writer = open_target(&settings.target)?;
+
+    // 等待第一帧确定输出尺寸
+    let first = {
+        let mut state = lock.lock().map_err(poisoned)?;
+        loop {
+            if state.stopping {
+                return Ok(0);
+            }
+            if let Some(frame) = state.latest.take() {
+                break frame;
+            }
+            state = condvar.wait(state).map_err(poisoned)?;
+        }
+    };
+    let (width, height) = settings.output_size(first.width, first.height);
+    if settings.format == OutputFormat::RawRgba {
+        let header = raw_header(width, height, settings.fps);
+        match &settings.target {
+            OutputTarget::Stdout => info!("原始帧输出格式:\n{}", header),
+            OutputTarget::Path(path) => {
+                if let Err(e) = std::fs::write(header_path(path), &header) {
+                    warn!("写入帧输出说明文件失败: {}", e);
+                }
+            }
+        }
+    }
+
+    let mut encoder = FrameEncoder::new(writer, settings.format, width, height, settings.fps);
+    let interval = Duration::from_secs_f64(1.0 / settings.fps.max(1) as f64);
+    let mut current = fit_frame(&first, width, height);
+    let mut next = Instant::now();
+    let mut written = 0;
+    loop {
+        encoder.write_frame(&current)?;
+        written += 1;
+        next += interval;
+
+        let mut state = lock.lock().map_err(poisoned)?;
+        // 写入阻塞超过一帧时跳过错过的输出帧，不补写
+        let now = Instant::now();
+        if now > next + interval {
+            let missed = (now - next).as_secs_f64() / interval.as_secs_f64();
+            state.dropped += missed as u64;
+            next = now;
+        }
+        // 等到下一帧的输出时间，期间到达的画面只保留最新一帧
+        loop {
+            if state.stopping {
+                return Ok(written);
+            }
+            let now = Instant::now();
+            if now >= next {
+                break;
+            }
+            state = condvar.wait_timeout(state, next - now).map_err(poisoned)?.0;
+        }
+        if let Some(frame) = state.latest.take() {
+            drop(state);
+            current = fit_frame(&frame, width, height);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::capture::CaptureSource;
+
+    fn solid_frame(width: u32, height: u32, color: [u8; 4]) -> CapturedFrame {
+        CapturedFrame {
+            source: CaptureSource::Composited,
+            width,
+            height,
+            rgba: color.repeat((width * height) as usize),
+            captured_at: Instant::now(),
+        }
+    }
+
+    #[test]
+    fn test_output_settings() {
+        assert_eq!(OutputFormat::from_name("Y4m"), Some(OutputFormat::Y4m));
+        assert_eq!(
+            OutputFormat::from_name("RawRgba"),
+            Some(OutputFormat::RawRgba)
+        );
+        assert_eq!(OutputFormat::from_name("Mp4"), None);
+        assert_eq!(OutputTarget::from_config("-"), OutputTarget::Stdout);
+        assert_eq!(OutputTarget::from_config(""), OutputTarget::Stdout);
+        assert_eq!(
+            OutputTarget::from_config("/tmp/mira.fifo"),
+            OutputTarget::Path(PathBuf::from("/tmp/mira.fifo"))
+        );
+
+        let mut settings = FrameOutputSettings {
+            format: OutputFormat::Y4m,
+            target: OutputTarget::Stdout,
+            width: 0,
+            height: 0,
+            fps: 30,
+        };
+        // Y4M 宽高向上取偶数
+        assert_eq!(settings.output_size(401, 300), (402, 300));
+        settings.format = OutputFormat::RawRgba;
+        assert_eq!(settings.output_size(401, 300), (401, 300));
+        settings.width = 640;
+        settings.height = 480;
+        assert_eq!(settings.output_size(401, 300), (640, 480));
+
+        assert_eq!(
+            header_path(Path::new("/tmp/out.rgba")),
+            PathBuf::from("/tmp/out.rgba.txt")
+        );
+        assert!(raw_header(4, 2, 30).contains("frame_bytes=32\n"));
+    }
+
+    #[test]
+    fn test_rgba_to_yuv420() {
+        let mut yuv = Vec::new();
+        // 白、红、黑、透明（合成到黑色）
+        let rgba = [
+            [255, 255, 255, 255],
+            [255, 0, 0, 255],
+            [0, 0, 0, 255],
+            [255, 255, 255, 0],
+        ]
+        .concat();
+        rgba_to_yuv420(&rgba, 2, 2, &mut yuv);
+        assert_eq!(yuv.len(), 6);
+        assert_eq!(&yuv[..4], &[235, 82, 16, 16]);
+
+        let mut yuv = Vec::new();
+        rgba_to_yuv420(&[255, 0, 0, 255].repeat(4), 2, 2, &mut yuv);
+        assert_eq!(&yuv[4..], &[90, 240]);
+        rgba_to_yuv420(&[128, 128, 128, 255].repeat(4), 2, 2, &mut yuv);
+        assert_eq!(&yuv[4..], &[128, 128]);
+    }
+
+    #[test]
+    fn test_y4m_encoder_to_file() {
+        let mut file = tempfile::tempfile().unwrap();
+        let mut encoder = FrameEncoder::new(&mut file, OutputFormat::Y4m, 4, 2, 25);
+        encoder
+            .write_frame(&[255, 255, 255, 255].repeat(8))
+            .unwrap();
+        encoder.write_frame(&[0, 0, 0, 0].repeat(8)).unwrap();
+        drop(encoder);
+
+        let data = {
+            use std::io::{Read, Seek};
+            let mut data = Vec::new();
+            file.rewind().unwrap();
+            file.read_to_end(&mut data).unwrap();
+            data
+        };
+        let header = b"YUV4MPEG2 W4 H2 F25:1 Ip A1:1 C420jpeg XYSCSS=420JPEG XCOLORRANGE=LIMITED\n";
+        assert!(data.starts_with(header));
+        let frame_len = b"FRAME\n".len() + 4 * 2 * 3 / 2;
+        assert_eq!(data.len(), header.len() + 2 * frame_len);
+        let first = &data[header.len()..header.len() + frame_len];
+        assert_eq!(&first[..6], b"FRAME\n");
+        assert_eq!(&first[6..14], &[235; 8]);
+        assert_eq!(&first[14..], &[128; 4]);
+        assert_eq!(&data[data.len() - 12..data.len() - 4], &[16; 8]);
+    }
+
+    #[test]
+    fn test_raw_output_to_file() {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("out.rgba");
+        let settings = FrameOutputSettings {
+            format: OutputFormat::RawRgba,
+            target: OutputTarget::Path(path.clone()),
+            width: 4,
+            height: 4,
+            fps: 100,
+        };
+        let now = Instant::now();
+        let mut output = FrameOutput::start(settings, now).unwrap();
+        assert!(output.wants_frame(now));
+        assert!(!output.wants_frame(now + Duration::from_millis(5)));
+        // 画面尺寸与输出不同时等比缩放
+        output.push_frame(solid_frame(2, 4, [255, 0, 0, 255]));
+        std::thread::sleep(Duration::from_millis(100));
+        assert!(output.is_running());
+        drop(output);
+
+        // 没有新画面时按帧率重复上一帧
+        let data = std::fs::read(&path).unwrap();
+        let frame_bytes = 4 * 4 * 4;
+        assert_eq!(data.len() % frame_bytes, 0);
+        assert!(data.len() / frame_bytes >= 2);
+        assert_eq!(&data[..4], &[0, 0, 0, 0]);
+        assert_eq!(&data[4..8], &[255, 0, 0, 255]);
+
+        let header = std::fs::read_to_string(header_path(&path)).unwrap();
+        assert!(header.contains("width=4\nheight=4\nfps=100\n"));
+    }
+
+    #[test]
+    fn test_output_stops_when_target_fails() {
+        let directory = tempfile::tempdir().unwrap();
+        let settings = FrameOutputSettings {
+            format: OutputFormat::Y4m,
+            target: OutputTarget::Path(directory.path().join("missing").join("out.y4m")),
+            width: 0,
+            height: 0,
+            fps: 30,
+        };
+        let mut output = FrameOutput::start(settings, Instant::now()).unwrap();
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while output.is_running() && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        assert!(!output.is_running());
+        assert!(!output.wants_frame(Instant::now()));
+    }
+}
@@ -4,11 +4,17 @@ pub mod camera;
 pub mod config;
 pub mod error;
 pub mod event;
+pub mod frame_output;
 pub mod logging;
 pub mod memory;
+pub mod overlay;
 pub mod performance;
+pub mod preview_server;
+pub mod recording;
 pub mod render;
+pub mod scheduler;
 pub mod shape;
+pub mod snapshot;
 pub mod tray;
 pub mod ui;
 pub mod window;
@@ -99,7 +99,7 @@ pub fn init_logging_with_config(config: LoggingConfig) -> Result<LoggingConfig,
             ))
         })
         .level(config.level)
-        .chain(std::io::stdout()); // 输出到控制台
+        .chain(std::io::stderr()); // 输出到控制台（stderr，stdout 留给帧输出流）
     
     // 添加文件输出
     dispatch = dispatch.chain(fern::log_file(&config.log_file)?);
@@ -5,11 +5,17 @@ mod camera;
 mod config;
 mod error;
 mod event;
+mod frame_output;
 mod logging;
 mod memory;
+mod overlay;
 mod performance;
+mod preview_server;
+mod recording;
 mod render;
+mod scheduler;
 mod shape;
+mod snapshot;
 mod tray;
 mod ui;
 mod window;
@@ -20,25 +26,34 @@ use event::EventHandler;
 use logging::LoggingConfig;
 use memory::MemoryMonitor;
 use performance::{PerformanceMonitor, PerformanceThresholds};
+use render::adapter::{backends_from_name, power_preference_from_name, present_mode_from_name, GpuSettings};
+use render::chroma::ChromaKey;
+use render::color::{ColorAdjustments, ColorPreset, CubeLut};
+use render::auto_correct::AutoCorrectSettings;
+use render::denoise::DenoiseSettings;
+use render::effects::load_effect;
+use overlay::{FontRasterizer, Sticker, StickerLayer, TextAnchor, TextOverlay, TextRenderer, TextSource};
 use render::RenderEngine;
-use shape::{ShapeMask, ShapeType};
+use scheduler::{FrameScheduler, Schedule};
+use shape::{MaskQuality, ShapeMask, ShapeType};
 use tray::{TrayManager, TrayMenuAction};
 use window::WindowManager;
 
 use log::{debug, error, info, warn};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 use winit::{
     event::{Event, WindowEvent},
-    event_loop::EventLoop,
+    event_loop::{ControlFlow, EventLoop},
 };
 
 /// 应用程序主结构
 struct MiraApp {
     event_handler: EventHandler,
     tray_manager: Option<TrayManager>,
-    last_frame_time: Instant,
-    target_frame_duration: Duration,
+    
+    // 帧调度：新帧到达、界面变化或动画时按目标帧率渲染
+    scheduler: FrameScheduler,
     
     // 性能监控
     performance_monitor: PerformanceMonitor,
@@ -153,6 +168,14 @@ impl MiraApp {
             warn!("未找到任何摄像头设备，应用将以演示模式运行");
         }
 
+        // 捕获线程收到新帧时唤醒事件循环（部分平台的 EventLoopProxy 不是 Sync）
+        let proxy = Mutex::new(event_loop.create_proxy());
+        camera_manager.set_frame_notifier(Arc::new(move || {
+            if let Ok(proxy) = proxy.lock() {
+                let _ = proxy.send_event(());
+            }
+        }));
+
         // 3. 初始化窗口管理器（使用配置中的位置和尺寸）
         info!("初始化窗口管理器...");
         let mut window_manager = WindowManager::new(&event_loop)
@@ -165,15 +188,32 @@ impl MiraApp {
         // 应用配置中的窗口设置
         window_manager.set_position(config.window.position_x, config.window.position_y);
         window_manager.set_size(config.window.width, config.window.height);
+        window_manager.set_snap_tolerances(config.window.rotation_snap_tolerance, config.window.video_snap_tolerance);
         window_manager.set_rotation(config.window.rotation);
+        window_manager.set_video_rotation(config.window.video_rotation);
+        window_manager.set_keep_video_upright(config.window.keep_video_upright);
         
-        info!("窗口管理器创建成功，位置: ({}, {}), 尺寸: {}x{}, 旋转: {:.1}°",
+        info!("窗口管理器创建成功，位置: ({}, {}), 尺寸: {}x{}, 旋转: {:.1}°, 视频旋转: {:.1}°",
               config.window.position_x, config.window.position_y,
-              config.window.width, config.window.height, config.window.rotation);
+              config.window.width, config.window.height, config.window.rotation,
+              config.window.video_rotation);
 
         // 4. 初始化渲染引擎
         info!("初始化渲染引擎...");
-        let render_engine = RenderEngine::new(window_manager.window()).await
+        let gpu = &config.render.gpu;
+        let gpu_settings = GpuSettings {
+            power_preference: power_preference_from_name(&gpu.power_preference).unwrap_or_else(|| {
+                warn!("未知的电源偏好 '{}'，使用低功耗", gpu.power_preference);
+                wgpu::PowerPreference::LowPower
+            }),
+            backends: backends_from_name(&gpu.backend).unwrap_or_else(|| {
+                warn!("未知的图形后端 '{}'，自动选择", gpu.backend);
+                wgpu::Backends::all()
+            }),
+            present_mode: present_mode_from_name(&gpu.present_mode),
+            force_fallback_adapter: gpu.force_fallback_adapter,
+        };
+        let mut render_engine = RenderEngine::with_gpu_settings(window_manager.window(), gpu_settings).await
             .map_err(|e| {
                 let error_msg = format!("渲染引擎创建失败: {}", e);
                 error!("{}", error_msg);
@@ -182,6 +222,144 @@ impl MiraApp {
         
         info!("渲染引擎创建成功");
 
+        // 应用配置中的颜色调整与滤镜
+        let color = &config.render.color;
+        render_engine.set_color_adjustments(ColorAdjustments {
+            brightness: color.brightness,
+            contrast: color.contrast,
+            saturation: color.saturation,
+            gamma: color.gamma,
+            hue_shift: color.hue_shift,
+            temperature: color.temperature,
+        });
+        render_engine.set_color_preset(ColorPreset::from_name(&color.preset).unwrap_or_else(|| {
+            warn!("未知的颜色预设 '{}'，使用原色", color.preset);
+            ColorPreset::default()
+        }));
+        if !color.lut_path.is_empty() {
+            match CubeLut::load(std::path::Path::new(&color.lut_path)) {
+                Ok(lut) => {
+                    render_engine.load_lut(lut);
+                    render_engine.set_lut_enabled(color.lut_enabled);
+                }
+                Err(e) => warn!("{}，将不使用 LUT 滤镜", e),
+            }
+        }
+        
+        // 应用配置中的绿幕抠像设置
+        let chroma_key = &config.render.chroma_key;
+        render_engine.set_chroma_key(ChromaKey {
+            enabled: chroma_key.enabled,
+            key_color: chroma_key.key_color.map(|c| c as f32 / 255.0),
+            similarity: chroma_key.similarity,
+            smoothness: chroma_key.smoothness,
+            spill: chroma_key.spill,
+        });
+        
+        // 应用配置中的降噪设置
+        let denoise = &config.render.denoise;
+        render_engine.set_denoise_settings(DenoiseSettings {
+            smoothing_enabled: denoise.smoothing_enabled,
+            smoothing_strength: denoise.smoothing_strength,
+            temporal_enabled: denoise.temporal_enabled,
+            temporal_strength: denoise.temporal_strength,
+            motion_threshold: denoise.motion_threshold,
+            bypass: false,
+        });
+        
+        // 应用配置中的自动校正设置
+        let auto_correct = &config.render.auto_correct;
+        render_engine.set_auto_correct_settings(AutoCorrectSettings {
+            white_balance: auto_correct.white_balance,
+            auto_levels: auto_correct.auto_levels,
+            low_light_boost: auto_correct.low_light_boost,
+            smoothing: auto_correct.smoothing,
+        });
+        
+        // 加载字体（文字叠加与右键菜单共用同一份）
+        let text_overlay = &config.render.text_overlay;
+        let font_path = (!text_overlay.font_path.is_empty()).then(|| std::path::Path::new(&text_overlay.font_path));
+        let fonts = match FontRasterizer::load(font_path) {
+            Ok(fonts) => Some(Arc::new(fonts)),
+            Err(e) => {
+                warn!("{}，文字叠加和右键菜单将不显示", e);
+                None
+            }
+        };
+
+        // 应用配置中的文字叠加
+        let overlays: Vec<TextOverlay> = text_overlay.items.iter()
+            .map(|item| TextOverlay {
+                source: TextSource::from_config(&item.source, &item.content)
+                    .unwrap_or_else(|| TextSource::Static(item.content.clone())),
+                font_size: item.font_size,
+                color: item.color,
+                background: item.background.then_some(item.background_color),
+                anchor: TextAnchor::from_name(&item.anchor).unwrap_or(TextAnchor::Bottom),
+                margin: item.margin,
+            })
+            .collect();
+        if let Some(fonts) = &fonts {
+            render_engine.set_text_renderer(TextRenderer::new(Box::new(fonts.clone())));
+        }
+        render_engine.set_text_overlays(overlays);
+        render_engine.set_overlay_visible(text_overlay.enabled);
+
+        // 应用配置中的贴纸（图片在后台解码）
+        let stickers: Vec<Sticker> = config.render.stickers.items.iter()
+            .filter(|item| !item.path.is_empty())
+            .map(|item| Sticker {
+                path: item.path.clone().into(),
+                position: item.position,
+                scale: item.scale,
+                rotation: item.rotation,
+                layer: StickerLayer::from_name(&item.layer).unwrap_or_default(),
+                frame_delay: (item.frame_delay_ms > 0).then(|| Duration::from_millis(item.frame_delay_ms as u64)),
+            })
+            .collect();
+        render_engine.set_stickers(stickers);
+        render_engine.set_stickers_visible(config.render.stickers.enabled);
+
+        // 加载配置中的特效链，加载或编译失败的特效只记录并跳过
+        let effects_config = &config.render.effects;
+        let effects_dir = if effects_config.directory.is_empty() {
+            config_manager.config_path()
+                .parent()
+                .map(|dir| dir.join("effects"))
+                .unwrap_or_else(|| "effects".into())
+        } else {
+            effects_config.directory.clone().into()
+        };
+        let effect_passes: Vec<_> = effects_config.passes.iter()
+            .filter_map(|pass_config| {
+                let mut pass = match load_effect(&effects_dir.join(&pass_config.name)) {
+                    Ok(pass) => pass,
+                    Err(e) => {
+                        warn!("跳过特效 {}: {}", pass_config.name, e);
+                        return None;
+                    }
+                };
+                for (name, value) in &pass_config.params {
+                    if !pass.set_param(name, *value) {
+                        warn!("特效 {} 没有参数 {}", pass_config.name, name);
+                    }
+                }
+                pass.enabled = pass_config.enabled;
+                Some(pass)
+            })
+            .collect();
+        if !effect_passes.is_empty() {
+            render_engine.set_effects(effect_passes);
+        }
+        render_engine.set_effects_enabled(effects_config.enabled);
+
+        // 应用配置中的标注画笔
+        let annotation = &config.render.annotation;
+        let annotations = render_engine.annotations_mut();
+        annotations.set_color(annotation.color);
+        annotations.set_width(annotation.width);
+        annotations.set_fade_after(annotation.auto_fade.then(|| Duration::from_secs_f32(annotation.fade_seconds)));
+
         // 5. 初始化形状遮罩
         info!("初始化形状遮罩系统...");
         let shape_type = match config.window.shape.as_str() {
@@ -196,13 +374,21 @@ impl MiraApp {
             }
         };
         
-        let shape_mask = ShapeMask::new(shape_type, config.window.width, config.window.height);
-        info!("形状遮罩创建成功，类型: {:?}, 尺寸: {}x{}", 
-              shape_type, config.window.width, config.window.height);
+        let mask_quality = MaskQuality::from_name(&config.render.mask_quality).unwrap_or_else(|| {
+            warn!("未知的遮罩质量 '{}'，使用默认均衡质量", config.render.mask_quality);
+            MaskQuality::default()
+        });
+        
+        let mut shape_mask = ShapeMask::with_quality(shape_type, config.window.width, config.window.height, mask_quality);
+        let mask_cache_budget = config.render.mask_cache_budget_mb as usize * 1024 * 1024;
+        shape_mask.set_cache_budget(mask_cache_budget);
+        render_engine.set_mask_cache_budget(mask_cache_budget);
+        info!("形状遮罩创建成功，类型: {:?}, 尺寸: {}x{}, 质量: {:?}", 
+              shape_type, config.window.width, config.window.height, mask_quality);
 
         // 6. 创建事件处理器
         info!("创建事件处理器...");
-        let event_handler = EventHandler::new(
+        let mut event_handler = EventHandler::new(
             window_manager,
             camera_manager,
             render_engine,
@@ -210,8 +396,12 @@ impl MiraApp {
             config_manager,
         );
 
-        // 注意：菜单渲染器已禁用，使用系统托盘菜单代替
-        info!("使用系统托盘菜单（右键点击托盘图标显示菜单）");
+        // 窗口内右键菜单由 GPU 渲染，系统托盘菜单同时保留
+        if let Some(fonts) = fonts {
+            if let Err(e) = event_handler.init_menu_renderer(Box::new(fonts)) {
+                warn!("菜单渲染器初始化失败: {}", e);
+            }
+        }
 
         let initialization_time = start_time.elapsed();
         info!("Mira 应用程序初始化完成，耗时: {:.2}秒", initialization_time.as_secs_f32());
@@ -230,6 +420,11 @@ impl MiraApp {
             max_render_time_ms: 16.0,
         };
         
+        // 降噪等滤镜在渲染耗时持续超出该预算时自动降级
+        event_handler.render_engine_mut().set_render_time_budget(
+            Duration::from_secs_f32(performance_thresholds.max_render_time_ms / 1000.0),
+        );
+        
         let performance_monitor = PerformanceMonitor::new(
             300,                          // 保留 5 分钟的历史记录（假设 60 FPS）
             Duration::from_secs(10),      // 每 10 秒报告一次性能统计
@@ -248,6 +443,19 @@ impl MiraApp {
         let tray_manager = match TrayManager::new() {
             Ok(tray) => {
                 info!("系统托盘图标初始化成功");
+                tray.set_keep_video_upright(event_handler.window_manager().keep_video_upright());
+                tray.set_lut_enabled(event_handler.render_engine().lut_enabled());
+                tray.set_chroma_key_enabled(event_handler.render_engine().chroma_key().enabled);
+                tray.set_effects_enabled(event_handler.render_engine().has_effects() && event_handler.render_engine().effects_enabled());
+                let denoise = event_handler.render_engine().denoise_settings();
+                tray.set_denoise_state(denoise.smoothing_enabled, denoise.temporal_enabled, denoise.bypass);
+                let auto_correct = event_handler.render_engine().auto_correct_settings();
+                tray.set_auto_correct_state(auto_correct.white_balance, auto_correct.auto_levels, auto_correct.low_light_boost);
+                let render_engine = event_handler.render_engine();
+                tray.set_text_overlay_visible(render_engine.has_text_overlays() && render_engine.overlay_visible());
+                tray.set_stickers_visible(render_engine.has_stickers() && render_engine.stickers_visible());
+                tray.set_annotation_mode(event_handler.annotation_mode());
+                tray.set_snapshot_raw_frame(event_handler.snapshot_raw_frame());
                 Some(tray)
             }
             Err(e) => {
@@ -259,8 +467,7 @@ impl MiraApp {
         Ok(Self {
             event_handler,
             tray_manager,
-            last_frame_time: Instant::now(),
-            target_frame_duration: Duration::from_millis(50), // 20 FPS（降低以减少渲染开销，优先拖拽流畅度）
+            scheduler: FrameScheduler::new(config.render.target_fps),
             
             // 性能监控初始化
             performance_monitor,
@@ -308,6 +515,7 @@ impl MiraApp {
         if let Some(tray_manager) = &self.tray_manager {
             if let Some(action) = tray_manager.handle_menu_event() {
                 info!("处理托盘菜单动作: {:?}", action);
+                self.scheduler.invalidate();
                 
                 match action {
                     TrayMenuAction::ShapeCircle => {
@@ -336,6 +544,7 @@ impl MiraApp {
                     }
                     TrayMenuAction::ResetRotation => {
                         self.event_handler.window_manager_mut().set_rotation(0.0);
+                        self.event_handler.window_manager_mut().set_video_rotation(0.0);
                         info!("重置窗口旋转");
                     }
                     TrayMenuAction::ResetSize => {
@@ -354,10 +563,105 @@ impl MiraApp {
                         let new_rotation = self.event_handler.window_manager().rotation();
                         info!("顺时针旋转 15°: {:.1}° -> {:.1}°", old_rotation, new_rotation);
                     }
+                    TrayMenuAction::RotateVideoLeft => {
+                        let old_rotation = self.event_handler.window_manager().video_rotation();
+                        self.event_handler.window_manager_mut().rotate_video(-15.0);
+                        let new_rotation = self.event_handler.window_manager().video_rotation();
+                        info!("视频逆时针旋转 15°: {:.1}° -> {:.1}°", old_rotation, new_rotation);
+                    }
+                    TrayMenuAction::RotateVideoRight => {
+                        let old_rotation = self.event_handler.window_manager().video_rotation();
+                        self.event_handler.window_manager_mut().rotate_video(15.0);
+                        let new_rotation = self.event_handler.window_manager().video_rotation();
+                        info!("视频顺时针旋转 15°: {:.1}° -> {:.1}°", old_rotation, new_rotation);
+                    }
+                    TrayMenuAction::ToggleKeepVideoUpright => {
+                        self.event_handler.toggle_keep_video_upright();
+                        tray_manager.set_keep_video_upright(self.event_handler.window_manager().keep_video_upright());
+                    }
+                    TrayMenuAction::MaskQuality(quality) => {
+                        self.event_handler.set_mask_quality(quality);
+                    }
+                    TrayMenuAction::ColorPreset(preset) => {
+                        self.event_handler.set_color_preset(preset);
+                    }
+                    TrayMenuAction::ToggleLut => {
+                        self.event_handler.toggle_lut();
+                        tray_manager.set_lut_enabled(self.event_handler.render_engine().lut_enabled());
+                    }
+                    TrayMenuAction::ToggleSmoothing => {
+                        self.event_handler.update_denoise_settings(|settings| settings.smoothing_enabled = !settings.smoothing_enabled);
+                        let denoise = self.event_handler.render_engine().denoise_settings();
+                        tray_manager.set_denoise_state(denoise.smoothing_enabled, denoise.temporal_enabled, denoise.bypass);
+                    }
+                    TrayMenuAction::ToggleTemporalDenoise => {
+                        self.event_handler.update_denoise_settings(|settings| settings.temporal_enabled = !settings.temporal_enabled);
+                        let denoise = self.event_handler.render_engine().denoise_settings();
+                        tray_manager.set_denoise_state(denoise.smoothing_enabled, denoise.temporal_enabled, denoise.bypass);
+                    }
+                    TrayMenuAction::ToggleDenoiseBypass => {
+                        self.event_handler.toggle_denoise_bypass();
+                        let denoise = self.event_handler.render_engine().denoise_settings();
+                        tray_manager.set_denoise_state(denoise.smoothing_enabled, denoise.temporal_enabled, denoise.bypass);
+                    }
+                    TrayMenuAction::ToggleAutoWhiteBalance => {
+                        self.event_handler.update_auto_correct_settings(|settings| settings.white_balance = !settings.white_balance);
+                        let auto_correct = self.event_handler.render_engine().auto_correct_settings();
+                        tray_manager.set_auto_correct_state(auto_correct.white_balance, auto_correct.auto_levels, auto_correct.low_light_boost);
+                    }
+                    TrayMenuAction::ToggleAutoLevels => {
+                        self.event_handler.update_auto_correct_settings(|settings| settings.auto_levels = !settings.auto_levels);
+                        let auto_correct = self.event_handler.render_engine().auto_correct_settings();
+                        tray_manager.set_auto_correct_state(auto_correct.white_balance, auto_correct.auto_levels, auto_correct.low_light_boost);
+                    }
+                    TrayMenuAction::ToggleLowLightBoost => {
+                        self.event_handler.update_auto_correct_settings(|settings| settings.low_light_boost = !settings.low_light_boost);
+                        let auto_correct = self.event_handler.render_engine().auto_correct_settings();
+                        tray_manager.set_auto_correct_state(auto_correct.white_balance, auto_correct.auto_levels, auto_correct.low_light_boost);
+                    }
+                    TrayMenuAction::ToggleTextOverlay => {
+                        self.event_handler.toggle_text_overlay();
+                        let render_engine = self.event_handler.render_engine();
+                        tray_manager.set_text_overlay_visible(render_engine.has_text_overlays() && render_engine.overlay_visible());
+                    }
+                    TrayMenuAction::ToggleStickers => {
+                        self.event_handler.toggle_stickers();
+                        let render_engine = self.event_handler.render_engine();
+                        tray_manager.set_stickers_visible(render_engine.has_stickers() && render_engine.stickers_visible());
+                    }
+                    TrayMenuAction::ToggleAnnotationMode => {
+                        self.event_handler.toggle_annotation_mode();
+                        tray_manager.set_annotation_mode(self.event_handler.annotation_mode());
+                    }
+                    TrayMenuAction::ClearAnnotations => {
+                        self.event_handler.clear_annotations();
+                    }
+                    TrayMenuAction::TakeSnapshot => {
+                        self.event_handler.take_snapshot();
+                    }
+                    TrayMenuAction::ToggleSnapshotRawFrame => {
+                        self.event_handler.toggle_snapshot_raw_frame();
+                        tray_manager.set_snapshot_raw_frame(self.event_handler.snapshot_raw_frame());
+                    }
+                    TrayMenuAction::ToggleRecording => {
+                        self.event_handler.toggle_recording();
+                        tray_manager.set_recording(self.event_handler.is_recording());
+                    }
+                    TrayMenuAction::ToggleChromaKey => {
+                        self.event_handler.toggle_chroma_key();
+                        tray_manager.set_chroma_key_enabled(self.event_handler.render_engine().chroma_key().enabled);
+                    }
+                    TrayMenuAction::ToggleEffects => {
+                        self.event_handler.toggle_effects();
+                        let render_engine = self.event_handler.render_engine();
+                        tray_manager.set_effects_enabled(render_engine.has_effects() && render_engine.effects_enabled());
+                    }
                     TrayMenuAction::ShowInfo => {
                         let window_size = self.event_handler.window_manager().size();
                         let window_position = self.event_handler.window_manager().position();
                         let rotation = self.event_handler.window_manager().rotation();
+                        let video_rotation = self.event_handler.window_manager().video_rotation();
+                        let keep_upright = self.event_handler.window_manager().keep_video_upright();
                         let current_device = self.event_handler.camera_manager().current_device()
                             .map(|d| d.name.as_str())
                             .unwrap_or("未知");
@@ -365,20 +669,28 @@ impl MiraApp {
                         // 在日志中显示
                         info!("=== 当前状态 ===");
                         info!("形状: {:?}", self.event_handler.shape_mask().shape_type());
+                        info!("遮罩质量: {}", self.event_handler.shape_mask().quality().display_name());
+                        info!("颜色滤镜: {}", self.event_handler.render_engine().color_preset().display_name());
                         info!("尺寸: {}x{}", window_size.width, window_size.height);
                         info!("位置: ({:.0}, {:.0})", window_position.x, window_position.y);
                         info!("旋转: {:.1}°", rotation);
+                        info!("视频旋转: {:.1}°{}", video_rotation,
+                              if keep_upright { "（保持正向）" } else { "" });
                         info!("摄像头: {}", current_device);
                         info!("================");
                         
                         // 在控制台也显示（如果有控制台窗口）
-                        println!("\n=== Mira 当前状态 ===");
-                        println!("形状: {:?}", self.event_handler.shape_mask().shape_type());
-                        println!("尺寸: {}x{}", window_size.width, window_size.height);
-                        println!("位置: ({:.0}, {:.0})", window_position.x, window_position.y);
-                        println!("旋转: {:.1}°", rotation);
-                        println!("摄像头: {}", current_device);
-                        println!("====================\n");
+                        eprintln!("\n=== Mira 当前状态 ===");
+                        eprintln!("形状: {:?}", self.event_handler.shape_mask().shape_type());
+                        eprintln!("遮罩质量: {}", self.event_handler.shape_mask().quality().display_name());
+                        eprintln!("颜色滤镜: {}", self.event_handler.render_engine().color_preset().display_name());
+                        eprintln!("尺寸: {}x{}", window_size.width, window_size.height);
+                        eprintln!("位置: ({:.0}, {:.0})", window_position.x, window_position.y);
+                        eprintln!("旋转: {:.1}°", rotation);
+                        eprintln!("视频旋转: {:.1}°{}", video_rotation,
+                                  if keep_upright { "（保持正向）" } else { "" });
+                        eprintln!("摄像头: {}", current_device);
+                        eprintln!("====================\n");
                     }
                     TrayMenuAction::Quit => {
                         info!("用户从托盘菜单请求退出");
@@ -391,30 +703,16 @@ impl MiraApp {
         }
     }
     
-    /// 切换形状（辅助方法，避免借用冲突）
+    /// 切换形状（由事件处理器更新遮罩，复用遮罩缓存）
     fn switch_shape(&mut self, shape_type: ShapeType) {
-        self.event_handler.shape_mask_mut().set_shape(shape_type);
-        // 创建新的遮罩来避免借用冲突
-        let width = self.event_handler.shape_mask().width();
-        let height = self.event_handler.shape_mask().height();
-        let new_mask = ShapeMask::new(shape_type, width, height);
-        if let Err(e) = self.event_handler.render_engine_mut().set_mask(&new_mask) {
-            error!("设置遮罩失败: {}", e);
-        }
+        self.event_handler.switch_shape(shape_type);
     }
 
     /// 渲染一帧
     fn render_frame(&mut self) -> Result<(), String> {
-        let _frame_start = Instant::now();
+        let frame_start = Instant::now();
         let now = Instant::now();
-        let frame_time = now.duration_since(self.last_frame_time);
-        
-        // 帧率控制：确保不超过目标帧率
-        if frame_time < self.target_frame_duration {
-            return Ok(());
-        }
-        
-        self.last_frame_time = now;
+        self.scheduler.begin_render(now);
         
         // 定期清理资源（降低频率）
         if now.duration_since(self.last_cleanup) >= self.cleanup_interval {
@@ -425,48 +723,53 @@ impl MiraApp {
         // 调用事件处理器的渲染方法
         let render_result = self.event_handler.render_frame();
         
-        // 简化性能监控（仅在 debug 模式下）
-        #[cfg(debug_assertions)]
-        {
-            let render_time = frame_start.elapsed();
-            let total_frame_time = frame_start.elapsed();
-            
-            // 更新性能监控
-            if let Some(alert) = self.performance_monitor.record_frame(total_frame_time, render_time) {
-                match alert.severity() {
-                    AlertSeverity::Critical => {
-                        error!("性能严重警告: {}", alert.message());
-                        
-                        // 对于严重的性能问题，尝试优化措施
-                        match alert {
-                            performance::PerformanceAlert::LowFps { .. } => {
-                                warn!("FPS 过低，尝试清理资源");
-                                self.cleanup_resources();
-                            }
-                            performance::PerformanceAlert::HighMemory { .. } => {
-                                warn!("内存使用过高，强制清理");
-                                self.force_cleanup_resources();
-                            }
-                            _ => {}
+        // 滤镜根据渲染耗时自动降级（release 模式下同样生效）
+        self.event_handler.render_engine_mut().record_render_time(frame_start.elapsed());
+        
+        // 性能与内存监控（release 模式下同样记录，退出时的统计和性能警告依赖这些数据）
+        let render_time = frame_start.elapsed();
+        let total_frame_time = frame_start.elapsed();
+        let timings = self.event_handler.render_engine().frame_timings();
+        
+        // 更新性能监控
+        if let Some(alert) = self.performance_monitor.record_frame_with_timings(total_frame_time, render_time, timings) {
+            match alert.severity() {
+                performance::AlertSeverity::Critical => {
+                    error!("性能严重警告: {}", alert.message());
+                    
+                    // 对于严重的性能问题，尝试优化措施
+                    match alert {
+                        performance::PerformanceAlert::LowFps { .. } => {
+                            warn!("FPS 过低，尝试清理资源");
+                            self.cleanup_resources();
+                        }
+                        performance::PerformanceAlert::HighMemory { .. } => {
+                            warn!("内存使用过高，强制清理");
+                            self.force_cleanup_resources();
                         }
+                        _ => {}
                     }
-                    _ => {}
                 }
+                _ => {}
             }
-            
-            // 更新内存监控
-            let current_memory = get_memory_usage_mb();
-            let render_stats = self.event_handler.render_engine().get_memory_stats();
-            if let Some(memory_alert) = self.memory_monitor.update(current_memory, render_stats.frame_buffer_pool.allocated_count) {
-                match memory_alert {
-                    memory::MemoryAlert::PossibleLeak { increase_mb, current_mb } => {
-                        error!("检测到可能的内存泄漏: 增长 {:.1}MB, 当前 {:.1}MB", increase_mb, current_mb);
-                        self.force_cleanup_resources();
-                    }
-                    memory::MemoryAlert::HighUsage { current_mb, threshold_mb } => {
-                        warn!("内存使用过高: {:.1}MB > {:.1}MB", current_mb, threshold_mb);
-                        self.cleanup_resources();
-                    }
+        }
+        
+        // 更新内存监控
+        let current_memory = get_memory_usage_mb();
+        let render_stats = self.event_handler.render_engine().get_memory_stats();
+        self.memory_monitor.record_mask_cache(
+            self.event_handler.shape_mask().cache_stats(),
+            render_stats.mask_texture_cache.clone(),
+        );
+        if let Some(memory_alert) = self.memory_monitor.update(current_memory, render_stats.frame_buffer_pool.allocated_count) {
+            match memory_alert {
+                memory::MemoryAlert::PossibleLeak { increase_mb, current_mb } => {
+                    error!("检测到可能的内存泄漏: 增长 {:.1}MB, 当前 {:.1}MB", increase_mb, current_mb);
+                    self.force_cleanup_resources();
+                }
+                memory::MemoryAlert::HighUsage { current_mb, threshold_mb } => {
+                    warn!("内存使用过高: {:.1}MB > {:.1}MB", current_mb, threshold_mb);
+                    self.cleanup_resources();
                 }
             }
         }
@@ -519,6 +822,42 @@ impl MiraApp {
         warn!("强制资源清理完成");
     }
 
+    /// 摄像头捕获线程送来新帧
+    fn on_camera_frame(&mut self) {
+        self.scheduler.notify_new_frame();
+    }
+    
+    /// 处理窗口事件并更新调度状态：改变画面的事件总是重绘，指针移动等只在界面状态变化时重绘
+    fn dispatch_window_event(&mut self, event: &WindowEvent) -> bool {
+        if let WindowEvent::Occluded(occluded) = event {
+            debug!("窗口{}", if *occluded { "被完全遮挡，暂停渲染" } else { "恢复可见" });
+        }
+        let ui_state = self.event_handler.pointer_ui_state();
+        let should_exit = self.handle_event(event);
+        let ui_changed = self.event_handler.pointer_ui_state() != ui_state;
+        self.scheduler.on_window_event(event, ui_changed);
+        should_exit
+    }
+    
+    /// 决定立即重绘还是休眠到下一帧，空闲时记录 CPU 使用率
+    fn schedule_next_frame(&mut self, event_loop: &winit::event_loop::EventLoopWindowTarget<()>) {
+        let now = Instant::now();
+        self.scheduler.set_animating(self.event_handler.is_animating());
+        
+        match self.scheduler.poll(now) {
+            Schedule::Render => {
+                self.window().request_redraw();
+                event_loop.set_control_flow(ControlFlow::WaitUntil(now + self.scheduler.frame_interval()));
+            }
+            Schedule::WaitUntil(deadline) => {
+                if self.scheduler.is_idle(now) {
+                    self.performance_monitor.record_idle(self.scheduler.skipped_frames());
+                }
+                event_loop.set_control_flow(ControlFlow::WaitUntil(deadline));
+            }
+        }
+    }
+
     /// 获取窗口引用
     fn window(&self) -> Arc<winit::window::Window> {
         self.event_handler.window_manager().window()
@@ -541,10 +880,22 @@ impl Drop for MiraApp {
               perf_stats.avg_cpu, perf_stats.max_cpu);
         info!("  内存统计: 平均={:.1}MB, 最大={:.1}MB", 
               memory_stats.avg_mb, memory_stats.max_mb);
+        info!("  遮罩缓存: 命中={}, 未命中={}, 纹理命中={}, 纹理未命中={}", 
+              memory_stats.mask_cache.hits, memory_stats.mask_cache.misses,
+              memory_stats.mask_texture_cache.hits, memory_stats.mask_texture_cache.misses);
         info!("  帧时间统计: 平均={:.1}ms, 最大={:.1}ms", 
               perf_stats.avg_frame_time, perf_stats.max_frame_time);
         info!("  渲染时间统计: 平均={:.1}ms, 最大={:.1}ms", 
               perf_stats.avg_render_time, perf_stats.max_render_time);
+        if let Some(cpu_encode) = perf_stats.avg_cpu_encode_time {
+            info!("  CPU 编码统计: 平均={:.2}ms, 最大={:.2}ms", cpu_encode, perf_stats.max_cpu_encode_time);
+        }
+        if let Some(gpu_time) = perf_stats.avg_gpu_time {
+            info!("  GPU 时间统计: 平均={:.2}ms", gpu_time);
+        }
+        if let Some(idle_cpu) = perf_stats.idle_cpu {
+            info!("  空闲统计: CPU={:.1}%, 跳过帧={}", idle_cpu, perf_stats.skipped_frames);
+        }
         info!("  错误统计: 摄像头={}, 渲染={}, 窗口={}, 配置={}", 
               self.camera_errors, self.render_errors, self.window_errors, self.config_errors);
         info!("  性能样本数: {}", perf_stats.sample_count);
@@ -741,7 +1092,7 @@ async fn run_application() -> Result<(), Box<dyn std::error::Error>> {
                         WindowEvent::CursorMoved { .. } | 
                         WindowEvent::MouseInput { .. } => {
                             // 立即处理鼠标事件，不等待渲染
-                            let should_exit = app.handle_event(&event);
+                            let should_exit = app.dispatch_window_event(&event);
                             if should_exit {
                                 info!("收到退出请求，正在关闭应用程序");
                                 event_loop.exit();
@@ -768,7 +1119,7 @@ async fn run_application() -> Result<(), Box<dyn std::error::Error>> {
                         }
                         _ => {
                             // 处理其他窗口事件
-                            let should_exit = app.handle_event(&event);
+                            let should_exit = app.dispatch_window_event(&event);
                             if should_exit {
                                 info!("收到退出请求，正在关闭应用程序");
                                 event_loop.exit();
@@ -777,6 +1128,9 @@ async fn run_application() -> Result<(), Box<dyn std::error::Error>> {
                     }
                 }
             }
+            Event::UserEvent(()) => {
+                app.on_camera_frame();
+            }
             Event::AboutToWait => {
                 // 处理托盘菜单事件
                 app.handle_tray_events();
@@ -788,8 +1142,8 @@ async fn run_application() -> Result<(), Box<dyn std::error::Error>> {
                     return;
                 }
                 
-                // 请求重绘以维持目标帧率
-                app.window().request_redraw();
+                // 有新帧、界面变化或动画时请求重绘，否则休眠到下一帧或空闲唤醒时间
+                app.schedule_next_frame(event_loop);
             }
             _ => {}
         }
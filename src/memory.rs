@@ -1,7 +1,8 @@
 // 内存管理和优化模块
 
 use log::{debug, info, warn};
-use std::collections::VecDeque;
+use std::collections::{HashMap, VecDeque};
+use std::hash::Hash;
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 
@@ -139,6 +140,8 @@ pub struct MemoryMonitor {
     max_history: usize,
     /// 内存泄漏检测阈值（MB）
     leak_threshold_mb: f32,
+    /// 最近一次记录的遮罩缓存统计（CPU 缓冲区, GPU 纹理）
+    mask_cache_stats: (MaskCacheStats, MaskCacheStats),
 }
 
 /// 内存快照
@@ -161,9 +164,15 @@ impl MemoryMonitor {
             memory_history: VecDeque::with_capacity(max_history),
             max_history,
             leak_threshold_mb,
+            mask_cache_stats: (MaskCacheStats::default(), MaskCacheStats::default()),
         }
     }
     
+    /// 记录遮罩缓存统计（CPU 遮罩缓冲区和 GPU 遮罩纹理）
+    pub fn record_mask_cache(&mut self, buffers: MaskCacheStats, textures: MaskCacheStats) {
+        self.mask_cache_stats = (buffers, textures);
+    }
+    
     /// 更新内存监控
     pub fn update(&mut self, current_memory_mb: f32, allocated_buffers: usize) -> Option<MemoryAlert> {
         let now = Instant::now();
@@ -234,7 +243,11 @@ impl MemoryMonitor {
     /// 获取内存统计信息
     pub fn get_stats(&self) -> MemoryStats {
         if self.memory_history.is_empty() {
-            return MemoryStats::default();
+            return MemoryStats {
+                mask_cache: self.mask_cache_stats.0.clone(),
+                mask_texture_cache: self.mask_cache_stats.1.clone(),
+                ..MemoryStats::default()
+            };
         }
         
         let current = &self.memory_history[self.memory_history.len() - 1];
@@ -249,6 +262,8 @@ impl MemoryMonitor {
             avg_mb: avg_memory,
             allocated_buffers: current.allocated_buffers,
             history_count: self.memory_history.len(),
+            mask_cache: self.mask_cache_stats.0.clone(),
+            mask_texture_cache: self.mask_cache_stats.1.clone(),
         }
     }
 }
@@ -275,6 +290,8 @@ pub struct MemoryStats {
     pub avg_mb: f32,
     pub allocated_buffers: usize,
     pub history_count: usize,
+    pub mask_cache: MaskCacheStats,
+    pub mask_texture_cache: MaskCacheStats,
 }
 
 /// GPU 纹理管理器
@@ -414,6 +431,162 @@ pub struct TextureManagerStats {
     pub cache_hit_rate: f32,
 }
 
+/// 遮罩缓存条目
+struct MaskCacheEntry<V> {
+    value: V,
+    size_bytes: usize,
+    last_used: u64,
+}
+
+/// 遮罩 LRU 缓存
+///
+/// 按调用方提供的键（形状、参数、尺寸和质量）缓存生成结果（CPU 缓冲区或 GPU 纹理），
+/// 总大小超过内存预算时淘汰最久未使用的条目。
+pub struct MaskCache<K, V> {
+    entries: HashMap<K, MaskCacheEntry<V>>,
+    /// 内存预算（字节），为 0 时禁用缓存
+    budget_bytes: usize,
+    used_bytes: usize,
+    /// 访问计数器，用于 LRU 排序
+    clock: u64,
+    hits: u64,
+    misses: u64,
+    evictions: u64,
+}
+
+impl<K: Hash + Eq + Clone, V> MaskCache<K, V> {
+    /// 创建新的遮罩缓存
+    pub fn new(budget_bytes: usize) -> Self {
+        debug!("创建遮罩缓存: 预算={}字节", budget_bytes);
+        
+        Self {
+            entries: HashMap::new(),
+            budget_bytes,
+            used_bytes: 0,
+            clock: 0,
+            hits: 0,
+            misses: 0,
+            evictions: 0,
+        }
+    }
+    
+    /// 查找缓存条目（命中时更新最近使用时间）
+    pub fn get(&mut self, key: &K) -> Option<&V> {
+        self.clock += 1;
+        match self.entries.get_mut(key) {
+            Some(entry) => {
+                entry.last_used = self.clock;
+                self.hits += 1;
+                Some(&entry.value)
+            }
+            None => {
+                self.misses += 1;
+                None
+            }
+        }
+    }
+    
+    /// 检查是否包含指定条目（不影响统计和 LRU 顺序）
+    pub fn contains(&self, key: &K) -> bool {
+        self.entries.contains_key(key)
+    }
+    
+    /// 插入缓存条目，必要时淘汰最久未使用的条目
+    ///
+    /// 单个条目超过预算时不缓存，返回 false。
+    pub fn insert(&mut self, key: K, value: V, size_bytes: usize) -> bool {
+        if size_bytes > self.budget_bytes {
+            return false;
+        }
+        
+        if let Some(old) = self.entries.remove(&key) {
+            self.used_bytes -= old.size_bytes;
+        }
+        
+        while self.used_bytes + size_bytes > self.budget_bytes {
+            if !self.evict_least_recently_used() {
+                break;
+            }
+        }
+        
+        self.clock += 1;
+        self.used_bytes += size_bytes;
+        self.entries.insert(key, MaskCacheEntry {
+            value,
+            size_bytes,
+            last_used: self.clock,
+        });
+        true
+    }
+    
+    /// 淘汰最久未使用的条目
+    fn evict_least_recently_used(&mut self) -> bool {
+        let oldest = self.entries.iter()
+            .min_by_key(|(_, entry)| entry.last_used)
+            .map(|(key, _)| key.clone());
+        
+        match oldest.and_then(|key| self.entries.remove(&key)) {
+            Some(entry) => {
+                self.used_bytes -= entry.size_bytes;
+                self.evictions += 1;
+                true
+            }
+            None => false,
+        }
+    }
+    
+    /// 调整内存预算，超出部分立即淘汰
+    pub fn set_budget(&mut self, budget_bytes: usize) {
+        self.budget_bytes = budget_bytes;
+        while self.used_bytes > self.budget_bytes {
+            if !self.evict_least_recently_used() {
+                break;
+            }
+        }
+    }
+    
+    /// 清空缓存（保留命中统计）
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.used_bytes = 0;
+    }
+    
+    /// 获取缓存统计信息
+    pub fn get_stats(&self) -> MaskCacheStats {
+        MaskCacheStats {
+            entries: self.entries.len(),
+            used_bytes: self.used_bytes,
+            budget_bytes: self.budget_bytes,
+            hits: self.hits,
+            misses: self.misses,
+            evictions: self.evictions,
+        }
+    }
+}
+
+/// 遮罩缓存统计信息
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MaskCacheStats {
+    pub entries: usize,
+    pub used_bytes: usize,
+    pub budget_bytes: usize,
+    pub hits: u64,
+    pub misses: u64,
+    pub evictions: u64,
+}
+
+impl MaskCacheStats {
+    /// 缓存命中率（0.0 - 1.0）
+    pub fn hit_rate(&self) -> f32 {
+        let total = self.hits + self.misses;
+        if total > 0 {
+            self.hits as f32 / total as f32
+        } else {
+            0.0
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -572,4 +745,77 @@ mod tests {
         assert!((stats.avg_mb - 123.33).abs() < 0.1); // 平均值
         assert_eq!(stats.history_count, 3);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_mask_cache_hit_and_miss() {
+        let mut cache = MaskCache::new(1024);
+        
+        assert!(cache.get(&10u32).is_none());
+        assert!(cache.insert(10u32, vec![1u8; 100], 100));
+        assert_eq!(cache.get(&10u32), Some(&vec![1u8; 100]));
+        
+        let stats = cache.get_stats();
+        assert_eq!(stats.hits, 1);
+        assert_eq!(stats.misses, 1);
+        assert_eq!(stats.entries, 1);
+        assert_eq!(stats.used_bytes, 100);
+        assert!((stats.hit_rate() - 0.5).abs() < f32::EPSILON);
+    }
+    
+    #[test]
+    fn test_mask_cache_lru_eviction() {
+        let mut cache = MaskCache::new(300);
+        
+        cache.insert(1u32, 1u8, 100);
+        cache.insert(2u32, 2u8, 100);
+        cache.insert(3u32, 3u8, 100);
+        
+        // 访问最早的条目，使第二个条目成为最久未使用
+        assert!(cache.get(&1u32).is_some());
+        cache.insert(4u32, 4u8, 100);
+        
+        assert!(cache.contains(&1u32));
+        assert!(!cache.contains(&2u32));
+        assert!(cache.contains(&3u32));
+        assert!(cache.contains(&4u32));
+        
+        let stats = cache.get_stats();
+        assert_eq!(stats.evictions, 1);
+        assert!(stats.used_bytes <= stats.budget_bytes);
+    }
+    
+    #[test]
+    fn test_mask_cache_budget() {
+        let mut cache = MaskCache::new(250);
+        
+        // 超过预算的条目不缓存
+        assert!(!cache.insert(1u32, 1u8, 500));
+        assert_eq!(cache.get_stats().entries, 0);
+        
+        cache.insert(1u32, 1u8, 100);
+        cache.insert(2u32, 2u8, 100);
+        
+        // 缩小预算后立即淘汰
+        cache.set_budget(150);
+        assert_eq!(cache.get_stats().entries, 1);
+        assert!(cache.contains(&2u32));
+        
+        // 预算为 0 时禁用缓存
+        cache.set_budget(0);
+        assert_eq!(cache.get_stats().used_bytes, 0);
+        assert!(!cache.insert(3u32, 3u8, 1));
+    }
+    
+    #[test]
+    fn test_memory_stats_include_mask_cache() {
+        let mut monitor = MemoryMonitor::new(Duration::from_secs(1), 10, 10.0);
+        let buffers = MaskCacheStats { hits: 3, misses: 1, ..MaskCacheStats::default() };
+        let textures = MaskCacheStats { hits: 2, misses: 2, ..MaskCacheStats::default() };
+        
+        monitor.record_mask_cache(buffers.clone(), textures.clone());
+        
+        let stats = monitor.get_stats();
+        assert_eq!(stats.mask_cache, buffers);
+        assert_eq!(stats.mask_texture_cache, textures);
+    }
+}
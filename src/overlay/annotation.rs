@@ -0,0 +1,333 @@
+// 手绘标注
+//
+// 标注模式下左键拖动绘制笔画。笔画点保存为遮罩纹理坐标（0.0 ~ 1.0），粗细相对窗口短边，
+// 因此窗口缩放、旋转后笔画仍停留在画面的同一位置。笔画与文字一起绘制到叠加层画布。
+
+use crate::overlay::canvas::OverlayCanvas;
+use std::time::{Duration, Instant};
+
+/// 标注调色板：红、黄、绿、蓝、白
+pub const ANNOTATION_COLORS: [[u8; 4]; 5] = [
+    [255, 59, 48, 255],
+    [255, 204, 0, 255],
+    [52, 199, 89, 255],
+    [0, 122, 255, 255],
+    [255, 255, 255, 255],
+];
+
+/// 可选的笔画粗细（相对窗口短边）：细、中、粗
+pub const ANNOTATION_WIDTHS: [f32; 3] = [0.006, 0.012, 0.024];
+
+/// 自动淡出时的渐隐时长
+const FADE_OUT_DURATION: Duration = Duration::from_millis(1000);
+
+/// 相邻两点的最小间距（纹理坐标），更密的点不记录
+const MIN_POINT_DISTANCE: f32 = 0.002;
+
+/// 一条笔画
+#[derive(Debug, Clone, PartialEq)]
+pub struct Stroke {
+    /// 遮罩纹理坐标下的点
+    pub points: Vec<[f32; 2]>,
+    pub color: [u8; 4],
+    /// 粗细（相对窗口短边）
+    pub width: f32,
+    /// 完成时间，绘制中为 None
+    pub finished_at: Option<Instant>,
+}
+
+/// 标注：笔画列表、当前画笔和自动淡出设置
+#[derive(Debug, Clone)]
+pub struct Annotations {
+    strokes: Vec<Stroke>,
+    // 最后一条笔画是否正在绘制
+    drawing: bool,
+    color: [u8; 4],
+    width: f32,
+    fade_after: Option<Duration>,
+    // 笔画变化时递增，用于判断是否需要重绘
+    revision: u64,
+}
+
+impl Default for Annotations {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Annotations {
+    pub fn new() -> Self {
+        Self {
+            strokes: Vec::new(),
+            drawing: false,
+            color: ANNOTATION_COLORS[0],
+            width: ANNOTATION_WIDTHS[1],
+            fade_after: None,
+            revision: 0,
+        }
+    }
+
+    /// 全部笔画（包括正在绘制的）
+    pub fn strokes(&self) -> &[Stroke] {
+        &self.strokes
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.strokes.is_empty()
+    }
+
+    /// 是否正在绘制笔画
+    pub fn is_drawing(&self) -> bool {
+        self.drawing
+    }
+
+    /// 每次笔画变化递增
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    /// 新笔画的颜色
+    pub fn color(&self) -> [u8; 4] {
+        self.color
+    }
+
+    pub fn set_color(&mut self, color: [u8; 4]) {
+        self.color = color;
+    }
+
+    /// 新笔画的粗细（相对窗口短边）
+    pub fn width(&self) -> f32 {
+        self.width
+    }
+
+    pub fn set_width(&mut self, width: f32) {
+        if width.is_finite() && width > 0.0 {
+            self.width = width;
+        }
+    }
+
+    /// 笔画完成后多久开始淡出，None 表示不淡出
+    pub fn fade_after(&self) -> Option<Duration> {
+        self.fade_after
+    }
+
+    /// 设置自动淡出，已完成的笔画从现在开始重新计时
+    pub fn set_fade_after(&mut self, fade_after: Option<Duration>) {
+        self.fade_after = fade_after;
+        let now = Instant::now();
+        for stroke in &mut self.strokes {
+            if stroke.finished_at.is_some() {
+                stroke.finished_at = Some(now);
+            }
+        }
+        self.revision += 1;
+    }
+
+    /// 以当前画笔开始一条新笔画
+    pub fn begin_stroke(&mut self, point: [f32; 2]) {
+        self.finish_drawing(Instant::now());
+        self.strokes.push(Stroke {
+            points: vec![point],
+            color: self.color,
+            width: self.width,
+            finished_at: None,
+        });
+        self.drawing = true;
+        self.revision += 1;
+    }
+
+    /// 向正在绘制的笔画追加一个点
+    pub fn extend_stroke(&mut self, point: [f32; 2]) {
+        if !self.drawing {
+            return;
+        }
+        let Some(stroke) = self.strokes.last_mut() else {
+            return;
+        };
+        if let Some(last) = stroke.points.last() {
+            if (point[0] - last[0]).hypot(point[1] - last[1]) < MIN_POINT_DISTANCE {
+                return;
+            }
+        }
+        stroke.points.push(point);
+        self.revision += 1;
+    }
+
+    /// 结束正在绘制的笔画
+    pub fn end_stroke(&mut self, now: Instant) {
+        self.finish_drawing(now);
+    }
+
+    /// 撤销最后一条笔画，没有笔画时返回 false
+    pub fn undo(&mut self) -> bool {
+        self.drawing = false;
+        if self.strokes.pop().is_none() {
+            return false;
+        }
+        self.revision += 1;
+        true
+    }
+
+    /// 清除全部笔画
+    pub fn clear(&mut self) {
+        self.drawing = false;
+        if !self.strokes.is_empty() {
+            self.strokes.clear();
+            self.revision += 1;
+        }
+    }
+
+    /// 笔画在 now 时刻的不透明度
+    pub fn opacity(&self, stroke: &Stroke, now: Instant) -> f32 {
+        stroke_opacity(stroke, self.fade_after, now)
+    }
+
+    /// 是否有正在渐隐的笔画（需要逐帧重绘）
+    pub fn is_fading(&self, now: Instant) -> bool {
+        self.strokes.iter().any(|stroke| self.opacity(stroke, now) < 1.0)
+    }
+
+    /// 移除已完全淡出的笔画，有笔画被移除时返回 true
+    pub fn expire(&mut self, now: Instant) -> bool {
+        let before = self.strokes.len();
+        let fade_after = self.fade_after;
+        self.strokes.retain(|stroke| stroke_opacity(stroke, fade_after, now) > 0.0);
+        let removed = self.strokes.len() != before;
+        if removed {
+            self.revision += 1;
+        }
+        removed
+    }
+
+    /// 按画布尺寸绘制全部笔画
+    pub fn draw(&self, canvas: &mut OverlayCanvas, now: Instant) {
+        let width = canvas.width() as f32;
+        let height = canvas.height() as f32;
+        let short_side = width.min(height);
+        for stroke in &self.strokes {
+            let opacity = self.opacity(stroke, now);
+            if opacity <= 0.0 {
+                continue;
+            }
+            let mut color = stroke.color;
+            color[3] = (color[3] as f32 * opacity).round() as u8;
+            let points: Vec<(f32, f32)> = stroke.points.iter().map(|p| (p[0] * width, p[1] * height)).collect();
+            canvas.stroke_polyline(&points, stroke.width * short_side, color);
+        }
+    }
+
+    fn finish_drawing(&mut self, now: Instant) {
+        if self.drawing {
+            if let Some(stroke) = self.strokes.last_mut() {
+                stroke.finished_at = Some(now);
+            }
+            self.drawing = false;
+        }
+    }
+}
+
+/// 笔画完成 fade_after 之后在 FADE_OUT_DURATION 内线性淡出
+fn stroke_opacity(stroke: &Stroke, fade_after: Option<Duration>, now: Instant) -> f32 {
+    let (Some(fade_after), Some(finished_at)) = (fade_after, stroke.finished_at) else {
+        return 1.0;
+    };
+    let elapsed = now.saturating_duration_since(finished_at);
+    if elapsed <= fade_after {
+        return 1.0;
+    }
+    let fading = (elapsed - fade_after).as_secs_f32() / FADE_OUT_DURATION.as_secs_f32();
+    (1.0 - fading).clamp(0.0, 1.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn draw_line(annotations: &mut Annotations, now: Instant) {
+        annotations.begin_stroke([0.25, 0.5]);
+        annotations.extend_stroke([0.5, 0.5]);
+        annotations.extend_stroke([0.75, 0.5]);
+        annotations.end_stroke(now);
+    }
+
+    #[test]
+    fn test_stroke_lifecycle() {
+        let mut annotations = Annotations::new();
+        let revision = annotations.revision();
+        annotations.begin_stroke([0.1, 0.1]);
+        assert!(annotations.is_drawing());
+        annotations.extend_stroke([0.1005, 0.1]);
+        annotations.extend_stroke([0.2, 0.2]);
+        assert_eq!(annotations.strokes()[0].points, vec![[0.1, 0.1], [0.2, 0.2]]);
+        assert!(annotations.revision() > revision);
+
+        annotations.end_stroke(Instant::now());
+        assert!(!annotations.is_drawing());
+        assert!(annotations.strokes()[0].finished_at.is_some());
+        // 未在绘制时追加的点被忽略
+        annotations.extend_stroke([0.9, 0.9]);
+        assert_eq!(annotations.strokes()[0].points.len(), 2);
+
+        // 新笔画使用当前画笔
+        annotations.set_color(ANNOTATION_COLORS[3]);
+        annotations.set_width(ANNOTATION_WIDTHS[2]);
+        annotations.set_width(f32::NAN);
+        annotations.begin_stroke([0.5, 0.5]);
+        assert_eq!(annotations.strokes()[1].color, ANNOTATION_COLORS[3]);
+        assert_eq!(annotations.strokes()[1].width, ANNOTATION_WIDTHS[2]);
+
+        assert!(annotations.undo());
+        assert!(!annotations.is_drawing());
+        assert_eq!(annotations.strokes().len(), 1);
+        annotations.clear();
+        assert!(annotations.is_empty());
+        assert!(!annotations.undo());
+    }
+
+    #[test]
+    fn test_draw_scales_with_canvas() {
+        let mut annotations = Annotations::new();
+        annotations.set_width(0.1);
+        draw_line(&mut annotations, Instant::now());
+
+        // 同一笔画在不同尺寸的画布上位于相同的相对位置
+        for (width, height) in [(100, 100), (300, 200)] {
+            let mut canvas = OverlayCanvas::new(width, height);
+            annotations.draw(&mut canvas, Instant::now());
+            assert_eq!(canvas.pixel(width / 2, height / 2).unwrap(), ANNOTATION_COLORS[0]);
+            assert_eq!(canvas.pixel(width / 2, height / 4).unwrap()[3], 0);
+            assert_eq!(canvas.pixel(width / 10, height / 2).unwrap()[3], 0);
+        }
+    }
+
+    #[test]
+    fn test_auto_fade() {
+        let mut annotations = Annotations::new();
+        annotations.set_fade_after(Some(Duration::from_secs(5)));
+        let start = Instant::now();
+        draw_line(&mut annotations, start);
+
+        // 正在绘制的笔画不淡出
+        annotations.begin_stroke([0.5, 0.2]);
+        let later = start + Duration::from_secs(60);
+        assert_eq!(annotations.opacity(&annotations.strokes()[1], later), 1.0);
+
+        let stroke = annotations.strokes()[0].clone();
+        assert_eq!(annotations.opacity(&stroke, start + Duration::from_secs(5)), 1.0);
+        assert!(!annotations.is_fading(start + Duration::from_secs(5)));
+        let half = annotations.opacity(&stroke, start + Duration::from_millis(5500));
+        assert!((half - 0.5).abs() < 1e-3);
+        assert!(annotations.is_fading(start + Duration::from_millis(5500)));
+
+        let revision = annotations.revision();
+        assert!(!annotations.expire(start + Duration::from_millis(5500)));
+        assert!(annotations.expire(start + Duration::from_secs(7)));
+        assert_eq!(annotations.strokes().len(), 1);
+        assert!(annotations.revision() > revision);
+
+        // 关闭淡出后笔画一直保留
+        annotations.set_fade_after(None);
+        annotations.end_stroke(start);
+        assert!(!annotations.expire(start + Duration::from_secs(3600)));
+    }
+}
@@ -0,0 +1,424 @@
+// 叠加层画布
+//
+// CPU 端 RGBA8 画布（sRGB 编码、非预乘透明度），文字、背景等叠加元素先绘制到这里，
+// 再整体上传为一张纹理，在着色器中与视频合成并被形状遮罩裁剪。
+
+/// 叠加层画布
+#[derive(Debug, Clone, PartialEq)]
+pub struct OverlayCanvas {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+}
+
+impl OverlayCanvas {
+    /// 创建全透明画布
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![0; (width * height * 4) as usize],
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// RGBA8 像素数据
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    /// 调整尺寸并清空
+    pub fn resize(&mut self, width: u32, height: u32) {
+        self.width = width;
+        self.height = height;
+        self.pixels.clear();
+        self.pixels.resize((width * height * 4) as usize, 0);
+    }
+
+    /// 清空为全透明
+    pub fn clear(&mut self) {
+        self.pixels.fill(0);
+    }
+
+    /// 画布是否完全透明
+    pub fn is_blank(&self) -> bool {
+        self.pixels.chunks_exact(4).all(|pixel| pixel[3] == 0)
+    }
+
+    /// 读取像素，越界时返回 None
+    pub fn pixel(&self, x: u32, y: u32) -> Option<[u8; 4]> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        let index = ((y * self.width + x) * 4) as usize;
+        Some([self.pixels[index], self.pixels[index + 1], self.pixels[index + 2], self.pixels[index + 3]])
+    }
+
+    /// 以覆盖率 coverage（0.0 ~ 1.0）将颜色叠加到像素上（Porter-Duff over），越界时忽略
+    pub fn blend_pixel(&mut self, x: i32, y: i32, color: [u8; 4], coverage: f32) {
+        if x < 0 || y < 0 || x >= self.width as i32 || y >= self.height as i32 {
+            return;
+        }
+        let src_alpha = color[3] as f32 / 255.0 * coverage.clamp(0.0, 1.0);
+        if src_alpha <= 0.0 {
+            return;
+        }
+
+        let index = ((y as u32 * self.width + x as u32) * 4) as usize;
+        let dst = &mut self.pixels[index..index + 4];
+        let dst_alpha = dst[3] as f32 / 255.0;
+        let out_alpha = src_alpha + dst_alpha * (1.0 - src_alpha);
+
+        for channel in 0..3 {
+            let src = color[channel] as f32;
+            let existing = dst[channel] as f32;
+            let blended = (src * src_alpha + existing * dst_alpha * (1.0 - src_alpha)) / out_alpha;
+            dst[channel] = blended.round().clamp(0.0, 255.0) as u8;
+        }
+        dst[3] = (out_alpha * 255.0).round() as u8;
+    }
+
+    /// 填充抗锯齿圆角矩形，radius 超过短边一半时为胶囊形
+    pub fn fill_rounded_rect(&mut self, x: f32, y: f32, width: f32, height: f32, radius: f32, color: [u8; 4]) {
+        if width <= 0.0 || height <= 0.0 {
+            return;
+        }
+        let radius = radius.clamp(0.0, width.min(height) * 0.5);
+        let center_x = x + width * 0.5;
+        let center_y = y + height * 0.5;
+        let half_width = width * 0.5 - radius;
+        let half_height = height * 0.5 - radius;
+
+        let (x0, y0, x1, y1) = self.clip_bounds(x, y, x + width, y + height);
+        for py in y0..y1 {
+            for px in x0..x1 {
+                // 圆角矩形有向距离场，边缘一个像素内线性过渡
+                let qx = (px as f32 + 0.5 - center_x).abs() - half_width;
+                let qy = (py as f32 + 0.5 - center_y).abs() - half_height;
+                let outside = (qx.max(0.0).powi(2) + qy.max(0.0).powi(2)).sqrt();
+                let distance = outside + qx.max(qy).min(0.0) - radius;
+                self.blend_pixel(px, py, color, 0.5 - distance);
+            }
+        }
+    }
+
+    /// 填充抗锯齿圆环扇段（角度为弧度，y 轴向下时顺时针为正）
+    pub fn fill_arc_band(
+        &mut self,
+        center: (f32, f32),
+        inner_radius: f32,
+        outer_radius: f32,
+        start_angle: f32,
+        end_angle: f32,
+        color: [u8; 4],
+    ) {
+        if outer_radius <= inner_radius || end_angle <= start_angle {
+            return;
+        }
+        let middle_angle = (start_angle + end_angle) * 0.5;
+        let half_span = (end_angle - start_angle) * 0.5;
+        let middle_radius = (inner_radius + outer_radius) * 0.5;
+        let half_thickness = (outer_radius - inner_radius) * 0.5;
+
+        let (x0, y0, x1, y1) = self.clip_bounds(
+            center.0 - outer_radius,
+            center.1 - outer_radius,
+            center.0 + outer_radius,
+            center.1 + outer_radius,
+        );
+        for py in y0..y1 {
+            for px in x0..x1 {
+                let dx = px as f32 + 0.5 - center.0;
+                let dy = py as f32 + 0.5 - center.1;
+                let radius = (dx * dx + dy * dy).sqrt();
+                let radial = (radius - middle_radius).abs() - half_thickness;
+
+                // 角度方向的距离按弧长换算为像素
+                let mut delta = dy.atan2(dx) - middle_angle;
+                delta = (delta + std::f32::consts::PI).rem_euclid(std::f32::consts::TAU) - std::f32::consts::PI;
+                let angular = (delta.abs() - half_span) * radius;
+
+                let coverage = (0.5 - radial).clamp(0.0, 1.0) * (0.5 - angular).clamp(0.0, 1.0);
+                self.blend_pixel(px, py, color, coverage);
+            }
+        }
+    }
+
+    /// 以灰度覆盖率位图为蒙版绘制纯色（位图左上角位于 (x, y)）
+    pub fn draw_coverage(&mut self, coverage: &[u8], width: usize, height: usize, x: i32, y: i32, color: [u8; 4]) {
+        for row in 0..height {
+            for column in 0..width {
+                let value = coverage[row * width + column];
+                if value > 0 {
+                    self.blend_pixel(x + column as i32, y + row as i32, color, value as f32 / 255.0);
+                }
+            }
+        }
+    }
+
+    /// 绕位图中心旋转 angle（弧度）后绘制，位图中心位于 center
+    pub fn draw_coverage_rotated(
+        &mut self,
+        coverage: &[u8],
+        width: usize,
+        height: usize,
+        center: (f32, f32),
+        angle: f32,
+        color: [u8; 4],
+    ) {
+        if width == 0 || height == 0 {
+            return;
+        }
+        let (sin_a, cos_a) = angle.sin_cos();
+        let half_width = width as f32 * 0.5;
+        let half_height = height as f32 * 0.5;
+        let extent = (half_width * half_width + half_height * half_height).sqrt() + 1.0;
+
+        let (x0, y0, x1, y1) = self.clip_bounds(
+            center.0 - extent,
+            center.1 - extent,
+            center.0 + extent,
+            center.1 + extent,
+        );
+        for py in y0..y1 {
+            for px in x0..x1 {
+                // 反向旋转到位图坐标后双线性采样
+                let dx = px as f32 + 0.5 - center.0;
+                let dy = py as f32 + 0.5 - center.1;
+                let u = cos_a * dx + sin_a * dy + half_width - 0.5;
+                let v = -sin_a * dx + cos_a * dy + half_height - 0.5;
+                let value = sample_bilinear(coverage, width, height, u, v);
+                if value > 0.0 {
+                    self.blend_pixel(px, py, color, value);
+                }
+            }
+        }
+    }
+
+    /// 绘制抗锯齿折线（圆头、圆角连接），线段重叠处取最大覆盖率，不会重复叠加变深；
+    /// 只有一个点时绘制圆点
+    pub fn stroke_polyline(&mut self, points: &[(f32, f32)], width: f32, color: [u8; 4]) {
+        let Some(&first) = points.first() else {
+            return;
+        };
+        if width <= 0.0 {
+            return;
+        }
+        let half_width = width * 0.5;
+        let reach = half_width + 1.0;
+        let (min_x, min_y, max_x, max_y) = points.iter().fold(
+            (first.0, first.1, first.0, first.1),
+            |(min_x, min_y, max_x, max_y), &(x, y)| (min_x.min(x), min_y.min(y), max_x.max(x), max_y.max(y)),
+        );
+        let (x0, y0, x1, y1) = self.clip_bounds(min_x - reach, min_y - reach, max_x + reach, max_y + reach);
+        if x0 >= x1 || y0 >= y1 {
+            return;
+        }
+
+        // 先在包围盒内累积覆盖率，再整体混合
+        let span = (x1 - x0) as usize;
+        let mut coverage = vec![0.0f32; span * (y1 - y0) as usize];
+        let segments: Vec<((f32, f32), (f32, f32))> = if points.len() == 1 {
+            vec![(first, first)]
+        } else {
+            points.windows(2).map(|pair| (pair[0], pair[1])).collect()
+        };
+        for (start, end) in segments {
+            let sx0 = ((start.0.min(end.0) - reach).floor() as i32).max(x0);
+            let sy0 = ((start.1.min(end.1) - reach).floor() as i32).max(y0);
+            let sx1 = ((start.0.max(end.0) + reach).ceil() as i32).min(x1);
+            let sy1 = ((start.1.max(end.1) + reach).ceil() as i32).min(y1);
+            let dx = end.0 - start.0;
+            let dy = end.1 - start.1;
+            let length_sq = dx * dx + dy * dy;
+            for py in sy0..sy1 {
+                for px in sx0..sx1 {
+                    // 像素中心到线段的距离
+                    let qx = px as f32 + 0.5 - start.0;
+                    let qy = py as f32 + 0.5 - start.1;
+                    let t = if length_sq > 0.0 { ((qx * dx + qy * dy) / length_sq).clamp(0.0, 1.0) } else { 0.0 };
+                    let distance = ((qx - t * dx).powi(2) + (qy - t * dy).powi(2)).sqrt();
+                    let value = (half_width + 0.5 - distance).clamp(0.0, 1.0);
+                    let cell = &mut coverage[(py - y0) as usize * span + (px - x0) as usize];
+                    *cell = cell.max(value);
+                }
+            }
+        }
+
+        for (index, value) in coverage.into_iter().enumerate() {
+            if value > 0.0 {
+                self.blend_pixel(x0 + (index % span) as i32, y0 + (index / span) as i32, color, value);
+            }
+        }
+    }
+
+    /// 将浮点矩形裁剪为画布内的整数像素范围 [x0, x1) × [y0, y1)
+    fn clip_bounds(&self, left: f32, top: f32, right: f32, bottom: f32) -> (i32, i32, i32, i32) {
+        (
+            (left.floor() as i32).max(0),
+            (top.floor() as i32).max(0),
+            (right.ceil() as i32).min(self.width as i32),
+            (bottom.ceil() as i32).min(self.height as i32),
+        )
+    }
+}
+
+/// 双线性采样覆盖率位图，位图外为 0
+fn sample_bilinear(coverage: &[u8], width: usize, height: usize, u: f32, v: f32) -> f32 {
+    if u <= -1.0 || v <= -1.0 || u >= width as f32 || v >= height as f32 {
+        return 0.0;
+    }
+    let x0 = u.floor();
+    let y0 = v.floor();
+    let fx = u - x0;
+    let fy = v - y0;
+    let fetch = |x: f32, y: f32| -> f32 {
+        if x < 0.0 || y < 0.0 || x >= width as f32 || y >= height as f32 {
+            0.0
+        } else {
+            coverage[y as usize * width + x as usize] as f32 / 255.0
+        }
+    };
+    let top = fetch(x0, y0) * (1.0 - fx) + fetch(x0 + 1.0, y0) * fx;
+    let bottom = fetch(x0, y0 + 1.0) * (1.0 - fx) + fetch(x0 + 1.0, y0 + 1.0) * fx;
+    top * (1.0 - fy) + bottom * fy
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_new_canvas_is_blank() {
+        let canvas = OverlayCanvas::new(8, 4);
+        assert_eq!(canvas.pixels().len(), 8 * 4 * 4);
+        assert!(canvas.is_blank());
+        assert_eq!(canvas.pixel(7, 3), Some([0, 0, 0, 0]));
+        assert_eq!(canvas.pixel(8, 0), None);
+    }
+
+    #[test]
+    fn test_blend_pixel_over() {
+        let mut canvas = OverlayCanvas::new(2, 2);
+        canvas.blend_pixel(0, 0, [255, 0, 0, 255], 1.0);
+        assert_eq!(canvas.pixel(0, 0), Some([255, 0, 0, 255]));
+
+        // 半透明蓝色叠加到不透明红色上
+        canvas.blend_pixel(0, 0, [0, 0, 255, 255], 0.5);
+        assert_eq!(canvas.pixel(0, 0), Some([128, 0, 128, 255]));
+
+        // 叠加到透明像素上时颜色不变暗
+        canvas.blend_pixel(1, 1, [200, 100, 50, 128], 1.0);
+        assert_eq!(canvas.pixel(1, 1), Some([200, 100, 50, 128]));
+
+        // 越界和零覆盖率被忽略
+        canvas.blend_pixel(-1, 0, [255; 4], 1.0);
+        canvas.blend_pixel(1, 0, [255; 4], 0.0);
+        assert_eq!(canvas.pixel(1, 0), Some([0, 0, 0, 0]));
+    }
+
+    #[test]
+    fn test_fill_rounded_rect() {
+        let mut canvas = OverlayCanvas::new(40, 20);
+        canvas.fill_rounded_rect(0.0, 0.0, 40.0, 20.0, 10.0, [0, 0, 0, 255]);
+
+        // 中心完全覆盖，胶囊形的角落透明
+        assert_eq!(canvas.pixel(20, 10).unwrap()[3], 255);
+        assert_eq!(canvas.pixel(0, 0).unwrap()[3], 0);
+        assert_eq!(canvas.pixel(39, 19).unwrap()[3], 0);
+        // 直边部分贴边
+        assert_eq!(canvas.pixel(20, 0).unwrap()[3], 255);
+
+        // 部分超出画布时只绘制可见部分
+        let mut canvas = OverlayCanvas::new(10, 10);
+        canvas.fill_rounded_rect(-5.0, -5.0, 10.0, 10.0, 0.0, [255; 4]);
+        assert_eq!(canvas.pixel(4, 4).unwrap()[3], 255);
+        assert_eq!(canvas.pixel(5, 5).unwrap()[3], 0);
+    }
+
+    #[test]
+    fn test_fill_arc_band() {
+        let mut canvas = OverlayCanvas::new(100, 100);
+        let bottom = std::f32::consts::FRAC_PI_2;
+        canvas.fill_arc_band((50.0, 50.0), 30.0, 40.0, bottom - 0.5, bottom + 0.5, [255; 4]);
+
+        // 正下方的环带内被覆盖，正上方和环带内侧不被覆盖
+        assert_eq!(canvas.pixel(50, 85).unwrap()[3], 255);
+        assert_eq!(canvas.pixel(50, 15).unwrap()[3], 0);
+        assert_eq!(canvas.pixel(50, 70).unwrap()[3], 0);
+    }
+
+    #[test]
+    fn test_draw_coverage() {
+        let mut canvas = OverlayCanvas::new(4, 4);
+        let bitmap = [255, 0, 128, 255];
+        canvas.draw_coverage(&bitmap, 2, 2, 1, 1, [255, 255, 255, 255]);
+        assert_eq!(canvas.pixel(1, 1).unwrap()[3], 255);
+        assert_eq!(canvas.pixel(2, 1).unwrap()[3], 0);
+        assert_eq!(canvas.pixel(1, 2).unwrap()[3], 128);
+        assert_eq!(canvas.pixel(0, 0).unwrap()[3], 0);
+
+        // 位图超出画布边界不会越界写入
+        canvas.draw_coverage(&bitmap, 2, 2, 3, 3, [255; 4]);
+        assert_eq!(canvas.pixel(3, 3).unwrap()[3], 255);
+    }
+
+    #[test]
+    fn test_stroke_polyline() {
+        let mut canvas = OverlayCanvas::new(40, 40);
+        let color = [255, 0, 0, 128];
+        canvas.stroke_polyline(&[(5.0, 20.0), (20.0, 20.0), (20.0, 35.0)], 5.0, color);
+
+        // 线上完全覆盖，线外透明
+        assert_eq!(canvas.pixel(10, 20).unwrap(), color);
+        assert_eq!(canvas.pixel(20, 30).unwrap(), color);
+        assert_eq!(canvas.pixel(10, 25).unwrap()[3], 0);
+        // 拐角处两条线段重叠，透明度不会叠加
+        assert_eq!(canvas.pixel(20, 20).unwrap(), color);
+        // 边缘抗锯齿
+        let edge = canvas.pixel(10, 22).unwrap()[3];
+        assert!(edge > 0 && edge < 128, "edge alpha {}", edge);
+
+        // 单点绘制为圆点
+        let mut dot = OverlayCanvas::new(20, 20);
+        dot.stroke_polyline(&[(10.0, 10.0)], 6.0, [255; 4]);
+        assert_eq!(dot.pixel(10, 10).unwrap()[3], 255);
+        assert_eq!(dot.pixel(10, 15).unwrap()[3], 0);
+    }
+
+    #[test]
+    fn test_draw_coverage_rotated() {
+        // 4x2 的横条旋转 90° 后变为竖条
+        let bitmap = [255u8; 8];
+        let mut canvas = OverlayCanvas::new(10, 10);
+        canvas.draw_coverage_rotated(&bitmap, 4, 2, (5.0, 5.0), std::f32::consts::FRAC_PI_2, [255; 4]);
+        assert!(canvas.pixel(5, 3).unwrap()[3] > 200);
+        assert!(canvas.pixel(5, 6).unwrap()[3] > 200);
+        assert_eq!(canvas.pixel(2, 5).unwrap()[3], 0);
+        assert_eq!(canvas.pixel(8, 5).unwrap()[3], 0);
+
+        // 不旋转时与直接绘制一致
+        let mut rotated = OverlayCanvas::new(10, 10);
+        rotated.draw_coverage_rotated(&bitmap, 4, 2, (5.0, 5.0), 0.0, [255; 4]);
+        let mut direct = OverlayCanvas::new(10, 10);
+        direct.draw_coverage(&bitmap, 4, 2, 3, 4, [255; 4]);
+        assert_eq!(rotated, direct);
+    }
+
+    #[test]
+    fn test_resize_clears() {
+        let mut canvas = OverlayCanvas::new(2, 2);
+        canvas.blend_pixel(0, 0, [255; 4], 1.0);
+        canvas.resize(3, 1);
+        assert_eq!(canvas.width(), 3);
+        assert_eq!(canvas.height(), 1);
+        assert!(canvas.is_blank());
+    }
+}
@@ -0,0 +1,258 @@
+// 基于字体文件的字形光栅化
+//
+// 优先使用配置中指定的字体，其后回退到系统自带的中文字体，最后是内置的
+// DejaVu Sans。某个字体缺少字形时依次尝试后续字体，保证中英文混排都能显示。
+//
+// 内置字体不含中文字形，菜单和文字叠加的中文依赖系统字体：先检查各平台的常见路径，
+// 都不含中文时再向系统字体服务（fontconfig）查询。
+
+use crate::error::RenderError;
+use crate::overlay::text::{GlyphBitmap, GlyphRasterizer, LineMetrics};
+use fontdue::{Font, FontSettings};
+use log::{debug, info, warn};
+use std::path::{Path, PathBuf};
+
+/// 系统中文字体候选路径（按优先级）
+#[cfg(target_os = "windows")]
+const SYSTEM_FONT_CANDIDATES: &[&str] = &[
+    "C:\\Windows\\Fonts\\msyh.ttc",
+    "C:\\Windows\\Fonts\\msyh.ttf",
+    "C:\\Windows\\Fonts\\simhei.ttf",
+    "C:\\Windows\\Fonts\\simsun.ttc",
+    "C:\\Windows\\Fonts\\segoeui.ttf",
+];
+
+#[cfg(target_os = "macos")]
+const SYSTEM_FONT_CANDIDATES: &[&str] = &[
+    "/System/Library/Fonts/PingFang.ttc",
+    "/System/Library/Fonts/Hiragino Sans GB.ttc",
+    "/System/Library/Fonts/STHeiti Medium.ttc",
+    "/Library/Fonts/Arial Unicode.ttf",
+    "/System/Library/Fonts/Helvetica.ttc",
+];
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+const SYSTEM_FONT_CANDIDATES: &[&str] = &[
+    "/usr/share/fonts/opentype/noto/NotoSansCJK-Regular.ttc",
+    "/usr/share/fonts/noto-cjk/NotoSansCJK-Regular.ttc",
+    "/usr/share/fonts/google-noto-cjk/NotoSansCJK-Regular.ttc",
+    "/usr/share/fonts/noto-cjk/NotoSansSC-Regular.otf",
+    "/usr/share/fonts/adobe-source-han-sans/SourceHanSansCN-Regular.otf",
+    "/usr/share/fonts/truetype/wqy/wqy-microhei.ttc",
+    "/usr/share/fonts/wenquanyi/wqy-microhei/wqy-microhei.ttc",
+    "/usr/share/fonts/truetype/droid/DroidSansFallbackFull.ttf",
+];
+
+/// 判断字体是否包含中文字形时检查的字符
+const CJK_PROBE: char = '中';
+
+/// 内置字体，系统中找不到任何字体时保证拉丁字符可以显示
+const EMBEDDED_FONT: &[u8] = include_bytes!("../../assets/fonts/DejaVuSans.ttf");
+
+/// 字体光栅化器，按顺序回退查找字形
+pub struct FontRasterizer {
+    fonts: Vec<Font>,
+}
+
+impl FontRasterizer {
+    /// 加载字体：custom_path 优先，其后追加第一个可用的系统字体和内置字体
+    pub fn load(custom_path: Option<&Path>) -> Result<Self, RenderError> {
+        let mut fonts = Vec::new();
+
+        if let Some(path) = custom_path {
+            match load_font_file(path) {
+                Ok(font) => {
+                    info!("已加载字体: {}", path.display());
+                    fonts.push(font);
+                }
+                Err(e) => warn!("{}，使用系统字体", e),
+            }
+        }
+
+        if let Some(font) = load_system_font() {
+            fonts.push(font);
+        }
+
+        fonts.push(parse_font(EMBEDDED_FONT)?);
+        Self::from_fonts(fonts)
+    }
+
+    /// 仅使用内置字体
+    pub fn embedded() -> Result<Self, RenderError> {
+        Self::from_bytes(EMBEDDED_FONT)
+    }
+
+    /// 从内存中的字体数据创建
+    pub fn from_bytes(data: &[u8]) -> Result<Self, RenderError> {
+        Self::from_fonts(vec![parse_font(data)?])
+    }
+
+    fn from_fonts(fonts: Vec<Font>) -> Result<Self, RenderError> {
+        if fonts.is_empty() {
+            return Err(RenderError::FontLoadFailed("未找到可用的字体文件".to_string()));
+        }
+        Ok(Self { fonts })
+    }
+
+    /// 第一个包含该字符的字体，都不包含时使用首选字体（显示缺字方框）
+    fn font_for(&self, ch: char) -> &Font {
+        &self.fonts[self.font_index(ch)]
+    }
+
+    fn font_index(&self, ch: char) -> usize {
+        self.fonts
+            .iter()
+            .position(|font| font.lookup_glyph_index(ch) != 0)
+            .unwrap_or(0)
+    }
+}
+
+impl GlyphRasterizer for FontRasterizer {
+    fn line_metrics(&self, px: f32) -> LineMetrics {
+        // 取所有字体中最大的上伸和下伸，保证回退字体的字形不超出背景
+        self.fonts
+            .iter()
+            .filter_map(|font| font.horizontal_line_metrics(px))
+            .fold(None, |acc: Option<LineMetrics>, metrics| {
+                let ascent = metrics.ascent;
+                let descent = -metrics.descent;
+                Some(match acc {
+                    Some(acc) => LineMetrics {
+                        ascent: acc.ascent.max(ascent),
+                        descent: acc.descent.max(descent),
+                    },
+                    None => LineMetrics { ascent, descent },
+                })
+            })
+            .unwrap_or(LineMetrics { ascent: px * 0.8, descent: px * 0.2 })
+    }
+
+    fn rasterize(&self, ch: char, px: f32) -> GlyphBitmap {
+        let (metrics, coverage) = self.font_for(ch).rasterize(ch, px);
+        GlyphBitmap {
+            width: metrics.width,
+            height: metrics.height,
+            xmin: metrics.xmin,
+            ymin: metrics.ymin,
+            advance: metrics.advance_width,
+            coverage,
+        }
+    }
+
+    fn kerning(&self, left: char, right: char, px: f32) -> f32 {
+        // 字距表只在同一字体内有效，跨字体回退时不调整
+        let index = self.font_index(left);
+        if index != self.font_index(right) {
+            return 0.0;
+        }
+        self.fonts[index].horizontal_kern(left, right, px).unwrap_or(0.0)
+    }
+}
+
+/// 加载系统字体：使用第一个包含中文字形的候选字体，候选路径都不含中文时查询系统字体服务；
+/// 仍然找不到时退回第一个可用的候选字体，至少保证拉丁字符的显示效果
+fn load_system_font() -> Option<Font> {
+    let mut fallback = None;
+    let candidates = SYSTEM_FONT_CANDIDATES.iter().map(PathBuf::from).filter(|path| path.exists());
+    for path in candidates.chain(std::iter::once_with(query_font_service).flatten()) {
+        match load_font_file(&path) {
+            Ok(font) if covers_cjk(&font) => {
+                info!("已加载系统字体: {}", path.display());
+                return Some(font);
+            }
+            Ok(font) => {
+                debug!("字体 {} 不含中文字形", path.display());
+                fallback.get_or_insert((path, font));
+            }
+            Err(e) => warn!("{}", e),
+        }
+    }
+
+    warn!("未找到包含中文字形的系统字体，中文将显示为缺字方框");
+    fallback.map(|(path, font)| {
+        info!("已加载系统字体: {}", path.display());
+        font
+    })
+}
+
+/// 向 fontconfig 查询最适合简体中文的字体文件
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+fn query_font_service() -> Option<PathBuf> {
+    let output = std::process::Command::new("fc-match")
+        .args(["--format", "%{file}", "sans-serif:lang=zh-cn"])
+        .output();
+    match output {
+        Ok(output) if output.status.success() && !output.stdout.is_empty() => {
+            Some(PathBuf::from(String::from_utf8_lossy(&output.stdout).trim()))
+        }
+        Ok(_) => None,
+        Err(e) => {
+            debug!("无法查询 fontconfig: {}", e);
+            None
+        }
+    }
+}
+
+/// Windows 和 macOS 的中文字体位于固定的系统目录，候选路径已经覆盖
+#[cfg(any(target_os = "windows", target_os = "macos"))]
+fn query_font_service() -> Option<PathBuf> {
+    None
+}
+
+fn covers_cjk(font: &Font) -> bool {
+    font.lookup_glyph_index(CJK_PROBE) != 0
+}
+
+fn load_font_file(path: &Path) -> Result<Font, RenderError> {
+    let data = std::fs::read(path)
+        .map_err(|e| RenderError::FontLoadFailed(format!("无法读取字体文件 {}: {}", path.display(), e)))?;
+    parse_font(&data)
+        .map_err(|e| RenderError::FontLoadFailed(format!("{}: {}", path.display(), e)))
+}
+
+fn parse_font(data: &[u8]) -> Result<Font, RenderError> {
+    // 字体集合（.ttc）使用其中第一个字体
+    Font::from_bytes(data, FontSettings::default())
+        .map_err(|e| RenderError::FontLoadFailed(format!("字体解析失败: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_invalid_font_data() {
+        let result = FontRasterizer::from_bytes(b"not a font");
+        assert!(matches!(result, Err(RenderError::FontLoadFailed(_))));
+    }
+
+    #[test]
+    fn test_missing_custom_font_falls_back() {
+        // 指定的字体不存在时不报错退出，继续尝试系统字体，最终总有内置字体可用
+        let result = FontRasterizer::load(Some(Path::new("/nonexistent/font.ttf")));
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_embedded_font_rasterizes() {
+        let fonts = FontRasterizer::embedded().unwrap();
+        let glyph = fonts.rasterize('A', 16.0);
+        assert!(glyph.width > 0 && glyph.height > 0);
+        assert!(glyph.advance > 0.0);
+        assert!(glyph.coverage.iter().any(|&c| c > 0));
+
+        let metrics = fonts.line_metrics(16.0);
+        assert!(metrics.ascent > 0.0 && metrics.descent > 0.0);
+        // 内置字体不含中文，不会与其他字符产生字距调整
+        assert_eq!(fonts.kerning('中', 'A', 16.0), 0.0);
+        assert!(!covers_cjk(&fonts.fonts[0]));
+    }
+
+    #[test]
+    fn test_font_service_returns_existing_file() {
+        // 没有 fontconfig 的环境返回 None，有结果时必须是存在的字体文件
+        if let Some(path) = query_font_service() {
+            assert!(path.exists(), "{}", path.display());
+        }
+    }
+}
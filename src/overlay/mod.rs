@@ -0,0 +1,304 @@
+// 叠加层模块
+//
+// 在视频上方绘制文字、手绘标注等元素。叠加层在 CPU 上绘制到与窗口同尺寸的画布，
+// 内容变化时才重新绘制并上传，在着色器中按遮罩纹理坐标采样，
+// 因此随形状一起旋转，并被形状遮罩裁剪。
+
+pub mod annotation;
+pub mod canvas;
+pub mod font;
+pub mod sticker;
+pub mod text;
+
+pub use annotation::{Annotations, Stroke, ANNOTATION_COLORS, ANNOTATION_WIDTHS};
+pub use canvas::OverlayCanvas;
+pub use font::FontRasterizer;
+pub use sticker::{Sticker, StickerLayer, StickerSet};
+pub use text::{TextAnchor, TextOverlay, TextRenderer, TextSource};
+
+use std::time::Instant;
+
+/// 叠加层：保存叠加元素并按需重绘画布
+pub struct OverlayLayer {
+    canvas: OverlayCanvas,
+    text_renderer: Option<TextRenderer>,
+    texts: Vec<TextOverlay>,
+    // 文字叠加是否显示（标注不受影响）
+    visible: bool,
+    annotations: Annotations,
+    // 上次绘制时各文字的实际内容，时钟变化时触发重绘
+    rendered_text: Vec<String>,
+    // 上次绘制时的标注版本
+    rendered_annotations: u64,
+    dirty: bool,
+}
+
+impl Default for OverlayLayer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl OverlayLayer {
+    pub fn new() -> Self {
+        Self {
+            canvas: OverlayCanvas::new(0, 0),
+            text_renderer: None,
+            texts: Vec::new(),
+            visible: true,
+            annotations: Annotations::new(),
+            rendered_text: Vec::new(),
+            rendered_annotations: 0,
+            dirty: true,
+        }
+    }
+
+    /// 设置文字渲染器（字体加载成功后调用）
+    pub fn set_text_renderer(&mut self, renderer: TextRenderer) {
+        self.text_renderer = Some(renderer);
+        self.dirty = true;
+    }
+
+    /// 是否已有可用的字体
+    pub fn has_text_renderer(&self) -> bool {
+        self.text_renderer.is_some()
+    }
+
+    /// 设置文字叠加列表
+    pub fn set_text_overlays(&mut self, texts: Vec<TextOverlay>) {
+        self.texts = texts;
+        self.dirty = true;
+    }
+
+    /// 当前文字叠加列表
+    pub fn text_overlays(&self) -> &[TextOverlay] {
+        &self.texts
+    }
+
+    /// 手绘标注
+    pub fn annotations(&self) -> &Annotations {
+        &self.annotations
+    }
+
+    /// 修改手绘标注，笔画变化会在下次 update 时重绘
+    pub fn annotations_mut(&mut self) -> &mut Annotations {
+        &mut self.annotations
+    }
+
+    /// 显示或隐藏文字叠加
+    pub fn set_visible(&mut self, visible: bool) {
+        if self.visible != visible {
+            self.visible = visible;
+            self.dirty = true;
+        }
+    }
+
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    /// 是否有需要绘制的内容
+    pub fn is_active(&self) -> bool {
+        self.texts_active() || !self.annotations.is_empty()
+    }
+
+    fn texts_active(&self) -> bool {
+        self.visible && self.text_renderer.is_some() && !self.texts.is_empty()
+    }
+
+    /// 时钟文字的当前内容与上次绘制的不同，需要重绘
+    pub fn clock_changed(&self) -> bool {
+        if !self.texts_active() {
+            return false;
+        }
+        self.texts.iter().enumerate().any(|(index, text)| {
+            matches!(text.source, TextSource::Clock(_))
+                && self.rendered_text.get(index).is_none_or(|rendered| *rendered != text.source.resolve())
+        })
+    }
+
+    /// 当前画布，未激活时尺寸为 0
+    pub fn canvas(&self) -> &OverlayCanvas {
+        &self.canvas
+    }
+
+    /// 强制下次 update 时重绘
+    pub fn invalidate(&mut self) {
+        self.dirty = true;
+    }
+
+    /// 按窗口尺寸和当前内容更新画布，返回画布是否发生变化
+    pub fn update(&mut self, width: u32, height: u32) -> bool {
+        let now = Instant::now();
+        self.annotations.expire(now);
+        if !self.is_active() {
+            // 未激活时释放画布内存
+            let changed = self.dirty || self.canvas.width() != 0 || self.canvas.height() != 0;
+            self.canvas.resize(0, 0);
+            self.rendered_text.clear();
+            self.rendered_annotations = self.annotations.revision();
+            self.dirty = false;
+            return changed;
+        }
+
+        let texts_active = self.texts_active();
+        let contents: Vec<String> = if texts_active {
+            self.texts.iter().map(|text| text.source.resolve()).collect()
+        } else {
+            Vec::new()
+        };
+        let size_changed = self.canvas.width() != width || self.canvas.height() != height;
+        // 渐隐中的笔画每帧都要重绘
+        let annotations_changed =
+            self.annotations.revision() != self.rendered_annotations || self.annotations.is_fading(now);
+        if !self.dirty && !size_changed && contents == self.rendered_text && !annotations_changed {
+            return false;
+        }
+
+        if size_changed {
+            self.canvas.resize(width, height);
+        } else {
+            self.canvas.clear();
+        }
+        if let Some(renderer) = self.text_renderer.as_mut().filter(|_| texts_active) {
+            for (overlay, text) in self.texts.iter().zip(&contents) {
+                renderer.draw(&mut self.canvas, overlay, text);
+            }
+        }
+        // 标注画在文字上方
+        self.annotations.draw(&mut self.canvas, now);
+
+        self.rendered_text = contents;
+        self.rendered_annotations = self.annotations.revision();
+        self.dirty = false;
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::overlay::text::tests::BoxRasterizer;
+    use crate::scheduler::{FrameScheduler, Schedule};
+
+    fn name_tag(text: &str) -> TextOverlay {
+        TextOverlay {
+            source: TextSource::Static(text.to_string()),
+            ..Default::default()
+        }
+    }
+
+    fn layer_with_font() -> OverlayLayer {
+        let mut layer = OverlayLayer::new();
+        layer.set_text_renderer(TextRenderer::new(Box::new(BoxRasterizer::new())));
+        layer
+    }
+
+    #[test]
+    fn test_inactive_layer_has_empty_canvas() {
+        let mut layer = OverlayLayer::new();
+        assert!(!layer.is_active());
+        // 首次更新报告变化，之后保持不变
+        assert!(layer.update(100, 100));
+        assert!(!layer.update(100, 100));
+        assert_eq!(layer.canvas().width(), 0);
+
+        // 没有字体时即使有文字也不激活
+        layer.set_text_overlays(vec![name_tag("Mira")]);
+        assert!(!layer.is_active());
+    }
+
+    #[test]
+    fn test_update_only_redraws_on_change() {
+        let mut layer = layer_with_font();
+        layer.set_text_overlays(vec![name_tag("Mira")]);
+        assert!(layer.is_active());
+
+        assert!(layer.update(200, 200));
+        assert_eq!(layer.canvas().width(), 200);
+        assert!(!layer.canvas().is_blank());
+        assert!(!layer.update(200, 200));
+
+        // 尺寸变化或内容变化时重绘
+        assert!(layer.update(300, 200));
+        layer.set_text_overlays(vec![name_tag("Mira 2")]);
+        assert!(layer.update(300, 200));
+        layer.invalidate();
+        assert!(layer.update(300, 200));
+        assert!(!layer.update(300, 200));
+    }
+
+    #[test]
+    fn test_ticking_clock_schedules_render() {
+        let mut layer = layer_with_font();
+        layer.set_text_overlays(vec![TextOverlay {
+            source: TextSource::Clock("%Y".to_string()),
+            ..Default::default()
+        }]);
+        assert!(layer.update(200, 200));
+        assert!(!layer.clock_changed());
+
+        // 模拟时钟走过：上次绘制的内容已过时
+        layer.rendered_text[0] = "1999".to_string();
+        assert!(layer.clock_changed());
+
+        // 没有摄像头新帧时，时钟变化仍然安排重绘
+        let mut scheduler = FrameScheduler::new(30);
+        let now = Instant::now();
+        scheduler.begin_render(now);
+        scheduler.set_animating(layer.clock_changed());
+        let next = now + scheduler.frame_interval();
+        assert_eq!(scheduler.poll(next), Schedule::Render);
+
+        // 重绘后不再需要渲染
+        scheduler.begin_render(next);
+        assert!(layer.update(200, 200));
+        scheduler.set_animating(layer.clock_changed());
+        assert!(!scheduler.has_pending_work());
+
+        // 固定文字不会触发重绘
+        layer.set_text_overlays(vec![name_tag("Mira")]);
+        layer.update(200, 200);
+        assert!(!layer.clock_changed());
+    }
+
+    #[test]
+    fn test_annotations_redraw_layer() {
+        let mut layer = OverlayLayer::new();
+        layer.update(100, 100);
+        assert!(!layer.is_active());
+
+        // 没有字体和文字时也可以绘制标注
+        layer.annotations_mut().begin_stroke([0.2, 0.5]);
+        layer.annotations_mut().extend_stroke([0.8, 0.5]);
+        assert!(layer.is_active());
+        assert!(layer.update(100, 100));
+        assert!(layer.canvas().pixel(50, 50).unwrap()[3] > 0);
+        assert!(!layer.update(100, 100));
+
+        // 隐藏文字叠加不影响标注
+        layer.set_visible(false);
+        layer.update(100, 100);
+        assert!(layer.canvas().pixel(50, 50).unwrap()[3] > 0);
+
+        layer.annotations_mut().undo();
+        assert!(layer.update(100, 100));
+        assert_eq!(layer.canvas().pixels().len(), 0);
+    }
+
+    #[test]
+    fn test_hiding_releases_canvas() {
+        let mut layer = layer_with_font();
+        layer.set_text_overlays(vec![name_tag("Mira")]);
+        layer.update(100, 100);
+
+        layer.set_visible(false);
+        assert!(layer.update(100, 100));
+        assert_eq!(layer.canvas().pixels().len(), 0);
+        assert!(!layer.update(100, 100));
+
+        layer.set_visible(true);
+        assert!(layer.update(100, 100));
+        assert!(!layer.canvas().is_blank());
+    }
+}
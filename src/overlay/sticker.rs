@@ -0,0 +1,527 @@
+// 贴纸叠加层
+//
+// 在形状上放置帽子、相框、表情等图片，支持 PNG、APNG 和动画 GIF。
+// 每个贴纸可设置相对形状的位置、缩放、旋转，以及位于遮罩边缘之下（被形状裁剪）
+// 或之上（可以超出形状边缘）。图片在后台线程解码，渲染线程只接收解码结果；
+// 动画帧按文件中的帧间隔播放，也可以在配置中统一指定帧间隔。
+
+use crate::error::RenderError;
+use image::codecs::gif::GifDecoder;
+use image::codecs::png::PngDecoder;
+use image::imageops::FilterType;
+use image::{AnimationDecoder, Frame, ImageFormat, RgbaImage};
+use log::{debug, info, warn};
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::time::{Duration, Instant};
+
+/// 贴纸边长上限，更大的图片在解码时缩小
+pub const MAX_STICKER_SIZE: u32 = 512;
+
+/// 动画最多保留的帧数
+pub const MAX_STICKER_FRAMES: usize = 300;
+
+/// 文件中帧间隔为 0 或过短时使用的间隔（与浏览器行为一致）
+const DEFAULT_FRAME_DELAY: Duration = Duration::from_millis(100);
+
+/// 短于该值的帧间隔视为未设置
+const MIN_FRAME_DELAY: Duration = Duration::from_millis(20);
+
+/// 贴纸相对遮罩边缘的层级
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StickerLayer {
+    /// 在遮罩之下，被形状裁剪
+    Below,
+    /// 在遮罩之上，可以超出形状边缘
+    #[default]
+    Above,
+}
+
+impl StickerLayer {
+    pub const ALL: [StickerLayer; 2] = [StickerLayer::Below, StickerLayer::Above];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StickerLayer::Below => "Below",
+            StickerLayer::Above => "Above",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|layer| layer.as_str().eq_ignore_ascii_case(name))
+    }
+}
+
+/// 贴纸
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sticker {
+    /// 图片路径
+    pub path: PathBuf,
+    /// 中心位置，以形状中心为原点、内切圆半径为单位（x 向右、y 向下）
+    pub position: [f32; 2],
+    /// 宽度占内切圆直径的比例，高度按图片比例
+    pub scale: f32,
+    /// 顺时针旋转角度（度）
+    pub rotation: f32,
+    pub layer: StickerLayer,
+    /// 统一的帧间隔，None 时使用文件中每帧的间隔
+    pub frame_delay: Option<Duration>,
+}
+
+impl Default for Sticker {
+    fn default() -> Self {
+        Self {
+            path: PathBuf::new(),
+            position: [0.0, 0.0],
+            scale: 0.3,
+            rotation: 0.0,
+            layer: StickerLayer::Above,
+            frame_delay: None,
+        }
+    }
+}
+
+impl Sticker {
+    /// 贴纸四角在目标画面中的像素坐标（左上、右上、右下、左下）
+    pub fn corners(&self, image_size: (u32, u32), target_size: (u32, u32)) -> [[f32; 2]; 4] {
+        let (target_width, target_height) = (target_size.0 as f32, target_size.1 as f32);
+        let radius = target_width.min(target_height) * 0.5;
+        let center = [
+            target_width * 0.5 + self.position[0] * radius,
+            target_height * 0.5 + self.position[1] * radius,
+        ];
+
+        let half_width = self.scale * radius;
+        let half_height = half_width * image_size.1 as f32 / image_size.0.max(1) as f32;
+        // y 轴向下，正角度为顺时针
+        let (sin, cos) = self.rotation.to_radians().sin_cos();
+        [[-1.0, -1.0], [1.0, -1.0], [1.0, 1.0], [-1.0, 1.0]].map(|[sx, sy]| {
+            let x = sx * half_width;
+            let y = sy * half_height;
+            [center[0] + x * cos - y * sin, center[1] + x * sin + y * cos]
+        })
+    }
+}
+
+/// 解码后的贴纸图片，所有帧尺寸相同（RGBA8，sRGB，非预乘）
+#[derive(Debug, Clone, PartialEq)]
+pub struct StickerImage {
+    width: u32,
+    height: u32,
+    frames: Vec<Vec<u8>>,
+    delays: Vec<Duration>,
+}
+
+impl StickerImage {
+    /// 静态图片
+    pub fn from_rgba(image: RgbaImage) -> Self {
+        let image = downscale(image);
+        Self {
+            width: image.width(),
+            height: image.height(),
+            frames: vec![image.into_raw()],
+            delays: vec![DEFAULT_FRAME_DELAY],
+        }
+    }
+
+    /// 动画帧（至少一帧）
+    fn from_frames(frames: Vec<Frame>) -> Result<Self, RenderError> {
+        let mut images = Vec::with_capacity(frames.len());
+        let mut delays = Vec::with_capacity(frames.len());
+        for frame in frames {
+            let (numer, denom) = frame.delay().numer_denom_ms();
+            let delay = Duration::from_secs_f64(numer as f64 / denom.max(1) as f64 / 1000.0);
+            delays.push(if delay < MIN_FRAME_DELAY { DEFAULT_FRAME_DELAY } else { delay });
+            images.push(downscale(frame.into_buffer()));
+        }
+
+        let first = images.first().ok_or_else(|| RenderError::StickerLoadFailed("动画没有帧".to_string()))?;
+        let (width, height) = first.dimensions();
+        Ok(Self {
+            width,
+            height,
+            frames: images.into_iter().map(RgbaImage::into_raw).collect(),
+            delays,
+        })
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn frame_count(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_animated(&self) -> bool {
+        self.frames.len() > 1
+    }
+
+    /// 指定帧的像素
+    pub fn frame(&self, index: usize) -> &[u8] {
+        &self.frames[index.min(self.frames.len() - 1)]
+    }
+
+    /// 播放 elapsed 后应显示的帧（循环播放），frame_delay 覆盖文件中的帧间隔
+    pub fn frame_at(&self, elapsed: Duration, frame_delay: Option<Duration>) -> usize {
+        if !self.is_animated() {
+            return 0;
+        }
+        let delay_of = |index: usize| frame_delay.unwrap_or(self.delays[index]).max(MIN_FRAME_DELAY);
+        let total: Duration = (0..self.frames.len()).map(delay_of).sum();
+        let mut remaining = Duration::from_nanos((elapsed.as_nanos() % total.as_nanos()) as u64);
+        for index in 0..self.frames.len() {
+            let delay = delay_of(index);
+            if remaining < delay {
+                return index;
+            }
+            remaining -= delay;
+        }
+        self.frames.len() - 1
+    }
+}
+
+/// 等比缩小到边长上限以内
+fn downscale(image: RgbaImage) -> RgbaImage {
+    let (width, height) = image.dimensions();
+    if width <= MAX_STICKER_SIZE && height <= MAX_STICKER_SIZE {
+        return image;
+    }
+    let ratio = MAX_STICKER_SIZE as f32 / width.max(height) as f32;
+    let new_width = ((width as f32 * ratio).round() as u32).max(1);
+    let new_height = ((height as f32 * ratio).round() as u32).max(1);
+    image::imageops::resize(&image, new_width, new_height, FilterType::Triangle)
+}
+
+/// 解码内存中的图片：PNG（含 APNG）、GIF 按动画处理，其余格式按静态图片处理
+pub fn decode_sticker(data: &[u8]) -> Result<StickerImage, RenderError> {
+    let error = |e: image::ImageError| RenderError::StickerLoadFailed(e.to_string());
+    match image::guess_format(data).map_err(error)? {
+        ImageFormat::Png => {
+            let decoder = PngDecoder::new(Cursor::new(data)).map_err(error)?;
+            if decoder.is_apng() {
+                collect_frames(decoder.apng().into_frames())
+            } else {
+                let image = image::DynamicImage::from_decoder(decoder).map_err(error)?;
+                Ok(StickerImage::from_rgba(image.into_rgba8()))
+            }
+        }
+        ImageFormat::Gif => {
+            let decoder = GifDecoder::new(Cursor::new(data)).map_err(error)?;
+            collect_frames(decoder.into_frames())
+        }
+        _ => {
+            let image = image::load_from_memory(data).map_err(error)?;
+            Ok(StickerImage::from_rgba(image.into_rgba8()))
+        }
+    }
+}
+
+fn collect_frames(frames: image::Frames) -> Result<StickerImage, RenderError> {
+    let frames = frames
+        .take(MAX_STICKER_FRAMES)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| RenderError::StickerLoadFailed(e.to_string()))?;
+    StickerImage::from_frames(frames)
+}
+
+/// 读取并解码贴纸文件
+pub fn load_sticker(path: &Path) -> Result<StickerImage, RenderError> {
+    let data = std::fs::read(path)
+        .map_err(|e| RenderError::StickerLoadFailed(format!("无法读取 {}: {}", path.display(), e)))?;
+    decode_sticker(&data).map_err(|e| match e {
+        RenderError::StickerLoadFailed(msg) => RenderError::StickerLoadFailed(format!("{}: {}", path.display(), msg)),
+        other => other,
+    })
+}
+
+/// 后台解码结果：(批次, 贴纸序号, 结果)
+type LoadResult = (u64, usize, Result<StickerImage, RenderError>);
+
+/// 贴纸集合：保存贴纸设置、后台解码并计算动画帧
+pub struct StickerSet {
+    stickers: Vec<Sticker>,
+    images: Vec<Option<StickerImage>>,
+    // 每次设置贴纸递增，丢弃过期批次的解码结果
+    generation: u64,
+    sender: Sender<LoadResult>,
+    receiver: Receiver<LoadResult>,
+    // 当前批次尚未收到结果的贴纸数量
+    pending: usize,
+    visible: bool,
+    started: Instant,
+}
+
+impl Default for StickerSet {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl StickerSet {
+    pub fn new() -> Self {
+        let (sender, receiver) = mpsc::channel();
+        Self {
+            stickers: Vec::new(),
+            images: Vec::new(),
+            generation: 0,
+            sender,
+            receiver,
+            pending: 0,
+            visible: true,
+            started: Instant::now(),
+        }
+    }
+
+    /// 设置贴纸列表，图片在后台线程中解码
+    pub fn set_stickers(&mut self, stickers: Vec<Sticker>) {
+        self.generation += 1;
+        self.images = vec![None; stickers.len()];
+        self.started = Instant::now();
+        self.pending = 0;
+
+        if !stickers.is_empty() {
+            let generation = self.generation;
+            let sender = self.sender.clone();
+            let paths: Vec<PathBuf> = stickers.iter().map(|sticker| sticker.path.clone()).collect();
+            let spawned = std::thread::Builder::new()
+                .name("sticker-decoder".to_string())
+                .spawn(move || {
+                    for (index, path) in paths.iter().enumerate() {
+                        // 接收端已释放时停止解码
+                        if sender.send((generation, index, load_sticker(path))).is_err() {
+                            break;
+                        }
+                    }
+                });
+            match spawned {
+                Ok(_) => self.pending = stickers.len(),
+                Err(e) => warn!("无法启动贴纸解码线程: {}", e),
+            }
+        }
+        self.stickers = stickers;
+    }
+
+    /// 当前贴纸列表
+    pub fn stickers(&self) -> &[Sticker] {
+        &self.stickers
+    }
+
+    /// 每次调用 set_stickers 递增
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// 接收后台解码结果，有新图片可用时返回 true
+    pub fn poll(&mut self) -> bool {
+        let mut loaded = false;
+        while let Ok((generation, index, result)) = self.receiver.try_recv() {
+            if generation != self.generation {
+                continue;
+            }
+            self.pending = self.pending.saturating_sub(1);
+            match result {
+                Ok(image) => {
+                    info!(
+                        "贴纸已加载: {} ({}x{}, {} 帧)",
+                        self.stickers[index].path.display(),
+                        image.width(),
+                        image.height(),
+                        image.frame_count()
+                    );
+                    self.images[index] = Some(image);
+                    loaded = true;
+                }
+                Err(e) => warn!("{}", e),
+            }
+        }
+        loaded
+    }
+
+    /// 已解码的图片
+    pub fn image(&self, index: usize) -> Option<&StickerImage> {
+        self.images.get(index).and_then(Option::as_ref)
+    }
+
+    /// 当前应显示的帧
+    pub fn current_frame(&self, index: usize) -> Option<usize> {
+        let image = self.image(index)?;
+        Some(image.frame_at(self.started.elapsed(), self.stickers[index].frame_delay))
+    }
+
+    /// 显示或隐藏贴纸
+    pub fn set_visible(&mut self, visible: bool) {
+        if self.visible != visible {
+            debug!("贴纸: {}", if visible { "显示" } else { "隐藏" });
+            self.visible = visible;
+        }
+    }
+
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    /// 是否有需要绘制的贴纸
+    pub fn is_active(&self) -> bool {
+        self.visible && self.images.iter().any(Option::is_some)
+    }
+
+    /// 是否需要逐帧重绘：有动画贴纸在播放，或后台解码尚未完成（结果要在渲染时取回并绘制）
+    pub fn is_animating(&self) -> bool {
+        self.visible && (self.pending > 0 || self.images.iter().flatten().any(StickerImage::is_animated))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::codecs::gif::GifEncoder;
+    use image::{Delay, Rgba};
+
+    fn png_bytes(image: &RgbaImage) -> Vec<u8> {
+        let mut data = Vec::new();
+        image.write_to(&mut Cursor::new(&mut data), ImageFormat::Png).unwrap();
+        data
+    }
+
+    fn gif_bytes(colors: &[[u8; 4]], delay_ms: u32) -> Vec<u8> {
+        let mut data = Vec::new();
+        {
+            let mut encoder = GifEncoder::new(&mut data);
+            let frames = colors.iter().map(|&color| {
+                Frame::from_parts(
+                    RgbaImage::from_pixel(4, 2, Rgba(color)),
+                    0,
+                    0,
+                    Delay::from_numer_denom_ms(delay_ms, 1),
+                )
+            });
+            encoder.encode_frames(frames).unwrap();
+        }
+        data
+    }
+
+    #[test]
+    fn test_layer_names() {
+        for layer in StickerLayer::ALL {
+            assert_eq!(StickerLayer::from_name(layer.as_str()), Some(layer));
+        }
+        assert_eq!(StickerLayer::from_name("below"), Some(StickerLayer::Below));
+        assert_eq!(StickerLayer::from_name("Middle"), None);
+    }
+
+    #[test]
+    fn test_decode_static_png() {
+        let image = decode_sticker(&png_bytes(&RgbaImage::from_pixel(3, 2, Rgba([255, 0, 0, 128])))).unwrap();
+        assert_eq!((image.width(), image.height()), (3, 2));
+        assert!(!image.is_animated());
+        assert_eq!(&image.frame(0)[..4], &[255, 0, 0, 128]);
+        assert_eq!(image.frame_at(Duration::from_secs(5), None), 0);
+
+        assert!(matches!(decode_sticker(b"not an image"), Err(RenderError::StickerLoadFailed(_))));
+    }
+
+    #[test]
+    fn test_decode_animated_gif() {
+        let image = decode_sticker(&gif_bytes(&[[255, 0, 0, 255], [0, 0, 255, 255]], 50)).unwrap();
+        assert_eq!((image.width(), image.height()), (4, 2));
+        assert_eq!(image.frame_count(), 2);
+        assert_eq!(image.frame(0)[0], 255);
+        assert_eq!(image.frame(1)[2], 255);
+
+        // 每帧 50ms，循环播放
+        assert_eq!(image.frame_at(Duration::from_millis(0), None), 0);
+        assert_eq!(image.frame_at(Duration::from_millis(60), None), 1);
+        assert_eq!(image.frame_at(Duration::from_millis(110), None), 0);
+        // 配置的帧间隔覆盖文件中的间隔
+        assert_eq!(image.frame_at(Duration::from_millis(60), Some(Duration::from_millis(100))), 0);
+        assert_eq!(image.frame_at(Duration::from_millis(150), Some(Duration::from_millis(100))), 1);
+    }
+
+    #[test]
+    fn test_zero_delay_uses_default() {
+        let image = decode_sticker(&gif_bytes(&[[255, 0, 0, 255], [0, 0, 255, 255]], 0)).unwrap();
+        assert_eq!(image.frame_at(Duration::from_millis(90), None), 0);
+        assert_eq!(image.frame_at(Duration::from_millis(110), None), 1);
+    }
+
+    #[test]
+    fn test_large_image_downscaled() {
+        let image = StickerImage::from_rgba(RgbaImage::new(MAX_STICKER_SIZE * 2, MAX_STICKER_SIZE));
+        assert_eq!((image.width(), image.height()), (MAX_STICKER_SIZE, MAX_STICKER_SIZE / 2));
+        assert_eq!(image.frame(0).len(), (image.width() * image.height() * 4) as usize);
+    }
+
+    #[test]
+    fn test_sticker_corners() {
+        let sticker = Sticker {
+            position: [0.0, -1.0],
+            scale: 0.5,
+            ..Default::default()
+        };
+        // 200x100 画面内切圆半径 50；宽 50，2:1 图片高 25，中心在形状顶部
+        let corners = sticker.corners((2, 1), (200, 100));
+        assert_eq!(corners, [[75.0, -12.5], [125.0, -12.5], [125.0, 12.5], [75.0, 12.5]]);
+
+        // 顺时针旋转 90 度后左上角转到右上方
+        let rotated = Sticker { rotation: 90.0, ..sticker }.corners((2, 1), (200, 100));
+        assert!((rotated[0][0] - 112.5).abs() < 1e-4);
+        assert!((rotated[0][1] + 25.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_sticker_set_loads_in_background() {
+        let dir = std::env::temp_dir().join(format!("mira_sticker_test_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("hat.png");
+        std::fs::write(&path, png_bytes(&RgbaImage::from_pixel(8, 8, Rgba([0, 255, 0, 255])))).unwrap();
+
+        let mut set = StickerSet::new();
+        assert!(!set.is_active());
+        set.set_stickers(vec![
+            Sticker { path: path.clone(), ..Default::default() },
+            Sticker { path: dir.join("missing.png"), ..Default::default() },
+        ]);
+        // 解码完成前持续重绘，以便及时取回结果
+        assert!(set.is_animating());
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while set.is_animating() && Instant::now() < deadline {
+            set.poll();
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        assert!(set.is_active());
+        assert_eq!(set.current_frame(0), Some(0));
+        // 加载失败的贴纸不显示，也不影响其他贴纸；全部是静态图片时不再逐帧重绘
+        assert!(set.image(1).is_none());
+        assert!(!set.is_animating());
+
+        // 动画贴纸播放时逐帧重绘，隐藏后停止
+        let gif = dir.join("sparkle.gif");
+        std::fs::write(&gif, gif_bytes(&[[255, 0, 0, 255], [0, 0, 255, 255]], 50)).unwrap();
+        set.set_stickers(vec![Sticker { path: gif, ..Default::default() }]);
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while set.image(0).is_none() && Instant::now() < deadline {
+            set.poll();
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        assert!(set.is_animating());
+
+        set.set_visible(false);
+        assert!(!set.is_active());
+        assert!(!set.is_animating());
+
+        // 重新设置后旧批次的结果被丢弃
+        set.set_stickers(Vec::new());
+        assert_eq!(set.generation(), 3);
+        assert!(!set.poll());
+        assert!(set.image(0).is_none());
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -0,0 +1,559 @@
+// 文字叠加层
+//
+// 将名牌、标签或时钟等文字绘制到叠加层画布上：支持纯色胶囊背景，
+// 可锚定在形状顶部、底部，或沿形状内切圆的底部弧线排列。
+// 字形由 GlyphRasterizer 光栅化（实际使用 font.rs 中基于字体文件的实现），并按字号缓存。
+
+use crate::overlay::canvas::OverlayCanvas;
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, Local, TimeZone};
+use std::collections::HashMap;
+use std::f32::consts::FRAC_PI_2;
+use std::fmt::Display;
+use std::sync::Arc;
+
+/// 默认时钟格式
+pub const DEFAULT_CLOCK_FORMAT: &str = "%H:%M";
+
+/// 字形缓存上限，超出后整体清空
+const MAX_CACHED_GLYPHS: usize = 1024;
+
+/// 背景胶囊的水平、垂直内边距（相对字号）
+const PADDING_X: f32 = 0.5;
+const PADDING_Y: f32 = 0.25;
+
+/// 单个字形的覆盖率位图及度量（像素）
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GlyphBitmap {
+    pub width: usize,
+    pub height: usize,
+    /// 位图左边缘相对笔位置的偏移
+    pub xmin: i32,
+    /// 位图下边缘相对基线的偏移（向上为正）
+    pub ymin: i32,
+    /// 笔位置前进量
+    pub advance: f32,
+    /// 覆盖率，行优先，width * height
+    pub coverage: Vec<u8>,
+}
+
+/// 行度量（像素）
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LineMetrics {
+    /// 基线以上高度
+    pub ascent: f32,
+    /// 基线以下深度（正值）
+    pub descent: f32,
+}
+
+/// 字形光栅化器
+pub trait GlyphRasterizer {
+    /// 指定字号的行度量
+    fn line_metrics(&self, px: f32) -> LineMetrics;
+    /// 光栅化单个字符
+    fn rasterize(&self, ch: char, px: f32) -> GlyphBitmap;
+    /// 相邻字符间的字距调整（像素，负值表示收紧）
+    fn kerning(&self, _left: char, _right: char, _px: f32) -> f32 {
+        0.0
+    }
+}
+
+/// 共享同一份字体（叠加层与菜单渲染器共用）
+impl<T: GlyphRasterizer + ?Sized> GlyphRasterizer for Arc<T> {
+    fn line_metrics(&self, px: f32) -> LineMetrics {
+        (**self).line_metrics(px)
+    }
+
+    fn rasterize(&self, ch: char, px: f32) -> GlyphBitmap {
+        (**self).rasterize(ch, px)
+    }
+
+    fn kerning(&self, left: char, right: char, px: f32) -> f32 {
+        (**self).kerning(left, right, px)
+    }
+}
+
+/// 文字在形状中的位置
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextAnchor {
+    /// 顶部居中
+    Top,
+    /// 底部居中（下三分之一名牌）
+    Bottom,
+    /// 沿底部弧线排列
+    Arc,
+}
+
+impl TextAnchor {
+    /// 所有位置
+    pub const ALL: [TextAnchor; 3] = [TextAnchor::Top, TextAnchor::Bottom, TextAnchor::Arc];
+
+    /// 配置文件中使用的名称
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TextAnchor::Top => "Top",
+            TextAnchor::Bottom => "Bottom",
+            TextAnchor::Arc => "Arc",
+        }
+    }
+
+    /// 从配置名称解析
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|anchor| anchor.as_str() == name)
+    }
+}
+
+/// 文字内容来源
+#[derive(Debug, Clone, PartialEq)]
+pub enum TextSource {
+    /// 固定文字
+    Static(String),
+    /// 当前时间，参数为 strftime 格式
+    Clock(String),
+}
+
+impl TextSource {
+    /// 配置文件中使用的类型名称
+    pub fn kind(&self) -> &'static str {
+        match self {
+            TextSource::Static(_) => "Static",
+            TextSource::Clock(_) => "Clock",
+        }
+    }
+
+    /// 从配置的类型名称和内容创建，未知类型返回 None
+    pub fn from_config(kind: &str, content: &str) -> Option<Self> {
+        match kind {
+            "Static" => Some(TextSource::Static(content.to_string())),
+            "Clock" => Some(TextSource::Clock(content.to_string())),
+            _ => None,
+        }
+    }
+
+    /// 配置中保存的内容（固定文字或时钟格式）
+    pub fn content(&self) -> &str {
+        match self {
+            TextSource::Static(text) | TextSource::Clock(text) => text,
+        }
+    }
+
+    /// 按当前时间生成显示文字
+    pub fn resolve(&self) -> String {
+        self.resolve_at(&Local::now())
+    }
+
+    /// 按指定时间生成显示文字，无效的时钟格式使用默认格式
+    pub fn resolve_at<Tz: TimeZone>(&self, time: &DateTime<Tz>) -> String
+    where
+        Tz::Offset: Display,
+    {
+        match self {
+            TextSource::Static(text) => text.clone(),
+            TextSource::Clock(format) => {
+                let format = if is_valid_clock_format(format) { format.as_str() } else { DEFAULT_CLOCK_FORMAT };
+                time.format(format).to_string()
+            }
+        }
+    }
+}
+
+/// 检查 strftime 格式是否有效（无效格式在格式化时会 panic）
+pub fn is_valid_clock_format(format: &str) -> bool {
+    !format.is_empty() && StrftimeItems::new(format).all(|item| !matches!(item, Item::Error))
+}
+
+/// 一条文字叠加
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextOverlay {
+    /// 内容来源
+    pub source: TextSource,
+    /// 字号（像素）
+    pub font_size: f32,
+    /// 文字颜色 RGBA
+    pub color: [u8; 4],
+    /// 背景胶囊颜色，None 表示不绘制背景
+    pub background: Option<[u8; 4]>,
+    /// 位置
+    pub anchor: TextAnchor,
+    /// 与形状边缘的距离（相对画布短边，0.0 ~ 0.5）
+    pub margin: f32,
+}
+
+impl Default for TextOverlay {
+    fn default() -> Self {
+        Self {
+            source: TextSource::Static(String::new()),
+            font_size: 20.0,
+            color: [255, 255, 255, 255],
+            background: Some([0, 0, 0, 160]),
+            anchor: TextAnchor::Bottom,
+            margin: 0.1,
+        }
+    }
+}
+
+/// 文字渲染器：光栅化器加字形缓存
+pub struct TextRenderer {
+    rasterizer: Box<dyn GlyphRasterizer + Send>,
+    cache: HashMap<(char, u32), GlyphBitmap>,
+}
+
+impl TextRenderer {
+    pub fn new(rasterizer: Box<dyn GlyphRasterizer + Send>) -> Self {
+        Self {
+            rasterizer,
+            cache: HashMap::new(),
+        }
+    }
+
+    /// 已缓存的字形数量
+    pub fn cached_glyphs(&self) -> usize {
+        self.cache.len()
+    }
+
+    /// 获取（必要时光栅化）字形
+    fn glyph(&mut self, ch: char, px: f32) -> &GlyphBitmap {
+        if self.cache.len() >= MAX_CACHED_GLYPHS && !self.cache.contains_key(&(ch, px.to_bits())) {
+            self.cache.clear();
+        }
+        let rasterizer = &self.rasterizer;
+        self.cache
+            .entry((ch, px.to_bits()))
+            .or_insert_with(|| rasterizer.rasterize(ch, px))
+    }
+
+    /// 测量单行文字宽度（含字距调整）
+    pub fn measure(&mut self, text: &str, px: f32) -> f32 {
+        let mut width = 0.0;
+        let mut previous = None;
+        for ch in text.chars() {
+            width += self.kerning(previous, ch, px) + self.glyph(ch, px).advance;
+            previous = Some(ch);
+        }
+        width
+    }
+
+    fn kerning(&self, previous: Option<char>, ch: char, px: f32) -> f32 {
+        previous.map_or(0.0, |left| self.rasterizer.kerning(left, ch, px))
+    }
+
+    /// 将文字绘制到画布上
+    pub fn draw(&mut self, canvas: &mut OverlayCanvas, overlay: &TextOverlay, text: &str) {
+        if text.is_empty() || canvas.width() == 0 || canvas.height() == 0 {
+            return;
+        }
+        match overlay.anchor {
+            TextAnchor::Top | TextAnchor::Bottom => self.draw_horizontal(canvas, overlay, text),
+            TextAnchor::Arc => self.draw_arc(canvas, overlay, text),
+        }
+    }
+
+    /// 水平排列，在顶部或底部居中
+    fn draw_horizontal(&mut self, canvas: &mut OverlayCanvas, overlay: &TextOverlay, text: &str) {
+        let px = overlay.font_size;
+        let metrics = self.rasterizer.line_metrics(px);
+        let text_width = self.measure(text, px);
+
+        let padding_x = px * PADDING_X;
+        let padding_y = px * PADDING_Y;
+        let pill_width = text_width + padding_x * 2.0;
+        let pill_height = metrics.ascent + metrics.descent + padding_y * 2.0;
+        let margin = overlay.margin * canvas.width().min(canvas.height()) as f32;
+
+        let pill_x = (canvas.width() as f32 - pill_width) * 0.5;
+        let pill_y = match overlay.anchor {
+            TextAnchor::Top => margin,
+            _ => canvas.height() as f32 - margin - pill_height,
+        };
+        if let Some(background) = overlay.background {
+            canvas.fill_rounded_rect(pill_x, pill_y, pill_width, pill_height, pill_height * 0.5, background);
+        }
+
+        let baseline = pill_y + padding_y + metrics.ascent;
+        let mut pen_x = pill_x + padding_x;
+        let mut previous = None;
+        for ch in text.chars() {
+            pen_x += self.kerning(previous, ch, px);
+            previous = Some(ch);
+            let glyph = self.glyph(ch, px);
+            let x = (pen_x + glyph.xmin as f32).round() as i32;
+            let y = (baseline - (glyph.ymin + glyph.height as i32) as f32).round() as i32;
+            canvas.draw_coverage(&glyph.coverage, glyph.width, glyph.height, x, y, overlay.color);
+            pen_x += glyph.advance;
+        }
+    }
+
+    /// 沿内切圆底部弧线排列，字形顶部朝向圆心
+    fn draw_arc(&mut self, canvas: &mut OverlayCanvas, overlay: &TextOverlay, text: &str) {
+        let px = overlay.font_size;
+        let metrics = self.rasterizer.line_metrics(px);
+        let text_width = self.measure(text, px);
+
+        let padding_x = px * PADDING_X;
+        let padding_y = px * PADDING_Y;
+        let min_side = canvas.width().min(canvas.height()) as f32;
+        let center = (canvas.width() as f32 * 0.5, canvas.height() as f32 * 0.5);
+        // 基线半径：外缘留出边距、内边距和下伸部分
+        let radius = min_side * (0.5 - overlay.margin) - padding_y - metrics.descent;
+        if radius <= metrics.ascent {
+            return;
+        }
+
+        // 弧线在正下方（π/2）居中，从左向右阅读时角度递减
+        let span = text_width / radius;
+        let start_angle = FRAC_PI_2 + span * 0.5;
+        if let Some(background) = overlay.background {
+            let padding_angle = padding_x / radius;
+            canvas.fill_arc_band(
+                center,
+                radius - metrics.ascent - padding_y,
+                radius + metrics.descent + padding_y,
+                FRAC_PI_2 - span * 0.5 - padding_angle,
+                FRAC_PI_2 + span * 0.5 + padding_angle,
+                background,
+            );
+        }
+
+        let mut pen = 0.0;
+        let mut previous = None;
+        for ch in text.chars() {
+            pen += self.kerning(previous, ch, px);
+            previous = Some(ch);
+            let glyph = self.glyph(ch, px);
+            let angle = start_angle - (pen + glyph.advance * 0.5) / radius;
+            let rotation = angle - FRAC_PI_2;
+            let (sin_r, cos_r) = rotation.sin_cos();
+
+            // 位图中心相对字形基线中点的偏移，随字形一起旋转
+            let offset_x = glyph.xmin as f32 + glyph.width as f32 * 0.5 - glyph.advance * 0.5;
+            let offset_y = -(glyph.ymin as f32 + glyph.height as f32 * 0.5);
+            let glyph_center = (
+                center.0 + radius * angle.cos() + offset_x * cos_r - offset_y * sin_r,
+                center.1 + radius * angle.sin() + offset_x * sin_r + offset_y * cos_r,
+            );
+            canvas.draw_coverage_rotated(
+                &glyph.coverage,
+                glyph.width,
+                glyph.height,
+                glyph_center,
+                rotation,
+                overlay.color,
+            );
+            pen += glyph.advance;
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// 测试用光栅化器：每个非空白字符为实心方块
+    pub(crate) struct BoxRasterizer {
+        pub calls: Arc<AtomicUsize>,
+    }
+
+    impl BoxRasterizer {
+        pub(crate) fn new() -> Self {
+            Self { calls: Arc::new(AtomicUsize::new(0)) }
+        }
+    }
+
+    impl GlyphRasterizer for BoxRasterizer {
+        fn line_metrics(&self, px: f32) -> LineMetrics {
+            LineMetrics { ascent: px * 0.8, descent: px * 0.2 }
+        }
+
+        fn rasterize(&self, ch: char, px: f32) -> GlyphBitmap {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            let advance = px * 0.6;
+            if ch.is_whitespace() {
+                return GlyphBitmap { advance, ..Default::default() };
+            }
+            let width = (px * 0.5) as usize;
+            let height = (px * 0.7) as usize;
+            GlyphBitmap { width, height, xmin: 0, ymin: 0, advance, coverage: vec![255; width * height] }
+        }
+    }
+
+    fn renderer() -> TextRenderer {
+        TextRenderer::new(Box::new(BoxRasterizer::new()))
+    }
+
+    fn overlay(anchor: TextAnchor) -> TextOverlay {
+        TextOverlay {
+            source: TextSource::Static("Mira".to_string()),
+            anchor,
+            color: [255, 0, 0, 255],
+            background: Some([0, 0, 255, 255]),
+            ..Default::default()
+        }
+    }
+
+    /// 统计画布上半部分和下半部分中指定颜色的像素数
+    fn count_color(canvas: &OverlayCanvas, color: [u8; 4]) -> (usize, usize) {
+        let mut counts = (0, 0);
+        for y in 0..canvas.height() {
+            for x in 0..canvas.width() {
+                if canvas.pixel(x, y) == Some(color) {
+                    if y < canvas.height() / 2 { counts.0 += 1 } else { counts.1 += 1 }
+                }
+            }
+        }
+        counts
+    }
+
+    #[test]
+    fn test_anchor_names() {
+        for anchor in TextAnchor::ALL {
+            assert_eq!(TextAnchor::from_name(anchor.as_str()), Some(anchor));
+        }
+        assert_eq!(TextAnchor::from_name("Middle"), None);
+    }
+
+    #[test]
+    fn test_text_source_resolve() {
+        let time = Local.with_ymd_and_hms(2024, 1, 2, 9, 5, 7).unwrap();
+        assert_eq!(TextSource::Static("张三 — 工程负责人".to_string()).resolve_at(&time), "张三 — 工程负责人");
+        assert_eq!(TextSource::Clock("%H:%M".to_string()).resolve_at(&time), "09:05");
+        assert_eq!(TextSource::Clock("%Y年%m月%d日 %H:%M:%S".to_string()).resolve_at(&time), "2024年01月02日 09:05:07");
+
+        // 无效格式回退到默认格式而不是 panic
+        assert!(!is_valid_clock_format("%Q"));
+        assert!(!is_valid_clock_format(""));
+        assert_eq!(TextSource::Clock("%Q".to_string()).resolve_at(&time), "09:05");
+
+        assert_eq!(TextSource::from_config("Clock", "%H"), Some(TextSource::Clock("%H".to_string())));
+        assert_eq!(TextSource::from_config("Weather", ""), None);
+        let source = TextSource::Static("abc".to_string());
+        assert_eq!(TextSource::from_config(source.kind(), source.content()), Some(source));
+    }
+
+    #[test]
+    fn test_measure_and_glyph_cache() {
+        let rasterizer = BoxRasterizer::new();
+        let calls = rasterizer.calls.clone();
+        let mut renderer = TextRenderer::new(Box::new(rasterizer));
+
+        assert_eq!(renderer.measure("ab a", 10.0), 24.0);
+        // 重复字符只光栅化一次
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+        assert_eq!(renderer.cached_glyphs(), 3);
+
+        renderer.measure("ab", 10.0);
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+        // 不同字号单独缓存
+        renderer.measure("a", 12.0);
+        assert_eq!(renderer.cached_glyphs(), 4);
+    }
+
+    /// 在 BoxRasterizer 基础上为 "AV" 收紧字距
+    struct KerningRasterizer(BoxRasterizer);
+
+    impl GlyphRasterizer for KerningRasterizer {
+        fn line_metrics(&self, px: f32) -> LineMetrics {
+            self.0.line_metrics(px)
+        }
+
+        fn rasterize(&self, ch: char, px: f32) -> GlyphBitmap {
+            self.0.rasterize(ch, px)
+        }
+
+        fn kerning(&self, left: char, right: char, px: f32) -> f32 {
+            if (left, right) == ('A', 'V') { -0.2 * px } else { 0.0 }
+        }
+    }
+
+    #[test]
+    fn test_measure_applies_kerning() {
+        let mut plain = renderer();
+        let mut kerned = TextRenderer::new(Box::new(Arc::new(KerningRasterizer(BoxRasterizer::new()))));
+
+        assert_eq!(plain.measure("AV", 10.0), 12.0);
+        assert_eq!(kerned.measure("AV", 10.0), 10.0);
+        // 只作用于相邻字符对
+        assert_eq!(kerned.measure("VA", 10.0), 12.0);
+        assert_eq!(kerned.measure("A V", 10.0), 18.0);
+    }
+
+    #[test]
+    fn test_bottom_name_tag() {
+        let mut canvas = OverlayCanvas::new(200, 200);
+        renderer().draw(&mut canvas, &overlay(TextAnchor::Bottom), "Mira");
+
+        let (text_top, text_bottom) = count_color(&canvas, [255, 0, 0, 255]);
+        let (_, background_bottom) = count_color(&canvas, [0, 0, 255, 255]);
+        assert_eq!(text_top, 0);
+        // 4 个 10x14 的方块
+        assert_eq!(text_bottom, 4 * 10 * 14);
+        assert!(background_bottom > 0);
+
+        // 水平居中
+        let row: Vec<u32> = (0..200).filter(|&x| canvas.pixel(x, 170).unwrap()[3] > 0).collect();
+        let middle = (row.first().unwrap() + row.last().unwrap()) as f32 / 2.0;
+        assert!((middle - 99.5).abs() <= 1.0);
+
+        // 背景底边与边距对齐
+        assert_eq!(canvas.pixel(100, 179).unwrap()[3], 255);
+        assert_eq!(canvas.pixel(100, 181).unwrap()[3], 0);
+    }
+
+    #[test]
+    fn test_top_anchor_without_background() {
+        let mut canvas = OverlayCanvas::new(200, 200);
+        let overlay = TextOverlay { background: None, ..overlay(TextAnchor::Top) };
+        renderer().draw(&mut canvas, &overlay, "Mira");
+
+        let (text_top, text_bottom) = count_color(&canvas, [255, 0, 0, 255]);
+        assert_eq!(text_top, 4 * 10 * 14);
+        assert_eq!(text_bottom, 0);
+        assert_eq!(count_color(&canvas, [0, 0, 255, 255]), (0, 0));
+    }
+
+    #[test]
+    fn test_arc_text_follows_bottom_curve() {
+        let mut canvas = OverlayCanvas::new(200, 200);
+        let overlay = TextOverlay { background: None, ..overlay(TextAnchor::Arc) };
+        renderer().draw(&mut canvas, &overlay, "MIRA MIRA");
+
+        let covered: Vec<(u32, u32)> = (0..200u32)
+            .flat_map(|y| (0..200u32).map(move |x| (x, y)))
+            .filter(|&(x, y)| canvas.pixel(x, y).unwrap()[3] > 128)
+            .collect();
+        assert!(!covered.is_empty());
+
+        // 所有文字都在内切圆以内的下半部分
+        for &(x, y) in &covered {
+            let dx = x as f32 + 0.5 - 100.0;
+            let dy = y as f32 + 0.5 - 100.0;
+            assert!(dy > 0.0);
+            assert!((dx * dx + dy * dy).sqrt() < 100.0 - 200.0 * overlay.margin + 1.0);
+        }
+
+        // 两端的字形比中间的字形位置更高
+        let leftmost = covered.iter().min_by_key(|p| p.0).unwrap();
+        let lowest = covered.iter().max_by_key(|p| p.1).unwrap();
+        assert!(leftmost.1 < lowest.1);
+    }
+
+    #[test]
+    fn test_arc_background_band() {
+        let mut canvas = OverlayCanvas::new(200, 200);
+        renderer().draw(&mut canvas, &overlay(TextAnchor::Arc), "Mira");
+        let (background_top, background_bottom) = count_color(&canvas, [0, 0, 255, 255]);
+        assert_eq!(background_top, 0);
+        assert!(background_bottom > 0);
+    }
+
+    #[test]
+    fn test_empty_text_draws_nothing() {
+        let mut canvas = OverlayCanvas::new(50, 50);
+        renderer().draw(&mut canvas, &overlay(TextAnchor::Bottom), "");
+        assert!(canvas.is_blank());
+
+        // 空画布不会 panic
+        let mut empty = OverlayCanvas::new(0, 0);
+        renderer().draw(&mut empty, &overlay(TextAnchor::Arc), "Mira");
+    }
+}
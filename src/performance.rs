@@ -1,5 +1,6 @@
 // 性能监控模块
 
+use crate::render::profiler::{FrameTimings, RenderPass};
 use log::{debug, info, warn};
 use std::collections::VecDeque;
 use std::time::{Duration, Instant};
@@ -22,6 +23,10 @@ pub struct PerformanceMonitor {
     report_interval: Duration,
     /// 性能警告阈值
     thresholds: PerformanceThresholds,
+    /// 空闲时（没有需要渲染的内容）最近一次采样的 CPU 使用率
+    idle_cpu_percent: Option<f32>,
+    /// 累计跳过的摄像头帧数
+    skipped_frames: u64,
 }
 
 /// 性能快照
@@ -33,6 +38,8 @@ pub struct PerformanceSnapshot {
     pub memory_mb: f32,
     pub frame_time_ms: f32,
     pub render_time_ms: f32,
+    /// CPU 编码时间与各阶段 GPU 耗时，未提供时为 None
+    pub timings: Option<FrameTimings>,
 }
 
 /// 性能阈值配置
@@ -75,11 +82,32 @@ impl PerformanceMonitor {
             last_report: Instant::now(),
             report_interval,
             thresholds: thresholds.unwrap_or_default(),
+            idle_cpu_percent: None,
+            skipped_frames: 0,
         }
     }
     
     /// 记录一帧的性能数据
     pub fn record_frame(&mut self, frame_time: Duration, render_time: Duration) -> Option<PerformanceAlert> {
+        self.record_frame_inner(frame_time, render_time, None)
+    }
+
+    /// 记录一帧的性能数据，同时记录渲染引擎提供的 CPU 编码时间和各阶段 GPU 耗时
+    pub fn record_frame_with_timings(
+        &mut self,
+        frame_time: Duration,
+        render_time: Duration,
+        timings: FrameTimings,
+    ) -> Option<PerformanceAlert> {
+        self.record_frame_inner(frame_time, render_time, Some(timings))
+    }
+
+    fn record_frame_inner(
+        &mut self,
+        frame_time: Duration,
+        render_time: Duration,
+        timings: Option<FrameTimings>,
+    ) -> Option<PerformanceAlert> {
         let now = Instant::now();
         
         // 更新 FPS 计算器
@@ -97,6 +125,7 @@ impl PerformanceMonitor {
             memory_mb,
             frame_time_ms: frame_time.as_secs_f32() * 1000.0,
             render_time_ms: render_time.as_secs_f32() * 1000.0,
+            timings,
         };
         
         // 添加到历史记录
@@ -120,6 +149,18 @@ impl PerformanceMonitor {
         alert
     }
     
+    /// 记录一次空闲等待：采样空闲时的 CPU 使用率，没有渲染时同样按间隔报告统计
+    pub fn record_idle(&mut self, skipped_frames: u64) {
+        let now = Instant::now();
+        self.skipped_frames = skipped_frames;
+        self.idle_cpu_percent = Some(self.cpu_monitor.get_usage());
+
+        if now.duration_since(self.last_report) >= self.report_interval {
+            self.report_performance_stats();
+            self.last_report = now;
+        }
+    }
+    
     /// 检查性能警告
     fn check_performance_alerts(&self, snapshot: &PerformanceSnapshot) -> Option<PerformanceAlert> {
         // 检查 FPS 过低
@@ -167,12 +208,14 @@ impl PerformanceMonitor {
     
     /// 报告性能统计信息
     fn report_performance_stats(&self) {
+        let stats = self.calculate_stats();
         if self.performance_history.is_empty() {
+            if let Some(idle_cpu) = stats.idle_cpu {
+                info!("空闲: CPU={:.1}%, 跳过帧={}", idle_cpu, stats.skipped_frames);
+            }
             return;
         }
         
-        let stats = self.calculate_stats();
-        
         info!("=== 性能统计报告 ===");
         info!("FPS: 当前={:.1}, 平均={:.1}, 最小={:.1}, 最大={:.1}", 
               stats.current_fps, stats.avg_fps, stats.min_fps, stats.max_fps);
@@ -184,6 +227,20 @@ impl PerformanceMonitor {
               stats.current_frame_time, stats.avg_frame_time, stats.max_frame_time);
         info!("渲染时间: 当前={:.1}ms, 平均={:.1}ms, 最大={:.1}ms", 
               stats.current_render_time, stats.avg_render_time, stats.max_render_time);
+        if let Some(cpu_encode) = stats.avg_cpu_encode_time {
+            info!("CPU 编码: 平均={:.2}ms, 最大={:.2}ms", cpu_encode, stats.max_cpu_encode_time);
+        }
+        if let Some(gpu_time) = stats.avg_gpu_time {
+            let passes: Vec<String> = RenderPass::ALL
+                .iter()
+                .zip(stats.avg_gpu_pass_times)
+                .filter_map(|(pass, time)| Some(format!("{}={:.2}ms", pass.name(), time?)))
+                .collect();
+            info!("GPU 时间: 平均={:.2}ms ({})", gpu_time, passes.join(", "));
+        }
+        if let Some(idle_cpu) = stats.idle_cpu {
+            info!("空闲: CPU={:.1}%, 跳过帧={}", idle_cpu, stats.skipped_frames);
+        }
         info!("样本数量: {}", stats.sample_count);
         info!("==================");
         
@@ -202,7 +259,11 @@ impl PerformanceMonitor {
     /// 计算性能统计信息
     fn calculate_stats(&self) -> PerformanceStats {
         if self.performance_history.is_empty() {
-            return PerformanceStats::default();
+            return PerformanceStats {
+                idle_cpu: self.idle_cpu_percent,
+                skipped_frames: self.skipped_frames,
+                ..PerformanceStats::default()
+            };
         }
         
         let current = &self.performance_history[self.performance_history.len() - 1];
@@ -220,6 +281,15 @@ impl PerformanceMonitor {
         let mut max_memory = f32::NEG_INFINITY;
         let mut max_frame_time = f32::NEG_INFINITY;
         let mut max_render_time = f32::NEG_INFINITY;
+
+        // 只统计带有计时数据的帧，各阶段分别按执行过的帧平均
+        let mut cpu_encode_sum = 0.0;
+        let mut cpu_encode_count = 0;
+        let mut max_cpu_encode_time = 0.0f32;
+        let mut gpu_pass_sums = [0.0f32; 4];
+        let mut gpu_pass_counts = [0u32; 4];
+        let mut gpu_sum = 0.0;
+        let mut gpu_count = 0;
         
         for snapshot in &self.performance_history {
             fps_sum += snapshot.fps;
@@ -234,6 +304,23 @@ impl PerformanceMonitor {
             max_memory = max_memory.max(snapshot.memory_mb);
             max_frame_time = max_frame_time.max(snapshot.frame_time_ms);
             max_render_time = max_render_time.max(snapshot.render_time_ms);
+
+            if let Some(timings) = &snapshot.timings {
+                let cpu_encode = timings.cpu_encode.as_secs_f32() * 1000.0;
+                cpu_encode_sum += cpu_encode;
+                cpu_encode_count += 1;
+                max_cpu_encode_time = max_cpu_encode_time.max(cpu_encode);
+                for (i, time) in timings.gpu_passes.iter().enumerate() {
+                    if let Some(time) = time {
+                        gpu_pass_sums[i] += time.as_secs_f32() * 1000.0;
+                        gpu_pass_counts[i] += 1;
+                    }
+                }
+                if let Some(total) = timings.gpu_total() {
+                    gpu_sum += total.as_secs_f32() * 1000.0;
+                    gpu_count += 1;
+                }
+            }
         }
         
         PerformanceStats {
@@ -254,6 +341,14 @@ impl PerformanceMonitor {
             avg_render_time: render_time_sum / count,
             max_render_time,
             sample_count: self.performance_history.len(),
+            avg_cpu_encode_time: (cpu_encode_count > 0).then(|| cpu_encode_sum / cpu_encode_count as f32),
+            max_cpu_encode_time,
+            avg_gpu_time: (gpu_count > 0).then(|| gpu_sum / gpu_count as f32),
+            avg_gpu_pass_times: std::array::from_fn(|i| {
+                (gpu_pass_counts[i] > 0).then(|| gpu_pass_sums[i] / gpu_pass_counts[i] as f32)
+            }),
+            idle_cpu: self.idle_cpu_percent,
+            skipped_frames: self.skipped_frames,
         }
     }
     
@@ -332,10 +427,15 @@ impl FpsCalculator {
 }
 
 /// CPU 使用率监控器
+///
+/// 能读取进程 CPU 时间的平台（Linux、Windows）统计本进程的使用率，
+/// 即两次采样之间进程占用的 CPU 时间占全部核心时间的百分比；其他平台使用系统整体负载
 struct CpuMonitor {
     last_check: Instant,
     check_interval: Duration,
     cached_usage: f32,
+    // 上次采样时的进程 CPU 时间
+    last_cpu_time: Option<Duration>,
 }
 
 impl CpuMonitor {
@@ -344,6 +444,7 @@ impl CpuMonitor {
             last_check: Instant::now(),
             check_interval: Duration::from_millis(500), // 每500ms检查一次
             cached_usage: 0.0,
+            last_cpu_time: process_cpu_time(),
         }
     }
     
@@ -351,7 +452,16 @@ impl CpuMonitor {
         let now = Instant::now();
         
         if now.duration_since(self.last_check) >= self.check_interval {
-            self.cached_usage = self.measure_cpu_usage();
+            self.cached_usage = match process_cpu_time() {
+                Some(cpu_time) => {
+                    let usage = self.last_cpu_time.map_or(self.cached_usage, |last| {
+                        process_cpu_percent(cpu_time.saturating_sub(last), now.duration_since(self.last_check))
+                    });
+                    self.last_cpu_time = Some(cpu_time);
+                    usage
+                }
+                None => self.measure_cpu_usage(),
+            };
             self.last_check = now;
         }
         
@@ -433,6 +543,67 @@ impl CpuMonitor {
     }
 }
 
+/// 进程 CPU 时间占全部核心时间的百分比
+fn process_cpu_percent(cpu_time: Duration, wall_time: Duration) -> f32 {
+    let capacity = wall_time.as_secs_f32() * num_cpus::get() as f32;
+    if capacity <= 0.0 {
+        return 0.0;
+    }
+    (cpu_time.as_secs_f32() / capacity * 100.0).clamp(0.0, 100.0)
+}
+
+/// 本进程累计占用的 CPU 时间（用户态 + 内核态）
+#[cfg(target_os = "linux")]
+fn process_cpu_time() -> Option<Duration> {
+    // /proc/self/stat 第 14、15 个字段为 utime 和 stime，单位为时钟节拍（USER_HZ，通常为 100）
+    const TICKS_PER_SECOND: u64 = 100;
+
+    let stat = std::fs::read_to_string("/proc/self/stat").ok()?;
+    // 进程名可能包含空格，从最后一个右括号之后开始解析（第 3 个字段起）
+    let mut fields = stat.rsplit_once(')')?.1.split_whitespace().skip(11);
+    let utime: u64 = fields.next()?.parse().ok()?;
+    let stime: u64 = fields.next()?.parse().ok()?;
+    Some(Duration::from_millis((utime + stime) * 1000 / TICKS_PER_SECOND))
+}
+
+/// 本进程累计占用的 CPU 时间（用户态 + 内核态）
+#[cfg(target_os = "windows")]
+fn process_cpu_time() -> Option<Duration> {
+    #[repr(C)]
+    #[derive(Default)]
+    struct FileTime {
+        low: u32,
+        high: u32,
+    }
+
+    extern "system" {
+        fn GetCurrentProcess() -> *mut std::ffi::c_void;
+        fn GetProcessTimes(
+            process: *mut std::ffi::c_void,
+            creation: *mut FileTime,
+            exit: *mut FileTime,
+            kernel: *mut FileTime,
+            user: *mut FileTime,
+        ) -> i32;
+    }
+
+    let (mut creation, mut exit, mut kernel, mut user) =
+        (FileTime::default(), FileTime::default(), FileTime::default(), FileTime::default());
+    let ok = unsafe { GetProcessTimes(GetCurrentProcess(), &mut creation, &mut exit, &mut kernel, &mut user) };
+    if ok == 0 {
+        return None;
+    }
+
+    // FILETIME 以 100 纳秒为单位
+    let ticks = |time: &FileTime| ((time.high as u64) << 32) | time.low as u64;
+    Some(Duration::from_nanos((ticks(&kernel) + ticks(&user)) * 100))
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "windows")))]
+fn process_cpu_time() -> Option<Duration> {
+    None
+}
+
 /// 内存使用监控器
 struct MemoryUsageMonitor {
     last_check: Instant,
@@ -622,6 +793,17 @@ pub struct PerformanceStats {
     pub avg_render_time: f32,
     pub max_render_time: f32,
     pub sample_count: usize,
+    /// CPU 编码命令的平均耗时（毫秒），渲染引擎未提供计时时为 None
+    pub avg_cpu_encode_time: Option<f32>,
+    pub max_cpu_encode_time: f32,
+    /// 每帧 GPU 耗时的平均值（毫秒），适配器不支持时间戳查询时为 None
+    pub avg_gpu_time: Option<f32>,
+    /// 各阶段 GPU 平均耗时（毫秒），按 RenderPass::ALL 顺序，未执行过的阶段为 None
+    pub avg_gpu_pass_times: [Option<f32>; 4],
+    /// 空闲时的 CPU 使用率，尚未空闲过时为 None
+    pub idle_cpu: Option<f32>,
+    /// 累计跳过的摄像头帧数
+    pub skipped_frames: u64,
 }
 
 #[cfg(test)]
@@ -697,6 +879,62 @@ mod tests {
         assert!(usage >= 0.0 && usage <= 100.0);
     }
     
+    #[test]
+    fn test_process_cpu_percent() {
+        let cores = num_cpus::get() as f32;
+        let usage = process_cpu_percent(Duration::from_millis(500), Duration::from_secs(1));
+        assert!((usage - 50.0 / cores).abs() < 1e-3);
+        assert_eq!(process_cpu_percent(Duration::from_secs(1), Duration::ZERO), 0.0);
+        assert_eq!(process_cpu_percent(Duration::from_secs(100), Duration::from_secs(1)), 100.0);
+
+        #[cfg(target_os = "linux")]
+        assert!(process_cpu_time().is_some());
+    }
+
+    #[test]
+    fn test_idle_stats() {
+        let mut monitor = PerformanceMonitor::new(10, Duration::from_secs(60), None);
+        assert_eq!(monitor.get_stats().idle_cpu, None);
+
+        monitor.record_idle(12);
+        let stats = monitor.get_stats();
+        assert_eq!(stats.sample_count, 0);
+        assert_eq!(stats.skipped_frames, 12);
+        assert!(stats.idle_cpu.is_some_and(|cpu| (0.0..=100.0).contains(&cpu)));
+    }
+    
+    #[test]
+    fn test_render_timing_stats() {
+        let mut monitor = PerformanceMonitor::new(10, Duration::from_secs(60), None);
+
+        // 没有计时数据（或适配器不支持时间戳查询）时只有墙钟时间
+        monitor.record_frame(Duration::from_millis(16), Duration::from_millis(8));
+        let stats = monitor.get_stats();
+        assert_eq!(stats.avg_cpu_encode_time, None);
+        assert_eq!(stats.avg_gpu_time, None);
+
+        let mut timings = FrameTimings {
+            cpu_encode: Duration::from_millis(2),
+            gpu_passes: [None, Some(Duration::from_millis(3)), None, None],
+        };
+        monitor.record_frame_with_timings(Duration::from_millis(16), Duration::from_millis(8), timings);
+        timings.cpu_encode = Duration::from_millis(4);
+        timings.gpu_passes[0] = Some(Duration::from_millis(1));
+        timings.gpu_passes[1] = Some(Duration::from_millis(5));
+        monitor.record_frame_with_timings(Duration::from_millis(16), Duration::from_millis(8), timings);
+
+        let stats = monitor.get_stats();
+        assert!((stats.avg_cpu_encode_time.unwrap() - 3.0).abs() < 1e-3);
+        assert!((stats.max_cpu_encode_time - 4.0).abs() < 1e-3);
+        assert!((stats.avg_gpu_time.unwrap() - 4.5).abs() < 1e-3);
+        assert!((stats.avg_gpu_pass_times[0].unwrap() - 1.0).abs() < 1e-3);
+        assert!((stats.avg_gpu_pass_times[1].unwrap() - 4.0).abs() < 1e-3);
+        assert_eq!(stats.avg_gpu_pass_times[2], None);
+
+        // 性能报告不应出错
+        monitor.report_performance_stats();
+    }
+    
     #[test]
     fn test_memory_monitor() {
         let mut monitor = MemoryUsageMonitor::new();
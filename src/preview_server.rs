@@ -0,0 +1,649 @@
+// 本地预览服务
+//
+// 只监听 127.0.0.1 的简易 HTTP 服务，供 OBS 浏览器源等本机程序使用合成画面：
+// - `/stream.mjpg`：multipart/x-mixed-replace 的 MJPEG 流，形状之外的透明区域合成到黑色背景
+// - `/snapshot.png`：带透明度的单帧 PNG
+// - `/`：状态页
+//
+// 只在有客户端观看或等待快照时回读画面，回读频率不超过配置的帧率。JPEG 在后台编码线程中进行，
+// 每个连接一个线程；慢客户端只会错过中间的帧，不影响渲染和其他客户端。只接受 Host 为本机地址的请求，
+// 避免网页通过 DNS 重绑定读取画面。
+
+use crate::render::capture::{CaptureThrottle, CapturedFrame};
+use image::codecs::jpeg::JpegEncoder;
+use image::codecs::png::PngEncoder;
+use image::{ColorType, ImageEncoder};
+use log::{debug, error, info, warn};
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, ErrorKind, Read, Write};
+use std::net::{Ipv4Addr, Shutdown, SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+/// MJPEG 各帧之间的分隔符
+const BOUNDARY: &str = "mira-frame";
+/// 读取请求和写入响应的超时，避免客户端停止读写后连接线程一直阻塞
+const IO_TIMEOUT: Duration = Duration::from_secs(2);
+/// 快照请求等待新画面的最长时间
+const SNAPSHOT_TIMEOUT: Duration = Duration::from_secs(2);
+/// 没有新连接时检查停止标志的间隔
+const ACCEPT_POLL_INTERVAL: Duration = Duration::from_millis(50);
+/// 拒绝连接时丢弃请求数据的等待时间
+const REJECT_DRAIN_TIMEOUT: Duration = Duration::from_millis(100);
+/// 请求头的最大行数
+const MAX_HEADER_LINES: usize = 64;
+
+/// 预览服务参数
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PreviewServerSettings {
+    /// 监听端口，0 表示由系统分配
+    pub port: u16,
+    /// 最高帧率
+    pub fps: u32,
+    /// 同时连接数上限
+    pub max_clients: usize,
+    /// JPEG 质量（1-100）
+    pub jpeg_quality: u8,
+}
+
+impl Default for PreviewServerSettings {
+    fn default() -> Self {
+        Self {
+            port: 8765,
+            fps: 15,
+            max_clients: 4,
+            jpeg_quality: 80,
+        }
+    }
+}
+
+/// 最新画面及其编码结果
+#[derive(Default)]
+struct Latest {
+    // 等待编码的画面，编码线程取走前被新帧替换
+    pending: Option<CapturedFrame>,
+    frame: Option<Arc<CapturedFrame>>,
+    // 没有客户端观看 MJPEG 流时不编码
+    jpeg: Option<Arc<Vec<u8>>>,
+    sequence: u64,
+    stopping: bool,
+}
+
+/// 各线程共享的状态
+struct Shared {
+    settings: PreviewServerSettings,
+    started: Instant,
+    latest: Mutex<Latest>,
+    changed: Condvar,
+    // 当前连接，停止时关闭以唤醒阻塞在读写上的连接线程
+    connections: Mutex<HashMap<u64, TcpStream>>,
+    streaming: AtomicUsize,
+    snapshot_waiting: AtomicUsize,
+    frames_sent: AtomicU64,
+}
+
+impl Shared {
+    fn latest(&self) -> MutexGuard<'_, Latest> {
+        self.latest.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn connections(&self) -> MutexGuard<'_, HashMap<u64, TcpStream>> {
+        self.connections.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// 本地预览服务：主线程节流回读并提交画面，后台线程编码并响应请求
+pub struct PreviewServer {
+    shared: Arc<Shared>,
+    local_addr: SocketAddr,
+    throttle: CaptureThrottle,
+    accept_handle: Option<JoinHandle<()>>,
+    encoder_handle: Option<JoinHandle<()>>,
+}
+
+impl PreviewServer {
+    /// 在本机回环地址上监听并启动服务线程
+    pub fn start(settings: PreviewServerSettings, now: Instant) -> Result<Self, String> {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, settings.port))
+            .map_err(|e| format!("无法监听端口 {}: {}", settings.port, e))?;
+        listener
+            .set_nonblocking(true)
+            .map_err(|e| format!("设置监听套接字失败: {}", e))?;
+        let local_addr = listener.local_addr().map_err(|e| format!("获取监听地址失败: {}", e))?;
+
+        let throttle = CaptureThrottle::new(settings.fps, now);
+        let shared = Arc::new(Shared {
+            settings,
+            started: now,
+            latest: Mutex::new(Latest::default()),
+            changed: Condvar::new(),
+            connections: Mutex::new(HashMap::new()),
+            streaming: AtomicUsize::new(0),
+            snapshot_waiting: AtomicUsize::new(0),
+            frames_sent: AtomicU64::new(0),
+        });
+
+        let encoder_handle = {
+            let shared = Arc::clone(&shared);
+            std::thread::Builder::new()
+                .name("preview-encoder".to_string())
+                .spawn(move || encode_frames(&shared))
+                .map_err(|e| format!("启动预览编码线程失败: {}", e))?
+        };
+        let mut server = Self {
+            shared: Arc::clone(&shared),
+            local_addr,
+            throttle,
+            accept_handle: None,
+            encoder_handle: Some(encoder_handle),
+        };
+        // 启动失败时 server 被丢弃，编码线程随之停止
+        server.accept_handle = Some(
+            std::thread::Builder::new()
+                .name("preview-server".to_string())
+                .spawn(move || accept_connections(listener, &shared))
+                .map_err(|e| format!("启动预览服务线程失败: {}", e))?,
+        );
+        Ok(server)
+    }
+
+    /// 实际监听的地址
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// 是否有客户端正在观看 MJPEG 流或等待快照
+    pub fn is_active(&self) -> bool {
+        self.shared.streaming.load(Ordering::Acquire) > 0 || self.shared.snapshot_waiting.load(Ordering::Acquire) > 0
+    }
+
+    /// 本帧是否需要回读画面：有客户端时按帧率上限节流
+    pub fn wants_frame(&mut self, now: Instant) -> bool {
+        self.is_active() && self.throttle.ready(now)
+    }
+
+    /// 提交回读完成的画面，由编码线程处理
+    pub fn push_frame(&self, frame: CapturedFrame) {
+        let mut latest = self.shared.latest();
+        latest.pending = Some(frame);
+        self.shared.changed.notify_all();
+    }
+}
+
+impl Drop for PreviewServer {
+    fn drop(&mut self) {
+        self.shared.latest().stopping = true;
+        self.shared.changed.notify_all();
+        if let Some(handle) = self.accept_handle.take() {
+            let _ = handle.join();
+        }
+        if let Some(handle) = self.encoder_handle.take() {
+            let _ = handle.join();
+        }
+        info!("预览服务已停止");
+    }
+}
+
+/// 编码线程：把提交的画面编码为 JPEG 并发布给各连接
+fn encode_frames(shared: &Shared) {
+    loop {
+        let frame = {
+            let mut latest = shared.latest();
+            loop {
+                if latest.stopping {
+                    return;
+                }
+                if let Some(frame) = latest.pending.take() {
+                    break frame;
+                }
+                latest = shared.changed.wait(latest).unwrap_or_else(|poisoned| poisoned.into_inner());
+            }
+        };
+
+        let jpeg = if shared.streaming.load(Ordering::Acquire) > 0 {
+            match encode_jpeg(&frame, shared.settings.jpeg_quality) {
+                Ok(jpeg) => Some(Arc::new(jpeg)),
+                Err(e) => {
+                    warn!("预览服务: {}", e);
+                    None
+                }
+            }
+        } else {
+            None
+        };
+
+        let mut latest = shared.latest();
+        latest.frame = Some(Arc::new(frame));
+        latest.jpeg = jpeg;
+        latest.sequence += 1;
+        shared.changed.notify_all();
+    }
+}
+
+/// 把画面合成到黑色背景后编码为 JPEG
+pub fn encode_jpeg(frame: &CapturedFrame, quality: u8) -> Result<Vec<u8>, String> {
+    let rgb: Vec<u8> = frame
+        .rgba
+        .chunks_exact(4)
+        .flat_map(|p| {
+            let a = p[3] as u32;
+            [0, 1, 2].map(|c| ((p[c] as u32 * a + 127) / 255) as u8)
+        })
+        .collect();
+    let mut jpeg = Vec::new();
+    JpegEncoder::new_with_quality(&mut jpeg, quality.clamp(1, 100))
+        .encode(&rgb, frame.width, frame.height, ColorType::Rgb8)
+        .map_err(|e| format!("JPEG 编码失败: {}", e))?;
+    Ok(jpeg)
+}
+
+/// 把画面编码为带透明度的 PNG
+pub fn encode_png(frame: &CapturedFrame) -> Result<Vec<u8>, String> {
+    let mut png = Vec::new();
+    PngEncoder::new(&mut png)
+        .write_image(&frame.rgba, frame.width, frame.height, ColorType::Rgba8)
+        .map_err(|e| format!("PNG 编码失败: {}", e))?;
+    Ok(png)
+}
+
+/// Host 请求头是否为本机地址（可带端口）
+pub fn is_local_host(host: &str) -> bool {
+    let host = host.trim();
+    let name = if let Some(rest) = host.strip_prefix('[') {
+        // IPv6 地址：[::1]:8765
+        rest.split(']').next().unwrap_or("")
+    } else {
+        host.split(':').next().unwrap_or("")
+    };
+    matches!(name.to_ascii_lowercase().as_str(), "localhost" | "127.0.0.1" | "::1")
+}
+
+/// 监听线程：接受连接并为每个连接启动线程，停止时关闭所有连接
+fn accept_connections(listener: TcpListener, shared: &Arc<Shared>) {
+    let mut handles: Vec<JoinHandle<()>> = Vec::new();
+    let mut next_id = 0u64;
+    loop {
+        if shared.latest().stopping {
+            break;
+        }
+        handles.retain(|handle| !handle.is_finished());
+        let (stream, peer) = match listener.accept() {
+            Ok(connection) => connection,
+            Err(e) if e.kind() == ErrorKind::WouldBlock => {
+                std::thread::sleep(ACCEPT_POLL_INTERVAL);
+                continue;
+            }
+            Err(e) => {
+                warn!("接受预览连接失败: {}", e);
+                std::thread::sleep(ACCEPT_POLL_INTERVAL);
+                continue;
+            }
+        };
+        if let Err(e) = prepare_stream(&stream) {
+            debug!("设置预览连接失败: {}", e);
+            continue;
+        }
+
+        // 达到连接数上限时直接拒绝
+        if shared.connections().len() >= shared.settings.max_clients {
+            debug!("预览连接数已达上限，拒绝 {}", peer);
+            reject(&stream);
+            continue;
+        }
+        let registered = match stream.try_clone() {
+            Ok(clone) => clone,
+            Err(e) => {
+                debug!("复制预览连接失败: {}", e);
+                continue;
+            }
+        };
+        let id = next_id;
+        next_id += 1;
+        shared.connections().insert(id, registered);
+
+        let connection_shared = Arc::clone(shared);
+        let spawned = std::thread::Builder::new()
+            .name("preview-client".to_string())
+            .spawn(move || {
+                if let Err(e) = handle_connection(&stream, &connection_shared) {
+                    debug!("预览连接 {} 结束: {}", peer, e);
+                }
+                connection_shared.connections().remove(&id);
+            });
+        match spawned {
+            Ok(handle) => handles.push(handle),
+            Err(e) => {
+                error!("启动预览连接线程失败: {}", e);
+                shared.connections().remove(&id);
+            }
+        }
+    }
+
+    // 关闭所有连接，唤醒阻塞在读写上的连接线程
+    for stream in shared.connections().values() {
+        let _ = stream.shutdown(Shutdown::Both);
+    }
+    for handle in handles {
+        let _ = handle.join();
+    }
+}
+
+fn prepare_stream(stream: &TcpStream) -> std::io::Result<()> {
+    stream.set_nonblocking(false)?;
+    stream.set_read_timeout(Some(IO_TIMEOUT))?;
+    stream.set_write_timeout(Some(IO_TIMEOUT))?;
+    stream.set_nodelay(true)
+}
+
+/// 以 503 拒绝超出连接数上限的连接。先丢弃已收到的请求再关闭，否则未读的数据会使客户端收到连接重置而读不到响应
+fn reject(mut stream: &TcpStream) {
+    let _ = write_response(stream, "503 Service Unavailable", "text/plain; charset=utf-8", "连接数已达上限\n".as_bytes());
+    let _ = stream.shutdown(Shutdown::Write);
+    let _ = stream.set_read_timeout(Some(REJECT_DRAIN_TIMEOUT));
+    let mut buffer = [0; 1024];
+    for _ in 0..16 {
+        match stream.read(&mut buffer) {
+            Ok(read) if read > 0 => {}
+            _ => break,
+        }
+    }
+}
+
+/// 读取请求行和 Host 请求头，返回 (方法, 路径, Host)
+fn read_request(stream: &TcpStream) -> std::io::Result<(String, String, Option<String>)> {
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+    let mut parts = line.split_whitespace();
+    let method = parts.next().unwrap_or("").to_string();
+    // 去掉查询参数
+    let path = parts.next().unwrap_or("").split('?').next().unwrap_or("").to_string();
+
+    let mut host = None;
+    for _ in 0..MAX_HEADER_LINES {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 || line.trim().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("host") {
+                host = Some(value.trim().to_string());
+            }
+        }
+    }
+    Ok((method, path, host))
+}
+
+fn write_response(mut stream: &TcpStream, status: &str, content_type: &str, body: &[u8]) -> std::io::Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nCache-Control: no-cache\r\nConnection: close\r\n\r\n",
+        status,
+        content_type,
+        body.len()
+    )?;
+    stream.write_all(body)?;
+    stream.flush()
+}
+
+fn handle_connection(stream: &TcpStream, shared: &Shared) -> std::io::Result<()> {
+    let (method, path, host) = read_request(stream)?;
+    if !host.as_deref().is_some_and(is_local_host) {
+        return write_response(stream, "403 Forbidden", "text/plain; charset=utf-8", "只接受本机请求\n".as_bytes());
+    }
+    if method != "GET" {
+        return write_response(stream, "405 Method Not Allowed", "text/plain; charset=utf-8", "只支持 GET\n".as_bytes());
+    }
+    match path.as_str() {
+        "/" => write_response(stream, "200 OK", "text/html; charset=utf-8", status_page(shared).as_bytes()),
+        "/stream.mjpg" => {
+            shared.streaming.fetch_add(1, Ordering::AcqRel);
+            let result = stream_mjpeg(stream, shared);
+            shared.streaming.fetch_sub(1, Ordering::AcqRel);
+            result
+        }
+        "/snapshot.png" => {
+            shared.snapshot_waiting.fetch_add(1, Ordering::AcqRel);
+            let frame = wait_for_snapshot(shared);
+            shared.snapshot_waiting.fetch_sub(1, Ordering::AcqRel);
+            match frame.map(|frame| encode_png(&frame)) {
+                Some(Ok(png)) => write_response(stream, "200 OK", "image/png", &png),
+                Some(Err(e)) => {
+                    warn!("预览服务: {}", e);
+                    write_response(stream, "500 Internal Server Error", "text/plain; charset=utf-8", "编码失败\n".as_bytes())
+                }
+                None => write_response(stream, "503 Service Unavailable", "text/plain; charset=utf-8", "暂无画面\n".as_bytes()),
+            }
+        }
+        _ => write_response(stream, "404 Not Found", "text/plain; charset=utf-8", "未找到\n".as_bytes()),
+    }
+}
+
+/// 持续发送新编码的 JPEG，直到客户端断开或服务停止
+fn stream_mjpeg(mut stream: &TcpStream, shared: &Shared) -> std::io::Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 200 OK\r\nContent-Type: multipart/x-mixed-replace; boundary={}\r\nCache-Control: no-cache\r\nConnection: close\r\n\r\n",
+        BOUNDARY
+    )?;
+    let mut sent_sequence = 0;
+    loop {
+        let jpeg = {
+            let mut latest = shared.latest();
+            loop {
+                if latest.stopping {
+                    return Ok(());
+                }
+                if latest.sequence > sent_sequence {
+                    if let Some(jpeg) = &latest.jpeg {
+                        sent_sequence = latest.sequence;
+                        break Arc::clone(jpeg);
+                    }
+                }
+                latest = shared.changed.wait(latest).unwrap_or_else(|poisoned| poisoned.into_inner());
+            }
+        };
+        write!(
+            stream,
+            "--{}\r\nContent-Type: image/jpeg\r\nContent-Length: {}\r\n\r\n",
+            BOUNDARY,
+            jpeg.len()
+        )?;
+        stream.write_all(&jpeg)?;
+        stream.write_all(b"\r\n")?;
+        stream.flush()?;
+        shared.frames_sent.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// 等待请求之后回读的新画面；超时时使用已有的最新画面
+fn wait_for_snapshot(shared: &Shared) -> Option<Arc<CapturedFrame>> {
+    let deadline = Instant::now() + SNAPSHOT_TIMEOUT;
+    let mut latest = shared.latest();
+    let requested = latest.sequence;
+    while latest.sequence == requested && !latest.stopping {
+        let now = Instant::now();
+        if now >= deadline {
+            break;
+        }
+        latest = shared
+            .changed
+            .wait_timeout(latest, deadline - now)
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .0;
+    }
+    latest.frame.clone()
+}
+
+fn status_page(shared: &Shared) -> String {
+    let size = match &shared.latest().frame {
+        Some(frame) => format!("{}x{}", frame.width, frame.height),
+        None => "暂无".to_string(),
+    };
+    format!(
+        "<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"><title>Mira 预览</title></head><body>\n\
+         <h1>Mira 预览服务</h1>\n<ul>\n\
+         <li>MJPEG 流：<a href=\"/stream.mjpg\">/stream.mjpg</a></li>\n\
+         <li>PNG 快照：<a href=\"/snapshot.png\">/snapshot.png</a></li>\n\
+         <li>连接数：{} / {}</li>\n<li>正在观看：{}</li>\n<li>帧率上限：{} fps</li>\n\
+         <li>画面尺寸：{}</li>\n<li>已发送帧数：{}</li>\n<li>运行时间：{} 秒</li>\n\
+         </ul>\n</body></html>\n",
+        shared.connections().len(),
+        shared.settings.max_clients,
+        shared.streaming.load(Ordering::Acquire),
+        shared.settings.fps,
+        size,
+        shared.frames_sent.load(Ordering::Relaxed),
+        shared.started.elapsed().as_secs()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::capture::CaptureSource;
+
+    fn test_settings(max_clients: usize) -> PreviewServerSettings {
+        PreviewServerSettings {
+            port: 0,
+            fps: 30,
+            max_clients,
+            jpeg_quality: 80,
+        }
+    }
+
+    fn test_frame(color: [u8; 4]) -> CapturedFrame {
+        CapturedFrame {
+            source: CaptureSource::Composited,
+            width: 8,
+            height: 4,
+            rgba: color.repeat(32),
+            captured_at: Instant::now(),
+        }
+    }
+
+    fn connect(server: &PreviewServer, path: &str, host: &str) -> TcpStream {
+        let mut stream = TcpStream::connect(server.local_addr()).unwrap();
+        stream.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+        write!(stream, "GET {} HTTP/1.1\r\nHost: {}\r\n\r\n", path, host).unwrap();
+        stream
+    }
+
+    fn get(server: &PreviewServer, path: &str) -> Vec<u8> {
+        let mut response = Vec::new();
+        connect(server, path, "localhost").read_to_end(&mut response).unwrap();
+        response
+    }
+
+    fn body(response: &[u8]) -> &[u8] {
+        let start = response.windows(4).position(|w| w == b"\r\n\r\n").unwrap() + 4;
+        &response[start..]
+    }
+
+    /// 读到包含 pattern 为止
+    fn read_until(stream: &mut TcpStream, pattern: &[u8]) -> Vec<u8> {
+        let mut data = Vec::new();
+        let mut buffer = [0; 1024];
+        while !data.windows(pattern.len()).any(|w| w == pattern) {
+            let read = stream.read(&mut buffer).unwrap();
+            assert!(read > 0, "连接提前关闭");
+            data.extend_from_slice(&buffer[..read]);
+        }
+        data
+    }
+
+    fn wait_until(mut condition: impl FnMut() -> bool) {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !condition() {
+            assert!(Instant::now() < deadline, "等待超时");
+            std::thread::sleep(Duration::from_millis(5));
+        }
+    }
+
+    #[test]
+    fn test_is_local_host() {
+        assert!(is_local_host("localhost"));
+        assert!(is_local_host("LocalHost:8765"));
+        assert!(is_local_host("127.0.0.1:8765"));
+        assert!(is_local_host("[::1]:8765"));
+        assert!(!is_local_host("evil.example:8765"));
+        assert!(!is_local_host("localhost.evil.example"));
+        assert!(!is_local_host(""));
+    }
+
+    #[test]
+    fn test_encode_jpeg_and_png() {
+        let frame = test_frame([255, 0, 0, 128]);
+        let jpeg = encode_jpeg(&frame, 90).unwrap();
+        let decoded = image::load_from_memory(&jpeg).unwrap().to_rgb8();
+        // 合成到黑色背景
+        let pixel = decoded.get_pixel(4, 2);
+        assert!((pixel[0] as i32 - 128).abs() <= 8 && pixel[1] < 16 && pixel[2] < 16);
+
+        let png = encode_png(&frame).unwrap();
+        let decoded = image::load_from_memory(&png).unwrap().to_rgba8();
+        assert_eq!(decoded.get_pixel(4, 2).0, [255, 0, 0, 128]);
+    }
+
+    #[test]
+    fn test_status_page_and_errors() {
+        let server = PreviewServer::start(test_settings(4), Instant::now()).unwrap();
+        assert!(server.local_addr().ip().is_loopback());
+
+        let response = get(&server, "/");
+        assert!(response.starts_with(b"HTTP/1.1 200 OK\r\n"));
+        assert!(String::from_utf8_lossy(body(&response)).contains("Mira 预览服务"));
+
+        assert!(get(&server, "/missing").starts_with(b"HTTP/1.1 404"));
+        let mut response = Vec::new();
+        connect(&server, "/", "evil.example").read_to_end(&mut response).unwrap();
+        assert!(response.starts_with(b"HTTP/1.1 403"));
+    }
+
+    #[test]
+    fn test_snapshot_endpoint() {
+        let mut server = PreviewServer::start(test_settings(4), Instant::now()).unwrap();
+        assert!(!server.wants_frame(Instant::now()));
+
+        let mut stream = connect(&server, "/snapshot.png", "127.0.0.1");
+        // 等待快照的客户端触发回读
+        wait_until(|| server.is_active());
+        assert!(server.wants_frame(Instant::now() + Duration::from_secs(1)));
+        server.push_frame(test_frame([0, 255, 0, 64]));
+
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response).unwrap();
+        assert!(response.starts_with(b"HTTP/1.1 200 OK\r\n"));
+        let decoded = image::load_from_memory(body(&response)).unwrap().to_rgba8();
+        assert_eq!(decoded.dimensions(), (8, 4));
+        assert_eq!(decoded.get_pixel(0, 0).0, [0, 255, 0, 64]);
+        wait_until(|| !server.is_active());
+    }
+
+    #[test]
+    fn test_mjpeg_stream_and_client_limit() {
+        let server = PreviewServer::start(test_settings(1), Instant::now()).unwrap();
+        let mut stream = connect(&server, "/stream.mjpg", "localhost");
+        let headers = read_until(&mut stream, b"\r\n\r\n");
+        assert!(String::from_utf8_lossy(&headers).contains("multipart/x-mixed-replace; boundary=mira-frame"));
+        wait_until(|| server.is_active());
+
+        // 超过连接数上限
+        let mut response = Vec::new();
+        connect(&server, "/", "localhost").read_to_end(&mut response).unwrap();
+        assert!(response.starts_with(b"HTTP/1.1 503"));
+
+        server.push_frame(test_frame([255, 255, 255, 255]));
+        let part = read_until(&mut stream, b"\xff\xd8");
+        assert!(String::from_utf8_lossy(&part).contains("--mira-frame\r\nContent-Type: image/jpeg\r\n"));
+
+        // 停止服务时关闭正在观看的连接
+        let stopping = Instant::now();
+        drop(server);
+        assert!(stopping.elapsed() < Duration::from_secs(1));
+        let mut rest = Vec::new();
+        let _ = stream.read_to_end(&mut rest);
+    }
+}
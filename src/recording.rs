@@ -0,0 +1,848 @@
+// 录制模块
+//
+// 录制期间按配置的帧率向 RenderEngine 请求合成画面回读（见 render/capture.rs），回读完成的画面交给
+// 后台编码线程写入 APNG、GIF 或 PNG 序列，形状之外保持透明（GIF 只区分全透明和不透明）。
+// 每帧带有回读时的时间戳，显示时长取与下一帧的时间差并累计取整，丢帧或帧率波动时回放速度仍与实际一致。
+// 编码线程来不及处理时丢弃新帧；达到最长时长或文件大小上限时自动停止。
+//
+// image 0.24 只能编码静态 WebP，动画 WebP 暂不支持。
+
+use crate::render::capture::{CaptureThrottle, CapturedFrame};
+use chrono::{DateTime, Local};
+use image::codecs::gif::{GifEncoder, Repeat};
+use image::{Delay, Frame, RgbaImage};
+use log::{debug, error, info, warn};
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, SyncSender, TrySendError};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+/// 等待编码的最大帧数，超过时丢弃新帧
+const QUEUE_CAPACITY: usize = 8;
+
+/// GIF 颜色量化速度（1-30），越大越快、质量越低
+const GIF_SPEED: i32 = 10;
+
+/// PNG 序列中记录每帧显示时长的文件，可直接用 ffmpeg -f concat 读取
+const SEQUENCE_INDEX: &str = "frames.ffconcat";
+
+/// 录制文件格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecordingFormat {
+    /// 动画 PNG，保留完整透明度
+    Apng,
+    /// GIF，256 色，只有全透明和不透明
+    Gif,
+    /// 每帧一个 PNG 文件，附带时长索引
+    PngSequence,
+}
+
+impl RecordingFormat {
+    /// 从配置名称解析格式
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "Apng" => Some(RecordingFormat::Apng),
+            "Gif" => Some(RecordingFormat::Gif),
+            "PngSequence" => Some(RecordingFormat::PngSequence),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            RecordingFormat::Apng => "APNG",
+            RecordingFormat::Gif => "GIF",
+            RecordingFormat::PngSequence => "PNG 序列",
+        }
+    }
+}
+
+/// 录制参数
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordingSettings {
+    pub format: RecordingFormat,
+    /// 回读画面的最高帧率
+    pub fps: u32,
+    pub max_duration: Duration,
+    /// 文件大小上限（字节），PNG 序列为所有文件之和
+    pub max_bytes: u64,
+}
+
+impl Default for RecordingSettings {
+    fn default() -> Self {
+        Self {
+            format: RecordingFormat::Apng,
+            fps: 15,
+            max_duration: Duration::from_secs(60),
+            max_bytes: 200 * 1024 * 1024,
+        }
+    }
+}
+
+/// 带时间戳的录制文件名；PNG 序列为目录名
+pub fn recording_file_name(time: DateTime<Local>, format: RecordingFormat) -> String {
+    let stem = format!("mira-rec-{}", time.format("%Y%m%d-%H%M%S"));
+    match format {
+        RecordingFormat::Apng => format!("{}.png", stem),
+        RecordingFormat::Gif => format!("{}.gif", stem),
+        RecordingFormat::PngSequence => stem,
+    }
+}
+
+/// 把帧时间戳换算为指定单位的显示时长；按累计时间取整，长时间录制后总时长不漂移
+#[derive(Debug, Clone, Copy)]
+pub struct FrameClock {
+    start: Instant,
+    units_per_second: u64,
+    elapsed_units: u64,
+}
+
+impl FrameClock {
+    pub fn new(start: Instant, units_per_second: u64) -> Self {
+        Self {
+            start,
+            units_per_second,
+            elapsed_units: 0,
+        }
+    }
+
+    /// 当前帧显示到 until 为止的时长，至少 1 个单位
+    pub fn advance(&mut self, until: Instant) -> u64 {
+        let elapsed = until.saturating_duration_since(self.start).as_secs_f64();
+        let target = (elapsed * self.units_per_second as f64).round() as u64;
+        let delay = target.saturating_sub(self.elapsed_units).max(1);
+        self.elapsed_units += delay;
+        delay
+    }
+}
+
+/// 把画面等比缩放到录制尺寸，居中放在透明画布上（录制中调整窗口大小时）
+pub fn fit_frame(frame: &CapturedFrame, width: u32, height: u32) -> Vec<u8> {
+    if frame.width == width && frame.height == height {
+        return frame.rgba.clone();
+    }
+    let Some(image) = RgbaImage::from_raw(frame.width, frame.height, frame.rgba.clone()) else {
+        return vec![0; width as usize * height as usize * 4];
+    };
+    let scale = (width as f32 / frame.width as f32).min(height as f32 / frame.height as f32);
+    let scaled_width = ((frame.width as f32 * scale).round() as u32).clamp(1, width);
+    let scaled_height = ((frame.height as f32 * scale).round() as u32).clamp(1, height);
+    let scaled = image::imageops::resize(&image, scaled_width, scaled_height, image::imageops::FilterType::Triangle);
+    let mut canvas = RgbaImage::new(width, height);
+    image::imageops::overlay(
+        &mut canvas,
+        &scaled,
+        ((width - scaled_width) / 2) as i64,
+        ((height - scaled_height) / 2) as i64,
+    );
+    canvas.into_raw()
+}
+
+/// 统计写入字节数的写入器
+struct CountingWriter<W> {
+    inner: W,
+    written: Arc<AtomicU64>,
+}
+
+impl<W: Write> Write for CountingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.written.fetch_add(written as u64, Ordering::Relaxed);
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// 编码输出
+trait FrameSink {
+    /// 写入一帧，显示到 until 为止
+    fn write_frame(&mut self, rgba: &[u8], until: Instant) -> Result<(), String>;
+    /// 已写入的字节数
+    fn bytes_written(&self) -> u64;
+    /// 结束写入，返回最终的字节数
+    fn finish(self: Box<Self>) -> Result<u64, String>;
+}
+
+/// 动画 PNG 输出
+///
+/// acTL 块中的帧数需要在写文件头时给出，录制时先写入最大值，结束后回填实际帧数。
+struct ApngSink {
+    path: PathBuf,
+    writer: png::Writer<CountingWriter<BufWriter<File>>>,
+    written: Arc<AtomicU64>,
+    clock: FrameClock,
+    frames: u32,
+}
+
+impl ApngSink {
+    fn create(path: &Path, width: u32, height: u32, start: Instant) -> Result<Self, String> {
+        let file = File::create(path).map_err(|e| format!("无法创建录制文件 {:?}: {}", path, e))?;
+        let written = Arc::new(AtomicU64::new(0));
+        let output = CountingWriter {
+            inner: BufWriter::new(file),
+            written: Arc::clone(&written),
+        };
+        let mut encoder = png::Encoder::new(output, width, height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.set_compression(png::Compression::Fast);
+        encoder.set_animated(i32::MAX as u32, 0).map_err(|e| format!("设置 APNG 动画参数失败: {}", e))?;
+        let writer = encoder.write_header().map_err(|e| format!("写入 APNG 文件头失败: {}", e))?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            writer,
+            written,
+            clock: FrameClock::new(start, 1000),
+            frames: 0,
+        })
+    }
+}
+
+impl FrameSink for ApngSink {
+    fn write_frame(&mut self, rgba: &[u8], until: Instant) -> Result<(), String> {
+        let delay_ms = self.clock.advance(until).min(u16::MAX as u64) as u16;
+        self.writer
+            .set_frame_delay(delay_ms, 1000)
+            .and_then(|_| self.writer.write_image_data(rgba))
+            .map_err(|e| format!("写入 APNG 帧失败: {}", e))?;
+        self.frames += 1;
+        Ok(())
+    }
+
+    fn bytes_written(&self) -> u64 {
+        self.written.load(Ordering::Relaxed)
+    }
+
+    fn finish(self: Box<Self>) -> Result<u64, String> {
+        let Self { path, writer, written, frames, .. } = *self;
+        writer.finish().map_err(|e| format!("结束 APNG 文件失败: {}", e))?;
+        patch_apng_frame_count(&path, frames)?;
+        Ok(written.load(Ordering::Relaxed))
+    }
+}
+
+/// 回填 APNG 的 acTL 帧数并重新计算校验和
+fn patch_apng_frame_count(path: &Path, frames: u32) -> Result<(), String> {
+    let mut file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(path)
+        .map_err(|e| format!("无法打开录制文件 {:?}: {}", path, e))?;
+    // acTL 紧跟在 IHDR 之后，只在文件开头查找
+    let mut head = vec![0u8; 256];
+    let read = file.read(&mut head).map_err(|e| format!("读取录制文件失败: {}", e))?;
+    let Some(type_offset) = head[..read].windows(4).position(|window| window == b"acTL") else {
+        return Err("录制文件中找不到 acTL 块".to_string());
+    };
+    let mut chunk = [0u8; 12];
+    chunk[..4].copy_from_slice(b"acTL");
+    chunk[4..8].copy_from_slice(&frames.to_be_bytes());
+    chunk[8..12].copy_from_slice(&head[type_offset + 8..type_offset + 12]);
+    file.seek(SeekFrom::Start(type_offset as u64 + 4))
+        .and_then(|_| file.write_all(&chunk[4..8]))
+        .and_then(|_| file.seek(SeekFrom::Start(type_offset as u64 + 12)))
+        .and_then(|_| file.write_all(&crc32(&chunk).to_be_bytes()))
+        .map_err(|e| format!("回填 APNG 帧数失败: {}", e))
+}
+
+/// PNG 块使用的 CRC-32
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ 0xEDB8_8320 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+/// GIF 输出
+struct GifSink {
+    encoder: GifEncoder<CountingWriter<BufWriter<File>>>,
+    written: Arc<AtomicU64>,
+    clock: FrameClock,
+    width: u32,
+    height: u32,
+}
+
+impl GifSink {
+    fn create(path: &Path, width: u32, height: u32, start: Instant) -> Result<Self, String> {
+        let file = File::create(path).map_err(|e| format!("无法创建录制文件 {:?}: {}", path, e))?;
+        let written = Arc::new(AtomicU64::new(0));
+        let output = CountingWriter {
+            inner: BufWriter::new(file),
+            written: Arc::clone(&written),
+        };
+        let mut encoder = GifEncoder::new_with_speed(output, GIF_SPEED);
+        encoder.set_repeat(Repeat::Infinite).map_err(|e| format!("设置 GIF 循环失败: {}", e))?;
+        Ok(Self {
+            encoder,
+            written,
+            // GIF 的帧延迟以百分之一秒为单位
+            clock: FrameClock::new(start, 100),
+            width,
+            height,
+        })
+    }
+}
+
+impl FrameSink for GifSink {
+    fn write_frame(&mut self, rgba: &[u8], until: Instant) -> Result<(), String> {
+        let delay_cs = self.clock.advance(until).min(u16::MAX as u64) as u32;
+        let image = RgbaImage::from_raw(self.width, self.height, rgba.to_vec())
+            .ok_or_else(|| "GIF 帧尺寸不匹配".to_string())?;
+        let frame = Frame::from_parts(image, 0, 0, Delay::from_numer_denom_ms(delay_cs * 10, 1));
+        self.encoder.encode_frame(frame).map_err(|e| format!("写入 GIF 帧失败: {}", e))
+    }
+
+    fn bytes_written(&self) -> u64 {
+        self.written.load(Ordering::Relaxed)
+    }
+
+    fn finish(self: Box<Self>) -> Result<u64, String> {
+        // 编码器析构时写入 GIF 结尾并刷新文件
+        drop(self.encoder);
+        Ok(self.written.load(Ordering::Relaxed))
+    }
+}
+
+/// PNG 序列输出
+struct PngSequenceSink {
+    directory: PathBuf,
+    index: BufWriter<File>,
+    clock: FrameClock,
+    width: u32,
+    height: u32,
+    frames: u32,
+    written: u64,
+}
+
+impl PngSequenceSink {
+    fn create(directory: &Path, width: u32, height: u32, start: Instant) -> Result<Self, String> {
+        std::fs::create_dir_all(directory).map_err(|e| format!("无法创建录制目录 {:?}: {}", directory, e))?;
+        let index_path = directory.join(SEQUENCE_INDEX);
+        let mut index = BufWriter::new(
+            File::create(&index_path).map_err(|e| format!("无法创建 {:?}: {}", index_path, e))?,
+        );
+        writeln!(index, "ffconcat version 1.0").map_err(|e| format!("写入帧索引失败: {}", e))?;
+        Ok(Self {
+            directory: directory.to_path_buf(),
+            index,
+            clock: FrameClock::new(start, 1000),
+            width,
+            height,
+            frames: 0,
+            written: 0,
+        })
+    }
+
+    fn frame_name(index: u32) -> String {
+        format!("frame-{:06}.png", index)
+    }
+}
+
+impl FrameSink for PngSequenceSink {
+    fn write_frame(&mut self, rgba: &[u8], until: Instant) -> Result<(), String> {
+        self.frames += 1;
+        let name = Self::frame_name(self.frames);
+        let path = self.directory.join(&name);
+        image::save_buffer(&path, rgba, self.width, self.height, image::ColorType::Rgba8)
+            .map_err(|e| format!("写入 {:?} 失败: {}", path, e))?;
+        self.written += std::fs::metadata(&path).map(|metadata| metadata.len()).unwrap_or(0);
+
+        let delay_ms = self.clock.advance(until);
+        writeln!(self.index, "file '{}'\nduration {}.{:03}", name, delay_ms / 1000, delay_ms % 1000)
+            .map_err(|e| format!("写入帧索引失败: {}", e))
+    }
+
+    fn bytes_written(&self) -> u64 {
+        self.written
+    }
+
+    fn finish(mut self: Box<Self>) -> Result<u64, String> {
+        // concat 分离器会忽略最后一项的 duration，最后一帧需要重复列出一次
+        if self.frames > 0 {
+            writeln!(self.index, "file '{}'", Self::frame_name(self.frames))
+                .map_err(|e| format!("写入帧索引失败: {}", e))?;
+        }
+        self.index.flush().map_err(|e| format!("写入帧索引失败: {}", e))?;
+        Ok(self.written)
+    }
+}
+
+fn create_sink(
+    format: RecordingFormat,
+    path: &Path,
+    width: u32,
+    height: u32,
+    start: Instant,
+) -> Result<Box<dyn FrameSink>, String> {
+    Ok(match format {
+        RecordingFormat::Apng => Box::new(ApngSink::create(path, width, height, start)?),
+        RecordingFormat::Gif => Box::new(GifSink::create(path, width, height, start)?),
+        RecordingFormat::PngSequence => Box::new(PngSequenceSink::create(path, width, height, start)?),
+    })
+}
+
+/// 发给编码线程的消息
+enum EncoderMessage {
+    Frame(CapturedFrame),
+    /// 停止录制：最后一帧显示到 end 为止
+    Finish { end: Instant, dropped: u64 },
+}
+
+/// 编码结果
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordingSummary {
+    pub frames: u32,
+    /// 第一帧到结束的时长
+    pub duration: Duration,
+    pub bytes: u64,
+    /// 达到文件大小上限而提前结束
+    pub size_limited: bool,
+}
+
+/// 编码线程主体：缓存一帧，收到下一帧或停止消息时才知道它的显示时长
+fn encode_frames(
+    receiver: Receiver<EncoderMessage>,
+    format: RecordingFormat,
+    path: &Path,
+    max_bytes: u64,
+) -> Result<RecordingSummary, String> {
+    let mut sink: Option<Box<dyn FrameSink>> = None;
+    let mut pending: Option<CapturedFrame> = None;
+    let mut first_frame: Option<Instant> = None;
+    let mut size = (0, 0);
+    let mut frames = 0;
+    let mut size_limited = false;
+
+    let end = loop {
+        let message = receiver.recv().unwrap_or_else(|_| EncoderMessage::Finish {
+            end: Instant::now(),
+            dropped: 0,
+        });
+        let (next, end) = match message {
+            EncoderMessage::Frame(frame) => (Some(frame), None),
+            EncoderMessage::Finish { end, dropped } => {
+                if dropped > 0 {
+                    info!("录制期间编码不及时，丢弃了 {} 帧", dropped);
+                }
+                (None, Some(end))
+            }
+        };
+        if let Some(frame) = pending.take() {
+            let until = next.as_ref().map_or_else(|| end.unwrap(), |next| next.captured_at);
+            let sink = match sink.as_mut() {
+                Some(sink) => sink,
+                None => {
+                    size = (frame.width, frame.height);
+                    first_frame = Some(frame.captured_at);
+                    sink.insert(create_sink(format, path, frame.width, frame.height, frame.captured_at)?)
+                }
+            };
+            sink.write_frame(&fit_frame(&frame, size.0, size.1), until)?;
+            frames += 1;
+            if sink.bytes_written() >= max_bytes {
+                size_limited = true;
+                break until;
+            }
+        }
+        if let Some(end) = end {
+            break end;
+        }
+        pending = next;
+    };
+
+    let bytes = match sink {
+        Some(sink) => sink.finish()?,
+        None => 0,
+    };
+    Ok(RecordingSummary {
+        frames,
+        duration: first_frame.map_or(Duration::ZERO, |first| end.saturating_duration_since(first)),
+        bytes,
+        size_limited,
+    })
+}
+
+/// 进行中的录制
+struct ActiveRecording {
+    sender: SyncSender<EncoderMessage>,
+    handle: JoinHandle<()>,
+    settings: RecordingSettings,
+    started: Instant,
+    throttle: CaptureThrottle,
+    dropped: u64,
+    // 编码线程已自行结束（达到大小上限或出错）
+    encoder_stopped: Arc<AtomicBool>,
+}
+
+/// 录制控制：在主线程中节流回读请求、把画面交给编码线程
+#[derive(Default)]
+pub struct Recorder {
+    active: Option<ActiveRecording>,
+    // 已停止、仍在写入文件的编码线程
+    finishing: Vec<JoinHandle<()>>,
+}
+
+impl Recorder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 开始录制，返回输出路径
+    pub fn start(&mut self, settings: RecordingSettings, directory: &Path, now: Instant) -> Result<PathBuf, String> {
+        if self.active.is_some() {
+            return Err("已经在录制中".to_string());
+        }
+        std::fs::create_dir_all(directory).map_err(|e| format!("无法创建录制目录 {:?}: {}", directory, e))?;
+        let path = unique_path(directory, &recording_file_name(Local::now(), settings.format));
+
+        let (sender, receiver) = mpsc::sync_channel(QUEUE_CAPACITY);
+        let encoder_stopped = Arc::new(AtomicBool::new(false));
+        let handle = {
+            let path = path.clone();
+            let format = settings.format;
+            let max_bytes = settings.max_bytes;
+            let encoder_stopped = Arc::clone(&encoder_stopped);
+            std::thread::Builder::new()
+                .name("recording-encoder".to_string())
+                .spawn(move || {
+                    match encode_frames(receiver, format, &path, max_bytes) {
+                        Ok(summary) if summary.frames == 0 => warn!("录制期间没有收到画面，未生成文件"),
+                        Ok(summary) => {
+                            if summary.size_limited {
+                                warn!("录制文件达到大小上限，已自动停止");
+                            }
+                            info!(
+                                "录制已保存: {:?}（{}，{} 帧，{:.1} 秒，{:.1} MB）",
+                                path,
+                                format.name(),
+                                summary.frames,
+                                summary.duration.as_secs_f32(),
+                                summary.bytes as f64 / (1024.0 * 1024.0)
+                            );
+                        }
+                        Err(e) => error!("录制编码失败: {}", e),
+                    }
+                    encoder_stopped.store(true, Ordering::Release);
+                })
+                .map_err(|e| format!("启动录制编码线程失败: {}", e))?
+        };
+
+        info!(
+            "开始录制 {}: {:?}（最高 {} fps，最长 {} 秒）",
+            settings.format.name(),
+            path,
+            settings.fps,
+            settings.max_duration.as_secs()
+        );
+        self.active = Some(ActiveRecording {
+            sender,
+            handle,
+            throttle: CaptureThrottle::new(settings.fps, now),
+            settings,
+            started: now,
+            dropped: 0,
+            encoder_stopped,
+        });
+        Ok(path)
+    }
+
+    /// 停止录制，编码线程在后台写完剩余的帧；没有在录制时返回 false
+    pub fn stop(&mut self, now: Instant) -> bool {
+        self.finish(now, "手动停止")
+    }
+
+    fn finish(&mut self, now: Instant, reason: &str) -> bool {
+        let Some(active) = self.active.take() else {
+            return false;
+        };
+        // 编码线程已结束时发送失败，可以忽略
+        let _ = active.sender.send(EncoderMessage::Finish {
+            end: now,
+            dropped: active.dropped,
+        });
+        info!("录制已停止（{}），录制时长 {:.1} 秒", reason, now.saturating_duration_since(active.started).as_secs_f32());
+        self.finishing.push(active.handle);
+        true
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.active.is_some()
+    }
+
+    /// 已录制的时长
+    pub fn elapsed(&self, now: Instant) -> Option<Duration> {
+        self.active.as_ref().map(|active| now.saturating_duration_since(active.started))
+    }
+
+    /// 本帧是否需要回读画面，按录制帧率节流
+    pub fn wants_frame(&mut self, now: Instant) -> bool {
+        self.active.as_mut().is_some_and(|active| active.throttle.ready(now))
+    }
+
+    /// 把回读完成的画面交给编码线程，编码线程忙时丢弃
+    pub fn push_frame(&mut self, frame: CapturedFrame) {
+        let Some(active) = self.active.as_mut() else {
+            return;
+        };
+        match active.sender.try_send(EncoderMessage::Frame(frame)) {
+            Ok(()) => {}
+            Err(TrySendError::Full(_)) => {
+                active.dropped += 1;
+                debug!("录制编码队列已满，丢弃一帧（累计 {} 帧）", active.dropped);
+            }
+            // 编码线程已结束，由 update 停止录制
+            Err(TrySendError::Disconnected(_)) => {}
+        }
+    }
+
+    /// 检查最长时长和编码线程状态，录制自动停止时返回 true
+    pub fn update(&mut self, now: Instant) -> bool {
+        let (finished, running): (Vec<_>, Vec<_>) =
+            std::mem::take(&mut self.finishing).into_iter().partition(|handle| handle.is_finished());
+        self.finishing = running;
+        for handle in finished {
+            let _ = handle.join();
+        }
+
+        let Some(active) = self.active.as_ref() else {
+            return false;
+        };
+        if active.encoder_stopped.load(Ordering::Acquire) {
+            self.finish(now, "编码线程已结束")
+        } else if now.saturating_duration_since(active.started) >= active.settings.max_duration {
+            self.finish(now, "达到最长录制时长")
+        } else {
+            false
+        }
+    }
+}
+
+impl Drop for Recorder {
+    /// 退出时等待编码线程写完文件
+    fn drop(&mut self) {
+        self.finish(Instant::now(), "应用退出");
+        for handle in self.finishing.drain(..) {
+            let _ = handle.join();
+        }
+    }
+}
+
+/// 目录中不与已有文件或目录重名的路径
+fn unique_path(directory: &Path, file_name: &str) -> PathBuf {
+    let path = directory.join(file_name);
+    if !path.exists() {
+        return path;
+    }
+    let (stem, extension) = match file_name.rsplit_once('.') {
+        Some((stem, extension)) => (stem, format!(".{}", extension)),
+        None => (file_name, String::new()),
+    };
+    (1..)
+        .map(|index| directory.join(format!("{}-{}{}", stem, index, extension)))
+        .find(|path| !path.exists())
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::capture::CaptureSource;
+    use chrono::TimeZone;
+
+    fn solid_frame(width: u32, height: u32, color: [u8; 4], captured_at: Instant) -> CapturedFrame {
+        CapturedFrame {
+            source: CaptureSource::Composited,
+            width,
+            height,
+            rgba: color.repeat((width * height) as usize),
+            captured_at,
+        }
+    }
+
+    /// 把一组帧交给编码线程主体，返回编码结果
+    fn encode(format: RecordingFormat, path: &Path, frames: Vec<CapturedFrame>, end: Instant) -> RecordingSummary {
+        let (sender, receiver) = mpsc::sync_channel(frames.len() + 1);
+        for frame in frames {
+            sender.send(EncoderMessage::Frame(frame)).unwrap();
+        }
+        sender.send(EncoderMessage::Finish { end, dropped: 0 }).unwrap();
+        encode_frames(receiver, format, path, u64::MAX).unwrap()
+    }
+
+    #[test]
+    fn test_recording_format_names() {
+        assert_eq!(RecordingFormat::from_name("Apng"), Some(RecordingFormat::Apng));
+        assert_eq!(RecordingFormat::from_name("PngSequence"), Some(RecordingFormat::PngSequence));
+        assert_eq!(RecordingFormat::from_name("WebP"), None);
+
+        let time = Local.with_ymd_and_hms(2024, 3, 9, 14, 5, 7).unwrap();
+        assert_eq!(recording_file_name(time, RecordingFormat::Apng), "mira-rec-20240309-140507.png");
+        assert_eq!(recording_file_name(time, RecordingFormat::Gif), "mira-rec-20240309-140507.gif");
+        assert_eq!(recording_file_name(time, RecordingFormat::PngSequence), "mira-rec-20240309-140507");
+    }
+
+    #[test]
+    fn test_frame_clock_does_not_drift() {
+        let start = Instant::now();
+        let mut clock = FrameClock::new(start, 100);
+        // 每帧 33ms，单独取整为 3cs 时 30 帧只有 0.9 秒
+        let total: u64 = (1..=30).map(|i| clock.advance(start + Duration::from_millis(33 * i))).sum();
+        assert_eq!(total, 99);
+
+        // 时间戳相同的帧至少显示 1 个单位
+        let mut clock = FrameClock::new(start, 1000);
+        assert_eq!(clock.advance(start), 1);
+        // 丢帧后下一帧的间隔按实际时间计算
+        assert_eq!(clock.advance(start + Duration::from_millis(500)), 499);
+    }
+
+    #[test]
+    fn test_fit_frame() {
+        let frame = solid_frame(4, 2, [255, 0, 0, 255], Instant::now());
+        assert_eq!(fit_frame(&frame, 4, 2), frame.rgba);
+
+        // 缩放到 4x4 时上下留出透明区域
+        let fitted = fit_frame(&frame, 4, 4);
+        assert_eq!(fitted.len(), 4 * 4 * 4);
+        assert_eq!(&fitted[0..4], &[0, 0, 0, 0]);
+        assert_eq!(&fitted[4 * 4 * 4 - 4..], &[0, 0, 0, 0]);
+        let middle = (4 + 1) * 4;
+        assert_eq!(&fitted[middle..middle + 4], &[255, 0, 0, 255]);
+    }
+
+    #[test]
+    fn test_crc32() {
+        assert_eq!(crc32(b"IEND"), 0xAE42_6082);
+    }
+
+    #[test]
+    fn test_encode_apng() {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("clip.png");
+        let start = Instant::now();
+        let frames = vec![
+            solid_frame(8, 8, [255, 0, 0, 255], start),
+            solid_frame(8, 8, [0, 0, 0, 0], start + Duration::from_millis(100)),
+            // 录制中调整了窗口大小
+            solid_frame(16, 8, [0, 0, 255, 128], start + Duration::from_millis(350)),
+        ];
+        let summary = encode(RecordingFormat::Apng, &path, frames, start + Duration::from_millis(1000));
+        assert_eq!(summary.frames, 3);
+        assert_eq!(summary.duration, Duration::from_secs(1));
+        assert!(!summary.size_limited);
+        assert_eq!(summary.bytes, std::fs::metadata(&path).unwrap().len());
+
+        let decoder = png::Decoder::new(File::open(&path).unwrap());
+        let mut reader = decoder.read_info().unwrap();
+        let animation = reader.info().animation_control().unwrap();
+        assert_eq!(animation.num_frames, 3);
+        assert_eq!(reader.info().size(), (8, 8));
+
+        // 每帧显示时长与时间戳一致，透明像素原样保存
+        let mut buffer = vec![0; reader.output_buffer_size()];
+        let mut delays = Vec::new();
+        for _ in 0..3 {
+            let output = reader.next_frame(&mut buffer).unwrap();
+            let control = reader.info().frame_control().unwrap();
+            delays.push(control.delay_num as u32 * 1000 / control.delay_den as u32);
+            assert_eq!(output.width, 8);
+        }
+        assert_eq!(delays, vec![100, 250, 650]);
+        assert_eq!(&buffer[8 * 4 * 4..8 * 4 * 4 + 4], &[0, 0, 255, 128]);
+    }
+
+    #[test]
+    fn test_encode_gif_and_sequence() {
+        let directory = tempfile::tempdir().unwrap();
+        let start = Instant::now();
+        let frames = || {
+            vec![
+                solid_frame(4, 4, [255, 0, 0, 255], start),
+                solid_frame(4, 4, [0, 255, 0, 255], start + Duration::from_millis(40)),
+            ]
+        };
+
+        let gif = directory.path().join("clip.gif");
+        let summary = encode(RecordingFormat::Gif, &gif, frames(), start + Duration::from_millis(100));
+        assert_eq!(summary.frames, 2);
+        assert_eq!(image::image_dimensions(&gif).unwrap(), (4, 4));
+
+        let sequence = directory.path().join("clip");
+        let summary = encode(RecordingFormat::PngSequence, &sequence, frames(), start + Duration::from_millis(100));
+        assert_eq!(summary.frames, 2);
+        assert!(sequence.join("frame-000002.png").exists());
+        let index = std::fs::read_to_string(sequence.join(SEQUENCE_INDEX)).unwrap();
+        assert_eq!(
+            index,
+            "ffconcat version 1.0\nfile 'frame-000001.png'\nduration 0.040\n\
+             file 'frame-000002.png'\nduration 0.060\nfile 'frame-000002.png'\n"
+        );
+    }
+
+    #[test]
+    fn test_size_limit() {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("clip.png");
+        let start = Instant::now();
+        let (sender, receiver) = mpsc::sync_channel(8);
+        for i in 0..5 {
+            sender
+                .send(EncoderMessage::Frame(solid_frame(8, 8, [i * 40, 0, 0, 255], start + Duration::from_millis(100 * i as u64))))
+                .unwrap();
+        }
+        drop(sender);
+        // 写入第一帧后就超过上限
+        let summary = encode_frames(receiver, RecordingFormat::Apng, &path, 1).unwrap();
+        assert!(summary.size_limited);
+        assert_eq!(summary.frames, 1);
+        assert_eq!(summary.duration, Duration::from_millis(100));
+        let reader = png::Decoder::new(File::open(&path).unwrap()).read_info().unwrap();
+        assert_eq!(reader.info().animation_control().unwrap().num_frames, 1);
+    }
+
+    #[test]
+    fn test_recorder() {
+        let directory = tempfile::tempdir().unwrap();
+        let mut recorder = Recorder::new();
+        let now = Instant::now();
+        assert!(!recorder.wants_frame(now));
+        assert!(!recorder.stop(now));
+
+        let settings = RecordingSettings {
+            fps: 10,
+            max_duration: Duration::from_secs(2),
+            ..RecordingSettings::default()
+        };
+        let path = recorder.start(settings.clone(), directory.path(), now).unwrap();
+        assert!(recorder.is_recording());
+        assert!(recorder.start(settings, directory.path(), now).is_err());
+
+        // 按录制帧率节流回读
+        assert!(recorder.wants_frame(now));
+        assert!(!recorder.wants_frame(now + Duration::from_millis(50)));
+        assert!(recorder.wants_frame(now + Duration::from_millis(100)));
+        recorder.push_frame(solid_frame(4, 4, [255, 255, 255, 255], now));
+        recorder.push_frame(solid_frame(4, 4, [0, 0, 0, 255], now + Duration::from_millis(100)));
+        assert_eq!(recorder.elapsed(now + Duration::from_secs(1)), Some(Duration::from_secs(1)));
+
+        // 达到最长时长自动停止
+        assert!(!recorder.update(now + Duration::from_secs(1)));
+        assert!(recorder.update(now + Duration::from_secs(2)));
+        assert!(!recorder.is_recording());
+        drop(recorder);
+
+        let reader = png::Decoder::new(File::open(&path).unwrap()).read_info().unwrap();
+        assert_eq!(reader.info().animation_control().unwrap().num_frames, 2);
+    }
+}
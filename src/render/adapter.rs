@@ -0,0 +1,299 @@
+// GPU 适配器选择
+//
+// 按配置的电源偏好、图形后端和软件适配器选项请求适配器。驱动缺失或损坏时
+// 依次放宽条件（所有后端 → 软件适配器）重试，尽量降级运行而不是直接退出。
+// 设备限制同样从默认限制逐级降到 WebGL2 级别，以兼容只支持 GLES 的旧显卡。
+
+use crate::error::RenderError;
+use crate::render::profiler::PROFILER_FEATURES;
+use log::{debug, info, warn};
+use std::sync::Arc;
+use winit::window::Window;
+
+/// GPU 选择设置
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GpuSettings {
+    /// 电源偏好
+    pub power_preference: wgpu::PowerPreference,
+    /// 允许使用的图形后端
+    pub backends: wgpu::Backends,
+    /// 呈现模式，None 表示使用表面的首选模式
+    pub present_mode: Option<wgpu::PresentMode>,
+    /// 强制使用软件适配器
+    pub force_fallback_adapter: bool,
+}
+
+impl Default for GpuSettings {
+    fn default() -> Self {
+        Self {
+            power_preference: wgpu::PowerPreference::LowPower,
+            backends: wgpu::Backends::all(),
+            present_mode: None,
+            force_fallback_adapter: false,
+        }
+    }
+}
+
+/// 从配置名称解析电源偏好
+pub fn power_preference_from_name(name: &str) -> Option<wgpu::PowerPreference> {
+    match name {
+        "LowPower" => Some(wgpu::PowerPreference::LowPower),
+        "HighPerformance" => Some(wgpu::PowerPreference::HighPerformance),
+        "None" => Some(wgpu::PowerPreference::None),
+        _ => None,
+    }
+}
+
+/// 从配置名称解析图形后端，Auto 表示所有后端
+pub fn backends_from_name(name: &str) -> Option<wgpu::Backends> {
+    match name {
+        "Auto" => Some(wgpu::Backends::all()),
+        "Vulkan" => Some(wgpu::Backends::VULKAN),
+        "Gl" => Some(wgpu::Backends::GL),
+        "Dx12" => Some(wgpu::Backends::DX12),
+        "Metal" => Some(wgpu::Backends::METAL),
+        _ => None,
+    }
+}
+
+/// 从配置名称解析呈现模式，Auto 和未知名称返回 None
+pub fn present_mode_from_name(name: &str) -> Option<wgpu::PresentMode> {
+    match name {
+        "Fifo" => Some(wgpu::PresentMode::Fifo),
+        "Mailbox" => Some(wgpu::PresentMode::Mailbox),
+        "Immediate" => Some(wgpu::PresentMode::Immediate),
+        _ => None,
+    }
+}
+
+/// 一次适配器请求的条件
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AdapterAttempt {
+    pub backends: wgpu::Backends,
+    pub power_preference: wgpu::PowerPreference,
+    pub force_fallback_adapter: bool,
+}
+
+/// 适配器请求的重试链：先按配置请求，再放开所有后端，最后使用软件适配器
+pub fn adapter_attempts(settings: &GpuSettings) -> Vec<AdapterAttempt> {
+    let candidates = [
+        AdapterAttempt {
+            backends: settings.backends,
+            power_preference: settings.power_preference,
+            force_fallback_adapter: settings.force_fallback_adapter,
+        },
+        AdapterAttempt {
+            backends: wgpu::Backends::all(),
+            power_preference: settings.power_preference,
+            force_fallback_adapter: settings.force_fallback_adapter,
+        },
+        AdapterAttempt {
+            backends: wgpu::Backends::all(),
+            power_preference: wgpu::PowerPreference::None,
+            force_fallback_adapter: true,
+        },
+    ];
+
+    let mut attempts = Vec::with_capacity(candidates.len());
+    for candidate in candidates {
+        if !attempts.contains(&candidate) {
+            attempts.push(candidate);
+        }
+    }
+    attempts
+}
+
+/// 选择呈现模式：请求的模式不受支持时使用 Fifo（所有平台都支持），未指定时使用表面的首选模式
+pub fn choose_present_mode(requested: Option<wgpu::PresentMode>, supported: &[wgpu::PresentMode]) -> wgpu::PresentMode {
+    let preferred = supported.first().copied().unwrap_or(wgpu::PresentMode::Fifo);
+    match requested {
+        Some(mode) if supported.contains(&mode) => mode,
+        Some(mode) => {
+            let fallback = if supported.contains(&wgpu::PresentMode::Fifo) {
+                wgpu::PresentMode::Fifo
+            } else {
+                preferred
+            };
+            warn!("表面不支持呈现模式 {:?}，使用 {:?}", mode, fallback);
+            fallback
+        }
+        None => preferred,
+    }
+}
+
+/// 适配器的简短描述，用于日志和信息菜单
+pub fn describe_adapter(info: &wgpu::AdapterInfo) -> String {
+    let device_type = match info.device_type {
+        wgpu::DeviceType::DiscreteGpu => "独立显卡",
+        wgpu::DeviceType::IntegratedGpu => "集成显卡",
+        wgpu::DeviceType::VirtualGpu => "虚拟显卡",
+        wgpu::DeviceType::Cpu => "软件渲染",
+        wgpu::DeviceType::Other => "未知类型",
+    };
+    format!("{} ({:?}, {})", info.name, info.backend, device_type)
+}
+
+/// 设备限制的降级顺序，纹理尺寸上限使用适配器的实际能力
+fn limit_candidates(adapter_limits: &wgpu::Limits) -> [wgpu::Limits; 3] {
+    [
+        wgpu::Limits::default(),
+        wgpu::Limits::downlevel_defaults().using_resolution(adapter_limits.clone()),
+        wgpu::Limits::downlevel_webgl2_defaults().using_resolution(adapter_limits.clone()),
+    ]
+}
+
+/// 已创建的表面、适配器和设备
+pub struct GpuContext {
+    pub surface: wgpu::Surface<'static>,
+    pub adapter: wgpu::Adapter,
+    pub device: wgpu::Device,
+    pub queue: wgpu::Queue,
+}
+
+/// 按重试链创建表面、适配器和设备
+pub async fn request_gpu(window: Arc<Window>, settings: &GpuSettings) -> Result<GpuContext, RenderError> {
+    let mut last_error = String::from("未找到合适的GPU适配器");
+
+    for (index, attempt) in adapter_attempts(settings).into_iter().enumerate() {
+        if index > 0 {
+            warn!("{}，改用 {:?} 重试", last_error, attempt);
+        }
+
+        // 每次尝试使用独立的实例，只加载允许的后端
+        debug!("创建 wgpu 实例: {:?}", attempt.backends);
+        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
+            backends: attempt.backends,
+            ..Default::default()
+        });
+
+        debug!("创建渲染表面");
+        let surface = match instance.create_surface(window.clone()) {
+            Ok(surface) => surface,
+            Err(e) => {
+                last_error = format!("创建表面失败: {}", e);
+                continue;
+            }
+        };
+
+        debug!("请求 GPU 适配器");
+        let Some(adapter) = instance
+            .request_adapter(&wgpu::RequestAdapterOptions {
+                power_preference: attempt.power_preference,
+                compatible_surface: Some(&surface),
+                force_fallback_adapter: attempt.force_fallback_adapter,
+            })
+            .await
+        else {
+            last_error = "未找到合适的GPU适配器".to_string();
+            continue;
+        };
+        info!("使用 GPU 适配器: {}", describe_adapter(&adapter.get_info()));
+
+        // GPU 计时等可选特性只在适配器支持时启用
+        let optional_features = adapter.features() & PROFILER_FEATURES;
+
+        // 旧显卡达不到默认限制时逐级降低要求
+        let adapter_limits = adapter.limits();
+        for limits in limit_candidates(&adapter_limits) {
+            if !limits.check_limits(&adapter_limits) {
+                continue;
+            }
+            debug!("请求 GPU 设备和队列");
+            match adapter
+                .request_device(
+                    &wgpu::DeviceDescriptor {
+                        required_features: optional_features,
+                        required_limits: limits,
+                        label: None,
+                    },
+                    None,
+                )
+                .await
+            {
+                Ok((device, queue)) => {
+                    return Ok(GpuContext {
+                        surface,
+                        adapter,
+                        device,
+                        queue,
+                    })
+                }
+                Err(e) => last_error = format!("创建设备失败: {}", e),
+            }
+        }
+    }
+
+    Err(RenderError::InitializationFailed(last_error))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_names() {
+        assert_eq!(power_preference_from_name("HighPerformance"), Some(wgpu::PowerPreference::HighPerformance));
+        assert_eq!(power_preference_from_name("Fastest"), None);
+        assert_eq!(backends_from_name("Auto"), Some(wgpu::Backends::all()));
+        assert_eq!(backends_from_name("Gl"), Some(wgpu::Backends::GL));
+        assert_eq!(backends_from_name("OpenGL"), None);
+        assert_eq!(present_mode_from_name("Mailbox"), Some(wgpu::PresentMode::Mailbox));
+        assert_eq!(present_mode_from_name("Auto"), None);
+    }
+
+    #[test]
+    fn test_adapter_attempts() {
+        // 默认设置：按配置请求，最后降级到软件适配器
+        let attempts = adapter_attempts(&GpuSettings::default());
+        assert_eq!(attempts.len(), 2);
+        assert_eq!(attempts[0].power_preference, wgpu::PowerPreference::LowPower);
+        assert!(!attempts[0].force_fallback_adapter);
+        assert!(attempts[1].force_fallback_adapter);
+        assert_eq!(attempts[1].backends, wgpu::Backends::all());
+
+        // 指定后端时先放开所有后端，再降级
+        let settings = GpuSettings {
+            backends: wgpu::Backends::VULKAN,
+            power_preference: wgpu::PowerPreference::HighPerformance,
+            ..GpuSettings::default()
+        };
+        let attempts = adapter_attempts(&settings);
+        assert_eq!(attempts.len(), 3);
+        assert_eq!(attempts[0].backends, wgpu::Backends::VULKAN);
+        assert_eq!(attempts[1].backends, wgpu::Backends::all());
+        assert_eq!(attempts[1].power_preference, wgpu::PowerPreference::HighPerformance);
+        assert!(attempts[2].force_fallback_adapter);
+
+        // 与软件适配器降级条件相同时不重复请求
+        let settings = GpuSettings {
+            power_preference: wgpu::PowerPreference::None,
+            force_fallback_adapter: true,
+            ..GpuSettings::default()
+        };
+        assert_eq!(adapter_attempts(&settings).len(), 1);
+    }
+
+    #[test]
+    fn test_choose_present_mode() {
+        use wgpu::PresentMode::*;
+
+        assert_eq!(choose_present_mode(None, &[Mailbox, Fifo]), Mailbox);
+        assert_eq!(choose_present_mode(Some(Fifo), &[Mailbox, Fifo]), Fifo);
+        assert_eq!(choose_present_mode(Some(Immediate), &[Mailbox, Fifo]), Fifo);
+        assert_eq!(choose_present_mode(Some(Immediate), &[FifoRelaxed]), FifoRelaxed);
+        assert_eq!(choose_present_mode(None, &[]), Fifo);
+    }
+
+    #[test]
+    fn test_limit_candidates() {
+        let adapter_limits = wgpu::Limits {
+            max_texture_dimension_2d: 16384,
+            ..wgpu::Limits::default()
+        };
+        let candidates = limit_candidates(&adapter_limits);
+        assert_eq!(candidates[0], wgpu::Limits::default());
+        assert_eq!(candidates[1].max_texture_dimension_2d, 16384);
+        assert_eq!(candidates[2].max_storage_buffers_per_shader_stage, 0);
+        assert!(candidates.iter().all(|limits| limits.check_limits(&adapter_limits)));
+    }
+}
@@ -0,0 +1,204 @@
+// 输出透明度编码
+//
+// 窗口合成器按表面的 CompositeAlphaMode 解释帧缓冲中的透明度：PreMultiplied 要求颜色已乘以 alpha，
+// PostMultiplied 由合成器自己相乘。过去着色器不论模式都输出非预乘颜色，再以 ALPHA_BLENDING
+// 混合到透明背景，帧缓冲中实际是在线性空间预乘后的颜色：PostMultiplied 下边缘颜色被乘两次出现暗边，
+// PreMultiplied 下又因为预乘发生在 sRGB 编码之前而偏亮。
+//
+// 现在各层仍在线性空间中按预乘透明度合成，输出时先还原为非预乘颜色并做 sRGB 编码，
+// 需要预乘时再乘 alpha——合成器在 sRGB 编码空间中混合，预乘必须作用在编码后的颜色上。
+// sRGB 表面由硬件编码，着色器输出编码结果对应的线性值；非 sRGB 表面由着色器自己编码。
+//
+// 拍摄快照时的快门闪光也在输出阶段进行：按闪光强度把可见部分提亮为白色，透明区域保持透明。
+
+/// 帧缓冲中颜色的透明度形式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputAlpha {
+    /// 颜色已乘以 alpha
+    Premultiplied,
+    /// 非预乘颜色，由合成器相乘
+    Straight,
+}
+
+impl OutputAlpha {
+    /// 表面合成模式要求的形式；Opaque 忽略 alpha，预乘后相当于合成到黑色背景，
+    /// Inherit 由平台决定，桌面平台的透明窗口都按预乘处理
+    pub fn for_mode(mode: wgpu::CompositeAlphaMode) -> Self {
+        match mode {
+            wgpu::CompositeAlphaMode::PostMultiplied => OutputAlpha::Straight,
+            _ => OutputAlpha::Premultiplied,
+        }
+    }
+}
+
+/// 选择合成透明度模式：优先预乘，其次非预乘，都不支持时才使用不透明模式
+pub fn choose_alpha_mode(supported: &[wgpu::CompositeAlphaMode]) -> wgpu::CompositeAlphaMode {
+    use wgpu::CompositeAlphaMode::*;
+
+    [PreMultiplied, PostMultiplied, Inherit, Opaque]
+        .into_iter()
+        .find(|mode| supported.contains(mode))
+        .unwrap_or(Auto)
+}
+
+/// 输出编码统一缓冲区数据（与 shader.wgsl 中的 OutputUniforms 对应）
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct OutputUniforms {
+    flags: [f32; 4], // 是否预乘、是否由着色器做 sRGB 编码、快门闪光强度、保留
+}
+
+impl Default for OutputUniforms {
+    fn default() -> Self {
+        Self::new(OutputAlpha::Premultiplied, wgpu::TextureFormat::Bgra8UnormSrgb)
+    }
+}
+
+impl OutputUniforms {
+    pub fn new(alpha: OutputAlpha, surface_format: wgpu::TextureFormat) -> Self {
+        Self {
+            flags: [
+                if alpha == OutputAlpha::Premultiplied { 1.0 } else { 0.0 },
+                if surface_format.is_srgb() { 0.0 } else { 1.0 },
+                0.0,
+                0.0,
+            ],
+        }
+    }
+
+    /// 设置快门闪光强度（0-1）
+    pub fn with_flash(mut self, intensity: f32) -> Self {
+        self.flags[2] = intensity.clamp(0.0, 1.0);
+        self
+    }
+}
+
+pub fn srgb_to_linear(value: f32) -> f32 {
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+pub fn linear_to_srgb(value: f32) -> f32 {
+    if value <= 0.0031308 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+/// 快门闪光：按强度把预乘透明度的合成结果提亮为白色（与 shader.wgsl 中的 fs_main 保持一致）
+pub fn apply_flash(composed: [f32; 4], intensity: f32) -> [f32; 4] {
+    let a = composed[3];
+    let t = intensity.clamp(0.0, 1.0);
+    [
+        composed[0] + (a - composed[0]) * t,
+        composed[1] + (a - composed[1]) * t,
+        composed[2] + (a - composed[2]) * t,
+        a,
+    ]
+}
+
+fn to_unorm8(value: f32) -> u8 {
+    (value.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+/// 将线性空间、预乘透明度的合成结果编码为帧缓冲中的 RGBA8（与 shader.wgsl 中的 encode_output 保持一致）
+pub fn encode_output(composed: [f32; 4], alpha: OutputAlpha) -> [u8; 4] {
+    let a = composed[3].clamp(0.0, 1.0);
+    let scale = match alpha {
+        OutputAlpha::Premultiplied => a,
+        OutputAlpha::Straight => 1.0,
+    };
+    let channel = |i: usize| {
+        let straight = (composed[i] / composed[3].max(1e-5)).clamp(0.0, 1.0);
+        to_unorm8(linear_to_srgb(straight) * scale)
+    };
+    [channel(0), channel(1), channel(2), to_unorm8(a)]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 模拟合成器在 sRGB 编码空间中把帧缓冲混合到不透明背景上
+    fn composite_on(pixel: [u8; 4], mode: wgpu::CompositeAlphaMode, background: u8) -> [u8; 3] {
+        let a = pixel[3] as f32 / 255.0;
+        let bg = background as f32 / 255.0;
+        std::array::from_fn(|i| {
+            let c = pixel[i] as f32 / 255.0;
+            let src = if mode == wgpu::CompositeAlphaMode::PostMultiplied { c * a } else { c };
+            to_unorm8(src + bg * (1.0 - a))
+        })
+    }
+
+    #[test]
+    fn test_choose_alpha_mode() {
+        use wgpu::CompositeAlphaMode::*;
+
+        assert_eq!(choose_alpha_mode(&[Opaque, PreMultiplied]), PreMultiplied);
+        assert_eq!(choose_alpha_mode(&[Opaque, PostMultiplied]), PostMultiplied);
+        assert_eq!(choose_alpha_mode(&[Opaque, Inherit]), Inherit);
+        assert_eq!(choose_alpha_mode(&[Opaque]), Opaque);
+        assert_eq!(choose_alpha_mode(&[]), Auto);
+
+        assert_eq!(OutputAlpha::for_mode(PostMultiplied), OutputAlpha::Straight);
+        assert_eq!(OutputAlpha::for_mode(PreMultiplied), OutputAlpha::Premultiplied);
+        assert_eq!(OutputAlpha::for_mode(Opaque), OutputAlpha::Premultiplied);
+    }
+
+    #[test]
+    fn test_encode_output() {
+        // 线性空间中半透明的白色边缘像素
+        let edge = [0.4, 0.4, 0.4, 0.4];
+        assert_eq!(encode_output(edge, OutputAlpha::Premultiplied), [102, 102, 102, 102]);
+        assert_eq!(encode_output(edge, OutputAlpha::Straight), [255, 255, 255, 102]);
+
+        // 完全透明的像素不产生颜色
+        assert_eq!(encode_output([0.0; 4], OutputAlpha::Premultiplied), [0, 0, 0, 0]);
+        // 灰色：先编码再预乘
+        let gray = srgb_to_linear(100.0 / 255.0) * 0.5;
+        assert_eq!(encode_output([gray, gray, gray, 0.5], OutputAlpha::Premultiplied), [50, 50, 50, 128]);
+        assert_eq!(encode_output([gray, gray, gray, 0.5], OutputAlpha::Straight), [100, 100, 100, 128]);
+    }
+
+    #[test]
+    fn test_no_halo_on_light_background() {
+        // 白色画面的抗锯齿边缘合成到白色桌面上应保持白色，不论合成器要求哪种形式
+        for mode in [wgpu::CompositeAlphaMode::PreMultiplied, wgpu::CompositeAlphaMode::PostMultiplied] {
+            for alpha in [0.1, 0.25, 0.5, 0.75, 0.9] {
+                let pixel = encode_output([alpha, alpha, alpha, alpha], OutputAlpha::for_mode(mode));
+                for channel in composite_on(pixel, mode, 255) {
+                    assert!(channel >= 254, "{:?} alpha={} -> {}", mode, alpha, channel);
+                }
+            }
+        }
+
+        // 黑色桌面上边缘按编码空间的 alpha 线性过渡
+        let pixel = encode_output([0.4, 0.4, 0.4, 0.4], OutputAlpha::Premultiplied);
+        assert_eq!(composite_on(pixel, wgpu::CompositeAlphaMode::PreMultiplied, 0), [102, 102, 102]);
+    }
+
+    #[test]
+    fn test_apply_flash() {
+        let composed = [0.1, 0.2, 0.3, 0.5];
+        assert_eq!(apply_flash(composed, 0.0), composed);
+        assert_eq!(apply_flash(composed, 1.0), [0.5, 0.5, 0.5, 0.5]);
+        // 透明区域不受闪光影响
+        assert_eq!(apply_flash([0.0; 4], 1.0), [0.0; 4]);
+        assert_eq!(encode_output(apply_flash(composed, 1.0), OutputAlpha::Straight), [255, 255, 255, 128]);
+    }
+
+    #[test]
+    fn test_output_uniforms() {
+        let srgb = OutputUniforms::new(OutputAlpha::Premultiplied, wgpu::TextureFormat::Bgra8UnormSrgb);
+        assert_eq!(srgb.flags, [1.0, 0.0, 0.0, 0.0]);
+        let unorm = OutputUniforms::new(OutputAlpha::Straight, wgpu::TextureFormat::Bgra8Unorm);
+        assert_eq!(unorm.flags, [0.0, 1.0, 0.0, 0.0]);
+        assert_eq!(OutputUniforms::default(), srgb);
+        assert_eq!(srgb.with_flash(2.0).flags, [1.0, 0.0, 1.0, 0.0]);
+        assert_eq!(std::mem::size_of::<OutputUniforms>(), 16);
+    }
+}
@@ -0,0 +1,519 @@
+// 自动图像校正：灰度世界白平衡、自动色阶与弱光增强
+//
+// 每帧在 CPU 上对降采样后的像素统计线性亮度直方图和通道均值，
+// 计算出校正参数后做指数平滑，避免亮度随画面内容来回跳动（"呼吸"）。
+// 校正本身在片段着色器中执行（apply_auto_correct），此处的 apply 为 CPU 参考实现。
+//
+// 视频纹理为 sRGB 格式，着色器中读到的是线性值，因此统计同样在线性空间进行。
+
+/// 降采样后每个方向最多统计的像素数
+const SAMPLE_GRID: u32 = 128;
+
+/// 直方图分箱数
+const HISTOGRAM_BINS: usize = 256;
+
+/// 自动色阶忽略的暗部/亮部像素比例
+const LEVELS_CLIP: f32 = 0.005;
+
+/// 色阶范围下限，画面几乎纯色时不做拉伸
+const MIN_LEVELS_RANGE: f32 = 0.1;
+
+/// 白平衡增益范围
+const MIN_GAIN: f32 = 0.5;
+const MAX_GAIN: f32 = 2.0;
+
+/// 弱光增强的目标平均亮度（线性，约等于 18% 中灰）
+const LOW_LIGHT_TARGET: f32 = 0.18;
+
+/// 弱光增强的最小指数（最大提亮幅度）
+const MIN_GAMMA_EXPONENT: f32 = 0.5;
+
+/// 自动校正开关
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AutoCorrectSettings {
+    /// 灰度世界白平衡
+    pub white_balance: bool,
+    /// 自动色阶
+    pub auto_levels: bool,
+    /// 弱光增强
+    pub low_light_boost: bool,
+    /// 时间平滑系数（0.0 ~ 0.99），越大变化越慢
+    pub smoothing: f32,
+}
+
+impl Default for AutoCorrectSettings {
+    fn default() -> Self {
+        Self {
+            white_balance: false,
+            auto_levels: false,
+            low_light_boost: false,
+            smoothing: 0.9,
+        }
+    }
+}
+
+impl AutoCorrectSettings {
+    /// 是否启用任一校正
+    pub fn is_active(&self) -> bool {
+        self.white_balance || self.auto_levels || self.low_light_boost
+    }
+}
+
+/// 单帧统计信息（线性空间）
+#[derive(Debug, Clone, PartialEq)]
+pub struct FrameStatistics {
+    /// 各通道均值
+    pub mean: [f32; 3],
+    /// 平均亮度
+    pub mean_luma: f32,
+    /// 暗部裁剪点亮度
+    pub low: f32,
+    /// 亮部裁剪点亮度
+    pub high: f32,
+    /// 参与统计的像素数
+    pub samples: u32,
+}
+
+impl FrameStatistics {
+    /// 对 RGB8/RGBA8（sRGB 编码）帧降采样统计，数据不足时返回 None
+    pub fn analyze(pixels: &[u8], width: u32, height: u32, bytes_per_pixel: u32) -> Option<Self> {
+        if width == 0 || height == 0 || bytes_per_pixel < 3 || pixels.len() < (width * height * bytes_per_pixel) as usize {
+            return None;
+        }
+
+        let to_linear = srgb_to_linear_table();
+        let step_x = width.div_ceil(SAMPLE_GRID).max(1);
+        let step_y = height.div_ceil(SAMPLE_GRID).max(1);
+
+        let mut histogram = [0u32; HISTOGRAM_BINS];
+        let mut sum = [0.0f64; 3];
+        let mut samples = 0u32;
+
+        for y in (0..height).step_by(step_y as usize) {
+            for x in (0..width).step_by(step_x as usize) {
+                let index = ((y * width + x) * bytes_per_pixel) as usize;
+                let rgb = [
+                    to_linear[pixels[index] as usize],
+                    to_linear[pixels[index + 1] as usize],
+                    to_linear[pixels[index + 2] as usize],
+                ];
+                for (total, value) in sum.iter_mut().zip(rgb) {
+                    *total += value as f64;
+                }
+                let bin = (luminance(rgb) * (HISTOGRAM_BINS - 1) as f32).round() as usize;
+                histogram[bin.min(HISTOGRAM_BINS - 1)] += 1;
+                samples += 1;
+            }
+        }
+
+        let mean = [
+            (sum[0] / samples as f64) as f32,
+            (sum[1] / samples as f64) as f32,
+            (sum[2] / samples as f64) as f32,
+        ];
+
+        Some(Self {
+            mean,
+            mean_luma: luminance(mean),
+            low: histogram_percentile(&histogram, samples, LEVELS_CLIP),
+            high: histogram_percentile(&histogram, samples, 1.0 - LEVELS_CLIP),
+            samples,
+        })
+    }
+}
+
+/// 直方图中累计比例达到 fraction 的亮度
+fn histogram_percentile(histogram: &[u32; HISTOGRAM_BINS], total: u32, fraction: f32) -> f32 {
+    let target = (total as f32 * fraction).ceil().max(1.0) as u32;
+    let mut accumulated = 0u32;
+    for (bin, &count) in histogram.iter().enumerate() {
+        accumulated += count;
+        if accumulated >= target {
+            return bin as f32 / (HISTOGRAM_BINS - 1) as f32;
+        }
+    }
+    1.0
+}
+
+/// sRGB 编码值到线性值的查找表
+fn srgb_to_linear_table() -> [f32; 256] {
+    let mut table = [0.0f32; 256];
+    for (value, entry) in table.iter_mut().enumerate() {
+        let c = value as f32 / 255.0;
+        *entry = if c <= 0.04045 { c / 12.92 } else { ((c + 0.055) / 1.055).powf(2.4) };
+    }
+    table
+}
+
+/// Rec.709 亮度
+fn luminance(rgb: [f32; 3]) -> f32 {
+    0.2126 * rgb[0] + 0.7152 * rgb[1] + 0.0722 * rgb[2]
+}
+
+/// 校正参数：先乘白平衡增益，再做色阶拉伸，最后按指数提亮
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Correction {
+    /// 白平衡增益
+    pub gains: [f32; 3],
+    /// 色阶黑点
+    pub black: f32,
+    /// 色阶白点
+    pub white: f32,
+    /// 亮度指数（小于 1 时提亮暗部）
+    pub exponent: f32,
+}
+
+impl Default for Correction {
+    fn default() -> Self {
+        Self {
+            gains: [1.0; 3],
+            black: 0.0,
+            white: 1.0,
+            exponent: 1.0,
+        }
+    }
+}
+
+impl Correction {
+    /// 根据帧统计计算目标校正参数
+    pub fn from_statistics(stats: &FrameStatistics, settings: &AutoCorrectSettings) -> Self {
+        let mut correction = Self::default();
+
+        if settings.white_balance {
+            // 灰度世界假设：画面平均颜色应为中性灰
+            let gray = (stats.mean[0] + stats.mean[1] + stats.mean[2]) / 3.0;
+            if gray > f32::EPSILON {
+                for (gain, mean) in correction.gains.iter_mut().zip(stats.mean) {
+                    *gain = (gray / mean.max(f32::EPSILON)).clamp(MIN_GAIN, MAX_GAIN);
+                }
+            }
+        }
+
+        if settings.auto_levels && stats.high - stats.low >= MIN_LEVELS_RANGE {
+            correction.black = stats.low;
+            correction.white = stats.high;
+        }
+
+        if settings.low_light_boost {
+            // 按色阶拉伸后的平均亮度计算，使其提升到目标亮度
+            let range = correction.white - correction.black;
+            let mean = ((stats.mean_luma - correction.black) / range).clamp(0.0, 1.0);
+            if mean > f32::EPSILON && mean < LOW_LIGHT_TARGET {
+                correction.exponent = (LOW_LIGHT_TARGET.ln() / mean.ln()).clamp(MIN_GAMMA_EXPONENT, 1.0);
+            }
+        }
+
+        correction
+    }
+
+    /// 向目标参数靠近，factor 为本次移动的比例（0.0 ~ 1.0）
+    pub fn blend_towards(&mut self, target: &Correction, factor: f32) {
+        let lerp = |from: f32, to: f32| from + (to - from) * factor;
+        for (gain, target_gain) in self.gains.iter_mut().zip(target.gains) {
+            *gain = lerp(*gain, target_gain);
+        }
+        self.black = lerp(self.black, target.black);
+        self.white = lerp(self.white, target.white);
+        self.exponent = lerp(self.exponent, target.exponent);
+    }
+
+    /// 对线性 RGB 应用校正（与 shader.wgsl 中的 apply_auto_correct 保持一致）
+    pub fn apply(&self, rgb: [f32; 3]) -> [f32; 3] {
+        let scale = 1.0 / (self.white - self.black).max(f32::EPSILON);
+        let mut output = [0.0; 3];
+        for channel in 0..3 {
+            let balanced = rgb[channel] * self.gains[channel];
+            let leveled = ((balanced - self.black) * scale).clamp(0.0, 1.0);
+            output[channel] = leveled.powf(self.exponent);
+        }
+        output
+    }
+}
+
+/// 自动校正器：分析每帧并平滑输出校正参数
+#[derive(Debug, Clone, Default)]
+pub struct AutoCorrector {
+    settings: AutoCorrectSettings,
+    current: Correction,
+    initialized: bool,
+}
+
+impl AutoCorrector {
+    pub fn new(settings: AutoCorrectSettings) -> Self {
+        Self {
+            settings,
+            current: Correction::default(),
+            initialized: false,
+        }
+    }
+
+    /// 当前设置
+    pub fn settings(&self) -> AutoCorrectSettings {
+        self.settings
+    }
+
+    /// 更新设置，关闭的校正项会在后续帧中平滑恢复到中性
+    pub fn set_settings(&mut self, settings: AutoCorrectSettings) {
+        if !settings.is_active() {
+            self.reset();
+        }
+        self.settings = settings;
+    }
+
+    /// 当前平滑后的校正参数
+    pub fn correction(&self) -> Correction {
+        self.current
+    }
+
+    /// 恢复中性参数，下一帧直接采用分析结果
+    pub fn reset(&mut self) {
+        self.current = Correction::default();
+        self.initialized = false;
+    }
+
+    /// 分析一帧并更新校正参数
+    pub fn process_frame(&mut self, pixels: &[u8], width: u32, height: u32, bytes_per_pixel: u32) -> Correction {
+        if let Some(stats) = FrameStatistics::analyze(pixels, width, height, bytes_per_pixel) {
+            self.update(&stats);
+        }
+        self.current
+    }
+
+    /// 根据帧统计更新校正参数（第一帧直接采用，之后指数平滑）
+    pub fn update(&mut self, stats: &FrameStatistics) -> Correction {
+        if !self.settings.is_active() {
+            return self.current;
+        }
+
+        let target = Correction::from_statistics(stats, &self.settings);
+        if self.initialized {
+            let factor = 1.0 - self.settings.smoothing.clamp(0.0, 0.99);
+            self.current.blend_towards(&target, factor);
+        } else {
+            self.current = target;
+            self.initialized = true;
+        }
+        self.current
+    }
+}
+
+/// 自动校正统一缓冲区数据（与 shader.wgsl 中的 AutoCorrectUniforms 对应）
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct AutoCorrectUniforms {
+    gains: [f32; 4],  // 白平衡增益 rgb，w 为是否启用
+    levels: [f32; 4], // 黑点、1/(白点-黑点)、亮度指数、保留
+}
+
+impl Default for AutoCorrectUniforms {
+    fn default() -> Self {
+        Self::new(&Correction::default(), false)
+    }
+}
+
+impl AutoCorrectUniforms {
+    pub fn new(correction: &Correction, enabled: bool) -> Self {
+        Self {
+            gains: [
+                correction.gains[0],
+                correction.gains[1],
+                correction.gains[2],
+                if enabled { 1.0 } else { 0.0 },
+            ],
+            levels: [
+                correction.black,
+                1.0 / (correction.white - correction.black).max(f32::EPSILON),
+                correction.exponent,
+                0.0,
+            ],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 生成纯色 RGBA 帧
+    fn solid_frame(width: u32, height: u32, rgb: [u8; 3]) -> Vec<u8> {
+        (0..width * height).flat_map(|_| [rgb[0], rgb[1], rgb[2], 255]).collect()
+    }
+
+    /// 生成水平亮度渐变帧（sRGB 值 low..=high）
+    fn gradient_frame(width: u32, height: u32, low: u8, high: u8, tint: [f32; 3]) -> Vec<u8> {
+        let mut rgba = Vec::with_capacity((width * height * 4) as usize);
+        for _y in 0..height {
+            for x in 0..width {
+                let t = x as f32 / (width - 1) as f32;
+                let value = low as f32 + (high - low) as f32 * t;
+                for factor in tint {
+                    rgba.push((value * factor).round().min(255.0) as u8);
+                }
+                rgba.push(255);
+            }
+        }
+        rgba
+    }
+
+    fn all_settings() -> AutoCorrectSettings {
+        AutoCorrectSettings {
+            white_balance: true,
+            auto_levels: true,
+            low_light_boost: true,
+            smoothing: 0.9,
+        }
+    }
+
+    #[test]
+    fn test_default_settings_are_inactive() {
+        let settings = AutoCorrectSettings::default();
+        assert!(!settings.is_active());
+        assert_eq!(settings.smoothing, 0.9);
+        assert_eq!(Correction::default().apply([0.3, 0.5, 0.7]), [0.3, 0.5, 0.7]);
+    }
+
+    #[test]
+    fn test_statistics_of_solid_frame() {
+        let rgba = solid_frame(640, 480, [255, 0, 0]);
+        let stats = FrameStatistics::analyze(&rgba, 640, 480, 4).unwrap();
+        assert_eq!(stats.mean, [1.0, 0.0, 0.0]);
+        assert!((stats.mean_luma - 0.2126).abs() < 1e-6);
+        assert_eq!(stats.low, stats.high);
+
+        // 降采样后统计的像素数不超过网格大小
+        assert!(stats.samples <= SAMPLE_GRID * SAMPLE_GRID);
+        assert!(stats.samples > 0);
+
+        // sRGB 中灰对应线性约 0.214
+        let gray = FrameStatistics::analyze(&solid_frame(4, 4, [128, 128, 128]), 4, 4, 4).unwrap();
+        assert!((gray.mean[0] - 0.2158).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_statistics_reject_invalid_frames() {
+        assert!(FrameStatistics::analyze(&[], 0, 0, 4).is_none());
+        assert!(FrameStatistics::analyze(&[0; 8], 4, 4, 4).is_none());
+        assert!(FrameStatistics::analyze(&[0; 48], 4, 4, 2).is_none());
+    }
+
+    #[test]
+    fn test_statistics_of_rgb_frame() {
+        // 紧凑的 RGB8 帧与对应的 RGBA8 帧统计结果相同
+        let rgba = gradient_frame(200, 30, 10, 240, [1.0, 0.7, 0.4]);
+        let rgb: Vec<u8> = rgba.chunks_exact(4).flat_map(|p| [p[0], p[1], p[2]]).collect();
+        assert_eq!(FrameStatistics::analyze(&rgb, 200, 30, 3), FrameStatistics::analyze(&rgba, 200, 30, 4));
+        assert!(FrameStatistics::analyze(&rgb, 200, 30, 4).is_none());
+    }
+
+    #[test]
+    fn test_gray_world_white_balance() {
+        // 偏蓝的画面
+        let rgba = gradient_frame(256, 64, 40, 220, [0.8, 0.9, 1.0]);
+        let stats = FrameStatistics::analyze(&rgba, 256, 64, 4).unwrap();
+        let settings = AutoCorrectSettings { white_balance: true, ..Default::default() };
+        let correction = Correction::from_statistics(&stats, &settings);
+
+        assert!(correction.gains[0] > 1.0);
+        assert!(correction.gains[2] < 1.0);
+
+        // 校正后的通道均值相等
+        let balanced: Vec<f32> = (0..3).map(|c| stats.mean[c] * correction.gains[c]).collect();
+        assert!((balanced[0] - balanced[1]).abs() < 1e-4);
+        assert!((balanced[1] - balanced[2]).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_white_balance_gains_are_clamped() {
+        let rgba = solid_frame(16, 16, [255, 10, 10]);
+        let stats = FrameStatistics::analyze(&rgba, 16, 16, 4).unwrap();
+        let settings = AutoCorrectSettings { white_balance: true, ..Default::default() };
+        let correction = Correction::from_statistics(&stats, &settings);
+        assert!(correction.gains.iter().all(|g| (MIN_GAIN..=MAX_GAIN).contains(g)));
+    }
+
+    #[test]
+    fn test_auto_levels_stretch_range() {
+        // 对比度很低的画面
+        let rgba = gradient_frame(256, 16, 100, 180, [1.0; 3]);
+        let stats = FrameStatistics::analyze(&rgba, 256, 16, 4).unwrap();
+        let settings = AutoCorrectSettings { auto_levels: true, ..Default::default() };
+        let correction = Correction::from_statistics(&stats, &settings);
+
+        assert!(correction.black > 0.1);
+        assert!(correction.white < 0.5);
+        assert_eq!(correction.apply([correction.black; 3]), [0.0; 3]);
+        let white = correction.apply([correction.white; 3]);
+        assert!(white.iter().all(|v| (v - 1.0).abs() < 1e-5));
+
+        // 纯色画面不拉伸
+        let flat = FrameStatistics::analyze(&solid_frame(16, 16, [90, 90, 90]), 16, 16, 4).unwrap();
+        assert_eq!(Correction::from_statistics(&flat, &settings), Correction::default());
+    }
+
+    #[test]
+    fn test_low_light_boost_lifts_dark_frames() {
+        let settings = AutoCorrectSettings { low_light_boost: true, ..Default::default() };
+
+        let dark = FrameStatistics::analyze(&gradient_frame(128, 16, 0, 90, [1.0; 3]), 128, 16, 4).unwrap();
+        let correction = Correction::from_statistics(&dark, &settings);
+        assert!(correction.exponent < 1.0);
+        assert!(correction.exponent >= MIN_GAMMA_EXPONENT);
+        let lifted = correction.apply([dark.mean_luma; 3])[0];
+        assert!(lifted > dark.mean_luma);
+
+        // 正常亮度的画面不提亮
+        let bright = FrameStatistics::analyze(&gradient_frame(128, 16, 60, 255, [1.0; 3]), 128, 16, 4).unwrap();
+        assert_eq!(Correction::from_statistics(&bright, &settings).exponent, 1.0);
+    }
+
+    #[test]
+    fn test_temporal_smoothing_prevents_pumping() {
+        let mut corrector = AutoCorrector::new(all_settings());
+        let dark = gradient_frame(128, 16, 0, 80, [1.0; 3]);
+        let bright = gradient_frame(128, 16, 40, 255, [1.0; 3]);
+
+        let first = corrector.process_frame(&dark, 128, 16, 4);
+        assert!(first.exponent < 1.0);
+
+        // 画面突然变亮后参数逐步变化而不是跳变
+        let target = Correction::from_statistics(&FrameStatistics::analyze(&bright, 128, 16, 4).unwrap(), &all_settings());
+        let next = corrector.process_frame(&bright, 128, 16, 4);
+        let full_step = (target.exponent - first.exponent).abs();
+        let actual_step = (next.exponent - first.exponent).abs();
+        assert!(actual_step > 0.0);
+        assert!(actual_step < full_step * 0.2);
+
+        // 持续同样的画面后收敛到目标
+        for _ in 0..200 {
+            corrector.process_frame(&bright, 128, 16, 4);
+        }
+        assert!((corrector.correction().exponent - target.exponent).abs() < 1e-3);
+        assert!((corrector.correction().black - target.black).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_disabling_resets_to_neutral() {
+        let mut corrector = AutoCorrector::new(all_settings());
+        corrector.process_frame(&gradient_frame(64, 8, 0, 60, [1.0, 0.8, 0.6]), 64, 8, 4);
+        assert_ne!(corrector.correction(), Correction::default());
+
+        corrector.set_settings(AutoCorrectSettings::default());
+        assert_eq!(corrector.correction(), Correction::default());
+        corrector.process_frame(&gradient_frame(64, 8, 0, 60, [1.0, 0.8, 0.6]), 64, 8, 4);
+        assert_eq!(corrector.correction(), Correction::default());
+    }
+
+    #[test]
+    fn test_auto_correct_uniforms() {
+        let uniforms = AutoCorrectUniforms::default();
+        assert_eq!(uniforms.gains, [1.0, 1.0, 1.0, 0.0]);
+        assert_eq!(uniforms.levels, [0.0, 1.0, 1.0, 0.0]);
+        assert_eq!(std::mem::size_of::<AutoCorrectUniforms>() % 16, 0);
+
+        let correction = Correction { gains: [1.2, 1.0, 0.8], black: 0.1, white: 0.6, exponent: 0.7 };
+        let uniforms = AutoCorrectUniforms::new(&correction, true);
+        assert_eq!(uniforms.gains, [1.2, 1.0, 0.8, 1.0]);
+        assert!((uniforms.levels[1] - 2.0).abs() < 1e-5);
+        assert_eq!(uniforms.levels[2], 0.7);
+    }
+}
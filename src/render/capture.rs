@@ -0,0 +1,364 @@
+// 渲染结果回读
+//
+// 快照需要取得 GPU 上的画面。请求在渲染一帧时编码：合成画面把主视频通道再渲染一遍到可复制的
+// 离屏纹理，因此不含窗口控件和上下文菜单；原始画面直接复制视频纹理，不经过遮罩和调色。
+// 纹理复制到行按 COPY_BYTES_PER_ROW_ALIGNMENT 对齐的回读缓冲区，提交后异步映射，通常晚一到两帧可用。
+//
+// 回读的像素统一转换为 RGBA 顺序、非预乘透明度的 sRGB 数据，可以直接保存为 PNG。
+
+use crate::render::alpha::OutputAlpha;
+use log::{debug, warn};
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+// 回读缓冲区状态
+const READBACK_WAITING: u8 = 0;
+const READBACK_PENDING: u8 = 1;
+const READBACK_MAPPED: u8 = 2;
+const READBACK_FAILED: u8 = 3;
+
+/// 回读的画面来源
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CaptureSource {
+    /// 遮罩、特效和叠加层合成后的画面，形状之外透明
+    Composited,
+    /// 摄像头原始画面，不经过遮罩
+    Raw,
+}
+
+impl CaptureSource {
+    pub fn name(self) -> &'static str {
+        match self {
+            CaptureSource::Composited => "合成画面",
+            CaptureSource::Raw => "原始画面",
+        }
+    }
+}
+
+/// 按帧率节流回读请求（录制和帧输出）
+#[derive(Debug, Clone, Copy)]
+pub struct CaptureThrottle {
+    interval: Duration,
+    next: Instant,
+}
+
+impl CaptureThrottle {
+    pub fn new(fps: u32, now: Instant) -> Self {
+        Self {
+            interval: Duration::from_secs_f64(1.0 / fps.max(1) as f64),
+            next: now,
+        }
+    }
+
+    /// 到达下一次回读时间时返回 true；落后超过一帧时从当前时间重新计时，不补回读
+    pub fn ready(&mut self, now: Instant) -> bool {
+        if now < self.next {
+            return false;
+        }
+        self.next = if now.saturating_duration_since(self.next) > self.interval {
+            now + self.interval
+        } else {
+            self.next + self.interval
+        };
+        true
+    }
+}
+
+/// 回读完成的一帧
+#[derive(Debug, Clone)]
+pub struct CapturedFrame {
+    pub source: CaptureSource,
+    pub width: u32,
+    pub height: u32,
+    /// RGBA 顺序、非预乘透明度的 sRGB 像素
+    pub rgba: Vec<u8>,
+    /// 编码回读命令的时间
+    pub captured_at: Instant,
+}
+
+/// 回读缓冲区中的像素排列
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PixelLayout {
+    /// 按 BGRA 顺序存放
+    pub bgra: bool,
+    /// 颜色已乘以 alpha
+    pub premultiplied: bool,
+}
+
+impl PixelLayout {
+    /// 纹理格式对应的排列；只支持每通道 8 位的 RGBA/BGRA 格式
+    pub fn for_format(format: wgpu::TextureFormat, alpha: OutputAlpha) -> Option<Self> {
+        use wgpu::TextureFormat::*;
+
+        let bgra = match format {
+            Rgba8Unorm | Rgba8UnormSrgb => false,
+            Bgra8Unorm | Bgra8UnormSrgb => true,
+            _ => return None,
+        };
+        Some(Self {
+            bgra,
+            premultiplied: alpha == OutputAlpha::Premultiplied,
+        })
+    }
+}
+
+/// 一行像素在回读缓冲区中占用的字节数
+pub fn padded_bytes_per_row(width: u32) -> u32 {
+    let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+    (width * 4).div_ceil(align) * align
+}
+
+/// 去掉行尾填充，转换为 RGBA 顺序和非预乘透明度
+pub fn unpack_pixels(data: &[u8], width: u32, height: u32, bytes_per_row: u32, layout: PixelLayout) -> Vec<u8> {
+    let row_len = width as usize * 4;
+    let mut rgba = Vec::with_capacity(row_len * height as usize);
+    for row in data.chunks(bytes_per_row as usize).take(height as usize) {
+        for pixel in row[..row_len].chunks_exact(4) {
+            let [mut r, g, mut b, a] = [pixel[0], pixel[1], pixel[2], pixel[3]];
+            if layout.bgra {
+                std::mem::swap(&mut r, &mut b);
+            }
+            let mut color = [r, g, b];
+            if layout.premultiplied {
+                color = color.map(|c| unpremultiply(c, a));
+            }
+            rgba.extend_from_slice(&[color[0], color[1], color[2], a]);
+        }
+    }
+    rgba
+}
+
+fn unpremultiply(value: u8, alpha: u8) -> u8 {
+    if alpha == 0 {
+        return 0;
+    }
+    ((value as u32 * 255 + alpha as u32 / 2) / alpha as u32).min(255) as u8
+}
+
+/// 等待映射的回读缓冲区
+struct Readback {
+    source: CaptureSource,
+    buffer: wgpu::Buffer,
+    state: Arc<AtomicU8>,
+    width: u32,
+    height: u32,
+    bytes_per_row: u32,
+    layout: PixelLayout,
+    captured_at: Instant,
+}
+
+/// 画面回读
+#[derive(Default)]
+pub struct FrameCapture {
+    readbacks: Vec<Readback>,
+    // 合成画面的离屏渲染目标
+    target: Option<wgpu::Texture>,
+}
+
+impl FrameCapture {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 合成画面的离屏渲染目标，尺寸或格式变化时重新创建
+    pub fn composite_target(
+        &mut self,
+        device: &wgpu::Device,
+        width: u32,
+        height: u32,
+        format: wgpu::TextureFormat,
+    ) -> wgpu::TextureView {
+        let matches = self.target.as_ref().is_some_and(|target| {
+            target.width() == width && target.height() == height && target.format() == format
+        });
+        if !matches {
+            debug!("创建回读渲染目标: {}x{} {:?}", width, height, format);
+            self.target = Some(device.create_texture(&wgpu::TextureDescriptor {
+                label: Some("Capture Target"),
+                size: wgpu::Extent3d {
+                    width,
+                    height,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format,
+                usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+                view_formats: &[],
+            }));
+        }
+        self.target.as_ref().unwrap().create_view(&wgpu::TextureViewDescriptor::default())
+    }
+
+    /// 复制已渲染的合成画面
+    pub fn copy_composited(&mut self, device: &wgpu::Device, encoder: &mut wgpu::CommandEncoder, alpha: OutputAlpha) {
+        let Some(target) = self.target.take() else {
+            warn!("回读渲染目标不存在，跳过合成画面回读");
+            return;
+        };
+        self.copy_texture(device, encoder, &target, CaptureSource::Composited, alpha);
+        self.target = Some(target);
+    }
+
+    /// 把纹理复制到回读缓冲区；不支持的纹理格式只记录警告
+    pub fn copy_texture(
+        &mut self,
+        device: &wgpu::Device,
+        encoder: &mut wgpu::CommandEncoder,
+        texture: &wgpu::Texture,
+        source: CaptureSource,
+        alpha: OutputAlpha,
+    ) {
+        let Some(layout) = PixelLayout::for_format(texture.format(), alpha) else {
+            warn!("不支持回读 {:?} 格式的纹理", texture.format());
+            return;
+        };
+        let (width, height) = (texture.width(), texture.height());
+        let bytes_per_row = padded_bytes_per_row(width);
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Capture Readback Buffer"),
+            size: bytes_per_row as u64 * height as u64,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        encoder.copy_texture_to_buffer(
+            texture.as_image_copy(),
+            wgpu::ImageCopyBuffer {
+                buffer: &buffer,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(bytes_per_row),
+                    rows_per_image: Some(height),
+                },
+            },
+            texture.size(),
+        );
+        self.readbacks.push(Readback {
+            source,
+            buffer,
+            state: Arc::new(AtomicU8::new(READBACK_WAITING)),
+            width,
+            height,
+            bytes_per_row,
+            layout,
+            captured_at: Instant::now(),
+        });
+    }
+
+    /// 提交后请求映射本帧新增的回读缓冲区
+    pub fn after_submit(&mut self) {
+        for readback in &self.readbacks {
+            if readback.state.load(Ordering::Acquire) != READBACK_WAITING {
+                continue;
+            }
+            readback.state.store(READBACK_PENDING, Ordering::Release);
+            let state = Arc::clone(&readback.state);
+            readback.buffer.slice(..).map_async(wgpu::MapMode::Read, move |result| {
+                let next = if result.is_ok() { READBACK_MAPPED } else { READBACK_FAILED };
+                state.store(next, Ordering::Release);
+            });
+        }
+    }
+
+    /// 取出已完成的回读结果，按请求顺序返回
+    pub fn poll(&mut self, device: &wgpu::Device) -> Vec<CapturedFrame> {
+        if self.readbacks.is_empty() {
+            return Vec::new();
+        }
+        device.poll(wgpu::Maintain::Poll);
+
+        let mut frames = Vec::new();
+        self.readbacks.retain(|readback| match readback.state.load(Ordering::Acquire) {
+            READBACK_MAPPED => {
+                let data = readback.buffer.slice(..).get_mapped_range();
+                let rgba = unpack_pixels(&data, readback.width, readback.height, readback.bytes_per_row, readback.layout);
+                drop(data);
+                readback.buffer.unmap();
+                frames.push(CapturedFrame {
+                    source: readback.source,
+                    width: readback.width,
+                    height: readback.height,
+                    rgba,
+                    captured_at: readback.captured_at,
+                });
+                false
+            }
+            READBACK_FAILED => {
+                warn!("读取{}失败", readback.source.name());
+                false
+            }
+            _ => true,
+        });
+        frames
+    }
+
+    /// 是否有尚未完成的回读
+    pub fn is_pending(&self) -> bool {
+        !self.readbacks.is_empty()
+    }
+
+    /// 丢弃未完成的回读（设备重建后旧缓冲区失效）
+    pub fn discard_pending(&mut self) -> usize {
+        let count = self.readbacks.len();
+        self.readbacks.clear();
+        self.target = None;
+        count
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_padded_bytes_per_row() {
+        assert_eq!(padded_bytes_per_row(64), 256);
+        assert_eq!(padded_bytes_per_row(65), 512);
+        assert_eq!(padded_bytes_per_row(1), 256);
+        assert_eq!(padded_bytes_per_row(400) % wgpu::COPY_BYTES_PER_ROW_ALIGNMENT, 0);
+    }
+
+    #[test]
+    fn test_capture_throttle() {
+        let now = Instant::now();
+        let mut throttle = CaptureThrottle::new(10, now);
+        assert!(throttle.ready(now));
+        assert!(!throttle.ready(now + Duration::from_millis(50)));
+        // 稍晚到达时仍按原节奏计时
+        assert!(throttle.ready(now + Duration::from_millis(120)));
+        assert!(throttle.ready(now + Duration::from_millis(200)));
+        // 落后多帧时不补回读
+        assert!(throttle.ready(now + Duration::from_millis(1000)));
+        assert!(!throttle.ready(now + Duration::from_millis(1050)));
+        assert!(throttle.ready(now + Duration::from_millis(1100)));
+    }
+
+    #[test]
+    fn test_unpack_pixels() {
+        // 2x2 的 BGRA 预乘数据，每行填充到 256 字节
+        let bytes_per_row = padded_bytes_per_row(2);
+        let mut data = vec![0xAA; (bytes_per_row * 2) as usize];
+        let rows = [[[0, 0, 255, 255], [0, 0, 0, 0]], [[64, 32, 16, 128], [10, 20, 30, 255]]];
+        for (y, row) in rows.iter().enumerate() {
+            let start = y * bytes_per_row as usize;
+            data[start..start + 8].copy_from_slice(&row.concat());
+        }
+        let layout = PixelLayout::for_format(wgpu::TextureFormat::Bgra8UnormSrgb, OutputAlpha::Premultiplied).unwrap();
+        let rgba = unpack_pixels(&data, 2, 2, bytes_per_row, layout);
+        assert_eq!(rgba.len(), 16);
+        assert_eq!(&rgba[0..4], &[255, 0, 0, 255]);
+        // 完全透明的像素颜色清零
+        assert_eq!(&rgba[4..8], &[0, 0, 0, 0]);
+        // 半透明像素还原为非预乘颜色
+        assert_eq!(&rgba[8..12], &[32, 64, 128, 128]);
+        assert_eq!(&rgba[12..16], &[30, 20, 10, 255]);
+
+        let straight = PixelLayout::for_format(wgpu::TextureFormat::Rgba8UnormSrgb, OutputAlpha::Straight).unwrap();
+        let rgba = unpack_pixels(&data, 2, 2, bytes_per_row, straight);
+        assert_eq!(&rgba[8..12], &[64, 32, 16, 128]);
+
+        assert!(PixelLayout::for_format(wgpu::TextureFormat::Rgba16Float, OutputAlpha::Straight).is_none());
+    }
+}
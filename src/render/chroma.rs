@@ -0,0 +1,269 @@
+// 绿幕抠像（色度键）
+//
+// 在 YCbCr 色彩空间中比较像素与关键色的色度距离：
+// 距离小于相似度的像素完全透明，之后在平滑度范围内逐渐过渡为不透明；
+// 溢色抑制将靠近关键色的像素去饱和，消除人物边缘的绿色反光。
+//
+// 此处的 CPU 实现与 shader.wgsl 中的 apply_chroma_key 保持一致，用于单元测试和取色。
+
+/// 默认关键色（常见绿幕颜色）
+pub const DEFAULT_KEY_COLOR: [f32; 3] = [0.0, 177.0 / 255.0, 64.0 / 255.0];
+
+/// 取色时平均的邻域半径（像素），降低摄像头噪点的影响
+const PICK_RADIUS: i64 = 2;
+
+/// 平滑度和溢色范围的下限，避免除零
+const MIN_RANGE: f32 = 1e-4;
+
+/// 抠像参数
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ChromaKey {
+    /// 是否启用
+    pub enabled: bool,
+    /// 关键色（线性 0.0 ~ 1.0）
+    pub key_color: [f32; 3],
+    /// 相似度：色度距离小于该值的像素完全透明（0.0 ~ 1.0）
+    pub similarity: f32,
+    /// 平滑度：透明到不透明的过渡范围（0.0 ~ 1.0）
+    pub smoothness: f32,
+    /// 溢色抑制范围，0 表示不抑制（0.0 ~ 1.0）
+    pub spill: f32,
+}
+
+impl Default for ChromaKey {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            key_color: DEFAULT_KEY_COLOR,
+            similarity: 0.2,
+            smoothness: 0.08,
+            spill: 0.1,
+        }
+    }
+}
+
+impl ChromaKey {
+    /// 对单个像素抠像，返回溢色抑制后的颜色和抠像透明度
+    pub fn apply(&self, rgb: [f32; 3]) -> ([f32; 3], f32) {
+        if !self.enabled {
+            return (rgb, 1.0);
+        }
+
+        let pixel = rgb_to_cbcr(rgb);
+        let key = rgb_to_cbcr(self.key_color);
+        let distance = ((pixel[0] - key[0]).powi(2) + (pixel[1] - key[1]).powi(2)).sqrt();
+        let base_mask = distance - self.similarity;
+
+        let alpha = (base_mask / self.smoothness.max(MIN_RANGE)).clamp(0.0, 1.0).powf(1.5);
+
+        let output = if self.spill > 0.0 {
+            let spill = (base_mask / self.spill).clamp(0.0, 1.0).powf(1.5);
+            let gray = luminance(rgb).clamp(0.0, 1.0);
+            [
+                gray + (rgb[0] - gray) * spill,
+                gray + (rgb[1] - gray) * spill,
+                gray + (rgb[2] - gray) * spill,
+            ]
+        } else {
+            rgb
+        };
+
+        (output, alpha)
+    }
+}
+
+/// RGB 转换为 BT.601 色度分量 (Cb, Cr)
+fn rgb_to_cbcr(rgb: [f32; 3]) -> [f32; 2] {
+    [
+        -0.168736 * rgb[0] - 0.331264 * rgb[1] + 0.5 * rgb[2],
+        0.5 * rgb[0] - 0.418688 * rgb[1] - 0.081312 * rgb[2],
+    ]
+}
+
+/// Rec.709 亮度
+fn luminance(rgb: [f32; 3]) -> f32 {
+    0.2126 * rgb[0] + 0.7152 * rgb[1] + 0.0722 * rgb[2]
+}
+
+/// 在 RGB8/RGBA8 帧的纹理坐标处取色（邻域平均），坐标超出范围时返回 None
+pub fn sample_key_color(pixels: &[u8], width: u32, height: u32, bytes_per_pixel: u32, tex_coords: (f32, f32)) -> Option<[f32; 3]> {
+    let (u, v) = tex_coords;
+    if width == 0 || height == 0 || !(0.0..=1.0).contains(&u) || !(0.0..=1.0).contains(&v) {
+        return None;
+    }
+    if bytes_per_pixel < 3 || pixels.len() < (width * height * bytes_per_pixel) as usize {
+        return None;
+    }
+
+    let center_x = ((u * width as f32) as i64).min(width as i64 - 1);
+    let center_y = ((v * height as f32) as i64).min(height as i64 - 1);
+
+    let mut sum = [0u32; 3];
+    let mut count = 0u32;
+    for y in (center_y - PICK_RADIUS)..=(center_y + PICK_RADIUS) {
+        for x in (center_x - PICK_RADIUS)..=(center_x + PICK_RADIUS) {
+            if x < 0 || y < 0 || x >= width as i64 || y >= height as i64 {
+                continue;
+            }
+            let index = ((y as u32 * width + x as u32) * bytes_per_pixel) as usize;
+            for (channel, total) in sum.iter_mut().enumerate() {
+                *total += pixels[index + channel] as u32;
+            }
+            count += 1;
+        }
+    }
+
+    let scale = 1.0 / (count as f32 * 255.0);
+    Some([sum[0] as f32 * scale, sum[1] as f32 * scale, sum[2] as f32 * scale])
+}
+
+/// 抠像统一缓冲区数据（与 shader.wgsl 中的 ChromaKeyUniforms 对应）
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct ChromaKeyUniforms {
+    key_color: [f32; 4], // 关键色 rgb，w 为是否启用
+    params: [f32; 4],    // 相似度、平滑度、溢色范围、保留
+}
+
+impl Default for ChromaKeyUniforms {
+    fn default() -> Self {
+        Self::new(&ChromaKey::default())
+    }
+}
+
+impl ChromaKeyUniforms {
+    pub fn new(key: &ChromaKey) -> Self {
+        Self {
+            key_color: [
+                key.key_color[0],
+                key.key_color[1],
+                key.key_color[2],
+                if key.enabled { 1.0 } else { 0.0 },
+            ],
+            params: [key.similarity, key.smoothness.max(MIN_RANGE), key.spill, 0.0],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn enabled_key() -> ChromaKey {
+        ChromaKey { enabled: true, ..Default::default() }
+    }
+
+    #[test]
+    fn test_default_chroma_key() {
+        let key = ChromaKey::default();
+        assert!(!key.enabled);
+        assert_eq!(key.key_color, DEFAULT_KEY_COLOR);
+        assert_eq!(key.similarity, 0.2);
+        assert_eq!(key.smoothness, 0.08);
+        assert_eq!(key.spill, 0.1);
+    }
+
+    #[test]
+    fn test_disabled_key_is_passthrough() {
+        let key = ChromaKey::default();
+        assert_eq!(key.apply(DEFAULT_KEY_COLOR), (DEFAULT_KEY_COLOR, 1.0));
+    }
+
+    #[test]
+    fn test_key_color_becomes_transparent() {
+        let key = enabled_key();
+        let (_, alpha) = key.apply(DEFAULT_KEY_COLOR);
+        assert_eq!(alpha, 0.0);
+
+        // 亮度不同但色度相近的绿色同样被抠除
+        let (_, alpha) = key.apply([0.1, 0.8, 0.35]);
+        assert_eq!(alpha, 0.0);
+    }
+
+    #[test]
+    fn test_foreground_stays_opaque() {
+        let key = enabled_key();
+        for rgb in [[0.9, 0.7, 0.6], [0.2, 0.2, 0.8], [1.0, 0.0, 0.0], [0.5, 0.5, 0.5]] {
+            let (_, alpha) = key.apply(rgb);
+            assert_eq!(alpha, 1.0, "{:?} 不应被抠除", rgb);
+        }
+    }
+
+    #[test]
+    fn test_smoothness_produces_gradual_edge() {
+        let key = ChromaKey { similarity: 0.1, smoothness: 0.4, ..enabled_key() };
+
+        // 从关键色向灰色插值，透明度应单调递增
+        let mut last_alpha = -1.0;
+        for step in 0..=10 {
+            let t = step as f32 / 10.0;
+            let rgb = [
+                DEFAULT_KEY_COLOR[0] + (0.5 - DEFAULT_KEY_COLOR[0]) * t,
+                DEFAULT_KEY_COLOR[1] + (0.5 - DEFAULT_KEY_COLOR[1]) * t,
+                DEFAULT_KEY_COLOR[2] + (0.5 - DEFAULT_KEY_COLOR[2]) * t,
+            ];
+            let (_, alpha) = key.apply(rgb);
+            assert!(alpha >= last_alpha);
+            last_alpha = alpha;
+        }
+        assert!(last_alpha > 0.0 && last_alpha < 1.0);
+    }
+
+    #[test]
+    fn test_spill_suppression_desaturates_green_fringe() {
+        let key = ChromaKey { similarity: 0.2, smoothness: 0.01, spill: 0.5, ..enabled_key() };
+        let fringe = [0.55, 0.7, 0.5];
+        let (suppressed, alpha) = key.apply(fringe);
+        assert_eq!(alpha, 1.0);
+        // 绿色分量相对红色的优势减小
+        assert!(suppressed[1] - suppressed[0] < fringe[1] - fringe[0]);
+
+        let no_spill = ChromaKey { spill: 0.0, ..key };
+        assert_eq!(no_spill.apply(fringe).0, fringe);
+    }
+
+    #[test]
+    fn test_sample_key_color() {
+        // 8x4 帧：左半部分绿色，右半部分红色
+        let mut rgba = Vec::new();
+        for _y in 0..4 {
+            for x in 0..8 {
+                let pixel = if x < 4 { [0, 255, 0, 255] } else { [255, 0, 0, 255] };
+                rgba.extend_from_slice(&pixel);
+            }
+        }
+
+        let color = sample_key_color(&rgba, 8, 4, 4, (0.0, 0.0)).unwrap();
+        assert_eq!(color, [0.0, 1.0, 0.0]);
+
+        // 中心处邻域包含两种颜色
+        let color = sample_key_color(&rgba, 8, 4, 4, (0.5, 0.5)).unwrap();
+        assert!(color[0] > 0.0 && color[1] > 0.0);
+        assert_eq!(color[2], 0.0);
+
+        assert!(sample_key_color(&rgba, 8, 4, 4, (1.0, 1.0)).is_some());
+        assert!(sample_key_color(&rgba, 8, 4, 4, (1.5, 0.5)).is_none());
+        assert!(sample_key_color(&rgba, 8, 4, 4, (0.5, -0.1)).is_none());
+        assert!(sample_key_color(&rgba[..8], 8, 4, 4, (0.5, 0.5)).is_none());
+
+        // 紧凑的 RGB8 数据结果相同
+        let rgb: Vec<u8> = rgba.chunks_exact(4).flat_map(|p| [p[0], p[1], p[2]]).collect();
+        for coords in [(0.0, 0.0), (0.5, 0.5), (1.0, 1.0)] {
+            assert_eq!(sample_key_color(&rgb, 8, 4, 3, coords), sample_key_color(&rgba, 8, 4, 4, coords));
+        }
+        assert!(sample_key_color(&rgb, 8, 4, 4, (0.5, 0.5)).is_none());
+    }
+
+    #[test]
+    fn test_chroma_key_uniforms() {
+        let uniforms = ChromaKeyUniforms::default();
+        assert_eq!(uniforms.key_color[3], 0.0);
+        assert_eq!(std::mem::size_of::<ChromaKeyUniforms>() % 16, 0);
+
+        let key = ChromaKey { key_color: [0.0, 0.0, 1.0], smoothness: 0.0, ..enabled_key() };
+        let uniforms = ChromaKeyUniforms::new(&key);
+        assert_eq!(uniforms.key_color, [0.0, 0.0, 1.0, 1.0]);
+        assert_eq!(uniforms.params[0], 0.2);
+        assert!(uniforms.params[1] > 0.0);
+    }
+}
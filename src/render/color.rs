@@ -0,0 +1,679 @@
+// 颜色调整与 LUT 滤镜
+//
+// 视频帧在片段着色器中依次经过：
+// 预设颜色矩阵 → 亮度 → 对比度 → 饱和度 → 色相偏移 → 色温 → 伽马 → 3D LUT
+// 调色在 sRGB 编码值上进行（预设和 .cube LUT 都按编码值定义），结果再解码为线性值参与合成。
+//
+// 本模块负责参数定义、预设外观、.cube 文件解析以及打包为统一缓冲区数据，
+// 并提供与着色器一致的 CPU 实现（供参考合成器使用），不依赖 GPU，可以直接进行单元测试。
+
+use crate::error::RenderError;
+use crate::render::alpha::{linear_to_srgb, srgb_to_linear};
+use log::debug;
+use std::path::Path;
+
+/// 支持的最大 LUT 边长（128³ 的 RGBA8 纹理约 8MB）
+pub const MAX_LUT_SIZE: u32 = 128;
+
+/// 基础颜色调整参数
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ColorAdjustments {
+    /// 亮度偏移（-1.0 ~ 1.0，0 为不变）
+    pub brightness: f32,
+    /// 对比度（0.0 ~ 4.0，1 为不变）
+    pub contrast: f32,
+    /// 饱和度（0.0 ~ 4.0，1 为不变，0 为灰度）
+    pub saturation: f32,
+    /// 伽马（0.1 ~ 5.0，1 为不变）
+    pub gamma: f32,
+    /// 色相偏移（度，-180 ~ 180）
+    pub hue_shift: f32,
+    /// 色温（-1.0 偏冷 ~ 1.0 偏暖，0 为不变）
+    pub temperature: f32,
+}
+
+impl Default for ColorAdjustments {
+    fn default() -> Self {
+        Self {
+            brightness: 0.0,
+            contrast: 1.0,
+            saturation: 1.0,
+            gamma: 1.0,
+            hue_shift: 0.0,
+            temperature: 0.0,
+        }
+    }
+}
+
+impl ColorAdjustments {
+    /// 是否为不改变画面的中性参数
+    pub fn is_neutral(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// 预设外观
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum ColorPreset {
+    /// 无预设
+    #[default]
+    None,
+    /// 灰度
+    Grayscale,
+    /// 复古（棕褐色调）
+    Sepia,
+    /// 高对比度
+    HighContrast,
+}
+
+impl ColorPreset {
+    /// 所有预设（用于菜单）
+    pub const ALL: [ColorPreset; 4] = [
+        ColorPreset::None,
+        ColorPreset::Grayscale,
+        ColorPreset::Sepia,
+        ColorPreset::HighContrast,
+    ];
+
+    /// 配置文件中使用的名称
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ColorPreset::None => "None",
+            ColorPreset::Grayscale => "Grayscale",
+            ColorPreset::Sepia => "Sepia",
+            ColorPreset::HighContrast => "HighContrast",
+        }
+    }
+
+    /// 从配置名称解析预设
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "None" => Some(ColorPreset::None),
+            "Grayscale" => Some(ColorPreset::Grayscale),
+            "Sepia" => Some(ColorPreset::Sepia),
+            "HighContrast" => Some(ColorPreset::HighContrast),
+            _ => None,
+        }
+    }
+
+    /// 菜单显示名称
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            ColorPreset::None => "原色",
+            ColorPreset::Grayscale => "灰度",
+            ColorPreset::Sepia => "复古",
+            ColorPreset::HighContrast => "高对比度",
+        }
+    }
+
+    /// 预设对应的仿射颜色矩阵（列主序，第 4 列为偏移）
+    pub fn matrix(&self) -> [[f32; 4]; 4] {
+        match self {
+            ColorPreset::None => [
+                [1.0, 0.0, 0.0, 0.0],
+                [0.0, 1.0, 0.0, 0.0],
+                [0.0, 0.0, 1.0, 0.0],
+                [0.0, 0.0, 0.0, 1.0],
+            ],
+            // Rec.709 亮度系数
+            ColorPreset::Grayscale => [
+                [0.2126, 0.2126, 0.2126, 0.0],
+                [0.7152, 0.7152, 0.7152, 0.0],
+                [0.0722, 0.0722, 0.0722, 0.0],
+                [0.0, 0.0, 0.0, 1.0],
+            ],
+            ColorPreset::Sepia => [
+                [0.393, 0.349, 0.272, 0.0],
+                [0.769, 0.686, 0.534, 0.0],
+                [0.189, 0.168, 0.131, 0.0],
+                [0.0, 0.0, 0.0, 1.0],
+            ],
+            // 以 0.5 为中心放大 1.5 倍
+            ColorPreset::HighContrast => [
+                [1.5, 0.0, 0.0, 0.0],
+                [0.0, 1.5, 0.0, 0.0],
+                [0.0, 0.0, 1.5, 0.0],
+                [-0.25, -0.25, -0.25, 1.0],
+            ],
+        }
+    }
+}
+
+/// 3D 颜色查找表（.cube 格式）
+#[derive(Debug, Clone, PartialEq)]
+pub struct CubeLut {
+    title: Option<String>,
+    size: u32,
+    domain_min: [f32; 3],
+    domain_max: [f32; 3],
+    // 按 R 变化最快、B 变化最慢的顺序排列，与 3D 纹理的内存布局一致
+    data: Vec<[f32; 3]>,
+}
+
+impl CubeLut {
+    /// 创建不改变颜色的恒等 LUT
+    pub fn identity(size: u32) -> Self {
+        let size = size.clamp(2, MAX_LUT_SIZE);
+        let scale = 1.0 / (size - 1) as f32;
+        let mut data = Vec::with_capacity((size * size * size) as usize);
+        for b in 0..size {
+            for g in 0..size {
+                for r in 0..size {
+                    data.push([r as f32 * scale, g as f32 * scale, b as f32 * scale]);
+                }
+            }
+        }
+
+        Self {
+            title: None,
+            size,
+            domain_min: [0.0; 3],
+            domain_max: [1.0; 3],
+            data,
+        }
+    }
+
+    /// 从文件加载 .cube LUT
+    pub fn load(path: &Path) -> Result<Self, RenderError> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| RenderError::LutLoadFailed(format!("无法读取 {}: {}", path.display(), e)))?;
+        Self::parse(&content)
+    }
+
+    /// 解析 .cube 文件内容
+    pub fn parse(content: &str) -> Result<Self, RenderError> {
+        let mut title = None;
+        let mut size: Option<u32> = None;
+        let mut domain_min = [0.0f32; 3];
+        let mut domain_max = [1.0f32; 3];
+        let mut data = Vec::new();
+
+        for (index, raw_line) in content.lines().enumerate() {
+            let line_number = index + 1;
+            let line = raw_line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let mut parts = line.split_whitespace();
+            let keyword = parts.next().unwrap_or_default();
+
+            match keyword {
+                "TITLE" => {
+                    let rest = line["TITLE".len()..].trim();
+                    title = Some(rest.trim_matches('"').to_string());
+                }
+                "LUT_3D_SIZE" => {
+                    if size.is_some() {
+                        return Err(lut_error(line_number, "重复的 LUT_3D_SIZE"));
+                    }
+                    let value: u32 = parts.next()
+                        .and_then(|v| v.parse().ok())
+                        .ok_or_else(|| lut_error(line_number, "LUT_3D_SIZE 不是有效的整数"))?;
+                    if !(2..=MAX_LUT_SIZE).contains(&value) {
+                        return Err(lut_error(line_number, &format!("LUT_3D_SIZE {} 超出范围 2-{}", value, MAX_LUT_SIZE)));
+                    }
+                    size = Some(value);
+                    data.reserve((value * value * value) as usize);
+                }
+                "LUT_1D_SIZE" => {
+                    return Err(lut_error(line_number, "不支持 1D LUT"));
+                }
+                "DOMAIN_MIN" => domain_min = parse_triplet(parts, line_number)?,
+                "DOMAIN_MAX" => domain_max = parse_triplet(parts, line_number)?,
+                "LUT_3D_INPUT_RANGE" => {
+                    // 部分软件导出的输入范围写法：LUT_3D_INPUT_RANGE min max
+                    let values: Vec<f32> = parts.filter_map(|v| v.parse().ok()).collect();
+                    if values.len() != 2 {
+                        return Err(lut_error(line_number, "LUT_3D_INPUT_RANGE 需要两个数值"));
+                    }
+                    domain_min = [values[0]; 3];
+                    domain_max = [values[1]; 3];
+                }
+                _ if keyword.starts_with(|c: char| c.is_ascii_alphabetic()) => {
+                    debug!("忽略未知的 LUT 关键字: {}", keyword);
+                }
+                _ => {
+                    if size.is_none() {
+                        return Err(lut_error(line_number, "数据行出现在 LUT_3D_SIZE 之前"));
+                    }
+                    let rgb = parse_triplet(line.split_whitespace(), line_number)?;
+                    data.push(rgb);
+                }
+            }
+        }
+
+        let size = size.ok_or_else(|| RenderError::LutLoadFailed("缺少 LUT_3D_SIZE".to_string()))?;
+        let expected = (size * size * size) as usize;
+        if data.len() != expected {
+            return Err(RenderError::LutLoadFailed(format!(
+                "数据条目数量 {} 与尺寸 {}³ = {} 不符", data.len(), size, expected
+            )));
+        }
+
+        if (0..3).any(|i| domain_max[i] <= domain_min[i]) {
+            return Err(RenderError::LutLoadFailed(format!(
+                "DOMAIN_MAX {:?} 必须大于 DOMAIN_MIN {:?}", domain_max, domain_min
+            )));
+        }
+
+        Ok(Self {
+            title,
+            size,
+            domain_min,
+            domain_max,
+            data,
+        })
+    }
+
+    /// LUT 标题
+    pub fn title(&self) -> Option<&str> {
+        self.title.as_deref()
+    }
+
+    /// LUT 边长
+    pub fn size(&self) -> u32 {
+        self.size
+    }
+
+    /// 输入颜色范围下限
+    pub fn domain_min(&self) -> [f32; 3] {
+        self.domain_min
+    }
+
+    /// 输入颜色范围上限
+    pub fn domain_max(&self) -> [f32; 3] {
+        self.domain_max
+    }
+
+    /// LUT 数据（R 变化最快）
+    pub fn data(&self) -> &[[f32; 3]] {
+        &self.data
+    }
+
+    /// 转换为 RGBA8 纹理数据
+    pub fn to_rgba8(&self) -> Vec<u8> {
+        let mut rgba = Vec::with_capacity(self.data.len() * 4);
+        for rgb in &self.data {
+            for &value in rgb {
+                rgba.push((value.clamp(0.0, 1.0) * 255.0).round() as u8);
+            }
+            rgba.push(255);
+        }
+        rgba
+    }
+
+    /// 按 GPU 三线性过滤采样 RGBA8 纹理版本的 LUT，coords 为 3D 纹理坐标（0.0 ~ 1.0）
+    pub fn sample_texture(&self, coords: [f32; 3]) -> [f32; 3] {
+        let size = self.size as usize;
+        let texel = |r: usize, g: usize, b: usize| -> [f32; 3] {
+            let value = self.data[r + g * size + b * size * size];
+            value.map(|v| (v.clamp(0.0, 1.0) * 255.0).round() / 255.0)
+        };
+
+        // 纹素中心位于 (i + 0.5) / size，边缘按 ClampToEdge 处理
+        let mut low = [0usize; 3];
+        let mut high = [0usize; 3];
+        let mut weight = [0.0f32; 3];
+        for axis in 0..3 {
+            let position = coords[axis] * size as f32 - 0.5;
+            let floor = position.floor();
+            weight[axis] = position - floor;
+            low[axis] = (floor.max(0.0) as usize).min(size - 1);
+            high[axis] = ((floor + 1.0).max(0.0) as usize).min(size - 1);
+        }
+
+        // 8 个相邻纹素按三个方向的权重混合
+        let mut output = [0.0; 3];
+        for corner in 0..8 {
+            let mut factor = 1.0;
+            let mut index = [0usize; 3];
+            for axis in 0..3 {
+                if corner >> axis & 1 == 1 {
+                    factor *= weight[axis];
+                    index[axis] = high[axis];
+                } else {
+                    factor *= 1.0 - weight[axis];
+                    index[axis] = low[axis];
+                }
+            }
+            let value = texel(index[0], index[1], index[2]);
+            for channel in 0..3 {
+                output[channel] += value[channel] * factor;
+            }
+        }
+        output
+    }
+}
+
+fn lut_error(line_number: usize, message: &str) -> RenderError {
+    RenderError::LutLoadFailed(format!("第 {} 行: {}", line_number, message))
+}
+
+/// 解析三个浮点数
+fn parse_triplet<'a>(mut parts: impl Iterator<Item = &'a str>, line_number: usize) -> Result<[f32; 3], RenderError> {
+    let mut values = [0.0f32; 3];
+    for value in values.iter_mut() {
+        *value = parts.next()
+            .and_then(|v| v.parse::<f32>().ok())
+            .filter(|v| v.is_finite())
+            .ok_or_else(|| lut_error(line_number, "需要三个有效的数值"))?;
+    }
+    if parts.next().is_some() {
+        return Err(lut_error(line_number, "数值数量多于三个"));
+    }
+    Ok(values)
+}
+
+/// 颜色调整统一缓冲区数据（与 shader.wgsl 中的 ColorUniforms 对应）
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct ColorUniforms {
+    preset_matrix: [[f32; 4]; 4],
+    adjust: [f32; 4], // 亮度、对比度、饱和度、1/伽马
+    extra: [f32; 4],  // 色相偏移（弧度）、色温、LUT 强度、LUT 边长
+    lut_domain_min: [f32; 4],
+    lut_domain_max: [f32; 4],
+}
+
+impl Default for ColorUniforms {
+    fn default() -> Self {
+        Self::new(&ColorAdjustments::default(), ColorPreset::None, None)
+    }
+}
+
+impl ColorUniforms {
+    /// 根据调整参数、预设和 LUT 生成统一缓冲区数据，lut 为 None 时不应用 LUT
+    pub fn new(adjustments: &ColorAdjustments, preset: ColorPreset, lut: Option<&CubeLut>) -> Self {
+        let (lut_strength, lut_size, domain_min, domain_max) = match lut {
+            Some(lut) => (1.0, lut.size() as f32, lut.domain_min(), lut.domain_max()),
+            // 未启用时着色器仍会采样恒等 LUT，保持有效的范围避免除零
+            None => (0.0, 2.0, [0.0; 3], [1.0; 3]),
+        };
+
+        Self {
+            preset_matrix: preset.matrix(),
+            adjust: [
+                adjustments.brightness,
+                adjustments.contrast,
+                adjustments.saturation,
+                1.0 / adjustments.gamma.max(0.01),
+            ],
+            extra: [
+                adjustments.hue_shift.to_radians(),
+                adjustments.temperature,
+                lut_strength,
+                lut_size,
+            ],
+            lut_domain_min: [domain_min[0], domain_min[1], domain_min[2], 0.0],
+            lut_domain_max: [domain_max[0], domain_max[1], domain_max[2], 0.0],
+        }
+    }
+
+    /// 对线性 RGB 调色（与 shader.wgsl 中的 apply_color_grading 保持一致）：
+    /// 先编码为 sRGB 再调色，结果解码回线性值。lut 需与创建统一缓冲区时传入的相同
+    pub fn apply(&self, rgb: [f32; 3], lut: Option<&CubeLut>) -> [f32; 3] {
+        let rgb = rgb.map(linear_to_srgb);
+        let m = &self.preset_matrix;
+        let mut rgb: [f32; 3] =
+            std::array::from_fn(|row| m[0][row] * rgb[0] + m[1][row] * rgb[1] + m[2][row] * rgb[2] + m[3][row]);
+
+        let [brightness, contrast, saturation, inverse_gamma] = self.adjust;
+        rgb = rgb.map(|v| (v + brightness - 0.5) * contrast + 0.5);
+        let gray = luminance(rgb);
+        rgb = rgb.map(|v| gray + (v - gray) * saturation);
+
+        // 绕灰度轴 (1,1,1)/√3 旋转色相（Rodrigues 公式）
+        let k = 0.57735027;
+        let (sin_h, cos_h) = self.extra[0].sin_cos();
+        let dot = k * (rgb[0] + rgb[1] + rgb[2]);
+        let cross = [k * (rgb[2] - rgb[1]), k * (rgb[0] - rgb[2]), k * (rgb[1] - rgb[0])];
+        rgb = std::array::from_fn(|i| rgb[i] * cos_h + cross[i] * sin_h + k * dot * (1.0 - cos_h));
+
+        // 正值偏暖（增红减蓝），负值偏冷
+        let t = self.extra[1];
+        rgb = [rgb[0] * (1.0 + 0.2 * t), rgb[1], rgb[2] * (1.0 - 0.2 * t)];
+
+        rgb = rgb.map(|v| v.clamp(0.0, 1.0).powf(inverse_gamma));
+
+        let strength = self.extra[2];
+        let Some(lut) = lut.filter(|_| strength > 0.0) else {
+            return rgb.map(srgb_to_linear);
+        };
+        let size = self.extra[3];
+        let coords: [f32; 3] = std::array::from_fn(|i| {
+            let domain = self.lut_domain_max[i] - self.lut_domain_min[i];
+            let normalized = ((rgb[i] - self.lut_domain_min[i]) / domain).clamp(0.0, 1.0);
+            normalized * ((size - 1.0) / size) + 0.5 / size
+        });
+        let graded = lut.sample_texture(coords);
+        std::array::from_fn(|i| srgb_to_linear(rgb[i] + (graded[i] - rgb[i]) * strength))
+    }
+}
+
+/// Rec.709 亮度
+fn luminance(rgb: [f32; 3]) -> f32 {
+    0.2126 * rgb[0] + 0.7152 * rgb[1] + 0.0722 * rgb[2]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_CUBE: &str = r#"
+# 由测试生成
+TITLE "Warm Look"
+LUT_3D_SIZE 2
+DOMAIN_MIN 0.0 0.0 0.0
+DOMAIN_MAX 1.0 1.0 1.0
+
+0.0 0.0 0.0
+1.0 0.0 0.0
+0.0 1.0 0.0
+1.0 1.0 0.0
+0.0 0.0 1.0
+1.0 0.0 1.0
+0.0 1.0 1.0
+1.0 1.0 1.0
+"#;
+
+    #[test]
+    fn test_default_adjustments_are_neutral() {
+        let adjustments = ColorAdjustments::default();
+        assert!(adjustments.is_neutral());
+        assert_eq!(adjustments.brightness, 0.0);
+        assert_eq!(adjustments.contrast, 1.0);
+        assert_eq!(adjustments.saturation, 1.0);
+        assert_eq!(adjustments.gamma, 1.0);
+        assert_eq!(adjustments.hue_shift, 0.0);
+        assert_eq!(adjustments.temperature, 0.0);
+
+        let tweaked = ColorAdjustments { saturation: 0.5, ..Default::default() };
+        assert!(!tweaked.is_neutral());
+    }
+
+    #[test]
+    fn test_preset_names() {
+        for preset in ColorPreset::ALL {
+            assert_eq!(ColorPreset::from_name(preset.as_str()), Some(preset));
+        }
+        assert_eq!(ColorPreset::from_name("Vintage"), None);
+        assert_eq!(ColorPreset::default(), ColorPreset::None);
+    }
+
+    #[test]
+    fn test_preset_matrices() {
+        // 按列主序计算 matrix * (r, g, b, 1)
+        let apply = |preset: ColorPreset, rgb: [f32; 3]| -> [f32; 3] {
+            let m = preset.matrix();
+            let mut out = [0.0; 3];
+            for (row, value) in out.iter_mut().enumerate() {
+                *value = m[0][row] * rgb[0] + m[1][row] * rgb[1] + m[2][row] * rgb[2] + m[3][row];
+            }
+            out
+        };
+
+        assert_eq!(apply(ColorPreset::None, [0.2, 0.4, 0.6]), [0.2, 0.4, 0.6]);
+
+        let gray = apply(ColorPreset::Grayscale, [1.0, 0.0, 0.0]);
+        assert!((gray[0] - 0.2126).abs() < 1e-6);
+        assert_eq!(gray[0], gray[1]);
+        assert_eq!(gray[1], gray[2]);
+
+        // 白色经过灰度矩阵仍为白色
+        let white = apply(ColorPreset::Grayscale, [1.0, 1.0, 1.0]);
+        assert!(white.iter().all(|v| (v - 1.0).abs() < 1e-4));
+
+        // 复古色调红色通道最强
+        let sepia = apply(ColorPreset::Sepia, [0.5, 0.5, 0.5]);
+        assert!(sepia[0] > sepia[1] && sepia[1] > sepia[2]);
+
+        // 高对比度保持中灰不变，拉开暗部和亮部
+        assert_eq!(apply(ColorPreset::HighContrast, [0.5, 0.5, 0.5]), [0.5, 0.5, 0.5]);
+        assert_eq!(apply(ColorPreset::HighContrast, [0.25, 0.75, 0.5]), [0.125, 0.875, 0.5]);
+    }
+
+    #[test]
+    fn test_parse_cube_lut() {
+        let lut = CubeLut::parse(SAMPLE_CUBE).unwrap();
+        assert_eq!(lut.title(), Some("Warm Look"));
+        assert_eq!(lut.size(), 2);
+        assert_eq!(lut.domain_min(), [0.0; 3]);
+        assert_eq!(lut.domain_max(), [1.0; 3]);
+        assert_eq!(lut.data().len(), 8);
+        assert_eq!(lut.data()[1], [1.0, 0.0, 0.0]);
+        assert_eq!(lut, CubeLut { title: Some("Warm Look".to_string()), ..CubeLut::identity(2) });
+    }
+
+    #[test]
+    fn test_parse_cube_input_range_and_unknown_keywords() {
+        let content = "LUT_3D_INPUT_RANGE 0.0 2.0\nLUT_IN_VIDEO_RANGE\nLUT_3D_SIZE 2\n".to_string()
+            + &"0.5 0.5 0.5\n".repeat(8);
+        let lut = CubeLut::parse(&content).unwrap();
+        assert_eq!(lut.title(), None);
+        assert_eq!(lut.domain_max(), [2.0; 3]);
+    }
+
+    #[test]
+    fn test_parse_cube_errors() {
+        let cases = [
+            ("", "缺少 LUT_3D_SIZE"),
+            ("LUT_1D_SIZE 16\n", "不支持 1D LUT"),
+            ("LUT_3D_SIZE 1\n", "超出范围"),
+            ("LUT_3D_SIZE abc\n", "不是有效的整数"),
+            ("0.0 0.0 0.0\nLUT_3D_SIZE 2\n", "LUT_3D_SIZE 之前"),
+            ("LUT_3D_SIZE 2\n0.0 0.0 0.0\n", "数据条目数量 1"),
+            ("LUT_3D_SIZE 2\n0.0 nan 0.0\n", "第 2 行"),
+            ("LUT_3D_SIZE 2\n0.0 0.0 0.0 0.0\n", "数值数量多于三个"),
+            ("DOMAIN_MIN 1 1 1\nDOMAIN_MAX 0 0 0\nLUT_3D_SIZE 2\n0 0 0\n0 0 0\n0 0 0\n0 0 0\n0 0 0\n0 0 0\n0 0 0\n0 0 0\n", "必须大于"),
+        ];
+
+        for (content, expected) in cases {
+            match CubeLut::parse(content) {
+                Err(RenderError::LutLoadFailed(message)) => {
+                    assert!(message.contains(expected), "{:?} 的错误信息 '{}' 应包含 '{}'", content, message, expected);
+                }
+                other => panic!("{:?} 应解析失败，实际为 {:?}", content, other),
+            }
+        }
+    }
+
+    #[test]
+    fn test_load_missing_lut_file() {
+        let result = CubeLut::load(Path::new("/nonexistent/look.cube"));
+        assert!(matches!(result, Err(RenderError::LutLoadFailed(_))));
+    }
+
+    #[test]
+    fn test_identity_lut_rgba() {
+        let lut = CubeLut::identity(3);
+        let rgba = lut.to_rgba8();
+        assert_eq!(rgba.len(), 27 * 4);
+        // (r=1, g=0, b=0) 位于索引 1，中间值四舍五入为 128
+        assert_eq!(&rgba[4..8], &[128, 0, 0, 255]);
+        // 最后一个条目为白色
+        assert_eq!(&rgba[26 * 4..], &[255, 255, 255, 255]);
+    }
+
+    #[test]
+    fn test_color_uniforms_packing() {
+        let uniforms = ColorUniforms::default();
+        assert_eq!(uniforms.adjust, [0.0, 1.0, 1.0, 1.0]);
+        assert_eq!(uniforms.extra, [0.0, 0.0, 0.0, 2.0]);
+        assert_eq!(uniforms.preset_matrix, ColorPreset::None.matrix());
+        // 统一缓冲区大小必须是 16 字节的倍数
+        assert_eq!(std::mem::size_of::<ColorUniforms>() % 16, 0);
+
+        let adjustments = ColorAdjustments { gamma: 2.0, hue_shift: 180.0, ..Default::default() };
+        let lut = CubeLut::identity(17);
+        let uniforms = ColorUniforms::new(&adjustments, ColorPreset::Sepia, Some(&lut));
+        assert_eq!(uniforms.adjust[3], 0.5);
+        assert!((uniforms.extra[0] - std::f32::consts::PI).abs() < 1e-6);
+        assert_eq!(uniforms.extra[2], 1.0);
+        assert_eq!(uniforms.extra[3], 17.0);
+        assert_eq!(uniforms.preset_matrix, ColorPreset::Sepia.matrix());
+    }
+
+    #[test]
+    fn test_lut_texture_sampling() {
+        // 恒等 LUT 在纹素中心之间线性插值，端点准确
+        let lut = CubeLut::identity(5);
+        let coords = |v: f32| v * 0.8 + 0.1;
+        let sampled = lut.sample_texture([coords(0.0), coords(1.0), coords(0.3)]);
+        assert!(sampled[0].abs() < 1e-6);
+        assert!((sampled[1] - 1.0).abs() < 1e-6);
+        assert!((sampled[2] - 0.3).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_color_uniforms_apply() {
+        let rgb = [0.2, 0.5, 0.8];
+        let neutral = ColorUniforms::default();
+        let output = neutral.apply(rgb, None);
+        assert!(output.iter().zip(rgb).all(|(a, b)| (a - b).abs() < 1e-5));
+
+        // 灰度预设与零饱和度结果一致
+        let gray = ColorUniforms::new(&ColorAdjustments::default(), ColorPreset::Grayscale, None).apply(rgb, None);
+        let desaturated = ColorAdjustments { saturation: 0.0, ..Default::default() };
+        let desaturated = ColorUniforms::new(&desaturated, ColorPreset::None, None).apply(rgb, None);
+        for (a, b) in gray.iter().zip(desaturated) {
+            assert!((a - b).abs() < 1e-5);
+        }
+
+        // 色相旋转 120° 使通道循环移位
+        let rotated = ColorAdjustments { hue_shift: 120.0, ..Default::default() };
+        let output = ColorUniforms::new(&rotated, ColorPreset::None, None).apply([1.0, 0.0, 0.0], None);
+        assert!((output[1] - 1.0).abs() < 1e-4 && output[0].abs() < 1e-4 && output[2].abs() < 1e-4);
+
+        // 恒等 LUT 不改变颜色（在 8 位量化误差内）
+        let lut = CubeLut::identity(17);
+        let output = ColorUniforms::new(&ColorAdjustments::default(), ColorPreset::None, Some(&lut)).apply(rgb, Some(&lut));
+        assert!(output.iter().zip(rgb).all(|(a, b)| (a - b).abs() < 2.0 / 255.0));
+    }
+
+    #[test]
+    fn test_identity_lut_is_noop() {
+        // LUT 按 sRGB 编码值采样，恒等 LUT 在编码空间中的误差不超过 8 位量化
+        let lut = CubeLut::identity(33);
+        let uniforms = ColorUniforms::new(&ColorAdjustments::default(), ColorPreset::None, Some(&lut));
+        for rgb in [[0.0, 0.0, 0.0], [1.0, 1.0, 1.0], [0.002, 0.05, 0.214], [0.5, 0.8, 0.01]] {
+            let output = uniforms.apply(rgb, Some(&lut));
+            for (value, expected) in output.iter().zip(rgb) {
+                let difference = (linear_to_srgb(*value) - linear_to_srgb(expected)).abs();
+                assert!(difference < 1.0 / 255.0, "{:?} -> {:?}", rgb, output);
+            }
+        }
+    }
+
+    #[test]
+    fn test_lut_maps_mid_grey_to_table_value() {
+        // 3³ LUT 的中心条目对应编码值 0.5 的中灰，输出为该条目的值（解码为线性值）
+        let mut lut = CubeLut::identity(3);
+        lut.data[1 + 3 + 9] = [0.2, 0.4, 0.6];
+        let uniforms = ColorUniforms::new(&ColorAdjustments::default(), ColorPreset::None, Some(&lut));
+        let output = uniforms.apply([srgb_to_linear(0.5); 3], Some(&lut));
+        for (value, expected) in output.iter().zip([0.2, 0.4, 0.6]) {
+            assert!((value - srgb_to_linear(expected)).abs() < 1e-4, "{:?}", output);
+        }
+    }
+}
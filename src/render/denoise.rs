@@ -0,0 +1,628 @@
+// 磨皮平滑与时域降噪
+//
+// 视频帧在主渲染通道之前经过两个可选的 GPU 通道：
+// 1. 双边滤波：保留边缘的同时平滑皮肤和噪点
+// 2. 时域降噪：静止区域与上一帧混合，运动区域保持当前帧
+//
+// 当帧耗时持续超过预算时，DenoiseGovernor 依次降低滤波半径、旁路全部通道，
+// 耗时恢复后再逐级恢复。
+
+use log::{debug, info, warn};
+use std::time::Duration;
+
+/// 完整质量下的双边滤波半径（像素）
+const FULL_RADIUS: u32 = 3;
+
+/// 降级后的双边滤波半径（像素）
+const REDUCED_RADIUS: u32 = 1;
+
+/// 时域混合的最大比例，保证当前帧始终有贡献，避免画面冻结
+pub const MAX_TEMPORAL_BLEND: f32 = 0.9;
+
+/// 连续超出预算多少帧后降级
+const DEGRADE_AFTER_FRAMES: u32 = 10;
+
+/// 连续低于恢复阈值多少帧后恢复一级
+const RECOVER_AFTER_FRAMES: u32 = 120;
+
+/// 帧耗时低于预算的该比例时才计入恢复
+const RECOVER_RATIO: f32 = 0.75;
+
+/// 运动阈值下限，避免着色器中 smoothstep 的两个边界重合
+const MIN_MOTION_THRESHOLD: f32 = 0.001;
+
+/// 降噪中间纹理格式（与视频纹理一致）
+const TARGET_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8UnormSrgb;
+
+/// 降噪参数
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DenoiseSettings {
+    /// 启用双边滤波平滑
+    pub smoothing_enabled: bool,
+    /// 平滑强度（0.0 ~ 1.0）
+    pub smoothing_strength: f32,
+    /// 启用时域降噪
+    pub temporal_enabled: bool,
+    /// 时域降噪强度（0.0 ~ 1.0）
+    pub temporal_strength: f32,
+    /// 运动阈值：颜色变化超过该值的像素视为运动（0.0 ~ 1.0）
+    pub motion_threshold: f32,
+    /// 旁路全部降噪通道
+    pub bypass: bool,
+}
+
+impl Default for DenoiseSettings {
+    fn default() -> Self {
+        Self {
+            smoothing_enabled: false,
+            smoothing_strength: 0.5,
+            temporal_enabled: false,
+            temporal_strength: 0.5,
+            motion_threshold: 0.05,
+            bypass: false,
+        }
+    }
+}
+
+impl DenoiseSettings {
+    /// 是否需要运行任一降噪通道
+    pub fn is_active(&self) -> bool {
+        !self.bypass && (self.smoothing_active() || self.temporal_active())
+    }
+
+    fn smoothing_active(&self) -> bool {
+        self.smoothing_enabled && self.smoothing_strength > 0.0
+    }
+
+    fn temporal_active(&self) -> bool {
+        self.temporal_enabled && self.temporal_strength > 0.0
+    }
+
+    /// 按降级等级得到实际生效的参数
+    pub fn effective(&self, level: DenoiseLevel) -> Self {
+        match level {
+            DenoiseLevel::Bypass => Self { bypass: true, ..*self },
+            DenoiseLevel::Full | DenoiseLevel::Reduced => *self,
+        }
+    }
+}
+
+/// 降噪降级等级
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum DenoiseLevel {
+    /// 完整质量
+    Full,
+    /// 缩小滤波半径
+    Reduced,
+    /// 旁路全部通道
+    Bypass,
+}
+
+impl DenoiseLevel {
+    /// 双边滤波半径
+    pub fn smoothing_radius(&self) -> u32 {
+        match self {
+            DenoiseLevel::Full => FULL_RADIUS,
+            DenoiseLevel::Reduced | DenoiseLevel::Bypass => REDUCED_RADIUS,
+        }
+    }
+
+    fn degrade(self) -> Self {
+        match self {
+            DenoiseLevel::Full => DenoiseLevel::Reduced,
+            DenoiseLevel::Reduced | DenoiseLevel::Bypass => DenoiseLevel::Bypass,
+        }
+    }
+
+    fn recover(self) -> Self {
+        match self {
+            DenoiseLevel::Full | DenoiseLevel::Reduced => DenoiseLevel::Full,
+            DenoiseLevel::Bypass => DenoiseLevel::Reduced,
+        }
+    }
+}
+
+/// 根据帧耗时自动调整降噪等级
+#[derive(Debug, Clone)]
+pub struct DenoiseGovernor {
+    budget: Duration,
+    level: DenoiseLevel,
+    over_budget_frames: u32,
+    under_budget_frames: u32,
+}
+
+impl DenoiseGovernor {
+    /// 创建调节器，budget 为单帧渲染耗时预算
+    pub fn new(budget: Duration) -> Self {
+        Self {
+            budget,
+            level: DenoiseLevel::Full,
+            over_budget_frames: 0,
+            under_budget_frames: 0,
+        }
+    }
+
+    /// 当前降级等级
+    pub fn level(&self) -> DenoiseLevel {
+        self.level
+    }
+
+    /// 单帧耗时预算
+    pub fn budget(&self) -> Duration {
+        self.budget
+    }
+
+    /// 更新耗时预算并恢复完整质量
+    pub fn set_budget(&mut self, budget: Duration) {
+        self.budget = budget;
+        self.reset();
+    }
+
+    /// 恢复完整质量并清除计数
+    pub fn reset(&mut self) {
+        self.level = DenoiseLevel::Full;
+        self.over_budget_frames = 0;
+        self.under_budget_frames = 0;
+    }
+
+    /// 记录一帧的渲染耗时，等级发生变化时返回新等级
+    pub fn record(&mut self, render_time: Duration) -> Option<DenoiseLevel> {
+        if render_time > self.budget {
+            self.under_budget_frames = 0;
+            self.over_budget_frames += 1;
+            if self.over_budget_frames >= DEGRADE_AFTER_FRAMES && self.level != DenoiseLevel::Bypass {
+                self.over_budget_frames = 0;
+                return self.change_level(self.level.degrade(), render_time);
+            }
+        } else if render_time.as_secs_f32() < self.budget.as_secs_f32() * RECOVER_RATIO {
+            self.over_budget_frames = 0;
+            self.under_budget_frames += 1;
+            if self.under_budget_frames >= RECOVER_AFTER_FRAMES && self.level != DenoiseLevel::Full {
+                self.under_budget_frames = 0;
+                return self.change_level(self.level.recover(), render_time);
+            }
+        } else {
+            // 接近预算时保持当前等级
+            self.over_budget_frames = 0;
+            self.under_budget_frames = 0;
+        }
+
+        None
+    }
+
+    fn change_level(&mut self, level: DenoiseLevel, render_time: Duration) -> Option<DenoiseLevel> {
+        if level > self.level {
+            warn!("渲染耗时 {:.1}ms 超过预算 {:.1}ms，降噪降级为 {:?}",
+                  render_time.as_secs_f32() * 1000.0, self.budget.as_secs_f32() * 1000.0, level);
+        } else {
+            info!("渲染耗时恢复，降噪等级提升为 {:?}", level);
+        }
+        self.level = level;
+        Some(level)
+    }
+}
+
+/// 时域混合比例：运动越大越接近当前帧（与 denoise.wgsl 中的 fs_temporal 保持一致）
+pub fn temporal_blend_factor(difference: f32, strength: f32, motion_threshold: f32) -> f32 {
+    let edge0 = motion_threshold;
+    let edge1 = motion_threshold * 3.0;
+    let t = ((difference - edge0) / (edge1 - edge0).max(f32::EPSILON)).clamp(0.0, 1.0);
+    let motion = t * t * (3.0 - 2.0 * t);
+    strength * MAX_TEMPORAL_BLEND * (1.0 - motion)
+}
+
+/// 降噪统一缓冲区数据（与 denoise.wgsl 中的 DenoiseUniforms 对应）
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct DenoiseUniforms {
+    params: [f32; 4], // 平滑强度、滤波半径、时域强度、运动阈值
+    extra: [f32; 4],  // 是否有历史帧、最大混合比例、保留、保留
+}
+
+impl DenoiseUniforms {
+    pub fn new(settings: &DenoiseSettings, level: DenoiseLevel, has_history: bool) -> Self {
+        Self {
+            params: [
+                settings.smoothing_strength,
+                level.smoothing_radius() as f32,
+                settings.temporal_strength,
+                settings.motion_threshold.max(MIN_MOTION_THRESHOLD),
+            ],
+            extra: [if has_history { 1.0 } else { 0.0 }, MAX_TEMPORAL_BLEND, 0.0, 0.0],
+        }
+    }
+}
+
+/// 与视频尺寸相同的中间纹理
+struct DenoiseTargets {
+    width: u32,
+    height: u32,
+    smoothed_view: wgpu::TextureView,
+    output: wgpu::Texture,
+    output_view: wgpu::TextureView,
+    history: wgpu::Texture,
+    history_view: wgpu::TextureView,
+    has_history: bool,
+}
+
+/// 降噪 GPU 通道
+pub struct DenoisePipeline {
+    bilateral_pipeline: wgpu::RenderPipeline,
+    temporal_pipeline: wgpu::RenderPipeline,
+    bind_group_layout: wgpu::BindGroupLayout,
+    uniform_buffer: wgpu::Buffer,
+    targets: Option<DenoiseTargets>,
+}
+
+impl DenoisePipeline {
+    pub fn new(device: &wgpu::Device) -> Self {
+        let texture_entry = |binding| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Texture {
+                multisampled: false,
+                view_dimension: wgpu::TextureViewDimension::D2,
+                sample_type: wgpu::TextureSampleType::Float { filterable: false },
+            },
+            count: None,
+        };
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[
+                // 输入纹理
+                texture_entry(0),
+                // 历史帧纹理
+                texture_entry(1),
+                // 降噪参数
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+            label: Some("denoise_bind_group_layout"),
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Denoise Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Denoise Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("denoise.wgsl").into()),
+        });
+
+        let create_pipeline = |label, entry_point| {
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some(label),
+                layout: Some(&pipeline_layout),
+                vertex: wgpu::VertexState {
+                    module: &shader,
+                    entry_point: "vs_fullscreen",
+                    buffers: &[],
+                    compilation_options: wgpu::PipelineCompilationOptions::default(),
+                },
+                fragment: Some(wgpu::FragmentState {
+                    module: &shader,
+                    entry_point,
+                    targets: &[Some(wgpu::ColorTargetState {
+                        format: TARGET_FORMAT,
+                        blend: None,
+                        write_mask: wgpu::ColorWrites::ALL,
+                    })],
+                    compilation_options: wgpu::PipelineCompilationOptions::default(),
+                }),
+                primitive: wgpu::PrimitiveState::default(),
+                depth_stencil: None,
+                multisample: wgpu::MultisampleState::default(),
+                multiview: None,
+            })
+        };
+
+        let bilateral_pipeline = create_pipeline("Bilateral Pipeline", "fs_bilateral");
+        let temporal_pipeline = create_pipeline("Temporal Denoise Pipeline", "fs_temporal");
+
+        let uniform_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Denoise Uniform Buffer"),
+            size: std::mem::size_of::<DenoiseUniforms>() as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        Self {
+            bilateral_pipeline,
+            temporal_pipeline,
+            bind_group_layout,
+            uniform_buffer,
+            targets: None,
+        }
+    }
+
+    /// 确保中间纹理与视频尺寸一致，重新创建时返回 true（输出纹理视图已变化）
+    pub fn ensure_targets(&mut self, device: &wgpu::Device, width: u32, height: u32) -> bool {
+        if self.targets.as_ref().is_some_and(|t| t.width == width && t.height == height) {
+            return false;
+        }
+
+        debug!("创建降噪中间纹理: {}x{}", width, height);
+        let create_texture = |label, usage| {
+            device.create_texture(&wgpu::TextureDescriptor {
+                label: Some(label),
+                size: wgpu::Extent3d {
+                    width,
+                    height,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: TARGET_FORMAT,
+                usage: wgpu::TextureUsages::TEXTURE_BINDING | usage,
+                view_formats: &[],
+            })
+        };
+
+        let smoothed = create_texture("denoise_smoothed", wgpu::TextureUsages::RENDER_ATTACHMENT);
+        let output = create_texture("denoise_output", wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC);
+        let history = create_texture("denoise_history", wgpu::TextureUsages::COPY_DST);
+
+        self.targets = Some(DenoiseTargets {
+            width,
+            height,
+            smoothed_view: smoothed.create_view(&wgpu::TextureViewDescriptor::default()),
+            output_view: output.create_view(&wgpu::TextureViewDescriptor::default()),
+            history_view: history.create_view(&wgpu::TextureViewDescriptor::default()),
+            output,
+            history,
+            has_history: false,
+        });
+        true
+    }
+
+    /// 降噪结果纹理视图（需先调用 ensure_targets）
+    pub fn output_view(&self) -> Option<&wgpu::TextureView> {
+        self.targets.as_ref().map(|t| &t.output_view)
+    }
+
+    /// 丢弃历史帧，例如切换摄像头后避免与旧画面混合
+    pub fn clear_history(&mut self) {
+        if let Some(targets) = &mut self.targets {
+            targets.has_history = false;
+        }
+    }
+
+    /// 释放中间纹理
+    pub fn release_targets(&mut self) {
+        self.targets = None;
+    }
+
+    /// 记录降噪通道，结果写入 output_view
+    pub fn encode(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        encoder: &mut wgpu::CommandEncoder,
+        source_view: &wgpu::TextureView,
+        settings: &DenoiseSettings,
+        level: DenoiseLevel,
+    ) {
+        let Some(targets) = &mut self.targets else {
+            warn!("降噪中间纹理未创建，跳过降噪");
+            return;
+        };
+
+        let smoothing = settings.smoothing_active();
+        let temporal = settings.temporal_active();
+
+        let uniforms = DenoiseUniforms::new(settings, level, targets.has_history);
+        queue.write_buffer(&self.uniform_buffer, 0, bytemuck::cast_slice(&[uniforms]));
+
+        let run_pass = |encoder: &mut wgpu::CommandEncoder,
+                        pipeline: &wgpu::RenderPipeline,
+                        input: &wgpu::TextureView,
+                        target: &wgpu::TextureView,
+                        label: &str| {
+            let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                layout: &self.bind_group_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::TextureView(input),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::TextureView(&targets.history_view),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 2,
+                        resource: self.uniform_buffer.as_entire_binding(),
+                    },
+                ],
+                label: Some("denoise_bind_group"),
+            });
+
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some(label),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: target,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                occlusion_query_set: None,
+                timestamp_writes: None,
+            });
+            render_pass.set_pipeline(pipeline);
+            render_pass.set_bind_group(0, &bind_group, &[]);
+            render_pass.draw(0..3, 0..1);
+        };
+
+        // 只有平滑时直接输出；同时启用时域降噪则先写入中间纹理
+        let temporal_input = if smoothing {
+            let target = if temporal { &targets.smoothed_view } else { &targets.output_view };
+            run_pass(encoder, &self.bilateral_pipeline, source_view, target, "Bilateral Smoothing Pass");
+            &targets.smoothed_view
+        } else {
+            source_view
+        };
+
+        if temporal {
+            run_pass(encoder, &self.temporal_pipeline, temporal_input, &targets.output_view, "Temporal Denoise Pass");
+
+            // 保存本帧结果作为下一帧的历史
+            encoder.copy_texture_to_texture(
+                targets.output.as_image_copy(),
+                targets.history.as_image_copy(),
+                wgpu::Extent3d {
+                    width: targets.width,
+                    height: targets.height,
+                    depth_or_array_layers: 1,
+                },
+            );
+        }
+
+        // 时域降噪关闭时丢弃历史，重新开启时不会混入过期画面
+        targets.has_history = temporal;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BUDGET: Duration = Duration::from_millis(16);
+
+    #[test]
+    fn test_default_settings_are_inactive() {
+        let settings = DenoiseSettings::default();
+        assert!(!settings.is_active());
+
+        let smoothing = DenoiseSettings { smoothing_enabled: true, ..Default::default() };
+        assert!(smoothing.is_active());
+
+        // 强度为 0 等同于关闭
+        let zero = DenoiseSettings { smoothing_strength: 0.0, ..smoothing };
+        assert!(!zero.is_active());
+
+        // 旁路优先于各通道开关
+        let bypassed = DenoiseSettings { bypass: true, temporal_enabled: true, ..smoothing };
+        assert!(!bypassed.is_active());
+    }
+
+    #[test]
+    fn test_effective_settings_by_level() {
+        let settings = DenoiseSettings { smoothing_enabled: true, temporal_enabled: true, ..Default::default() };
+        assert_eq!(settings.effective(DenoiseLevel::Full), settings);
+        assert_eq!(settings.effective(DenoiseLevel::Reduced), settings);
+        assert!(!settings.effective(DenoiseLevel::Bypass).is_active());
+
+        assert!(DenoiseLevel::Full.smoothing_radius() > DenoiseLevel::Reduced.smoothing_radius());
+    }
+
+    #[test]
+    fn test_governor_degrades_after_sustained_overrun() {
+        let mut governor = DenoiseGovernor::new(BUDGET);
+        let slow = Duration::from_millis(25);
+
+        for _ in 0..DEGRADE_AFTER_FRAMES - 1 {
+            assert_eq!(governor.record(slow), None);
+        }
+        assert_eq!(governor.record(slow), Some(DenoiseLevel::Reduced));
+
+        for _ in 0..DEGRADE_AFTER_FRAMES - 1 {
+            assert_eq!(governor.record(slow), None);
+        }
+        assert_eq!(governor.record(slow), Some(DenoiseLevel::Bypass));
+
+        // 已经旁路时不再变化
+        for _ in 0..DEGRADE_AFTER_FRAMES * 2 {
+            assert_eq!(governor.record(slow), None);
+        }
+        assert_eq!(governor.level(), DenoiseLevel::Bypass);
+    }
+
+    #[test]
+    fn test_governor_ignores_isolated_spikes() {
+        let mut governor = DenoiseGovernor::new(BUDGET);
+        for _ in 0..100 {
+            for _ in 0..DEGRADE_AFTER_FRAMES - 1 {
+                governor.record(Duration::from_millis(30));
+            }
+            governor.record(Duration::from_millis(5));
+        }
+        assert_eq!(governor.level(), DenoiseLevel::Full);
+    }
+
+    #[test]
+    fn test_governor_recovers_gradually() {
+        let mut governor = DenoiseGovernor::new(BUDGET);
+        for _ in 0..DEGRADE_AFTER_FRAMES * 2 {
+            governor.record(Duration::from_millis(30));
+        }
+        assert_eq!(governor.level(), DenoiseLevel::Bypass);
+
+        // 接近预算的帧不计入恢复
+        for _ in 0..RECOVER_AFTER_FRAMES * 2 {
+            assert_eq!(governor.record(Duration::from_millis(15)), None);
+        }
+
+        let fast = Duration::from_millis(5);
+        for _ in 0..RECOVER_AFTER_FRAMES - 1 {
+            assert_eq!(governor.record(fast), None);
+        }
+        assert_eq!(governor.record(fast), Some(DenoiseLevel::Reduced));
+        for _ in 0..RECOVER_AFTER_FRAMES - 1 {
+            governor.record(fast);
+        }
+        assert_eq!(governor.record(fast), Some(DenoiseLevel::Full));
+
+        governor.set_budget(Duration::from_millis(8));
+        assert_eq!(governor.budget(), Duration::from_millis(8));
+        assert_eq!(governor.level(), DenoiseLevel::Full);
+    }
+
+    #[test]
+    fn test_temporal_blend_factor() {
+        // 静止像素按强度混合，上限为 MAX_TEMPORAL_BLEND
+        assert_eq!(temporal_blend_factor(0.0, 1.0, 0.05), MAX_TEMPORAL_BLEND);
+        assert_eq!(temporal_blend_factor(0.0, 0.5, 0.05), 0.5 * MAX_TEMPORAL_BLEND);
+
+        // 运动超过阈值 3 倍时完全使用当前帧
+        assert_eq!(temporal_blend_factor(0.15, 1.0, 0.05), 0.0);
+        assert_eq!(temporal_blend_factor(1.0, 1.0, 0.05), 0.0);
+
+        // 阈值之间单调递减
+        let mut last = f32::MAX;
+        for step in 0..=20 {
+            let factor = temporal_blend_factor(step as f32 * 0.01, 1.0, 0.05);
+            assert!(factor <= last);
+            last = factor;
+        }
+
+        // 阈值为 0 时不会产生 NaN
+        assert!(temporal_blend_factor(0.0, 1.0, 0.0).is_finite());
+    }
+
+    #[test]
+    fn test_denoise_uniforms() {
+        let settings = DenoiseSettings { smoothing_strength: 0.7, temporal_strength: 0.3, ..Default::default() };
+        let uniforms = DenoiseUniforms::new(&settings, DenoiseLevel::Reduced, true);
+        assert_eq!(uniforms.params, [0.7, REDUCED_RADIUS as f32, 0.3, 0.05]);
+        assert_eq!(uniforms.extra, [1.0, MAX_TEMPORAL_BLEND, 0.0, 0.0]);
+        assert_eq!(std::mem::size_of::<DenoiseUniforms>() % 16, 0);
+
+        let uniforms = DenoiseUniforms::new(&settings, DenoiseLevel::Full, false);
+        assert_eq!(uniforms.params[1], FULL_RADIUS as f32);
+        assert_eq!(uniforms.extra[0], 0.0);
+    }
+}
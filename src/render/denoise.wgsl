@@ -0,0 +1,83 @@
+// 降噪着色器
+//
+// 全屏三角形绘制，两个片段入口：
+// fs_bilateral：双边滤波（保边平滑/磨皮）
+// fs_temporal：按逐像素运动量与上一帧混合的时域降噪
+
+// 降噪参数
+struct DenoiseUniforms {
+    // 平滑强度、滤波半径（像素）、时域强度、运动阈值
+    params: vec4<f32>,
+    // 是否有历史帧、最大混合比例、保留、保留
+    extra: vec4<f32>,
+}
+
+@group(0) @binding(0)
+var source_texture: texture_2d<f32>;
+
+@group(0) @binding(1)
+var history_texture: texture_2d<f32>;
+
+@group(0) @binding(2)
+var<uniform> denoise: DenoiseUniforms;
+
+// 覆盖整个目标的三角形
+@vertex
+fn vs_fullscreen(@builtin(vertex_index) vertex_index: u32) -> @builtin(position) vec4<f32> {
+    let x = f32(i32(vertex_index & 1u) * 4 - 1);
+    let y = f32(i32(vertex_index >> 1u) * 4 - 1);
+    return vec4<f32>(x, y, 0.0, 1.0);
+}
+
+fn load_clamped(coords: vec2<i32>) -> vec4<f32> {
+    let size = vec2<i32>(textureDimensions(source_texture));
+    return textureLoad(source_texture, clamp(coords, vec2<i32>(0), size - vec2<i32>(1)), 0);
+}
+
+// 双边滤波：空间权重与颜色差异权重相乘，边缘两侧颜色差异大因而不会被抹平
+@fragment
+fn fs_bilateral(@builtin(position) position: vec4<f32>) -> @location(0) vec4<f32> {
+    let strength = denoise.params.x;
+    let radius = i32(denoise.params.y);
+    let coords = vec2<i32>(position.xy);
+    let center = load_clamped(coords);
+
+    let sigma_spatial = f32(radius) * 0.5 + 0.5;
+    let sigma_range = mix(0.03, 0.25, strength);
+    let spatial_factor = -0.5 / (sigma_spatial * sigma_spatial);
+    let range_factor = -0.5 / (sigma_range * sigma_range);
+
+    var sum = vec3<f32>(0.0);
+    var weight_sum = 0.0;
+    for (var dy = -radius; dy <= radius; dy = dy + 1) {
+        for (var dx = -radius; dx <= radius; dx = dx + 1) {
+            let sample = load_clamped(coords + vec2<i32>(dx, dy)).rgb;
+            let delta = sample - center.rgb;
+            let weight = exp(f32(dx * dx + dy * dy) * spatial_factor + dot(delta, delta) * range_factor);
+            sum = sum + sample * weight;
+            weight_sum = weight_sum + weight;
+        }
+    }
+
+    let filtered = sum / weight_sum;
+    return vec4<f32>(mix(center.rgb, filtered, strength), center.a);
+}
+
+// 时域降噪：静止区域与上一帧混合以抑制噪点，运动区域保持当前帧避免拖影
+@fragment
+fn fs_temporal(@builtin(position) position: vec4<f32>) -> @location(0) vec4<f32> {
+    let coords = vec2<i32>(position.xy);
+    let current = textureLoad(source_texture, coords, 0);
+    let previous = textureLoad(history_texture, coords, 0);
+
+    let strength = denoise.params.z;
+    let threshold = denoise.params.w;
+    let diff = abs(current.rgb - previous.rgb);
+    let difference = max(diff.r, max(diff.g, diff.b));
+
+    // 与 denoise.rs 中的 temporal_blend_factor 保持一致
+    let motion = smoothstep(threshold, threshold * 3.0, difference);
+    let blend = strength * denoise.extra.y * (1.0 - motion) * denoise.extra.x;
+
+    return vec4<f32>(mix(current.rgb, previous.rgb, blend), current.a);
+}
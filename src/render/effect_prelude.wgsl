@@ -0,0 +1,48 @@
+// 特效着色器公共前缀
+//
+// EffectChain 把它拼接在每个特效的 WGSL 之前。特效只需提供片段入口：
+//
+//   @fragment
+//   fn fs_main(in: EffectVertexOutput) -> @location(0) vec4<f32>
+//
+// 输入纹理为上一个通道的结果（第一个通道为摄像头画面），输出写入下一张乒乓纹理。
+
+struct EffectUniforms {
+    // 输出宽、高、1/宽、1/高（像素）
+    resolution: vec4<f32>,
+    // 运行时间（秒）、帧序号、保留、保留
+    time: vec4<f32>,
+    // 清单中声明的参数，按顺序每 4 个打包为一个 vec4，用 param(i) 读取
+    params: array<vec4<f32>, 4>,
+}
+
+struct EffectVertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+}
+
+@group(0) @binding(0)
+var input_texture: texture_2d<f32>;
+
+@group(0) @binding(1)
+var input_sampler: sampler;
+
+@group(0) @binding(2)
+var<uniform> effect: EffectUniforms;
+
+// 覆盖整个目标的三角形
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index: u32) -> EffectVertexOutput {
+    let x = f32(i32(vertex_index & 1u) * 4 - 1);
+    let y = f32(i32(vertex_index >> 1u) * 4 - 1);
+    var out: EffectVertexOutput;
+    out.position = vec4<f32>(x, y, 0.0, 1.0);
+    out.uv = vec2<f32>((x + 1.0) * 0.5, (1.0 - y) * 0.5);
+    return out;
+}
+
+// 第 index 个参数（清单中的顺序）
+fn param(index: u32) -> f32 {
+    return effect.params[index / 4u][index % 4u];
+}
+
@@ -0,0 +1,637 @@
+// 后处理特效链
+//
+// 视频帧在降噪之后、主渲染通道之前依次经过若干特效通道。每个特效有自己的 WGSL、
+// 参数和统一缓冲区，通道之间在两张中间纹理上乒乓交替。特效从用户目录加载：
+//
+//   effects/
+//     vignette/
+//       effect.toml   清单：名称、着色器文件和参数
+//       effect.wgsl   片段着色器，入口 fs_main
+//
+// 着色器之前会拼接 effect_prelude.wgsl，提供输入纹理、采样器、统一缓冲区、
+// 全屏顶点入口和 param(i)。编译失败的特效只记录错误并跳过，其余通道照常运行。
+
+use crate::error::RenderError;
+use crate::render::hot_reload::{poll_error_scope, validate_wgsl};
+use log::{debug, error, info, warn};
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+/// 特效着色器公共前缀
+pub const EFFECT_PRELUDE: &str = include_str!("effect_prelude.wgsl");
+
+/// 清单文件名
+pub const MANIFEST_FILE: &str = "effect.toml";
+
+/// 每个特效最多的参数个数（统一缓冲区中 4 个 vec4）
+pub const MAX_EFFECT_PARAMS: usize = 16;
+
+/// 特效片段入口
+const FRAGMENT_ENTRY: &str = "fs_main";
+
+/// 特效中间纹理格式（与视频纹理一致）
+const TARGET_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8UnormSrgb;
+
+fn default_shader_file() -> String {
+    "effect.wgsl".to_string()
+}
+
+fn default_param_max() -> f32 {
+    1.0
+}
+
+/// 特效清单（effect.toml）
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct EffectManifest {
+    /// 显示名称
+    pub name: String,
+    /// 着色器文件（相对特效目录）
+    #[serde(default = "default_shader_file")]
+    pub shader: String,
+    /// 参数，按顺序对应着色器中的 param(0)、param(1) ...
+    #[serde(default)]
+    pub params: Vec<EffectParam>,
+}
+
+/// 特效参数
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct EffectParam {
+    pub name: String,
+    #[serde(default)]
+    pub default: f32,
+    #[serde(default)]
+    pub min: f32,
+    #[serde(default = "default_param_max")]
+    pub max: f32,
+}
+
+impl EffectManifest {
+    /// 解析并检查清单
+    pub fn parse(text: &str) -> Result<Self, RenderError> {
+        let mut manifest: Self = toml::from_str(text)
+            .map_err(|e| RenderError::EffectLoadFailed(format!("清单格式错误: {}", e)))?;
+
+        if manifest.params.len() > MAX_EFFECT_PARAMS {
+            return Err(RenderError::EffectLoadFailed(format!(
+                "参数过多: {}（最多 {} 个）",
+                manifest.params.len(),
+                MAX_EFFECT_PARAMS
+            )));
+        }
+        for param in &mut manifest.params {
+            if !param.min.is_finite() || !param.max.is_finite() || param.min > param.max {
+                return Err(RenderError::EffectLoadFailed(format!(
+                    "参数 {} 的范围无效: {} ~ {}",
+                    param.name, param.min, param.max
+                )));
+            }
+            if !param.default.is_finite() {
+                param.default = param.min;
+            }
+            param.default = param.default.clamp(param.min, param.max);
+        }
+        Ok(manifest)
+    }
+}
+
+/// 一个特效通道：清单、当前参数值和完整的着色器源码
+#[derive(Debug, Clone, PartialEq)]
+pub struct EffectPass {
+    /// 特效目录名（配置中引用的名称）
+    pub id: String,
+    pub manifest: EffectManifest,
+    /// 特效目录
+    pub directory: PathBuf,
+    pub enabled: bool,
+    values: Vec<f32>,
+    // 拼接前缀后的 WGSL
+    source: String,
+}
+
+impl EffectPass {
+    /// 由清单和特效自身的 WGSL 创建，参数取默认值
+    pub fn new(id: &str, manifest: EffectManifest, directory: PathBuf, wgsl: &str) -> Self {
+        let values = manifest.params.iter().map(|param| param.default).collect();
+        Self {
+            id: id.to_string(),
+            manifest,
+            directory,
+            enabled: true,
+            values,
+            source: format!("{}\n{}", EFFECT_PRELUDE, wgsl),
+        }
+    }
+
+    /// 参数当前值，顺序与清单一致
+    pub fn values(&self) -> &[f32] {
+        &self.values
+    }
+
+    /// 按名称设置参数（限制在清单范围内），参数不存在或值无效时返回 false
+    pub fn set_param(&mut self, name: &str, value: f32) -> bool {
+        let Some(index) = self.manifest.params.iter().position(|param| param.name == name) else {
+            return false;
+        };
+        if !value.is_finite() {
+            return false;
+        }
+        let param = &self.manifest.params[index];
+        self.values[index] = value.clamp(param.min, param.max);
+        true
+    }
+
+    /// 拼接前缀后的完整着色器源码
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+}
+
+/// 从特效目录加载清单和着色器
+pub fn load_effect(directory: &Path) -> Result<EffectPass, RenderError> {
+    let id = directory
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let manifest_path = directory.join(MANIFEST_FILE);
+    let manifest_text = std::fs::read_to_string(&manifest_path)
+        .map_err(|e| RenderError::EffectLoadFailed(format!("{}: {}", manifest_path.display(), e)))?;
+    let manifest = EffectManifest::parse(&manifest_text)
+        .map_err(|e| RenderError::EffectLoadFailed(format!("{}: {}", manifest_path.display(), e)))?;
+
+    let shader_path = directory.join(&manifest.shader);
+    let wgsl = std::fs::read_to_string(&shader_path)
+        .map_err(|e| RenderError::EffectLoadFailed(format!("{}: {}", shader_path.display(), e)))?;
+
+    Ok(EffectPass::new(&id, manifest, directory.to_path_buf(), &wgsl))
+}
+
+/// 验证特效着色器并检查片段入口，返回可读的错误信息（包含出错位置）
+pub fn validate_effect_source(source: &str) -> Result<(), String> {
+    let module = validate_wgsl(source)?;
+
+    let has_entry = module
+        .entry_points
+        .iter()
+        .any(|entry| entry.name == FRAGMENT_ENTRY && entry.stage == naga::ShaderStage::Fragment);
+    if !has_entry {
+        return Err(format!("缺少片段入口 {}", FRAGMENT_ENTRY));
+    }
+    Ok(())
+}
+
+/// 特效统一缓冲区
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+struct EffectUniforms {
+    resolution: [f32; 4],
+    time: [f32; 4],
+    params: [[f32; 4]; MAX_EFFECT_PARAMS / 4],
+}
+
+impl EffectUniforms {
+    fn new(width: u32, height: u32, seconds: f32, frame: u64, values: &[f32]) -> Self {
+        let mut params = [[0.0; 4]; MAX_EFFECT_PARAMS / 4];
+        for (index, value) in values.iter().take(MAX_EFFECT_PARAMS).enumerate() {
+            params[index / 4][index % 4] = *value;
+        }
+        Self {
+            resolution: [width as f32, height as f32, 1.0 / width.max(1) as f32, 1.0 / height.max(1) as f32],
+            time: [seconds, frame as f32, 0.0, 0.0],
+            params,
+        }
+    }
+}
+
+/// 编译后的特效通道，编译失败时 pipeline 为 None
+struct CompiledPass {
+    pass: EffectPass,
+    pipeline: Option<wgpu::RenderPipeline>,
+    uniform_buffer: wgpu::Buffer,
+}
+
+/// 乒乓中间纹理
+struct EffectTargets {
+    width: u32,
+    height: u32,
+    views: [wgpu::TextureView; 2],
+}
+
+/// 后处理特效链
+pub struct EffectChain {
+    bind_group_layout: wgpu::BindGroupLayout,
+    pipeline_layout: wgpu::PipelineLayout,
+    sampler: wgpu::Sampler,
+    passes: Vec<CompiledPass>,
+    targets: Option<EffectTargets>,
+    enabled: bool,
+    started: Instant,
+    frame: u64,
+}
+
+impl EffectChain {
+    pub fn new(device: &wgpu::Device) -> Self {
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[
+                // 输入纹理
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        multisampled: false,
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                    },
+                    count: None,
+                },
+                // 采样器
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+                // 特效参数
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+            label: Some("effect_bind_group_layout"),
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Effect Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        Self {
+            bind_group_layout,
+            pipeline_layout,
+            sampler,
+            passes: Vec::new(),
+            targets: None,
+            enabled: true,
+            started: Instant::now(),
+            frame: 0,
+        }
+    }
+
+    /// 设置特效通道并编译，编译失败的通道会被跳过
+    pub fn set_passes(&mut self, device: &wgpu::Device, passes: Vec<EffectPass>) {
+        self.passes = passes
+            .into_iter()
+            .map(|pass| {
+                let pipeline = self.compile(device, &pass);
+                let uniform_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+                    label: Some("Effect Uniform Buffer"),
+                    size: std::mem::size_of::<EffectUniforms>() as wgpu::BufferAddress,
+                    usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+                    mapped_at_creation: false,
+                });
+                CompiledPass { pass, pipeline, uniform_buffer }
+            })
+            .collect();
+
+        info!("特效链: {} 个通道，{} 个可用", self.passes.len(), self.compiled_count());
+    }
+
+    /// 编译单个特效，失败时记录错误并返回 None
+    fn compile(&self, device: &wgpu::Device, pass: &EffectPass) -> Option<wgpu::RenderPipeline> {
+        if let Err(message) = validate_effect_source(pass.source()) {
+            error!("特效 {} 编译失败，已跳过:\n{}", pass.id, message);
+            return None;
+        }
+
+        // 着色器已通过验证，这里捕获入口签名不匹配等管线错误
+        device.push_error_scope(wgpu::ErrorFilter::Validation);
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some(&pass.id),
+            source: wgpu::ShaderSource::Wgsl(pass.source().into()),
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some(&pass.id),
+            layout: Some(&self.pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: FRAGMENT_ENTRY,
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: TARGET_FORMAT,
+                    blend: None,
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+        if let Some(e) = poll_error_scope(device.pop_error_scope()) {
+            error!("特效 {} 创建管线失败，已跳过: {}", pass.id, e);
+            return None;
+        }
+
+        debug!("特效 {} 编译完成", pass.id);
+        Some(pipeline)
+    }
+
+    /// 全部通道（包括编译失败的）
+    pub fn passes(&self) -> impl Iterator<Item = &EffectPass> {
+        self.passes.iter().map(|compiled| &compiled.pass)
+    }
+
+    /// 通道是否编译成功
+    pub fn is_compiled(&self, index: usize) -> bool {
+        self.passes.get(index).is_some_and(|compiled| compiled.pipeline.is_some())
+    }
+
+    /// 编译成功的通道数
+    pub fn compiled_count(&self) -> usize {
+        self.passes.iter().filter(|compiled| compiled.pipeline.is_some()).count()
+    }
+
+    /// 启用或禁用单个通道
+    pub fn set_pass_enabled(&mut self, index: usize, enabled: bool) {
+        if let Some(compiled) = self.passes.get_mut(index) {
+            compiled.pass.enabled = enabled;
+        }
+    }
+
+    /// 按名称设置通道参数
+    pub fn set_param(&mut self, index: usize, name: &str, value: f32) -> bool {
+        self.passes.get_mut(index).is_some_and(|compiled| compiled.pass.set_param(name, value))
+    }
+
+    /// 整条特效链的总开关
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// 本帧实际运行的通道数
+    fn runnable_count(&self) -> usize {
+        if !self.enabled {
+            return 0;
+        }
+        self.passes
+            .iter()
+            .filter(|compiled| compiled.pass.enabled && compiled.pipeline.is_some())
+            .count()
+    }
+
+    /// 是否有需要运行的通道
+    pub fn is_active(&self) -> bool {
+        self.runnable_count() > 0
+    }
+
+    /// 结果所在的乒乓纹理序号（通道数变化时结果纹理随之变化）
+    pub fn output_index(&self) -> usize {
+        (self.runnable_count() + 1) % 2
+    }
+
+    /// 确保中间纹理与视频尺寸一致，重新创建时返回 true（输出纹理视图已变化）
+    pub fn ensure_targets(&mut self, device: &wgpu::Device, width: u32, height: u32) -> bool {
+        if self.targets.as_ref().is_some_and(|t| t.width == width && t.height == height) {
+            return false;
+        }
+
+        debug!("创建特效中间纹理: {}x{}", width, height);
+        let create_view = |label| {
+            device
+                .create_texture(&wgpu::TextureDescriptor {
+                    label: Some(label),
+                    size: wgpu::Extent3d {
+                        width,
+                        height,
+                        depth_or_array_layers: 1,
+                    },
+                    mip_level_count: 1,
+                    sample_count: 1,
+                    dimension: wgpu::TextureDimension::D2,
+                    format: TARGET_FORMAT,
+                    usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::RENDER_ATTACHMENT,
+                    view_formats: &[],
+                })
+                .create_view(&wgpu::TextureViewDescriptor::default())
+        };
+
+        self.targets = Some(EffectTargets {
+            width,
+            height,
+            views: [create_view("effect_ping"), create_view("effect_pong")],
+        });
+        true
+    }
+
+    /// 特效链结果纹理视图（需先调用 ensure_targets）
+    pub fn output_view(&self) -> Option<&wgpu::TextureView> {
+        let index = self.output_index();
+        self.targets.as_ref().map(|t| &t.views[index])
+    }
+
+    /// 释放中间纹理
+    pub fn release_targets(&mut self) {
+        self.targets = None;
+    }
+
+    /// 依次记录各特效通道，结果写入 output_view
+    pub fn encode(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        encoder: &mut wgpu::CommandEncoder,
+        source_view: &wgpu::TextureView,
+    ) {
+        let Some(targets) = &self.targets else {
+            warn!("特效中间纹理未创建，跳过特效链");
+            return;
+        };
+        if !self.enabled {
+            return;
+        }
+
+        let seconds = self.started.elapsed().as_secs_f32();
+        let mut input = source_view;
+        let mut target_index = 0;
+        for compiled in &self.passes {
+            let Some(pipeline) = compiled.pipeline.as_ref().filter(|_| compiled.pass.enabled) else {
+                continue;
+            };
+
+            let uniforms = EffectUniforms::new(targets.width, targets.height, seconds, self.frame, compiled.pass.values());
+            queue.write_buffer(&compiled.uniform_buffer, 0, bytemuck::cast_slice(&[uniforms]));
+
+            let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                layout: &self.bind_group_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::TextureView(input),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::Sampler(&self.sampler),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 2,
+                        resource: compiled.uniform_buffer.as_entire_binding(),
+                    },
+                ],
+                label: Some("effect_bind_group"),
+            });
+
+            let target = &targets.views[target_index];
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some(&compiled.pass.id),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: target,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                occlusion_query_set: None,
+                timestamp_writes: None,
+            });
+            render_pass.set_pipeline(pipeline);
+            render_pass.set_bind_group(0, &bind_group, &[]);
+            render_pass.draw(0..3, 0..1);
+            drop(render_pass);
+
+            input = target;
+            target_index = 1 - target_index;
+        }
+
+        self.frame += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VIGNETTE_MANIFEST: &str = include_str!("../../assets/effects/vignette/effect.toml");
+    const VIGNETTE_WGSL: &str = include_str!("../../assets/effects/vignette/effect.wgsl");
+
+    #[test]
+    fn test_parse_manifest() {
+        let manifest = EffectManifest::parse(VIGNETTE_MANIFEST).unwrap();
+        assert_eq!(manifest.shader, "effect.wgsl");
+        assert_eq!(manifest.params.len(), 2);
+        assert_eq!(manifest.params[0].name, "strength");
+
+        // 省略的字段取默认值，默认值被限制在范围内
+        let manifest = EffectManifest::parse(
+            r#"
+name = "测试"
+[[params]]
+name = "amount"
+default = 5.0
+"#,
+        )
+        .unwrap();
+        assert_eq!(manifest.shader, "effect.wgsl");
+        assert_eq!(manifest.params[0].min, 0.0);
+        assert_eq!(manifest.params[0].max, 1.0);
+        assert_eq!(manifest.params[0].default, 1.0);
+    }
+
+    #[test]
+    fn test_invalid_manifest() {
+        assert!(EffectManifest::parse("shader = \"a.wgsl\"").is_err());
+        assert!(EffectManifest::parse("name = \"x\"\n[[params]]\nname = \"a\"\nmin = 2.0\nmax = 1.0").is_err());
+
+        let too_many: String = std::iter::once("name = \"x\"\n".to_string())
+            .chain((0..=MAX_EFFECT_PARAMS).map(|i| format!("[[params]]\nname = \"p{}\"\n", i)))
+            .collect();
+        let err = EffectManifest::parse(&too_many).unwrap_err();
+        assert!(err.to_string().contains("参数过多"));
+    }
+
+    #[test]
+    fn test_pass_params() {
+        let manifest = EffectManifest::parse(VIGNETTE_MANIFEST).unwrap();
+        let mut pass = EffectPass::new("vignette", manifest, PathBuf::from("effects/vignette"), VIGNETTE_WGSL);
+        assert_eq!(pass.values(), &[0.5, 0.75]);
+        assert!(pass.source().starts_with(EFFECT_PRELUDE));
+
+        assert!(pass.set_param("radius", 9.0));
+        assert_eq!(pass.values()[1], 1.5);
+        assert!(!pass.set_param("missing", 0.0));
+        assert!(!pass.set_param("strength", f32::NAN));
+        assert_eq!(pass.values()[0], 0.5);
+    }
+
+    #[test]
+    fn test_validate_effect_source() {
+        // 示例特效可以编译
+        let source = format!("{}\n{}", EFFECT_PRELUDE, VIGNETTE_WGSL);
+        assert_eq!(validate_effect_source(&source), Ok(()));
+
+        // 语法错误带有出错位置
+        let broken = format!("{}\n@fragment fn fs_main( -> vec4<f32> {{}}", EFFECT_PRELUDE);
+        let err = validate_effect_source(&broken).unwrap_err();
+        assert!(err.contains("error"), "{}", err);
+
+        // 缺少片段入口
+        let missing = format!("{}\nfn helper() -> f32 {{ return 1.0; }}", EFFECT_PRELUDE);
+        assert!(validate_effect_source(&missing).unwrap_err().contains(FRAGMENT_ENTRY));
+    }
+
+    #[test]
+    fn test_load_effect_from_directory() {
+        let directory = std::env::temp_dir().join(format!("mira_effect_test_{}", std::process::id()));
+        let effect_dir = directory.join("vignette");
+        std::fs::create_dir_all(&effect_dir).unwrap();
+        std::fs::write(effect_dir.join(MANIFEST_FILE), VIGNETTE_MANIFEST).unwrap();
+
+        // 着色器文件缺失
+        assert!(load_effect(&effect_dir).is_err());
+
+        std::fs::write(effect_dir.join("effect.wgsl"), VIGNETTE_WGSL).unwrap();
+        let pass = load_effect(&effect_dir).unwrap();
+        assert_eq!(pass.id, "vignette");
+        assert_eq!(pass.manifest.name, "暗角");
+        assert!(pass.enabled);
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn test_uniform_packing() {
+        let values: Vec<f32> = (0..6).map(|i| i as f32).collect();
+        let uniforms = EffectUniforms::new(200, 100, 1.5, 7, &values);
+        assert_eq!(uniforms.resolution, [200.0, 100.0, 0.005, 0.01]);
+        assert_eq!(uniforms.time, [1.5, 7.0, 0.0, 0.0]);
+        assert_eq!(uniforms.params[0], [0.0, 1.0, 2.0, 3.0]);
+        assert_eq!(uniforms.params[1], [4.0, 5.0, 0.0, 0.0]);
+        assert_eq!(std::mem::size_of::<EffectUniforms>(), 96);
+    }
+}
@@ -1,14 +1,39 @@
 // 渲染引擎实现
 
 use crate::error::RenderError;
-use crate::memory::{FrameBufferPool, TextureManager, PoolStats, TextureManagerStats};
-use crate::shape::ShapeMask;
+use crate::render::adapter::{choose_present_mode, request_gpu, GpuContext, GpuSettings};
+use crate::render::alpha::{choose_alpha_mode, OutputAlpha, OutputUniforms};
+use crate::render::auto_correct::{AutoCorrectSettings, AutoCorrectUniforms, AutoCorrector};
+use crate::render::capture::{CaptureSource, CapturedFrame, FrameCapture};
+use crate::render::chroma::{sample_key_color, ChromaKey, ChromaKeyUniforms};
+use crate::render::color::{ColorAdjustments, ColorPreset, ColorUniforms, CubeLut};
+use crate::render::denoise::{DenoiseGovernor, DenoiseLevel, DenoisePipeline, DenoiseSettings};
+use crate::render::effects::{EffectChain, EffectPass};
+use crate::render::hot_reload::{poll_error_scope, poll_ready, ShaderWatcher};
+use crate::render::profiler::{FrameTimings, GpuProfiler, RenderPass};
+use crate::render::sticker::StickerPipeline;
+use crate::render::upload::FrameUploader;
+use crate::overlay::{Annotations, OverlayCanvas, OverlayLayer, Sticker, StickerSet, TextOverlay, TextRenderer};
+use crate::memory::{FrameBufferPool, MaskCache, MaskCacheStats, TextureManager, PoolStats, TextureManagerStats};
+use crate::shape::{MaskCacheKey, ShapeMask, DEFAULT_MASK_CACHE_BUDGET};
 use log::{debug, error, info, warn};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use wgpu::util::DeviceExt;
 use winit::window::Window;
-use winit::dpi::PhysicalSize;
+use winit::dpi::{PhysicalPosition, PhysicalSize};
+
+/// 默认单帧渲染耗时预算，超出时降噪逐级降级
+const DEFAULT_RENDER_TIME_BUDGET: Duration = Duration::from_millis(16);
+
+/// 主着色器（热重载时从磁盘读取同名文件）
+const MAIN_SHADER_FILE: &str = "shader.wgsl";
+const MAIN_SHADER: &str = include_str!("shader.wgsl");
+
+/// UI 按钮着色器
+const UI_SHADER_FILE: &str = "ui_shader.wgsl";
+const UI_SHADER: &str = include_str!("ui_shader.wgsl");
 
 /// UI渲染信息
 pub struct UIRenderInfo {
@@ -90,6 +115,7 @@ pub struct RenderMemoryStats {
     pub texture_manager: TextureManagerStats,
     pub video_texture_allocated: bool,
     pub mask_texture_allocated: bool,
+    pub mask_texture_cache: MaskCacheStats,
 }
 
 /// 视频帧数据
@@ -108,6 +134,17 @@ pub enum PixelFormat {
     YUV420,
 }
 
+impl PixelFormat {
+    /// 每像素字节数，平面格式返回 None
+    pub fn bytes_per_pixel(&self) -> Option<u32> {
+        match self {
+            PixelFormat::RGB8 => Some(3),
+            PixelFormat::RGBA8 => Some(4),
+            PixelFormat::YUV420 => None,
+        }
+    }
+}
+
 impl Frame {
     /// 创建新的帧
     pub fn new(data: Vec<u8>, width: u32, height: u32, format: PixelFormat) -> Self {
@@ -127,15 +164,31 @@ impl Frame {
     }
 }
 
+/// 设备恢复失败后再次尝试的最小间隔
+const DEVICE_RECOVERY_RETRY_INTERVAL: Duration = Duration::from_secs(1);
+
+/// 设备恢复后、新视频帧到达前显示的占位颜色（sRGB）
+const PLACEHOLDER_COLOR: [u8; 4] = [48, 48, 48, 255];
+
+/// 当前遮罩的 CPU 副本，设备恢复后重新上传
+struct MaskUpload {
+    key: MaskCacheKey,
+    width: u32,
+    height: u32,
+    data: Vec<u8>,
+}
+
 /// 渲染引擎
 pub struct RenderEngine {
+    window: Arc<Window>,
     device: wgpu::Device,
     queue: wgpu::Queue,
     surface: wgpu::Surface<'static>,
     surface_config: wgpu::SurfaceConfiguration,
     pipeline: wgpu::RenderPipeline,
+    pipeline_layout: wgpu::PipelineLayout,
     video_texture: Option<wgpu::Texture>,
-    mask_texture: Option<wgpu::Texture>,
+    mask_texture: Option<Arc<wgpu::Texture>>,
     video_bind_group: Option<wgpu::BindGroup>,
     bind_group_layout: wgpu::BindGroupLayout,
     sampler: wgpu::Sampler,
@@ -150,10 +203,91 @@ pub struct RenderEngine {
     ui_uniform_buffer: Option<wgpu::Buffer>,
     ui_uniform_bind_group: Option<wgpu::BindGroup>,
     ui_bind_group_layout: Option<wgpu::BindGroupLayout>,
+    ui_pipeline_layout: Option<wgpu::PipelineLayout>,
+
+    // 开发模式下监视磁盘上的着色器，未开启热重载时为 None
+    shader_watcher: Option<ShaderWatcher>,
     
     // 内存管理优化
     frame_buffer_pool: Arc<FrameBufferPool>,
     texture_manager: TextureManager,
+    // 按遮罩缓存键复用的遮罩纹理
+    mask_texture_cache: MaskCache<MaskCacheKey, Arc<wgpu::Texture>>,
+    // 视频内容在屏幕上的旋转角度（弧度）
+    video_rotation: f32,
+
+    // 颜色调整与滤镜
+    color_adjustments: ColorAdjustments,
+    color_preset: ColorPreset,
+    lut: Option<CubeLut>,
+    lut_enabled: bool,
+    lut_texture: wgpu::Texture,
+    lut_sampler: wgpu::Sampler,
+
+    // 绿幕抠像
+    chroma_key: ChromaKey,
+    // 等待在下一帧视频中取色的纹理坐标
+    pending_key_pick: Option<(f32, f32)>,
+
+    // 磨皮平滑与时域降噪
+    denoise: DenoisePipeline,
+    denoise_settings: DenoiseSettings,
+    denoise_governor: DenoiseGovernor,
+    // 当前视频绑定组绑定的视频来源
+    video_bind_group_source: VideoSource,
+
+    // 用户自定义的后处理特效链
+    effects: EffectChain,
+
+    // RGB8 帧的 GPU 展开通道，适配器不支持计算着色器时为 None
+    frame_uploader: Option<FrameUploader>,
+
+    // 自动白平衡、色阶与弱光增强
+    auto_corrector: AutoCorrector,
+
+    // 文字等叠加元素及其纹理
+    overlay: OverlayLayer,
+    overlay_texture: wgpu::Texture,
+
+    // 贴纸及其贴纸层，未显示贴纸时绑定透明占位纹理
+    stickers: StickerSet,
+    sticker_pipeline: StickerPipeline,
+    sticker_placeholder: wgpu::TextureView,
+
+    // GPU 时间戳计时，适配器不支持时为 None
+    profiler: Option<GpuProfiler>,
+    // 最近一帧的 CPU 编码时间和最近完成回读的 GPU 各阶段耗时
+    frame_timings: FrameTimings,
+
+    // 画面回读（快照和录制）
+    frame_capture: FrameCapture,
+    capture_requests: Vec<CaptureSource>,
+    shutter_flash: f32,
+    // 录制中在左上角显示红点
+    recording_indicator: bool,
+
+    // 设备丢失回调设置的标志，下一帧渲染前重建设备
+    device_lost: Arc<AtomicBool>,
+    // 上次尝试恢复设备的时间
+    last_recovery_attempt: Option<Instant>,
+    // 设备重建次数，持有 GPU 资源的外部组件据此重建
+    device_generation: u64,
+    current_mask: Option<MaskUpload>,
+
+    // 适配器选择设置（设备恢复时沿用）和当前适配器信息
+    gpu_settings: GpuSettings,
+    adapter_info: wgpu::AdapterInfo,
+}
+
+/// 主渲染通道采样的视频来源
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum VideoSource {
+    /// 摄像头原始帧
+    Raw,
+    /// 降噪结果
+    Denoised,
+    /// 特效链结果（乒乓纹理序号）
+    Effects(usize),
 }
 
 /// 顶点数据
@@ -183,7 +317,12 @@ impl Vertex {
 #[repr(C)]
 #[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
 struct Uniforms {
-    transform: [[f32; 4]; 4], // 4x4 变换矩阵
+    transform: [[f32; 4]; 4], // 4x4 变换矩阵（遮罩旋转）
+    video_rotation: [f32; 4], // 视频相对遮罩的旋转（cos, sin, 0, 0）
+    color: ColorUniforms,     // 颜色调整与滤镜参数
+    chroma_key: ChromaKeyUniforms, // 绿幕抠像参数
+    auto_correct: AutoCorrectUniforms, // 自动校正参数
+    output: OutputUniforms,   // 输出透明度编码
 }
 
 impl Uniforms {
@@ -195,9 +334,20 @@ impl Uniforms {
                 [0.0, 0.0, 1.0, 0.0],
                 [0.0, 0.0, 0.0, 1.0],
             ],
+            video_rotation: [1.0, 0.0, 0.0, 0.0],
+            color: ColorUniforms::default(),
+            chroma_key: ChromaKeyUniforms::default(),
+            auto_correct: AutoCorrectUniforms::default(),
+            output: OutputUniforms::default(),
         }
     }
 
+    /// 视频纹理坐标相对遮罩反向旋转的角度，使视频在屏幕上呈现 video_rotation
+    fn update_video_rotation(&mut self, mask_rotation: f32, video_rotation: f32) {
+        let relative = mask_rotation - video_rotation;
+        self.video_rotation = [relative.cos(), relative.sin(), 0.0, 0.0];
+    }
+
     fn update_rotation(&mut self, rotation: f32) {
         let cos_r = rotation.cos();
         let sin_r = rotation.sin();
@@ -211,6 +361,53 @@ impl Uniforms {
     }
 }
 
+/// 将窗口坐标映射到遮罩纹理坐标（与顶点着色器的旋转互逆），点位于遮罩四边形之外时返回 None
+pub fn window_to_mask_coords(
+    position: PhysicalPosition<f64>,
+    window_size: PhysicalSize<u32>,
+    mask_rotation: f32,
+) -> Option<(f32, f32)> {
+    if window_size.width == 0 || window_size.height == 0 {
+        return None;
+    }
+
+    // 窗口坐标 -> 裁剪空间
+    let clip_x = (2.0 * position.x / window_size.width as f64 - 1.0) as f32;
+    let clip_y = (1.0 - 2.0 * position.y / window_size.height as f64) as f32;
+
+    // 撤销顶点变换得到四边形局部坐标
+    let (sin_m, cos_m) = mask_rotation.sin_cos();
+    let local_x = cos_m * clip_x - sin_m * clip_y;
+    let local_y = sin_m * clip_x + cos_m * clip_y;
+    if local_x.abs() > 1.0 || local_y.abs() > 1.0 {
+        return None;
+    }
+
+    // 局部坐标 -> 遮罩纹理坐标
+    Some(((local_x + 1.0) * 0.5, (1.0 - local_y) * 0.5))
+}
+
+/// 将窗口坐标映射到视频纹理坐标（与顶点着色器的旋转和 rotate_video_coords 互逆），
+/// 点位于遮罩四边形或视频之外时返回 None
+pub fn window_to_video_coords(
+    position: PhysicalPosition<f64>,
+    window_size: PhysicalSize<u32>,
+    mask_rotation: f32,
+    video_rotation: f32,
+) -> Option<(f32, f32)> {
+    // 遮罩纹理坐标 -> 视频纹理坐标
+    let (mask_u, mask_v) = window_to_mask_coords(position, window_size, mask_rotation)?;
+    let (sin_v, cos_v) = (mask_rotation - video_rotation).sin_cos();
+    let px = mask_u - 0.5;
+    let py = 0.5 - mask_v;
+    let rx = cos_v * px + sin_v * py;
+    let ry = -sin_v * px + cos_v * py;
+    let coords = (rx + 0.5, 0.5 - ry);
+
+    let valid = |c: f32| (0.0..=1.0).contains(&c);
+    (valid(coords.0) && valid(coords.1)).then_some(coords)
+}
+
 const VERTICES: &[Vertex] = &[
     Vertex { position: [-1.0, -1.0], tex_coords: [0.0, 1.0] }, // 左下
     Vertex { position: [1.0, -1.0], tex_coords: [1.0, 1.0] },  // 右下
@@ -226,57 +423,46 @@ const INDICES: &[u16] = &[
 impl RenderEngine {
     /// 创建新的渲染引擎
     pub async fn new(window: Arc<Window>) -> Result<Self, RenderError> {
+        Self::with_gpu_settings(window, GpuSettings::default()).await
+    }
+
+    /// 按指定的适配器、后端和呈现模式设置创建渲染引擎
+    pub async fn with_gpu_settings(window: Arc<Window>, gpu_settings: GpuSettings) -> Result<Self, RenderError> {
+        Self::create(window, gpu_settings, true).await
+    }
+
+    /// 创建实例、表面、设备和全部 GPU 资源
+    ///
+    /// 设备恢复时 configure_surface 为 false：旧引擎的交换链释放后才能配置新表面
+    async fn create(window: Arc<Window>, gpu_settings: GpuSettings, configure_surface: bool) -> Result<Self, RenderError> {
         info!("开始初始化渲染引擎");
         let size = window.inner_size();
         info!("窗口尺寸: {}x{}", size.width, size.height);
 
-        // 创建 wgpu 实例
-        debug!("创建 wgpu 实例");
-        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
-            backends: wgpu::Backends::all(),
-            ..Default::default()
-        });
-
-        // 创建表面
-        debug!("创建渲染表面");
-        let surface = instance.create_surface(window.clone())
+        // 按重试链创建表面、适配器和设备，失败时逐级降级到软件适配器
+        let GpuContext { surface, adapter, device, queue } = request_gpu(window.clone(), &gpu_settings)
+            .await
             .map_err(|e| {
-                error!("创建渲染表面失败: {}", e);
-                RenderError::InitializationFailed(format!("创建表面失败: {}", e))
+                error!("初始化 GPU 失败: {}", e);
+                e
             })?;
-
-        // 请求适配器
-        debug!("请求 GPU 适配器");
-        let adapter = instance.request_adapter(&wgpu::RequestAdapterOptions {
-            power_preference: wgpu::PowerPreference::default(),
-            compatible_surface: Some(&surface),
-            force_fallback_adapter: false,
-        }).await.ok_or_else(|| {
-            error!("未找到合适的 GPU 适配器");
-            RenderError::InitializationFailed("未找到合适的GPU适配器".to_string())
-        })?;
-        
-        // 记录适配器信息
         let adapter_info = adapter.get_info();
-        info!("使用 GPU 适配器: {} ({:?})", adapter_info.name, adapter_info.backend);
-        info!("GPU 设备类型: {:?}", adapter_info.device_type);
-
-        // 请求设备和队列
-        debug!("请求 GPU 设备和队列");
-        let (device, queue) = adapter.request_device(
-            &wgpu::DeviceDescriptor {
-                required_features: wgpu::Features::empty(),
-                required_limits: wgpu::Limits::default(),
-                label: None,
-            },
-            None,
-        ).await.map_err(|e| {
-            error!("创建 GPU 设备失败: {}", e);
-            RenderError::InitializationFailed(format!("创建设备失败: {}", e))
-        })?;
-        
+        info!("GPU 设备类型: {:?}, 驱动: {} {}", adapter_info.device_type, adapter_info.driver, adapter_info.driver_info);
+
         info!("GPU 设备创建成功");
 
+        // 驱动重置、拔插扩展坞等导致设备丢失时只记录标志，由下一帧渲染重建设备
+        let device_lost = Arc::new(AtomicBool::new(false));
+        let lost_flag = Arc::clone(&device_lost);
+        device.set_device_lost_callback(move |reason, message| {
+            if matches!(reason, wgpu::DeviceLostReason::Unknown | wgpu::DeviceLostReason::DeviceInvalid) {
+                error!("GPU 设备丢失 ({:?}): {}", reason, message);
+                lost_flag.store(true, Ordering::SeqCst);
+            }
+        });
+        // 设备丢失后的 GPU 调用会产生错误，只记录日志，不让程序崩溃
+        device.on_uncaptured_error(Box::new(|e| error!("GPU 错误: {}", e)));
+
         // 获取表面能力
         debug!("获取表面能力");
         let surface_caps = surface.get_capabilities(&adapter);
@@ -290,6 +476,10 @@ impl RenderEngine {
             .unwrap_or(surface_caps.formats[0]);
         info!("选择表面格式: {:?}", surface_format);
 
+        // 透明窗口需要合成器按 alpha 混合，优先预乘模式
+        let alpha_mode = choose_alpha_mode(&surface_caps.alpha_modes);
+        info!("选择合成透明度模式: {:?}（支持: {:?}）", alpha_mode, surface_caps.alpha_modes);
+
         // 配置表面
         debug!("配置渲染表面");
         let surface_config = wgpu::SurfaceConfiguration {
@@ -297,13 +487,15 @@ impl RenderEngine {
             format: surface_format,
             width: size.width,
             height: size.height,
-            present_mode: surface_caps.present_modes[0],
-            alpha_mode: surface_caps.alpha_modes[0],
+            present_mode: choose_present_mode(gpu_settings.present_mode, &surface_caps.present_modes),
+            alpha_mode,
             view_formats: vec![],
             desired_maximum_frame_latency: 2,
         };
-        surface.configure(&device, &surface_config);
-        debug!("表面配置完成");
+        if configure_surface {
+            surface.configure(&device, &surface_config);
+            debug!("表面配置完成");
+        }
 
         // 创建采样器
         debug!("创建纹理采样器");
@@ -317,6 +509,26 @@ impl RenderEngine {
             ..Default::default()
         });
 
+        // 3D LUT 需要三线性插值，使用单独的线性采样器
+        let lut_sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("LUT Sampler"),
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::FilterMode::Nearest,
+            ..Default::default()
+        });
+
+        // 未加载 LUT 时绑定恒等 LUT
+        let lut_texture = Self::create_lut_texture(&device, &queue, &CubeLut::identity(2));
+
+        // 没有叠加内容时绑定 1x1 透明纹理
+        let overlay_texture = Self::create_overlay_texture(&device, &queue, &OverlayCanvas::new(0, 0));
+        let sticker_placeholder = Self::create_overlay_texture(&device, &queue, &OverlayCanvas::new(0, 0))
+            .create_view(&wgpu::TextureViewDescriptor::default());
+
         // 创建绑定组布局
         debug!("创建绑定组布局");
         let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
@@ -350,6 +562,57 @@ impl RenderEngine {
                     ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
                     count: None,
                 },
+                // 3D LUT 纹理
+                wgpu::BindGroupLayoutEntry {
+                    binding: 3,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        multisampled: false,
+                        view_dimension: wgpu::TextureViewDimension::D3,
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                    },
+                    count: None,
+                },
+                // LUT 采样器
+                wgpu::BindGroupLayoutEntry {
+                    binding: 4,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+                // 叠加层纹理
+                wgpu::BindGroupLayoutEntry {
+                    binding: 5,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        multisampled: false,
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                    },
+                    count: None,
+                },
+                // 遮罩之下的贴纸层
+                wgpu::BindGroupLayoutEntry {
+                    binding: 6,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        multisampled: false,
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                    },
+                    count: None,
+                },
+                // 遮罩之上的贴纸层
+                wgpu::BindGroupLayoutEntry {
+                    binding: 7,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        multisampled: false,
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                    },
+                    count: None,
+                },
             ],
             label: Some("texture_bind_group_layout"),
         });
@@ -360,7 +623,7 @@ impl RenderEngine {
             entries: &[
                 wgpu::BindGroupLayoutEntry {
                     binding: 0,
-                    visibility: wgpu::ShaderStages::VERTEX,
+                    visibility: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
                     ty: wgpu::BindingType::Buffer {
                         ty: wgpu::BufferBindingType::Uniform,
                         has_dynamic_offset: false,
@@ -401,54 +664,46 @@ impl RenderEngine {
             push_constant_ranges: &[],
         });
 
-        // 加载着色器
+        // 加载着色器（开发模式下从磁盘读取并监视变化）
         debug!("加载 WGSL 着色器");
-        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
-            label: Some("Shader"),
-            source: wgpu::ShaderSource::Wgsl(include_str!("shader.wgsl").into()),
-        });
-        debug!("着色器加载成功");
+        let mut shader_watcher = ShaderWatcher::from_env();
+        let shader_source = match &mut shader_watcher {
+            Some(watcher) => watcher.watch(MAIN_SHADER_FILE, MAIN_SHADER),
+            None => MAIN_SHADER.to_string(),
+        };
 
         // 创建渲染管线
         debug!("创建渲染管线");
-        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: Some("Render Pipeline"),
-            layout: Some(&render_pipeline_layout),
-            vertex: wgpu::VertexState {
-                module: &shader,
-                entry_point: "vs_main",
-                buffers: &[Vertex::desc()],
-                compilation_options: wgpu::PipelineCompilationOptions::default(),
-            },
-            fragment: Some(wgpu::FragmentState {
-                module: &shader,
-                entry_point: "fs_main",
-                targets: &[Some(wgpu::ColorTargetState {
-                    format: surface_config.format,
-                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
-                    write_mask: wgpu::ColorWrites::ALL,
-                })],
-                compilation_options: wgpu::PipelineCompilationOptions::default(),
-            }),
-            primitive: wgpu::PrimitiveState {
-                topology: wgpu::PrimitiveTopology::TriangleList,
-                strip_index_format: None,
-                front_face: wgpu::FrontFace::Ccw,
-                cull_mode: Some(wgpu::Face::Back),
-                polygon_mode: wgpu::PolygonMode::Fill,
-                unclipped_depth: false,
-                conservative: false,
-            },
-            depth_stencil: None,
-            multisample: wgpu::MultisampleState {
-                count: 1,
-                mask: !0,
-                alpha_to_coverage_enabled: false,
-            },
-            multiview: None,
-        });
+        let pipeline = Self::create_main_pipeline(&device, &render_pipeline_layout, surface_config.format, &shader_source);
         debug!("渲染管线创建成功");
 
+        // 创建降噪通道
+        debug!("创建降噪通道");
+        let denoise = DenoisePipeline::new(&device);
+
+        // 创建贴纸通道
+        debug!("创建贴纸通道");
+        let sticker_pipeline = StickerPipeline::new(&device);
+
+        // 创建特效链（初始没有通道）
+        debug!("创建特效链");
+        let effects = EffectChain::new(&device);
+
+        // 创建视频帧上传通道
+        let frame_uploader = if FrameUploader::is_supported(&adapter, &device) {
+            debug!("创建视频帧上传通道");
+            Some(FrameUploader::new(&device))
+        } else {
+            info!("适配器不支持计算着色器，RGB 视频帧在 CPU 上转换");
+            None
+        };
+
+        // 创建 GPU 计时器
+        let profiler = GpuProfiler::new(&device, &queue);
+        if profiler.is_none() {
+            info!("适配器不支持时间戳查询，只统计 CPU 编码时间");
+        }
+
         // 创建顶点缓冲区
         debug!("创建顶点缓冲区");
         let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
@@ -482,11 +737,13 @@ impl RenderEngine {
         info!("内存管理组件初始化完成");
         
         Ok(Self {
+            window,
             device,
             queue,
             surface,
             surface_config,
             pipeline,
+            pipeline_layout: render_pipeline_layout,
             video_texture: None,
             mask_texture: None,
             video_bind_group: None,
@@ -501,11 +758,167 @@ impl RenderEngine {
             ui_uniform_buffer: None,
             ui_uniform_bind_group: None,
             ui_bind_group_layout: None,
+            ui_pipeline_layout: None,
+            shader_watcher,
             frame_buffer_pool,
             texture_manager,
+            mask_texture_cache: MaskCache::new(DEFAULT_MASK_CACHE_BUDGET),
+            video_rotation: 0.0,
+            color_adjustments: ColorAdjustments::default(),
+            color_preset: ColorPreset::None,
+            lut: None,
+            lut_enabled: false,
+            lut_texture,
+            lut_sampler,
+            chroma_key: ChromaKey::default(),
+            pending_key_pick: None,
+            denoise,
+            denoise_settings: DenoiseSettings::default(),
+            denoise_governor: DenoiseGovernor::new(DEFAULT_RENDER_TIME_BUDGET),
+            video_bind_group_source: VideoSource::Raw,
+            effects,
+            frame_uploader,
+            auto_corrector: AutoCorrector::default(),
+            overlay: OverlayLayer::new(),
+            overlay_texture,
+            stickers: StickerSet::new(),
+            sticker_pipeline,
+            sticker_placeholder,
+            profiler,
+            frame_timings: FrameTimings::default(),
+            frame_capture: FrameCapture::new(),
+            capture_requests: Vec::new(),
+            shutter_flash: 0.0,
+            recording_indicator: false,
+            device_lost,
+            last_recovery_attempt: None,
+            device_generation: 0,
+            current_mask: None,
+            gpu_settings,
+            adapter_info,
         })
     }
 
+    /// 设备丢失后重建适配器、设备、管线和纹理，并接管原有的渲染状态
+    fn recover_device(&mut self) -> Result<(), RenderError> {
+        let now = Instant::now();
+        if self.last_recovery_attempt.is_some_and(|last| now.duration_since(last) < DEVICE_RECOVERY_RETRY_INTERVAL) {
+            return Err(RenderError::RenderFailed("GPU 设备丢失，等待重新创建".to_string()));
+        }
+        self.last_recovery_attempt = Some(now);
+
+        warn!("GPU 设备丢失，重新创建设备和 GPU 资源");
+        let mut engine = poll_ready(Self::create(Arc::clone(&self.window), self.gpu_settings, false))
+            .ok_or_else(|| RenderError::InitializationFailed("创建 GPU 设备未能立即完成".to_string()))?
+            .map_err(|e| {
+                error!("重新创建 GPU 设备失败: {}，稍后重试", e);
+                e
+            })?;
+        engine.adopt_state(self);
+
+        // 先释放旧设备和交换链，再配置新表面
+        drop(std::mem::replace(self, engine));
+        self.surface.configure(&self.device, &self.surface_config);
+
+        if let Some(mask) = self.current_mask.take() {
+            self.upload_mask(mask);
+        }
+        self.upload_placeholder_frame();
+        info!("GPU 设备恢复完成（第 {} 次）", self.device_generation);
+        Ok(())
+    }
+
+    /// 从旧引擎接管与 GPU 资源无关的状态，纹理类资源在新设备上重新创建
+    fn adopt_state(&mut self, old: &mut RenderEngine) {
+        self.surface_config.width = old.surface_config.width;
+        self.surface_config.height = old.surface_config.height;
+        self.frame_buffer_pool = Arc::clone(&old.frame_buffer_pool);
+        self.mask_texture_cache.set_budget(old.mask_texture_cache.get_stats().budget_bytes);
+        self.video_rotation = old.video_rotation;
+
+        self.color_adjustments = old.color_adjustments;
+        self.color_preset = old.color_preset;
+        if let Some(lut) = old.lut.take() {
+            self.load_lut(lut);
+        }
+        self.lut_enabled = old.lut_enabled;
+        self.chroma_key = old.chroma_key;
+        self.pending_key_pick = old.pending_key_pick.take();
+
+        self.denoise_settings = old.denoise_settings;
+        self.denoise_governor.set_budget(old.denoise_governor.budget());
+        self.set_effects(old.effects.passes().cloned().collect());
+        self.set_effects_enabled(old.effects.is_enabled());
+        self.auto_corrector = std::mem::take(&mut old.auto_corrector);
+
+        self.overlay = std::mem::take(&mut old.overlay);
+        self.overlay.invalidate();
+        self.stickers = std::mem::take(&mut old.stickers);
+
+        self.current_mask = old.current_mask.take();
+        // 未完成的回读属于旧设备，只保留尚未编码的请求
+        self.capture_requests = std::mem::take(&mut old.capture_requests);
+        let discarded = old.frame_capture.discard_pending();
+        if discarded > 0 {
+            warn!("GPU 设备重建，丢弃 {} 个未完成的画面回读", discarded);
+        }
+        self.device_generation = old.device_generation + 1;
+    }
+
+    /// 当前使用的 GPU 适配器信息
+    pub fn adapter_info(&self) -> &wgpu::AdapterInfo {
+        &self.adapter_info
+    }
+
+    /// 当前使用的呈现模式
+    pub fn present_mode(&self) -> wgpu::PresentMode {
+        self.surface_config.present_mode
+    }
+
+    /// 设备重建次数；菜单渲染器等持有 GPU 资源的组件在变化时需要重新创建
+    pub fn device_generation(&self) -> u64 {
+        self.device_generation
+    }
+
+    /// 用占位颜色填充视频纹理，新的视频帧到达后自动替换
+    fn upload_placeholder_frame(&mut self) {
+        let frame = Frame::new(PLACEHOLDER_COLOR.to_vec(), 1, 1, PixelFormat::RGBA8);
+        if let Err(e) = self.upload_frame(&frame) {
+            warn!("上传占位画面失败: {}", e);
+        }
+    }
+
+    /// 获取表面纹理；表面失效时重新配置，无法获取时返回 None 跳过本帧
+    fn acquire_surface_texture(&mut self) -> Result<Option<wgpu::SurfaceTexture>, RenderError> {
+        match self.surface.get_current_texture() {
+            Ok(output) => Ok(Some(output)),
+            Err(wgpu::SurfaceError::Lost | wgpu::SurfaceError::Outdated) => {
+                // 显示器变化、扩展坞拔插等会使交换链失效，按当前窗口尺寸重新配置
+                warn!("渲染表面已失效，重新配置");
+                let size = self.window.inner_size();
+                if size.width == 0 || size.height == 0 {
+                    return Ok(None);
+                }
+                self.resize(size.width, size.height);
+                match self.surface.get_current_texture() {
+                    Ok(output) => Ok(Some(output)),
+                    Err(e) => {
+                        warn!("重新配置后仍无法获取表面纹理: {}，跳过本帧", e);
+                        Ok(None)
+                    }
+                }
+            }
+            Err(wgpu::SurfaceError::Timeout) => {
+                warn!("获取表面纹理超时，跳过本帧");
+                Ok(None)
+            }
+            Err(e) => {
+                error!("获取表面纹理失败: {}", e);
+                Err(RenderError::RenderFailed(format!("获取表面纹理失败: {}", e)))
+            }
+        }
+    }
+
     /// 调整表面大小
     pub fn resize(&mut self, width: u32, height: u32) {
         if width > 0 && height > 0 {
@@ -538,27 +951,40 @@ impl RenderEngine {
     }
 
     /// 上传视频帧到 GPU
+    ///
+    /// RGBA8 帧直接写入纹理；RGB8 帧由计算着色器在 GPU 上展开，
+    /// 适配器不支持计算着色器时才在 CPU 上转换。纹理尺寸不变时原地复用。
     pub fn upload_frame(&mut self, frame: &Frame) -> Result<(), RenderError> {
         debug!("上传视频帧: {}x{}, 格式: {:?}", frame.width, frame.height, frame.format);
         
-        // 转换帧格式为 RGBA
-        let rgba_data = self.convert_frame_to_rgba(frame)
-            .map_err(|e| {
-                error!("转换帧格式失败: {}", e);
-                e
-            })?;
-        
-        debug!("转换后的 RGBA 数据大小: {} 字节 (期望: {} 字节)", 
-               rgba_data.len(), frame.width * frame.height * 4);
-        
         // 验证数据大小
-        let expected_size = (frame.width * frame.height * 4) as usize;
-        if rgba_data.len() != expected_size {
-            error!("RGBA 数据大小不匹配: 实际 {} 字节, 期望 {} 字节", 
-                   rgba_data.len(), expected_size);
+        let Some(bytes_per_pixel) = frame.format.bytes_per_pixel() else {
+            error!("不支持的像素格式: {:?}", frame.format);
+            return Err(RenderError::TextureUploadFailed);
+        };
+        let expected_size = (frame.width * frame.height * bytes_per_pixel) as usize;
+        if frame.data.len() != expected_size {
+            error!("{:?} 数据大小不匹配: 实际 {} 字节, 期望 {} 字节 ({}x{}x{})", 
+                   frame.format, frame.data.len(), expected_size, frame.width, frame.height, bytes_per_pixel);
             return Err(RenderError::TextureUploadFailed);
         }
         
+        // 处理待取色请求
+        if let Some(tex_coords) = self.pending_key_pick.take() {
+            match sample_key_color(&frame.data, frame.width, frame.height, bytes_per_pixel, tex_coords) {
+                Some(color) => {
+                    info!("抠像关键色已设置为 ({:.3}, {:.3}, {:.3})", color[0], color[1], color[2]);
+                    self.chroma_key.key_color = color;
+                }
+                None => warn!("取色位置 ({:.3}, {:.3}) 超出视频范围", tex_coords.0, tex_coords.1),
+            }
+        }
+        
+        // 自动校正：统计本帧并平滑更新校正参数
+        if self.auto_corrector.settings().is_active() {
+            self.auto_corrector.process_frame(&frame.data, frame.width, frame.height, bytes_per_pixel);
+        }
+        
         // 创建或更新视频纹理
         let texture_size = wgpu::Extent3d {
             width: frame.width,
@@ -588,7 +1014,8 @@ impl RenderEngine {
                 sample_count: 1,
                 dimension: wgpu::TextureDimension::D2,
                 format: wgpu::TextureFormat::Rgba8UnormSrgb,
-                usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+                // COPY_SRC 用于回读原始画面
+                usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST | wgpu::TextureUsages::COPY_SRC,
                 label: Some("video_texture"),
                 view_formats: &[],
             }));
@@ -599,98 +1026,439 @@ impl RenderEngine {
         }
 
         // 上传数据到纹理
-        if let Some(texture) = &self.video_texture {
-            debug!("准备上传 {} 字节数据到 {}x{} 视频纹理", 
-                   rgba_data.len(), frame.width, frame.height);
-            
-            self.queue.write_texture(
-                wgpu::ImageCopyTexture {
-                    texture,
-                    mip_level: 0,
-                    origin: wgpu::Origin3d::ZERO,
-                    aspect: wgpu::TextureAspect::All,
-                },
-                &rgba_data,
-                wgpu::ImageDataLayout {
-                    offset: 0,
-                    bytes_per_row: Some(4 * frame.width),
-                    rows_per_image: Some(frame.height),
-                },
-                texture_size,
-            );
-            
-            debug!("视频帧上传成功");
+        let Some(texture) = &self.video_texture else {
+            return Ok(());
+        };
+        match (frame.format, &mut self.frame_uploader) {
+            (PixelFormat::RGB8, Some(uploader)) => {
+                uploader.upload_rgb(&self.device, &self.queue, &frame.data, texture);
+            }
+            (PixelFormat::RGBA8, _) => {
+                Self::write_rgba_texture(&self.queue, texture, &frame.data);
+            }
+            _ => {
+                let rgba_data = self.convert_frame_to_rgba(frame)?;
+                Self::write_rgba_texture(&self.queue, texture, &rgba_data);
+                self.frame_buffer_pool.return_buffer(rgba_data);
+            }
         }
+        debug!("视频帧上传成功");
 
         Ok(())
     }
 
+    /// 把 RGBA8 数据写入整个纹理
+    fn write_rgba_texture(queue: &wgpu::Queue, texture: &wgpu::Texture, rgba: &[u8]) {
+        let size = texture.size();
+        queue.write_texture(
+            texture.as_image_copy(),
+            rgba,
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(4 * size.width),
+                rows_per_image: Some(size.height),
+            },
+            size,
+        );
+    }
+
     /// 设置形状遮罩
     pub fn set_mask(&mut self, mask: &ShapeMask) -> Result<(), RenderError> {
         debug!("设置形状遮罩: {:?}, 尺寸: {}x{}", mask.shape_type(), mask.width(), mask.height());
         
-        let texture_size = wgpu::Extent3d {
+        // 保留 CPU 副本，设备丢失后重新上传
+        let mut data = self.current_mask.take().map(|current| current.data).unwrap_or_default();
+        data.clear();
+        data.extend_from_slice(mask.data());
+        self.upload_mask(MaskUpload {
+            key: mask.cache_key(),
             width: mask.width(),
             height: mask.height(),
+            data,
+        });
+        Ok(())
+    }
+
+    fn upload_mask(&mut self, mask: MaskUpload) {
+        // 之前生成过相同的遮罩，直接复用缓存的纹理
+        let key = mask.key;
+        if let Some(texture) = self.mask_texture_cache.get(&key) {
+            debug!("遮罩纹理缓存命中: {:?}", key);
+            self.mask_texture = Some(Arc::clone(texture));
+            self.video_bind_group = None;
+            self.current_mask = Some(mask);
+            return;
+        }
+        
+        let texture_size = wgpu::Extent3d {
+            width: mask.width,
+            height: mask.height,
             depth_or_array_layers: 1,
         };
 
-        // 创建或更新遮罩纹理
-        let need_new_texture = self.mask_texture.as_ref()
-            .map(|t| {
-                let size = t.size();
-                size.width != mask.width() || size.height != mask.height()
-            })
-            .unwrap_or(true);
+        // 缓存中的纹理可能仍被引用，每个遮罩都使用独立的纹理
+        debug!("创建新的遮罩纹理: {}x{}", mask.width, mask.height);
+        let texture = Arc::new(self.device.create_texture(&wgpu::TextureDescriptor {
+            size: texture_size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::R8Unorm, // 单通道 alpha
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            label: Some("mask_texture"),
+            view_formats: &[],
+        }));
 
-        if need_new_texture {
-            debug!("创建新的遮罩纹理: {}x{}", mask.width(), mask.height());
-            
-            self.mask_texture = Some(self.device.create_texture(&wgpu::TextureDescriptor {
-                size: texture_size,
+        // 上传遮罩数据到纹理
+        debug!("上传 {} 字节遮罩数据到纹理", mask.data.len());
+        self.queue.write_texture(
+            wgpu::ImageCopyTexture {
+                texture: &texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            &mask.data,
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(mask.width),
+                rows_per_image: Some(mask.height),
+            },
+            texture_size,
+        );
+        debug!("遮罩数据上传成功");
+
+        self.mask_texture_cache.insert(key, Arc::clone(&texture), key.size_bytes());
+        self.mask_texture = Some(texture);
+        self.current_mask = Some(mask);
+
+        // 清除旧的绑定组，强制重新创建
+        self.video_bind_group = None;
+        debug!("遮罩设置完成，绑定组将在下次渲染时重新创建");
+    }
+
+    /// 设置视频内容在屏幕上的旋转角度（弧度），与遮罩旋转相互独立
+    pub fn set_video_rotation(&mut self, rotation: f32) {
+        self.video_rotation = rotation;
+    }
+
+    /// 设置颜色调整参数
+    pub fn set_color_adjustments(&mut self, adjustments: ColorAdjustments) {
+        self.color_adjustments = adjustments;
+    }
+
+    /// 获取颜色调整参数
+    pub fn color_adjustments(&self) -> ColorAdjustments {
+        self.color_adjustments
+    }
+
+    /// 设置预设外观
+    pub fn set_color_preset(&mut self, preset: ColorPreset) {
+        info!("切换颜色预设: {}", preset.as_str());
+        self.color_preset = preset;
+    }
+
+    /// 获取当前预设外观
+    pub fn color_preset(&self) -> ColorPreset {
+        self.color_preset
+    }
+
+    /// 加载 3D LUT 并上传到 GPU，加载后自动启用
+    pub fn load_lut(&mut self, lut: CubeLut) {
+        info!("加载 {}³ LUT: {}", lut.size(), lut.title().unwrap_or("未命名"));
+        self.lut_texture = Self::create_lut_texture(&self.device, &self.queue, &lut);
+        self.lut = Some(lut);
+        self.lut_enabled = true;
+
+        // LUT 纹理变化后需要重新创建绑定组
+        self.video_bind_group = None;
+    }
+
+    /// 是否已加载 LUT
+    pub fn has_lut(&self) -> bool {
+        self.lut.is_some()
+    }
+
+    /// 启用或禁用已加载的 LUT
+    pub fn set_lut_enabled(&mut self, enabled: bool) {
+        self.lut_enabled = enabled;
+    }
+
+    /// LUT 是否正在生效
+    pub fn lut_enabled(&self) -> bool {
+        self.lut_enabled && self.lut.is_some()
+    }
+
+    /// 设置绿幕抠像参数
+    pub fn set_chroma_key(&mut self, chroma_key: ChromaKey) {
+        self.chroma_key = chroma_key;
+    }
+
+    /// 获取绿幕抠像参数
+    pub fn chroma_key(&self) -> ChromaKey {
+        self.chroma_key
+    }
+
+    /// 启用或禁用绿幕抠像
+    pub fn set_chroma_key_enabled(&mut self, enabled: bool) {
+        info!("绿幕抠像: {}", if enabled { "开启" } else { "关闭" });
+        self.chroma_key.enabled = enabled;
+    }
+
+    /// 请求在下一帧视频的纹理坐标处拾取关键色
+    pub fn request_key_color_pick(&mut self, tex_coords: (f32, f32)) {
+        debug!("请求抠像取色: ({:.3}, {:.3})", tex_coords.0, tex_coords.1);
+        self.pending_key_pick = Some(tex_coords);
+    }
+
+    /// 设置自动校正参数
+    pub fn set_auto_correct_settings(&mut self, settings: AutoCorrectSettings) {
+        self.auto_corrector.set_settings(settings);
+    }
+
+    /// 获取自动校正参数
+    pub fn auto_correct_settings(&self) -> AutoCorrectSettings {
+        self.auto_corrector.settings()
+    }
+
+    /// 设置降噪参数
+    pub fn set_denoise_settings(&mut self, settings: DenoiseSettings) {
+        if !settings.is_active() {
+            // 关闭后释放中间纹理，再次开启时重新创建
+            self.denoise.release_targets();
+        }
+        self.denoise_settings = settings;
+    }
+
+    /// 设置特效通道并编译，编译失败的通道只记录错误并跳过
+    pub fn set_effects(&mut self, passes: Vec<EffectPass>) {
+        self.effects.set_passes(&self.device, passes);
+        if !self.effects.is_active() {
+            self.effects.release_targets();
+        }
+        self.video_bind_group = None;
+    }
+
+    /// 已加载的特效通道
+    pub fn effect_passes(&self) -> impl Iterator<Item = &EffectPass> {
+        self.effects.passes()
+    }
+
+    /// 是否加载了至少一个编译成功的特效
+    pub fn has_effects(&self) -> bool {
+        self.effects.compiled_count() > 0
+    }
+
+    /// 启用或关闭整条特效链
+    pub fn set_effects_enabled(&mut self, enabled: bool) {
+        info!("特效链: {}", if enabled { "开启" } else { "关闭" });
+        self.effects.set_enabled(enabled);
+        if !enabled {
+            self.effects.release_targets();
+        }
+    }
+
+    /// 特效链是否开启
+    pub fn effects_enabled(&self) -> bool {
+        self.effects.is_enabled()
+    }
+
+    /// 获取降噪参数
+    pub fn denoise_settings(&self) -> DenoiseSettings {
+        self.denoise_settings
+    }
+
+    /// 当前降噪降级等级
+    pub fn denoise_level(&self) -> DenoiseLevel {
+        self.denoise_governor.level()
+    }
+
+    /// 设置单帧渲染耗时预算（通常取 PerformanceThresholds::max_render_time_ms）
+    pub fn set_render_time_budget(&mut self, budget: Duration) {
+        info!("渲染耗时预算: {:.1}ms", budget.as_secs_f32() * 1000.0);
+        self.denoise_governor.set_budget(budget);
+    }
+
+    /// 记录一帧的渲染耗时，持续超出预算时降低降噪质量
+    pub fn record_render_time(&mut self, render_time: Duration) {
+        if !self.denoise_settings.is_active() {
+            return;
+        }
+        if self.denoise_governor.record(render_time) == Some(DenoiseLevel::Bypass) {
+            self.denoise.clear_history();
+        }
+    }
+
+    /// 当前颜色参数对应的统一缓冲区数据
+    fn color_uniforms(&self) -> ColorUniforms {
+        let lut = self.lut.as_ref().filter(|_| self.lut_enabled);
+        ColorUniforms::new(&self.color_adjustments, self.color_preset, lut)
+    }
+
+    /// 创建 3D LUT 纹理
+    fn create_lut_texture(device: &wgpu::Device, queue: &wgpu::Queue, lut: &CubeLut) -> wgpu::Texture {
+        let size = lut.size();
+        device.create_texture_with_data(
+            queue,
+            &wgpu::TextureDescriptor {
+                label: Some("Color LUT"),
+                size: wgpu::Extent3d {
+                    width: size,
+                    height: size,
+                    depth_or_array_layers: size,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D3,
+                format: wgpu::TextureFormat::Rgba8Unorm,
+                usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+                view_formats: &[],
+            },
+            wgpu::util::TextureDataOrder::LayerMajor,
+            &lut.to_rgba8(),
+        )
+    }
+
+    /// 创建叠加层纹理，画布为空时创建 1x1 透明纹理
+    fn create_overlay_texture(device: &wgpu::Device, queue: &wgpu::Queue, canvas: &OverlayCanvas) -> wgpu::Texture {
+        let (width, height, data) = if canvas.width() == 0 || canvas.height() == 0 {
+            (1, 1, &[0u8; 4][..])
+        } else {
+            (canvas.width(), canvas.height(), canvas.pixels())
+        };
+        device.create_texture_with_data(
+            queue,
+            &wgpu::TextureDescriptor {
+                label: Some("Overlay Texture"),
+                size: wgpu::Extent3d {
+                    width,
+                    height,
+                    depth_or_array_layers: 1,
+                },
                 mip_level_count: 1,
                 sample_count: 1,
                 dimension: wgpu::TextureDimension::D2,
-                format: wgpu::TextureFormat::R8Unorm, // 单通道 alpha
+                format: wgpu::TextureFormat::Rgba8UnormSrgb,
                 usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
-                label: Some("mask_texture"),
                 view_formats: &[],
-            }));
-            
-            debug!("遮罩纹理创建成功");
+            },
+            wgpu::util::TextureDataOrder::LayerMajor,
+            data,
+        )
+    }
+
+    /// 按窗口尺寸更新叠加层，画布变化时上传到纹理
+    fn update_overlay_texture(&mut self) {
+        if !self.overlay.update(self.surface_config.width, self.surface_config.height) {
+            return;
         }
 
-        // 上传遮罩数据到纹理
-        if let Some(texture) = &self.mask_texture {
-            debug!("上传 {} 字节遮罩数据到纹理", mask.data().len());
-            
+        let canvas = self.overlay.canvas();
+        let size = self.overlay_texture.size();
+        if canvas.width() > 0 && canvas.width() == size.width && canvas.height() == size.height {
             self.queue.write_texture(
                 wgpu::ImageCopyTexture {
-                    texture,
+                    texture: &self.overlay_texture,
                     mip_level: 0,
                     origin: wgpu::Origin3d::ZERO,
                     aspect: wgpu::TextureAspect::All,
                 },
-                mask.data(),
+                canvas.pixels(),
                 wgpu::ImageDataLayout {
                     offset: 0,
-                    bytes_per_row: Some(mask.width()),
-                    rows_per_image: Some(mask.height()),
+                    bytes_per_row: Some(4 * canvas.width()),
+                    rows_per_image: Some(canvas.height()),
                 },
-                texture_size,
+                size,
             );
-            
-            debug!("遮罩数据上传成功");
         } else {
-            error!("遮罩纹理创建失败");
-            return Err(RenderError::TextureUploadFailed);
+            debug!("重新创建叠加层纹理: {}x{}", canvas.width(), canvas.height());
+            self.overlay_texture = Self::create_overlay_texture(&self.device, &self.queue, canvas);
+            self.video_bind_group = None;
         }
+    }
 
-        // 清除旧的绑定组，强制重新创建
-        self.video_bind_group = None;
-        debug!("遮罩设置完成，绑定组将在下次渲染时重新创建");
+    /// 设置文字渲染器（字体加载成功后调用）
+    pub fn set_text_renderer(&mut self, renderer: TextRenderer) {
+        self.overlay.set_text_renderer(renderer);
+    }
 
-        Ok(())
+    /// 设置文字叠加列表
+    pub fn set_text_overlays(&mut self, overlays: Vec<TextOverlay>) {
+        self.overlay.set_text_overlays(overlays);
+    }
+
+    /// 当前文字叠加列表
+    pub fn text_overlays(&self) -> &[TextOverlay] {
+        self.overlay.text_overlays()
+    }
+
+    /// 是否有可显示的文字叠加（已配置文字且字体可用）
+    pub fn has_text_overlays(&self) -> bool {
+        self.overlay.has_text_renderer() && !self.overlay.text_overlays().is_empty()
+    }
+
+    /// 显示或隐藏叠加层
+    pub fn set_overlay_visible(&mut self, visible: bool) {
+        info!("叠加层: {}", if visible { "显示" } else { "隐藏" });
+        self.overlay.set_visible(visible);
+    }
+
+    /// 叠加层是否显示
+    pub fn overlay_visible(&self) -> bool {
+        self.overlay.is_visible()
+    }
+
+    /// 时钟文字叠加的显示内容是否已变化，需要重绘
+    pub fn overlay_clock_changed(&self) -> bool {
+        self.overlay.clock_changed()
+    }
+
+    /// 设置贴纸列表，图片在后台解码，加载完成后显示
+    pub fn set_stickers(&mut self, stickers: Vec<Sticker>) {
+        info!("设置 {} 个贴纸", stickers.len());
+        self.stickers.set_stickers(stickers);
+    }
+
+    /// 当前贴纸列表
+    pub fn stickers(&self) -> &[Sticker] {
+        self.stickers.stickers()
+    }
+
+    /// 是否配置了贴纸
+    pub fn has_stickers(&self) -> bool {
+        !self.stickers.stickers().is_empty()
+    }
+
+    /// 显示或隐藏贴纸
+    pub fn set_stickers_visible(&mut self, visible: bool) {
+        info!("贴纸: {}", if visible { "显示" } else { "隐藏" });
+        self.stickers.set_visible(visible);
+    }
+
+    /// 贴纸是否显示
+    pub fn stickers_visible(&self) -> bool {
+        self.stickers.is_visible()
+    }
+
+    /// 是否有需要逐帧重绘的贴纸（动画贴纸播放中，或图片仍在后台解码）
+    pub fn stickers_animating(&self) -> bool {
+        self.stickers.is_animating()
+    }
+
+    /// 手绘标注
+    pub fn annotations(&self) -> &Annotations {
+        self.overlay.annotations()
+    }
+
+    /// 修改手绘标注，下一帧重绘叠加层
+    pub fn annotations_mut(&mut self) -> &mut Annotations {
+        self.overlay.annotations_mut()
+    }
+
+    /// 设置遮罩纹理缓存的内存预算（字节）
+    pub fn set_mask_cache_budget(&mut self, budget_bytes: usize) {
+        self.mask_texture_cache.set_budget(budget_bytes);
     }
 
     /// 渲染一帧
@@ -706,7 +1474,44 @@ impl RenderEngine {
     
     /// 渲染一帧（带UI）
     pub fn render_with_ui(&mut self, rotation: f32, ui_info: &UIRenderInfo) -> Result<(), RenderError> {
+        self.render_frame(rotation, ui_info, None)
+    }
+
+    /// 渲染一帧（带UI和上下文菜单），菜单与视频绘制在同一帧中再呈现
+    pub fn render_with_menu(
+        &mut self,
+        rotation: f32,
+        ui_info: &UIRenderInfo,
+        menu_renderer: &mut crate::ui::MenuRenderer,
+        context_menu: &crate::ui::ContextMenu,
+    ) -> Result<(), RenderError> {
+        self.render_frame(rotation, ui_info, Some((menu_renderer, context_menu)))
+    }
+
+    fn render_frame(
+        &mut self,
+        rotation: f32,
+        ui_info: &UIRenderInfo,
+        menu: Option<(&mut crate::ui::MenuRenderer, &crate::ui::ContextMenu)>,
+    ) -> Result<(), RenderError> {
         debug!("开始渲染帧，旋转角度: {:.1}°", rotation.to_degrees());
+
+        // 设备丢失后重建，本帧跳过：调用方传入的菜单渲染器仍属于旧设备
+        if self.device_lost.load(Ordering::SeqCst) {
+            return self.recover_device();
+        }
+
+        // 开发模式：着色器文件变化时重建管线
+        self.reload_shaders();
+
+        // 收取之前各帧的 GPU 计时结果，CPU 编码时间从这里开始计算
+        let encode_start = Instant::now();
+        if let Some(profiler) = &mut self.profiler {
+            if let Some(gpu_passes) = profiler.poll(&self.device) {
+                self.frame_timings.gpu_passes = gpu_passes;
+            }
+            profiler.begin_frame();
+        }
         
         // 检查是否有视频纹理和遮罩纹理
         let has_video_texture = self.video_texture.is_some();
@@ -725,8 +1530,62 @@ impl RenderEngine {
         debug!("更新旋转矩阵");
         let mut uniforms = Uniforms::new();
         uniforms.update_rotation(rotation);
+        uniforms.update_video_rotation(rotation, self.video_rotation);
+        uniforms.color = self.color_uniforms();
+        uniforms.chroma_key = ChromaKeyUniforms::new(&self.chroma_key);
+        uniforms.auto_correct = AutoCorrectUniforms::new(
+            &self.auto_corrector.correction(),
+            self.auto_corrector.settings().is_active(),
+        );
+        // 回读合成画面的一帧与窗口共用统一缓冲区，不带快门闪光
+        let output_alpha = OutputAlpha::for_mode(self.surface_config.alpha_mode);
+        let capture_composited = self.capture_requests.contains(&CaptureSource::Composited);
+        let flash = if capture_composited { 0.0 } else { self.shutter_flash };
+        uniforms.output = OutputUniforms::new(output_alpha, self.surface_config.format).with_flash(flash);
         self.queue.write_buffer(&self.uniform_buffer, 0, bytemuck::cast_slice(&[uniforms]));
 
+        // 降噪开关或中间纹理变化时需要重新绑定视频来源
+        let denoise_settings = self.denoise_settings.effective(self.denoise_governor.level());
+        let denoise_active = denoise_settings.is_active();
+        if denoise_active {
+            let size = self.video_texture.as_ref().unwrap().size();
+            if self.denoise.ensure_targets(&self.device, size.width, size.height) {
+                self.video_bind_group = None;
+            }
+        }
+
+        // 特效链接在降噪之后，通道增减会改变结果所在的乒乓纹理
+        let effects_active = self.effects.is_active();
+        let video_source = if effects_active {
+            let size = self.video_texture.as_ref().unwrap().size();
+            if self.effects.ensure_targets(&self.device, size.width, size.height) {
+                self.video_bind_group = None;
+            }
+            VideoSource::Effects(self.effects.output_index())
+        } else if denoise_active {
+            VideoSource::Denoised
+        } else {
+            VideoSource::Raw
+        };
+        if video_source != self.video_bind_group_source {
+            self.video_bind_group = None;
+        }
+
+        // 叠加层内容变化时重新上传
+        self.update_overlay_texture();
+
+        // 贴纸：接收后台解码结果，贴纸层随窗口尺寸创建，内容或动画帧变化时重绘
+        self.stickers.poll();
+        let stickers_active = self.stickers.is_active();
+        if stickers_active {
+            if self.sticker_pipeline.ensure_targets(&self.device, self.surface_config.width, self.surface_config.height) {
+                self.video_bind_group = None;
+            }
+        } else if self.sticker_pipeline.release_targets() {
+            self.video_bind_group = None;
+        }
+        let redraw_stickers = stickers_active && self.sticker_pipeline.sync(&self.device, &self.queue, &self.stickers);
+
         // 创建或更新纹理绑定组
         if self.video_bind_group.is_none() {
             debug!("创建纹理绑定组");
@@ -734,38 +1593,69 @@ impl RenderEngine {
             let video_texture = self.video_texture.as_ref().unwrap();
             let mask_texture = self.mask_texture.as_ref().unwrap();
             
-            let video_view = video_texture.create_view(&wgpu::TextureViewDescriptor::default());
+            let raw_video_view = video_texture.create_view(&wgpu::TextureViewDescriptor::default());
+            let video_view = match video_source {
+                VideoSource::Effects(_) => self.effects.output_view(),
+                VideoSource::Denoised => self.denoise.output_view(),
+                VideoSource::Raw => None,
+            }
+            .unwrap_or(&raw_video_view);
             let mask_view = mask_texture.create_view(&wgpu::TextureViewDescriptor::default());
+            let lut_view = self.lut_texture.create_view(&wgpu::TextureViewDescriptor::default());
+            let overlay_view = self.overlay_texture.create_view(&wgpu::TextureViewDescriptor::default());
 
             self.video_bind_group = Some(self.device.create_bind_group(&wgpu::BindGroupDescriptor {
                 layout: &self.bind_group_layout,
                 entries: &[
                     wgpu::BindGroupEntry {
-                        binding: 0,
-                        resource: wgpu::BindingResource::TextureView(&video_view),
+                        binding: 0,
+                        resource: wgpu::BindingResource::TextureView(video_view),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::TextureView(&mask_view),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 2,
+                        resource: wgpu::BindingResource::Sampler(&self.sampler),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 3,
+                        resource: wgpu::BindingResource::TextureView(&lut_view),
                     },
                     wgpu::BindGroupEntry {
-                        binding: 1,
-                        resource: wgpu::BindingResource::TextureView(&mask_view),
+                        binding: 4,
+                        resource: wgpu::BindingResource::Sampler(&self.lut_sampler),
                     },
                     wgpu::BindGroupEntry {
-                        binding: 2,
-                        resource: wgpu::BindingResource::Sampler(&self.sampler),
+                        binding: 5,
+                        resource: wgpu::BindingResource::TextureView(&overlay_view),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 6,
+                        resource: wgpu::BindingResource::TextureView(
+                            self.sticker_pipeline.below_view().unwrap_or(&self.sticker_placeholder),
+                        ),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 7,
+                        resource: wgpu::BindingResource::TextureView(
+                            self.sticker_pipeline.above_view().unwrap_or(&self.sticker_placeholder),
+                        ),
                     },
                 ],
                 label: Some("video_bind_group"),
             }));
+            self.video_bind_group_source = video_source;
             
             debug!("纹理绑定组创建完成");
         }
 
         // 获取表面纹理
         debug!("获取表面纹理");
-        let output = self.surface.get_current_texture()
-            .map_err(|e| {
-                error!("获取表面纹理失败: {}", e);
-                RenderError::RenderFailed(format!("获取表面纹理失败: {}", e))
-            })?;
+        let Some(output) = self.acquire_surface_texture()? else {
+            return Ok(());
+        };
         
         let view = output.texture.create_view(&wgpu::TextureViewDescriptor::default());
 
@@ -775,59 +1665,58 @@ impl RenderEngine {
             label: Some("Render Encoder"),
         });
 
-        // 第一阶段：渲染主视频内容
-        {
-            debug!("开始主视频渲染通道");
-            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-                label: Some("Main Video Render Pass"),
-                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view: &view,
-                    resolve_target: None,
-                    ops: wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(wgpu::Color {
-                            r: 0.0,
-                            g: 0.0,
-                            b: 0.0,
-                            a: 0.0, // 透明背景
-                        }),
-                        store: wgpu::StoreOp::Store,
-                    },
-                })],
-                depth_stencil_attachment: None,
-                occlusion_query_set: None,
-                timestamp_writes: None,
-            });
+        let preprocess_active = denoise_active || effects_active || redraw_stickers;
+        if preprocess_active {
+            self.profile_begin(&mut encoder, RenderPass::Effects);
+        }
 
-            // 设置主视频渲染管线
-            render_pass.set_pipeline(&self.pipeline);
-            
-            // 绑定纹理
-            if let Some(bind_group) = &self.video_bind_group {
-                render_pass.set_bind_group(0, bind_group, &[]);
-                debug!("纹理绑定组已绑定");
-            } else {
-                error!("纹理绑定组不存在");
-                return Err(RenderError::RenderFailed("纹理绑定组不存在".to_string()));
-            }
-            
-            // 绑定统一缓冲区
-            render_pass.set_bind_group(1, &self.uniform_bind_group, &[]);
-            debug!("统一缓冲区已绑定");
-            
-            // 设置顶点和索引缓冲区
-            render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
-            render_pass.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
-            debug!("顶点和索引缓冲区已设置");
-            
-            // 绘制主视频内容
-            render_pass.draw_indexed(0..INDICES.len() as u32, 0, 0..1);
-            debug!("主视频内容绘制完成，索引数量: {}", INDICES.len());
+        // 预处理：磨皮平滑与时域降噪
+        if denoise_active {
+            let video_view = self.video_texture.as_ref().unwrap().create_view(&wgpu::TextureViewDescriptor::default());
+            self.denoise.encode(
+                &self.device,
+                &self.queue,
+                &mut encoder,
+                &video_view,
+                &denoise_settings,
+                self.denoise_governor.level(),
+            );
+        }
+
+        // 预处理：用户特效链，输入为降噪结果或原始帧
+        if effects_active {
+            let raw_video_view = self.video_texture.as_ref().unwrap().create_view(&wgpu::TextureViewDescriptor::default());
+            let input_view = match self.denoise.output_view() {
+                Some(view) if denoise_active => view,
+                _ => &raw_video_view,
+            };
+            self.effects.encode(&self.device, &self.queue, &mut encoder, input_view);
+        }
+
+        // 预处理：重绘贴纸层
+        if redraw_stickers {
+            self.sticker_pipeline.encode(&self.device, &mut encoder, &self.stickers);
+        }
+
+        if preprocess_active {
+            self.profile_end(&mut encoder, RenderPass::Effects);
         }
 
-        // 第二阶段：渲染UI控件（如果需要显示）
-        if ui_info.show_controls {
+        // 第一阶段：渲染主视频内容
+        self.profile_begin(&mut encoder, RenderPass::Video);
+        self.encode_video_pass(&mut encoder, &view, "Main Video Render Pass")?;
+        self.profile_end(&mut encoder, RenderPass::Video);
+
+        // 画面回读：在控件和菜单之前编码，合成画面单独渲染到离屏纹理
+        self.encode_capture_requests(&mut encoder, output_alpha)?;
+
+        // 第二阶段：渲染UI控件（如果需要显示）和录制指示
+        if ui_info.show_controls || self.recording_indicator {
             debug!("开始UI控件渲染");
-            match self.render_ui_controls(&mut encoder, &view, ui_info) {
+            self.profile_begin(&mut encoder, RenderPass::Controls);
+            let result = self.render_ui_controls(&mut encoder, &view, ui_info);
+            self.profile_end(&mut encoder, RenderPass::Controls);
+            match result {
                 Ok(()) => {
                     debug!("UI控件渲染成功");
                 }
@@ -839,9 +1728,26 @@ impl RenderEngine {
             }
         }
 
+        // 第三阶段：渲染上下文菜单（在视频和UI控件之上）
+        if let Some((menu_renderer, context_menu)) = menu {
+            let screen_size = [self.surface_config.width as f32, self.surface_config.height as f32];
+            // 失败时已记录日志，不影响主视频
+            self.profile_begin(&mut encoder, RenderPass::Menu);
+            let _ = self.render_context_menu_integrated(menu_renderer, context_menu, screen_size, &mut encoder, &view);
+            self.profile_end(&mut encoder, RenderPass::Menu);
+        }
+
         // 提交命令
         debug!("提交渲染命令到 GPU");
+        if let Some(profiler) = &mut self.profiler {
+            profiler.resolve(&mut encoder);
+        }
+        self.frame_timings.cpu_encode = encode_start.elapsed();
         self.queue.submit(std::iter::once(encoder.finish()));
+        if let Some(profiler) = &mut self.profiler {
+            profiler.after_submit();
+        }
+        self.frame_capture.after_submit();
         
         // 呈现到屏幕
         output.present();
@@ -850,6 +1756,132 @@ impl RenderEngine {
         Ok(())
     }
     
+    /// 编码主视频渲染通道：清除为透明背景后绘制合成画面
+    fn encode_video_pass(&self, encoder: &mut wgpu::CommandEncoder, view: &wgpu::TextureView, label: &str) -> Result<(), RenderError> {
+        debug!("开始渲染通道: {}", label);
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some(label),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color {
+                        r: 0.0,
+                        g: 0.0,
+                        b: 0.0,
+                        a: 0.0, // 透明背景
+                    }),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            occlusion_query_set: None,
+            timestamp_writes: None,
+        });
+
+        // 设置主视频渲染管线
+        render_pass.set_pipeline(&self.pipeline);
+        
+        // 绑定纹理
+        if let Some(bind_group) = &self.video_bind_group {
+            render_pass.set_bind_group(0, bind_group, &[]);
+            debug!("纹理绑定组已绑定");
+        } else {
+            error!("纹理绑定组不存在");
+            return Err(RenderError::RenderFailed("纹理绑定组不存在".to_string()));
+        }
+        
+        // 绑定统一缓冲区
+        render_pass.set_bind_group(1, &self.uniform_bind_group, &[]);
+        debug!("统一缓冲区已绑定");
+        
+        // 设置顶点和索引缓冲区
+        render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+        render_pass.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
+        debug!("顶点和索引缓冲区已设置");
+        
+        // 绘制主视频内容
+        render_pass.draw_indexed(0..INDICES.len() as u32, 0, 0..1);
+        debug!("主视频内容绘制完成，索引数量: {}", INDICES.len());
+        Ok(())
+    }
+
+    /// 编码本帧的画面回读请求
+    fn encode_capture_requests(&mut self, encoder: &mut wgpu::CommandEncoder, output_alpha: OutputAlpha) -> Result<(), RenderError> {
+        for source in std::mem::take(&mut self.capture_requests) {
+            match source {
+                CaptureSource::Composited => {
+                    let target = self.frame_capture.composite_target(
+                        &self.device,
+                        self.surface_config.width,
+                        self.surface_config.height,
+                        self.surface_config.format,
+                    );
+                    self.encode_video_pass(encoder, &target, "Capture Render Pass")?;
+                    self.frame_capture.copy_composited(&self.device, encoder, output_alpha);
+                }
+                CaptureSource::Raw => {
+                    // 视频纹理为非预乘的不透明画面
+                    let texture = self.video_texture.as_ref().unwrap();
+                    self.frame_capture.copy_texture(&self.device, encoder, texture, source, OutputAlpha::Straight);
+                }
+            }
+            debug!("已编码{}回读", source.name());
+        }
+        Ok(())
+    }
+
+    /// 请求回读下一帧的画面，结果通过 poll_captures 取得
+    pub fn request_capture(&mut self, source: CaptureSource) {
+        if !self.capture_requests.contains(&source) {
+            self.capture_requests.push(source);
+        }
+    }
+
+    /// 取出已完成的画面回读
+    pub fn poll_captures(&mut self) -> Vec<CapturedFrame> {
+        self.frame_capture.poll(&self.device)
+    }
+
+    /// 是否有尚未完成的回读请求
+    pub fn capture_pending(&self) -> bool {
+        !self.capture_requests.is_empty() || self.frame_capture.is_pending()
+    }
+
+    /// 设置快门闪光强度（0-1），闪光只作用于形状内可见的部分
+    pub fn set_shutter_flash(&mut self, intensity: f32) {
+        self.shutter_flash = intensity.clamp(0.0, 1.0);
+    }
+
+    /// 设置是否显示录制指示；指示只画在窗口上，不会出现在回读的合成画面中
+    pub fn set_recording_indicator(&mut self, recording: bool) {
+        self.recording_indicator = recording;
+    }
+
+    /// 在渲染阶段开始前写入 GPU 时间戳
+    fn profile_begin(&mut self, encoder: &mut wgpu::CommandEncoder, pass: RenderPass) {
+        if let Some(profiler) = &mut self.profiler {
+            profiler.begin_pass(encoder, pass);
+        }
+    }
+
+    /// 在渲染阶段结束后写入 GPU 时间戳
+    fn profile_end(&mut self, encoder: &mut wgpu::CommandEncoder, pass: RenderPass) {
+        if let Some(profiler) = &mut self.profiler {
+            profiler.end_pass(encoder, pass);
+        }
+    }
+
+    /// 最近一帧的 CPU 编码时间和各阶段 GPU 耗时（GPU 结果异步回读，通常晚一到两帧）
+    pub fn frame_timings(&self) -> FrameTimings {
+        self.frame_timings
+    }
+
+    /// 是否支持 GPU 时间戳计时
+    pub fn gpu_timing_supported(&self) -> bool {
+        self.profiler.is_some()
+    }
+
     /// 渲染上下文菜单（集成到现有渲染管线）
     pub fn render_context_menu_integrated(
         &mut self,
@@ -862,7 +1894,7 @@ impl RenderEngine {
         debug!("渲染上下文菜单（集成模式）");
         
         // 渲染菜单（在现有内容之上）
-        match menu_renderer.render_menu(encoder, view, context_menu, screen_size) {
+        match menu_renderer.render_menu(&self.device, &self.queue, encoder, view, context_menu, screen_size) {
             Ok(()) => {
                 debug!("上下文菜单渲染成功");
                 Ok(())
@@ -917,7 +1949,7 @@ impl RenderEngine {
         let symbol_color = [1.0, 1.0, 1.0, 1.0]; // 白色符号
         
         // 生成UI几何体数据
-        let ui_geometry = self.generate_ui_geometry(&ui_uniforms, button_size, close_color, minimize_color, symbol_color)
+        let ui_geometry = self.generate_ui_geometry(&ui_uniforms, button_size, close_color, minimize_color, symbol_color, ui_info.show_controls)
             .map_err(|e| {
                 error!("生成UI几何体失败: {}", e);
                 RenderError::UIRenderFailed(format!("UI几何体生成失败: {}", e))
@@ -976,75 +2008,91 @@ impl RenderEngine {
         close_color: [f32; 4],
         minimize_color: [f32; 4],
         symbol_color: [f32; 4],
+        show_buttons: bool,
     ) -> Result<UIGeometry, String> {
-        // 关闭按钮几何体（圆形背景）
-        let close_bg_vertices = self.create_button_geometry(
-            ui_uniforms.close_button_pos[0] + button_size / 2.0,
-            ui_uniforms.close_button_pos[1] + button_size / 2.0,
-            button_size,
-            close_color,
-        );
-        
-        // 关闭按钮符号（X）
-        let close_symbol_vertices = self.create_button_symbol(
-            ui_uniforms.close_button_pos[0] + button_size / 2.0,
-            ui_uniforms.close_button_pos[1] + button_size / 2.0,
-            button_size,
-            "×",
-            symbol_color,
-        );
-        
-        // 最小化按钮几何体（圆形背景）
-        let minimize_bg_vertices = self.create_button_geometry(
-            ui_uniforms.minimize_button_pos[0] + button_size / 2.0,
-            ui_uniforms.minimize_button_pos[1] + button_size / 2.0,
-            button_size,
-            minimize_color,
-        );
-        
-        // 最小化按钮符号（减号）
-        let minimize_symbol_vertices = self.create_button_symbol(
-            ui_uniforms.minimize_button_pos[0] + button_size / 2.0,
-            ui_uniforms.minimize_button_pos[1] + button_size / 2.0,
-            button_size,
-            "−",
-            symbol_color,
-        );
-        
-        // 合并所有顶点数据
-        let mut all_vertices = Vec::new();
-        all_vertices.extend(close_bg_vertices);
-        all_vertices.extend(close_symbol_vertices);
-        all_vertices.extend(minimize_bg_vertices);
-        all_vertices.extend(minimize_symbol_vertices);
-        
-        // 创建索引数据
         let segments = 16;
+        let mut all_vertices = Vec::new();
         let mut all_indices = Vec::new();
         let mut vertex_offset = 0u16;
         
-        // 关闭按钮背景索引
-        let close_bg_indices = self.create_button_indices(segments);
-        for &index in &close_bg_indices {
-            all_indices.push(index + vertex_offset);
+        if show_buttons {
+            // 关闭按钮几何体（圆形背景）
+            let close_bg_vertices = self.create_button_geometry(
+                ui_uniforms.close_button_pos[0] + button_size / 2.0,
+                ui_uniforms.close_button_pos[1] + button_size / 2.0,
+                button_size,
+                close_color,
+            );
+            
+            // 关闭按钮符号（X）
+            let close_symbol_vertices = self.create_button_symbol(
+                ui_uniforms.close_button_pos[0] + button_size / 2.0,
+                ui_uniforms.close_button_pos[1] + button_size / 2.0,
+                button_size,
+                "×",
+                symbol_color,
+            );
+            
+            // 最小化按钮几何体（圆形背景）
+            let minimize_bg_vertices = self.create_button_geometry(
+                ui_uniforms.minimize_button_pos[0] + button_size / 2.0,
+                ui_uniforms.minimize_button_pos[1] + button_size / 2.0,
+                button_size,
+                minimize_color,
+            );
+            
+            // 最小化按钮符号（减号）
+            let minimize_symbol_vertices = self.create_button_symbol(
+                ui_uniforms.minimize_button_pos[0] + button_size / 2.0,
+                ui_uniforms.minimize_button_pos[1] + button_size / 2.0,
+                button_size,
+                "−",
+                symbol_color,
+            );
+            
+            // 合并所有顶点数据
+            all_vertices.extend(close_bg_vertices);
+            all_vertices.extend(close_symbol_vertices);
+            all_vertices.extend(minimize_bg_vertices);
+            all_vertices.extend(minimize_symbol_vertices);
+            
+            // 创建索引数据：关闭按钮背景
+            let close_bg_indices = self.create_button_indices(segments);
+            for &index in &close_bg_indices {
+                all_indices.push(index + vertex_offset);
+            }
+            vertex_offset += (segments + 2) as u16;
+            
+            // 关闭按钮符号索引（两条线，每条线4个顶点）
+            let close_symbol_indices = self.create_symbol_indices(vertex_offset, 8); // X符号有8个顶点
+            all_indices.extend(close_symbol_indices);
+            vertex_offset += 8;
+            
+            // 最小化按钮背景索引
+            let minimize_bg_indices = self.create_button_indices(segments);
+            for &index in &minimize_bg_indices {
+                all_indices.push(index + vertex_offset);
+            }
+            vertex_offset += (segments + 2) as u16;
+            
+            // 最小化按钮符号索引（一条线，4个顶点）
+            let minimize_symbol_indices = self.create_symbol_indices(vertex_offset, 4); // 减号有4个顶点
+            all_indices.extend(minimize_symbol_indices);
+            vertex_offset += 4;
         }
-        vertex_offset += (segments + 2) as u16;
-        
-        // 关闭按钮符号索引（两条线，每条线4个顶点）
-        let close_symbol_indices = self.create_symbol_indices(vertex_offset, 8); // X符号有8个顶点
-        all_indices.extend(close_symbol_indices);
-        vertex_offset += 8;
         
-        // 最小化按钮背景索引
-        let minimize_bg_indices = self.create_button_indices(segments);
-        for &index in &minimize_bg_indices {
-            all_indices.push(index + vertex_offset);
+        // 录制指示：左上角带深色描边的红点
+        if self.recording_indicator {
+            let margin = 8.0;
+            let outline_size = 18.0;
+            let center = margin + outline_size / 2.0;
+            all_vertices.extend(self.create_button_geometry(center, center, outline_size, [0.0, 0.0, 0.0, 0.5]));
+            all_vertices.extend(self.create_button_geometry(center, center, 12.0, [0.95, 0.15, 0.15, 1.0]));
+            for _ in 0..2 {
+                all_indices.extend(self.create_button_indices(segments).into_iter().map(|index| index + vertex_offset));
+                vertex_offset += (segments + 2) as u16;
+            }
         }
-        vertex_offset += (segments + 2) as u16;
-        
-        // 最小化按钮符号索引（一条线，4个顶点）
-        let minimize_symbol_indices = self.create_symbol_indices(vertex_offset, 4); // 减号有4个顶点
-        all_indices.extend(minimize_symbol_indices);
         
         // 创建GPU缓冲区
         let vertex_buffer = self.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
@@ -1072,6 +2120,8 @@ impl RenderEngine {
         
         let video_view = video_texture.create_view(&wgpu::TextureViewDescriptor::default());
         let mask_view = mask_texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let lut_view = self.lut_texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let overlay_view = self.overlay_texture.create_view(&wgpu::TextureViewDescriptor::default());
 
         self.video_bind_group = Some(self.device.create_bind_group(&wgpu::BindGroupDescriptor {
             layout: &self.bind_group_layout,
@@ -1088,6 +2138,30 @@ impl RenderEngine {
                     binding: 2,
                     resource: wgpu::BindingResource::Sampler(&self.sampler),
                 },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: wgpu::BindingResource::TextureView(&lut_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 4,
+                    resource: wgpu::BindingResource::Sampler(&self.lut_sampler),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 5,
+                    resource: wgpu::BindingResource::TextureView(&overlay_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 6,
+                    resource: wgpu::BindingResource::TextureView(
+                        self.sticker_pipeline.below_view().unwrap_or(&self.sticker_placeholder),
+                    ),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 7,
+                    resource: wgpu::BindingResource::TextureView(
+                        self.sticker_pipeline.above_view().unwrap_or(&self.sticker_placeholder),
+                    ),
+                },
             ],
             label: Some("video_bind_group"),
         }));
@@ -1151,16 +2225,88 @@ impl RenderEngine {
             push_constant_ranges: &[],
         });
         
-        // 加载 UI 着色器
-        let ui_shader = self.device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        // 加载 UI 着色器（开发模式下从磁盘读取并监视变化）
+        let ui_shader_source = match &mut self.shader_watcher {
+            Some(watcher) => watcher.watch(UI_SHADER_FILE, UI_SHADER),
+            None => UI_SHADER.to_string(),
+        };
+        let ui_pipeline = Self::create_ui_pipeline(&self.device, &ui_pipeline_layout, self.surface_config.format, &ui_shader_source);
+        
+        // 存储 UI 组件
+        self.ui_pipeline = Some(ui_pipeline);
+        self.ui_uniform_buffer = Some(ui_uniform_buffer);
+        self.ui_uniform_bind_group = Some(ui_uniform_bind_group);
+        self.ui_bind_group_layout = Some(ui_bind_group_layout);
+        self.ui_pipeline_layout = Some(ui_pipeline_layout);
+        
+        info!("UI 渲染管线初始化完成");
+        Ok(())
+    }
+
+    /// 创建主渲染管线
+    fn create_main_pipeline(
+        device: &wgpu::Device,
+        layout: &wgpu::PipelineLayout,
+        format: wgpu::TextureFormat,
+        source: &str,
+    ) -> wgpu::RenderPipeline {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Shader"),
+            source: wgpu::ShaderSource::Wgsl(source.into()),
+        });
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Render Pipeline"),
+            layout: Some(layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[Vertex::desc()],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    // 主视频是清屏后的第一次绘制，直接写入着色器按合成透明度模式编码的结果
+                    blend: None,
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face: wgpu::FrontFace::Ccw,
+                cull_mode: Some(wgpu::Face::Back),
+                polygon_mode: wgpu::PolygonMode::Fill,
+                unclipped_depth: false,
+                conservative: false,
+            },
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState {
+                count: 1,
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+            multiview: None,
+        })
+    }
+
+    /// 创建 UI 按钮渲染管线
+    fn create_ui_pipeline(
+        device: &wgpu::Device,
+        layout: &wgpu::PipelineLayout,
+        format: wgpu::TextureFormat,
+        source: &str,
+    ) -> wgpu::RenderPipeline {
+        let ui_shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
             label: Some("UI Shader"),
-            source: wgpu::ShaderSource::Wgsl(include_str!("ui_shader.wgsl").into()),
+            source: wgpu::ShaderSource::Wgsl(source.into()),
         });
-        
-        // 创建 UI 渲染管线
-        let ui_pipeline = self.device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
             label: Some("UI Render Pipeline"),
-            layout: Some(&ui_pipeline_layout),
+            layout: Some(layout),
             vertex: wgpu::VertexState {
                 module: &ui_shader,
                 entry_point: "ui_vs_main",
@@ -1171,7 +2317,7 @@ impl RenderEngine {
                 module: &ui_shader,
                 entry_point: "ui_circle_fs_main", // 使用圆形按钮着色器
                 targets: &[Some(wgpu::ColorTargetState {
-                    format: self.surface_config.format,
+                    format,
                     blend: Some(wgpu::BlendState::ALPHA_BLENDING), // 支持透明度混合
                     write_mask: wgpu::ColorWrites::ALL,
                 })],
@@ -1193,16 +2339,40 @@ impl RenderEngine {
                 alpha_to_coverage_enabled: false,
             },
             multiview: None,
-        });
-        
-        // 存储 UI 组件
-        self.ui_pipeline = Some(ui_pipeline);
-        self.ui_uniform_buffer = Some(ui_uniform_buffer);
-        self.ui_uniform_bind_group = Some(ui_uniform_bind_group);
-        self.ui_bind_group_layout = Some(ui_bind_group_layout);
-        
-        info!("UI 渲染管线初始化完成");
-        Ok(())
+        })
+    }
+
+    /// 热重载：重新编译磁盘上修改过的着色器，创建管线失败时保留原管线
+    fn reload_shaders(&mut self) {
+        let Some(watcher) = &mut self.shader_watcher else {
+            return;
+        };
+        for (file, source) in watcher.poll(std::time::Instant::now()) {
+            self.device.push_error_scope(wgpu::ErrorFilter::Validation);
+            let pipeline = match file {
+                MAIN_SHADER_FILE => Some(Self::create_main_pipeline(
+                    &self.device,
+                    &self.pipeline_layout,
+                    self.surface_config.format,
+                    &source,
+                )),
+                UI_SHADER_FILE => self.ui_pipeline_layout.as_ref().map(|layout| {
+                    Self::create_ui_pipeline(&self.device, layout, self.surface_config.format, &source)
+                }),
+                _ => None,
+            };
+            if let Some(e) = poll_error_scope(self.device.pop_error_scope()) {
+                error!("{} 创建管线失败，继续使用上一个版本: {}", file, e);
+                continue;
+            }
+
+            match (file, pipeline) {
+                (MAIN_SHADER_FILE, Some(pipeline)) => self.pipeline = pipeline,
+                (UI_SHADER_FILE, Some(pipeline)) => self.ui_pipeline = Some(pipeline),
+                _ => continue,
+            }
+            info!("已重新加载着色器: {}", file);
+        }
     }
 
     /// 创建按钮几何体（圆形）
@@ -1372,6 +2542,7 @@ impl RenderEngine {
         // 检查是否在圆形按钮内
         distance <= radius
     }
+    /// 在 CPU 上转换为 RGBA8（适配器不支持计算着色器时的回退路径）
     fn convert_frame_to_rgba(&self, frame: &Frame) -> Result<Vec<u8>, RenderError> {
         debug!("转换帧格式: {:?} -> RGBA8", frame.format);
         
@@ -1445,49 +2616,6 @@ impl RenderEngine {
         }
     }
     
-    /// 渲染上下文菜单
-    pub fn render_context_menu(
-        &mut self,
-        menu_renderer: &mut crate::ui::MenuRenderer,
-        context_menu: &crate::ui::ContextMenu,
-        screen_size: [f32; 2],
-    ) -> Result<(), RenderError> {
-        debug!("开始渲染上下文菜单");
-        
-        // 获取表面纹理
-        let output = self.surface.get_current_texture()
-            .map_err(|e| {
-                error!("获取表面纹理失败: {}", e);
-                RenderError::RenderFailed(format!("获取表面纹理失败: {}", e))
-            })?;
-        
-        let view = output.texture.create_view(&wgpu::TextureViewDescriptor::default());
-        
-        // 创建命令编码器
-        let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
-            label: Some("Context Menu Render Encoder"),
-        });
-        
-        // 渲染菜单（在现有内容之上）
-        match menu_renderer.render_menu(&mut encoder, &view, context_menu, screen_size) {
-            Ok(()) => {
-                debug!("上下文菜单渲染成功");
-                
-                // 提交命令
-                self.queue.submit(std::iter::once(encoder.finish()));
-                
-                // 呈现到屏幕
-                output.present();
-                
-                Ok(())
-            }
-            Err(e) => {
-                error!("上下文菜单渲染失败: {}", e);
-                Err(RenderError::UIRenderFailed(format!("上下文菜单渲染失败: {}", e)))
-            }
-        }
-    }
-    
     /// 清理未使用的 GPU 资源
     pub fn cleanup_resources(&mut self) {
         debug!("开始清理 GPU 资源");
@@ -1511,6 +2639,7 @@ impl RenderEngine {
             texture_manager: texture_stats,
             video_texture_allocated: self.video_texture.is_some(),
             mask_texture_allocated: self.mask_texture.is_some(),
+            mask_texture_cache: self.mask_texture_cache.get_stats(),
         }
     }
     
@@ -1537,6 +2666,15 @@ impl RenderEngine {
         self.video_texture = None;
         self.mask_texture = None;
         self.video_bind_group = None;
+        self.mask_texture_cache.clear();
+        self.denoise.release_targets();
+        self.effects.release_targets();
+        if let Some(uploader) = &mut self.frame_uploader {
+            uploader.release_buffers();
+        }
+        self.overlay_texture = Self::create_overlay_texture(&self.device, &self.queue, &OverlayCanvas::new(0, 0));
+        self.overlay.invalidate();
+        self.sticker_pipeline.release();
         
         // 重新创建纹理管理器以清除所有缓存
         self.texture_manager = TextureManager::new(
@@ -1587,6 +2725,63 @@ mod tests {
         assert_eq!(uniforms.transform[3], [0.0, 0.0, 0.0, 1.0]);
     }
 
+    #[test]
+    fn test_window_to_mask_coords() {
+        let size = PhysicalSize::new(400, 200);
+        let near = |a: (f32, f32), b: (f32, f32)| (a.0 - b.0).abs() < 1e-4 && (a.1 - b.1).abs() < 1e-4;
+
+        let point = window_to_mask_coords(PhysicalPosition::new(300.0, 50.0), size, 0.0).unwrap();
+        assert!(near(point, (0.75, 0.25)));
+
+        // 遮罩旋转 90° 后，窗口中心右侧的点对应遮罩上方
+        let rotated = window_to_mask_coords(PhysicalPosition::new(300.0, 100.0), size, std::f32::consts::FRAC_PI_2).unwrap();
+        assert!(near(rotated, (0.5, 0.25)));
+
+        assert!(window_to_mask_coords(PhysicalPosition::new(0.0, 0.0), size, std::f32::consts::FRAC_PI_4).is_none());
+    }
+
+    #[test]
+    fn test_window_to_video_coords() {
+        let size = PhysicalSize::new(400, 400);
+        let near = |a: (f32, f32), b: (f32, f32)| (a.0 - b.0).abs() < 1e-4 && (a.1 - b.1).abs() < 1e-4;
+
+        // 无旋转时直接对应
+        let center = window_to_video_coords(PhysicalPosition::new(200.0, 200.0), size, 0.0, 0.0).unwrap();
+        assert!(near(center, (0.5, 0.5)));
+        let corner = window_to_video_coords(PhysicalPosition::new(0.0, 0.0), size, 0.0, 0.0).unwrap();
+        assert!(near(corner, (0.0, 0.0)));
+        let point = window_to_video_coords(PhysicalPosition::new(300.0, 100.0), size, 0.0, 0.0).unwrap();
+        assert!(near(point, (0.75, 0.25)));
+
+        // 遮罩与视频同步旋转时，视频坐标与遮罩纹理坐标一致（四边形内部的点）
+        let angle = std::f32::consts::FRAC_PI_2;
+        let rotated = window_to_video_coords(PhysicalPosition::new(300.0, 200.0), size, angle, angle).unwrap();
+        let (sin_a, cos_a) = angle.sin_cos();
+        let (clip_x, clip_y) = (0.5f32, 0.0f32);
+        let expected = ((cos_a * clip_x - sin_a * clip_y + 1.0) * 0.5, (1.0 - (sin_a * clip_x + cos_a * clip_y)) * 0.5);
+        assert!(near(rotated, expected));
+
+        // 视频保持正向时，屏幕上的点直接对应视频坐标
+        let upright = window_to_video_coords(PhysicalPosition::new(300.0, 100.0), size, 0.7, 0.0).unwrap();
+        assert!(near(upright, (0.75, 0.25)));
+
+        // 旋转 45° 后窗口角落位于四边形之外
+        let quarter = std::f32::consts::FRAC_PI_4;
+        assert!(window_to_video_coords(PhysicalPosition::new(0.0, 0.0), size, quarter, 0.0).is_none());
+        assert!(window_to_video_coords(PhysicalPosition::new(0.0, 0.0), PhysicalSize::new(0, 0), 0.0, 0.0).is_none());
+    }
+
+    #[test]
+    fn test_uniforms_color_defaults() {
+        let uniforms = Uniforms::new();
+        assert_eq!(uniforms.color, ColorUniforms::default());
+        assert_eq!(uniforms.chroma_key, ChromaKeyUniforms::default());
+        assert_eq!(uniforms.auto_correct, AutoCorrectUniforms::default());
+        assert_eq!(uniforms.output, OutputUniforms::default());
+        // WGSL 统一缓冲区按 16 字节对齐
+        assert_eq!(std::mem::size_of::<Uniforms>() % 16, 0);
+    }
+
     #[test]
     fn test_uniforms_rotation() {
         let mut uniforms = Uniforms::new();
@@ -1602,6 +2797,23 @@ mod tests {
         assert!((uniforms.transform[1][1] - cos_90).abs() < 1e-6);
     }
 
+    #[test]
+    fn test_video_rotation_uniform() {
+        let mut uniforms = Uniforms::new();
+        assert_eq!(uniforms.video_rotation, [1.0, 0.0, 0.0, 0.0]);
+        
+        // 视频与遮罩同角度时纹理坐标不需要额外旋转
+        uniforms.update_video_rotation(0.5, 0.5);
+        assert!((uniforms.video_rotation[0] - 1.0).abs() < 1e-6);
+        assert!(uniforms.video_rotation[1].abs() < 1e-6);
+        
+        // 保持正向时抵消遮罩的旋转
+        let mask_rotation = std::f32::consts::PI / 2.0;
+        uniforms.update_video_rotation(mask_rotation, 0.0);
+        assert!(uniforms.video_rotation[0].abs() < 1e-6);
+        assert!((uniforms.video_rotation[1] - 1.0).abs() < 1e-6);
+    }
+
     #[test]
     fn test_pixel_format_variants() {
         // 测试所有像素格式变体
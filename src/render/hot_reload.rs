@@ -0,0 +1,192 @@
+// 着色器热重载（开发模式）
+//
+// 设置环境变量 MIRA_SHADER_DIR 后，渲染引擎从该目录读取 shader.wgsl 和 ui_shader.wgsl，
+// 并定期检查修改时间，文件变化时重新编译对应的管线。取值为 1 时使用源码目录 src/render。
+// 新着色器先经 naga 验证，失败时记录带行号的错误并继续使用上一个可用的管线。
+
+use log::{debug, error, info, warn};
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::task::{Context, Poll, Waker};
+use std::time::{Duration, Instant, SystemTime};
+
+/// 开启热重载并指定着色器目录的环境变量
+pub const SHADER_DIR_ENV: &str = "MIRA_SHADER_DIR";
+
+/// 两次检查文件修改时间的最小间隔
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// 用 naga 解析并验证 WGSL，错误信息包含出错的行号和源码片段
+pub fn validate_wgsl(source: &str) -> Result<naga::Module, String> {
+    let module = naga::front::wgsl::parse_str(source).map_err(|e| e.emit_to_string(source))?;
+    naga::valid::Validator::new(naga::valid::ValidationFlags::all(), naga::valid::Capabilities::empty())
+        .validate(&module)
+        .map_err(|e| e.emit_to_string(source))?;
+    Ok(module)
+}
+
+/// wgpu 原生后端的异步调用（错误作用域、请求适配器和设备）立即完成，直接轮询一次取得结果
+pub(crate) fn poll_ready<F: Future>(future: F) -> Option<F::Output> {
+    let mut future = std::pin::pin!(future);
+    match future.as_mut().poll(&mut Context::from_waker(Waker::noop())) {
+        Poll::Ready(output) => Some(output),
+        Poll::Pending => None,
+    }
+}
+
+/// 轮询错误作用域，未完成时视为没有错误
+pub(crate) fn poll_error_scope(future: impl Future<Output = Option<wgpu::Error>>) -> Option<wgpu::Error> {
+    poll_ready(future).flatten()
+}
+
+/// 被监视的着色器文件
+struct WatchedShader {
+    file: &'static str,
+    modified: Option<SystemTime>,
+}
+
+/// 监视着色器目录中的文件变化
+pub struct ShaderWatcher {
+    directory: PathBuf,
+    shaders: Vec<WatchedShader>,
+    last_poll: Option<Instant>,
+}
+
+impl ShaderWatcher {
+    /// 根据环境变量创建，未设置时返回 None（使用编译进程序的着色器）
+    pub fn from_env() -> Option<Self> {
+        let value = std::env::var(SHADER_DIR_ENV).ok()?;
+        let directory = match value.trim() {
+            "" | "0" => return None,
+            "1" => Path::new(env!("CARGO_MANIFEST_DIR")).join("src").join("render"),
+            path => PathBuf::from(path),
+        };
+        info!("着色器热重载已开启，目录: {}", directory.display());
+        Some(Self::new(directory))
+    }
+
+    pub fn new(directory: PathBuf) -> Self {
+        Self {
+            directory,
+            shaders: Vec::new(),
+            last_poll: None,
+        }
+    }
+
+    /// 着色器目录
+    pub fn directory(&self) -> &Path {
+        &self.directory
+    }
+
+    /// 开始监视文件并返回当前内容；文件缺失或验证失败时返回内置版本
+    pub fn watch(&mut self, file: &'static str, builtin: &str) -> String {
+        let path = self.directory.join(file);
+        let modified = modified_time(&path);
+        if let Some(watched) = self.shaders.iter_mut().find(|watched| watched.file == file) {
+            watched.modified = modified;
+        } else {
+            self.shaders.push(WatchedShader { file, modified });
+        }
+
+        match read_shader(&path) {
+            Ok(source) => source,
+            Err(message) => {
+                error!("{}，使用内置着色器:\n{}", path.display(), message);
+                builtin.to_string()
+            }
+        }
+    }
+
+    /// 检查文件变化，返回修改过且验证通过的着色器（文件名, 源码）
+    pub fn poll(&mut self, now: Instant) -> Vec<(&'static str, String)> {
+        if self.last_poll.is_some_and(|last| now.saturating_duration_since(last) < POLL_INTERVAL) {
+            return Vec::new();
+        }
+        self.last_poll = Some(now);
+
+        let mut changed = Vec::new();
+        for watched in &mut self.shaders {
+            let path = self.directory.join(watched.file);
+            let modified = modified_time(&path);
+            if modified.is_none() || modified == watched.modified {
+                continue;
+            }
+            watched.modified = modified;
+
+            debug!("着色器文件已修改: {}", path.display());
+            match read_shader(&path) {
+                Ok(source) => changed.push((watched.file, source)),
+                Err(message) => error!("{} 编译失败，继续使用上一个版本:\n{}", path.display(), message),
+            }
+        }
+        changed
+    }
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+}
+
+/// 读取并验证着色器文件
+fn read_shader(path: &Path) -> Result<String, String> {
+    let source = std::fs::read_to_string(path).map_err(|e| {
+        warn!("读取着色器失败: {}: {}", path.display(), e);
+        e.to_string()
+    })?;
+    validate_wgsl(&source)?;
+    Ok(source)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VALID: &str = "@fragment fn fs_main() -> @location(0) vec4<f32> { return vec4<f32>(1.0); }";
+    const INVALID: &str = "@fragment fn fs_main() -> @location(0) vec4<f32> {\n    return vec3<f32>(1.0);\n}";
+
+    #[test]
+    fn test_poll_ready() {
+        assert_eq!(poll_ready(async { 1 }), Some(1));
+        assert_eq!(poll_ready(std::future::pending::<()>()), None);
+        assert!(poll_error_scope(std::future::pending()).is_none());
+    }
+
+    #[test]
+    fn test_validate_wgsl_reports_line() {
+        assert!(validate_wgsl(VALID).is_ok());
+        let message = validate_wgsl(INVALID).unwrap_err();
+        assert!(message.contains(":2:"), "{}", message);
+    }
+
+    #[test]
+    fn test_watcher_detects_changes() {
+        let directory = std::env::temp_dir().join(format!("mira_hot_reload_test_{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let path = directory.join("shader.wgsl");
+        std::fs::write(&path, VALID).unwrap();
+
+        let mut watcher = ShaderWatcher::new(directory.clone());
+        assert_eq!(watcher.watch("shader.wgsl", "builtin"), VALID);
+        assert_eq!(watcher.watch("missing.wgsl", "builtin"), "builtin");
+
+        let start = Instant::now();
+        assert!(watcher.poll(start).is_empty());
+
+        // 文件修改后在下一次检查时返回新内容，间隔内不重复检查
+        let touch = |source: &str, seconds: u64| {
+            std::fs::write(&path, source).unwrap();
+            let file = std::fs::File::options().write(true).open(&path).unwrap();
+            file.set_modified(SystemTime::UNIX_EPOCH + Duration::from_secs(seconds)).unwrap();
+        };
+        touch(VALID, 1000);
+        assert!(watcher.poll(start + Duration::from_millis(100)).is_empty());
+        assert_eq!(watcher.poll(start + POLL_INTERVAL), vec![("shader.wgsl", VALID.to_string())]);
+        assert!(watcher.poll(start + POLL_INTERVAL * 2).is_empty());
+
+        // 编译失败的版本不返回
+        touch(INVALID, 2000);
+        assert!(watcher.poll(start + POLL_INTERVAL * 3).is_empty());
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+}
@@ -1,6 +1,19 @@
 // 渲染引擎模块
 
+pub mod adapter;
+pub mod alpha;
+pub mod auto_correct;
+pub mod capture;
+pub mod chroma;
+pub mod color;
+pub mod denoise;
+pub mod effects;
 pub mod engine;
+pub mod hot_reload;
+pub mod profiler;
+pub mod reference;
+pub mod sticker;
+pub mod upload;
 
 #[cfg(test)]
 mod ui_tests;
@@ -0,0 +1,290 @@
+// GPU 计时
+//
+// 主循环记录的渲染时间是墙钟时间，包含获取交换链纹理、上传等 CPU 等待。适配器支持时间戳查询时，
+// 在命令编码器中于各阶段前后写入时间戳，得到特效、视频、控件和菜单各阶段在 GPU 上的实际耗时；
+// CPU 编码命令的耗时由 RenderEngine 单独统计。
+//
+// 查询结果通过回读缓冲区异步读取，通常晚一到两帧可用；回读缓冲区都在使用中时本帧不计时。
+// 适配器不支持在编码器中写入时间戳（TIMESTAMP_QUERY_INSIDE_ENCODERS）时不创建计时器，只统计 CPU 时间。
+
+use log::{debug, warn};
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// 计时需要的设备特性，适配器支持时在创建设备时启用
+pub const PROFILER_FEATURES: wgpu::Features =
+    wgpu::Features::TIMESTAMP_QUERY.union(wgpu::Features::TIMESTAMP_QUERY_INSIDE_ENCODERS);
+
+/// 同时等待回读的帧数
+const READBACK_SLOTS: usize = 3;
+
+/// 每个阶段的开始和结束时间戳
+const QUERY_COUNT: u32 = RenderPass::ALL.len() as u32 * 2;
+
+/// 时间戳结果的字节数
+const TIMESTAMP_SIZE: u64 = std::mem::size_of::<u64>() as u64;
+
+// 回读缓冲区状态
+const SLOT_IDLE: u8 = 0;
+const SLOT_PENDING: u8 = 1;
+const SLOT_MAPPED: u8 = 2;
+const SLOT_FAILED: u8 = 3;
+
+/// 单独计时的渲染阶段
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RenderPass {
+    /// 降噪、特效链和贴纸层等预处理
+    Effects,
+    /// 主视频
+    Video,
+    /// 窗口控件
+    Controls,
+    /// 上下文菜单
+    Menu,
+}
+
+impl RenderPass {
+    pub const ALL: [RenderPass; 4] = [RenderPass::Effects, RenderPass::Video, RenderPass::Controls, RenderPass::Menu];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            RenderPass::Effects => "特效",
+            RenderPass::Video => "视频",
+            RenderPass::Controls => "控件",
+            RenderPass::Menu => "菜单",
+        }
+    }
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// 一帧的渲染计时
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct FrameTimings {
+    /// CPU 编码命令的耗时（不含获取交换链纹理之后的等待和呈现）
+    pub cpu_encode: Duration,
+    /// 各阶段的 GPU 耗时，按 RenderPass::ALL 顺序；不支持时间戳查询或阶段未执行时为 None
+    pub gpu_passes: [Option<Duration>; 4],
+}
+
+impl FrameTimings {
+    /// 指定阶段的 GPU 耗时
+    pub fn gpu_pass(&self, pass: RenderPass) -> Option<Duration> {
+        self.gpu_passes[pass.index()]
+    }
+
+    /// 已计时阶段的 GPU 耗时之和，没有任何阶段计时时为 None
+    pub fn gpu_total(&self) -> Option<Duration> {
+        self.gpu_passes.iter().flatten().copied().reduce(|a, b| a + b)
+    }
+}
+
+/// 由时间戳计算各阶段耗时；结束早于开始（计数器回绕或驱动异常）的阶段丢弃
+pub fn pass_durations(timestamps: &[u64], written: [bool; 4], period_ns: f32) -> [Option<Duration>; 4] {
+    std::array::from_fn(|i| {
+        if !written[i] {
+            return None;
+        }
+        let begin = *timestamps.get(i * 2)?;
+        let end = *timestamps.get(i * 2 + 1)?;
+        let ticks = end.checked_sub(begin)?;
+        Some(Duration::from_nanos((ticks as f64 * period_ns as f64) as u64))
+    })
+}
+
+/// 一帧时间戳的回读缓冲区
+struct Readback {
+    buffer: wgpu::Buffer,
+    state: Arc<AtomicU8>,
+    // 该帧写入了时间戳的阶段
+    written: [bool; 4],
+    // 帧序号，同时有多帧完成时取最新的一帧
+    frame: u64,
+}
+
+/// GPU 时间戳计时器
+pub struct GpuProfiler {
+    query_set: wgpu::QuerySet,
+    resolve_buffer: wgpu::Buffer,
+    readbacks: Vec<Readback>,
+    // 每个时间戳刻度对应的纳秒数
+    period_ns: f32,
+    // 本帧使用的回读缓冲区，没有空闲缓冲区时为 None
+    current: Option<usize>,
+    written: [bool; 4],
+    frame: u64,
+}
+
+impl GpuProfiler {
+    /// 设备启用了计时特性时创建计时器，否则返回 None
+    pub fn new(device: &wgpu::Device, queue: &wgpu::Queue) -> Option<Self> {
+        if !device.features().contains(PROFILER_FEATURES) {
+            debug!("适配器不支持编码器时间戳查询，GPU 计时不可用");
+            return None;
+        }
+
+        let query_set = device.create_query_set(&wgpu::QuerySetDescriptor {
+            label: Some("Profiler Query Set"),
+            ty: wgpu::QueryType::Timestamp,
+            count: QUERY_COUNT,
+        });
+        // 每个阶段单独解析，解析目标偏移需按 QUERY_RESOLVE_BUFFER_ALIGNMENT 对齐
+        let resolve_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Profiler Resolve Buffer"),
+            size: RenderPass::ALL.len() as u64 * wgpu::QUERY_RESOLVE_BUFFER_ALIGNMENT,
+            usage: wgpu::BufferUsages::QUERY_RESOLVE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+        let readbacks = (0..READBACK_SLOTS)
+            .map(|_| Readback {
+                buffer: device.create_buffer(&wgpu::BufferDescriptor {
+                    label: Some("Profiler Readback Buffer"),
+                    size: QUERY_COUNT as u64 * TIMESTAMP_SIZE,
+                    usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+                    mapped_at_creation: false,
+                }),
+                state: Arc::new(AtomicU8::new(SLOT_IDLE)),
+                written: [false; 4],
+                frame: 0,
+            })
+            .collect();
+
+        Some(Self {
+            query_set,
+            resolve_buffer,
+            readbacks,
+            period_ns: queue.get_timestamp_period(),
+            current: None,
+            written: [false; 4],
+            frame: 0,
+        })
+    }
+
+    /// 开始一帧：选择空闲的回读缓冲区
+    pub fn begin_frame(&mut self) {
+        self.frame += 1;
+        self.written = [false; 4];
+        self.current = self
+            .readbacks
+            .iter()
+            .position(|readback| readback.state.load(Ordering::Acquire) == SLOT_IDLE);
+        if self.current.is_none() {
+            debug!("GPU 计时回读缓冲区都在使用中，本帧不计时");
+        }
+    }
+
+    /// 在阶段开始前写入时间戳
+    pub fn begin_pass(&mut self, encoder: &mut wgpu::CommandEncoder, pass: RenderPass) {
+        if self.current.is_some() {
+            encoder.write_timestamp(&self.query_set, pass.index() as u32 * 2);
+        }
+    }
+
+    /// 在阶段结束后写入时间戳
+    pub fn end_pass(&mut self, encoder: &mut wgpu::CommandEncoder, pass: RenderPass) {
+        if self.current.is_some() {
+            encoder.write_timestamp(&self.query_set, pass.index() as u32 * 2 + 1);
+            self.written[pass.index()] = true;
+        }
+    }
+
+    /// 在编码器结束前解析本帧写入的时间戳并复制到回读缓冲区
+    pub fn resolve(&mut self, encoder: &mut wgpu::CommandEncoder) {
+        let Some(current) = self.current else {
+            return;
+        };
+        // 只解析写入过的查询，未写入的查询在部分后端上解析结果未定义
+        for pass in RenderPass::ALL.into_iter().filter(|pass| self.written[pass.index()]) {
+            let first = pass.index() as u32 * 2;
+            let resolve_offset = pass.index() as u64 * wgpu::QUERY_RESOLVE_BUFFER_ALIGNMENT;
+            encoder.resolve_query_set(&self.query_set, first..first + 2, &self.resolve_buffer, resolve_offset);
+            encoder.copy_buffer_to_buffer(
+                &self.resolve_buffer,
+                resolve_offset,
+                &self.readbacks[current].buffer,
+                first as u64 * TIMESTAMP_SIZE,
+                2 * TIMESTAMP_SIZE,
+            );
+        }
+    }
+
+    /// 提交后请求映射本帧的回读缓冲区
+    pub fn after_submit(&mut self) {
+        let Some(current) = self.current.take() else {
+            return;
+        };
+        let readback = &mut self.readbacks[current];
+        if !self.written.iter().any(|&written| written) {
+            return;
+        }
+        readback.written = self.written;
+        readback.frame = self.frame;
+        readback.state.store(SLOT_PENDING, Ordering::Release);
+        let state = Arc::clone(&readback.state);
+        readback.buffer.slice(..).map_async(wgpu::MapMode::Read, move |result| {
+            let next = if result.is_ok() { SLOT_MAPPED } else { SLOT_FAILED };
+            state.store(next, Ordering::Release);
+        });
+    }
+
+    /// 读取已完成的计时结果，返回最新一帧的各阶段耗时
+    pub fn poll(&mut self, device: &wgpu::Device) -> Option<[Option<Duration>; 4]> {
+        device.poll(wgpu::Maintain::Poll);
+
+        let mut latest: Option<(u64, [Option<Duration>; 4])> = None;
+        for readback in &mut self.readbacks {
+            match readback.state.load(Ordering::Acquire) {
+                SLOT_MAPPED => {
+                    if latest.is_none_or(|(frame, _)| readback.frame > frame) {
+                        let data = readback.buffer.slice(..).get_mapped_range();
+                        let timestamps: &[u64] = bytemuck::cast_slice(&data);
+                        latest = Some((readback.frame, pass_durations(timestamps, readback.written, self.period_ns)));
+                    }
+                    readback.buffer.unmap();
+                    readback.state.store(SLOT_IDLE, Ordering::Release);
+                }
+                SLOT_FAILED => {
+                    warn!("读取 GPU 计时结果失败");
+                    readback.state.store(SLOT_IDLE, Ordering::Release);
+                }
+                _ => {}
+            }
+        }
+        latest.map(|(_, durations)| durations)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pass_durations() {
+        let timestamps = [100, 1100, 2000, 2500, 0, 0, 5000, 4000];
+        let durations = pass_durations(&timestamps, [true, true, false, true], 2.0);
+        assert_eq!(durations[0], Some(Duration::from_nanos(2000)));
+        assert_eq!(durations[1], Some(Duration::from_nanos(1000)));
+        // 未执行的阶段
+        assert_eq!(durations[2], None);
+        // 结束早于开始
+        assert_eq!(durations[3], None);
+
+        assert_eq!(pass_durations(&[], [true; 4], 1.0), [None; 4]);
+    }
+
+    #[test]
+    fn test_frame_timings() {
+        let mut timings = FrameTimings::default();
+        assert_eq!(timings.gpu_total(), None);
+
+        timings.gpu_passes[RenderPass::Video.index()] = Some(Duration::from_micros(800));
+        timings.gpu_passes[RenderPass::Menu.index()] = Some(Duration::from_micros(200));
+        assert_eq!(timings.gpu_pass(RenderPass::Video), Some(Duration::from_micros(800)));
+        assert_eq!(timings.gpu_pass(RenderPass::Effects), None);
+        assert_eq!(timings.gpu_total(), Some(Duration::from_millis(1)));
+        assert_eq!(RenderPass::ALL.map(RenderPass::index), [0, 1, 2, 3]);
+    }
+}
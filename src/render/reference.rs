@@ -0,0 +1,421 @@
+// CPU 参考合成器
+//
+// 按 shader.wgsl 的逻辑在 CPU 上合成一帧：遮罩旋转、视频独立旋转、绿幕抠像、自动校正、
+// 调色与 LUT、贴纸层和叠加层。不需要窗口和 GPU，用于无头环境下的金样图测试。
+//
+// 纹理采样模拟 GPU 的行为：sRGB 纹理先解码为线性值再过滤，放大时双线性插值、缩小时取最近纹素
+// （与主采样器的 mag_filter / min_filter 一致），边缘按 ClampToEdge 处理。
+// 输出为 sRGB 表面上的帧缓冲内容，按合成透明度模式编码（见 alpha.rs），默认为编码后预乘。
+
+use crate::error::RenderError;
+use crate::render::alpha::{apply_flash, encode_output, srgb_to_linear, OutputAlpha};
+use crate::render::auto_correct::Correction;
+use crate::render::chroma::ChromaKey;
+use crate::render::color::{ColorAdjustments, ColorPreset, ColorUniforms, CubeLut};
+use crate::render::engine::{Frame, PixelFormat};
+use crate::shape::ShapeMask;
+use image::{Rgba, RgbaImage};
+
+/// 参考合成参数，对应 RenderEngine 的渲染状态
+#[derive(Debug, Clone)]
+pub struct ReferenceParams<'a> {
+    /// 输出（窗口）宽度
+    pub width: u32,
+    /// 输出（窗口）高度
+    pub height: u32,
+    /// 遮罩旋转角度（弧度）
+    pub mask_rotation: f32,
+    /// 视频在屏幕上的旋转角度（弧度）
+    pub video_rotation: f32,
+    pub adjustments: ColorAdjustments,
+    pub preset: ColorPreset,
+    /// 已启用的 LUT
+    pub lut: Option<&'a CubeLut>,
+    pub chroma_key: ChromaKey,
+    /// 自动校正参数，None 表示未启用
+    pub auto_correct: Option<Correction>,
+    /// 叠加层（非预乘透明度，与遮罩纹理坐标对应）
+    pub overlay: Option<&'a RgbaImage>,
+    /// 遮罩之下的贴纸层（预乘透明度）
+    pub sticker_below: Option<&'a RgbaImage>,
+    /// 遮罩之上的贴纸层（预乘透明度，不受遮罩裁剪）
+    pub sticker_above: Option<&'a RgbaImage>,
+    /// 帧缓冲中颜色的透明度形式
+    pub output_alpha: OutputAlpha,
+    /// 快门闪光强度（0-1）
+    pub shutter_flash: f32,
+}
+
+impl<'a> ReferenceParams<'a> {
+    /// 指定输出尺寸，其余参数与 RenderEngine 的初始状态一致
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            mask_rotation: 0.0,
+            video_rotation: 0.0,
+            adjustments: ColorAdjustments::default(),
+            preset: ColorPreset::None,
+            lut: None,
+            chroma_key: ChromaKey::default(),
+            auto_correct: None,
+            overlay: None,
+            sticker_below: None,
+            sticker_above: None,
+            output_alpha: OutputAlpha::Premultiplied,
+            shutter_flash: 0.0,
+        }
+    }
+}
+
+/// 解码为线性浮点值的纹理
+struct Texture {
+    width: u32,
+    height: u32,
+    texels: Vec<[f32; 4]>,
+}
+
+impl Texture {
+    /// 由 RGBA8 数据创建，srgb 为 true 时颜色通道按 sRGB 解码（与 Rgba8UnormSrgb 一致）
+    fn from_rgba8(data: &[u8], width: u32, height: u32, srgb: bool) -> Self {
+        let decode = |value: u8| {
+            let value = value as f32 / 255.0;
+            if srgb {
+                srgb_to_linear(value)
+            } else {
+                value
+            }
+        };
+        let texels = data
+            .chunks_exact(4)
+            .map(|p| [decode(p[0]), decode(p[1]), decode(p[2]), p[3] as f32 / 255.0])
+            .collect();
+        Self { width, height, texels }
+    }
+
+    fn from_image(image: &RgbaImage) -> Self {
+        Self::from_rgba8(image.as_raw(), image.width(), image.height(), true)
+    }
+
+    /// 单通道遮罩（R8Unorm）
+    fn from_mask(mask: &ShapeMask) -> Self {
+        let texels = mask.data().iter().map(|&value| [value as f32 / 255.0, 0.0, 0.0, 1.0]).collect();
+        Self {
+            width: mask.width(),
+            height: mask.height(),
+            texels,
+        }
+    }
+
+    fn texel(&self, x: i64, y: i64) -> [f32; 4] {
+        let x = x.clamp(0, self.width as i64 - 1) as usize;
+        let y = y.clamp(0, self.height as i64 - 1) as usize;
+        self.texels[y * self.width as usize + x]
+    }
+
+    /// 每个输出像素对应的纹素跨度不超过 1 时为放大，使用双线性过滤（GPU 的 lod <= 0）
+    fn is_magnified(&self, dx: [f32; 2], dy: [f32; 2]) -> bool {
+        let length = |d: [f32; 2]| (d[0] * self.width as f32).hypot(d[1] * self.height as f32);
+        length(dx).max(length(dy)) <= 1.0 + 1e-4
+    }
+
+    fn sample(&self, uv: [f32; 2], linear: bool) -> [f32; 4] {
+        if self.width == 0 || self.height == 0 {
+            return [0.0; 4];
+        }
+        let x = uv[0] * self.width as f32;
+        let y = uv[1] * self.height as f32;
+        if !linear {
+            return self.texel(x.floor() as i64, y.floor() as i64);
+        }
+
+        let (x, y) = (x - 0.5, y - 0.5);
+        let (x0, y0) = (x.floor(), y.floor());
+        let (fx, fy) = (x - x0, y - y0);
+        let (x0, y0) = (x0 as i64, y0 as i64);
+        let top = lerp4(self.texel(x0, y0), self.texel(x0 + 1, y0), fx);
+        let bottom = lerp4(self.texel(x0, y0 + 1), self.texel(x0 + 1, y0 + 1), fx);
+        lerp4(top, bottom, fy)
+    }
+}
+
+fn lerp4(a: [f32; 4], b: [f32; 4], t: f32) -> [f32; 4] {
+    std::array::from_fn(|i| a[i] + (b[i] - a[i]) * t)
+}
+
+/// 预乘透明度的 "over" 合成
+fn over(top: [f32; 4], bottom: [f32; 4]) -> [f32; 4] {
+    std::array::from_fn(|i| top[i] + bottom[i] * (1.0 - top[3]))
+}
+
+/// 与 RenderEngine 上传视频帧时相同的格式转换
+fn frame_to_rgba(frame: &Frame) -> Result<Vec<u8>, RenderError> {
+    let pixels = frame.width as usize * frame.height as usize;
+    match frame.format {
+        PixelFormat::RGB8 if frame.data.len() == pixels * 3 => Ok(frame
+            .data
+            .chunks_exact(3)
+            .flat_map(|rgb| [rgb[0], rgb[1], rgb[2], 255])
+            .collect()),
+        PixelFormat::RGBA8 if frame.data.len() == pixels * 4 => Ok(frame.data.clone()),
+        _ => Err(RenderError::TextureUploadFailed),
+    }
+}
+
+/// 按着色器逻辑合成一帧，返回 sRGB 编码、按 output_alpha 编码透明度的 RGBA 图像
+pub fn composite(frame: &Frame, mask: &ShapeMask, params: &ReferenceParams) -> Result<RgbaImage, RenderError> {
+    if frame.width == 0 || frame.height == 0 || mask.width() == 0 || mask.height() == 0 {
+        return Err(RenderError::RenderFailed("参考合成的输入尺寸为 0".to_string()));
+    }
+    let video = Texture::from_rgba8(&frame_to_rgba(frame)?, frame.width, frame.height, true);
+    let mask_texture = Texture::from_mask(mask);
+    let overlay = params.overlay.map(Texture::from_image);
+    let sticker_below = params.sticker_below.map(Texture::from_image);
+    let sticker_above = params.sticker_above.map(Texture::from_image);
+    let color = ColorUniforms::new(&params.adjustments, params.preset, params.lut);
+
+    let (width, height) = (params.width as f32, params.height as f32);
+    let (sin_m, cos_m) = params.mask_rotation.sin_cos();
+    let (sin_v, cos_v) = (params.mask_rotation - params.video_rotation).sin_cos();
+
+    // 窗口坐标 -> 四边形局部坐标（撤销顶点着色器的旋转）
+    let to_local = |x: f32, y: f32| -> [f32; 2] {
+        let clip_x = 2.0 * x / width - 1.0;
+        let clip_y = 1.0 - 2.0 * y / height;
+        [cos_m * clip_x - sin_m * clip_y, sin_m * clip_x + cos_m * clip_y]
+    };
+    let to_mask = |local: [f32; 2]| [(local[0] + 1.0) * 0.5, (1.0 - local[1]) * 0.5];
+    // 与 rotate_video_coords 相同
+    let to_video = |uv: [f32; 2]| -> [f32; 2] {
+        let p = [uv[0] - 0.5, 0.5 - uv[1]];
+        let r = [cos_v * p[0] + sin_v * p[1], -sin_v * p[0] + cos_v * p[1]];
+        [r[0] + 0.5, 0.5 - r[1]]
+    };
+
+    // 映射是仿射的，纹理坐标对像素的导数处处相同，按窗口中心确定各纹理的过滤方式
+    let center = (width * 0.5, height * 0.5);
+    let mask_at = |dx: f32, dy: f32| to_mask(to_local(center.0 + dx, center.1 + dy));
+    let video_at = |dx: f32, dy: f32| to_video(mask_at(dx, dy));
+    let derivative = |at: &dyn Fn(f32, f32) -> [f32; 2], dx: f32, dy: f32| {
+        let (a, b) = (at(0.0, 0.0), at(dx, dy));
+        [b[0] - a[0], b[1] - a[1]]
+    };
+    let mask_dx = derivative(&mask_at, 1.0, 0.0);
+    let mask_dy = derivative(&mask_at, 0.0, 1.0);
+    let video_linear = video.is_magnified(derivative(&video_at, 1.0, 0.0), derivative(&video_at, 0.0, 1.0));
+    let layer_linear = |texture: &Texture| texture.is_magnified(mask_dx, mask_dy);
+    let mask_linear = layer_linear(&mask_texture);
+    let overlay = overlay.as_ref().map(|texture| (texture, layer_linear(texture)));
+    let sticker_below = sticker_below.as_ref().map(|texture| (texture, layer_linear(texture)));
+    let sticker_above = sticker_above.as_ref().map(|texture| (texture, layer_linear(texture)));
+
+    let mut output = RgbaImage::new(params.width, params.height);
+    for (x, y, pixel) in output.enumerate_pixels_mut() {
+        // 在像素中心采样，四边形之外保持透明背景
+        let local = to_local(x as f32 + 0.5, y as f32 + 0.5);
+        if local[0].abs() > 1.0 || local[1].abs() > 1.0 {
+            continue;
+        }
+        let uv = to_mask(local);
+
+        let video_color = video.sample(to_video(uv), video_linear);
+        let mask_alpha = mask_texture.sample(uv, mask_linear)[0];
+        let (keyed, key_alpha) = params.chroma_key.apply([video_color[0], video_color[1], video_color[2]]);
+        let corrected = params.auto_correct.map_or(keyed, |correction| correction.apply(keyed));
+
+        let base_color = color.apply(corrected, params.lut);
+        let base_alpha = video_color[3] * key_alpha;
+        let mut composed = [
+            base_color[0] * base_alpha,
+            base_color[1] * base_alpha,
+            base_color[2] * base_alpha,
+            base_alpha,
+        ];
+        if let Some((texture, linear)) = sticker_below {
+            composed = over(texture.sample(uv, linear), composed);
+        }
+        if let Some((texture, linear)) = overlay {
+            let o = texture.sample(uv, linear);
+            composed = over([o[0] * o[3], o[1] * o[3], o[2] * o[3], o[3]], composed);
+        }
+        composed = composed.map(|value| value * mask_alpha);
+        if let Some((texture, linear)) = sticker_above {
+            composed = over(texture.sample(uv, linear), composed);
+        }
+
+        composed = apply_flash(composed, params.shutter_flash);
+
+        *pixel = Rgba(encode_output(composed, params.output_alpha));
+    }
+    Ok(output)
+}
+
+/// 两幅图像的逐通道比较结果
+#[derive(Debug, Clone)]
+pub struct ImageDiff {
+    /// 最大通道差值
+    pub max_difference: u8,
+    /// 差值超过容差的像素数
+    pub mismatched_pixels: usize,
+    /// 差异图：超出容差的像素为红色，其余为变暗的期望图像
+    pub diff_image: RgbaImage,
+}
+
+impl ImageDiff {
+    pub fn is_match(&self) -> bool {
+        self.mismatched_pixels == 0
+    }
+}
+
+/// 逐像素比较，任一通道差值大于 tolerance 的像素计为不匹配；尺寸不同时返回 None
+pub fn compare_images(actual: &RgbaImage, expected: &RgbaImage, tolerance: u8) -> Option<ImageDiff> {
+    if actual.dimensions() != expected.dimensions() {
+        return None;
+    }
+
+    let mut max_difference = 0;
+    let mut mismatched_pixels = 0;
+    let mut diff_image = RgbaImage::new(expected.width(), expected.height());
+    for ((a, e), d) in actual.pixels().zip(expected.pixels()).zip(diff_image.pixels_mut()) {
+        let difference = (0..4).map(|i| a[i].abs_diff(e[i])).max().unwrap_or(0);
+        max_difference = max_difference.max(difference);
+        if difference > tolerance {
+            mismatched_pixels += 1;
+            *d = Rgba([255, 0, 0, 255]);
+        } else {
+            *d = Rgba([e[0] / 4, e[1] / 4, e[2] / 4, 255]);
+        }
+    }
+
+    Some(ImageDiff {
+        max_difference,
+        mismatched_pixels,
+        diff_image,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shape::ShapeType;
+
+    fn solid_frame(width: u32, height: u32, rgb: [u8; 3]) -> Frame {
+        Frame::new(rgb.repeat((width * height) as usize), width, height, PixelFormat::RGB8)
+    }
+
+    #[test]
+    fn test_full_mask_reproduces_frame() {
+        // 矩形遮罩、尺寸一致时输出与输入相同
+        let frame = solid_frame(16, 16, [200, 100, 50]);
+        let mask = ShapeMask::new(ShapeType::Rectangle, 16, 16);
+        let output = composite(&frame, &mask, &ReferenceParams::new(16, 16)).unwrap();
+        for pixel in output.pixels() {
+            let difference = (0..3).map(|i| pixel[i].abs_diff([200, 100, 50][i])).max().unwrap();
+            assert!(difference <= 1, "{:?}", pixel);
+            assert_eq!(pixel[3], 255);
+        }
+    }
+
+    #[test]
+    fn test_circle_mask_and_rotation() {
+        let frame = solid_frame(32, 32, [255, 255, 255]);
+        let mask = ShapeMask::new(ShapeType::Circle, 32, 32);
+        let output = composite(&frame, &mask, &ReferenceParams::new(32, 32)).unwrap();
+        assert_eq!(output.get_pixel(0, 0)[3], 0);
+        assert_eq!(output.get_pixel(16, 16)[3], 255);
+
+        // 旋转 45° 后四边形角落移出窗口，窗口角落在四边形之外保持透明
+        let params = ReferenceParams {
+            mask_rotation: std::f32::consts::FRAC_PI_4,
+            ..ReferenceParams::new(32, 32)
+        };
+        let square = ShapeMask::new(ShapeType::Rectangle, 32, 32);
+        let output = composite(&frame, &square, &params).unwrap();
+        assert_eq!(output.get_pixel(0, 0)[3], 0);
+        assert_eq!(output.get_pixel(16, 0)[3], 255);
+    }
+
+    #[test]
+    fn test_video_rotation_is_independent() {
+        // 左半红、右半蓝的视频旋转 180° 后左右互换，遮罩不变
+        let mut data = Vec::new();
+        for _ in 0..8 {
+            for x in 0..8 {
+                data.extend_from_slice(if x < 4 { &[255, 0, 0] } else { &[0, 0, 255] });
+            }
+        }
+        let frame = Frame::new(data, 8, 8, PixelFormat::RGB8);
+        let mask = ShapeMask::new(ShapeType::Rectangle, 8, 8);
+        let params = ReferenceParams {
+            video_rotation: std::f32::consts::PI,
+            ..ReferenceParams::new(8, 8)
+        };
+        let output = composite(&frame, &mask, &params).unwrap();
+        assert_eq!(output.get_pixel(0, 4).0, [0, 0, 255, 255]);
+        assert_eq!(output.get_pixel(7, 4).0, [255, 0, 0, 255]);
+    }
+
+    #[test]
+    fn test_edge_pixels() {
+        // 白色画面的圆形遮罩：抗锯齿边缘预乘后颜色等于 alpha，非预乘时颜色保持白色
+        let frame = solid_frame(32, 32, [255, 255, 255]);
+        let mask = ShapeMask::new(ShapeType::Circle, 32, 32);
+        let premultiplied = composite(&frame, &mask, &ReferenceParams::new(32, 32)).unwrap();
+        let params = ReferenceParams {
+            output_alpha: OutputAlpha::Straight,
+            ..ReferenceParams::new(32, 32)
+        };
+        let straight = composite(&frame, &mask, &params).unwrap();
+
+        let mut edges = 0;
+        for (p, s) in premultiplied.pixels().zip(straight.pixels()) {
+            assert_eq!(p[3], s[3]);
+            if p[3] == 0 || p[3] == 255 {
+                continue;
+            }
+            edges += 1;
+            assert!((0..3).all(|i| p[i].abs_diff(p[3]) <= 1), "{:?}", p);
+            assert_eq!(&s.0[..3], &[255, 255, 255]);
+        }
+        assert!(edges > 0);
+    }
+
+    #[test]
+    fn test_shutter_flash() {
+        // 全强度闪光时形状内为白色，形状外保持透明
+        let frame = solid_frame(32, 32, [40, 80, 120]);
+        let mask = ShapeMask::new(ShapeType::Circle, 32, 32);
+        let params = ReferenceParams {
+            output_alpha: OutputAlpha::Straight,
+            shutter_flash: 1.0,
+            ..ReferenceParams::new(32, 32)
+        };
+        let output = composite(&frame, &mask, &params).unwrap();
+        assert_eq!(output.get_pixel(16, 16).0, [255, 255, 255, 255]);
+        assert_eq!(output.get_pixel(0, 0).0, [0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_invalid_frame() {
+        let frame = Frame::new(vec![0; 10], 4, 4, PixelFormat::RGB8);
+        let mask = ShapeMask::new(ShapeType::Circle, 4, 4);
+        assert!(composite(&frame, &mask, &ReferenceParams::new(4, 4)).is_err());
+    }
+
+    #[test]
+    fn test_compare_images() {
+        let expected = RgbaImage::from_pixel(4, 4, Rgba([100, 100, 100, 255]));
+        let mut actual = expected.clone();
+        actual.put_pixel(1, 1, Rgba([103, 100, 100, 255]));
+        actual.put_pixel(2, 2, Rgba([120, 100, 100, 255]));
+
+        let diff = compare_images(&actual, &expected, 4).unwrap();
+        assert_eq!(diff.max_difference, 20);
+        assert_eq!(diff.mismatched_pixels, 1);
+        assert!(!diff.is_match());
+        assert_eq!(diff.diff_image.get_pixel(2, 2).0, [255, 0, 0, 255]);
+        assert_eq!(diff.diff_image.get_pixel(1, 1).0, [25, 25, 25, 255]);
+
+        assert!(compare_images(&actual, &RgbaImage::new(2, 2), 4).is_none());
+    }
+}
@@ -1,7 +1,11 @@
 // WGSL 着色器
 // 
-// 顶点着色器：实现旋转变换矩阵
-// 片段着色器：采样视频纹理和遮罩纹理，实现遮罩应用逻辑
+// 顶点着色器：实现旋转变换矩阵（遮罩旋转）
+// 片段着色器：采样视频纹理和遮罩纹理，实现遮罩应用逻辑；
+//             视频纹理坐标单独旋转，使视频可以独立于遮罩旋转；
+//             视频颜色先经过绿幕抠像，再经过调色与 3D LUT 滤镜
+//             贴纸层与文字叠加层按预乘透明度逐层合成，上层贴纸不受遮罩裁剪；
+//             输出按表面的合成透明度模式编码（见 alpha.rs）
 
 // 顶点输入
 struct VertexInput {
@@ -15,9 +19,49 @@ struct VertexOutput {
     @location(0) tex_coords: vec2<f32>,
 }
 
+// 颜色调整参数
+struct ColorUniforms {
+    // 预设颜色矩阵（第 4 列为偏移）
+    preset_matrix: mat4x4<f32>,
+    // 亮度、对比度、饱和度、1/伽马
+    adjust: vec4<f32>,
+    // 色相偏移（弧度）、色温、LUT 强度、LUT 边长
+    extra: vec4<f32>,
+    lut_domain_min: vec4<f32>,
+    lut_domain_max: vec4<f32>,
+}
+
+// 绿幕抠像参数
+struct ChromaKeyUniforms {
+    // 关键色 rgb，w 为是否启用
+    key_color: vec4<f32>,
+    // 相似度、平滑度、溢色范围、保留
+    params: vec4<f32>,
+}
+
+// 自动校正参数
+struct AutoCorrectUniforms {
+    // 白平衡增益 rgb，w 为是否启用
+    gains: vec4<f32>,
+    // 黑点、1/(白点-黑点)、亮度指数、保留
+    levels: vec4<f32>,
+}
+
+// 输出编码参数
+struct OutputUniforms {
+    // 是否预乘、是否由着色器做 sRGB 编码、快门闪光强度、保留
+    flags: vec4<f32>,
+}
+
 // 统一缓冲区（变换矩阵）
 struct Uniforms {
     transform: mat4x4<f32>,
+    // 视频相对遮罩的旋转（cos, sin, 0, 0）
+    video_rotation: vec4<f32>,
+    color: ColorUniforms,
+    chroma_key: ChromaKeyUniforms,
+    auto_correct: AutoCorrectUniforms,
+    output: OutputUniforms,
 }
 
 @group(1) @binding(0)
@@ -45,16 +89,174 @@ var mask_texture: texture_2d<f32>;
 @group(0) @binding(2)
 var texture_sampler: sampler;
 
+@group(0) @binding(3)
+var lut_texture: texture_3d<f32>;
+
+@group(0) @binding(4)
+var lut_sampler: sampler;
+
+// 叠加层纹理（文字等），与遮罩使用相同的纹理坐标
+@group(0) @binding(5)
+var overlay_texture: texture_2d<f32>;
+
+// 贴纸层纹理（预乘透明度），分别位于遮罩之下和之上
+@group(0) @binding(6)
+var sticker_below_texture: texture_2d<f32>;
+
+@group(0) @binding(7)
+var sticker_above_texture: texture_2d<f32>;
+
+// 将遮罩纹理坐标旋转到视频纹理坐标（以纹理中心为原点，y 轴向上与裁剪空间一致）
+fn rotate_video_coords(tex_coords: vec2<f32>) -> vec2<f32> {
+    let c = uniforms.video_rotation.x;
+    let s = uniforms.video_rotation.y;
+    let p = vec2<f32>(tex_coords.x - 0.5, 0.5 - tex_coords.y);
+    let r = vec2<f32>(c * p.x + s * p.y, -s * p.x + c * p.y);
+    return vec2<f32>(r.x + 0.5, 0.5 - r.y);
+}
+
+// Rec.709 亮度
+fn luminance(rgb: vec3<f32>) -> f32 {
+    return dot(rgb, vec3<f32>(0.2126, 0.7152, 0.0722));
+}
+
+// BT.601 色度分量 (Cb, Cr)
+fn rgb_to_cbcr(rgb: vec3<f32>) -> vec2<f32> {
+    return vec2<f32>(
+        dot(rgb, vec3<f32>(-0.168736, -0.331264, 0.5)),
+        dot(rgb, vec3<f32>(0.5, -0.418688, -0.081312)),
+    );
+}
+
+// 绿幕抠像：返回溢色抑制后的颜色和抠像透明度（与 chroma.rs 保持一致）
+fn apply_chroma_key(rgb: vec3<f32>) -> vec4<f32> {
+    let key = uniforms.chroma_key;
+    if (key.key_color.w < 0.5) {
+        return vec4<f32>(rgb, 1.0);
+    }
+
+    let distance = distance(rgb_to_cbcr(rgb), rgb_to_cbcr(key.key_color.rgb));
+    let base_mask = distance - key.params.x;
+    let alpha = pow(clamp(base_mask / key.params.y, 0.0, 1.0), 1.5);
+
+    var output = rgb;
+    if (key.params.z > 0.0) {
+        let spill = pow(clamp(base_mask / key.params.z, 0.0, 1.0), 1.5);
+        output = mix(vec3<f32>(clamp(luminance(rgb), 0.0, 1.0)), rgb, spill);
+    }
+
+    return vec4<f32>(output, alpha);
+}
+
+// 自动白平衡、色阶与弱光增强（与 auto_correct.rs 保持一致）
+fn apply_auto_correct(rgb: vec3<f32>) -> vec3<f32> {
+    let correction = uniforms.auto_correct;
+    if (correction.gains.w < 0.5) {
+        return rgb;
+    }
+
+    let balanced = rgb * correction.gains.rgb;
+    let leveled = clamp((balanced - vec3<f32>(correction.levels.x)) * correction.levels.y, vec3<f32>(0.0), vec3<f32>(1.0));
+    return pow(leveled, vec3<f32>(correction.levels.z));
+}
+
+// 依次应用预设、亮度、对比度、饱和度、色相、色温、伽马和 LUT（与 color.rs 保持一致）。
+// 预设和 .cube LUT 都按 sRGB 编码值定义，而视频纹理采样得到的是线性值：
+// 先编码为 sRGB 再调色，结果解码回线性空间参与合成
+fn apply_color_grading(input_rgb: vec3<f32>) -> vec3<f32> {
+    let color = uniforms.color;
+    var rgb = (color.preset_matrix * vec4<f32>(linear_to_srgb(input_rgb), 1.0)).rgb;
+
+    rgb = rgb + vec3<f32>(color.adjust.x);
+    rgb = (rgb - vec3<f32>(0.5)) * color.adjust.y + vec3<f32>(0.5);
+    rgb = mix(vec3<f32>(luminance(rgb)), rgb, color.adjust.z);
+
+    // 绕灰度轴 (1,1,1)/√3 旋转色相（Rodrigues 公式）
+    let k = vec3<f32>(0.57735027);
+    let cos_h = cos(color.extra.x);
+    let sin_h = sin(color.extra.x);
+    rgb = rgb * cos_h + cross(k, rgb) * sin_h + k * dot(k, rgb) * (1.0 - cos_h);
+
+    // 正值偏暖（增红减蓝），负值偏冷
+    let t = color.extra.y;
+    rgb = rgb * vec3<f32>(1.0 + 0.2 * t, 1.0, 1.0 - 0.2 * t);
+
+    rgb = pow(clamp(rgb, vec3<f32>(0.0), vec3<f32>(1.0)), vec3<f32>(color.adjust.w));
+
+    // 3D LUT：将输入范围映射到纹素中心之间，保证端点采样准确
+    let lut_size = color.extra.w;
+    let domain = color.lut_domain_max.xyz - color.lut_domain_min.xyz;
+    let normalized = clamp((rgb - color.lut_domain_min.xyz) / domain, vec3<f32>(0.0), vec3<f32>(1.0));
+    let lut_coords = normalized * ((lut_size - 1.0) / lut_size) + vec3<f32>(0.5 / lut_size);
+    let graded = textureSampleLevel(lut_texture, lut_sampler, lut_coords, 0.0).rgb;
+
+    return srgb_to_linear(mix(rgb, graded, color.extra.z));
+}
+
+fn linear_to_srgb(rgb: vec3<f32>) -> vec3<f32> {
+    let low = rgb * 12.92;
+    let high = 1.055 * pow(rgb, vec3<f32>(1.0 / 2.4)) - vec3<f32>(0.055);
+    return select(high, low, rgb <= vec3<f32>(0.0031308));
+}
+
+fn srgb_to_linear(rgb: vec3<f32>) -> vec3<f32> {
+    let low = rgb / 12.92;
+    let high = pow((rgb + vec3<f32>(0.055)) / 1.055, vec3<f32>(2.4));
+    return select(high, low, rgb <= vec3<f32>(0.04045));
+}
+
+// 将线性空间、预乘透明度的合成结果编码为输出颜色（与 alpha.rs 中的 encode_output 保持一致）：
+// 合成器在 sRGB 编码空间中混合，先还原非预乘颜色并编码，需要预乘时再乘 alpha
+fn encode_output(composed: vec4<f32>) -> vec4<f32> {
+    let alpha = clamp(composed.a, 0.0, 1.0);
+    let straight = clamp(composed.rgb / max(composed.a, 1e-5), vec3<f32>(0.0), vec3<f32>(1.0));
+    var encoded = linear_to_srgb(straight);
+    if (uniforms.output.flags.x > 0.5) {
+        encoded = encoded * alpha;
+    }
+    if (uniforms.output.flags.y > 0.5) {
+        return vec4<f32>(encoded, alpha);
+    }
+    // sRGB 表面写入时由硬件编码，输出编码结果对应的线性值
+    return vec4<f32>(srgb_to_linear(encoded), alpha);
+}
+
 // 片段着色器
 @fragment
 fn fs_main(input: VertexOutput) -> @location(0) vec4<f32> {
-    // 采样视频纹理
-    let video_color = textureSample(video_texture, texture_sampler, input.tex_coords);
+    // 采样视频纹理（按视频自身的旋转角度）
+    let video_color = textureSample(video_texture, texture_sampler, rotate_video_coords(input.tex_coords));
     
     // 采样遮罩纹理（alpha 通道）
     let mask_alpha = textureSample(mask_texture, texture_sampler, input.tex_coords).r;
     
-    // 应用遮罩：video.rgb * mask.a
-    // 输出带透明度的颜色
-    return vec4<f32>(video_color.rgb, video_color.a * mask_alpha);
+    // 绿幕抠像在调色之前进行，按摄像头原始颜色判断背景
+    let keyed = apply_chroma_key(video_color.rgb);
+    
+    // 自动校正在手动调色之前，调色基于校正后的画面
+    let corrected = apply_auto_correct(keyed.rgb);
+    
+    // 以下按预乘透明度逐层合成：视频、下层贴纸、叠加层
+    let base_color = apply_color_grading(corrected);
+    let base_alpha = video_color.a * keyed.a;
+    var composed = vec4<f32>(base_color * base_alpha, base_alpha);
+    let below = textureSample(sticker_below_texture, texture_sampler, input.tex_coords);
+    composed = below + composed * (1.0 - below.a);
+    // 叠加层纹理为非预乘透明度
+    let overlay = textureSample(overlay_texture, texture_sampler, input.tex_coords);
+    composed = vec4<f32>(overlay.rgb * overlay.a, overlay.a) + composed * (1.0 - overlay.a);
+    
+    // 应用遮罩：以上各层都被形状裁剪
+    composed = composed * mask_alpha;
+    
+    // 上层贴纸不受遮罩裁剪，可以越过形状边缘
+    let above = textureSample(sticker_above_texture, texture_sampler, input.tex_coords);
+    composed = above + composed * (1.0 - above.a);
+    
+    // 快门闪光：可见部分按强度提亮为白色（与 alpha.rs 中的 apply_flash 保持一致）
+    let flash = clamp(uniforms.output.flags.z, 0.0, 1.0);
+    composed = vec4<f32>(mix(composed.rgb, vec3<f32>(composed.a), flash), composed.a);
+    
+    // 按表面要求的透明度形式输出
+    return encode_output(composed);
 }
\ No newline at end of file
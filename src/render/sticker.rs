@@ -0,0 +1,376 @@
+// 贴纸 GPU 通道
+//
+// 每个贴纸对应一张纹理，动画帧变化时只上传当前帧。贴纸按层级分别绘制到两张
+// 与窗口同尺寸的贴纸层纹理（遮罩坐标系，预乘透明度），只在内容或尺寸变化时重绘。
+// 主着色器在遮罩之前合成 Below 层，在遮罩之后合成 Above 层。
+
+use crate::overlay::sticker::{StickerLayer, StickerSet};
+use log::debug;
+use wgpu::util::DeviceExt;
+
+/// 贴纸层纹理格式
+const TARGET_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8UnormSrgb;
+
+/// 贴纸顶点
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+struct StickerVertex {
+    position: [f32; 2],   // NDC 坐标
+    tex_coords: [f32; 2], // 贴纸纹理坐标
+}
+
+impl StickerVertex {
+    const ATTRIBS: [wgpu::VertexAttribute; 2] = wgpu::vertex_attr_array![
+        0 => Float32x2,  // position
+        1 => Float32x2   // tex_coords
+    ];
+
+    fn desc() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<StickerVertex>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &Self::ATTRIBS,
+        }
+    }
+}
+
+/// 贴纸四角的像素坐标转换为两个三角形的顶点
+fn quad_vertices(corners: [[f32; 2]; 4], target_size: (u32, u32)) -> [StickerVertex; 6] {
+    let (width, height) = (target_size.0 as f32, target_size.1 as f32);
+    let tex_coords = [[0.0, 0.0], [1.0, 0.0], [1.0, 1.0], [0.0, 1.0]];
+    let vertex = |index: usize| StickerVertex {
+        position: [corners[index][0] / width * 2.0 - 1.0, 1.0 - corners[index][1] / height * 2.0],
+        tex_coords: tex_coords[index],
+    };
+    [vertex(0), vertex(1), vertex(2), vertex(0), vertex(2), vertex(3)]
+}
+
+/// 单个贴纸的纹理
+struct StickerTexture {
+    texture: wgpu::Texture,
+    bind_group: wgpu::BindGroup,
+    width: u32,
+    height: u32,
+    // 当前已上传的帧
+    frame: usize,
+}
+
+/// 两个层级的贴纸层纹理
+struct StickerTargets {
+    width: u32,
+    height: u32,
+    below_view: wgpu::TextureView,
+    above_view: wgpu::TextureView,
+}
+
+/// 贴纸 GPU 通道
+pub struct StickerPipeline {
+    pipeline: wgpu::RenderPipeline,
+    bind_group_layout: wgpu::BindGroupLayout,
+    sampler: wgpu::Sampler,
+    textures: Vec<Option<StickerTexture>>,
+    // 对应 StickerSet 的批次，变化时丢弃全部贴纸纹理
+    generation: u64,
+    targets: Option<StickerTargets>,
+    needs_redraw: bool,
+}
+
+impl StickerPipeline {
+    pub fn new(device: &wgpu::Device) -> Self {
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[
+                // 贴纸纹理
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        multisampled: false,
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                    },
+                    count: None,
+                },
+                // 采样器
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+            label: Some("sticker_bind_group_layout"),
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Sticker Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Sticker Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("sticker.wgsl").into()),
+        });
+
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Sticker Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[StickerVertex::desc()],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: TARGET_FORMAT,
+                    blend: Some(wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        Self {
+            pipeline,
+            bind_group_layout,
+            sampler,
+            textures: Vec::new(),
+            generation: 0,
+            targets: None,
+            needs_redraw: true,
+        }
+    }
+
+    /// 确保贴纸层纹理与窗口尺寸一致，重新创建时返回 true（纹理视图已变化）
+    pub fn ensure_targets(&mut self, device: &wgpu::Device, width: u32, height: u32) -> bool {
+        if self.targets.as_ref().is_some_and(|t| t.width == width && t.height == height) {
+            return false;
+        }
+
+        debug!("创建贴纸层纹理: {}x{}", width, height);
+        let create_view = |label| {
+            device
+                .create_texture(&wgpu::TextureDescriptor {
+                    label: Some(label),
+                    size: wgpu::Extent3d {
+                        width,
+                        height,
+                        depth_or_array_layers: 1,
+                    },
+                    mip_level_count: 1,
+                    sample_count: 1,
+                    dimension: wgpu::TextureDimension::D2,
+                    format: TARGET_FORMAT,
+                    usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::RENDER_ATTACHMENT,
+                    view_formats: &[],
+                })
+                .create_view(&wgpu::TextureViewDescriptor::default())
+        };
+
+        self.targets = Some(StickerTargets {
+            width,
+            height,
+            below_view: create_view("sticker_below"),
+            above_view: create_view("sticker_above"),
+        });
+        self.needs_redraw = true;
+        true
+    }
+
+    /// 遮罩之下的贴纸层（需先调用 ensure_targets）
+    pub fn below_view(&self) -> Option<&wgpu::TextureView> {
+        self.targets.as_ref().map(|t| &t.below_view)
+    }
+
+    /// 遮罩之上的贴纸层（需先调用 ensure_targets）
+    pub fn above_view(&self) -> Option<&wgpu::TextureView> {
+        self.targets.as_ref().map(|t| &t.above_view)
+    }
+
+    /// 释放贴纸层纹理，之前存在时返回 true
+    pub fn release_targets(&mut self) -> bool {
+        self.targets.take().is_some()
+    }
+
+    /// 释放全部纹理（贴纸纹理在下次 sync 时重新上传）
+    pub fn release(&mut self) {
+        self.textures.clear();
+        self.targets = None;
+    }
+
+    /// 同步贴纸纹理：新的贴纸集合、新解码的图片和动画帧变化，返回是否需要重绘贴纸层
+    pub fn sync(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, set: &StickerSet) -> bool {
+        if set.generation() != self.generation {
+            self.textures.clear();
+            self.generation = set.generation();
+            self.needs_redraw = true;
+        }
+        self.textures.resize_with(set.stickers().len(), || None);
+
+        for index in 0..self.textures.len() {
+            let (Some(image), Some(frame)) = (set.image(index), set.current_frame(index)) else {
+                continue;
+            };
+            let extent = wgpu::Extent3d {
+                width: image.width(),
+                height: image.height(),
+                depth_or_array_layers: 1,
+            };
+
+            match &mut self.textures[index] {
+                Some(texture) if texture.frame == frame => {}
+                Some(texture) => {
+                    queue.write_texture(
+                        texture.texture.as_image_copy(),
+                        image.frame(frame),
+                        wgpu::ImageDataLayout {
+                            offset: 0,
+                            bytes_per_row: Some(4 * image.width()),
+                            rows_per_image: Some(image.height()),
+                        },
+                        extent,
+                    );
+                    texture.frame = frame;
+                    self.needs_redraw = true;
+                }
+                slot @ None => {
+                    let texture = device.create_texture_with_data(
+                        queue,
+                        &wgpu::TextureDescriptor {
+                            label: Some("Sticker Texture"),
+                            size: extent,
+                            mip_level_count: 1,
+                            sample_count: 1,
+                            dimension: wgpu::TextureDimension::D2,
+                            format: wgpu::TextureFormat::Rgba8UnormSrgb,
+                            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+                            view_formats: &[],
+                        },
+                        wgpu::util::TextureDataOrder::LayerMajor,
+                        image.frame(frame),
+                    );
+                    let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+                    let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                        layout: &self.bind_group_layout,
+                        entries: &[
+                            wgpu::BindGroupEntry {
+                                binding: 0,
+                                resource: wgpu::BindingResource::TextureView(&view),
+                            },
+                            wgpu::BindGroupEntry {
+                                binding: 1,
+                                resource: wgpu::BindingResource::Sampler(&self.sampler),
+                            },
+                        ],
+                        label: Some("sticker_bind_group"),
+                    });
+                    *slot = Some(StickerTexture {
+                        texture,
+                        bind_group,
+                        width: image.width(),
+                        height: image.height(),
+                        frame,
+                    });
+                    self.needs_redraw = true;
+                }
+            }
+        }
+
+        self.needs_redraw
+    }
+
+    /// 重绘两个贴纸层，列表中靠后的贴纸绘制在上方
+    pub fn encode(&mut self, device: &wgpu::Device, encoder: &mut wgpu::CommandEncoder, set: &StickerSet) {
+        let Some(targets) = &self.targets else {
+            return;
+        };
+
+        let mut vertices = Vec::new();
+        let mut draws = Vec::new();
+        for (sticker, texture) in set.stickers().iter().zip(&self.textures) {
+            let Some(texture) = texture else {
+                continue;
+            };
+            let corners = sticker.corners((texture.width, texture.height), (targets.width, targets.height));
+            let first = vertices.len() as u32;
+            vertices.extend(quad_vertices(corners, (targets.width, targets.height)));
+            draws.push((sticker.layer, &texture.bind_group, first));
+        }
+
+        let vertex_buffer = (!vertices.is_empty()).then(|| {
+            device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Sticker Vertex Buffer"),
+                contents: bytemuck::cast_slice(&vertices),
+                usage: wgpu::BufferUsages::VERTEX,
+            })
+        });
+
+        for (layer, view, label) in [
+            (StickerLayer::Below, &targets.below_view, "Sticker Below Pass"),
+            (StickerLayer::Above, &targets.above_view, "Sticker Above Pass"),
+        ] {
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some(label),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                occlusion_query_set: None,
+                timestamp_writes: None,
+            });
+            let Some(vertex_buffer) = &vertex_buffer else {
+                continue;
+            };
+            render_pass.set_pipeline(&self.pipeline);
+            render_pass.set_vertex_buffer(0, vertex_buffer.slice(..));
+            for (_, bind_group, first) in draws.iter().filter(|(draw_layer, _, _)| *draw_layer == layer) {
+                render_pass.set_bind_group(0, bind_group, &[]);
+                render_pass.draw(*first..*first + 6, 0..1);
+            }
+        }
+
+        self.needs_redraw = false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sticker_vertex_layout() {
+        let desc = StickerVertex::desc();
+        assert_eq!(desc.array_stride, std::mem::size_of::<StickerVertex>() as u64);
+        assert_eq!(desc.attributes.len(), 2);
+    }
+
+    #[test]
+    fn test_quad_vertices_to_ndc() {
+        let vertices = quad_vertices([[0.0, 0.0], [200.0, 0.0], [200.0, 100.0], [0.0, 100.0]], (200, 100));
+        // 左上角对应 NDC (-1, 1)，右下角对应 (1, -1)
+        assert_eq!(vertices[0], StickerVertex { position: [-1.0, 1.0], tex_coords: [0.0, 0.0] });
+        assert_eq!(vertices[2], StickerVertex { position: [1.0, -1.0], tex_coords: [1.0, 1.0] });
+        assert_eq!(vertices[3], vertices[0]);
+        assert_eq!(vertices[5], StickerVertex { position: [-1.0, -1.0], tex_coords: [0.0, 1.0] });
+    }
+}
@@ -0,0 +1,34 @@
+// 贴纸着色器
+//
+// 将贴纸四边形绘制到遮罩坐标系下的贴纸层纹理，输出预乘透明度，
+// 多个贴纸按顺序叠加。主着色器再把贴纸层与视频合成。
+
+struct VertexInput {
+    @location(0) position: vec2<f32>,
+    @location(1) tex_coords: vec2<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) tex_coords: vec2<f32>,
+}
+
+@group(0) @binding(0)
+var sticker_texture: texture_2d<f32>;
+
+@group(0) @binding(1)
+var sticker_sampler: sampler;
+
+@vertex
+fn vs_main(input: VertexInput) -> VertexOutput {
+    var out: VertexOutput;
+    out.clip_position = vec4<f32>(input.position, 0.0, 1.0);
+    out.tex_coords = input.tex_coords;
+    return out;
+}
+
+@fragment
+fn fs_main(input: VertexOutput) -> @location(0) vec4<f32> {
+    let color = textureSample(sticker_texture, sticker_sampler, input.tex_coords);
+    return vec4<f32>(color.rgb * color.a, color.a);
+}
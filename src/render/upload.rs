@@ -0,0 +1,262 @@
+// 视频帧上传
+//
+// 摄像头通常输出紧凑的 RGB8 数据，而视频纹理为 RGBA8。在 CPU 上逐像素补齐 alpha
+// 在 1080p 下每帧要多复制约 8 MB，因此 RGB8 数据原样写入存储缓冲区，
+// 由计算着色器展开为按行对齐的 RGBA 数据，再在 GPU 上复制到视频纹理。
+// 缓冲区在帧尺寸不变时复用；不支持计算着色器的适配器（如 GLES）由 RenderEngine 回退到 CPU 转换。
+
+use log::debug;
+
+/// 计算着色器工作组边长（与 upload.wgsl 一致）
+const WORKGROUP_SIZE: u32 = 8;
+
+/// 上传参数（与 upload.wgsl 中的 UploadParams 对应）
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+struct UploadParams {
+    width: u32,
+    height: u32,
+    output_row_words: u32,
+    _padding: u32,
+}
+
+/// 输出缓冲区每行字节数，按 copy_buffer_to_texture 的要求对齐到 256 字节
+pub fn padded_bytes_per_row(width: u32) -> u32 {
+    (width * 4).next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT)
+}
+
+/// 输入缓冲区大小：RGB 数据向上对齐到 4 字节
+pub fn rgb_buffer_size(width: u32, height: u32) -> u64 {
+    (width as u64 * height as u64 * 3).next_multiple_of(wgpu::COPY_BUFFER_ALIGNMENT)
+}
+
+/// 与帧尺寸对应的缓冲区
+struct UploadBuffers {
+    width: u32,
+    height: u32,
+    input: wgpu::Buffer,
+    output: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+}
+
+/// RGB8 帧的 GPU 上传通道
+pub struct FrameUploader {
+    pipeline: wgpu::ComputePipeline,
+    bind_group_layout: wgpu::BindGroupLayout,
+    params_buffer: wgpu::Buffer,
+    buffers: Option<UploadBuffers>,
+}
+
+impl FrameUploader {
+    /// 适配器是否支持计算着色器，且设备（可能以降级限制创建）提供所需的存储缓冲区
+    pub fn is_supported(adapter: &wgpu::Adapter, device: &wgpu::Device) -> bool {
+        adapter
+            .get_downlevel_capabilities()
+            .flags
+            .contains(wgpu::DownlevelFlags::COMPUTE_SHADERS)
+            && device.limits().max_storage_buffers_per_shader_stage >= 2
+    }
+
+    pub fn new(device: &wgpu::Device) -> Self {
+        let storage_entry = |binding, read_only| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Storage { read_only },
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[
+                // 紧凑的 RGB 数据
+                storage_entry(0, true),
+                // 按行对齐的 RGBA 数据
+                storage_entry(1, false),
+                // 上传参数
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+            label: Some("upload_bind_group_layout"),
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Upload Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Upload Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("upload.wgsl").into()),
+        });
+
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("Unpack RGB Pipeline"),
+            layout: Some(&pipeline_layout),
+            module: &shader,
+            entry_point: "cs_unpack_rgb",
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+        });
+
+        let params_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Upload Params Buffer"),
+            size: std::mem::size_of::<UploadParams>() as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        Self {
+            pipeline,
+            bind_group_layout,
+            params_buffer,
+            buffers: None,
+        }
+    }
+
+    /// 确保缓冲区与帧尺寸一致，尺寸变化时重新创建
+    fn ensure_buffers(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, width: u32, height: u32) {
+        if self.buffers.as_ref().is_some_and(|b| b.width == width && b.height == height) {
+            return;
+        }
+
+        debug!("创建上传缓冲区: {}x{}", width, height);
+        let input = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("upload_rgb_buffer"),
+            size: rgb_buffer_size(width, height),
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let output = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("upload_rgba_buffer"),
+            size: padded_bytes_per_row(width) as u64 * height as u64,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &self.bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: input.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: output.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: self.params_buffer.as_entire_binding(),
+                },
+            ],
+            label: Some("upload_bind_group"),
+        });
+
+        let params = UploadParams {
+            width,
+            height,
+            output_row_words: padded_bytes_per_row(width) / 4,
+            _padding: 0,
+        };
+        queue.write_buffer(&self.params_buffer, 0, bytemuck::cast_slice(&[params]));
+
+        self.buffers = Some(UploadBuffers {
+            width,
+            height,
+            input,
+            output,
+            bind_group,
+        });
+    }
+
+    /// 上传紧凑的 RGB8 数据到视频纹理（纹理尺寸需与帧一致，数据长度由调用方检查）
+    pub fn upload_rgb(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, rgb: &[u8], texture: &wgpu::Texture) {
+        let size = texture.size();
+        self.ensure_buffers(device, queue, size.width, size.height);
+        let Some(buffers) = &self.buffers else {
+            return;
+        };
+
+        // write_buffer 要求长度为 4 的倍数，不足的尾部单独补零写入
+        let aligned = rgb.len() - rgb.len() % wgpu::COPY_BUFFER_ALIGNMENT as usize;
+        if aligned > 0 {
+            queue.write_buffer(&buffers.input, 0, &rgb[..aligned]);
+        }
+        if aligned < rgb.len() {
+            let mut tail = [0u8; wgpu::COPY_BUFFER_ALIGNMENT as usize];
+            tail[..rgb.len() - aligned].copy_from_slice(&rgb[aligned..]);
+            queue.write_buffer(&buffers.input, aligned as u64, &tail);
+        }
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Upload Encoder"),
+        });
+        {
+            let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("Unpack RGB Pass"),
+                timestamp_writes: None,
+            });
+            compute_pass.set_pipeline(&self.pipeline);
+            compute_pass.set_bind_group(0, &buffers.bind_group, &[]);
+            compute_pass.dispatch_workgroups(
+                size.width.div_ceil(WORKGROUP_SIZE),
+                size.height.div_ceil(WORKGROUP_SIZE),
+                1,
+            );
+        }
+        encoder.copy_buffer_to_texture(
+            wgpu::ImageCopyBuffer {
+                buffer: &buffers.output,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_bytes_per_row(size.width)),
+                    rows_per_image: Some(size.height),
+                },
+            },
+            texture.as_image_copy(),
+            size,
+        );
+        queue.submit(std::iter::once(encoder.finish()));
+    }
+
+    /// 释放上传缓冲区
+    pub fn release_buffers(&mut self) {
+        self.buffers = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_buffer_layout() {
+        assert_eq!(padded_bytes_per_row(1), 256);
+        assert_eq!(padded_bytes_per_row(64), 256);
+        assert_eq!(padded_bytes_per_row(65), 512);
+        assert_eq!(padded_bytes_per_row(1920), 7680);
+        assert_eq!(padded_bytes_per_row(1366) % wgpu::COPY_BYTES_PER_ROW_ALIGNMENT, 0);
+
+        assert_eq!(rgb_buffer_size(4, 4), 48);
+        assert_eq!(rgb_buffer_size(3, 3), 28);
+        assert_eq!(rgb_buffer_size(1920, 1080), 1920 * 1080 * 3);
+    }
+
+    #[test]
+    fn test_shader_is_valid() {
+        let module = crate::render::hot_reload::validate_wgsl(include_str!("upload.wgsl")).unwrap();
+        let entry = module.entry_points.iter().find(|e| e.name == "cs_unpack_rgb").unwrap();
+        assert_eq!(entry.workgroup_size, [WORKGROUP_SIZE, WORKGROUP_SIZE, 1]);
+        assert_eq!(std::mem::size_of::<UploadParams>(), 16);
+    }
+}
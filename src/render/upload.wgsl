@@ -0,0 +1,33 @@
+// 视频帧上传：把紧凑的 RGB8 数据展开为 RGBA8
+//
+// 输入缓冲区按字节保存 RGB 数据（每 4 字节打包为一个 u32，小端序），
+// 输出缓冲区每行按 256 字节对齐，随后复制到视频纹理。
+
+struct UploadParams {
+    width: u32,
+    height: u32,
+    // 输出缓冲区每行的 u32 数量
+    output_row_words: u32,
+    _padding: u32,
+}
+
+@group(0) @binding(0) var<storage, read> input: array<u32>;
+@group(0) @binding(1) var<storage, read_write> output: array<u32>;
+@group(0) @binding(2) var<uniform> params: UploadParams;
+
+fn read_byte(index: u32) -> u32 {
+    return (input[index / 4u] >> ((index % 4u) * 8u)) & 0xffu;
+}
+
+@compute @workgroup_size(8, 8)
+fn cs_unpack_rgb(@builtin(global_invocation_id) id: vec3<u32>) {
+    if (id.x >= params.width || id.y >= params.height) {
+        return;
+    }
+
+    let byte = (id.y * params.width + id.x) * 3u;
+    let r = read_byte(byte);
+    let g = read_byte(byte + 1u);
+    let b = read_byte(byte + 2u);
+    output[id.y * params.output_row_words + id.x] = r | (g << 8u) | (b << 16u) | 0xff000000u;
+}
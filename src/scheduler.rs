@@ -0,0 +1,274 @@
+// 帧调度模块
+//
+// 事件循环不再每次空闲都请求重绘，而是按以下条件决定何时渲染：
+// 摄像头新帧到达、界面状态变化（输入、菜单、尺寸变化）或有动画（标注渐隐、动画贴纸、时钟文字）时才渲染；
+// 指针移动等不直接改变画面的事件只在悬浮、拖拽或菜单状态随之变化时重绘，
+// 相邻两帧间隔不小于目标帧率对应的时间；窗口不可见时完全跳过渲染。
+// 没有需要渲染的内容时按较长的空闲间隔唤醒，只用于处理托盘菜单等轮询事件。
+
+use std::time::{Duration, Instant};
+use winit::event::WindowEvent;
+
+/// 默认目标帧率
+pub const DEFAULT_TARGET_FPS: u32 = 30;
+
+/// 目标帧率上限
+pub const MAX_TARGET_FPS: u32 = 240;
+
+/// 空闲时的唤醒间隔（托盘菜单事件需要轮询）
+pub const IDLE_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// 超过该时间没有渲染视为空闲
+pub const IDLE_THRESHOLD: Duration = Duration::from_secs(1);
+
+/// 调度结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Schedule {
+    /// 立即请求重绘
+    Render,
+    /// 休眠到指定时间
+    WaitUntil(Instant),
+}
+
+/// 帧调度器
+#[derive(Debug)]
+pub struct FrameScheduler {
+    frame_interval: Duration,
+    last_render: Option<Instant>,
+    // 有尚未渲染的摄像头帧
+    frame_pending: bool,
+    // 界面状态变化，需要重绘
+    redraw_pending: bool,
+    // 有逐帧变化的动画
+    animating: bool,
+    visible: bool,
+    // 未渲染就被新帧替换或因窗口不可见而丢弃的摄像头帧
+    skipped_frames: u64,
+}
+
+impl FrameScheduler {
+    /// 按目标帧率创建调度器
+    pub fn new(target_fps: u32) -> Self {
+        Self {
+            frame_interval: Self::interval_for(target_fps),
+            last_render: None,
+            frame_pending: false,
+            redraw_pending: true,
+            animating: false,
+            visible: true,
+            skipped_frames: 0,
+        }
+    }
+
+    fn interval_for(target_fps: u32) -> Duration {
+        Duration::from_secs(1) / target_fps.clamp(1, MAX_TARGET_FPS)
+    }
+
+    /// 相邻两帧的最小间隔
+    pub fn frame_interval(&self) -> Duration {
+        self.frame_interval
+    }
+
+    /// 摄像头新帧到达；返回 false 表示该帧不会被渲染（窗口不可见或上一帧尚未渲染）
+    pub fn notify_new_frame(&mut self) -> bool {
+        let rendered = self.visible && !self.frame_pending;
+        if !rendered {
+            self.skipped_frames += 1;
+        }
+        self.frame_pending = self.visible;
+        rendered
+    }
+
+    /// 界面状态变化，需要重绘
+    pub fn invalidate(&mut self) {
+        self.redraw_pending = true;
+    }
+
+    /// 根据窗口事件更新调度状态；ui_changed 表示处理该事件后悬浮、拖拽或菜单等界面状态是否变化
+    pub fn on_window_event(&mut self, event: &WindowEvent, ui_changed: bool) {
+        match event {
+            WindowEvent::Occluded(occluded) => self.set_visible(!occluded),
+            // 最小化时部分平台只报告 0x0 尺寸
+            WindowEvent::Resized(size) => {
+                self.set_visible(size.width > 0 && size.height > 0);
+                self.invalidate();
+            }
+            // 本身不改变画面的事件
+            WindowEvent::CursorMoved { .. }
+            | WindowEvent::CursorEntered { .. }
+            | WindowEvent::CursorLeft { .. }
+            | WindowEvent::AxisMotion { .. }
+            | WindowEvent::TouchpadPressure { .. }
+            | WindowEvent::Moved(_)
+            | WindowEvent::Focused(_)
+            | WindowEvent::ModifiersChanged(_)
+            | WindowEvent::RedrawRequested => {
+                if ui_changed {
+                    self.invalidate();
+                }
+            }
+            _ => self.invalidate(),
+        }
+    }
+
+    /// 设置是否有动画需要逐帧重绘
+    pub fn set_animating(&mut self, animating: bool) {
+        self.animating = animating;
+    }
+
+    /// 设置窗口是否可见（最小化或被完全遮挡时不可见）
+    pub fn set_visible(&mut self, visible: bool) {
+        if visible && !self.visible {
+            // 重新可见时立即补画一帧
+            self.redraw_pending = true;
+        }
+        self.visible = visible;
+    }
+
+    /// 是否有需要渲染的内容
+    pub fn has_pending_work(&self) -> bool {
+        self.visible && (self.frame_pending || self.redraw_pending || self.animating)
+    }
+
+    /// 是否处于空闲状态：没有待渲染内容，且已超过 IDLE_THRESHOLD 没有渲染
+    pub fn is_idle(&self, now: Instant) -> bool {
+        !self.has_pending_work() && self.last_render.is_none_or(|last| now >= last + IDLE_THRESHOLD)
+    }
+
+    /// 决定现在渲染还是休眠到何时
+    pub fn poll(&self, now: Instant) -> Schedule {
+        if !self.has_pending_work() {
+            return Schedule::WaitUntil(now + IDLE_POLL_INTERVAL);
+        }
+
+        match self.last_render {
+            Some(last) if now < last + self.frame_interval => Schedule::WaitUntil(last + self.frame_interval),
+            _ => Schedule::Render,
+        }
+    }
+
+    /// 开始渲染一帧，清除待渲染标志
+    pub fn begin_render(&mut self, now: Instant) {
+        self.last_render = Some(now);
+        self.frame_pending = false;
+        self.redraw_pending = false;
+    }
+
+    /// 累计跳过的摄像头帧数
+    pub fn skipped_frames(&self) -> u64 {
+        self.skipped_frames
+    }
+}
+
+impl Default for FrameScheduler {
+    fn default() -> Self {
+        Self::new(DEFAULT_TARGET_FPS)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frame_interval() {
+        assert_eq!(FrameScheduler::new(50).frame_interval(), Duration::from_millis(20));
+        assert_eq!(FrameScheduler::new(0).frame_interval(), Duration::from_secs(1));
+        assert_eq!(FrameScheduler::new(10_000).frame_interval(), Duration::from_secs(1) / MAX_TARGET_FPS);
+        assert_eq!(FrameScheduler::default().frame_interval(), Duration::from_secs(1) / DEFAULT_TARGET_FPS);
+    }
+
+    #[test]
+    fn test_idle_when_nothing_changed() {
+        let mut scheduler = FrameScheduler::new(30);
+        let now = Instant::now();
+
+        // 启动后先画一帧
+        assert_eq!(scheduler.poll(now), Schedule::Render);
+        scheduler.begin_render(now);
+
+        // 没有新帧、没有界面变化时只按空闲间隔唤醒
+        assert!(!scheduler.is_idle(now));
+        let later = now + IDLE_THRESHOLD;
+        assert_eq!(scheduler.poll(later), Schedule::WaitUntil(later + IDLE_POLL_INTERVAL));
+        assert!(scheduler.is_idle(later));
+    }
+
+    #[test]
+    fn test_frame_rate_limit() {
+        let mut scheduler = FrameScheduler::new(20);
+        let now = Instant::now();
+        scheduler.begin_render(now);
+
+        // 新帧到达过早时等到帧间隔结束
+        assert!(scheduler.notify_new_frame());
+        let early = now + Duration::from_millis(10);
+        assert_eq!(scheduler.poll(early), Schedule::WaitUntil(now + Duration::from_millis(50)));
+
+        // 等待期间又到达一帧，旧帧被替换
+        assert!(!scheduler.notify_new_frame());
+        assert_eq!(scheduler.skipped_frames(), 1);
+
+        assert_eq!(scheduler.poll(now + Duration::from_millis(50)), Schedule::Render);
+        scheduler.begin_render(now + Duration::from_millis(50));
+        assert!(!scheduler.has_pending_work());
+    }
+
+    #[test]
+    fn test_invisible_window_skips_rendering() {
+        let mut scheduler = FrameScheduler::new(30);
+        let now = Instant::now();
+        scheduler.begin_render(now);
+
+        scheduler.set_visible(false);
+        assert!(!scheduler.notify_new_frame());
+        scheduler.invalidate();
+        scheduler.set_animating(true);
+        let later = now + Duration::from_secs(1);
+        assert_eq!(scheduler.poll(later), Schedule::WaitUntil(later + IDLE_POLL_INTERVAL));
+
+        // 恢复可见后立即重绘
+        scheduler.set_visible(true);
+        assert_eq!(scheduler.poll(later), Schedule::Render);
+    }
+
+    #[test]
+    fn test_idle_cursor_move_does_not_redraw() {
+        let mut scheduler = FrameScheduler::new(30);
+        let now = Instant::now();
+        scheduler.begin_render(now);
+
+        // 界面状态不变的指针移动不触发重绘
+        let device_id = unsafe { winit::event::DeviceId::dummy() };
+        let cursor_moved = WindowEvent::CursorMoved {
+            device_id,
+            position: winit::dpi::PhysicalPosition::new(10.0, 20.0),
+        };
+        scheduler.on_window_event(&cursor_moved, false);
+        let later = now + Duration::from_millis(200);
+        assert_eq!(scheduler.poll(later), Schedule::WaitUntil(later + IDLE_POLL_INTERVAL));
+
+        // 悬浮状态变化时重绘
+        scheduler.on_window_event(&cursor_moved, true);
+        assert_eq!(scheduler.poll(later), Schedule::Render);
+        scheduler.begin_render(later);
+
+        // 尺寸变化总是重绘
+        scheduler.on_window_event(&WindowEvent::Resized(winit::dpi::PhysicalSize::new(400, 400)), false);
+        let next = later + scheduler.frame_interval();
+        assert_eq!(scheduler.poll(next), Schedule::Render);
+    }
+
+    #[test]
+    fn test_animation_keeps_rendering() {
+        let mut scheduler = FrameScheduler::new(30);
+        let now = Instant::now();
+        scheduler.begin_render(now);
+        scheduler.set_animating(true);
+
+        let next = now + scheduler.frame_interval();
+        assert_eq!(scheduler.poll(next), Schedule::Render);
+        scheduler.begin_render(next);
+        assert!(scheduler.has_pending_work());
+    }
+}
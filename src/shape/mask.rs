@@ -9,6 +9,24 @@
 //
 // 所有形状生成算法都经过性能优化，确保切换时间 < 100ms。
 // 遮罩数据使用单通道 alpha 值表示，255 为完全不透明，0 为完全透明。
+// 生成结果按形状、参数、尺寸和质量缓存，滚轮缩放回到已生成过的尺寸时直接复用。
+//
+// 边缘质量由 MaskQuality 控制：
+// - 快速 (Fast): 仅使用形状内置的 1 像素边缘渐变
+// - 均衡 (Balanced): 额外进行 2x2 超采样
+// - 高质量 (High): 4x4 超采样 + 轻微高斯模糊
+// - 自适应 (Adaptive): 窗口空闲时使用高质量，拖拽或缩放时使用快速
+//
+// 形状生成、超采样和模糊都按行分块在多个线程上并行计算，
+// 每个像素的计算与串行路径完全相同，因此输出逐字节一致。
+
+use crate::memory::{MaskCache, MaskCacheStats};
+
+/// 遮罩缓存默认内存预算（32MB）
+pub const DEFAULT_MASK_CACHE_BUDGET: usize = 32 * 1024 * 1024;
+
+/// 每个线程至少处理的行数，行数太少时线程调度开销大于收益
+const MIN_ROWS_PER_THREAD: usize = 16;
 
 /// 形状类型
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -20,29 +38,152 @@ pub enum ShapeType {
     Heart,
 }
 
+/// 遮罩边缘质量
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum MaskQuality {
+    /// 快速：仅使用内置边缘渐变
+    Fast,
+    /// 均衡：2x2 超采样
+    #[default]
+    Balanced,
+    /// 高质量：4x4 超采样 + 高斯模糊
+    High,
+    /// 自适应：空闲时高质量，交互时快速
+    Adaptive,
+}
+
+impl MaskQuality {
+    /// 所有质量等级（用于菜单和基准测试）
+    pub const ALL: [MaskQuality; 4] = [
+        MaskQuality::Fast,
+        MaskQuality::Balanced,
+        MaskQuality::High,
+        MaskQuality::Adaptive,
+    ];
+
+    /// 配置文件中使用的名称
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MaskQuality::Fast => "Fast",
+            MaskQuality::Balanced => "Balanced",
+            MaskQuality::High => "High",
+            MaskQuality::Adaptive => "Adaptive",
+        }
+    }
+
+    /// 从配置名称解析质量等级
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "Fast" => Some(MaskQuality::Fast),
+            "Balanced" => Some(MaskQuality::Balanced),
+            "High" => Some(MaskQuality::High),
+            "Adaptive" => Some(MaskQuality::Adaptive),
+            _ => None,
+        }
+    }
+
+    /// 菜单显示名称
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            MaskQuality::Fast => "快速",
+            MaskQuality::Balanced => "均衡",
+            MaskQuality::High => "高质量",
+            MaskQuality::Adaptive => "自适应",
+        }
+    }
+
+    /// 400x400 遮罩生成的时间预算（毫秒，release 构建）
+    ///
+    /// 自适应模式以其最慢的等级（高质量）计算。
+    pub fn time_budget_ms(&self) -> u64 {
+        match self {
+            MaskQuality::Fast => 10,
+            MaskQuality::Balanced => 40,
+            MaskQuality::High | MaskQuality::Adaptive => 120,
+        }
+    }
+}
+
+/// 遮罩缓存键：唯一确定一份遮罩数据
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct MaskCacheKey {
+    shape: u8,
+    // 形状参数的位模式（如圆角半径），f32 本身不能作为哈希键
+    param_bits: u32,
+    width: u32,
+    height: u32,
+    // 实际生成使用的质量（自适应已解析）
+    quality: MaskQuality,
+}
+
+impl MaskCacheKey {
+    /// 对应遮罩数据的字节数
+    pub fn size_bytes(&self) -> usize {
+        self.width as usize * self.height as usize
+    }
+}
+
 /// 形状遮罩
 pub struct ShapeMask {
     shape_type: ShapeType,
     width: u32,
     height: u32,
     mask_data: Vec<u8>,
+    quality: MaskQuality,
+    // 窗口是否正在被拖拽或缩放（仅影响自适应质量）
+    interacting: bool,
+    cache: MaskCache<MaskCacheKey, Vec<u8>>,
+    // 并行生成使用的线程数，1 表示串行
+    thread_count: usize,
 }
 
 impl ShapeMask {
-    /// 创建新的形状遮罩
+    /// 创建新的形状遮罩（快速质量，与旧版生成结果一致）
     pub fn new(shape_type: ShapeType, width: u32, height: u32) -> Self {
+        Self::with_quality(shape_type, width, height, MaskQuality::Fast)
+    }
+
+    /// 创建指定边缘质量的形状遮罩，使用所有可用 CPU 核心并行生成
+    pub fn with_quality(shape_type: ShapeType, width: u32, height: u32, quality: MaskQuality) -> Self {
+        Self::with_thread_count(shape_type, width, height, quality, num_cpus::get())
+    }
+
+    /// 创建指定边缘质量和生成线程数的形状遮罩
+    pub fn with_thread_count(
+        shape_type: ShapeType,
+        width: u32,
+        height: u32,
+        quality: MaskQuality,
+        thread_count: usize,
+    ) -> Self {
         let mut mask = Self {
             shape_type,
             width,
             height,
             mask_data: Vec::new(),
+            quality,
+            interacting: false,
+            cache: MaskCache::new(DEFAULT_MASK_CACHE_BUDGET),
+            thread_count: thread_count.max(1),
         };
         mask.generate();
         mask
     }
 
-    /// 生成遮罩数据（alpha 通道）
+    /// 生成遮罩数据（alpha 通道），优先使用缓存
     pub fn generate(&mut self) {
+        let key = self.cache_key();
+        if let Some(data) = self.cache.get(&key) {
+            self.mask_data.clone_from(data);
+            return;
+        }
+        
+        self.generate_uncached();
+        self.cache.insert(key, self.mask_data.clone(), key.size_bytes());
+    }
+
+    /// 不经过缓存直接生成遮罩数据
+    fn generate_uncached(&mut self) {
         match self.shape_type {
             ShapeType::Circle => self.generate_circle(),
             ShapeType::Ellipse => self.generate_ellipse(),
@@ -50,6 +191,101 @@ impl ShapeMask {
             ShapeType::RoundedRectangle { radius } => self.generate_rounded_rectangle(radius),
             ShapeType::Heart => self.generate_heart(),
         }
+
+        match self.effective_quality() {
+            MaskQuality::Fast | MaskQuality::Adaptive => {}
+            MaskQuality::Balanced => self.apply_msaa(2),
+            MaskQuality::High => {
+                self.apply_msaa(4);
+                self.apply_gaussian_blur(1.0);
+            }
+        }
+    }
+
+    /// 当前遮罩数据对应的缓存键
+    pub fn cache_key(&self) -> MaskCacheKey {
+        let (shape, param_bits) = match self.shape_type {
+            ShapeType::Circle => (0, 0),
+            ShapeType::Ellipse => (1, 0),
+            ShapeType::Rectangle => (2, 0),
+            ShapeType::RoundedRectangle { radius } => (3, radius.to_bits()),
+            ShapeType::Heart => (4, 0),
+        };
+        
+        MaskCacheKey {
+            shape,
+            param_bits,
+            width: self.width,
+            height: self.height,
+            quality: self.effective_quality(),
+        }
+    }
+
+    /// 设置遮罩缓存的内存预算（字节），为 0 时禁用缓存
+    pub fn set_cache_budget(&mut self, budget_bytes: usize) {
+        self.cache.set_budget(budget_bytes);
+    }
+
+    /// 获取遮罩缓存统计信息
+    pub fn cache_stats(&self) -> MaskCacheStats {
+        self.cache.get_stats()
+    }
+
+    /// 设置生成遮罩使用的线程数（最少 1 个），不影响生成结果
+    pub fn set_thread_count(&mut self, thread_count: usize) {
+        self.thread_count = thread_count.max(1);
+    }
+
+    /// 获取生成遮罩使用的线程数
+    pub fn thread_count(&self) -> usize {
+        self.thread_count
+    }
+
+    /// 获取配置的边缘质量
+    pub fn quality(&self) -> MaskQuality {
+        self.quality
+    }
+
+    /// 获取当前实际使用的边缘质量（自适应模式会解析为快速或高质量）
+    pub fn effective_quality(&self) -> MaskQuality {
+        match self.quality {
+            MaskQuality::Adaptive if self.interacting => MaskQuality::Fast,
+            MaskQuality::Adaptive => MaskQuality::High,
+            quality => quality,
+        }
+    }
+
+    /// 设置边缘质量，返回遮罩是否重新生成
+    pub fn set_quality(&mut self, quality: MaskQuality) -> bool {
+        if self.quality == quality {
+            return false;
+        }
+        let old_effective = self.effective_quality();
+        self.quality = quality;
+        if self.effective_quality() != old_effective {
+            self.generate();
+            return true;
+        }
+        false
+    }
+
+    /// 设置窗口交互状态（拖拽/缩放），返回遮罩是否重新生成
+    pub fn set_interacting(&mut self, interacting: bool) -> bool {
+        if self.interacting == interacting {
+            return false;
+        }
+        let old_effective = self.effective_quality();
+        self.interacting = interacting;
+        if self.effective_quality() != old_effective {
+            self.generate();
+            return true;
+        }
+        false
+    }
+
+    /// 窗口是否处于交互状态
+    pub fn is_interacting(&self) -> bool {
+        self.interacting
     }
 
     /// 获取遮罩数据
@@ -92,27 +328,21 @@ impl ShapeMask {
         // 确保圆形始终使用较小边作为直径，保持完美圆形
         let radius = (self.width.min(self.height) as f32) / 2.0;
         
-        self.mask_data = vec![0u8; (self.width * self.height) as usize];
-        
-        for y in 0..self.height {
-            for x in 0..self.width {
-                let dx = x as f32 - center_x;
-                let dy = y as f32 - center_y;
-                let distance = (dx * dx + dy * dy).sqrt();
-                
-                // 添加抗锯齿效果，使边缘更平滑
-                let alpha = if distance <= radius - 1.0 {
-                    255
-                } else if distance <= radius {
-                    // 边缘像素使用渐变透明度
-                    ((radius - distance) * 255.0) as u8
-                } else {
-                    0
-                };
-                
-                self.mask_data[(y * self.width + x) as usize] = alpha;
+        self.mask_data = self.render_rows(|x, y| {
+            let dx = x as f32 - center_x;
+            let dy = y as f32 - center_y;
+            let distance = (dx * dx + dy * dy).sqrt();
+            
+            // 添加抗锯齿效果，使边缘更平滑
+            if distance <= radius - 1.0 {
+                255
+            } else if distance <= radius {
+                // 边缘像素使用渐变透明度
+                ((radius - distance) * 255.0) as u8
+            } else {
+                0
             }
-        }
+        });
     }
 
     /// 生成椭圆形遮罩
@@ -123,27 +353,21 @@ impl ShapeMask {
         let radius_x = self.width as f32 / 2.0;
         let radius_y = self.height as f32 / 2.5; // 减小Y轴半径，让椭圆更扁
         
-        self.mask_data = vec![0u8; (self.width * self.height) as usize];
-        
-        for y in 0..self.height {
-            for x in 0..self.width {
-                let dx = (x as f32 - center_x) / radius_x;
-                let dy = (y as f32 - center_y) / radius_y;
-                let distance_squared = dx * dx + dy * dy;
-                
-                // 添加抗锯齿效果，使边缘更平滑
-                let alpha = if distance_squared <= 0.9 {
-                    255
-                } else if distance_squared <= 1.0 {
-                    // 边缘像素使用渐变透明度
-                    ((1.0 - distance_squared) * 255.0 / 0.1) as u8
-                } else {
-                    0
-                };
-                
-                self.mask_data[(y * self.width + x) as usize] = alpha;
+        self.mask_data = self.render_rows(|x, y| {
+            let dx = (x as f32 - center_x) / radius_x;
+            let dy = (y as f32 - center_y) / radius_y;
+            let distance_squared = dx * dx + dy * dy;
+            
+            // 添加抗锯齿效果，使边缘更平滑
+            if distance_squared <= 0.9 {
+                255
+            } else if distance_squared <= 1.0 {
+                // 边缘像素使用渐变透明度
+                ((1.0 - distance_squared) * 255.0 / 0.1) as u8
+            } else {
+                0
             }
-        }
+        });
     }
 
     /// 生成矩形遮罩
@@ -152,52 +376,56 @@ impl ShapeMask {
         self.mask_data = vec![255u8; (self.width * self.height) as usize];
     }
 
+    /// 按行分块并行计算每个像素的 alpha 值，返回完整的遮罩数据
+    fn render_rows<F>(&self, pixel: F) -> Vec<u8>
+    where
+        F: Fn(u32, u32) -> u8 + Sync,
+    {
+        let mut data = vec![0u8; (self.width * self.height) as usize];
+        fill_rows(&mut data, self.width, self.height, self.thread_count, &pixel);
+        data
+    }
+
     /// 生成圆角矩形遮罩
     fn generate_rounded_rectangle(&mut self, radius: f32) {
         let radius = radius.min(self.width as f32 / 2.0).min(self.height as f32 / 2.0);
+        let width_f = self.width as f32;
+        let height_f = self.height as f32;
         
-        self.mask_data = vec![0u8; (self.width * self.height) as usize];
-        
-        for y in 0..self.height {
-            for x in 0..self.width {
-                let x_f = x as f32;
-                let y_f = y as f32;
-                let width_f = self.width as f32;
-                let height_f = self.height as f32;
-                
-                // 计算到最近圆角的距离
-                let alpha = if x_f < radius && y_f < radius {
-                    // 左上角
-                    let dx = radius - x_f;
-                    let dy = radius - y_f;
-                    let distance = (dx * dx + dy * dy).sqrt();
-                    self.calculate_corner_alpha(distance, radius)
-                } else if x_f >= width_f - radius && y_f < radius {
-                    // 右上角
-                    let dx = x_f - (width_f - radius);
-                    let dy = radius - y_f;
-                    let distance = (dx * dx + dy * dy).sqrt();
-                    self.calculate_corner_alpha(distance, radius)
-                } else if x_f < radius && y_f >= height_f - radius {
-                    // 左下角
-                    let dx = radius - x_f;
-                    let dy = y_f - (height_f - radius);
-                    let distance = (dx * dx + dy * dy).sqrt();
-                    self.calculate_corner_alpha(distance, radius)
-                } else if x_f >= width_f - radius && y_f >= height_f - radius {
-                    // 右下角
-                    let dx = x_f - (width_f - radius);
-                    let dy = y_f - (height_f - radius);
-                    let distance = (dx * dx + dy * dy).sqrt();
-                    self.calculate_corner_alpha(distance, radius)
-                } else {
-                    // 不在圆角区域，直接在矩形内
-                    255
-                };
-                
-                self.mask_data[(y * self.width + x) as usize] = alpha;
+        self.mask_data = self.render_rows(|x, y| {
+            let x_f = x as f32;
+            let y_f = y as f32;
+            
+            // 计算到最近圆角的距离
+            if x_f < radius && y_f < radius {
+                // 左上角
+                let dx = radius - x_f;
+                let dy = radius - y_f;
+                let distance = (dx * dx + dy * dy).sqrt();
+                self.calculate_corner_alpha(distance, radius)
+            } else if x_f >= width_f - radius && y_f < radius {
+                // 右上角
+                let dx = x_f - (width_f - radius);
+                let dy = radius - y_f;
+                let distance = (dx * dx + dy * dy).sqrt();
+                self.calculate_corner_alpha(distance, radius)
+            } else if x_f < radius && y_f >= height_f - radius {
+                // 左下角
+                let dx = radius - x_f;
+                let dy = y_f - (height_f - radius);
+                let distance = (dx * dx + dy * dy).sqrt();
+                self.calculate_corner_alpha(distance, radius)
+            } else if x_f >= width_f - radius && y_f >= height_f - radius {
+                // 右下角
+                let dx = x_f - (width_f - radius);
+                let dy = y_f - (height_f - radius);
+                let distance = (dx * dx + dy * dy).sqrt();
+                self.calculate_corner_alpha(distance, radius)
+            } else {
+                // 不在圆角区域，直接在矩形内
+                255
             }
-        }
+        });
     }
     
     /// 计算圆角的抗锯齿alpha值
@@ -218,38 +446,36 @@ impl ShapeMask {
             return; // 不需要抗锯齿
         }
         
-        let original_data = self.mask_data.clone();
+        let original_data = std::mem::take(&mut self.mask_data);
         let sample_offset = 1.0 / (samples as f32);
         
-        for y in 0..self.height {
-            for x in 0..self.width {
-                let mut total_alpha = 0u32;
-                
-                // 对每个像素进行多重采样
-                for sy in 0..samples {
-                    for sx in 0..samples {
-                        let sample_x = x as f32 + (sx as f32 + 0.5) * sample_offset - 0.5;
-                        let sample_y = y as f32 + (sy as f32 + 0.5) * sample_offset - 0.5;
-                        
-                        // 获取采样点的alpha值
-                        let sample_alpha = self.sample_at_position(sample_x, sample_y, &original_data);
-                        total_alpha += sample_alpha as u32;
-                    }
+        self.mask_data = self.render_rows(|x, y| {
+            let mut total_alpha = 0u32;
+            
+            // 对每个像素进行多重采样
+            for sy in 0..samples {
+                for sx in 0..samples {
+                    let sample_x = x as f32 + (sx as f32 + 0.5) * sample_offset - 0.5;
+                    let sample_y = y as f32 + (sy as f32 + 0.5) * sample_offset - 0.5;
+                    
+                    // 获取采样点的alpha值
+                    let sample_alpha = self.sample_at_position(sample_x, sample_y, &original_data);
+                    total_alpha += sample_alpha as u32;
                 }
-                
-                // 计算平均alpha值
-                let avg_alpha = (total_alpha / (samples * samples)) as u8;
-                self.mask_data[(y * self.width + x) as usize] = avg_alpha;
             }
-        }
+            
+            // 计算平均alpha值
+            (total_alpha / (samples * samples)) as u8
+        });
     }
     
     /// 在指定位置采样alpha值（支持亚像素精度）
     fn sample_at_position(&self, x: f32, y: f32, data: &[u8]) -> u8 {
-        // 边界检查
-        if x < 0.0 || y < 0.0 || x >= self.width as f32 || y >= self.height as f32 {
-            return 0;
-        }
+        // 边界外的采样点夹取到边缘，避免超采样使图像边框变暗
+        let max_x = (self.width - 1) as f32;
+        let max_y = (self.height - 1) as f32;
+        let x = x.clamp(0.0, max_x);
+        let y = y.clamp(0.0, max_y);
         
         // 双线性插值
         let x0 = x.floor() as u32;
@@ -269,7 +495,7 @@ impl ShapeMask {
         let a1 = a01 * (1.0 - fx) + a11 * fx;
         let result = a0 * (1.0 - fy) + a1 * fy;
         
-        result as u8
+        result.round() as u8
     }
     
     /// 应用高斯模糊以改善边缘质量
@@ -298,42 +524,36 @@ impl ShapeMask {
             kernel[i] /= sum;
         }
         
-        let original_data = self.mask_data.clone();
+        let original_data = std::mem::take(&mut self.mask_data);
+        let width = self.width;
+        let height = self.height;
         
         // 水平模糊
-        let mut temp_data = vec![0u8; self.mask_data.len()];
-        for y in 0..self.height {
-            for x in 0..self.width {
-                let mut blurred_value = 0.0;
-                
-                for i in 0..kernel_size {
-                    let sample_x = x as i32 + i as i32 - center as i32;
-                    if sample_x >= 0 && sample_x < self.width as i32 {
-                        let idx = (y * self.width + sample_x as u32) as usize;
-                        blurred_value += original_data[idx] as f32 * kernel[i];
-                    }
-                }
-                
-                temp_data[(y * self.width + x) as usize] = blurred_value as u8;
+        let temp_data = self.render_rows(|x, y| {
+            let mut blurred_value = 0.0;
+            
+            for i in 0..kernel_size {
+                // 边界外的采样点夹取到边缘，避免图像边框变暗
+                let sample_x = (x as i32 + i as i32 - center as i32).clamp(0, width as i32 - 1);
+                let idx = (y * width + sample_x as u32) as usize;
+                blurred_value += original_data[idx] as f32 * kernel[i];
             }
-        }
+            
+            blurred_value.round().min(255.0) as u8
+        });
         
         // 垂直模糊
-        for y in 0..self.height {
-            for x in 0..self.width {
-                let mut blurred_value = 0.0;
-                
-                for i in 0..kernel_size {
-                    let sample_y = y as i32 + i as i32 - center as i32;
-                    if sample_y >= 0 && sample_y < self.height as i32 {
-                        let idx = (sample_y as u32 * self.width + x) as usize;
-                        blurred_value += temp_data[idx] as f32 * kernel[i];
-                    }
-                }
-                
-                self.mask_data[(y * self.width + x) as usize] = blurred_value as u8;
+        self.mask_data = self.render_rows(|x, y| {
+            let mut blurred_value = 0.0;
+            
+            for i in 0..kernel_size {
+                let sample_y = (y as i32 + i as i32 - center as i32).clamp(0, height as i32 - 1);
+                let idx = (sample_y as u32 * width + x) as usize;
+                blurred_value += temp_data[idx] as f32 * kernel[i];
             }
-        }
+            
+            blurred_value.round().min(255.0) as u8
+        });
     }
     
     /// 生成高质量形状遮罩（带抗锯齿）
@@ -366,31 +586,25 @@ impl ShapeMask {
         // 进一步增加心形大小，从 /5.0 改为 /3.0，让心形更大
         let scale = (self.width.min(self.height) as f32) / 3.0;
         
-        self.mask_data = vec![0u8; (self.width * self.height) as usize];
-        
-        for y in 0..self.height {
-            for x in 0..self.width {
-                let px = (x as f32 - center_x) / scale;
-                let py = -(y as f32 - center_y) / scale;
-                
-                // 使用心形隐式方程: (x^2 + y^2 - 1)^3 - x^2 * y^3 <= 0
-                // 添加抗锯齿效果
-                let alpha = if self.is_inside_heart(px, py) {
-                    // 在心形内部，检查是否在边缘附近
-                    let edge_distance = self.heart_edge_distance(px, py);
-                    if edge_distance > 0.1 {
-                        255 // 完全不透明
-                    } else {
-                        // 边缘抗锯齿
-                        ((edge_distance / 0.1) * 255.0) as u8
-                    }
+        self.mask_data = self.render_rows(|x, y| {
+            let px = (x as f32 - center_x) / scale;
+            let py = -(y as f32 - center_y) / scale;
+            
+            // 使用心形隐式方程: (x^2 + y^2 - 1)^3 - x^2 * y^3 <= 0
+            // 添加抗锯齿效果
+            if self.is_inside_heart(px, py) {
+                // 在心形内部，检查是否在边缘附近
+                let edge_distance = self.heart_edge_distance(px, py);
+                if edge_distance > 0.1 {
+                    255 // 完全不透明
                 } else {
-                    0 // 完全透明
-                };
-                
-                self.mask_data[(y * self.width + x) as usize] = alpha;
+                    // 边缘抗锯齿
+                    ((edge_distance / 0.1) * 255.0) as u8
+                }
+            } else {
+                0 // 完全透明
             }
-        }
+        });
     }
 
     /// 检查点是否在心形内部
@@ -426,443 +640,667 @@ impl ShapeMask {
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn test_shape_mask_creation() {
-        let mask = ShapeMask::new(ShapeType::Circle, 100, 100);
-        assert_eq!(mask.width(), 100);
-        assert_eq!(mask.height(), 100);
-        assert_eq!(mask.shape_type(), ShapeType::Circle);
+/// 将 data 按行切分给最多 thread_count 个线程填充
+///
+/// 每个像素只依赖自身坐标，所以无论分成几块，结果都与串行填充一致。
+fn fill_rows<F>(data: &mut [u8], width: u32, height: u32, thread_count: usize, pixel: &F)
+where
+    F: Fn(u32, u32) -> u8 + Sync,
+{
+    if width == 0 || height == 0 {
+        return;
     }
-
-    #[test]
-    fn test_shape_mask_resize() {
-        let mut mask = ShapeMask::new(ShapeType::Circle, 100, 100);
-        mask.resize(200, 200);
-        assert_eq!(mask.width(), 200);
-        assert_eq!(mask.height(), 200);
+    
+    let threads = thread_count.min(height as usize / MIN_ROWS_PER_THREAD).max(1);
+    if threads == 1 {
+        fill_row_range(data, width, 0, pixel);
+        return;
     }
+    
+    let rows_per_chunk = (height as usize).div_ceil(threads);
+    std::thread::scope(|scope| {
+        for (chunk_index, chunk) in data.chunks_mut(rows_per_chunk * width as usize).enumerate() {
+            let first_row = (chunk_index * rows_per_chunk) as u32;
+            scope.spawn(move || fill_row_range(chunk, width, first_row, pixel));
+        }
+    });
+}
 
-    #[test]
-    fn test_shape_mask_set_shape() {
-        let mut mask = ShapeMask::new(ShapeType::Circle, 100, 100);
-        mask.set_shape(ShapeType::Rectangle);
-        assert_eq!(mask.shape_type(), ShapeType::Rectangle);
+/// 从 first_row 开始逐行填充一段连续的行
+fn fill_row_range<F>(chunk: &mut [u8], width: u32, first_row: u32, pixel: &F)
+where
+    F: Fn(u32, u32) -> u8,
+{
+    for (row_offset, row) in chunk.chunks_mut(width as usize).enumerate() {
+        let y = first_row + row_offset as u32;
+        for (x, alpha) in row.iter_mut().enumerate() {
+            *alpha = pixel(x as u32, y);
+        }
     }
+}
 
-    #[test]
-    fn test_circle_mask_generation() {
-        let mask = ShapeMask::new(ShapeType::Circle, 100, 100);
-        let data = mask.data();
-        
-        // 检查中心点应该是不透明的
-        let center_idx = (50 * 100 + 50) as usize;
-        assert_eq!(data[center_idx], 255);
-        
-        // 检查角落应该是透明的
-        let corner_idx = (0 * 100 + 0) as usize;
-        assert_eq!(data[corner_idx], 0);
-    }
+// 遮罩测试用例，分别以串行和并行线程数实例化，确保两条路径都满足同样的断言
+#[cfg(test)]
+macro_rules! mask_tests {
+    ($threads:expr) => {
+        use super::*;
 
-    #[test]
-    fn test_ellipse_mask_generation() {
-        let mask = ShapeMask::new(ShapeType::Ellipse, 100, 50);
-        let data = mask.data();
-        
-        // 检查中心点应该是不透明的
-        let center_idx = (25 * 100 + 50) as usize;
-        assert_eq!(data[center_idx], 255);
-        
-        // 检查角落应该是透明的
-        let corner_idx = (0 * 100 + 0) as usize;
-        assert_eq!(data[corner_idx], 0);
-    }
+        const THREADS: usize = $threads;
 
-    #[test]
-    fn test_rectangle_mask_generation() {
-        let mask = ShapeMask::new(ShapeType::Rectangle, 100, 100);
-        let data = mask.data();
-        
-        // 矩形遮罩所有像素都应该是不透明的
-        for &pixel in data {
-            assert_eq!(pixel, 255);
+        fn new_mask(shape_type: ShapeType, width: u32, height: u32) -> ShapeMask {
+            ShapeMask::with_thread_count(shape_type, width, height, MaskQuality::Fast, THREADS)
         }
-    }
 
-    #[test]
-    fn test_rounded_rectangle_mask_generation() {
-        let mask = ShapeMask::new(ShapeType::RoundedRectangle { radius: 10.0 }, 100, 100);
-        let data = mask.data();
-        
-        // 检查中心点应该是不透明的
-        let center_idx = (50 * 100 + 50) as usize;
-        assert_eq!(data[center_idx], 255);
-        
-        // 检查角落应该是透明的（在圆角半径外）
-        let corner_idx = (0 * 100 + 0) as usize;
-        assert_eq!(data[corner_idx], 0);
-    }
+        fn mask_with_quality(shape_type: ShapeType, width: u32, height: u32, quality: MaskQuality) -> ShapeMask {
+            ShapeMask::with_thread_count(shape_type, width, height, quality, THREADS)
+        }
 
-    #[test]
-    fn test_heart_mask_generation() {
-        let mask = ShapeMask::new(ShapeType::Heart, 100, 100);
-        let data = mask.data();
-        
-        // 检查中心点应该是不透明的
-        let center_idx = (50 * 100 + 50) as usize;
-        assert_eq!(data[center_idx], 255);
-        
-        // 检查角落应该是透明的
-        let corner_idx = (0 * 100 + 0) as usize;
-        assert_eq!(data[corner_idx], 0);
-    }
+        #[test]
+        fn test_shape_mask_creation() {
+            let mask = new_mask(ShapeType::Circle, 100, 100);
+            assert_eq!(mask.width(), 100);
+            assert_eq!(mask.height(), 100);
+            assert_eq!(mask.shape_type(), ShapeType::Circle);
+        }
 
-    #[test]
-    fn test_mask_data_size() {
-        let mask = ShapeMask::new(ShapeType::Circle, 100, 50);
-        assert_eq!(mask.data().len(), 5000); // 100 * 50
-    }
+        #[test]
+        fn test_shape_mask_resize() {
+            let mut mask = new_mask(ShapeType::Circle, 100, 100);
+            mask.resize(200, 200);
+            assert_eq!(mask.width(), 200);
+            assert_eq!(mask.height(), 200);
+        }
 
-    #[test]
-    fn test_shape_switching_performance() {
-        let mut mask = ShapeMask::new(ShapeType::Circle, 400, 400);
-        
-        let start = std::time::Instant::now();
-        mask.set_shape(ShapeType::Heart);
-        let duration = start.elapsed();
-        
-        // 确保切换时间 < 100ms
-        assert!(duration.as_millis() < 100, "Shape switching took {}ms", duration.as_millis());
-    }
+        #[test]
+        fn test_shape_mask_set_shape() {
+            let mut mask = new_mask(ShapeType::Circle, 100, 100);
+            mask.set_shape(ShapeType::Rectangle);
+            assert_eq!(mask.shape_type(), ShapeType::Rectangle);
+        }
 
-    #[test]
-    fn test_all_shapes_generate_valid_data() {
-        let shapes = vec![
-            ShapeType::Circle,
-            ShapeType::Ellipse,
-            ShapeType::Rectangle,
-            ShapeType::RoundedRectangle { radius: 5.0 },
-            ShapeType::Heart,
-        ];
+        #[test]
+        fn test_circle_mask_generation() {
+            let mask = new_mask(ShapeType::Circle, 100, 100);
+            let data = mask.data();
+            
+            // 检查中心点应该是不透明的
+            let center_idx = (50 * 100 + 50) as usize;
+            assert_eq!(data[center_idx], 255);
+            
+            // 检查角落应该是透明的
+            let corner_idx = (0 * 100 + 0) as usize;
+            assert_eq!(data[corner_idx], 0);
+        }
 
-        for shape in shapes {
-            let mask = ShapeMask::new(shape, 100, 100);
+        #[test]
+        fn test_ellipse_mask_generation() {
+            let mask = new_mask(ShapeType::Ellipse, 100, 50);
             let data = mask.data();
             
-            // 检查数据长度正确
-            assert_eq!(data.len(), 10000);
+            // 检查中心点应该是不透明的
+            let center_idx = (25 * 100 + 50) as usize;
+            assert_eq!(data[center_idx], 255);
+            
+            // 检查角落应该是透明的
+            let corner_idx = (0 * 100 + 0) as usize;
+            assert_eq!(data[corner_idx], 0);
+        }
+
+        #[test]
+        fn test_rectangle_mask_generation() {
+            let mask = new_mask(ShapeType::Rectangle, 100, 100);
+            let data = mask.data();
             
-            // 检查所有像素值都是有效的（0-255范围，支持抗锯齿）
+            // 矩形遮罩所有像素都应该是不透明的
             for &pixel in data {
-                assert!(pixel <= 255, "Invalid pixel value: {}", pixel);
+                assert_eq!(pixel, 255);
             }
         }
-    }
 
-    #[test]
-    fn test_circle_vs_ellipse_difference() {
-        // 测试非正方形窗口中圆形和椭圆形的差异
-        let width = 400;
-        let height = 300;
-        
-        let circle_mask = ShapeMask::new(ShapeType::Circle, width, height);
-        let ellipse_mask = ShapeMask::new(ShapeType::Ellipse, width, height);
-        
-        let circle_data = circle_mask.data();
-        let ellipse_data = ellipse_mask.data();
-        
-        // 计算差异像素数量
-        let mut diff_count = 0;
-        for i in 0..circle_data.len() {
-            if circle_data[i] != ellipse_data[i] {
-                diff_count += 1;
-            }
+        #[test]
+        fn test_rounded_rectangle_mask_generation() {
+            let mask = new_mask(ShapeType::RoundedRectangle { radius: 10.0 }, 100, 100);
+            let data = mask.data();
+            
+            // 检查中心点应该是不透明的
+            let center_idx = (50 * 100 + 50) as usize;
+            assert_eq!(data[center_idx], 255);
+            
+            // 检查角落应该是透明的（在圆角半径外）
+            let corner_idx = (0 * 100 + 0) as usize;
+            assert_eq!(data[corner_idx], 0);
         }
-        
-        // 在非正方形窗口中，圆形和椭圆形应该有显著差异
-        let total_pixels = (width * height) as usize;
-        let diff_percentage = (diff_count as f32 / total_pixels as f32) * 100.0;
-        
-        // 至少应该有10%的像素不同
-        assert!(diff_percentage > 10.0, 
-                "圆形和椭圆形差异不够明显: {:.1}%", diff_percentage);
-    }
 
-    #[test]
-    fn test_circle_maintains_aspect_ratio() {
-        // 测试圆形在不同尺寸窗口中保持圆形
-        let test_sizes = vec![(400, 300), (300, 400), (500, 200)];
-        
-        for (width, height) in test_sizes {
-            let mask = ShapeMask::new(ShapeType::Circle, width, height);
+        #[test]
+        fn test_heart_mask_generation() {
+            let mask = new_mask(ShapeType::Heart, 100, 100);
             let data = mask.data();
             
-            let center_x = width as f32 / 2.0;
-            let center_y = height as f32 / 2.0;
-            let expected_radius = (width.min(height) as f32) / 2.0;
+            // 检查中心点应该是不透明的
+            let center_idx = (50 * 100 + 50) as usize;
+            assert_eq!(data[center_idx], 255);
             
-            // 检查圆形边界上的点
-            let test_angles = vec![0.0, std::f32::consts::PI / 2.0, std::f32::consts::PI, 3.0 * std::f32::consts::PI / 2.0];
+            // 检查角落应该是透明的
+            let corner_idx = (0 * 100 + 0) as usize;
+            assert_eq!(data[corner_idx], 0);
+        }
+
+        #[test]
+        fn test_mask_data_size() {
+            let mask = new_mask(ShapeType::Circle, 100, 50);
+            assert_eq!(mask.data().len(), 5000); // 100 * 50
+        }
+
+        #[test]
+        fn test_shape_switching_performance() {
+            let mut mask = new_mask(ShapeType::Circle, 400, 400);
+            
+            let start = std::time::Instant::now();
+            mask.set_shape(ShapeType::Heart);
+            let duration = start.elapsed();
             
-            for angle in test_angles {
-                let test_x = center_x + expected_radius * angle.cos();
-                let test_y = center_y + expected_radius * angle.sin();
+            // 确保切换时间 < 100ms
+            assert!(duration.as_millis() < 100, "Shape switching took {}ms", duration.as_millis());
+        }
+
+        #[test]
+        fn test_all_shapes_generate_valid_data() {
+            let shapes = vec![
+                ShapeType::Circle,
+                ShapeType::Ellipse,
+                ShapeType::Rectangle,
+                ShapeType::RoundedRectangle { radius: 5.0 },
+                ShapeType::Heart,
+            ];
+
+            for shape in shapes {
+                let mask = new_mask(shape, 100, 100);
+                let data = mask.data();
                 
-                if test_x >= 0.0 && test_x < width as f32 && test_y >= 0.0 && test_y < height as f32 {
-                    let pixel_idx = (test_y as u32 * width + test_x as u32) as usize;
-                    // 边界附近的像素应该不是完全透明的
-                    assert!(data[pixel_idx] > 0, 
-                            "圆形边界点 ({:.1}, {:.1}) 应该不透明", test_x, test_y);
+                // 检查数据长度正确
+                assert_eq!(data.len(), 10000);
+                
+                // 检查所有像素值都是有效的（0-255范围，支持抗锯齿）
+                for &pixel in data {
+                    assert!(pixel <= 255, "Invalid pixel value: {}", pixel);
                 }
             }
         }
-    }
 
-    #[test]
-    fn test_ellipse_adapts_to_window_ratio() {
-        // 测试椭圆形适应窗口宽高比
-        let test_sizes = vec![(400, 200), (200, 400)];
-        
-        for (width, height) in test_sizes {
-            let mask = ShapeMask::new(ShapeType::Ellipse, width, height);
-            let data = mask.data();
+        #[test]
+        fn test_circle_vs_ellipse_difference() {
+            // 测试非正方形窗口中圆形和椭圆形的差异
+            let width = 400;
+            let height = 300;
             
-            let center_x = width as f32 / 2.0;
-            let center_y = height as f32 / 2.0;
+            let circle_mask = new_mask(ShapeType::Circle, width, height);
+            let ellipse_mask = new_mask(ShapeType::Ellipse, width, height);
             
-            // 检查椭圆在长轴和短轴方向的边界
-            let long_axis_x = if width > height { width as f32 / 2.0 - 1.0 } else { center_x };
-            let long_axis_y = if height > width { height as f32 / 2.0 - 1.0 } else { center_y };
+            let circle_data = circle_mask.data();
+            let ellipse_data = ellipse_mask.data();
             
-            let long_axis_idx = (long_axis_y as u32 * width + long_axis_x as u32) as usize;
-            assert!(data[long_axis_idx] > 0, 
-                    "椭圆长轴边界点应该不透明");
+            // 计算差异像素数量
+            let mut diff_count = 0;
+            for i in 0..circle_data.len() {
+                if circle_data[i] != ellipse_data[i] {
+                    diff_count += 1;
+                }
+            }
+            
+            // 在非正方形窗口中，圆形和椭圆形应该有显著差异
+            let total_pixels = (width * height) as usize;
+            let diff_percentage = (diff_count as f32 / total_pixels as f32) * 100.0;
             
-            // 检查椭圆外的点应该是透明的
-            let outside_x = if width > height { width - 1 } else { width / 2 };
-            let outside_y = if height > width { height - 1 } else { height / 2 };
+            // 至少应该有10%的像素不同
+            assert!(diff_percentage > 10.0, 
+                    "圆形和椭圆形差异不够明显: {:.1}%", diff_percentage);
+        }
+
+        #[test]
+        fn test_circle_maintains_aspect_ratio() {
+            // 测试圆形在不同尺寸窗口中保持圆形
+            let test_sizes = vec![(400, 300), (300, 400), (500, 200)];
             
-            if width > height {
-                // 宽椭圆，检查上下边界外的点
-                if outside_y < height {
-                    let outside_idx = (outside_y * width + outside_x) as usize;
-                    assert_eq!(data[outside_idx], 0, "椭圆外的点应该透明");
+            for (width, height) in test_sizes {
+                let mask = new_mask(ShapeType::Circle, width, height);
+                let data = mask.data();
+                
+                let center_x = width as f32 / 2.0;
+                let center_y = height as f32 / 2.0;
+                let expected_radius = (width.min(height) as f32) / 2.0;
+                
+                // 检查圆形边界上的点
+                let test_angles = vec![0.0, std::f32::consts::PI / 2.0, std::f32::consts::PI, 3.0 * std::f32::consts::PI / 2.0];
+                
+                for angle in test_angles {
+                    let test_x = center_x + expected_radius * angle.cos();
+                    let test_y = center_y + expected_radius * angle.sin();
+                    
+                    if test_x >= 0.0 && test_x < width as f32 && test_y >= 0.0 && test_y < height as f32 {
+                        let pixel_idx = (test_y as u32 * width + test_x as u32) as usize;
+                        // 边界附近的像素应该不是完全透明的
+                        assert!(data[pixel_idx] > 0, 
+                                "圆形边界点 ({:.1}, {:.1}) 应该不透明", test_x, test_y);
+                    }
                 }
-            } else {
-                // 高椭圆，检查左右边界外的点
-                if outside_x < width {
-                    let outside_idx = (outside_y * width + outside_x) as usize;
-                    assert_eq!(data[outside_idx], 0, "椭圆外的点应该透明");
+            }
+        }
+
+        #[test]
+        fn test_ellipse_adapts_to_window_ratio() {
+            // 测试椭圆形适应窗口宽高比
+            let test_sizes = vec![(400, 200), (200, 400)];
+            
+            for (width, height) in test_sizes {
+                let mask = new_mask(ShapeType::Ellipse, width, height);
+                let data = mask.data();
+                
+                let center_x = width as f32 / 2.0;
+                let center_y = height as f32 / 2.0;
+                
+                // 检查椭圆在长轴和短轴方向的边界
+                let long_axis_x = if width > height { width as f32 / 2.0 - 1.0 } else { center_x };
+                let long_axis_y = if height > width { height as f32 / 2.0 - 1.0 } else { center_y };
+                
+                let long_axis_idx = (long_axis_y as u32 * width + long_axis_x as u32) as usize;
+                assert!(data[long_axis_idx] > 0, 
+                        "椭圆长轴边界点应该不透明");
+                
+                // 检查椭圆外的点应该是透明的
+                let outside_x = if width > height { width - 1 } else { width / 2 };
+                let outside_y = if height > width { height - 1 } else { height / 2 };
+                
+                if width > height {
+                    // 宽椭圆，检查上下边界外的点
+                    if outside_y < height {
+                        let outside_idx = (outside_y * width + outside_x) as usize;
+                        assert_eq!(data[outside_idx], 0, "椭圆外的点应该透明");
+                    }
+                } else {
+                    // 高椭圆，检查左右边界外的点
+                    if outside_x < width {
+                        let outside_idx = (outside_y * width + outside_x) as usize;
+                        assert_eq!(data[outside_idx], 0, "椭圆外的点应该透明");
+                    }
                 }
             }
         }
-    }
 
-    #[test]
-    fn test_heart_size_and_coverage() {
-        // 测试心形大小和覆盖率
-        let test_sizes = vec![(400, 400), (300, 300), (500, 500)];
-        
-        for (width, height) in test_sizes {
-            let mask = ShapeMask::new(ShapeType::Heart, width, height);
-            let data = mask.data();
+        #[test]
+        fn test_heart_size_and_coverage() {
+            // 测试心形大小和覆盖率
+            let test_sizes = vec![(400, 400), (300, 300), (500, 500)];
             
-            // 计算心形覆盖的像素数量
-            let mut covered_pixels = 0;
-            for &pixel in data {
-                if pixel > 0 {
-                    covered_pixels += 1;
+            for (width, height) in test_sizes {
+                let mask = new_mask(ShapeType::Heart, width, height);
+                let data = mask.data();
+                
+                // 计算心形覆盖的像素数量
+                let mut covered_pixels = 0;
+                for &pixel in data {
+                    if pixel > 0 {
+                        covered_pixels += 1;
+                    }
                 }
+                
+                let total_pixels = (width * height) as usize;
+                let coverage_percentage = (covered_pixels as f32 / total_pixels as f32) * 100.0;
+                
+                // 心形应该占据至少60%的窗口面积
+                assert!(coverage_percentage >= 60.0, 
+                        "心形覆盖率不足: {:.1}% (期望 >= 60%)", coverage_percentage);
+                
+                // 但也不应该覆盖过多（不超过80%）
+                assert!(coverage_percentage <= 80.0, 
+                        "心形覆盖率过高: {:.1}% (期望 <= 80%)", coverage_percentage);
             }
+        }
+
+        #[test]
+        fn test_heart_centering() {
+            // 测试心形居中显示
+            let mask = new_mask(ShapeType::Heart, 400, 400);
+            let data = mask.data();
             
-            let total_pixels = (width * height) as usize;
-            let coverage_percentage = (covered_pixels as f32 / total_pixels as f32) * 100.0;
+            let center_x = 200;
+            let center_y = 200;
             
-            // 心形应该占据至少60%的窗口面积
-            assert!(coverage_percentage >= 60.0, 
-                    "心形覆盖率不足: {:.1}% (期望 >= 60%)", coverage_percentage);
+            // 检查中心点应该在心形内部
+            let center_idx = (center_y * 400 + center_x) as usize;
+            assert!(data[center_idx] > 0, "心形中心点应该不透明");
             
-            // 但也不应该覆盖过多（不超过80%）
-            assert!(coverage_percentage <= 80.0, 
-                    "心形覆盖率过高: {:.1}% (期望 <= 80%)", coverage_percentage);
+            // 检查心形的对称性（左右对称）
+            for y in 100..300 {
+                for x_offset in 1..50 {
+                    let left_x = center_x - x_offset;
+                    let right_x = center_x + x_offset;
+                    
+                    if left_x < 400 && right_x < 400 {
+                        let left_idx = (y * 400 + left_x) as usize;
+                        let right_idx = (y * 400 + right_x) as usize;
+                        
+                        // 左右对称点的透明度应该相近（允许一定误差）
+                        let diff = (data[left_idx] as i16 - data[right_idx] as i16).abs();
+                        assert!(diff <= 10, 
+                                "心形左右对称性检查失败，位置 ({}, {}): 左={}, 右={}", 
+                                x_offset, y, data[left_idx], data[right_idx]);
+                    }
+                }
+            }
         }
-    }
 
-    #[test]
-    fn test_heart_centering() {
-        // 测试心形居中显示
-        let mask = ShapeMask::new(ShapeType::Heart, 400, 400);
-        let data = mask.data();
-        
-        let center_x = 200;
-        let center_y = 200;
-        
-        // 检查中心点应该在心形内部
-        let center_idx = (center_y * 400 + center_x) as usize;
-        assert!(data[center_idx] > 0, "心形中心点应该不透明");
-        
-        // 检查心形的对称性（左右对称）
-        for y in 100..300 {
-            for x_offset in 1..50 {
-                let left_x = center_x - x_offset;
-                let right_x = center_x + x_offset;
+        #[test]
+        fn test_heart_proportions() {
+            // 测试心形比例在不同窗口尺寸下的正确性
+            let test_sizes = vec![(300, 400), (400, 300), (600, 400)];
+            
+            for (width, height) in test_sizes {
+                let mask = new_mask(ShapeType::Heart, width, height);
+                let data = mask.data();
                 
-                if left_x < 400 && right_x < 400 {
-                    let left_idx = (y * 400 + left_x) as usize;
-                    let right_idx = (y * 400 + right_x) as usize;
-                    
-                    // 左右对称点的透明度应该相近（允许一定误差）
-                    let diff = (data[left_idx] as i16 - data[right_idx] as i16).abs();
-                    assert!(diff <= 10, 
-                            "心形左右对称性检查失败，位置 ({}, {}): 左={}, 右={}", 
-                            x_offset, y, data[left_idx], data[right_idx]);
+                // 计算心形的边界框
+                let mut min_x = width;
+                let mut max_x = 0;
+                let mut min_y = height;
+                let mut max_y = 0;
+                
+                for y in 0..height {
+                    for x in 0..width {
+                        let idx = (y * width + x) as usize;
+                        if data[idx] > 0 {
+                            min_x = min_x.min(x);
+                            max_x = max_x.max(x);
+                            min_y = min_y.min(y);
+                            max_y = max_y.max(y);
+                        }
+                    }
                 }
+                
+                let heart_width = max_x - min_x;
+                let heart_height = max_y - min_y;
+                
+                // 心形应该基于较小边进行缩放，保持合理的宽高比
+                let expected_size = width.min(height) as f32 * 0.8; // 约80%的较小边
+                let actual_size = heart_width.max(heart_height) as f32;
+                
+                let size_ratio = actual_size / expected_size;
+                assert!(size_ratio >= 0.8 && size_ratio <= 1.2, 
+                        "心形尺寸比例不正确: {:.2} (期望接近1.0)", size_ratio);
             }
         }
-    }
 
-    #[test]
-    fn test_heart_proportions() {
-        // 测试心形比例在不同窗口尺寸下的正确性
-        let test_sizes = vec![(300, 400), (400, 300), (600, 400)];
-        
-        for (width, height) in test_sizes {
-            let mask = ShapeMask::new(ShapeType::Heart, width, height);
-            let data = mask.data();
+        #[test]
+        fn test_edge_smoothness() {
+            // 测试所有形状的边缘平滑度
+            let shapes = vec![
+                ShapeType::Circle,
+                ShapeType::Ellipse,
+                ShapeType::RoundedRectangle { radius: 10.0 },
+                ShapeType::Heart,
+            ];
             
-            // 计算心形的边界框
-            let mut min_x = width;
-            let mut max_x = 0;
-            let mut min_y = height;
-            let mut max_y = 0;
-            
-            for y in 0..height {
-                for x in 0..width {
-                    let idx = (y * width + x) as usize;
-                    if data[idx] > 0 {
-                        min_x = min_x.min(x);
-                        max_x = max_x.max(x);
-                        min_y = min_y.min(y);
-                        max_y = max_y.max(y);
+            for shape in shapes {
+                let mask = new_mask(shape, 200, 200);
+                let data = mask.data();
+                
+                // 计算边缘像素的数量（alpha值在1-254之间的像素）
+                let mut edge_pixels = 0;
+                let mut total_edge_transitions = 0;
+                
+                for y in 1..(200-1) {
+                    for x in 1..(200-1) {
+                        let idx = (y * 200 + x) as usize;
+                        let current = data[idx];
+                        
+                        // 检查是否是边缘像素（有抗锯齿效果）
+                        if current > 0 && current < 255 {
+                            edge_pixels += 1;
+                        }
+                        
+                        // 检查与邻居的过渡是否平滑
+                        let neighbors = [
+                            data[((y-1) * 200 + x) as usize],
+                            data[((y+1) * 200 + x) as usize],
+                            data[(y * 200 + (x-1)) as usize],
+                            data[(y * 200 + (x+1)) as usize],
+                        ];
+                        
+                        for &neighbor in &neighbors {
+                            let diff = (current as i16 - neighbor as i16).abs();
+                            if diff > 128 {
+                                total_edge_transitions += 1;
+                            }
+                        }
                     }
                 }
+                
+                // 应该有一定数量的边缘像素（表示有抗锯齿）
+                assert!(edge_pixels > 0, "形状 {:?} 应该有抗锯齿边缘像素", shape);
+                
+                // 急剧的边缘过渡应该相对较少（表示边缘平滑）
+                let total_pixels = 200 * 200;
+                let harsh_transition_ratio = total_edge_transitions as f32 / total_pixels as f32;
+                assert!(harsh_transition_ratio < 0.1, 
+                        "形状 {:?} 的急剧边缘过渡过多: {:.3}", shape, harsh_transition_ratio);
             }
+        }
+
+        #[test]
+        fn test_high_quality_generation() {
+            // 测试高质量生成模式
+            let mut mask = new_mask(ShapeType::Circle, 100, 100);
             
-            let heart_width = max_x - min_x;
-            let heart_height = max_y - min_y;
+            // 生成高质量版本
+            mask.generate_high_quality();
+            let hq_data = mask.data();
             
-            // 心形应该基于较小边进行缩放，保持合理的宽高比
-            let expected_size = width.min(height) as f32 * 0.8; // 约80%的较小边
-            let actual_size = heart_width.max(heart_height) as f32;
+            // 生成标准版本进行比较
+            mask.generate();
+            let std_data = mask.data();
             
-            let size_ratio = actual_size / expected_size;
-            assert!(size_ratio >= 0.8 && size_ratio <= 1.2, 
-                    "心形尺寸比例不正确: {:.2} (期望接近1.0)", size_ratio);
+            // 高质量版本应该有更多的中间alpha值（更平滑的边缘）
+            let mut hq_intermediate_pixels = 0;
+            let mut std_intermediate_pixels = 0;
+            
+            for i in 0..hq_data.len() {
+                if hq_data[i] > 0 && hq_data[i] < 255 {
+                    hq_intermediate_pixels += 1;
+                }
+                if std_data[i] > 0 && std_data[i] < 255 {
+                    std_intermediate_pixels += 1;
+                }
+            }
+            
+            // 高质量版本应该有至少相同数量的中间像素
+            assert!(hq_intermediate_pixels >= std_intermediate_pixels,
+                    "高质量版本应该有更多的抗锯齿像素: {} vs {}", 
+                    hq_intermediate_pixels, std_intermediate_pixels);
         }
+
+        #[test]
+        fn test_antialiasing_performance() {
+            // 测试抗锯齿不会显著影响性能
+            let mut mask = new_mask(ShapeType::Heart, 400, 400);
+            
+            let start = std::time::Instant::now();
+            mask.generate_high_quality();
+            let duration = start.elapsed();
+            
+            // 即使是高质量生成，也应该在合理时间内完成（< 200ms）
+            assert!(duration.as_millis() < 200, 
+                    "高质量形状生成耗时过长: {}ms", duration.as_millis());
+        }
+
+        #[test]
+        fn test_quality_levels_keep_shape_coverage() {
+            for quality in MaskQuality::ALL {
+                let mask = mask_with_quality(ShapeType::Circle, 100, 100, quality);
+                let data = mask.data();
+                assert_eq!(data.len(), 100 * 100);
+                assert_eq!(data[50 * 100 + 50], 255, "{:?} 质量下中心应不透明", quality);
+                assert_eq!(data[0], 0, "{:?} 质量下角落应透明", quality);
+            }
+
+            // 超采样不应使矩形的图像边框变暗
+            let mask = mask_with_quality(ShapeType::Rectangle, 50, 50, MaskQuality::High);
+            assert!(mask.data().iter().all(|&alpha| alpha == 255));
+        }
+
+        #[test]
+        fn test_higher_quality_smooths_edges() {
+            let count_intermediate = |data: &[u8]| data.iter().filter(|&&a| a > 0 && a < 255).count();
+
+            let fast = mask_with_quality(ShapeType::Heart, 200, 200, MaskQuality::Fast);
+            let high = mask_with_quality(ShapeType::Heart, 200, 200, MaskQuality::High);
+
+            assert!(count_intermediate(high.data()) > count_intermediate(fast.data()),
+                    "高质量遮罩应有更多边缘过渡像素");
+        }
+
+        #[test]
+        fn test_adaptive_quality_follows_interaction() {
+            let mut mask = mask_with_quality(ShapeType::Circle, 100, 100, MaskQuality::Adaptive);
+            assert_eq!(mask.effective_quality(), MaskQuality::High);
+
+            // 开始拖拽/缩放时降级为快速并重新生成
+            assert!(mask.set_interacting(true));
+            assert!(mask.is_interacting());
+            assert_eq!(mask.effective_quality(), MaskQuality::Fast);
+            let fast_data = new_mask(ShapeType::Circle, 100, 100).data().to_vec();
+            assert_eq!(mask.data(), fast_data.as_slice());
+
+            // 重复设置不会重新生成
+            assert!(!mask.set_interacting(true));
+
+            // 空闲后恢复高质量
+            assert!(mask.set_interacting(false));
+            assert_eq!(mask.effective_quality(), MaskQuality::High);
+        }
+
+        #[test]
+        fn test_fixed_quality_ignores_interaction() {
+            let mut mask = mask_with_quality(ShapeType::Ellipse, 100, 80, MaskQuality::Balanced);
+            assert!(!mask.set_interacting(true));
+            assert_eq!(mask.effective_quality(), MaskQuality::Balanced);
+
+            assert!(mask.set_quality(MaskQuality::High));
+            assert_eq!(mask.quality(), MaskQuality::High);
+            assert!(!mask.set_quality(MaskQuality::High));
+        }
+
+        #[test]
+        fn test_mask_cache_reuses_previous_sizes() {
+            let mut mask = mask_with_quality(ShapeType::Heart, 200, 200, MaskQuality::High);
+            let original = mask.data().to_vec();
+            assert_eq!(mask.cache_stats().misses, 1);
+
+            // 模拟滚轮缩放后回到原尺寸
+            mask.resize(220, 220);
+            mask.resize(200, 200);
+
+            let stats = mask.cache_stats();
+            assert_eq!(stats.hits, 1);
+            assert_eq!(stats.misses, 2);
+            assert_eq!(stats.entries, 2);
+            assert_eq!(mask.data(), original.as_slice());
+        }
+
+        #[test]
+        fn test_cache_key_distinguishes_parameters() {
+            let small = new_mask(ShapeType::RoundedRectangle { radius: 10.0 }, 100, 100);
+            let large = new_mask(ShapeType::RoundedRectangle { radius: 20.0 }, 100, 100);
+            assert_ne!(small.cache_key(), large.cache_key());
+
+            let fast = mask_with_quality(ShapeType::Circle, 100, 100, MaskQuality::Fast);
+            let high = mask_with_quality(ShapeType::Circle, 100, 100, MaskQuality::High);
+            assert_ne!(fast.cache_key(), high.cache_key());
+            assert_eq!(fast.cache_key().size_bytes(), 100 * 100);
+        }
+
+        #[test]
+        fn test_disabled_cache_matches_cached_output() {
+            let mut cached = mask_with_quality(ShapeType::Ellipse, 120, 80, MaskQuality::Balanced);
+            let mut uncached = mask_with_quality(ShapeType::Ellipse, 120, 80, MaskQuality::Balanced);
+            uncached.set_cache_budget(0);
+
+            cached.set_shape(ShapeType::Circle);
+            cached.set_shape(ShapeType::Ellipse);
+            uncached.set_shape(ShapeType::Circle);
+            uncached.set_shape(ShapeType::Ellipse);
+
+            assert_eq!(cached.data(), uncached.data());
+            assert_eq!(uncached.cache_stats().entries, 0);
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod serial {
+        mask_tests!(1);
+    }
+
+    mod parallel {
+        mask_tests!(4);
     }
 
     #[test]
-    fn test_edge_smoothness() {
-        // 测试所有形状的边缘平滑度
-        let shapes = vec![
+    fn test_mask_quality_names() {
+        for quality in MaskQuality::ALL {
+            assert_eq!(MaskQuality::from_name(quality.as_str()), Some(quality));
+        }
+        assert_eq!(MaskQuality::from_name("Ultra"), None);
+        assert_eq!(MaskQuality::default(), MaskQuality::Balanced);
+    }
+
+    #[test]
+    fn test_parallel_output_matches_serial() {
+        let shapes = [
             ShapeType::Circle,
             ShapeType::Ellipse,
-            ShapeType::RoundedRectangle { radius: 10.0 },
+            ShapeType::Rectangle,
+            ShapeType::RoundedRectangle { radius: 12.5 },
             ShapeType::Heart,
         ];
-        
+        // 包含无法整除线程数的行数以及比线程数还少的行数
+        let sizes = [(97, 61), (200, 200), (3, 200), (1, 1), (64, 2)];
+
         for shape in shapes {
-            let mask = ShapeMask::new(shape, 200, 200);
-            let data = mask.data();
-            
-            // 计算边缘像素的数量（alpha值在1-254之间的像素）
-            let mut edge_pixels = 0;
-            let mut total_edge_transitions = 0;
-            
-            for y in 1..(200-1) {
-                for x in 1..(200-1) {
-                    let idx = (y * 200 + x) as usize;
-                    let current = data[idx];
-                    
-                    // 检查是否是边缘像素（有抗锯齿效果）
-                    if current > 0 && current < 255 {
-                        edge_pixels += 1;
-                    }
-                    
-                    // 检查与邻居的过渡是否平滑
-                    let neighbors = [
-                        data[((y-1) * 200 + x) as usize],
-                        data[((y+1) * 200 + x) as usize],
-                        data[(y * 200 + (x-1)) as usize],
-                        data[(y * 200 + (x+1)) as usize],
-                    ];
-                    
-                    for &neighbor in &neighbors {
-                        let diff = (current as i16 - neighbor as i16).abs();
-                        if diff > 128 {
-                            total_edge_transitions += 1;
-                        }
+            for quality in MaskQuality::ALL {
+                for (width, height) in sizes {
+                    let serial = ShapeMask::with_thread_count(shape, width, height, quality, 1);
+                    for threads in [2, 3, 8] {
+                        let parallel = ShapeMask::with_thread_count(shape, width, height, quality, threads);
+                        assert!(serial.data() == parallel.data(),
+                                "{:?} {:?} {}x{} 在 {} 线程下与串行结果不一致",
+                                shape, quality, width, height, threads);
                     }
                 }
             }
-            
-            // 应该有一定数量的边缘像素（表示有抗锯齿）
-            assert!(edge_pixels > 0, "形状 {:?} 应该有抗锯齿边缘像素", shape);
-            
-            // 急剧的边缘过渡应该相对较少（表示边缘平滑）
-            let total_pixels = 200 * 200;
-            let harsh_transition_ratio = total_edge_transitions as f32 / total_pixels as f32;
-            assert!(harsh_transition_ratio < 0.1, 
-                    "形状 {:?} 的急剧边缘过渡过多: {:.3}", shape, harsh_transition_ratio);
         }
     }
 
     #[test]
-    fn test_high_quality_generation() {
-        // 测试高质量生成模式
-        let mut mask = ShapeMask::new(ShapeType::Circle, 100, 100);
-        
-        // 生成高质量版本
-        mask.generate_high_quality();
-        let hq_data = mask.data();
-        
-        // 生成标准版本进行比较
-        mask.generate();
-        let std_data = mask.data();
-        
-        // 高质量版本应该有更多的中间alpha值（更平滑的边缘）
-        let mut hq_intermediate_pixels = 0;
-        let mut std_intermediate_pixels = 0;
-        
-        for i in 0..hq_data.len() {
-            if hq_data[i] > 0 && hq_data[i] < 255 {
-                hq_intermediate_pixels += 1;
-            }
-            if std_data[i] > 0 && std_data[i] < 255 {
-                std_intermediate_pixels += 1;
-            }
-        }
-        
-        // 高质量版本应该有至少相同数量的中间像素
-        assert!(hq_intermediate_pixels >= std_intermediate_pixels,
-                "高质量版本应该有更多的抗锯齿像素: {} vs {}", 
-                hq_intermediate_pixels, std_intermediate_pixels);
+    fn test_parallel_blur_matches_serial() {
+        let mut serial = ShapeMask::with_thread_count(ShapeType::Heart, 150, 130, MaskQuality::Fast, 1);
+        let mut parallel = ShapeMask::with_thread_count(ShapeType::Heart, 150, 130, MaskQuality::Fast, 6);
+
+        serial.generate_high_quality();
+        parallel.generate_high_quality();
+        assert_eq!(serial.data(), parallel.data());
     }
 
     #[test]
-    fn test_antialiasing_performance() {
-        // 测试抗锯齿不会显著影响性能
-        let mut mask = ShapeMask::new(ShapeType::Heart, 400, 400);
-        
-        let start = std::time::Instant::now();
-        mask.generate_high_quality();
-        let duration = start.elapsed();
-        
-        // 即使是高质量生成，也应该在合理时间内完成（< 200ms）
-        assert!(duration.as_millis() < 200, 
-                "高质量形状生成耗时过长: {}ms", duration.as_millis());
+    fn test_thread_count_is_at_least_one() {
+        let mut mask = ShapeMask::with_thread_count(ShapeType::Circle, 10, 10, MaskQuality::Fast, 0);
+        assert_eq!(mask.thread_count(), 1);
+        mask.set_thread_count(4);
+        assert_eq!(mask.thread_count(), 4);
+        mask.set_thread_count(0);
+        assert_eq!(mask.thread_count(), 1);
+
+        assert!(ShapeMask::new(ShapeType::Circle, 10, 10).thread_count() >= 1);
     }
 }
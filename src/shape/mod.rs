@@ -2,4 +2,4 @@
 
 pub mod mask;
 
-pub use mask::{ShapeMask, ShapeType};
+pub use mask::{MaskCacheKey, MaskQuality, ShapeMask, ShapeType, DEFAULT_MASK_CACHE_BUDGET};
@@ -0,0 +1,241 @@
+// 快照模块
+//
+// 快照可以立即拍摄，也可以先倒计时：倒计时期间每秒轻闪一下提示，拍摄时全强度闪光后渐隐。
+// 画面由 RenderEngine 回读（见 render/capture.rs），合成画面保留形状之外的透明区域；
+// PNG 编码和写入文件在后台线程进行，不阻塞渲染。文件名带本地时间戳，同名文件已存在时追加序号。
+
+use crate::render::capture::{CaptureSource, CapturedFrame};
+use chrono::{DateTime, Local};
+use log::{error, info};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// 拍摄时快门闪光的渐隐时间
+pub const FLASH_DURATION: Duration = Duration::from_millis(300);
+
+/// 倒计时每秒提示闪光的持续时间和强度
+const COUNTDOWN_PULSE: Duration = Duration::from_millis(150);
+const COUNTDOWN_PULSE_INTENSITY: f32 = 0.3;
+
+/// 快照计时：倒计时和快门闪光
+#[derive(Debug, Default)]
+pub struct SnapshotTimer {
+    // 倒计时开始时间和拍摄时间
+    countdown: Option<(Instant, Instant)>,
+    flash_started: Option<Instant>,
+}
+
+impl SnapshotTimer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 开始拍摄；countdown 为零时在下一次 update 时立即拍摄。倒计时进行中时返回 false
+    pub fn trigger(&mut self, now: Instant, countdown: Duration) -> bool {
+        if self.countdown.is_some() {
+            return false;
+        }
+        self.countdown = Some((now, now + countdown));
+        true
+    }
+
+    /// 取消倒计时，返回是否有进行中的倒计时
+    pub fn cancel(&mut self) -> bool {
+        self.countdown.take().is_some()
+    }
+
+    /// 到达拍摄时间时返回 true，并开始快门闪光
+    pub fn update(&mut self, now: Instant) -> bool {
+        match self.countdown {
+            Some((_, shoot_at)) if now >= shoot_at => {
+                self.countdown = None;
+                self.flash_started = Some(now);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// 倒计时剩余的整秒数（向上取整），没有倒计时时为 None
+    pub fn remaining_seconds(&self, now: Instant) -> Option<u64> {
+        let (_, shoot_at) = self.countdown?;
+        let remaining = shoot_at.saturating_duration_since(now);
+        Some(remaining.as_millis().div_ceil(1000) as u64)
+    }
+
+    /// 当前的闪光强度（0-1）
+    pub fn flash_intensity(&self, now: Instant) -> f32 {
+        if let Some(started) = self.flash_started {
+            let elapsed = now.saturating_duration_since(started);
+            if elapsed < FLASH_DURATION {
+                return 1.0 - elapsed.as_secs_f32() / FLASH_DURATION.as_secs_f32();
+            }
+        }
+        if let Some((started, _)) = self.countdown {
+            let into_second = now.saturating_duration_since(started).as_millis() % 1000;
+            if into_second < COUNTDOWN_PULSE.as_millis() {
+                return COUNTDOWN_PULSE_INTENSITY * (1.0 - into_second as f32 / COUNTDOWN_PULSE.as_millis() as f32);
+            }
+        }
+        0.0
+    }
+
+    /// 倒计时或闪光进行中，需要逐帧重绘
+    pub fn is_animating(&self, now: Instant) -> bool {
+        self.countdown.is_some()
+            || self.flash_started.is_some_and(|started| now.saturating_duration_since(started) < FLASH_DURATION)
+    }
+}
+
+/// 快照保存目录：配置为空时使用图片目录下的 Mira 文件夹
+pub fn snapshot_directory(configured: &str) -> PathBuf {
+    if !configured.is_empty() {
+        return PathBuf::from(configured);
+    }
+    let home = if cfg!(target_os = "windows") {
+        std::env::var("USERPROFILE")
+    } else {
+        std::env::var("HOME")
+    };
+    match home {
+        Ok(home) => [home.as_str(), "Pictures", "Mira"].iter().collect(),
+        Err(_) => PathBuf::from("snapshots"),
+    }
+}
+
+/// 带时间戳的快照文件名，原始画面加 -raw 后缀
+pub fn snapshot_file_name(time: DateTime<Local>, source: CaptureSource) -> String {
+    let suffix = match source {
+        CaptureSource::Composited => "",
+        CaptureSource::Raw => "-raw",
+    };
+    format!("mira-{}{}.png", time.format("%Y%m%d-%H%M%S-%3f"), suffix)
+}
+
+/// 目录中不与已有文件重名的路径
+fn unique_path(directory: &Path, file_name: &str) -> PathBuf {
+    let path = directory.join(file_name);
+    if !path.exists() {
+        return path;
+    }
+    let stem = file_name.trim_end_matches(".png");
+    (1..)
+        .map(|index| directory.join(format!("{}-{}.png", stem, index)))
+        .find(|path| !path.exists())
+        .unwrap()
+}
+
+/// 把回读的画面保存为带透明度的 PNG，返回文件路径
+pub fn save_png(frame: &CapturedFrame, directory: &Path, time: DateTime<Local>) -> Result<PathBuf, String> {
+    std::fs::create_dir_all(directory).map_err(|e| format!("无法创建快照目录 {:?}: {}", directory, e))?;
+    let path = unique_path(directory, &snapshot_file_name(time, frame.source));
+    image::save_buffer(&path, &frame.rgba, frame.width, frame.height, image::ColorType::Rgba8)
+        .map_err(|e| format!("写入快照 {:?} 失败: {}", path, e))?;
+    Ok(path)
+}
+
+/// 在后台线程中保存快照，结果只记录日志
+pub fn save_in_background(frame: CapturedFrame, directory: PathBuf) {
+    let time = Local::now();
+    let spawned = std::thread::Builder::new()
+        .name("snapshot-writer".to_string())
+        .spawn(move || match save_png(&frame, &directory, time) {
+            Ok(path) => info!("快照已保存: {:?}（{}x{}，{}）", path, frame.width, frame.height, frame.source.name()),
+            Err(e) => error!("保存快照失败: {}", e),
+        });
+    if let Err(e) = spawned {
+        error!("启动快照保存线程失败: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_immediate_snapshot() {
+        let mut timer = SnapshotTimer::new();
+        let now = Instant::now();
+        assert!(!timer.update(now));
+        assert!(!timer.is_animating(now));
+
+        assert!(timer.trigger(now, Duration::ZERO));
+        assert!(timer.update(now));
+        // 拍摄后闪光渐隐
+        assert_eq!(timer.flash_intensity(now), 1.0);
+        let half = now + FLASH_DURATION / 2;
+        assert!((timer.flash_intensity(half) - 0.5).abs() < 0.01);
+        assert!(timer.is_animating(half));
+        assert_eq!(timer.flash_intensity(now + FLASH_DURATION), 0.0);
+        assert!(!timer.is_animating(now + FLASH_DURATION));
+        assert!(!timer.update(now + FLASH_DURATION));
+    }
+
+    #[test]
+    fn test_countdown() {
+        let mut timer = SnapshotTimer::new();
+        let now = Instant::now();
+        assert!(timer.trigger(now, Duration::from_secs(3)));
+        // 倒计时进行中再次触发无效
+        assert!(!timer.trigger(now + Duration::from_millis(500), Duration::ZERO));
+
+        assert_eq!(timer.remaining_seconds(now), Some(3));
+        assert_eq!(timer.remaining_seconds(now + Duration::from_millis(1500)), Some(2));
+        assert!(timer.is_animating(now + Duration::from_millis(1500)));
+
+        // 每秒开始时轻闪提示
+        assert!(timer.flash_intensity(now + Duration::from_secs(1)) > 0.0);
+        assert!(timer.flash_intensity(now + Duration::from_secs(1)) < 1.0);
+        assert_eq!(timer.flash_intensity(now + Duration::from_millis(1500)), 0.0);
+
+        assert!(!timer.update(now + Duration::from_millis(2999)));
+        assert!(timer.update(now + Duration::from_secs(3)));
+        assert_eq!(timer.remaining_seconds(now + Duration::from_secs(3)), None);
+        assert_eq!(timer.flash_intensity(now + Duration::from_secs(3)), 1.0);
+    }
+
+    #[test]
+    fn test_cancel_countdown() {
+        let mut timer = SnapshotTimer::new();
+        let now = Instant::now();
+        assert!(!timer.cancel());
+        timer.trigger(now, Duration::from_secs(2));
+        assert!(timer.cancel());
+        assert!(!timer.update(now + Duration::from_secs(5)));
+        assert!(!timer.is_animating(now));
+    }
+
+    #[test]
+    fn test_snapshot_file_name() {
+        let time = Local.with_ymd_and_hms(2024, 3, 9, 14, 5, 7).unwrap() + chrono::Duration::milliseconds(42);
+        assert_eq!(snapshot_file_name(time, CaptureSource::Composited), "mira-20240309-140507-042.png");
+        assert_eq!(snapshot_file_name(time, CaptureSource::Raw), "mira-20240309-140507-042-raw.png");
+        assert_eq!(snapshot_directory("/tmp/shots"), PathBuf::from("/tmp/shots"));
+        let default = snapshot_directory("");
+        assert!(default.ends_with("Mira") || default == Path::new("snapshots"));
+    }
+
+    #[test]
+    fn test_save_png() {
+        let directory = tempfile::tempdir().unwrap();
+        let frame = CapturedFrame {
+            source: CaptureSource::Composited,
+            width: 2,
+            height: 1,
+            rgba: vec![255, 0, 0, 255, 0, 0, 0, 0],
+            captured_at: Instant::now(),
+        };
+        let time = Local::now();
+        let nested = directory.path().join("snapshots");
+        let first = save_png(&frame, &nested, time).unwrap();
+        let second = save_png(&frame, &nested, time).unwrap();
+        assert_ne!(first, second);
+
+        // 形状之外的透明像素原样保存
+        let image = image::open(&first).unwrap().to_rgba8();
+        assert_eq!(image.dimensions(), (2, 1));
+        assert_eq!(image.get_pixel(0, 0).0, [255, 0, 0, 255]);
+        assert_eq!(image.get_pixel(1, 0).0, [0, 0, 0, 0]);
+    }
+}
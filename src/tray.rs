@@ -2,9 +2,11 @@
 //
 // 提供系统托盘图标和右键菜单功能
 
+use crate::render::color::ColorPreset;
+use crate::shape::MaskQuality;
 use log::{debug, info, warn};
 use tray_icon::{
-    menu::{Menu, MenuEvent, MenuItem, PredefinedMenuItem, Submenu},
+    menu::{CheckMenuItem, Menu, MenuEvent, MenuItem, PredefinedMenuItem, Submenu},
     Icon, TrayIcon, TrayIconBuilder,
 };
 
@@ -26,6 +28,39 @@ pub struct TrayManager {
     
     rotate_left: MenuItem,
     rotate_right: MenuItem,
+    rotate_video_left: MenuItem,
+    rotate_video_right: MenuItem,
+    keep_video_upright: CheckMenuItem,
+    
+    // 遮罩质量菜单项（与 MaskQuality::ALL 顺序一致）
+    quality_items: Vec<(MaskQuality, MenuItem)>,
+    
+    // 颜色滤镜菜单项（与 ColorPreset::ALL 顺序一致）
+    filter_items: Vec<(ColorPreset, MenuItem)>,
+    lut_enabled: CheckMenuItem,
+    chroma_key: CheckMenuItem,
+    effects: CheckMenuItem,
+    
+    // 降噪菜单项
+    denoise_smoothing: CheckMenuItem,
+    denoise_temporal: CheckMenuItem,
+    denoise_bypass: CheckMenuItem,
+    
+    // 画面校正菜单项
+    auto_white_balance: CheckMenuItem,
+    auto_levels: CheckMenuItem,
+    low_light_boost: CheckMenuItem,
+    
+    // 叠加层菜单项
+    text_overlay: CheckMenuItem,
+    stickers: CheckMenuItem,
+    annotation_mode: CheckMenuItem,
+    clear_annotations: MenuItem,
+    
+    // 快照与录制菜单项
+    take_snapshot: MenuItem,
+    snapshot_raw_frame: CheckMenuItem,
+    recording: CheckMenuItem,
     
     show_info: MenuItem,
     quit: MenuItem,
@@ -113,13 +148,101 @@ impl TrayManager {
         let rotate_menu = Submenu::new("旋转控制", true);
         let rotate_left = MenuItem::new("逆时针旋转 15° (Ctrl+滚轮下)", true, None);
         let rotate_right = MenuItem::new("顺时针旋转 15° (Ctrl+滚轮上)", true, None);
+        let rotate_video_left = MenuItem::new("视频逆时针旋转 15° (Alt+滚轮下)", true, None);
+        let rotate_video_right = MenuItem::new("视频顺时针旋转 15° (Alt+滚轮上)", true, None);
+        let keep_video_upright = CheckMenuItem::new("保持视频正向 (U)", true, false, None);
         
         rotate_menu.append(&rotate_left).map_err(|e| format!("添加菜单项失败: {}", e))?;
         rotate_menu.append(&rotate_right).map_err(|e| format!("添加菜单项失败: {}", e))?;
+        rotate_menu.append(&PredefinedMenuItem::separator()).map_err(|e| format!("添加分隔符失败: {}", e))?;
+        rotate_menu.append(&rotate_video_left).map_err(|e| format!("添加菜单项失败: {}", e))?;
+        rotate_menu.append(&rotate_video_right).map_err(|e| format!("添加菜单项失败: {}", e))?;
+        rotate_menu.append(&keep_video_upright).map_err(|e| format!("添加菜单项失败: {}", e))?;
         
         menu.append(&rotate_menu).map_err(|e| format!("添加子菜单失败: {}", e))?;
         menu.append(&PredefinedMenuItem::separator()).map_err(|e| format!("添加分隔符失败: {}", e))?;
         
+        // 遮罩质量子菜单
+        let quality_menu = Submenu::new("遮罩质量", true);
+        let mut quality_items = Vec::with_capacity(MaskQuality::ALL.len());
+        for quality in MaskQuality::ALL {
+            let item = MenuItem::new(quality.display_name(), true, None);
+            quality_menu.append(&item).map_err(|e| format!("添加菜单项失败: {}", e))?;
+            quality_items.push((quality, item));
+        }
+        
+        menu.append(&quality_menu).map_err(|e| format!("添加子菜单失败: {}", e))?;
+        
+        // 颜色滤镜子菜单
+        let filter_menu = Submenu::new("颜色滤镜", true);
+        let mut filter_items = Vec::with_capacity(ColorPreset::ALL.len());
+        for preset in ColorPreset::ALL {
+            let item = MenuItem::new(preset.display_name(), true, None);
+            filter_menu.append(&item).map_err(|e| format!("添加菜单项失败: {}", e))?;
+            filter_items.push((preset, item));
+        }
+        let lut_enabled = CheckMenuItem::new("LUT 滤镜", true, false, None);
+        filter_menu.append(&PredefinedMenuItem::separator()).map_err(|e| format!("添加分隔符失败: {}", e))?;
+        filter_menu.append(&lut_enabled).map_err(|e| format!("添加菜单项失败: {}", e))?;
+        let chroma_key = CheckMenuItem::new("绿幕抠像 (K)", true, false, None);
+        filter_menu.append(&chroma_key).map_err(|e| format!("添加菜单项失败: {}", e))?;
+        let effects = CheckMenuItem::new("特效链 (E)", true, false, None);
+        filter_menu.append(&effects).map_err(|e| format!("添加菜单项失败: {}", e))?;
+        
+        menu.append(&filter_menu).map_err(|e| format!("添加子菜单失败: {}", e))?;
+        
+        // 降噪子菜单
+        let denoise_menu = Submenu::new("降噪", true);
+        let denoise_smoothing = CheckMenuItem::new("磨皮平滑", true, false, None);
+        let denoise_temporal = CheckMenuItem::new("时域降噪", true, false, None);
+        let denoise_bypass = CheckMenuItem::new("旁路降噪 (D)", true, false, None);
+        denoise_menu.append(&denoise_smoothing).map_err(|e| format!("添加菜单项失败: {}", e))?;
+        denoise_menu.append(&denoise_temporal).map_err(|e| format!("添加菜单项失败: {}", e))?;
+        denoise_menu.append(&PredefinedMenuItem::separator()).map_err(|e| format!("添加分隔符失败: {}", e))?;
+        denoise_menu.append(&denoise_bypass).map_err(|e| format!("添加菜单项失败: {}", e))?;
+        
+        menu.append(&denoise_menu).map_err(|e| format!("添加子菜单失败: {}", e))?;
+        
+        // 画面校正子菜单
+        let auto_correct_menu = Submenu::new("画面校正", true);
+        let auto_white_balance = CheckMenuItem::new("自动白平衡", true, false, None);
+        let auto_levels = CheckMenuItem::new("自动色阶", true, false, None);
+        let low_light_boost = CheckMenuItem::new("弱光增强", true, false, None);
+        auto_correct_menu.append(&auto_white_balance).map_err(|e| format!("添加菜单项失败: {}", e))?;
+        auto_correct_menu.append(&auto_levels).map_err(|e| format!("添加菜单项失败: {}", e))?;
+        auto_correct_menu.append(&low_light_boost).map_err(|e| format!("添加菜单项失败: {}", e))?;
+        
+        menu.append(&auto_correct_menu).map_err(|e| format!("添加子菜单失败: {}", e))?;
+        
+        // 叠加层子菜单
+        let overlay_menu = Submenu::new("叠加层", true);
+        let text_overlay = CheckMenuItem::new("文字叠加 (T)", true, false, None);
+        let stickers = CheckMenuItem::new("贴纸 (S)", true, false, None);
+        overlay_menu.append(&text_overlay).map_err(|e| format!("添加菜单项失败: {}", e))?;
+        overlay_menu.append(&stickers).map_err(|e| format!("添加菜单项失败: {}", e))?;
+        
+        // 手绘标注
+        let annotation_mode = CheckMenuItem::new("标注模式 (P)", true, false, None);
+        let clear_annotations = MenuItem::new("清除标注 (C)", true, None);
+        overlay_menu.append(&PredefinedMenuItem::separator()).map_err(|e| format!("添加分隔符失败: {}", e))?;
+        overlay_menu.append(&annotation_mode).map_err(|e| format!("添加菜单项失败: {}", e))?;
+        overlay_menu.append(&clear_annotations).map_err(|e| format!("添加菜单项失败: {}", e))?;
+        
+        menu.append(&overlay_menu).map_err(|e| format!("添加子菜单失败: {}", e))?;
+        
+        // 快照与录制子菜单
+        let snapshot_menu = Submenu::new("快照与录制", true);
+        let take_snapshot = MenuItem::new("拍摄快照 (F12)", true, None);
+        let snapshot_raw_frame = CheckMenuItem::new("保存原始画面", true, false, None);
+        let recording = CheckMenuItem::new("录制 (F10)", true, false, None);
+        snapshot_menu.append(&take_snapshot).map_err(|e| format!("添加菜单项失败: {}", e))?;
+        snapshot_menu.append(&snapshot_raw_frame).map_err(|e| format!("添加菜单项失败: {}", e))?;
+        snapshot_menu.append(&PredefinedMenuItem::separator()).map_err(|e| format!("添加分隔符失败: {}", e))?;
+        snapshot_menu.append(&recording).map_err(|e| format!("添加菜单项失败: {}", e))?;
+        
+        menu.append(&snapshot_menu).map_err(|e| format!("添加子菜单失败: {}", e))?;
+        menu.append(&PredefinedMenuItem::separator()).map_err(|e| format!("添加分隔符失败: {}", e))?;
+        
         // 其他功能
         let show_info = MenuItem::new("显示信息", true, None);
         menu.append(&show_info).map_err(|e| format!("添加菜单项失败: {}", e))?;
@@ -154,6 +277,27 @@ impl TrayManager {
             reset_size,
             rotate_left,
             rotate_right,
+            rotate_video_left,
+            rotate_video_right,
+            keep_video_upright,
+            quality_items,
+            filter_items,
+            lut_enabled,
+            chroma_key,
+            effects,
+            denoise_smoothing,
+            denoise_temporal,
+            denoise_bypass,
+            auto_white_balance,
+            auto_levels,
+            low_light_boost,
+            text_overlay,
+            stickers,
+            annotation_mode,
+            clear_annotations,
+            take_snapshot,
+            snapshot_raw_frame,
+            recording,
             show_info,
             quit,
         })
@@ -184,6 +328,48 @@ impl TrayManager {
                 return Some(TrayMenuAction::RotateLeft);
             } else if event.id == self.rotate_right.id() {
                 return Some(TrayMenuAction::RotateRight);
+            } else if event.id == self.rotate_video_left.id() {
+                return Some(TrayMenuAction::RotateVideoLeft);
+            } else if event.id == self.rotate_video_right.id() {
+                return Some(TrayMenuAction::RotateVideoRight);
+            } else if event.id == self.keep_video_upright.id() {
+                return Some(TrayMenuAction::ToggleKeepVideoUpright);
+            } else if let Some((quality, _)) = self.quality_items.iter().find(|(_, item)| event.id == item.id()) {
+                return Some(TrayMenuAction::MaskQuality(*quality));
+            } else if let Some((preset, _)) = self.filter_items.iter().find(|(_, item)| event.id == item.id()) {
+                return Some(TrayMenuAction::ColorPreset(*preset));
+            } else if event.id == self.lut_enabled.id() {
+                return Some(TrayMenuAction::ToggleLut);
+            } else if event.id == self.chroma_key.id() {
+                return Some(TrayMenuAction::ToggleChromaKey);
+            } else if event.id == self.effects.id() {
+                return Some(TrayMenuAction::ToggleEffects);
+            } else if event.id == self.denoise_smoothing.id() {
+                return Some(TrayMenuAction::ToggleSmoothing);
+            } else if event.id == self.denoise_temporal.id() {
+                return Some(TrayMenuAction::ToggleTemporalDenoise);
+            } else if event.id == self.denoise_bypass.id() {
+                return Some(TrayMenuAction::ToggleDenoiseBypass);
+            } else if event.id == self.auto_white_balance.id() {
+                return Some(TrayMenuAction::ToggleAutoWhiteBalance);
+            } else if event.id == self.auto_levels.id() {
+                return Some(TrayMenuAction::ToggleAutoLevels);
+            } else if event.id == self.low_light_boost.id() {
+                return Some(TrayMenuAction::ToggleLowLightBoost);
+            } else if event.id == self.text_overlay.id() {
+                return Some(TrayMenuAction::ToggleTextOverlay);
+            } else if event.id == self.stickers.id() {
+                return Some(TrayMenuAction::ToggleStickers);
+            } else if event.id == self.annotation_mode.id() {
+                return Some(TrayMenuAction::ToggleAnnotationMode);
+            } else if event.id == self.clear_annotations.id() {
+                return Some(TrayMenuAction::ClearAnnotations);
+            } else if event.id == self.take_snapshot.id() {
+                return Some(TrayMenuAction::TakeSnapshot);
+            } else if event.id == self.snapshot_raw_frame.id() {
+                return Some(TrayMenuAction::ToggleSnapshotRawFrame);
+            } else if event.id == self.recording.id() {
+                return Some(TrayMenuAction::ToggleRecording);
             } else if event.id == self.show_info.id() {
                 return Some(TrayMenuAction::ShowInfo);
             } else if event.id == self.quit.id() {
@@ -193,6 +379,65 @@ impl TrayManager {
         
         None
     }
+    
+    /// 同步"保持视频正向"复选项的状态
+    pub fn set_keep_video_upright(&self, keep_upright: bool) {
+        self.keep_video_upright.set_checked(keep_upright);
+    }
+    
+    /// 同步"LUT 滤镜"复选项的状态
+    pub fn set_lut_enabled(&self, enabled: bool) {
+        self.lut_enabled.set_checked(enabled);
+    }
+    
+    /// 同步"绿幕抠像"复选项的状态
+    pub fn set_chroma_key_enabled(&self, enabled: bool) {
+        self.chroma_key.set_checked(enabled);
+    }
+    
+    /// 同步"特效链"复选项的状态
+    pub fn set_effects_enabled(&self, enabled: bool) {
+        self.effects.set_checked(enabled);
+    }
+    
+    /// 同步降噪复选项的状态
+    pub fn set_denoise_state(&self, smoothing: bool, temporal: bool, bypass: bool) {
+        self.denoise_smoothing.set_checked(smoothing);
+        self.denoise_temporal.set_checked(temporal);
+        self.denoise_bypass.set_checked(bypass);
+    }
+    
+    /// 同步画面校正复选项的状态
+    pub fn set_auto_correct_state(&self, white_balance: bool, auto_levels: bool, low_light_boost: bool) {
+        self.auto_white_balance.set_checked(white_balance);
+        self.auto_levels.set_checked(auto_levels);
+        self.low_light_boost.set_checked(low_light_boost);
+    }
+    
+    /// 同步"文字叠加"复选项的状态
+    pub fn set_text_overlay_visible(&self, visible: bool) {
+        self.text_overlay.set_checked(visible);
+    }
+    
+    /// 同步"贴纸"复选项的状态
+    pub fn set_stickers_visible(&self, visible: bool) {
+        self.stickers.set_checked(visible);
+    }
+    
+    /// 同步"标注模式"复选项的状态
+    pub fn set_annotation_mode(&self, enabled: bool) {
+        self.annotation_mode.set_checked(enabled);
+    }
+    
+    /// 同步"保存原始画面"复选项的状态
+    pub fn set_snapshot_raw_frame(&self, raw_frame: bool) {
+        self.snapshot_raw_frame.set_checked(raw_frame);
+    }
+    
+    /// 同步"录制"复选项的状态
+    pub fn set_recording(&self, recording: bool) {
+        self.recording.set_checked(recording);
+    }
 }
 
 /// 托盘菜单动作
@@ -208,6 +453,27 @@ pub enum TrayMenuAction {
     ResetSize,
     RotateLeft,
     RotateRight,
+    RotateVideoLeft,
+    RotateVideoRight,
+    ToggleKeepVideoUpright,
+    MaskQuality(MaskQuality),
+    ColorPreset(ColorPreset),
+    ToggleLut,
+    ToggleChromaKey,
+    ToggleEffects,
+    ToggleSmoothing,
+    ToggleTemporalDenoise,
+    ToggleDenoiseBypass,
+    ToggleAutoWhiteBalance,
+    ToggleAutoLevels,
+    ToggleLowLightBoost,
+    ToggleTextOverlay,
+    ToggleStickers,
+    ToggleAnnotationMode,
+    ClearAnnotations,
+    TakeSnapshot,
+    ToggleSnapshotRawFrame,
+    ToggleRecording,
     ShowInfo,
     Quit,
 }
@@ -2,11 +2,18 @@
 //
 // 提供右键菜单功能，包括菜单项管理、布局计算、状态管理等
 
+use crate::ui::glyph_atlas::SharedMenuFont;
 use log::{debug, error, info, warn};
 use std::collections::HashMap;
 use winit::dpi::PhysicalPosition;
 use winit::dpi::PhysicalSize;
 
+/// 菜单文字字号（逻辑像素，按缩放系数放大）
+pub const MENU_FONT_SIZE: f32 = 14.0;
+
+/// 摄像头设备子菜单项ID，设备项在运行时加入
+const CAMERA_SUBMENU_ID: &str = "menu_cameras";
+
 /// 菜单项回调函数类型
 pub type MenuCallback = Box<dyn Fn() -> Result<(), String> + Send + Sync>;
 
@@ -55,6 +62,25 @@ pub struct MenuGroup {
     pub items: Vec<String>,
 }
 
+/// 菜单面板：根菜单或一个展开的子菜单
+#[derive(Debug, Clone, PartialEq)]
+pub struct MenuPanel {
+    /// 子菜单项ID，根菜单为 None
+    pub submenu_id: Option<String>,
+    /// 面板位置
+    pub position: PhysicalPosition<f32>,
+    /// 面板尺寸
+    pub size: PhysicalSize<f32>,
+}
+
+impl MenuPanel {
+    /// 点是否在面板内
+    pub fn contains(&self, position: PhysicalPosition<f32>) -> bool {
+        position.x >= self.position.x && position.x <= self.position.x + self.size.width &&
+        position.y >= self.position.y && position.y <= self.position.y + self.size.height
+    }
+}
+
 /// 菜单布局信息
 #[derive(Debug, Clone)]
 pub struct MenuLayout {
@@ -74,6 +100,10 @@ pub struct MenuLayout {
     pub max_width: f32,
     /// 最小宽度
     pub min_width: f32,
+    /// 文字左侧的图标（勾选标记）空间
+    pub icon_space: f32,
+    /// 文字字号
+    pub font_size: f32,
 }
 
 impl Default for MenuLayout {
@@ -87,6 +117,26 @@ impl Default for MenuLayout {
             separator_height: 1.0,
             max_width: 300.0,
             min_width: 120.0,
+            icon_space: 24.0,
+            font_size: MENU_FONT_SIZE,
+        }
+    }
+}
+
+impl MenuLayout {
+    /// 按显示器缩放系数放大的默认布局（位置和尺寸由 calculate_layout 计算）
+    pub fn scaled(scale_factor: f32) -> Self {
+        let base = Self::default();
+        Self {
+            item_height: base.item_height * scale_factor,
+            padding: base.padding * scale_factor,
+            border_width: (base.border_width * scale_factor).round().max(1.0),
+            separator_height: (base.separator_height * scale_factor).round().max(1.0),
+            max_width: base.max_width * scale_factor,
+            min_width: base.min_width * scale_factor,
+            icon_space: base.icon_space * scale_factor,
+            font_size: base.font_size * scale_factor,
+            ..base
         }
     }
 }
@@ -110,8 +160,12 @@ pub struct ContextMenu {
     items: HashMap<String, MenuItem>,
     /// 菜单分组
     groups: HashMap<String, MenuGroup>,
-    /// 菜单项显示顺序
+    /// 根菜单的菜单项显示顺序
     display_order: Vec<String>,
+    /// 子菜单的子项显示顺序（键为子菜单项ID）
+    submenus: HashMap<String, Vec<String>>,
+    /// 已展开的子菜单面板，由浅到深
+    open_submenus: Vec<MenuPanel>,
     /// 当前菜单状态
     state: MenuState,
     /// 菜单布局
@@ -124,6 +178,10 @@ pub struct ContextMenu {
     callbacks: HashMap<String, MenuCallback>,
     /// 屏幕尺寸（用于边界检查）
     screen_size: PhysicalSize<u32>,
+    /// 显示器缩放系数
+    scale_factor: f32,
+    /// 与菜单渲染器共用的字体，用于测量文字宽度
+    font: Option<SharedMenuFont>,
 }
 
 impl ContextMenu {
@@ -135,12 +193,16 @@ impl ContextMenu {
             items: HashMap::new(),
             groups: HashMap::new(),
             display_order: Vec::new(),
+            submenus: HashMap::new(),
+            open_submenus: Vec::new(),
             state: MenuState::Hidden,
             layout: MenuLayout::default(),
             selected_item: None,
             hovered_item: None,
             callbacks: HashMap::new(),
             screen_size,
+            scale_factor: 1.0,
+            font: None,
         };
         
         // 初始化默认菜单项
@@ -153,9 +215,10 @@ impl ContextMenu {
     fn initialize_default_menu(&mut self) {
         debug!("初始化默认菜单项");
         
-        // 形状选择分组
+        // 形状选择分组（子菜单）
         self.add_group("shapes", "形状选择");
-        self.add_menu_item(MenuItem {
+        self.add_submenu(None, "menu_shapes", "形状");
+        self.add_submenu_item("menu_shapes", MenuItem {
             id: "shape_circle".to_string(),
             text: "圆形".to_string(),
             icon: Some("circle".to_string()),
@@ -164,7 +227,7 @@ impl ContextMenu {
             item_type: MenuItemType::Radio,
             group_id: Some("shapes".to_string()),
         });
-        self.add_menu_item(MenuItem {
+        self.add_submenu_item("menu_shapes", MenuItem {
             id: "shape_ellipse".to_string(),
             text: "椭圆形".to_string(),
             icon: Some("ellipse".to_string()),
@@ -173,7 +236,7 @@ impl ContextMenu {
             item_type: MenuItemType::Radio,
             group_id: Some("shapes".to_string()),
         });
-        self.add_menu_item(MenuItem {
+        self.add_submenu_item("menu_shapes", MenuItem {
             id: "shape_rectangle".to_string(),
             text: "矩形".to_string(),
             icon: Some("rectangle".to_string()),
@@ -182,7 +245,7 @@ impl ContextMenu {
             item_type: MenuItemType::Radio,
             group_id: Some("shapes".to_string()),
         });
-        self.add_menu_item(MenuItem {
+        self.add_submenu_item("menu_shapes", MenuItem {
             id: "shape_rounded_rectangle".to_string(),
             text: "圆角矩形".to_string(),
             icon: Some("rounded_rectangle".to_string()),
@@ -191,7 +254,7 @@ impl ContextMenu {
             item_type: MenuItemType::Radio,
             group_id: Some("shapes".to_string()),
         });
-        self.add_menu_item(MenuItem {
+        self.add_submenu_item("menu_shapes", MenuItem {
             id: "shape_heart".to_string(),
             text: "心形".to_string(),
             icon: Some("heart".to_string()),
@@ -201,19 +264,9 @@ impl ContextMenu {
             group_id: Some("shapes".to_string()),
         });
         
-        // 分隔线
-        self.add_menu_item(MenuItem {
-            id: "separator_1".to_string(),
-            text: "".to_string(),
-            icon: None,
-            enabled: false,
-            checked: false,
-            item_type: MenuItemType::Separator,
-            group_id: None,
-        });
-        
-        // 摄像头设备分组
+        // 摄像头设备分组（子菜单）
         self.add_group("cameras", "摄像头设备");
+        self.add_submenu(None, CAMERA_SUBMENU_ID, "摄像头");
         // 摄像头设备项将在运行时动态添加
         
         // 分隔线
@@ -247,6 +300,15 @@ impl ContextMenu {
             item_type: MenuItemType::Normal,
             group_id: Some("window_control".to_string()),
         });
+        self.add_menu_item(MenuItem {
+            id: "keep_video_upright".to_string(),
+            text: "保持视频正向 (U)".to_string(),
+            icon: None,
+            enabled: true,
+            checked: false,
+            item_type: MenuItemType::Checkbox,
+            group_id: Some("window_control".to_string()),
+        });
         self.add_menu_item(MenuItem {
             id: "reset_size".to_string(),
             text: "重置大小".to_string(),
@@ -268,6 +330,221 @@ impl ContextMenu {
             group_id: None,
         });
         
+        // 遮罩质量分组（子菜单）
+        self.add_group("mask_quality", "遮罩质量");
+        self.add_submenu(None, "menu_mask_quality", "遮罩质量");
+        for (id, text) in [
+            ("quality_fast", "快速"),
+            ("quality_balanced", "均衡"),
+            ("quality_high", "高质量"),
+            ("quality_adaptive", "自适应"),
+        ] {
+            self.add_submenu_item("menu_mask_quality", MenuItem {
+                id: id.to_string(),
+                text: text.to_string(),
+                icon: None,
+                enabled: true,
+                checked: id == "quality_balanced", // 默认均衡
+                item_type: MenuItemType::Radio,
+                group_id: Some("mask_quality".to_string()),
+            });
+        }
+        
+        // 颜色滤镜分组（子菜单）
+        self.add_group("color_filter", "颜色滤镜");
+        self.add_submenu(None, "menu_color_filter", "颜色滤镜");
+        for (id, text) in [
+            ("filter_none", "原色"),
+            ("filter_grayscale", "灰度"),
+            ("filter_sepia", "复古"),
+            ("filter_highcontrast", "高对比度"),
+        ] {
+            self.add_submenu_item("menu_color_filter", MenuItem {
+                id: id.to_string(),
+                text: text.to_string(),
+                icon: None,
+                enabled: true,
+                checked: id == "filter_none", // 默认原色
+                item_type: MenuItemType::Radio,
+                group_id: Some("color_filter".to_string()),
+            });
+        }
+        self.add_submenu_item("menu_color_filter", MenuItem {
+            id: "filter_lut".to_string(),
+            text: "LUT 滤镜".to_string(),
+            icon: None,
+            enabled: true,
+            checked: false,
+            item_type: MenuItemType::Checkbox,
+            group_id: Some("color_filter".to_string()),
+        });
+        self.add_submenu_item("menu_color_filter", MenuItem {
+            id: "chroma_key".to_string(),
+            text: "绿幕抠像 (K, Shift+单击取色)".to_string(),
+            icon: None,
+            enabled: true,
+            checked: false,
+            item_type: MenuItemType::Checkbox,
+            group_id: Some("color_filter".to_string()),
+        });
+        self.add_submenu_item("menu_color_filter", MenuItem {
+            id: "effects".to_string(),
+            text: "特效链 (E)".to_string(),
+            icon: None,
+            enabled: true,
+            checked: false,
+            item_type: MenuItemType::Checkbox,
+            group_id: Some("color_filter".to_string()),
+        });
+        
+        // 降噪分组（子菜单）
+        self.add_group("denoise", "降噪");
+        self.add_submenu(None, "menu_denoise", "降噪");
+        for (id, text) in [
+            ("denoise_smoothing", "磨皮平滑"),
+            ("denoise_temporal", "时域降噪"),
+            ("denoise_bypass", "旁路降噪 (D)"),
+        ] {
+            self.add_submenu_item("menu_denoise", MenuItem {
+                id: id.to_string(),
+                text: text.to_string(),
+                icon: None,
+                enabled: true,
+                checked: false,
+                item_type: MenuItemType::Checkbox,
+                group_id: Some("denoise".to_string()),
+            });
+        }
+        
+        // 自动校正分组（子菜单）
+        self.add_group("auto_correct", "画面校正");
+        self.add_submenu(None, "menu_auto_correct", "画面校正");
+        for (id, text) in [
+            ("auto_white_balance", "自动白平衡"),
+            ("auto_levels", "自动色阶"),
+            ("low_light_boost", "弱光增强"),
+        ] {
+            self.add_submenu_item("menu_auto_correct", MenuItem {
+                id: id.to_string(),
+                text: text.to_string(),
+                icon: None,
+                enabled: true,
+                checked: false,
+                item_type: MenuItemType::Checkbox,
+                group_id: Some("auto_correct".to_string()),
+            });
+        }
+        
+        // 分隔线
+        self.add_menu_item(MenuItem {
+            id: "separator_7".to_string(),
+            text: "".to_string(),
+            icon: None,
+            enabled: false,
+            checked: false,
+            item_type: MenuItemType::Separator,
+            group_id: None,
+        });
+        
+        // 叠加层分组（子菜单）
+        self.add_group("overlay", "叠加层");
+        self.add_submenu(None, "menu_overlay", "叠加层");
+        self.add_submenu_item("menu_overlay", MenuItem {
+            id: "text_overlay".to_string(),
+            text: "文字叠加 (T)".to_string(),
+            icon: None,
+            enabled: true,
+            checked: false,
+            item_type: MenuItemType::Checkbox,
+            group_id: Some("overlay".to_string()),
+        });
+        self.add_submenu_item("menu_overlay", MenuItem {
+            id: "stickers".to_string(),
+            text: "贴纸 (S)".to_string(),
+            icon: None,
+            enabled: true,
+            checked: false,
+            item_type: MenuItemType::Checkbox,
+            group_id: Some("overlay".to_string()),
+        });
+        
+        // 手绘标注分组（子菜单，颜色和线宽再各自收进下一级子菜单）
+        self.add_group("annotation", "标注");
+        self.add_submenu(None, "menu_annotation", "标注");
+        for (id, text, item_type) in [
+            ("annotation_mode", "标注模式 (P)", MenuItemType::Checkbox),
+            ("annotation_fade", "自动淡出", MenuItemType::Checkbox),
+            ("annotation_undo", "撤销 (Ctrl+Z)", MenuItemType::Normal),
+            ("annotation_clear", "清除标注 (C)", MenuItemType::Normal),
+        ] {
+            self.add_submenu_item("menu_annotation", MenuItem {
+                id: id.to_string(),
+                text: text.to_string(),
+                icon: None,
+                enabled: true,
+                checked: false,
+                item_type,
+                group_id: Some("annotation".to_string()),
+            });
+        }
+        // 颜色与粗细的顺序与 overlay::ANNOTATION_COLORS / ANNOTATION_WIDTHS 一致
+        self.add_group("annotation_color", "画笔颜色");
+        self.add_submenu(Some("menu_annotation"), "menu_annotation_color", "颜色");
+        for (index, text) in ["红色", "黄色", "绿色", "蓝色", "白色"].into_iter().enumerate() {
+            self.add_submenu_item("menu_annotation_color", MenuItem {
+                id: format!("annotation_color_{}", index),
+                text: text.to_string(),
+                icon: None,
+                enabled: true,
+                checked: index == 0,
+                item_type: MenuItemType::Radio,
+                group_id: Some("annotation_color".to_string()),
+            });
+        }
+        self.add_group("annotation_width", "画笔粗细");
+        self.add_submenu(Some("menu_annotation"), "menu_annotation_width", "线宽");
+        for (index, text) in ["细", "中", "粗"].into_iter().enumerate() {
+            self.add_submenu_item("menu_annotation_width", MenuItem {
+                id: format!("annotation_width_{}", index),
+                text: text.to_string(),
+                icon: None,
+                enabled: true,
+                checked: index == 1,
+                item_type: MenuItemType::Radio,
+                group_id: Some("annotation_width".to_string()),
+            });
+        }
+        
+        // 快照与录制分组（子菜单）
+        self.add_group("snapshot", "快照与录制");
+        self.add_submenu(None, "menu_snapshot", "快照与录制");
+        for (id, text, item_type) in [
+            ("snapshot_take", "拍摄快照 (F12)", MenuItemType::Normal),
+            ("snapshot_raw", "保存原始画面", MenuItemType::Checkbox),
+            ("recording_toggle", "录制 (F10)", MenuItemType::Checkbox),
+        ] {
+            self.add_submenu_item("menu_snapshot", MenuItem {
+                id: id.to_string(),
+                text: text.to_string(),
+                icon: None,
+                enabled: true,
+                checked: false,
+                item_type,
+                group_id: Some("snapshot".to_string()),
+            });
+        }
+        
+        // 分隔线
+        self.add_menu_item(MenuItem {
+            id: "separator_8".to_string(),
+            text: "".to_string(),
+            icon: None,
+            enabled: false,
+            checked: false,
+            item_type: MenuItemType::Separator,
+            group_id: None,
+        });
+        
         // 状态信息分组
         self.add_group("status", "状态信息");
         self.add_menu_item(MenuItem {
@@ -296,19 +573,56 @@ impl ContextMenu {
         self.groups.insert(id.to_string(), group);
     }
     
-    /// 添加菜单项
+    /// 添加菜单项（根菜单）
     pub fn add_menu_item(&mut self, item: MenuItem) {
+        self.insert_menu_item(None, item);
+    }
+
+    /// 添加子菜单项，parent_id 为所属子菜单项的ID
+    pub fn add_submenu_item(&mut self, parent_id: &str, item: MenuItem) {
+        if !self.submenus.contains_key(parent_id) {
+            warn!("菜单项 {} 指定的子菜单 {} 不存在", item.id, parent_id);
+            return;
+        }
+        self.insert_menu_item(Some(parent_id), item);
+    }
+
+    /// 添加子菜单，parent_id 为 None 时加入根菜单
+    pub fn add_submenu(&mut self, parent_id: Option<&str>, id: &str, text: &str) {
+        let item = MenuItem {
+            id: id.to_string(),
+            text: text.to_string(),
+            icon: None,
+            enabled: true,
+            checked: false,
+            item_type: MenuItemType::Submenu,
+            group_id: None,
+        };
+        match parent_id {
+            Some(parent_id) => self.add_submenu_item(parent_id, item),
+            None => self.add_menu_item(item),
+        }
+    }
+
+    /// 添加菜单项到根菜单或指定子菜单
+    fn insert_menu_item(&mut self, parent_id: Option<&str>, item: MenuItem) {
         debug!("添加菜单项: {} - {}", item.id, item.text);
-        
+
         let item_id = item.id.clone();
         let group_id = item.group_id.clone();
-        
+        if item.item_type == MenuItemType::Submenu {
+            self.submenus.entry(item_id.clone()).or_default();
+        }
+
         // 添加到菜单项列表
         self.items.insert(item_id.clone(), item);
-        
+
         // 添加到显示顺序
-        self.display_order.push(item_id.clone());
-        
+        match parent_id.and_then(|parent_id| self.submenus.get_mut(parent_id)) {
+            Some(children) => children.push(item_id.clone()),
+            None => self.display_order.push(item_id.clone()),
+        }
+
         // 如果有分组，添加到分组中
         if let Some(group_id) = group_id {
             if let Some(group) = self.groups.get_mut(&group_id) {
@@ -326,7 +640,21 @@ impl ContextMenu {
         if let Some(item) = self.items.remove(item_id) {
             // 从显示顺序中移除
             self.display_order.retain(|id| id != item_id);
-            
+            for children in self.submenus.values_mut() {
+                children.retain(|id| id != item_id);
+            }
+
+            // 子菜单连同其子项一起移除，展开中的面板随之收起
+            if let Some(children) = self.submenus.remove(item_id) {
+                if let Some(level) = self.open_submenus.iter()
+                    .position(|panel| panel.submenu_id.as_deref() == Some(item_id)) {
+                    self.open_submenus.truncate(level);
+                }
+                for child_id in children {
+                    self.remove_menu_item(&child_id);
+                }
+            }
+
             // 从分组中移除
             if let Some(group_id) = &item.group_id {
                 if let Some(group) = self.groups.get_mut(group_id) {
@@ -378,17 +706,47 @@ impl ContextMenu {
         self.state = MenuState::Hidden;
         self.selected_item = None;
         self.hovered_item = None;
+        self.open_submenus.clear();
     }
     
     /// 计算菜单布局
     fn calculate_layout(&mut self, requested_position: PhysicalPosition<f32>) {
         debug!("计算菜单布局，请求位置: ({:.1}, {:.1})", requested_position.x, requested_position.y);
         
+        // 重新布局时收起所有子菜单
+        self.open_submenus.clear();
+        
+        // 菜单项至少容纳一行文字
+        if let Some(metrics) = self.font.as_ref()
+            .and_then(|font| font.lock().ok())
+            .map(|font| font.line_metrics(self.layout.font_size)) {
+            self.layout.item_height = self.layout.item_height.max((metrics.ascent + metrics.descent).ceil());
+        }
+        
+        // 设置菜单尺寸
+        self.layout.size = self.panel_size(&self.display_order);
+        
+        // 边界检查和位置调整
+        let adjusted_position = self.adjust_position_for_screen_bounds(requested_position, self.layout.size);
+        self.layout.position = adjusted_position;
+        
+        debug!("菜单布局计算完成: 位置({:.1}, {:.1}), 尺寸({:.1}x{:.1})", 
+               self.layout.position.x, self.layout.position.y,
+               self.layout.size.width, self.layout.size.height);
+    }
+    
+    /// 计算一个面板的尺寸
+    ///
+    /// 文字宽度用与菜单渲染器相同的字体和排版测量；尚未设置字体时
+    /// （菜单渲染器未初始化，菜单不会绘制）按最小宽度布局
+    fn panel_size(&self, item_ids: &[String]) -> PhysicalSize<f32> {
         // 计算菜单内容尺寸
         let mut content_height = self.layout.padding * 2.0;
         let mut max_text_width = 0.0f32;
+        let mut has_submenu = false;
+        let mut font = self.font.as_ref().and_then(|font| font.lock().ok());
         
-        for item_id in &self.display_order {
+        for item_id in item_ids {
             if let Some(item) = self.items.get(item_id) {
                 match item.item_type {
                     MenuItemType::Separator => {
@@ -396,38 +754,31 @@ impl ContextMenu {
                     }
                     _ => {
                         content_height += self.layout.item_height;
-                        // 估算文本宽度（简化计算，每个字符约8像素）
-                        let text_width = item.text.chars().count() as f32 * 8.0;
+                        // 排版时字形对齐到整像素，留出 1 像素舍入余量
+                        let text_width = font.as_mut()
+                            .map_or(0.0, |font| font.text_width(&item.text, self.layout.font_size).ceil() + 1.0);
                         max_text_width = max_text_width.max(text_width);
+                        has_submenu |= item.item_type == MenuItemType::Submenu;
                     }
                 }
             }
         }
         
-        // 计算菜单宽度（文本宽度 + 图标空间 + 内边距）
-        let icon_space = 24.0; // 图标空间
-        let content_width = (max_text_width + icon_space + self.layout.padding * 2.0)
+        // 计算菜单宽度（文本宽度 + 图标空间 + 子菜单箭头空间 + 内边距）
+        let arrow_space = if has_submenu { self.layout.icon_space } else { 0.0 };
+        let content_width = (max_text_width + self.layout.icon_space + arrow_space + self.layout.padding * 2.0)
             .max(self.layout.min_width)
             .min(self.layout.max_width);
         
-        // 设置菜单尺寸
-        self.layout.size = PhysicalSize::new(content_width, content_height);
-        
-        // 边界检查和位置调整
-        let adjusted_position = self.adjust_position_for_screen_bounds(requested_position);
-        self.layout.position = adjusted_position;
-        
-        debug!("菜单布局计算完成: 位置({:.1}, {:.1}), 尺寸({:.1}x{:.1})", 
-               self.layout.position.x, self.layout.position.y,
-               self.layout.size.width, self.layout.size.height);
+        PhysicalSize::new(content_width, content_height)
     }
     
     /// 调整菜单位置以适应屏幕边界
-    fn adjust_position_for_screen_bounds(&self, requested_position: PhysicalPosition<f32>) -> PhysicalPosition<f32> {
+    fn adjust_position_for_screen_bounds(&self, requested_position: PhysicalPosition<f32>, size: PhysicalSize<f32>) -> PhysicalPosition<f32> {
         let screen_width = self.screen_size.width as f32;
         let screen_height = self.screen_size.height as f32;
-        let menu_width = self.layout.size.width;
-        let menu_height = self.layout.size.height;
+        let menu_width = size.width;
+        let menu_height = size.height;
         
         let mut adjusted_x = requested_position.x;
         let mut adjusted_y = requested_position.y;
@@ -460,36 +811,21 @@ impl ContextMenu {
         PhysicalPosition::new(adjusted_x, adjusted_y)
     }
     
-    /// 检查点是否在菜单区域内
+    /// 检查点是否在菜单区域内（根菜单或任一展开的子菜单）
     pub fn is_point_inside(&self, position: PhysicalPosition<f32>) -> bool {
-        if self.state != MenuState::Visible {
-            return false;
-        }
-        
-        let menu_left = self.layout.position.x;
-        let menu_top = self.layout.position.y;
-        let menu_right = menu_left + self.layout.size.width;
-        let menu_bottom = menu_top + self.layout.size.height;
-        
-        position.x >= menu_left && position.x <= menu_right &&
-        position.y >= menu_top && position.y <= menu_bottom
+        self.panel_at(position).is_some()
     }
     
     /// 获取指定位置的菜单项
     pub fn get_item_at_position(&self, position: PhysicalPosition<f32>) -> Option<&str> {
-        if !self.is_point_inside(position) {
-            return None;
-        }
+        let panel = self.panel_at(position)?;
         
-        let relative_y = position.y - self.layout.position.y - self.layout.padding;
+        let relative_y = position.y - panel.position.y - self.layout.padding;
         let mut current_y = 0.0;
         
-        for item_id in &self.display_order {
+        for item_id in self.child_ids(panel.submenu_id.as_deref()) {
             if let Some(item) = self.items.get(item_id) {
-                let item_height = match item.item_type {
-                    MenuItemType::Separator => self.layout.separator_height,
-                    _ => self.layout.item_height,
-                };
+                let item_height = self.item_height(item);
                 
                 if relative_y >= current_y && relative_y < current_y + item_height {
                     // 分隔线不可选择
@@ -506,12 +842,109 @@ impl ContextMenu {
         None
     }
     
+    /// 指定位置所在的面板，子菜单叠在父菜单之上，优先返回最深的一层
+    fn panel_at(&self, position: PhysicalPosition<f32>) -> Option<MenuPanel> {
+        if self.state != MenuState::Visible {
+            return None;
+        }
+        self.panels().into_iter().rev().find(|panel| panel.contains(position))
+    }
+    
+    /// 根菜单（None）或子菜单的子项ID
+    fn child_ids(&self, submenu_id: Option<&str>) -> &[String] {
+        match submenu_id {
+            None => &self.display_order,
+            Some(submenu_id) => self.submenus.get(submenu_id).map(Vec::as_slice).unwrap_or(&[]),
+        }
+    }
+    
+    /// 菜单项占据的高度
+    fn item_height(&self, item: &MenuItem) -> f32 {
+        match item.item_type {
+            MenuItemType::Separator => self.layout.separator_height,
+            _ => self.layout.item_height,
+        }
+    }
+    
     /// 设置悬浮菜单项
     pub fn set_hovered_item(&mut self, item_id: Option<String>) {
         if self.hovered_item != item_id {
             debug!("悬浮菜单项变更: {:?} -> {:?}", self.hovered_item, item_id);
-            self.hovered_item = item_id;
+            self.hovered_item = item_id.clone();
+            // 悬浮到某一层的菜单项时收起更深的子菜单，悬浮到子菜单项时展开它
+            if let Some(item_id) = item_id {
+                self.update_open_submenus(&item_id);
+            }
+        }
+    }
+    
+    /// 展开子菜单项对应的子菜单（点击子菜单项时使用，菜单保持显示）
+    pub fn expand_submenu(&mut self, item_id: &str) {
+        self.hovered_item = Some(item_id.to_string());
+        self.update_open_submenus(item_id);
+    }
+    
+    /// 是否为子菜单项
+    pub fn is_submenu(&self, item_id: &str) -> bool {
+        self.submenus.contains_key(item_id)
+    }
+    
+    /// 子菜单是否已展开
+    pub fn is_submenu_open(&self, item_id: &str) -> bool {
+        self.open_submenus.iter().any(|panel| panel.submenu_id.as_deref() == Some(item_id))
+    }
+    
+    /// 按菜单项所在层级收起更深的子菜单，并展开该项自身的子菜单
+    fn update_open_submenus(&mut self, item_id: &str) {
+        let Some(level) = self.panels().iter()
+            .position(|panel| self.child_ids(panel.submenu_id.as_deref()).iter().any(|id| id == item_id))
+        else {
+            return;
+        };
+        self.open_submenus.truncate(level);
+        
+        let expandable = self.items.get(item_id)
+            .is_some_and(|item| item.item_type == MenuItemType::Submenu && item.enabled);
+        if expandable {
+            self.open_submenu(level, item_id);
+        }
+    }
+    
+    /// 在第 level 层面板中的子菜单项旁展开子菜单
+    fn open_submenu(&mut self, level: usize, submenu_id: &str) {
+        let Some(parent) = self.panels().into_iter().nth(level) else {
+            return;
+        };
+        
+        // 子菜单项在父面板中的纵向位置
+        let mut item_y = parent.position.y + self.layout.padding;
+        for item_id in self.child_ids(parent.submenu_id.as_deref()) {
+            if item_id == submenu_id {
+                break;
+            }
+            if let Some(item) = self.items.get(item_id) {
+                item_y += self.item_height(item);
+            }
+        }
+        
+        let size = self.panel_size(self.child_ids(Some(submenu_id)));
+        
+        // 优先展开在父面板右侧，放不下时改到左侧；第一个子项与子菜单项对齐
+        let border_width = self.layout.border_width;
+        let mut x = parent.position.x + parent.size.width - border_width;
+        if x + size.width > self.screen_size.width as f32 {
+            x = parent.position.x - size.width + border_width;
         }
+        let requested_position = PhysicalPosition::new(x, item_y - self.layout.padding);
+        let position = self.adjust_position_for_screen_bounds(requested_position, size);
+        
+        debug!("展开子菜单 {}: 位置({:.1}, {:.1}), 尺寸({:.1}x{:.1})",
+               submenu_id, position.x, position.y, size.width, size.height);
+        self.open_submenus.push(MenuPanel {
+            submenu_id: Some(submenu_id.to_string()),
+            position,
+            size,
+        });
     }
     
     /// 执行菜单项
@@ -524,6 +957,12 @@ impl ContextMenu {
                 return Err(format!("菜单项 {} 已禁用", item_id));
             }
             
+            // 子菜单项只展开子菜单
+            if item.item_type == MenuItemType::Submenu {
+                self.expand_submenu(item_id);
+                return Ok(());
+            }
+            
             // 处理单选项逻辑
             if item.item_type == MenuItemType::Radio {
                 self.handle_radio_selection(item_id);
@@ -554,18 +993,34 @@ impl ContextMenu {
         }
     }
     
+    /// 选中单选项（不执行回调，用于同步外部状态）
+    pub fn select_radio_item(&mut self, item_id: &str) {
+        match self.items.get(item_id) {
+            Some(item) if item.item_type == MenuItemType::Radio => self.handle_radio_selection(item_id),
+            Some(_) => warn!("菜单项 {} 不是单选项", item_id),
+            None => warn!("尝试选中不存在的菜单项: {}", item_id),
+        }
+    }
+    
+    /// 设置复选项的选中状态（不执行回调，用于同步外部状态）
+    pub fn set_item_checked(&mut self, item_id: &str, checked: bool) {
+        match self.items.get_mut(item_id) {
+            Some(item) if item.item_type == MenuItemType::Checkbox => item.checked = checked,
+            Some(_) => warn!("菜单项 {} 不是复选项", item_id),
+            None => warn!("尝试设置不存在的菜单项: {}", item_id),
+        }
+    }
+    
     /// 处理单选项选择逻辑
     fn handle_radio_selection(&mut self, selected_item_id: &str) {
         let group_id = self.items.get(selected_item_id)
             .and_then(|item| item.group_id.clone());
         
         if let Some(group_id) = group_id {
-            // 取消同组其他项的选中状态
-            for item_id in &self.display_order {
-                if let Some(item) = self.items.get_mut(item_id) {
-                    if item.group_id.as_ref() == Some(&group_id) && item.item_type == MenuItemType::Radio {
-                        item.checked = item_id == selected_item_id;
-                    }
+            // 取消同组其他项的选中状态（同组的项可能位于子菜单中）
+            for (item_id, item) in self.items.iter_mut() {
+                if item.group_id.as_ref() == Some(&group_id) && item.item_type == MenuItemType::Radio {
+                    item.checked = item_id == selected_item_id;
                 }
             }
             debug!("单选组 {} 选择更新: {}", group_id, selected_item_id);
@@ -577,46 +1032,27 @@ impl ContextMenu {
         debug!("更新摄像头设备列表，设备数量: {}", devices.len());
         
         // 移除现有的摄像头菜单项
-        let camera_items: Vec<String> = self.display_order.iter()
-            .filter(|id| id.starts_with("camera_"))
-            .cloned()
-            .collect();
-        
+        let camera_items = self.child_ids(Some(CAMERA_SUBMENU_ID)).to_vec();
         for item_id in camera_items {
             self.remove_menu_item(&item_id);
         }
         
-        // 找到摄像头分组的插入位置（在第一个分隔线之后）
-        let insert_index = self.display_order.iter()
-            .position(|id| id == "separator_1")
-            .map(|pos| pos + 1)
-            .unwrap_or(self.display_order.len());
-        
         // 添加新的摄像头设备项
         for (device_index, device_name) in devices {
-            let item_id = format!("camera_{}", device_index);
-            let is_current = current_device == Some(*device_index);
-            
-            let item = MenuItem {
-                id: item_id.clone(),
+            self.add_submenu_item(CAMERA_SUBMENU_ID, MenuItem {
+                id: format!("camera_{}", device_index),
                 text: device_name.clone(),
                 icon: Some("camera".to_string()),
                 enabled: true,
-                checked: is_current,
+                checked: current_device == Some(*device_index),
                 item_type: MenuItemType::Radio,
                 group_id: Some("cameras".to_string()),
-            };
-            
-            // 添加菜单项
-            self.items.insert(item_id.clone(), item);
-            
-            // 插入到正确位置
-            self.display_order.insert(insert_index + devices.iter().position(|(idx, _)| idx == device_index).unwrap(), item_id.clone());
-            
-            // 添加到分组
-            if let Some(group) = self.groups.get_mut("cameras") {
-                group.items.push(item_id);
-            }
+            });
+        }
+        
+        // 没有设备时子菜单不可展开
+        if let Some(item) = self.items.get_mut(CAMERA_SUBMENU_ID) {
+            item.enabled = !devices.is_empty();
         }
         
         debug!("摄像头设备列表更新完成");
@@ -643,9 +1079,37 @@ impl ContextMenu {
         &self.layout
     }
     
-    /// 获取显示顺序的菜单项列表
+    /// 获取显示顺序的菜单项列表（子菜单的子项依次跟在子菜单项之后）
     pub fn get_display_items(&self) -> Vec<&MenuItem> {
-        self.display_order.iter()
+        let mut items = Vec::new();
+        self.collect_display_items(None, &mut items);
+        items
+    }
+    
+    fn collect_display_items<'a>(&'a self, submenu_id: Option<&str>, items: &mut Vec<&'a MenuItem>) {
+        for item_id in self.child_ids(submenu_id) {
+            if let Some(item) = self.items.get(item_id) {
+                items.push(item);
+                if item.item_type == MenuItemType::Submenu {
+                    self.collect_display_items(Some(item_id), items);
+                }
+            }
+        }
+    }
+    
+    /// 当前显示的菜单面板：根菜单在前，随后是由浅到深展开的子菜单
+    pub fn panels(&self) -> Vec<MenuPanel> {
+        let root = MenuPanel {
+            submenu_id: None,
+            position: self.layout.position,
+            size: self.layout.size,
+        };
+        std::iter::once(root).chain(self.open_submenus.iter().cloned()).collect()
+    }
+    
+    /// 面板中按显示顺序排列的菜单项
+    pub fn panel_items(&self, panel: &MenuPanel) -> Vec<&MenuItem> {
+        self.child_ids(panel.submenu_id.as_deref()).iter()
             .filter_map(|id| self.items.get(id))
             .collect()
     }
@@ -655,6 +1119,38 @@ impl ContextMenu {
         self.hovered_item.as_deref()
     }
     
+    /// 显示器缩放系数
+    pub fn scale_factor(&self) -> f32 {
+        self.scale_factor
+    }
+
+    /// 设置显示器缩放系数，菜单尺寸和字号随之缩放
+    pub fn set_scale_factor(&mut self, scale_factor: f64) {
+        let scale_factor = scale_factor as f32;
+        if !scale_factor.is_finite() || scale_factor <= 0.0 || scale_factor == self.scale_factor {
+            return;
+        }
+        debug!("菜单缩放系数: {:.2} -> {:.2}", self.scale_factor, scale_factor);
+        self.scale_factor = scale_factor;
+
+        let position = self.layout.position;
+        self.layout = MenuLayout::scaled(scale_factor);
+        self.layout.position = position;
+        // 显示中的菜单立即按新尺寸重新布局
+        if self.state == MenuState::Visible {
+            self.calculate_layout(position);
+        }
+    }
+
+    /// 设置与菜单渲染器共用的字体，之后的布局按实际字形测量文字宽度
+    pub fn set_font(&mut self, font: SharedMenuFont) {
+        self.font = Some(font);
+        if self.state == MenuState::Visible {
+            let position = self.layout.position;
+            self.calculate_layout(position);
+        }
+    }
+
     /// 更新屏幕尺寸
     pub fn update_screen_size(&mut self, screen_size: PhysicalSize<u32>) {
         if self.screen_size != screen_size {
@@ -794,4 +1290,254 @@ mod tests {
         assert!(menu.items.get("camera_0").unwrap().checked);
         assert!(!menu.items.get("camera_1").unwrap().checked);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_select_radio_item() {
+        let screen_size = PhysicalSize::new(1920, 1080);
+        let mut menu = ContextMenu::new(screen_size);
+        
+        assert!(menu.items.get("quality_balanced").unwrap().checked);
+        
+        menu.select_radio_item("quality_adaptive");
+        assert!(menu.items.get("quality_adaptive").unwrap().checked);
+        assert!(!menu.items.get("quality_balanced").unwrap().checked);
+        
+        // 其他单选组不受影响
+        assert!(menu.items.get("shape_circle").unwrap().checked);
+        
+        // 颜色滤镜单选组独立于遮罩质量
+        assert!(menu.items.get("filter_none").unwrap().checked);
+        menu.select_radio_item("filter_sepia");
+        assert!(menu.items.get("filter_sepia").unwrap().checked);
+        assert!(!menu.items.get("filter_none").unwrap().checked);
+        assert!(menu.items.get("quality_adaptive").unwrap().checked);
+    }
+
+    #[test]
+    fn test_set_item_checked() {
+        let screen_size = PhysicalSize::new(1920, 1080);
+        let mut menu = ContextMenu::new(screen_size);
+        
+        assert!(!menu.items.get("keep_video_upright").unwrap().checked);
+        menu.set_item_checked("keep_video_upright", true);
+        assert!(menu.items.get("keep_video_upright").unwrap().checked);
+        
+        // 非复选项不受影响
+        menu.set_item_checked("shape_ellipse", true);
+        assert!(!menu.items.get("shape_ellipse").unwrap().checked);
+    }
+
+    #[test]
+    fn test_submenu_opens_on_hover() {
+        let screen_size = PhysicalSize::new(1920, 1080);
+        let mut menu = ContextMenu::new(screen_size);
+        menu.show(PhysicalPosition::new(100.0, 100.0));
+        
+        // 子项不在根菜单中
+        let root = menu.panels().remove(0);
+        let root_ids: Vec<&str> = menu.panel_items(&root).iter().map(|item| item.id.as_str()).collect();
+        assert!(root_ids.contains(&"menu_denoise"));
+        assert!(!root_ids.contains(&"denoise_smoothing"));
+        
+        menu.set_hovered_item(Some("menu_denoise".to_string()));
+        assert!(menu.is_submenu_open("menu_denoise"));
+        let panels = menu.panels();
+        assert_eq!(panels.len(), 2);
+        let submenu = &panels[1];
+        assert_eq!(submenu.position.x, root.position.x + root.size.width - menu.layout.border_width);
+        
+        // 子菜单中的点命中子项，也算在菜单内
+        let first_item = PhysicalPosition::new(
+            submenu.position.x + submenu.size.width / 2.0,
+            submenu.position.y + menu.layout.padding + menu.layout.item_height / 2.0,
+        );
+        assert!(menu.is_point_inside(first_item));
+        assert_eq!(menu.get_item_at_position(first_item), Some("denoise_smoothing"));
+        
+        // 悬浮到子菜单中的项不收起子菜单，悬浮到根菜单的其他项时收起
+        menu.set_hovered_item(Some("denoise_smoothing".to_string()));
+        assert_eq!(menu.panels().len(), 2);
+        menu.set_hovered_item(Some("reset_position".to_string()));
+        assert_eq!(menu.panels().len(), 1);
+        
+        // 点击子菜单项只展开子菜单，菜单保持显示
+        assert!(menu.execute_item("menu_denoise").is_ok());
+        assert_eq!(menu.state, MenuState::Visible);
+        assert!(menu.is_submenu_open("menu_denoise"));
+        
+        menu.hide();
+        assert_eq!(menu.panels().len(), 1);
+    }
+
+    #[test]
+    fn test_submenu_flips_left_at_screen_edge() {
+        let screen_size = PhysicalSize::new(800, 600);
+        let mut menu = ContextMenu::new(screen_size);
+        menu.show(PhysicalPosition::new(790.0, 0.0));
+        menu.expand_submenu("menu_denoise");
+        
+        let panels = menu.panels();
+        let (root, submenu) = (&panels[0], &panels[1]);
+        assert!(submenu.position.x + submenu.size.width <= root.position.x + menu.layout.border_width);
+        assert!(submenu.position.x >= 0.0);
+        assert!(submenu.position.y + submenu.size.height <= screen_size.height as f32);
+    }
+
+    #[test]
+    fn test_remove_submenu_removes_children() {
+        let screen_size = PhysicalSize::new(1920, 1080);
+        let mut menu = ContextMenu::new(screen_size);
+        menu.show(PhysicalPosition::new(100.0, 100.0));
+        menu.expand_submenu("menu_denoise");
+        
+        menu.remove_menu_item("menu_denoise");
+        assert!(!menu.items.contains_key("denoise_temporal"));
+        assert!(!menu.is_submenu("menu_denoise"));
+        assert_eq!(menu.panels().len(), 1);
+        assert!(menu.groups.get("denoise").unwrap().items.is_empty());
+    }
+
+    #[test]
+    fn test_annotation_nested_submenus() {
+        let screen_size = PhysicalSize::new(1920, 1080);
+        let mut menu = ContextMenu::new(screen_size);
+        menu.show(PhysicalPosition::new(100.0, 100.0));
+        
+        let root = menu.panels().remove(0);
+        let root_ids: Vec<&str> = menu.panel_items(&root).iter().map(|item| item.id.as_str()).collect();
+        assert!(root_ids.contains(&"menu_annotation"));
+        assert!(!root_ids.iter().any(|id| id.starts_with("annotation_")));
+        
+        menu.set_hovered_item(Some("menu_annotation".to_string()));
+        let annotation = menu.panels().remove(1);
+        let annotation_ids: Vec<&str> = menu.panel_items(&annotation).iter().map(|item| item.id.as_str()).collect();
+        assert_eq!(annotation_ids, [
+            "annotation_mode", "annotation_fade", "annotation_undo", "annotation_clear",
+            "menu_annotation_color", "menu_annotation_width",
+        ]);
+        
+        // 第二级子菜单：颜色
+        menu.set_hovered_item(Some("menu_annotation_color".to_string()));
+        let panels = menu.panels();
+        assert_eq!(panels.len(), 3);
+        assert_eq!(menu.panel_items(&panels[2]).len(), 5);
+        assert!(menu.is_submenu_open("menu_annotation"));
+        
+        // 切换到同一层的线宽时颜色子菜单收起
+        menu.set_hovered_item(Some("menu_annotation_width".to_string()));
+        let panels = menu.panels();
+        assert_eq!(panels.len(), 3);
+        assert!(!menu.is_submenu_open("menu_annotation_color"));
+        assert_eq!(menu.panel_items(&panels[2]).len(), 3);
+        
+        // 单选组仍按分组互斥
+        menu.select_radio_item("annotation_width_2");
+        assert!(menu.items.get("annotation_width_2").unwrap().checked);
+        assert!(!menu.items.get("annotation_width_1").unwrap().checked);
+        assert!(menu.items.get("annotation_color_0").unwrap().checked);
+    }
+
+
+    #[test]
+    fn test_root_menu_fits_default_window() {
+        // 默认窗口 400x400，设置分组都收进子菜单后根菜单应能完整显示
+        let screen_size = PhysicalSize::new(400, 400);
+        let mut menu = ContextMenu::new(screen_size);
+        menu.update_camera_devices(&[(0, "内置摄像头".to_string()), (1, "USB摄像头".to_string())], Some(0));
+        menu.show(PhysicalPosition::new(200.0, 200.0));
+        
+        assert!(menu.layout.size.height <= screen_size.height as f32);
+        assert!(menu.layout.position.y >= 0.0);
+        assert!(menu.layout.position.y + menu.layout.size.height <= screen_size.height as f32);
+        
+        let root = menu.panels().remove(0);
+        for item in menu.panel_items(&root) {
+            assert_ne!(item.item_type, MenuItemType::Radio, "根菜单中不应有单选项: {}", item.id);
+        }
+        for submenu_id in [
+            "menu_shapes", "menu_cameras", "menu_mask_quality", "menu_color_filter",
+            "menu_denoise", "menu_auto_correct", "menu_overlay", "menu_annotation", "menu_snapshot",
+        ] {
+            assert!(menu.is_submenu(submenu_id), "缺少子菜单: {}", submenu_id);
+        }
+    }
+
+    #[test]
+    fn test_camera_submenu() {
+        let screen_size = PhysicalSize::new(1920, 1080);
+        let mut menu = ContextMenu::new(screen_size);
+        
+        menu.update_camera_devices(&[], None);
+        assert!(!menu.items.get("menu_cameras").unwrap().enabled);
+        
+        let devices = vec![(0, "内置摄像头".to_string()), (1, "USB摄像头".to_string())];
+        menu.update_camera_devices(&devices, Some(1));
+        menu.update_camera_devices(&devices, Some(1));
+        assert!(menu.items.get("menu_cameras").unwrap().enabled);
+        assert_eq!(menu.child_ids(Some("menu_cameras")), ["camera_0", "camera_1"]);
+        assert!(menu.items.get("camera_1").unwrap().checked);
+        assert_eq!(menu.groups.get("cameras").unwrap().items.len(), 2);
+    }
+
+    #[test]
+    fn test_layout_measures_text_with_font() {
+        use crate::overlay::text::tests::BoxRasterizer;
+        use crate::ui::glyph_atlas::MenuFont;
+        
+        let screen_size = PhysicalSize::new(1920, 1080);
+        let mut menu = ContextMenu::new(screen_size);
+        
+        // 未设置字体时按最小宽度布局
+        menu.show(PhysicalPosition::new(100.0, 100.0));
+        assert_eq!(menu.layout.size.width, menu.layout.min_width);
+        
+        // BoxRasterizer：每个字符前进 0.6 个字号，字形宽 0.5 个字号
+        let font = MenuFont::shared(Box::new(BoxRasterizer::new()));
+        menu.set_font(font.clone());
+        let text = "测试一个比较长的菜单项文字";
+        menu.add_menu_item(MenuItem {
+            id: "long_item".to_string(),
+            text: text.to_string(),
+            icon: None,
+            enabled: true,
+            checked: false,
+            item_type: MenuItemType::Normal,
+            group_id: None,
+        });
+        menu.show(PhysicalPosition::new(100.0, 100.0));
+        
+        // 13 个字：最后一个字形从 12 * 8.4 取整到 101 开始，宽 7
+        let text_width = font.lock().unwrap().text_width(text, menu.layout.font_size);
+        assert_eq!(text_width, 108.0);
+        let expected = text_width.ceil() + 1.0 + menu.layout.icon_space * 2.0 + menu.layout.padding * 2.0;
+        assert_eq!(menu.layout.size.width, expected);
+        
+        // 渲染器排版后的最后一个字形不超出右侧内边距（根菜单含子菜单箭头空间）
+        let text_x = menu.layout.position.x + menu.layout.padding + menu.layout.icon_space;
+        let right = menu.layout.position.x + menu.layout.size.width - menu.layout.padding - menu.layout.icon_space;
+        let placed = font.lock().unwrap().layout_line(text, menu.layout.font_size, [text_x, 200.0]);
+        let last = placed.last().unwrap();
+        assert!(last.position[0] + last.glyph.rect[2] as f32 <= right);
+    }
+
+    #[test]
+    fn test_scale_factor_scales_layout() {
+        let screen_size = PhysicalSize::new(1920, 1080);
+        let mut menu = ContextMenu::new(screen_size);
+        menu.show(PhysicalPosition::new(100.0, 100.0));
+        let normal = menu.layout().clone();
+
+        menu.set_scale_factor(2.0);
+        assert_eq!(menu.scale_factor(), 2.0);
+        let scaled = menu.layout();
+        assert_eq!(scaled.item_height, normal.item_height * 2.0);
+        assert_eq!(scaled.font_size, MENU_FONT_SIZE * 2.0);
+        assert!(scaled.size.height > normal.size.height * 1.9);
+        assert_eq!(scaled.position.x, 100.0);
+
+        // 无效值被忽略
+        menu.set_scale_factor(0.0);
+        menu.set_scale_factor(f64::NAN);
+        assert_eq!(menu.scale_factor(), 2.0);
+    }
+}
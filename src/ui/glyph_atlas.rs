@@ -0,0 +1,407 @@
+// 菜单文字的动态字形图集
+//
+// 字形在首次使用时光栅化，按行（shelf）装入单通道图集。图集放满时尺寸翻倍，
+// 达到上限后清空重建。CPU 端保留完整像素，由 MenuRenderer 在变化时上传到 GPU。
+// 菜单布局（ContextMenu）和菜单渲染器通过 MenuFont 共用同一份字体和图集，
+// 测量出的文字宽度与实际绘制结果一致。
+
+use crate::overlay::text::{GlyphRasterizer, LineMetrics};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// 初始图集边长
+const INITIAL_ATLAS_SIZE: u32 = 256;
+/// 图集边长上限
+pub const MAX_ATLAS_SIZE: u32 = 2048;
+/// 字形之间的留白，避免线性采样时串色
+const GLYPH_PADDING: u32 = 1;
+
+/// 图集中的一个字形
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AtlasGlyph {
+    /// 图集中的像素区域 [x, y, 宽, 高]，空白字符宽高为 0
+    pub rect: [u32; 4],
+    /// 位图左上角相对笔位置的偏移（相对基线，y 向下为正）
+    pub offset: [f32; 2],
+    /// 笔位置前进量
+    pub advance: f32,
+}
+
+impl AtlasGlyph {
+    /// 是否没有可见像素
+    pub fn is_empty(&self) -> bool {
+        self.rect[2] == 0 || self.rect[3] == 0
+    }
+
+    /// 按图集边长换算纹理坐标 [u0, v0, u1, v1]
+    pub fn uv(&self, atlas_size: u32) -> [f32; 4] {
+        let size = atlas_size as f32;
+        let [x, y, w, h] = self.rect;
+        [x as f32 / size, y as f32 / size, (x + w) as f32 / size, (y + h) as f32 / size]
+    }
+}
+
+/// 排版后的字形：左上角的屏幕坐标
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PlacedGlyph {
+    pub glyph: AtlasGlyph,
+    pub position: [f32; 2],
+}
+
+/// 图集中的一行
+#[derive(Debug, Clone, Copy)]
+struct Shelf {
+    y: u32,
+    height: u32,
+    cursor_x: u32,
+}
+
+/// 动态字形图集（R8 覆盖率）
+pub struct GlyphAtlas {
+    size: u32,
+    pixels: Vec<u8>,
+    shelves: Vec<Shelf>,
+    glyphs: HashMap<(char, u32), AtlasGlyph>,
+    generation: u64,
+    dirty: bool,
+}
+
+impl Default for GlyphAtlas {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl GlyphAtlas {
+    pub fn new() -> Self {
+        Self {
+            size: INITIAL_ATLAS_SIZE,
+            pixels: vec![0; (INITIAL_ATLAS_SIZE * INITIAL_ATLAS_SIZE) as usize],
+            shelves: Vec::new(),
+            glyphs: HashMap::new(),
+            generation: 0,
+            dirty: true,
+        }
+    }
+
+    /// 图集边长（正方形）
+    pub fn size(&self) -> u32 {
+        self.size
+    }
+
+    /// 图集像素，行优先，size * size
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    /// 已缓存的字形数量
+    pub fn glyph_count(&self) -> usize {
+        self.glyphs.len()
+    }
+
+    /// 每次清空递增，之前取得的字形区域随之失效
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// 像素自上次上传后是否有变化
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    /// 上传完成后调用
+    pub fn mark_clean(&mut self) {
+        self.dirty = false;
+    }
+
+    /// 清空所有字形，保留当前尺寸
+    pub fn clear(&mut self) {
+        self.pixels.fill(0);
+        self.shelves.clear();
+        self.glyphs.clear();
+        self.generation += 1;
+        self.dirty = true;
+    }
+
+    /// 获取（必要时光栅化并装入）字形
+    pub fn glyph(&mut self, rasterizer: &dyn GlyphRasterizer, ch: char, px: f32) -> AtlasGlyph {
+        let key = (ch, px.to_bits());
+        if let Some(glyph) = self.glyphs.get(&key) {
+            return *glyph;
+        }
+
+        let bitmap = rasterizer.rasterize(ch, px);
+        let width = bitmap.width as u32;
+        let height = bitmap.height as u32;
+        let mut glyph = AtlasGlyph {
+            rect: [0, 0, 0, 0],
+            offset: [bitmap.xmin as f32, -(bitmap.ymin as f32 + bitmap.height as f32)],
+            advance: bitmap.advance,
+        };
+
+        if width > 0 && height > 0 {
+            if let Some((x, y)) = self.allocate_or_make_room(width, height) {
+                for row in 0..bitmap.height {
+                    let src = &bitmap.coverage[row * bitmap.width..(row + 1) * bitmap.width];
+                    let start = ((y as usize + row) * self.size as usize) + x as usize;
+                    self.pixels[start..start + bitmap.width].copy_from_slice(src);
+                }
+                glyph.rect = [x, y, width, height];
+                self.dirty = true;
+            }
+            // 单个字形比图集上限还大时只前进笔位置，不绘制
+        }
+
+        self.glyphs.insert(key, glyph);
+        glyph
+    }
+
+    /// 单行排版：从 origin（笔位置，基线）开始依次放置字形，包含字距调整
+    pub fn layout_line(
+        &mut self,
+        rasterizer: &dyn GlyphRasterizer,
+        text: &str,
+        px: f32,
+        origin: [f32; 2],
+    ) -> Vec<PlacedGlyph> {
+        let mut placed = Vec::with_capacity(text.len());
+        let mut pen_x = origin[0];
+        let mut previous = None;
+        for ch in text.chars() {
+            if let Some(left) = previous {
+                pen_x += rasterizer.kerning(left, ch, px);
+            }
+            previous = Some(ch);
+
+            let glyph = self.glyph(rasterizer, ch, px);
+            if !glyph.is_empty() {
+                // 对齐到整像素，避免字形被线性采样模糊
+                placed.push(PlacedGlyph {
+                    glyph,
+                    position: [(pen_x + glyph.offset[0]).round(), (origin[1] + glyph.offset[1]).round()],
+                });
+            }
+            pen_x += glyph.advance;
+        }
+        placed
+    }
+
+    /// 分配空间；放不下时先扩大图集，到达上限后清空重建
+    fn allocate_or_make_room(&mut self, width: u32, height: u32) -> Option<(u32, u32)> {
+        if width + GLYPH_PADDING > MAX_ATLAS_SIZE || height + GLYPH_PADDING > MAX_ATLAS_SIZE {
+            return None;
+        }
+        loop {
+            if let Some(position) = self.allocate(width, height) {
+                return Some(position);
+            }
+            if self.size >= MAX_ATLAS_SIZE {
+                break;
+            }
+            self.grow();
+        }
+        if self.glyphs.is_empty() {
+            return None;
+        }
+        self.clear();
+        self.allocate(width, height)
+    }
+
+    fn allocate(&mut self, width: u32, height: u32) -> Option<(u32, u32)> {
+        let padded_width = width + GLYPH_PADDING;
+        let padded_height = height + GLYPH_PADDING;
+
+        // 优先放入高度足够且最接近的行
+        let size = self.size;
+        let best = self
+            .shelves
+            .iter_mut()
+            .filter(|shelf| shelf.height >= padded_height && shelf.cursor_x + padded_width <= size)
+            .min_by_key(|shelf| shelf.height);
+        if let Some(shelf) = best {
+            let x = shelf.cursor_x;
+            shelf.cursor_x += padded_width;
+            return Some((x, shelf.y));
+        }
+
+        let y = self.shelves.last().map_or(0, |shelf| shelf.y + shelf.height);
+        if padded_width > self.size || y + padded_height > self.size {
+            return None;
+        }
+        self.shelves.push(Shelf { y, height: padded_height, cursor_x: padded_width });
+        Some((0, y))
+    }
+
+    /// 边长翻倍，已有字形位置不变
+    fn grow(&mut self) {
+        let old_size = self.size as usize;
+        let new_size = (self.size * 2).min(MAX_ATLAS_SIZE);
+        let mut pixels = vec![0; (new_size * new_size) as usize];
+        for row in 0..old_size {
+            let start = row * new_size as usize;
+            pixels[start..start + old_size].copy_from_slice(&self.pixels[row * old_size..(row + 1) * old_size]);
+        }
+        self.size = new_size;
+        self.pixels = pixels;
+        self.dirty = true;
+    }
+}
+
+/// 菜单字体：字形光栅化器和它的图集
+pub struct MenuFont {
+    rasterizer: Box<dyn GlyphRasterizer + Send>,
+    atlas: GlyphAtlas,
+}
+
+/// 菜单布局和菜单渲染器共用的字体
+pub type SharedMenuFont = Arc<Mutex<MenuFont>>;
+
+impl MenuFont {
+    pub fn new(rasterizer: Box<dyn GlyphRasterizer + Send>) -> Self {
+        Self { rasterizer, atlas: GlyphAtlas::new() }
+    }
+
+    /// 包装为可共享的字体
+    pub fn shared(rasterizer: Box<dyn GlyphRasterizer + Send>) -> SharedMenuFont {
+        Arc::new(Mutex::new(Self::new(rasterizer)))
+    }
+
+    pub fn atlas(&self) -> &GlyphAtlas {
+        &self.atlas
+    }
+
+    pub fn atlas_mut(&mut self) -> &mut GlyphAtlas {
+        &mut self.atlas
+    }
+
+    /// 指定字号的行度量
+    pub fn line_metrics(&self, px: f32) -> LineMetrics {
+        self.rasterizer.line_metrics(px)
+    }
+
+    /// 获取（必要时光栅化并装入）字形
+    pub fn glyph(&mut self, ch: char, px: f32) -> AtlasGlyph {
+        self.atlas.glyph(self.rasterizer.as_ref(), ch, px)
+    }
+
+    /// 单行排版，见 GlyphAtlas::layout_line
+    pub fn layout_line(&mut self, text: &str, px: f32, origin: [f32; 2]) -> Vec<PlacedGlyph> {
+        self.atlas.layout_line(self.rasterizer.as_ref(), text, px, origin)
+    }
+
+    /// 单行文字的绘制宽度：排版后最右侧字形的右边缘
+    pub fn text_width(&mut self, text: &str, px: f32) -> f32 {
+        self.layout_line(text, px, [0.0, 0.0])
+            .iter()
+            .map(|placed| placed.position[0] + placed.glyph.rect[2] as f32)
+            .fold(0.0, f32::max)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::overlay::text::tests::BoxRasterizer;
+    use std::sync::atomic::Ordering;
+
+    #[test]
+    fn test_glyph_cached_and_written() {
+        let rasterizer = BoxRasterizer::new();
+        let calls = rasterizer.calls.clone();
+        let mut atlas = GlyphAtlas::new();
+
+        let glyph = atlas.glyph(&rasterizer, '中', 20.0);
+        assert_eq!(glyph.rect, [0, 0, 10, 14]);
+        assert_eq!(glyph.offset, [0.0, -14.0]);
+        assert_eq!(glyph.advance, 12.0);
+        assert_eq!(atlas.pixels()[0], 255);
+        assert!(atlas.is_dirty());
+
+        atlas.mark_clean();
+        assert_eq!(atlas.glyph(&rasterizer, '中', 20.0), glyph);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert!(!atlas.is_dirty());
+
+        // 不同字号是不同的字形，放在同一行右侧并留出间隔
+        let small = atlas.glyph(&rasterizer, '中', 10.0);
+        assert_eq!(small.rect, [11, 0, 5, 7]);
+        assert!(atlas.is_dirty());
+
+        let uv = glyph.uv(atlas.size());
+        assert_eq!(uv, [0.0, 0.0, 10.0 / 256.0, 14.0 / 256.0]);
+    }
+
+    #[test]
+    fn test_whitespace_takes_no_space() {
+        let rasterizer = BoxRasterizer::new();
+        let mut atlas = GlyphAtlas::new();
+        let space = atlas.glyph(&rasterizer, ' ', 20.0);
+        assert!(space.is_empty());
+        assert_eq!(space.advance, 12.0);
+        assert_eq!(atlas.glyph(&rasterizer, 'a', 20.0).rect[0], 0);
+    }
+
+    #[test]
+    fn test_atlas_grows_then_clears() {
+        let rasterizer = BoxRasterizer::new();
+        let mut atlas = GlyphAtlas::new();
+
+        // 100px 字形为 50x70，初始图集只能放下 5x3 个
+        let first = atlas.glyph(&rasterizer, '\u{4e00}', 100.0);
+        for offset in 1..15 {
+            atlas.glyph(&rasterizer, char::from_u32(0x4e00 + offset).unwrap(), 100.0);
+        }
+        assert_eq!(atlas.size(), 256);
+        atlas.glyph(&rasterizer, '\u{4e10}', 100.0);
+        assert_eq!(atlas.size(), 512);
+        assert_eq!(atlas.generation(), 0);
+        // 扩大后原有字形位置和像素不变
+        assert_eq!(atlas.glyph(&rasterizer, '\u{4e00}', 100.0), first);
+        assert_eq!(atlas.pixels()[0], 255);
+
+        // 到达上限后清空重建
+        let mut code = 0x4e11;
+        while atlas.generation() == 0 {
+            atlas.glyph(&rasterizer, char::from_u32(code).unwrap(), 100.0);
+            code += 1;
+        }
+        assert_eq!(atlas.size(), MAX_ATLAS_SIZE);
+        assert_eq!(atlas.glyph_count(), 1);
+        assert_eq!(atlas.glyph(&rasterizer, char::from_u32(code - 1).unwrap(), 100.0).rect[..2], [0, 0]);
+    }
+
+    #[test]
+    fn test_oversized_glyph_is_skipped() {
+        let rasterizer = BoxRasterizer::new();
+        let mut atlas = GlyphAtlas::new();
+        let glyph = atlas.glyph(&rasterizer, 'W', 5000.0);
+        assert!(glyph.is_empty());
+        assert_eq!(glyph.advance, 3000.0);
+        assert_eq!(atlas.size(), 256);
+        assert_eq!(atlas.generation(), 0);
+    }
+
+    #[test]
+    fn test_menu_font_text_width() {
+        let mut font = MenuFont::new(Box::new(BoxRasterizer::new()));
+        assert_eq!(font.text_width("", 10.0), 0.0);
+        // 最后一个字形从 12 开始，宽 5
+        assert_eq!(font.text_width("a b", 10.0), 17.0);
+        // 末尾空白不计入绘制宽度
+        assert_eq!(font.text_width("a ", 10.0), 5.0);
+        assert!(font.atlas().glyph_count() > 0);
+    }
+
+    #[test]
+    fn test_layout_line() {
+        let rasterizer = BoxRasterizer::new();
+        let mut atlas = GlyphAtlas::new();
+        let placed = atlas.layout_line(&rasterizer, "a b", 10.0, [100.0, 50.0]);
+
+        // 空格不生成字形，但占据前进量
+        assert_eq!(placed.len(), 2);
+        assert_eq!(placed[0].position, [100.0, 43.0]);
+        assert_eq!(placed[1].position, [112.0, 43.0]);
+        assert_eq!(placed[1].glyph.rect[2..], [5, 7]);
+    }
+}
@@ -1,36 +1,47 @@
 // 菜单渲染器
 //
-// 负责渲染上下文菜单的视觉元素，包括背景、文本、图标、边框等
+// 负责渲染上下文菜单的视觉元素，包括背景、文本、勾选标记、子菜单箭头、边框等。
+// 文字使用真实字体按需光栅化到动态字形图集（见 glyph_atlas.rs），
+// 支持中文、字距调整，字号随显示器缩放系数变化。字体与 ContextMenu 共用，
+// 布局时测量的文字宽度与这里的排版一致。
 
-use crate::ui::context_menu::{ContextMenu, MenuItemType, MenuLayout};
+use crate::ui::context_menu::{ContextMenu, MenuItem, MenuItemType, MenuLayout, MenuState};
+use crate::ui::glyph_atlas::{GlyphAtlas, MenuFont, PlacedGlyph, SharedMenuFont};
 use log::debug;
-use std::collections::HashMap;
 use wgpu::util::DeviceExt;
 
+/// 复选项勾选标记
+const CHECK_MARK: char = '✓';
+/// 单选项选中标记
+const RADIO_MARK: char = '●';
+/// 子菜单箭头
+const SUBMENU_ARROW: char = '▶';
+
 /// 菜单渲染器
+///
+/// 不持有 device 和 queue，渲染时由调用方（渲染引擎）传入
 pub struct MenuRenderer {
-    device: wgpu::Device,
-    queue: wgpu::Queue,
-    
     // 渲染管线
-    menu_pipeline: Option<wgpu::RenderPipeline>,
-    text_pipeline: Option<wgpu::RenderPipeline>,
-    
+    menu_pipeline: wgpu::RenderPipeline,
+    text_pipeline: wgpu::RenderPipeline,
+
     // 缓冲区
     vertex_buffer: Option<wgpu::Buffer>,
     index_buffer: Option<wgpu::Buffer>,
-    uniform_buffer: Option<wgpu::Buffer>,
-    
-    // 绑定组
-    bind_group_layout: Option<wgpu::BindGroupLayout>,
+    uniform_buffer: wgpu::Buffer,
+
+    // 绑定组（图集纹理重建后需要重新创建）
+    bind_group_layout: wgpu::BindGroupLayout,
     uniform_bind_group: Option<wgpu::BindGroup>,
-    
+
     // 字体和文本渲染
-    font_atlas: Option<FontAtlas>,
+    font: SharedMenuFont,
+    atlas_texture: Option<AtlasTexture>,
+    sampler: wgpu::Sampler,
     text_vertices: Vec<TextVertex>,
-    
-    // 菜单几何体缓存
-    menu_geometry: Option<MenuGeometry>,
+
+    // 菜单背景索引数量
+    index_count: u32,
 }
 
 /// 菜单顶点数据
@@ -93,46 +104,30 @@ struct MenuUniforms {
     _padding: [f32; 2],        // 对齐填充
 }
 
-/// 菜单几何体数据
-struct MenuGeometry {
-    vertices: Vec<MenuVertex>,
-    indices: Vec<u16>,
-    vertex_count: u32,
-    index_count: u32,
-}
-
-/// 简化的字体图集
-struct FontAtlas {
+/// GPU 上的字形图集
+struct AtlasTexture {
     texture: wgpu::Texture,
-    texture_view: wgpu::TextureView,
-    sampler: wgpu::Sampler,
-    char_map: HashMap<char, CharInfo>,
+    view: wgpu::TextureView,
+    size: u32,
 }
 
-/// 字符信息
-struct CharInfo {
-    tex_coords: [f32; 4], // [x1, y1, x2, y2] 纹理坐标
-    size: [f32; 2],       // 字符尺寸
-    bearing: [f32; 2],    // 字符偏移
-    advance: f32,         // 字符间距
+/// 一段待绘制的文字：已排版的字形和颜色
+struct TextRun {
+    glyphs: Vec<PlacedGlyph>,
+    color: [f32; 4],
 }
 
 impl MenuRenderer {
     /// 创建菜单渲染器
-    pub fn new(_device: &wgpu::Device, _queue: &wgpu::Queue, _surface_format: wgpu::TextureFormat) -> Result<Self, String> {
+    pub fn new(
+        device: &wgpu::Device,
+        surface_format: wgpu::TextureFormat,
+        font: SharedMenuFont,
+    ) -> Result<Self, String> {
         debug!("创建菜单渲染器");
-        
-        // 由于我们需要存储 device 和 queue 的引用，但它们的生命周期管理比较复杂
-        // 暂时返回错误，让系统使用简单文本菜单
-        Err("菜单渲染器暂时不可用，使用简单文本菜单".to_string())
-    }
-    
-    /// 初始化渲染管线
-    fn init_pipelines(&mut self, surface_format: wgpu::TextureFormat) -> Result<(), String> {
-        debug!("初始化菜单渲染管线");
-        
+
         // 创建绑定组布局
-        let bind_group_layout = self.device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
             entries: &[
                 // 统一缓冲区
                 wgpu::BindGroupLayoutEntry {
@@ -166,7 +161,7 @@ impl MenuRenderer {
             ],
             label: Some("menu_bind_group_layout"),
         });
-        
+
         // 创建统一缓冲区
         let uniforms = MenuUniforms {
             screen_size: [1920.0, 1080.0], // 默认值，会在渲染时更新
@@ -174,189 +169,47 @@ impl MenuRenderer {
             menu_size: [200.0, 100.0],
             _padding: [0.0, 0.0],
         };
-        
-        let uniform_buffer = self.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+
+        let uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
             label: Some("Menu Uniform Buffer"),
             contents: bytemuck::cast_slice(&[uniforms]),
             usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
         });
-        
+
         // 创建菜单渲染管线布局
-        let pipeline_layout = self.device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
             label: Some("Menu Pipeline Layout"),
             bind_group_layouts: &[&bind_group_layout],
             push_constant_ranges: &[],
         });
-        
+
         // 创建菜单着色器
-        let menu_shader = self.device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        let menu_shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
             label: Some("Menu Shader"),
             source: wgpu::ShaderSource::Wgsl(include_str!("menu_shader.wgsl").into()),
         });
-        
-        // 创建菜单渲染管线
-        let menu_pipeline = self.device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: Some("Menu Render Pipeline"),
-            layout: Some(&pipeline_layout),
-            vertex: wgpu::VertexState {
-                module: &menu_shader,
-                entry_point: "menu_vs_main",
-                buffers: &[MenuVertex::desc()],
-                compilation_options: wgpu::PipelineCompilationOptions::default(),
-            },
-            fragment: Some(wgpu::FragmentState {
-                module: &menu_shader,
-                entry_point: "menu_fs_main",
-                targets: &[Some(wgpu::ColorTargetState {
-                    format: surface_format,
-                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
-                    write_mask: wgpu::ColorWrites::ALL,
-                })],
-                compilation_options: wgpu::PipelineCompilationOptions::default(),
-            }),
-            primitive: wgpu::PrimitiveState {
-                topology: wgpu::PrimitiveTopology::TriangleList,
-                strip_index_format: None,
-                front_face: wgpu::FrontFace::Ccw,
-                cull_mode: None,
-                polygon_mode: wgpu::PolygonMode::Fill,
-                unclipped_depth: false,
-                conservative: false,
-            },
-            depth_stencil: None,
-            multisample: wgpu::MultisampleState {
-                count: 1,
-                mask: !0,
-                alpha_to_coverage_enabled: false,
-            },
-            multiview: None,
-        });
-        
-        // 创建文本渲染管线
-        let text_pipeline = self.device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: Some("Text Render Pipeline"),
-            layout: Some(&pipeline_layout),
-            vertex: wgpu::VertexState {
-                module: &menu_shader,
-                entry_point: "text_vs_main",
-                buffers: &[TextVertex::desc()],
-                compilation_options: wgpu::PipelineCompilationOptions::default(),
-            },
-            fragment: Some(wgpu::FragmentState {
-                module: &menu_shader,
-                entry_point: "text_fs_main",
-                targets: &[Some(wgpu::ColorTargetState {
-                    format: surface_format,
-                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
-                    write_mask: wgpu::ColorWrites::ALL,
-                })],
-                compilation_options: wgpu::PipelineCompilationOptions::default(),
-            }),
-            primitive: wgpu::PrimitiveState {
-                topology: wgpu::PrimitiveTopology::TriangleList,
-                strip_index_format: None,
-                front_face: wgpu::FrontFace::Ccw,
-                cull_mode: None,
-                polygon_mode: wgpu::PolygonMode::Fill,
-                unclipped_depth: false,
-                conservative: false,
-            },
-            depth_stencil: None,
-            multisample: wgpu::MultisampleState {
-                count: 1,
-                mask: !0,
-                alpha_to_coverage_enabled: false,
-            },
-            multiview: None,
-        });
-        
-        // 存储组件
-        self.bind_group_layout = Some(bind_group_layout);
-        self.uniform_buffer = Some(uniform_buffer);
-        self.menu_pipeline = Some(menu_pipeline);
-        self.text_pipeline = Some(text_pipeline);
-        
-        debug!("菜单渲染管线初始化完成");
-        Ok(())
-    }
-    
-    /// 创建简化的字体图集
-    fn create_font_atlas(&mut self) -> Result<(), String> {
-        debug!("创建字体图集");
-        
-        // 创建简化的字体纹理（8x8像素的字符，支持基本ASCII字符）
-        let atlas_width = 128;
-        let atlas_height = 128;
-        let char_size = 8;
-        
-        // 生成简化的字体数据（位图字体）
-        let mut atlas_data = vec![0u8; (atlas_width * atlas_height) as usize];
-        let mut char_map = HashMap::new();
-        
-        // 添加基本字符（简化实现，实际应用中需要真实的字体渲染）
-        let chars = "ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789 .,!?-+()[]{}:;";
-        
-        for (i, ch) in chars.chars().enumerate() {
-            let x = (i % 16) * char_size;
-            let y = (i / 16) * char_size;
-            
-            // 简化的字符渲染（实际应用中需要使用真实字体）
-            self.render_simple_char(&mut atlas_data, atlas_width, x, y, char_size, ch);
-            
-            // 添加字符信息
-            let tex_x1 = x as f32 / atlas_width as f32;
-            let tex_y1 = y as f32 / atlas_height as f32;
-            let tex_x2 = (x + char_size) as f32 / atlas_width as f32;
-            let tex_y2 = (y + char_size) as f32 / atlas_height as f32;
-            
-            char_map.insert(ch, CharInfo {
-                tex_coords: [tex_x1, tex_y1, tex_x2, tex_y2],
-                size: [char_size as f32, char_size as f32],
-                bearing: [0.0, 0.0],
-                advance: char_size as f32,
-            });
-        }
-        
-        // 创建字体纹理
-        let texture = self.device.create_texture(&wgpu::TextureDescriptor {
-            size: wgpu::Extent3d {
-                width: atlas_width,
-                height: atlas_height,
-                depth_or_array_layers: 1,
-            },
-            mip_level_count: 1,
-            sample_count: 1,
-            dimension: wgpu::TextureDimension::D2,
-            format: wgpu::TextureFormat::R8Unorm,
-            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
-            label: Some("font_atlas_texture"),
-            view_formats: &[],
-        });
-        
-        // 上传字体数据
-        self.queue.write_texture(
-            wgpu::ImageCopyTexture {
-                texture: &texture,
-                mip_level: 0,
-                origin: wgpu::Origin3d::ZERO,
-                aspect: wgpu::TextureAspect::All,
-            },
-            &atlas_data,
-            wgpu::ImageDataLayout {
-                offset: 0,
-                bytes_per_row: Some(atlas_width),
-                rows_per_image: Some(atlas_height),
-            },
-            wgpu::Extent3d {
-                width: atlas_width,
-                height: atlas_height,
-                depth_or_array_layers: 1,
-            },
+
+        let menu_pipeline = create_pipeline(
+            device,
+            &pipeline_layout,
+            &menu_shader,
+            surface_format,
+            "Menu Render Pipeline",
+            ("menu_vs_main", "menu_fs_main"),
+            MenuVertex::desc(),
         );
-        
-        // 创建纹理视图和采样器
-        let texture_view = texture.create_view(&wgpu::TextureViewDescriptor::default());
-        let sampler = self.device.create_sampler(&wgpu::SamplerDescriptor {
+        let text_pipeline = create_pipeline(
+            device,
+            &pipeline_layout,
+            &menu_shader,
+            surface_format,
+            "Text Render Pipeline",
+            ("text_vs_main", "text_fs_main"),
+            TextVertex::desc(),
+        );
+
+        // 字形已按整像素对齐，线性采样只影响缩放中的边缘
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
             address_mode_u: wgpu::AddressMode::ClampToEdge,
             address_mode_v: wgpu::AddressMode::ClampToEdge,
             address_mode_w: wgpu::AddressMode::ClampToEdge,
@@ -365,90 +218,68 @@ impl MenuRenderer {
             mipmap_filter: wgpu::FilterMode::Nearest,
             ..Default::default()
         });
-        
-        // 创建字体图集
-        self.font_atlas = Some(FontAtlas {
-            texture,
-            texture_view,
+
+        debug!("菜单渲染管线初始化完成");
+        Ok(Self {
+            menu_pipeline,
+            text_pipeline,
+            vertex_buffer: None,
+            index_buffer: None,
+            uniform_buffer,
+            bind_group_layout,
+            uniform_bind_group: None,
+            font,
+            atlas_texture: None,
             sampler,
-            char_map,
-        });
-        
-        debug!("字体图集创建完成");
-        Ok(())
+            text_vertices: Vec::new(),
+            index_count: 0,
+        })
     }
-    
-    /// 渲染简化字符到图集
-    fn render_simple_char(&self, atlas_data: &mut [u8], atlas_width: u32, x: usize, y: usize, size: usize, ch: char) {
-        // 简化的字符渲染（使用基本形状表示字符）
-        match ch {
-            ' ' => {}, // 空格不渲染
-            '.' => {
-                // 点
-                let center_x = x + size / 2;
-                let center_y = y + size - 2;
-                if center_x < atlas_width as usize && center_y < atlas_width as usize {
-                    atlas_data[center_y * atlas_width as usize + center_x] = 255;
-                }
-            }
-            '-' => {
-                // 横线
-                let line_y = y + size / 2;
-                for px in (x + 1)..(x + size - 1) {
-                    if px < atlas_width as usize && line_y < atlas_width as usize {
-                        atlas_data[line_y * atlas_width as usize + px] = 255;
-                    }
-                }
-            }
-            '|' => {
-                // 竖线
-                let line_x = x + size / 2;
-                for py in (y + 1)..(y + size - 1) {
-                    if line_x < atlas_width as usize && py < atlas_width as usize {
-                        atlas_data[py * atlas_width as usize + line_x] = 255;
-                    }
-                }
-            }
-            _ => {
-                // 其他字符用简单矩形表示
-                for py in (y + 1)..(y + size - 1) {
-                    for px in (x + 1)..(x + size - 1) {
-                        if px < atlas_width as usize && py < atlas_width as usize {
-                            // 创建简单的字符轮廓
-                            if px == x + 1 || px == x + size - 2 || py == y + 1 || py == y + size - 2 {
-                                atlas_data[py * atlas_width as usize + px] = 255;
-                            }
-                        }
-                    }
-                }
-            }
-        }
+
+    /// 菜单字体，GPU 设备重建后用它创建新的菜单渲染器
+    pub fn font(&self) -> SharedMenuFont {
+        self.font.clone()
     }
-    
-    /// 渲染菜单
+
+    /// 渲染菜单（在 view 现有内容之上）
     pub fn render_menu(
         &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
         encoder: &mut wgpu::CommandEncoder,
         view: &wgpu::TextureView,
         menu: &ContextMenu,
         screen_size: [f32; 2],
     ) -> Result<(), String> {
-        if menu.state() != &crate::ui::context_menu::MenuState::Visible {
+        if menu.state() != &MenuState::Visible {
             return Ok(()); // 菜单不可见，跳过渲染
         }
-        
+
         debug!("渲染上下文菜单");
-        
-        // 生成菜单几何体
-        self.generate_menu_geometry(menu)?;
-        
-        // 更新统一缓冲区
-        self.update_uniforms(menu.layout(), screen_size)?;
-        
-        // 创建绑定组
-        self.create_bind_group()?;
-        
-        // 创建渲染通道
+
+        // 生成菜单几何体和文本顶点（可能向图集中加入新字形）
+        let font = self.font.clone();
+        let mut font = font.lock().map_err(|_| "菜单字体锁已损坏".to_string())?;
+        self.generate_menu_geometry(device, menu);
+        self.generate_text_vertices(&mut font, menu);
+
+        // 图集有变化时上传，尺寸变化时重建纹理和绑定组
+        self.sync_atlas_texture(device, queue, font.atlas_mut());
+        self.update_uniforms(queue, menu.layout(), screen_size);
+        self.create_bind_group(device)?;
+        let bind_group = self.uniform_bind_group.as_ref().ok_or("菜单绑定组未创建")?;
+
+        let text_vertex_buffer = if self.text_vertices.is_empty() {
+            None
+        } else {
+            Some(device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Text Vertex Buffer"),
+                contents: bytemuck::cast_slice(&self.text_vertices),
+                usage: wgpu::BufferUsages::VERTEX,
+            }))
+        };
+
+        // 背景和文字在同一个渲染通道中绘制
         let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
             label: Some("Menu Render Pass"),
             color_attachments: &[Some(wgpu::RenderPassColorAttachment {
@@ -463,186 +294,135 @@ impl MenuRenderer {
             occlusion_query_set: None,
             timestamp_writes: None,
         });
-        
+        render_pass.set_bind_group(0, bind_group, &[]);
+
         // 渲染菜单背景和边框
-        if let (Some(pipeline), Some(bind_group)) = (&self.menu_pipeline, &self.uniform_bind_group) {
-            render_pass.set_pipeline(pipeline);
-            render_pass.set_bind_group(0, bind_group, &[]);
-            
-            if let Some(geometry) = &self.menu_geometry {
-                if let (Some(vertex_buffer), Some(index_buffer)) = (&self.vertex_buffer, &self.index_buffer) {
-                    render_pass.set_vertex_buffer(0, vertex_buffer.slice(..));
-                    render_pass.set_index_buffer(index_buffer.slice(..), wgpu::IndexFormat::Uint16);
-                    render_pass.draw_indexed(0..geometry.index_count, 0, 0..1);
-                }
-            }
+        if let (Some(vertex_buffer), Some(index_buffer)) = (&self.vertex_buffer, &self.index_buffer) {
+            render_pass.set_pipeline(&self.menu_pipeline);
+            render_pass.set_vertex_buffer(0, vertex_buffer.slice(..));
+            render_pass.set_index_buffer(index_buffer.slice(..), wgpu::IndexFormat::Uint16);
+            render_pass.draw_indexed(0..self.index_count, 0, 0..1);
         }
-        
-        // 结束渲染通道以释放借用
-        drop(render_pass);
-        
-        // 渲染文本
-        self.render_menu_text(encoder, view, menu)?;
-        
+
+        // 渲染文本（三角形列表，每个字形6个顶点）
+        if let Some(text_vertex_buffer) = &text_vertex_buffer {
+            render_pass.set_pipeline(&self.text_pipeline);
+            render_pass.set_vertex_buffer(0, text_vertex_buffer.slice(..));
+            render_pass.draw(0..self.text_vertices.len() as u32, 0..1);
+        }
+
         debug!("菜单渲染完成");
         Ok(())
     }
-    
+
     /// 生成菜单几何体
-    fn generate_menu_geometry(&mut self, menu: &ContextMenu) -> Result<(), String> {
+    fn generate_menu_geometry(&mut self, device: &wgpu::Device, menu: &ContextMenu) {
         let layout = menu.layout();
-        let items = menu.get_display_items();
-        
+
         let mut vertices = Vec::new();
         let mut indices = Vec::new();
         let mut vertex_offset = 0u16;
-        
+
         // 菜单背景颜色
         let bg_color = [0.2, 0.2, 0.2, 0.9]; // 深灰色半透明背景
         let border_color = [0.4, 0.4, 0.4, 1.0]; // 边框颜色
         let hover_color = [0.3, 0.3, 0.3, 0.9]; // 悬浮高亮颜色
-        
-        // 生成菜单背景矩形
-        let bg_vertices = self.create_rect_vertices(
-            layout.position.x,
-            layout.position.y,
-            layout.size.width,
-            layout.size.height,
-            bg_color,
-        );
-        vertices.extend(bg_vertices);
-        
+
         // 背景矩形索引
         let bg_indices = [0, 1, 2, 0, 2, 3];
-        indices.extend(bg_indices.iter().map(|&i| i + vertex_offset));
-        vertex_offset += 4;
-        
-        // 生成菜单边框
         let border_width = layout.border_width;
-        if border_width > 0.0 {
-            // 上边框
-            let top_border = self.create_rect_vertices(
-                layout.position.x,
-                layout.position.y,
-                layout.size.width,
-                border_width,
-                border_color,
-            );
-            vertices.extend(top_border);
-            indices.extend(bg_indices.iter().map(|&i| i + vertex_offset));
-            vertex_offset += 4;
-            
-            // 下边框
-            let bottom_border = self.create_rect_vertices(
-                layout.position.x,
-                layout.position.y + layout.size.height - border_width,
-                layout.size.width,
-                border_width,
-                border_color,
-            );
-            vertices.extend(bottom_border);
-            indices.extend(bg_indices.iter().map(|&i| i + vertex_offset));
-            vertex_offset += 4;
-            
-            // 左边框
-            let left_border = self.create_rect_vertices(
-                layout.position.x,
-                layout.position.y,
-                border_width,
-                layout.size.height,
-                border_color,
-            );
-            vertices.extend(left_border);
-            indices.extend(bg_indices.iter().map(|&i| i + vertex_offset));
-            vertex_offset += 4;
-            
-            // 右边框
-            let right_border = self.create_rect_vertices(
-                layout.position.x + layout.size.width - border_width,
-                layout.position.y,
-                border_width,
-                layout.size.height,
-                border_color,
-            );
-            vertices.extend(right_border);
+
+        // 根菜单和展开的子菜单依次绘制，子菜单覆盖在父菜单之上
+        for panel in menu.panels() {
+            let position = panel.position;
+            let size = panel.size;
+
+            // 生成面板背景矩形
+            vertices.extend(self.create_rect_vertices(position.x, position.y, size.width, size.height, bg_color));
             indices.extend(bg_indices.iter().map(|&i| i + vertex_offset));
             vertex_offset += 4;
-        }
-        
-        // 生成菜单项背景（悬浮高亮和分隔线）
-        let mut current_y = layout.position.y + layout.padding;
-        
-        for item in items {
-            match item.item_type {
-                MenuItemType::Separator => {
-                    // 分隔线
-                    let separator_vertices = self.create_rect_vertices(
-                        layout.position.x + layout.padding,
-                        current_y + layout.separator_height / 2.0 - 0.5,
-                        layout.size.width - layout.padding * 2.0,
-                        1.0,
-                        [0.5, 0.5, 0.5, 0.8],
-                    );
-                    vertices.extend(separator_vertices);
+
+            // 生成面板边框
+            if border_width > 0.0 {
+                let borders = [
+                    // 上边框
+                    (position.x, position.y, size.width, border_width),
+                    // 下边框
+                    (position.x, position.y + size.height - border_width, size.width, border_width),
+                    // 左边框
+                    (position.x, position.y, border_width, size.height),
+                    // 右边框
+                    (position.x + size.width - border_width, position.y, border_width, size.height),
+                ];
+                for (x, y, width, height) in borders {
+                    vertices.extend(self.create_rect_vertices(x, y, width, height, border_color));
                     indices.extend(bg_indices.iter().map(|&i| i + vertex_offset));
                     vertex_offset += 4;
-                    
-                    current_y += layout.separator_height;
                 }
-                _ => {
-                    // 检查是否为悬浮项
-                    let is_hovered = menu.hovered_item() == Some(&item.id);
-                    
-                    if is_hovered {
-                        // 悬浮高亮背景
-                        let hover_vertices = self.create_rect_vertices(
-                            layout.position.x + 1.0,
+            }
+
+            // 生成菜单项背景（悬浮高亮和分隔线）
+            let mut current_y = position.y + layout.padding;
+
+            for item in menu.panel_items(&panel) {
+                match item.item_type {
+                    MenuItemType::Separator => {
+                        // 分隔线
+                        let separator_vertices = self.create_rect_vertices(
+                            position.x + layout.padding,
                             current_y,
-                            layout.size.width - 2.0,
-                            layout.item_height,
-                            hover_color,
+                            size.width - layout.padding * 2.0,
+                            layout.separator_height,
+                            [0.5, 0.5, 0.5, 0.8],
                         );
-                        vertices.extend(hover_vertices);
+                        vertices.extend(separator_vertices);
                         indices.extend(bg_indices.iter().map(|&i| i + vertex_offset));
                         vertex_offset += 4;
+
+                        current_y += layout.separator_height;
+                    }
+                    _ => {
+                        // 悬浮项和已展开子菜单的父项都高亮
+                        let is_highlighted = menu.hovered_item() == Some(&item.id) || menu.is_submenu_open(&item.id);
+
+                        if is_highlighted && item.enabled {
+                            // 悬浮高亮背景
+                            let hover_vertices = self.create_rect_vertices(
+                                position.x + border_width,
+                                current_y,
+                                size.width - border_width * 2.0,
+                                layout.item_height,
+                                hover_color,
+                            );
+                            vertices.extend(hover_vertices);
+                            indices.extend(bg_indices.iter().map(|&i| i + vertex_offset));
+                            vertex_offset += 4;
+                        }
+
+                        current_y += layout.item_height;
                     }
-                    
-                    current_y += layout.item_height;
                 }
             }
         }
-        
+
         // 创建GPU缓冲区
-        if !vertices.is_empty() {
-            let vertex_buffer = self.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-                label: Some("Menu Vertex Buffer"),
-                contents: bytemuck::cast_slice(&vertices),
-                usage: wgpu::BufferUsages::VERTEX,
-            });
-            
-            let index_buffer = self.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-                label: Some("Menu Index Buffer"),
-                contents: bytemuck::cast_slice(&indices),
-                usage: wgpu::BufferUsages::INDEX,
-            });
-            
-            let vertex_count = vertices.len() as u32;
-            let index_count = indices.len() as u32;
-            
-            self.vertex_buffer = Some(vertex_buffer);
-            self.index_buffer = Some(index_buffer);
-            
-            self.menu_geometry = Some(MenuGeometry {
-                vertices,
-                indices,
-                vertex_count,
-                index_count,
-            });
-        }
-        
-        Ok(())
+        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Menu Vertex Buffer"),
+            contents: bytemuck::cast_slice(&vertices),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+
+        let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Menu Index Buffer"),
+            contents: bytemuck::cast_slice(&indices),
+            usage: wgpu::BufferUsages::INDEX,
+        });
+
+        self.vertex_buffer = Some(vertex_buffer);
+        self.index_buffer = Some(index_buffer);
+        self.index_count = indices.len() as u32;
     }
-    
+
     /// 创建矩形顶点
     fn create_rect_vertices(&self, x: f32, y: f32, width: f32, height: f32, color: [f32; 4]) -> Vec<MenuVertex> {
         vec![
@@ -652,194 +432,249 @@ impl MenuRenderer {
             MenuVertex { position: [x, y + height], color, tex_coords: [0.0, 1.0] },                 // 左下
         ]
     }
-    
-    /// 渲染菜单文本
-    fn render_menu_text(&mut self, encoder: &mut wgpu::CommandEncoder, view: &wgpu::TextureView, menu: &ContextMenu) -> Result<(), String> {
-        // 生成文本顶点
-        self.generate_text_vertices(menu)?;
-        
-        if let (Some(text_pipeline), Some(bind_group), Some(_font_atlas)) = 
-            (&self.text_pipeline, &self.uniform_bind_group, &self.font_atlas) {
-            
-            if !self.text_vertices.is_empty() {
-                // 创建文本顶点缓冲区
-                let text_vertex_buffer = self.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-                    label: Some("Text Vertex Buffer"),
-                    contents: bytemuck::cast_slice(&self.text_vertices),
-                    usage: wgpu::BufferUsages::VERTEX,
-                });
-                
-                // 创建新的渲染通道用于文本
-                let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-                    label: Some("Text Render Pass"),
-                    color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                        view,
-                        resolve_target: None,
-                        ops: wgpu::Operations {
-                            load: wgpu::LoadOp::Load,
-                            store: wgpu::StoreOp::Store,
-                        },
-                    })],
-                    depth_stencil_attachment: None,
-                    occlusion_query_set: None,
-                    timestamp_writes: None,
-                });
-                
-                // 切换到文本渲染管线
-                render_pass.set_pipeline(text_pipeline);
-                render_pass.set_bind_group(0, bind_group, &[]);
-                render_pass.set_vertex_buffer(0, text_vertex_buffer.slice(..));
-                
-                // 绘制文本（使用三角形列表，每个字符6个顶点）
-                let vertex_count = self.text_vertices.len() as u32;
-                render_pass.draw(0..vertex_count, 0..1);
-            }
-        }
-        
-        Ok(())
-    }
-    
+
     /// 生成文本顶点
-    fn generate_text_vertices(&mut self, menu: &ContextMenu) -> Result<(), String> {
+    fn generate_text_vertices(&mut self, font: &mut MenuFont, menu: &ContextMenu) {
+        let generation = font.atlas().generation();
+        let mut runs = layout_menu_text(font, menu);
+        if font.atlas().generation() != generation {
+            // 图集在排版过程中被清空重建，之前取得的字形区域已失效
+            runs = layout_menu_text(font, menu);
+        }
+
+        // 图集可能在排版过程中扩大，纹理坐标按最终尺寸计算
+        let atlas_size = font.atlas().size();
         self.text_vertices.clear();
-        
-        let layout = menu.layout();
-        let items = menu.get_display_items();
-        let font_atlas = self.font_atlas.as_ref().ok_or("字体图集未初始化")?;
-        
-        let mut current_y = layout.position.y + layout.padding;
-        let text_color = [1.0, 1.0, 1.0, 1.0]; // 白色文本
-        let disabled_color = [0.6, 0.6, 0.6, 1.0]; // 禁用文本颜色
-        
-        for item in items {
-            match item.item_type {
-                MenuItemType::Separator => {
-                    current_y += layout.separator_height;
-                }
-                _ => {
-                    if !item.text.is_empty() {
-                        let color = if item.enabled { text_color } else { disabled_color };
-                        let text_x = layout.position.x + layout.padding + 24.0; // 为图标留出空间
-                        let text_y = current_y + (layout.item_height - 8.0) / 2.0; // 垂直居中
-                        
-                        // 渲染文本字符
-                        let mut char_x = text_x;
-                        for ch in item.text.chars() {
-                            if let Some(char_info) = font_atlas.char_map.get(&ch) {
-                                // 创建字符的四个顶点（两个三角形）
-                                let x1 = char_x;
-                                let y1 = text_y;
-                                let x2 = char_x + char_info.size[0];
-                                let y2 = text_y + char_info.size[1];
-                                
-                                let tex_coords = char_info.tex_coords;
-                                
-                                // 第一个三角形
-                                self.text_vertices.push(TextVertex {
-                                    position: [x1, y1],
-                                    tex_coords: [tex_coords[0], tex_coords[1]],
-                                    color,
-                                });
-                                self.text_vertices.push(TextVertex {
-                                    position: [x2, y1],
-                                    tex_coords: [tex_coords[2], tex_coords[1]],
-                                    color,
-                                });
-                                self.text_vertices.push(TextVertex {
-                                    position: [x1, y2],
-                                    tex_coords: [tex_coords[0], tex_coords[3]],
-                                    color,
-                                });
-                                
-                                // 第二个三角形
-                                self.text_vertices.push(TextVertex {
-                                    position: [x2, y1],
-                                    tex_coords: [tex_coords[2], tex_coords[1]],
-                                    color,
-                                });
-                                self.text_vertices.push(TextVertex {
-                                    position: [x2, y2],
-                                    tex_coords: [tex_coords[2], tex_coords[3]],
-                                    color,
-                                });
-                                self.text_vertices.push(TextVertex {
-                                    position: [x1, y2],
-                                    tex_coords: [tex_coords[0], tex_coords[3]],
-                                    color,
-                                });
-                                
-                                char_x += char_info.advance;
-                            }
-                        }
-                    }
-                    
-                    current_y += layout.item_height;
-                }
+        for run in runs {
+            for placed in run.glyphs {
+                let [u0, v0, u1, v1] = placed.glyph.uv(atlas_size);
+                let [x1, y1] = placed.position;
+                let x2 = x1 + placed.glyph.rect[2] as f32;
+                let y2 = y1 + placed.glyph.rect[3] as f32;
+                let color = run.color;
+
+                self.text_vertices.extend_from_slice(&[
+                    // 第一个三角形
+                    TextVertex { position: [x1, y1], tex_coords: [u0, v0], color },
+                    TextVertex { position: [x2, y1], tex_coords: [u1, v0], color },
+                    TextVertex { position: [x1, y2], tex_coords: [u0, v1], color },
+                    // 第二个三角形
+                    TextVertex { position: [x2, y1], tex_coords: [u1, v0], color },
+                    TextVertex { position: [x2, y2], tex_coords: [u1, v1], color },
+                    TextVertex { position: [x1, y2], tex_coords: [u0, v1], color },
+                ]);
             }
         }
-        
-        Ok(())
     }
-    
+
+    /// 图集有变化时上传到 GPU，尺寸变化时重建纹理
+    fn sync_atlas_texture(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, atlas: &mut GlyphAtlas) {
+        let size = atlas.size();
+        let extent = wgpu::Extent3d {
+            width: size,
+            height: size,
+            depth_or_array_layers: 1,
+        };
+
+        if self.atlas_texture.as_ref().map(|texture| texture.size) != Some(size) {
+            debug!("创建字形图集纹理: {}x{}", size, size);
+            let texture = device.create_texture(&wgpu::TextureDescriptor {
+                size: extent,
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: wgpu::TextureFormat::R8Unorm,
+                usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+                label: Some("font_atlas_texture"),
+                view_formats: &[],
+            });
+            let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+            self.atlas_texture = Some(AtlasTexture { texture, view, size });
+            // 绑定组引用旧纹理视图，需要重建；新纹理内容需要完整上传
+            self.uniform_bind_group = None;
+        } else if !atlas.is_dirty() {
+            return;
+        }
+
+        if let Some(atlas_texture) = &self.atlas_texture {
+            queue.write_texture(
+                wgpu::ImageCopyTexture {
+                    texture: &atlas_texture.texture,
+                    mip_level: 0,
+                    origin: wgpu::Origin3d::ZERO,
+                    aspect: wgpu::TextureAspect::All,
+                },
+                atlas.pixels(),
+                wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(size),
+                    rows_per_image: Some(size),
+                },
+                extent,
+            );
+            atlas.mark_clean();
+        }
+    }
+
     /// 更新统一缓冲区
-    fn update_uniforms(&mut self, layout: &MenuLayout, screen_size: [f32; 2]) -> Result<(), String> {
+    fn update_uniforms(&self, queue: &wgpu::Queue, layout: &MenuLayout, screen_size: [f32; 2]) {
         let uniforms = MenuUniforms {
             screen_size,
             menu_position: [layout.position.x, layout.position.y],
             menu_size: [layout.size.width, layout.size.height],
             _padding: [0.0, 0.0],
         };
-        
-        if let Some(uniform_buffer) = &self.uniform_buffer {
-            self.queue.write_buffer(uniform_buffer, 0, bytemuck::cast_slice(&[uniforms]));
-        }
-        
-        Ok(())
+
+        queue.write_buffer(&self.uniform_buffer, 0, bytemuck::cast_slice(&[uniforms]));
     }
-    
+
     /// 创建绑定组
-    fn create_bind_group(&mut self) -> Result<(), String> {
+    fn create_bind_group(&mut self, device: &wgpu::Device) -> Result<(), String> {
         if self.uniform_bind_group.is_some() {
             return Ok(()); // 已创建
         }
-        
-        let (bind_group_layout, uniform_buffer, font_atlas) = match (
-            &self.bind_group_layout,
-            &self.uniform_buffer,
-            &self.font_atlas,
-        ) {
-            (Some(layout), Some(buffer), Some(atlas)) => (layout, buffer, atlas),
-            _ => return Err("绑定组组件未初始化".to_string()),
-        };
-        
-        let bind_group = self.device.create_bind_group(&wgpu::BindGroupDescriptor {
-            layout: bind_group_layout,
+
+        let atlas_texture = self.atlas_texture.as_ref().ok_or("字形图集纹理未创建")?;
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &self.bind_group_layout,
             entries: &[
                 wgpu::BindGroupEntry {
                     binding: 0,
-                    resource: uniform_buffer.as_entire_binding(),
+                    resource: self.uniform_buffer.as_entire_binding(),
                 },
                 wgpu::BindGroupEntry {
                     binding: 1,
-                    resource: wgpu::BindingResource::TextureView(&font_atlas.texture_view),
+                    resource: wgpu::BindingResource::TextureView(&atlas_texture.view),
                 },
                 wgpu::BindGroupEntry {
                     binding: 2,
-                    resource: wgpu::BindingResource::Sampler(&font_atlas.sampler),
+                    resource: wgpu::BindingResource::Sampler(&self.sampler),
                 },
             ],
             label: Some("menu_bind_group"),
         });
-        
+
         self.uniform_bind_group = Some(bind_group);
         Ok(())
     }
 }
 
+/// 排版所有菜单项的文字、勾选标记和子菜单箭头
+fn layout_menu_text(font: &mut MenuFont, menu: &ContextMenu) -> Vec<TextRun> {
+    let layout = menu.layout();
+    let text_color = [1.0, 1.0, 1.0, 1.0]; // 白色文本
+    let disabled_color = [0.6, 0.6, 0.6, 1.0]; // 禁用文本颜色
+
+    let mut runs = Vec::new();
+    for panel in menu.panels() {
+        let mut current_y = panel.position.y + layout.padding;
+        for item in menu.panel_items(&panel) {
+            if item.item_type == MenuItemType::Separator {
+                current_y += layout.separator_height;
+                continue;
+            }
+
+            let color = if item.enabled { text_color } else { disabled_color };
+            let baseline = item_baseline(font, layout, current_y);
+            let text_x = panel.position.x + layout.padding + layout.icon_space;
+            // 超出面板右侧内边距的字形不绘制
+            let mut right = panel.position.x + panel.size.width - layout.padding;
+
+            if let Some(mark) = item_mark(item) {
+                // 标记在图标空间中水平居中
+                let glyph = font.glyph(mark, layout.font_size);
+                let mark_x = panel.position.x + layout.padding + (layout.icon_space - glyph.advance) * 0.5;
+                let glyphs = font.layout_line(&mark.to_string(), layout.font_size, [mark_x, baseline]);
+                runs.push(TextRun { glyphs, color });
+            }
+
+            if item.item_type == MenuItemType::Submenu {
+                // 箭头在右侧图标空间中水平居中，文字不进入该空间
+                right -= layout.icon_space;
+                let glyph = font.glyph(SUBMENU_ARROW, layout.font_size);
+                let arrow_x = right + (layout.icon_space - glyph.advance) * 0.5;
+                let glyphs = font.layout_line(&SUBMENU_ARROW.to_string(), layout.font_size, [arrow_x, baseline]);
+                runs.push(TextRun { glyphs, color });
+            }
+
+            if !item.text.is_empty() {
+                let mut glyphs = font.layout_line(&item.text, layout.font_size, [text_x, baseline]);
+                glyphs.retain(|placed| placed.position[0] + placed.glyph.rect[2] as f32 <= right);
+                runs.push(TextRun { glyphs, color });
+            }
+
+            current_y += layout.item_height;
+        }
+    }
+    runs
+}
+
+/// 菜单项内垂直居中的基线位置
+fn item_baseline(font: &MenuFont, layout: &MenuLayout, item_y: f32) -> f32 {
+    let metrics = font.line_metrics(layout.font_size);
+    (item_y + (layout.item_height - metrics.ascent - metrics.descent) * 0.5 + metrics.ascent).round()
+}
+
+/// 菜单项前的勾选标记
+fn item_mark(item: &MenuItem) -> Option<char> {
+    match item.item_type {
+        MenuItemType::Checkbox if item.checked => Some(CHECK_MARK),
+        MenuItemType::Radio if item.checked => Some(RADIO_MARK),
+        _ => None,
+    }
+}
+
+/// 创建菜单使用的渲染管线（背景与文字只有入口和顶点格式不同）
+fn create_pipeline(
+    device: &wgpu::Device,
+    layout: &wgpu::PipelineLayout,
+    shader: &wgpu::ShaderModule,
+    surface_format: wgpu::TextureFormat,
+    label: &str,
+    (vs_entry, fs_entry): (&str, &str),
+    vertex_layout: wgpu::VertexBufferLayout<'static>,
+) -> wgpu::RenderPipeline {
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some(label),
+        layout: Some(layout),
+        vertex: wgpu::VertexState {
+            module: shader,
+            entry_point: vs_entry,
+            buffers: &[vertex_layout],
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: shader,
+            entry_point: fs_entry,
+            targets: &[Some(wgpu::ColorTargetState {
+                format: surface_format,
+                blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+        }),
+        primitive: wgpu::PrimitiveState {
+            topology: wgpu::PrimitiveTopology::TriangleList,
+            strip_index_format: None,
+            front_face: wgpu::FrontFace::Ccw,
+            cull_mode: None,
+            polygon_mode: wgpu::PolygonMode::Fill,
+            unclipped_depth: false,
+            conservative: false,
+        },
+        depth_stencil: None,
+        multisample: wgpu::MultisampleState {
+            count: 1,
+            mask: !0,
+            alpha_to_coverage_enabled: false,
+        },
+        multiview: None,
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use winit::dpi::PhysicalSize;
 
     #[test]
     fn test_menu_vertex_layout() {
@@ -882,4 +717,24 @@ mod tests {
         assert_eq!(vertices[0].position, [0.0, 0.0]);
         assert_eq!(vertices[2].position, [10.0, 10.0]);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_item_marks() {
+        let mut item = MenuItem {
+            id: "test".to_string(),
+            text: "测试".to_string(),
+            icon: None,
+            enabled: true,
+            checked: false,
+            item_type: MenuItemType::Checkbox,
+            group_id: None,
+        };
+        assert_eq!(item_mark(&item), None);
+        item.checked = true;
+        assert_eq!(item_mark(&item), Some(CHECK_MARK));
+        item.item_type = MenuItemType::Radio;
+        assert_eq!(item_mark(&item), Some(RADIO_MARK));
+        item.item_type = MenuItemType::Normal;
+        assert_eq!(item_mark(&item), None);
+    }
+}
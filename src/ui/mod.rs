@@ -3,7 +3,9 @@
 // 包含所有用户界面相关的组件和功能
 
 pub mod context_menu;
+pub mod glyph_atlas;
 pub mod menu_renderer;
 
 pub use context_menu::*;
-pub use menu_renderer::*;
\ No newline at end of file
+pub use glyph_atlas::*;
+pub use menu_renderer::*;
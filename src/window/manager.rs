@@ -34,12 +34,23 @@ pub enum BoundaryCheckState {
     Completed,
 }
 
+/// 默认旋转自动对齐容差（度）
+pub const DEFAULT_ROTATION_SNAP_TOLERANCE: f32 = 5.0;
+
 /// 窗口管理器
 pub struct WindowManager {
     window: Arc<Window>,
     position: PhysicalPosition<f64>,
     size: PhysicalSize<u32>,
+    // 遮罩形状的旋转角度（度）
     rotation: f32,
+    // 视频内容的旋转角度（度）
+    video_rotation: f32,
+    // 为 true 时视频不跟随遮罩旋转
+    keep_video_upright: bool,
+    // 遮罩和视频各自的自动对齐容差（度），0 表示禁用
+    rotation_snap_tolerance: f32,
+    video_snap_tolerance: f32,
     is_dragging: bool,
     drag_offset: PhysicalPosition<f64>,
     
@@ -86,6 +97,10 @@ impl WindowManager {
             position: default_position,
             size: default_size,
             rotation: 0.0,
+            video_rotation: 0.0,
+            keep_video_upright: false,
+            rotation_snap_tolerance: DEFAULT_ROTATION_SNAP_TOLERANCE,
+            video_snap_tolerance: DEFAULT_ROTATION_SNAP_TOLERANCE,
             is_dragging: false,
             drag_offset: PhysicalPosition::new(0.0, 0.0),
             
@@ -164,13 +179,13 @@ impl WindowManager {
         self.size
     }
     
-    /// 设置窗口旋转角度（度数）
+    /// 设置遮罩形状旋转角度（度数）
     pub fn set_rotation(&mut self, degrees: f32) {
         // 将角度归一化到 0-360 度范围
         let normalized = self.normalize_angle(degrees);
         
         // 应用自动对齐逻辑
-        let aligned = self.apply_auto_alignment(normalized);
+        let aligned = self.apply_auto_alignment(normalized, self.rotation_snap_tolerance);
         
         self.rotation = aligned;
         info!("窗口旋转角度更新为: {:.1}°", aligned);
@@ -182,15 +197,67 @@ impl WindowManager {
         self.set_rotation(new_angle);
     }
     
+    /// 设置视频内容旋转角度（度数）
+    pub fn set_video_rotation(&mut self, degrees: f32) {
+        let normalized = self.normalize_angle(degrees);
+        let aligned = self.apply_auto_alignment(normalized, self.video_snap_tolerance);
+        
+        self.video_rotation = aligned;
+        info!("视频旋转角度更新为: {:.1}°", aligned);
+    }
+    
+    /// 旋转视频内容（增量旋转）
+    pub fn rotate_video(&mut self, delta_degrees: f32) {
+        let new_angle = self.video_rotation + delta_degrees;
+        self.set_video_rotation(new_angle);
+    }
+    
+    /// 获取视频内容自身的旋转角度
+    pub fn video_rotation(&self) -> f32 {
+        self.video_rotation
+    }
+    
+    /// 设置视频是否保持正向（不跟随遮罩旋转）
+    pub fn set_keep_video_upright(&mut self, keep_upright: bool) {
+        self.keep_video_upright = keep_upright;
+        info!("视频保持正向: {}", if keep_upright { "启用" } else { "禁用" });
+    }
+    
+    /// 视频是否保持正向
+    pub fn keep_video_upright(&self) -> bool {
+        self.keep_video_upright
+    }
+    
+    /// 视频在屏幕上的实际旋转角度
+    ///
+    /// 保持正向时只使用视频自身的角度，否则叠加在遮罩旋转之上。
+    pub fn effective_video_rotation(&self) -> f32 {
+        if self.keep_video_upright {
+            self.video_rotation
+        } else {
+            self.normalize_angle(self.rotation + self.video_rotation)
+        }
+    }
+    
+    /// 设置遮罩和视频旋转的自动对齐容差（度），0 表示禁用对齐
+    pub fn set_snap_tolerances(&mut self, rotation_tolerance: f32, video_tolerance: f32) {
+        self.rotation_snap_tolerance = rotation_tolerance.max(0.0);
+        self.video_snap_tolerance = video_tolerance.max(0.0);
+    }
+    
+    /// 获取遮罩和视频旋转的自动对齐容差（度）
+    pub fn snap_tolerances(&self) -> (f32, f32) {
+        (self.rotation_snap_tolerance, self.video_snap_tolerance)
+    }
+    
     /// 将角度归一化到 0-360 度范围
     fn normalize_angle(&self, degrees: f32) -> f32 {
         let normalized = degrees % 360.0;
         if normalized < 0.0 { normalized + 360.0 } else { normalized }
     }
     
-    /// 应用自动对齐逻辑（0°、90°、180°、270° ±5° 范围内自动对齐）
-    fn apply_auto_alignment(&self, degrees: f32) -> f32 {
-        const ALIGNMENT_TOLERANCE: f32 = 5.0;
+    /// 应用自动对齐逻辑（0°、90°、180°、270° ±tolerance 范围内自动对齐）
+    fn apply_auto_alignment(&self, degrees: f32, tolerance: f32) -> f32 {
         const ALIGNMENT_ANGLES: [f32; 4] = [0.0, 90.0, 180.0, 270.0];
         
         for &target_angle in &ALIGNMENT_ANGLES {
@@ -201,7 +268,7 @@ impl WindowManager {
             
             let min_diff = diff.min(diff_wrapped).min(diff_wrapped_neg);
             
-            if min_diff <= ALIGNMENT_TOLERANCE {
+            if min_diff <= tolerance {
                 info!("自动对齐: {:.1}° -> {:.1}°", degrees, target_angle);
                 return target_angle;
             }
@@ -210,7 +277,7 @@ impl WindowManager {
         degrees
     }
     
-    /// 获取窗口旋转角度
+    /// 获取遮罩形状旋转角度
     pub fn rotation(&self) -> f32 {
         self.rotation
     }
@@ -711,6 +778,53 @@ mod tests {
         }
     }
     
+    #[test]
+    fn test_video_rotation_independent_of_mask() {
+        let event_loop = EventLoop::new().unwrap();
+        if let Ok(mut manager) = WindowManager::new(&event_loop) {
+            // 旋转遮罩不改变视频自身的角度
+            manager.set_rotation(45.0);
+            assert_eq!(manager.rotation(), 45.0);
+            assert_eq!(manager.video_rotation(), 0.0);
+            
+            manager.rotate_video(-30.0);
+            assert_eq!(manager.video_rotation(), 330.0);
+            assert_eq!(manager.rotation(), 45.0);
+            
+            // 未保持正向时视频跟随遮罩旋转
+            assert_eq!(manager.effective_video_rotation(), 15.0);
+            
+            // 保持正向时只使用视频自身的角度
+            manager.set_keep_video_upright(true);
+            assert!(manager.keep_video_upright());
+            assert_eq!(manager.effective_video_rotation(), 330.0);
+        }
+    }
+    
+    #[test]
+    fn test_separate_snap_tolerances() {
+        let event_loop = EventLoop::new().unwrap();
+        if let Ok(mut manager) = WindowManager::new(&event_loop) {
+            assert_eq!(manager.snap_tolerances(), (DEFAULT_ROTATION_SNAP_TOLERANCE, DEFAULT_ROTATION_SNAP_TOLERANCE));
+            
+            // 遮罩禁用对齐，视频放宽对齐范围
+            manager.set_snap_tolerances(0.0, 10.0);
+            
+            manager.set_rotation(87.0);
+            assert_eq!(manager.rotation(), 87.0);
+            
+            manager.set_video_rotation(172.0);
+            assert_eq!(manager.video_rotation(), 180.0);
+            
+            manager.set_video_rotation(169.0);
+            assert_eq!(manager.video_rotation(), 169.0);
+            
+            // 负容差按 0 处理
+            manager.set_snap_tolerances(-1.0, 5.0);
+            assert_eq!(manager.snap_tolerances(), (0.0, 5.0));
+        }
+    }
+    
     #[test]
     fn test_size_constraints() {
         let event_loop = EventLoop::new().unwrap();
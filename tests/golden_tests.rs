@@ -0,0 +1,261 @@
+// 金样图测试
+// 用 CPU 参考合成器渲染固定场景，与 tests/golden 下的 PNG 逐像素比较。
+//
+// 设置 MIRA_UPDATE_GOLDEN=1 时写入新的金样图，否则金样图缺失视为失败；
+// 比较失败时把实际输出和差异图写入 target/tmp/golden-diff，便于查看。
+
+use mira::{
+    render::{
+        alpha::OutputAlpha,
+        auto_correct::Correction,
+        chroma::ChromaKey,
+        color::{ColorAdjustments, ColorPreset},
+        engine::{Frame, PixelFormat},
+        reference::{compare_images, composite, ReferenceParams},
+    },
+    shape::{ShapeMask, ShapeType},
+};
+use image::{Rgba, RgbaImage};
+use std::f32::consts::PI;
+use std::path::{Path, PathBuf};
+
+/// 更新金样图的环境变量
+const UPDATE_ENV: &str = "MIRA_UPDATE_GOLDEN";
+
+/// 允许的单通道差值，吸收不同平台浮点运算的细微差别
+const TOLERANCE: u8 = 2;
+
+const WIDTH: u32 = 96;
+const HEIGHT: u32 = 96;
+
+fn golden_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("golden")
+}
+
+fn diff_dir() -> PathBuf {
+    Path::new(env!("CARGO_TARGET_TMPDIR")).join("golden-diff")
+}
+
+/// 与金样图比较，失败时写出实际输出和差异图
+fn assert_golden(name: &str, actual: &RgbaImage) {
+    let path = golden_dir().join(format!("{}.png", name));
+    let update = std::env::var(UPDATE_ENV).is_ok_and(|value| value == "1");
+    if update {
+        std::fs::create_dir_all(golden_dir()).unwrap();
+        actual.save(&path).unwrap();
+        return;
+    }
+    if !path.exists() {
+        panic!("缺少金样图: {}\n设置 {}=1 运行测试以生成", path.display(), UPDATE_ENV);
+    }
+
+    let expected = image::open(&path).expect("读取金样图失败").to_rgba8();
+    let diff = compare_images(actual, &expected, TOLERANCE);
+    if diff.as_ref().is_some_and(|diff| diff.is_match()) {
+        return;
+    }
+
+    std::fs::create_dir_all(diff_dir()).unwrap();
+    let actual_path = diff_dir().join(format!("{}.actual.png", name));
+    actual.save(&actual_path).unwrap();
+    match diff {
+        Some(diff) => {
+            let diff_path = diff_dir().join(format!("{}.diff.png", name));
+            diff.diff_image.save(&diff_path).unwrap();
+            panic!(
+                "{} 与金样图不一致: {} 个像素超出容差，最大差值 {}\n实际输出: {}\n差异图: {}",
+                name,
+                diff.mismatched_pixels,
+                diff.max_difference,
+                actual_path.display(),
+                diff_path.display()
+            );
+        }
+        None => panic!(
+            "{} 尺寸 {:?} 与金样图 {:?} 不同\n实际输出: {}",
+            name,
+            actual.dimensions(),
+            expected.dimensions(),
+            actual_path.display()
+        ),
+    }
+}
+
+/// 水平红色、垂直绿色渐变，左上角有一个白色方块用于辨认方向
+fn gradient_frame(width: u32, height: u32) -> Frame {
+    let mut data = Vec::with_capacity((width * height * 3) as usize);
+    for y in 0..height {
+        for x in 0..width {
+            if x < width / 4 && y < height / 4 {
+                data.extend_from_slice(&[255, 255, 255]);
+            } else {
+                data.extend_from_slice(&[(x * 255 / (width - 1)) as u8, (y * 255 / (height - 1)) as u8, 96]);
+            }
+        }
+    }
+    Frame::new(data, width, height, PixelFormat::RGB8)
+}
+
+/// 绿幕背景，中央一个肤色方块
+fn green_screen_frame(width: u32, height: u32) -> Frame {
+    let mut data = Vec::with_capacity((width * height * 3) as usize);
+    for y in 0..height {
+        for x in 0..width {
+            let inside = (width / 4..width * 3 / 4).contains(&x) && (height / 4..height * 3 / 4).contains(&y);
+            data.extend_from_slice(if inside { &[224, 172, 138] } else { &[0, 177, 64] });
+        }
+    }
+    Frame::new(data, width, height, PixelFormat::RGB8)
+}
+
+/// 模拟合成器在 sRGB 编码空间中把输出混合到纯色桌面上
+fn composite_on_desktop(image: &RgbaImage, output_alpha: OutputAlpha, desktop: [u8; 3]) -> RgbaImage {
+    RgbaImage::from_fn(image.width(), image.height(), |x, y| {
+        let pixel = image.get_pixel(x, y);
+        let alpha = pixel[3] as f32 / 255.0;
+        let channel = |i: usize| {
+            let color = pixel[i] as f32 / 255.0;
+            let color = match output_alpha {
+                OutputAlpha::Premultiplied => color,
+                OutputAlpha::Straight => color * alpha,
+            };
+            ((color + desktop[i] as f32 / 255.0 * (1.0 - alpha)) * 255.0).round().min(255.0) as u8
+        };
+        Rgba([channel(0), channel(1), channel(2), 255])
+    })
+}
+
+fn render(name: &str, frame: &Frame, shape: ShapeType, params: &ReferenceParams) {
+    let mask = ShapeMask::new(shape, params.width, params.height);
+    let output = composite(frame, &mask, params).expect("参考合成失败");
+    assert_golden(name, &output);
+}
+
+#[test]
+fn golden_circle() {
+    render("circle", &gradient_frame(64, 48), ShapeType::Circle, &ReferenceParams::new(WIDTH, HEIGHT));
+}
+
+#[test]
+fn golden_heart_rotated() {
+    let params = ReferenceParams {
+        mask_rotation: PI / 6.0,
+        ..ReferenceParams::new(WIDTH, HEIGHT)
+    };
+    render("heart_rotated", &gradient_frame(64, 48), ShapeType::Heart, &params);
+}
+
+#[test]
+fn golden_video_rotation() {
+    // 视频旋转 90°，遮罩保持不动
+    let params = ReferenceParams {
+        video_rotation: PI / 2.0,
+        ..ReferenceParams::new(WIDTH, HEIGHT)
+    };
+    render(
+        "video_rotation",
+        &gradient_frame(64, 48),
+        ShapeType::RoundedRectangle { radius: 0.2 },
+        &params,
+    );
+}
+
+#[test]
+fn golden_color_grading() {
+    let params = ReferenceParams {
+        adjustments: ColorAdjustments {
+            brightness: 0.05,
+            contrast: 1.3,
+            hue_shift: 60.0,
+            temperature: 0.4,
+            gamma: 1.2,
+            ..ColorAdjustments::default()
+        },
+        preset: ColorPreset::Sepia,
+        ..ReferenceParams::new(WIDTH, HEIGHT)
+    };
+    render("color_grading", &gradient_frame(64, 48), ShapeType::Ellipse, &params);
+}
+
+#[test]
+fn golden_chroma_key() {
+    let params = ReferenceParams {
+        chroma_key: ChromaKey {
+            enabled: true,
+            ..ChromaKey::default()
+        },
+        auto_correct: Some(Correction {
+            gains: [1.1, 1.0, 0.9],
+            black: 0.05,
+            white: 0.95,
+            exponent: 0.8,
+        }),
+        ..ReferenceParams::new(WIDTH, HEIGHT)
+    };
+    render("chroma_key", &green_screen_frame(48, 48), ShapeType::Rectangle, &params);
+}
+
+#[test]
+fn golden_layers() {
+    // 半透明叠加层横条、遮罩下方的贴纸层和不受遮罩裁剪的上方贴纸层
+    let overlay = RgbaImage::from_fn(WIDTH, HEIGHT, |_, y| {
+        if (40..56).contains(&y) {
+            Rgba([255, 255, 0, 160])
+        } else {
+            Rgba([0, 0, 0, 0])
+        }
+    });
+    let sticker_below = RgbaImage::from_fn(WIDTH, HEIGHT, |x, y| {
+        if x > 60 && y > 60 {
+            Rgba([0, 0, 200, 255])
+        } else {
+            Rgba([0, 0, 0, 0])
+        }
+    });
+    let sticker_above = RgbaImage::from_fn(WIDTH, HEIGHT, |x, y| {
+        if x < 20 && y > 70 {
+            Rgba([100, 0, 100, 128])
+        } else {
+            Rgba([0, 0, 0, 0])
+        }
+    });
+    let params = ReferenceParams {
+        overlay: Some(&overlay),
+        sticker_below: Some(&sticker_below),
+        sticker_above: Some(&sticker_above),
+        ..ReferenceParams::new(WIDTH, HEIGHT)
+    };
+    render("layers", &gradient_frame(32, 32), ShapeType::Circle, &params);
+}
+
+#[test]
+fn golden_circle_straight_alpha() {
+    // 合成器要求非预乘颜色（PostMultiplied）时的输出
+    let params = ReferenceParams {
+        output_alpha: OutputAlpha::Straight,
+        ..ReferenceParams::new(WIDTH, HEIGHT)
+    };
+    render("circle_straight_alpha", &gradient_frame(64, 48), ShapeType::Circle, &params);
+}
+
+#[test]
+fn edge_pixels_have_no_halo() {
+    // 白色画面的抗锯齿边缘合成到白色桌面上不应出现暗边，合成到黑色桌面上按 alpha 线性过渡
+    let frame = Frame::new(vec![255; (WIDTH * HEIGHT * 3) as usize], WIDTH, HEIGHT, PixelFormat::RGB8);
+    let mask = ShapeMask::new(ShapeType::Circle, WIDTH, HEIGHT);
+    for output_alpha in [OutputAlpha::Premultiplied, OutputAlpha::Straight] {
+        let params = ReferenceParams {
+            output_alpha,
+            ..ReferenceParams::new(WIDTH, HEIGHT)
+        };
+        let output = composite(&frame, &mask, &params).expect("参考合成失败");
+        assert!(output.pixels().any(|p| p[3] > 0 && p[3] < 255), "没有抗锯齿边缘");
+
+        let on_white = composite_on_desktop(&output, output_alpha, [255, 255, 255]);
+        let on_black = composite_on_desktop(&output, output_alpha, [0, 0, 0]);
+        for ((source, white), black) in output.pixels().zip(on_white.pixels()).zip(on_black.pixels()) {
+            assert!(white.0[..3].iter().all(|&c| c >= 254), "{:?}: {:?} -> {:?}", output_alpha, source, white);
+            assert!(black.0[..3].iter().all(|&c| c.abs_diff(source[3]) <= 1), "{:?}: {:?} -> {:?}", output_alpha, source, black);
+        }
+    }
+}